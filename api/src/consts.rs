@@ -9,6 +9,8 @@ pub const SPOOL: &[u8]                     = b"spool";
 pub const TAPE: &[u8]                      = b"tape";
 pub const WRITER: &[u8]                    = b"writer";
 pub const MINER: &[u8]                     = b"miner";
+pub const CONFIG: &[u8]                    = b"config";
+pub const ESCROW: &[u8]                    = b"escrow";
 
 pub const MINT: &[u8]                      = b"mint";
 pub const MINT_SEED: &[u8]                 = &[152, 68, 212, 200, 25, 113, 221, 71];
@@ -22,12 +24,15 @@ pub const TREE_HEIGHT: usize               = 18;
 pub const PROOF_LEN: usize                 = TREE_HEIGHT;
 
 pub const SEGMENT_SIZE: usize              = 128; // Bytes (chosen to fit recall proofs comfortably)
-pub const MAX_TAPE_SIZE: usize             = 2_usize.pow(TREE_HEIGHT as u32) * SEGMENT_SIZE; // 32MB
+pub const MAX_SEGMENTS: usize              = 2_usize.pow(TREE_HEIGHT as u32); // Leaves in a writer tree
+pub const MAX_TAPE_SIZE: usize             = MAX_SEGMENTS * SEGMENT_SIZE; // 32MB
 
 pub const SPOOL_COUNT: usize               = 8;
 pub const NAME_LEN: usize                  = 32;  // Bytes
 pub const HEADER_SIZE: usize               = 128; // Bytes
 
+pub const ACCOUNT_VERSION: u8              = 1;
+
 pub const TOKEN_DECIMALS: u8               = 10;
 pub const ONE_TAPE: u64                    = 10u64.pow(TOKEN_DECIMALS as u32);
 pub const MAX_SUPPLY: u64                  = 7_000_000 * ONE_TAPE;
@@ -37,6 +42,10 @@ pub const ONE_MINUTE: i64                  = 60 * ONE_SECOND;
 pub const EPOCH_DURATION_MINUTES: i64      = 15;
 pub const EPOCH_SECONDS: i64               = EPOCH_DURATION_MINUTES * ONE_MINUTE;
 pub const GRACE_PERIOD_SECONDS: i64        = 15 * ONE_SECOND;
+pub const MAX_VEST_EPOCHS: u64             = 365 * 24 * 60 / EPOCH_DURATION_MINUTES as u64; // A year
+
+pub const MAX_MINER_DEPOSIT: u64           = 10_000 * ONE_TAPE; // Most governance can ask of a new miner
+pub const MINER_DEPOSIT_COOLDOWN: i64      = 7 * 24 * 60 * ONE_MINUTE; // From the last proof to the refund
 
 // -- Const Addresses --
 // (There isn't a better way to do this yet; maybe a build.rs + include)
@@ -56,6 +65,12 @@ pub const EPOCH_ADDRESS: Pubkey =
 pub const EPOCH_BUMP: u8 =
     ed25519::derive_program_address(&[EPOCH], &PROGRAM_ID).1;
 
+pub const CONFIG_ADDRESS: Pubkey =
+    Pubkey::new_from_array(ed25519::derive_program_address(&[CONFIG], &PROGRAM_ID).0);
+
+pub const CONFIG_BUMP: u8 =
+    ed25519::derive_program_address(&[CONFIG], &PROGRAM_ID).1;
+
 pub const MINT_ADDRESS: Pubkey =
     Pubkey::new_from_array(ed25519::derive_program_address(&[MINT, &MINT_SEED], &PROGRAM_ID).0);
 
@@ -80,6 +95,24 @@ pub const TREASURY_ATA: Pubkey = Pubkey::new_from_array(
     .0,
 );
 
+pub const ESCROW_ADDRESS: Pubkey =
+    Pubkey::new_from_array(ed25519::derive_program_address(&[ESCROW], &PROGRAM_ID).0);
+
+pub const ESCROW_BUMP: u8 =
+    ed25519::derive_program_address(&[ESCROW], &PROGRAM_ID).1;
+
+pub const ESCROW_ATA: Pubkey = Pubkey::new_from_array(
+    ed25519::derive_program_address(
+        &[
+            unsafe { &*(&ESCROW_ADDRESS as *const Pubkey as *const [u8; 32]) },
+            unsafe { &*(&spl_token::id() as *const Pubkey as *const [u8; 32]) },
+            unsafe { &*(&MINT_ADDRESS as *const Pubkey as *const [u8; 32]) },
+        ],
+        unsafe { &*(&spl_associated_token_account::id() as *const Pubkey as *const [u8; 32]) },
+    )
+    .0,
+);
+
 pub const SPOOL_ADDRESSES: [Pubkey; SPOOL_COUNT] = 
     array_const_fn_init![const_spool_address; 8];
 
@@ -0,0 +1,108 @@
+use steel::*;
+use crate::consts::*;
+
+/// The tape program deployment to talk to. The default is the one this crate was built for;
+/// forks and local test deployments use their own program ID, and every address derived from it
+/// changes with it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ProgramContext {
+    pub program_id: Pubkey,
+}
+
+impl Default for ProgramContext {
+    fn default() -> Self {
+        Self { program_id: crate::ID }
+    }
+}
+
+impl ProgramContext {
+    /// Environment variable that overrides the program ID, see [`ProgramContext::from_env`].
+    pub const ENV_VAR: &'static str = "TAPE_PROGRAM_ID";
+
+    pub fn new(program_id: Pubkey) -> Self {
+        Self { program_id }
+    }
+
+    /// The deployment named by `TAPE_PROGRAM_ID`, or the default one when it isn't set.
+    #[cfg(not(target_os = "solana"))]
+    pub fn from_env() -> Result<Self, solana_program::pubkey::ParsePubkeyError> {
+        match std::env::var(Self::ENV_VAR) {
+            Ok(id) if !id.trim().is_empty() => Ok(Self::new(id.trim().parse()?)),
+            _ => Ok(Self::default()),
+        }
+    }
+
+    /// Whether this is the deployment the const addresses were derived for.
+    pub fn is_default(&self) -> bool {
+        self.program_id == crate::ID
+    }
+
+    pub fn archive_address(&self) -> Pubkey {
+        if self.is_default() { ARCHIVE_ADDRESS } else { self.archive_pda().0 }
+    }
+
+    pub fn epoch_address(&self) -> Pubkey {
+        if self.is_default() { EPOCH_ADDRESS } else { self.epoch_pda().0 }
+    }
+
+    pub fn config_address(&self) -> Pubkey {
+        if self.is_default() { CONFIG_ADDRESS } else { self.config_pda().0 }
+    }
+
+    pub fn mint_address(&self) -> Pubkey {
+        if self.is_default() { MINT_ADDRESS } else { self.mint_pda().0 }
+    }
+
+    pub fn treasury_address(&self) -> Pubkey {
+        if self.is_default() { TREASURY_ADDRESS } else { self.treasury_pda().0 }
+    }
+
+    pub fn treasury_ata_address(&self) -> Pubkey {
+        if self.is_default() { TREASURY_ATA } else { self.treasury_ata().0 }
+    }
+
+    pub fn escrow_address(&self) -> Pubkey {
+        if self.is_default() { ESCROW_ADDRESS } else { self.escrow_pda().0 }
+    }
+
+    pub fn escrow_ata_address(&self) -> Pubkey {
+        if self.is_default() { ESCROW_ATA } else { self.escrow_ata().0 }
+    }
+
+    /// The owner's TAPE token account, where miner deposits are paid from and refunded to.
+    pub fn tape_ata_address(&self, owner: &Pubkey) -> Pubkey {
+        spl_associated_token_account::get_associated_token_address(owner, &self.mint_address())
+    }
+
+    pub fn spool_addresses(&self) -> [Pubkey; SPOOL_COUNT] {
+        if self.is_default() {
+            SPOOL_ADDRESSES
+        } else {
+            core::array::from_fn(|i| self.spool_pda(i as u8).0)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_context_addresses() {
+        let default = ProgramContext::default();
+        assert_eq!(default.archive_address(), default.archive_pda().0);
+        assert_eq!(default.treasury_ata_address(), default.treasury_ata().0);
+        assert_eq!(default.escrow_ata_address(), default.escrow_ata().0);
+        assert_eq!(default.spool_addresses()[3], default.spool_pda(3).0);
+
+        let fork = ProgramContext::new(Pubkey::new_unique());
+        assert!(!fork.is_default());
+        assert_ne!(fork.archive_address(), ARCHIVE_ADDRESS);
+        assert_ne!(fork.mint_address(), MINT_ADDRESS);
+        assert_eq!(fork.config_address(), fork.config_pda().0);
+
+        let ix = fork.build_write_ix(Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), b"data");
+        assert_eq!(ix.program_id, fork.program_id);
+        assert_eq!(ix.accounts[3].pubkey, fork.config_address());
+    }
+}
@@ -0,0 +1,283 @@
+use crate::consts::*;
+
+pub const MIN_CONSISTENCY_MULTIPLIER: u64  = 1;
+pub const MAX_CONSISTENCY_MULTIPLIER: u64  = 32;
+pub const REWARD_SCALE_FACTOR: u64         = 16;
+pub const SMOOTHING_FACTOR: u64            = 2;
+
+// Helper: Update miner multiplier based on timing of this solution.
+//
+// Miners that consistently submit solutions on-time will have a higher multiplier number. This
+// number is the base of pow(), which is used to calculate the base reward. Initially, the
+// multiplier is set to 1, meaning the reward earned for a solution is low. As the miner submits
+// more solutions, the multiplier increases, and the reward increases as well. It is in the miner's
+// best interest to submit solutions on-time, as the multiplier will decrease if they are late.
+//
+// This encourages miners to come up with strategies that allow them quick access to the tape data
+// needed to solve the challenge.
+#[inline(always)]
+pub fn update_multiplier(multiplier: u64, last_proof_at: i64, current_time: i64) -> u64 {
+    let target_time = last_proof_at + ONE_MINUTE;
+    let liveness_threshold = target_time + GRACE_PERIOD_SECONDS;
+
+    if current_time > liveness_threshold {
+        // Maybe this should be a division instead of a subtraction?
+        multiplier
+            .saturating_sub(1)
+            .max(MIN_CONSISTENCY_MULTIPLIER)
+    } else {
+        multiplier
+            .saturating_add(1)
+            .min(MAX_CONSISTENCY_MULTIPLIER)
+    }
+}
+
+// Helper: Calculate base reward based on difficulty and multiplier
+#[inline(always)]
+pub fn compute_base_reward(
+    multiplier: u64,
+    base_rate: u64,
+    difficulty: u64,
+    difficulty_attempt: u32,
+) -> u64 {
+    assert!(difficulty_attempt >= difficulty as u32);
+
+    let consistency_multiplier = multiplier
+        .saturating_div(REWARD_SCALE_FACTOR)
+        .max(MIN_CONSISTENCY_MULTIPLIER)
+        .min(MAX_CONSISTENCY_MULTIPLIER);
+
+    let difficulty_excess = difficulty_attempt - difficulty as u32;
+
+    consistency_multiplier
+        .saturating_pow(difficulty_excess)
+        .saturating_mul(base_rate)
+}
+
+// Helper: Apply lateness penalty to base reward
+//
+// Following what ORE has done, we penalize the reward based on how late the miner is, the timings
+// are different, but the formula is the same.
+//
+// Example: If late by 90 seconds (1 minute + 30 seconds), the reward was first halved for the
+// 1 minute, then further reduced for the 30 seconds.
+//
+// Reference:
+// https://github.com/regolith-labs/ore/blob/c18503d0ee98b8a7823b993b38823b7867059659/program/src/mine.rs#L105-L125
+#[inline(always)]
+pub fn penalize_lateness(
+    base_reward: u64,
+    last_proof_at: i64,
+    current_time: i64
+) -> u64 {
+    let target_time = last_proof_at + ONE_MINUTE;
+    let liveness_threshold = target_time + GRACE_PERIOD_SECONDS;
+    let mut penalized_reward = base_reward;
+
+    if current_time > liveness_threshold {
+        let late_seconds = current_time.saturating_sub(target_time) as u64;
+        let late_minutes = late_seconds.saturating_div(ONE_MINUTE as u64);
+
+        // An exponential penalty for full minutes late: base_reward / (2^late_minutes).
+        if late_minutes > 0 {
+            penalized_reward =
+                base_reward.saturating_div(2u64.saturating_pow(late_minutes as u32));
+        }
+
+        // A linear penalty for any extra seconds: A fraction of the reward is subtracted based on the
+        // proportion of extra_seconds relative to ONE_MINUTE.
+        let extra_seconds = late_seconds.saturating_sub(late_minutes.saturating_mul(ONE_MINUTE as u64));
+        if extra_seconds > 0 && penalized_reward > 0 {
+            let time_penalty = penalized_reward
+                .saturating_div(2)
+                .saturating_mul(extra_seconds)
+                .saturating_div(ONE_MINUTE as u64);
+            penalized_reward = penalized_reward.saturating_sub(time_penalty);
+        }
+    }
+
+    penalized_reward
+}
+
+// Helper: Calculate final reward with limits
+#[inline(always)]
+pub fn compute_final_reward(
+    penalized_reward: u64,
+    theoretical_rewards: u64,
+    target_rate: u64,
+) -> u64 {
+    let final_reward = penalized_reward
+        .min(theoretical_rewards)
+        .min(target_rate);
+    final_reward
+}
+
+// Helper: Compute new reward rate based on current rate and epoch rewards.
+//
+// Formula:
+// new_rate = current_rate * (target_rewards / actual_rewards)
+//
+// Following what ORE here to avoid footguns.
+// Reference: https://github.com/regolith-labs/ore/blob/c18503d0ee98b8a7823b993b38823b7867059659/program/src/reset.rs#L146
+#[inline(always)]
+pub fn compute_new_reward_rate(
+    current_rate: u64,
+    actual_rewards: u64,
+    target_rewards: u64,
+) -> u64 {
+
+    if actual_rewards == 0 {
+        return current_rate;
+    }
+
+    let adjusted_rate = (current_rate as u128)
+        .saturating_mul(target_rewards as u128)
+        .saturating_div(actual_rewards as u128) as u64;
+
+    let min_rate = current_rate.saturating_div(SMOOTHING_FACTOR);
+    let max_rate = current_rate.saturating_mul(SMOOTHING_FACTOR);
+    let smoothed_rate = adjusted_rate.min(max_rate).max(min_rate);
+
+    smoothed_rate
+        .max(1)
+        .min(target_rewards)
+}
+
+// Pre-computed emissions rate based on current supply. Decay of ~14% every 12 months with
+// a target of 7 million TAPE.
+pub fn get_emissions_rate(current_supply: u64) -> u64 {
+    match current_supply {
+        n if n < ONE_TAPE * 1000000 => 19025875190, // Year 1: ~1.90 TAPE/min
+        n if n < ONE_TAPE * 1861000 => 16381278538, // Year 2: ~1.64 TAPE/min
+        n if n < ONE_TAPE * 2602321 => 14104280821, // Year 3: ~1.41 TAPE/min
+        n if n < ONE_TAPE * 3240598 => 12143785787, // Year 4: ~1.21 TAPE/min
+        n if n < ONE_TAPE * 3790155 => 10455799563, // Year 5: ~1.05 TAPE/min
+        n if n < ONE_TAPE * 4263323 => 9002443423,  // Year 6: ~0.90 TAPE/min
+        n if n < ONE_TAPE * 4670721 => 7751103787,  // Year 7: ~0.78 TAPE/min
+        n if n < ONE_TAPE * 5021491 => 6673700361,  // Year 8: ~0.67 TAPE/min
+        n if n < ONE_TAPE * 5323504 => 5746056011,  // Year 9: ~0.57 TAPE/min
+        n if n < ONE_TAPE * 5583536 => 4947354225,  // Year 10: ~0.49 TAPE/min
+        n if n < ONE_TAPE * 5807425 => 4259671988,  // Year 11: ~0.43 TAPE/min
+        n if n < ONE_TAPE * 6000193 => 3667577581,  // Year 12: ~0.37 TAPE/min
+        n if n < ONE_TAPE * 6166166 => 3157784298,  // Year 13: ~0.32 TAPE/min
+        n if n < ONE_TAPE * 6309069 => 2718852280,  // Year 14: ~0.27 TAPE/min
+        n if n < ONE_TAPE * 6432108 => 2340931813,  // Year 15: ~0.23 TAPE/min
+        n if n < ONE_TAPE * 6538045 => 2015542291,  // Year 16: ~0.20 TAPE/min
+        n if n < ONE_TAPE * 6629257 => 1735381912,  // Year 17: ~0.17 TAPE/min
+        n if n < ONE_TAPE * 6707790 => 1494163827,  // Year 18: ~0.15 TAPE/min
+        n if n < ONE_TAPE * 6775407 => 1286475055,  // Year 19: ~0.13 TAPE/min
+        n if n < ONE_TAPE * 6833625 => 1107655022,  // Year 20: ~0.11 TAPE/min
+        n if n < ONE_TAPE * 6883751 => 953690974,   // Year 21: ~0.10 TAPE/min
+        n if n < ONE_TAPE * 6926910 => 821127928,   // Year 22: ~0.08 TAPE/min
+        n if n < ONE_TAPE * 6964069 => 706991146,   // Year 23: ~0.07 TAPE/min
+        n if n < ONE_TAPE * 6996064 => 608719377,   // Year 24: ~0.06 TAPE/min
+        n if n < ONE_TAPE * 7000000 => 524107383,   // Year 25: ~0.05 TAPE/min
+        _ => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_update_multiplier() {
+        // On time (within the grace period) grows, late shrinks, both within bounds
+        assert_eq!(update_multiplier(1, 0, ONE_MINUTE + GRACE_PERIOD_SECONDS), 2);
+        assert_eq!(update_multiplier(MAX_CONSISTENCY_MULTIPLIER, 0, ONE_MINUTE), MAX_CONSISTENCY_MULTIPLIER);
+        assert_eq!(update_multiplier(5, 0, ONE_MINUTE + GRACE_PERIOD_SECONDS + 1), 4);
+        assert_eq!(update_multiplier(1, 0, 10 * ONE_MINUTE), MIN_CONSISTENCY_MULTIPLIER);
+    }
+
+    #[test]
+    fn test_compute_base_reward() {
+        assert_eq!(compute_base_reward(1, 100, 10, 10), 100);
+        // Below the scale factor the multiplier is 1, so extra difficulty earns nothing
+        assert_eq!(compute_base_reward(15, 100, 10, 14), 100);
+        assert_eq!(compute_base_reward(32, 100, 10, 13), 800);
+    }
+
+    #[test]
+    fn test_penalize_lateness() {
+        assert_eq!(penalize_lateness(1000, 0, ONE_MINUTE), 1000);
+        assert_eq!(penalize_lateness(1000, 0, ONE_MINUTE + GRACE_PERIOD_SECONDS), 1000);
+        assert_eq!(penalize_lateness(1000, 0, 2 * ONE_MINUTE), 500);
+        assert_eq!(penalize_lateness(1000, 0, 2 * ONE_MINUTE + 30), 375);
+    }
+
+    #[test]
+    fn test_rewards_monotonic() {
+        // Later proofs never earn more
+        let mut previous = u64::MAX;
+        for current_time in 0..20 * ONE_MINUTE {
+            let reward = penalize_lateness(1_000_000, 0, current_time);
+            assert!(reward <= previous, "reward grew at {}", current_time);
+            previous = reward;
+        }
+
+        // Harder proofs and higher multipliers never earn less
+        for multiplier in MIN_CONSISTENCY_MULTIPLIER..=MAX_CONSISTENCY_MULTIPLIER * REWARD_SCALE_FACTOR {
+            let mut previous = 0;
+            for attempt in 7..40 {
+                let reward = compute_base_reward(multiplier, 100, 7, attempt);
+                assert!(reward >= previous);
+                assert!(reward >= compute_base_reward(multiplier.saturating_sub(1), 100, 7, attempt));
+                previous = reward;
+            }
+        }
+    }
+
+    #[test]
+    fn test_compute_new_reward_rate() {
+        for current_rate in [1, 2, 100, 1_000_000] {
+            for actual_rewards in [1, 10, 1_000, 1_000_000_000] {
+                let target_rewards = 1_000_000;
+                let rate = compute_new_reward_rate(current_rate, actual_rewards, target_rewards);
+                assert!(rate >= (current_rate / SMOOTHING_FACTOR).max(1));
+                assert!(rate <= current_rate * SMOOTHING_FACTOR);
+                assert!(rate <= target_rewards);
+            }
+        }
+        assert_eq!(compute_new_reward_rate(100, 0, 1_000), 100);
+    }
+
+    #[test]
+    fn test_emissions_decreasing() {
+        let mut previous = u64::MAX;
+        for supply in (0..=MAX_SUPPLY + ONE_TAPE).step_by(100 * ONE_TAPE as usize) {
+            let rate = get_emissions_rate(supply);
+            assert!(rate <= previous, "emissions grew at supply {}", supply);
+            assert_eq!(rate > 0, supply < MAX_SUPPLY);
+            previous = rate;
+        }
+    }
+
+    #[test]
+    fn test_emissions_budget() {
+        const MINUTES_PER_YEAR: u64 = 365 * 24 * 60;
+
+        // A year at each tier's rate mints the tier, until the max supply caps the last one
+        let mut supply = 0;
+        for year in 1..=25 {
+            let rate = get_emissions_rate(supply);
+            assert!(rate > 0, "no emissions in year {}", year);
+
+            let minted = (rate * MINUTES_PER_YEAR).min(MAX_SUPPLY - supply);
+            supply += minted;
+
+            if year < 25 {
+                assert!(get_emissions_rate(supply + 10 * ONE_TAPE) < rate, "year {} misses its tier", year);
+            }
+        }
+
+        assert_eq!(supply, MAX_SUPPLY);
+        assert_eq!(get_emissions_rate(supply), 0);
+    }
+
+    #[test]
+    fn test_compute_final_reward() {
+        assert_eq!(compute_final_reward(500, 1000, 2000), 500);
+        assert_eq!(compute_final_reward(500, 100, 2000), 100);
+        assert_eq!(compute_final_reward(500, 1000, 200), 200);
+    }
+}
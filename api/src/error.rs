@@ -1,7 +1,7 @@
 use steel::*;
 
 #[repr(u32)]
-#[derive(Debug, Error, Clone, Copy, PartialEq, Eq, IntoPrimitive)]
+#[derive(Debug, Error, Clone, Copy, PartialEq, Eq, IntoPrimitive, TryFromPrimitive)]
 pub enum TapeError {
     #[error("Unknown error")]
     UnknownError = 0,
@@ -23,6 +23,16 @@ pub enum TapeError {
     ClockInvalid = 8,
     #[error("The maximum supply has been reached")]
     MaxSupply = 9,
+    #[error("The program is paused by its guardian")]
+    Paused = 10,
+    #[error("The tape has no room left for this write")]
+    TapeFull = 11,
+    #[error("The segment number is past the end of the tape")]
+    SegmentTooLarge = 12,
+    #[error("Part of the claim is still locked")]
+    RewardsLocked = 13,
+    #[error("The miner's deposit is still cooling down")]
+    DepositLocked = 14,
 }
 
 error!(TapeError);
@@ -10,6 +10,8 @@ pub enum EventType {
     WriteEvent,
     UpdateEvent,
     FinalizeEvent,
+    MineEvent,
+    ClaimEvent,
 }
 
 #[repr(C)]
@@ -34,6 +36,27 @@ pub struct FinalizeEvent {
     pub address: [u8; 32],
 }
 
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Pod, Zeroable)]
+pub struct MineEvent {
+    pub miner: [u8; 32],
+    pub tape: u64,
+    pub difficulty: u64,
+    pub reward: u64,
+    pub slot: u64,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Pod, Zeroable)]
+pub struct ClaimEvent {
+    pub miner: [u8; 32],
+    pub beneficiary: [u8; 32],
+    pub amount: u64,
+    pub unclaimed: u64,
+}
+
 event!(EventType, WriteEvent);
 event!(EventType, UpdateEvent);
 event!(EventType, FinalizeEvent);
+event!(EventType, MineEvent);
+event!(EventType, ClaimEvent);
@@ -19,6 +19,20 @@ pub enum InstructionType {
     Close,
     Mine,
     Claim,
+
+    // Guardian instructions
+    Pause,
+    Resume,
+    SetGuardian,
+
+    // Maintenance instructions
+    Migrate,
+
+    // Guardian instructions, after the rest so their discriminants don't move
+    SetMinerDeposit,
+
+    // Maintenance instructions, after the rest so their discriminants don't move
+    InitializeConfig,
 }
 
 instruction!(InstructionType, Initialize);
@@ -34,6 +48,16 @@ instruction!(InstructionType, Close);
 instruction!(InstructionType, Mine);
 instruction!(InstructionType, Claim);
 
+instruction!(InstructionType, Pause);
+instruction!(InstructionType, Resume);
+instruction!(InstructionType, SetGuardian);
+
+instruction!(InstructionType, Migrate);
+
+instruction!(InstructionType, SetMinerDeposit);
+
+instruction!(InstructionType, InitializeConfig);
+
 
 #[repr(C)]
 #[derive(Clone, Copy, Debug, Pod, Zeroable)]
@@ -75,6 +99,8 @@ pub struct Finalize {
 #[derive(Clone, Copy, Debug, Pod, Zeroable)]
 pub struct Register {
     pub name: [u8; 32],
+    pub locked_percent: [u8; 8],
+    pub vest_epochs: [u8; 8],
 }
 
 #[repr(C)]
@@ -95,3 +121,31 @@ pub struct Mine {
 pub struct Claim {
     pub amount: [u8; 8],
 }
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct Pause {}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct Resume {}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct SetGuardian {
+    pub guardian: [u8; 32],
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct Migrate {}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct SetMinerDeposit {
+    pub amount: [u8; 8],
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct InitializeConfig {}
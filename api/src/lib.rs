@@ -1,16 +1,36 @@
+#![cfg_attr(not(feature = "solana"), no_std)]
+
+pub mod verify;
+
+#[cfg(feature = "solana")]
 pub mod consts;
+#[cfg(feature = "solana")]
+pub mod curve;
+#[cfg(feature = "solana")]
 pub mod error;
+#[cfg(feature = "solana")]
 pub mod instruction;
+#[cfg(feature = "solana")]
 pub mod sdk;
+#[cfg(feature = "solana")]
 pub mod state;
+#[cfg(feature = "solana")]
 pub mod pda;
+#[cfg(feature = "solana")]
+pub mod context;
+#[cfg(feature = "solana")]
 pub mod utils;
+#[cfg(feature = "solana")]
 pub mod loaders;
+#[cfg(feature = "solana")]
 pub mod event;
+#[cfg(feature = "solana")]
 mod macros;
 
+#[cfg(feature = "solana")]
 pub use crate::consts::*;
 
+#[cfg(feature = "solana")]
 pub mod prelude {
     pub use crate::consts::*;
     pub use crate::error::*;
@@ -18,11 +38,14 @@ pub mod prelude {
     pub use crate::sdk::*;
     pub use crate::state::*;
     pub use crate::pda::*;
+    pub use crate::context::*;
     pub use crate::utils::*;
     pub use crate::event::*;
     pub use crate::loaders::*;
 }
 
+#[cfg(feature = "solana")]
 use steel::*;
 
+#[cfg(feature = "solana")]
 declare_id!("tape9hFAE7jstfKB2QT1ovFNUZKKtDUyGZiGQpnBFdL"); 
@@ -1,14 +1,17 @@
 use steel::*;
 
 use crate::consts::*;
-use crate::state::{Archive, Epoch, Treasury};
+use crate::state::{Archive, Config, Epoch, Treasury};
 
 pub trait AccountInfoLoader {
     fn is_archive(&self) -> Result<&Self, ProgramError>;
     fn is_epoch(&self) -> Result<&Self, ProgramError>;
     fn is_treasury(&self) -> Result<&Self, ProgramError>;
     fn is_treasury_ata(&self) -> Result<&Self, ProgramError>;
+    fn is_escrow(&self) -> Result<&Self, ProgramError>;
+    fn is_escrow_ata(&self) -> Result<&Self, ProgramError>;
     fn is_spool(&self) -> Result<&Self, ProgramError>;
+    fn is_config(&self) -> Result<&Self, ProgramError>;
 }
 
 impl AccountInfoLoader for AccountInfo<'_> {
@@ -31,10 +34,23 @@ impl AccountInfoLoader for AccountInfo<'_> {
         self.has_address(&TREASURY_ATA)
     }
 
+    fn is_escrow(&self) -> Result<&Self, ProgramError> {
+        self.has_address(&ESCROW_ADDRESS)
+    }
+
+    fn is_escrow_ata(&self) -> Result<&Self, ProgramError> {
+        self.has_address(&ESCROW_ATA)
+    }
+
     fn is_spool(&self) -> Result<&Self, ProgramError> {
         if !SPOOL_ADDRESSES.contains(self.key) {
             return Err(ProgramError::InvalidSeeds);
         }
         Ok(self)
     }
+
+    fn is_config(&self) -> Result<&Self, ProgramError> {
+        self.has_address(&CONFIG_ADDRESS)?
+            .is_type::<Config>(&crate::ID)
+    }
 }
@@ -8,19 +8,34 @@ macro_rules! state {
                 8 + core::mem::size_of::<Self>()
             }
 
-            /// Immutably unpack from a raw account data slice
+            /// Immutably unpack from a raw account data slice, an error if it's shorter than the
+            /// current layout (an account that needs migrating)
             pub fn unpack(data: &[u8]) -> Result<&Self, ProgramError> {
-                let data = &data[..Self::get_size()];
+                let data = data
+                    .get(..Self::get_size())
+                    .ok_or(ProgramError::AccountDataTooSmall)?;
                 Self::try_from_bytes(data)
             }
 
             /// Mutably unpack from a raw account data slice
             pub fn unpack_mut(data: &mut [u8]) -> Result<&mut Self, ProgramError> {
-                let data = &mut data[..Self::get_size()];
+                let data = data
+                    .get_mut(..Self::get_size())
+                    .ok_or(ProgramError::AccountDataTooSmall)?;
                 Self::try_from_bytes_mut(data)
             }
         }
 
+        impl $crate::state::Versioned for $data_ty {
+            fn version(&self) -> u8 {
+                self.version
+            }
+
+            fn set_version(&mut self, version: u8) {
+                self.version = version;
+            }
+        }
+
         // steel account macro
         account!($acct_ty, $data_ty);
     };
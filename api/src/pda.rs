@@ -1,34 +1,119 @@
 use steel::*;
 use crate::consts::*;
+use crate::context::ProgramContext;
+
+impl ProgramContext {
+    pub fn archive_pda(&self) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[ARCHIVE], &self.program_id)
+    }
+
+    pub fn epoch_pda(&self) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[EPOCH], &self.program_id)
+    }
+
+    pub fn config_pda(&self) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[CONFIG], &self.program_id)
+    }
+
+    /// The program's ProgramData account under the upgradeable loader, holding its upgrade authority.
+    pub fn program_data_pda(&self) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[self.program_id.as_ref()], &solana_program::bpf_loader_upgradeable::ID)
+    }
+
+    pub fn treasury_pda(&self) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[TREASURY], &self.program_id)
+    }
+
+    pub fn treasury_ata(&self) -> (Pubkey, u8) {
+        let (treasury_pda, _bump) = self.treasury_pda();
+        let (mint_pda, _bump) = self.mint_pda();
+
+        Pubkey::find_program_address(
+            &[
+                treasury_pda.as_ref(), 
+                spl_token::ID.as_ref(),
+                mint_pda.as_ref()
+            ],
+            &spl_associated_token_account::ID,
+        )
+    }
+
+    /// Signs for the token account that holds miner deposits, apart from the treasury's rewards.
+    pub fn escrow_pda(&self) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[ESCROW], &self.program_id)
+    }
+
+    pub fn escrow_ata(&self) -> (Pubkey, u8) {
+        let (escrow_pda, _bump) = self.escrow_pda();
+        let (mint_pda, _bump) = self.mint_pda();
+
+        Pubkey::find_program_address(
+            &[
+                escrow_pda.as_ref(), 
+                spl_token::ID.as_ref(),
+                mint_pda.as_ref()
+            ],
+            &spl_associated_token_account::ID,
+        )
+    }
+
+    pub fn mint_pda(&self) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[MINT, MINT_SEED], &self.program_id)
+    }
+
+    pub fn spool_pda(&self, id: u8) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[SPOOL, &[id]], &self.program_id)
+    }
+
+    pub fn tape_pda(&self, authority: Pubkey, name: &[u8; NAME_LEN]) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[TAPE, authority.as_ref(), name.as_ref()], &self.program_id)
+    }
+
+    pub fn writer_pda(&self, tape: Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[WRITER, tape.as_ref()], &self.program_id)
+    }
+
+    pub fn miner_pda(&self, authority: Pubkey, name: [u8; NAME_LEN]) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[MINER, authority.as_ref(), name.as_ref()], &self.program_id)
+    }
+}
+
+// The free functions derive addresses for the default deployment, see `ProgramContext` for others.
 
 pub fn archive_pda() -> (Pubkey, u8) {
-    Pubkey::find_program_address(&[ARCHIVE], &crate::id())
+    ProgramContext::default().archive_pda()
 }
 
 pub fn epoch_pda() -> (Pubkey, u8) {
-    Pubkey::find_program_address(&[EPOCH], &crate::id())
+    ProgramContext::default().epoch_pda()
+}
+
+pub fn config_pda() -> (Pubkey, u8) {
+    ProgramContext::default().config_pda()
+}
+
+pub fn program_data_pda() -> (Pubkey, u8) {
+    ProgramContext::default().program_data_pda()
 }
 
 pub fn treasury_pda() -> (Pubkey, u8) {
-    Pubkey::find_program_address(&[TREASURY], &crate::id())
+    ProgramContext::default().treasury_pda()
 }
 
 pub fn treasury_ata() -> (Pubkey, u8) {
-    let (treasury_pda, _bump) = treasury_pda();
-    let (mint_pda, _bump) = mint_pda();
+    ProgramContext::default().treasury_ata()
+}
 
-    Pubkey::find_program_address(
-        &[
-            treasury_pda.as_ref(), 
-            spl_token::ID.as_ref(),
-            mint_pda.as_ref()
-        ],
-        &spl_associated_token_account::ID,
-    )
+pub fn escrow_pda() -> (Pubkey, u8) {
+    ProgramContext::default().escrow_pda()
+}
+
+pub fn escrow_ata() -> (Pubkey, u8) {
+    ProgramContext::default().escrow_ata()
 }
 
 pub fn mint_pda() -> (Pubkey, u8) {
-    Pubkey::find_program_address(&[MINT, MINT_SEED], &crate::id())
+    ProgramContext::default().mint_pda()
 }
 
 pub fn metadata_pda(mint: Pubkey) -> (Pubkey, u8) {
@@ -39,19 +124,19 @@ pub fn metadata_pda(mint: Pubkey) -> (Pubkey, u8) {
 }
 
 pub fn spool_pda(id: u8) -> (Pubkey, u8) {
-    Pubkey::find_program_address(&[SPOOL, &[id]], &crate::id())
+    ProgramContext::default().spool_pda(id)
 }
 
 pub fn tape_pda(authority: Pubkey, name: &[u8; NAME_LEN]) -> (Pubkey, u8) {
-    Pubkey::find_program_address(&[TAPE, authority.as_ref(), name.as_ref()], &crate::id())
+    ProgramContext::default().tape_pda(authority, name)
 }
 
 pub fn writer_pda(tape: Pubkey) -> (Pubkey, u8) {
-    Pubkey::find_program_address(&[WRITER, tape.as_ref()], &crate::id())
+    ProgramContext::default().writer_pda(tape)
 }
 
 pub fn miner_pda(authority: Pubkey, name: [u8; NAME_LEN]) -> (Pubkey, u8) {
-    Pubkey::find_program_address(&[MINER, authority.as_ref(), name.as_ref()], &crate::id())
+    ProgramContext::default().miner_pda(authority, name)
 }
 
 #[cfg(test)]
@@ -68,6 +153,10 @@ mod tests {
         assert_eq!(bump, EPOCH_BUMP);
         assert_eq!(pda, EPOCH_ADDRESS);
 
+        let (pda, bump) = config_pda();
+        assert_eq!(bump, CONFIG_BUMP);
+        assert_eq!(pda, CONFIG_ADDRESS);
+
         let (pda, bump) = mint_pda();
         assert_eq!(bump, MINT_BUMP);
         assert_eq!(pda, MINT_ADDRESS);
@@ -78,5 +167,12 @@ mod tests {
 
         let (pda, _bump) = treasury_ata();
         assert_eq!(pda, TREASURY_ATA);
+
+        let (pda, bump) = escrow_pda();
+        assert_eq!(bump, ESCROW_BUMP);
+        assert_eq!(pda, ESCROW_ADDRESS);
+
+        let (pda, _bump) = escrow_ata();
+        assert_eq!(pda, ESCROW_ATA);
     }
 }
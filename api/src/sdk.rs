@@ -4,36 +4,436 @@ use crankx::Solution;
 use crate::{
     consts::*, 
     instruction::*, 
-    pda::*,
+    context::ProgramContext,
+    pda::metadata_pda,
     utils,
 };
 
+impl ProgramContext {
+    pub fn build_create_ix(
+        &self,
+        signer: Pubkey,
+        name: &str,
+        header: Option<[u8; HEADER_SIZE]>,
+    ) -> Instruction {
+        let name = utils::to_name(name);
+        let header = header.unwrap_or([0; HEADER_SIZE]);
+
+        let (tape_address, _tape_bump) = self.tape_pda(signer, &name);
+        let (writer_address, _writer_bump) = self.writer_pda(tape_address);
+
+        Instruction {
+            program_id: self.program_id,
+            accounts: vec![
+                AccountMeta::new(signer, true),
+                AccountMeta::new(tape_address, false),
+                AccountMeta::new(writer_address, false),
+                AccountMeta::new_readonly(solana_program::system_program::ID, false),
+                AccountMeta::new_readonly(sysvar::rent::ID, false),
+                AccountMeta::new_readonly(sysvar::slot_hashes::ID, false),
+            ],
+            data: Create {
+                name,
+                header,
+            }.to_bytes(),
+        }
+    }
+
+    pub fn build_write_ix(
+        &self,
+        signer: Pubkey,
+        tape: Pubkey,
+        writer: Pubkey,
+        data: &[u8],
+    ) -> Instruction {
+
+        let mut ix_data = Write{}.to_bytes();
+        ix_data.extend_from_slice(data);
+
+        Instruction {
+            program_id: self.program_id,
+            accounts: vec![
+                AccountMeta::new(signer, true),
+                AccountMeta::new(tape, false),
+                AccountMeta::new(writer, false),
+                AccountMeta::new_readonly(self.config_address(), false),
+            ],
+            data: ix_data,
+        }
+    }
+
+    pub fn build_update_ix(
+        &self,
+        signer: Pubkey,
+        tape: Pubkey,
+        writer: Pubkey,
+        segment_number: u64,
+        old_data: [u8; SEGMENT_SIZE],
+        new_data: [u8; SEGMENT_SIZE],
+        proof: [[u8;32]; PROOF_LEN],
+    ) -> Instruction {
+
+        let segment_number = segment_number.to_le_bytes();
+
+        Instruction {
+            program_id: self.program_id,
+            accounts: vec![
+                AccountMeta::new(signer, true),
+                AccountMeta::new(tape, false),
+                AccountMeta::new(writer, false),
+                AccountMeta::new_readonly(self.config_address(), false),
+            ],
+            data: Update {
+                segment_number,
+                old_data,
+                new_data,
+                proof,
+            }.to_bytes(),
+        }
+    }
+
+    pub fn build_finalize_ix(
+        &self,
+        signer: Pubkey, 
+        tape: Pubkey,
+        writer: Pubkey,
+        header: Option<[u8; HEADER_SIZE]>,
+    ) -> Instruction {
+        let header = header.unwrap_or([0; HEADER_SIZE]);
+
+        Instruction {
+            program_id: self.program_id,
+            accounts: vec![
+                AccountMeta::new(signer, true),
+                AccountMeta::new(tape, false),
+                AccountMeta::new(writer, false),
+                AccountMeta::new(self.archive_address(), false),
+                AccountMeta::new_readonly(solana_program::system_program::ID, false),
+                AccountMeta::new_readonly(sysvar::rent::ID, false),
+            ],
+            data: Finalize {
+                header,
+            }.to_bytes(),
+        }
+    }
+
+    pub fn build_register_ix(
+        &self,
+        signer: Pubkey, 
+        name: &str
+    ) -> Instruction {
+        self.build_register_with_lockup_ix(signer, name, 0, 0)
+    }
+
+    /// Register a miner that locks `locked_percent` of each reward it mines, unlocking it evenly
+    /// over `vest_epochs` epochs. The miner deposit, if the guardian set one, is paid from the
+    /// signer's TAPE token account into escrow.
+    pub fn build_register_with_lockup_ix(
+        &self,
+        signer: Pubkey, 
+        name: &str,
+        locked_percent: u64,
+        vest_epochs: u64,
+    ) -> Instruction {
+        let name = utils::to_name(name);
+        let (miner_address, _bump) = self.miner_pda(signer, name);
+
+        Instruction {
+            program_id: self.program_id,
+            accounts: vec![
+                AccountMeta::new(signer, true),
+                AccountMeta::new(miner_address, false),
+                AccountMeta::new_readonly(self.archive_address(), false),
+                AccountMeta::new_readonly(solana_program::system_program::ID, false),
+                AccountMeta::new_readonly(sysvar::rent::ID, false),
+                AccountMeta::new_readonly(sysvar::slot_hashes::ID, false),
+                AccountMeta::new_readonly(self.config_address(), false),
+                AccountMeta::new(self.tape_ata_address(&signer), false),
+                AccountMeta::new_readonly(self.escrow_address(), false),
+                AccountMeta::new(self.escrow_ata_address(), false),
+                AccountMeta::new_readonly(self.mint_address(), false),
+                AccountMeta::new_readonly(spl_token::ID, false),
+                AccountMeta::new_readonly(spl_associated_token_account::ID, false),
+            ],
+            data: Register {
+                name,
+                locked_percent: locked_percent.to_le_bytes(),
+                vest_epochs: vest_epochs.to_le_bytes(),
+            }.to_bytes(),
+        }
+    }
+
+    pub fn build_mine_ix(
+        &self,
+        signer: Pubkey,
+        miner: Pubkey,
+        spool: Pubkey,
+        tape: Pubkey,
+        solution: Solution,
+        recall_segment: [u8; SEGMENT_SIZE],
+        recall_proof: [[u8;32]; PROOF_LEN],
+    ) -> Instruction {
+        Instruction {
+            program_id: self.program_id,
+            accounts: vec![
+                AccountMeta::new(signer, true),
+                AccountMeta::new(spool, false),
+                AccountMeta::new(miner, false),
+                AccountMeta::new_readonly(tape, false),
+                AccountMeta::new_readonly(self.epoch_address(), false),
+                AccountMeta::new_readonly(self.archive_address(), false),
+                AccountMeta::new_readonly(sysvar::slot_hashes::ID, false),
+                AccountMeta::new_readonly(self.config_address(), false),
+            ],
+            data: Mine {
+                digest: solution.d,
+                nonce: solution.n,
+                recall_segment,
+                recall_proof,
+            }.to_bytes(),
+        }
+    }
+
+    pub fn build_claim_ix(
+        &self,
+        signer: Pubkey, 
+        miner: Pubkey,
+        beneficiary: Pubkey, 
+        amount: u64
+    ) -> Instruction {
+        Instruction {
+            program_id: self.program_id,
+            accounts: vec![
+                AccountMeta::new(signer, true),
+                AccountMeta::new(beneficiary, false),
+                AccountMeta::new(miner, false),
+                AccountMeta::new_readonly(self.treasury_address(), false),
+                AccountMeta::new(self.treasury_ata_address(), false),
+                AccountMeta::new_readonly(spl_token::ID, false),
+            ],
+            data: Claim {
+                amount: amount.to_le_bytes(),
+            }.to_bytes(),
+        }
+    }
+
+    /// Close a miner, refunding its deposit to the signer's TAPE token account. A miner without a
+    /// deposit only needs the first three accounts.
+    pub fn build_close_ix(
+        &self,
+        signer: Pubkey,
+        miner: Pubkey,
+    ) -> Instruction {
+        Instruction {
+            program_id: self.program_id,
+            accounts: vec![
+                AccountMeta::new(signer, true),
+                AccountMeta::new(miner, false),
+                AccountMeta::new_readonly(solana_program::system_program::ID, false),
+                AccountMeta::new(self.tape_ata_address(&signer), false),
+                AccountMeta::new_readonly(self.escrow_address(), false),
+                AccountMeta::new(self.escrow_ata_address(), false),
+                AccountMeta::new_readonly(spl_token::ID, false),
+            ],
+            data: Close {}.to_bytes(),
+        }
+    }
+
+    pub fn build_initialize_ix(
+        &self,
+        signer: Pubkey
+    ) -> Instruction {
+        let spool_pdas = [
+            self.spool_pda(0).0,
+            self.spool_pda(1).0,
+            self.spool_pda(2).0,
+            self.spool_pda(3).0,
+            self.spool_pda(4).0,
+            self.spool_pda(5).0,
+            self.spool_pda(6).0,
+            self.spool_pda(7).0,
+        ];
+
+        let (archive_pda, _archive_bump) = self.archive_pda();
+        let (epoch_pda, _epoch_bump) = self.epoch_pda();
+        let (config_pda, _config_bump) = self.config_pda();
+        let (mint_pda, _mint_bump) = self.mint_pda();
+        let (treasury_pda, _treasury_bump) = self.treasury_pda();
+        let (treasury_ata, _treasury_ata_bump) = self.treasury_ata();
+        let (metadata_pda, _metadata_bump) = metadata_pda(mint_pda);
+
+        if self.is_default() {
+            assert_eq!(archive_pda, ARCHIVE_ADDRESS);
+            assert_eq!(epoch_pda, EPOCH_ADDRESS);
+            assert_eq!(config_pda, CONFIG_ADDRESS);
+            assert_eq!(mint_pda, MINT_ADDRESS);
+            assert_eq!(treasury_pda, TREASURY_ADDRESS);
+            assert_eq!(treasury_ata, TREASURY_ATA);
+        }
+
+        Instruction {
+            program_id: self.program_id,
+            accounts: vec![
+                AccountMeta::new(signer, true),
+                AccountMeta::new(spool_pdas[0], false),
+                AccountMeta::new(spool_pdas[1], false),
+                AccountMeta::new(spool_pdas[2], false),
+                AccountMeta::new(spool_pdas[3], false),
+                AccountMeta::new(spool_pdas[4], false),
+                AccountMeta::new(spool_pdas[5], false),
+                AccountMeta::new(spool_pdas[6], false),
+                AccountMeta::new(spool_pdas[7], false),
+                AccountMeta::new(archive_pda, false),
+                AccountMeta::new(epoch_pda, false),
+                AccountMeta::new(config_pda, false),
+                AccountMeta::new(metadata_pda, false),
+                AccountMeta::new(mint_pda, false),
+                AccountMeta::new(treasury_pda, false),
+                AccountMeta::new(treasury_ata, false),
+                AccountMeta::new_readonly(system_program::ID, false),
+                AccountMeta::new_readonly(spl_token::ID, false),
+                AccountMeta::new_readonly(spl_associated_token_account::ID, false),
+                AccountMeta::new_readonly(mpl_token_metadata::ID, false),
+                AccountMeta::new_readonly(sysvar::rent::ID, false),
+            ],
+            data: Initialize {}.to_bytes(),
+        }
+    }
+
+    pub fn build_advance_ix(
+        &self,
+        signer: Pubkey
+    ) -> Instruction {
+        let spools = self.spool_addresses();
+
+        Instruction {
+            program_id: self.program_id,
+            accounts: vec![
+                AccountMeta::new(signer, true),
+                AccountMeta::new(spools[0], false),
+                AccountMeta::new(spools[1], false),
+                AccountMeta::new(spools[2], false),
+                AccountMeta::new(spools[3], false),
+                AccountMeta::new(spools[4], false),
+                AccountMeta::new(spools[5], false),
+                AccountMeta::new(spools[6], false),
+                AccountMeta::new(spools[7], false),
+                AccountMeta::new(self.epoch_address(), false),
+                AccountMeta::new(self.mint_address(), false),
+                AccountMeta::new(self.treasury_address(), false),
+                AccountMeta::new(self.treasury_ata_address(), false),
+                AccountMeta::new_readonly(spl_token::ID, false),
+            ],
+            data: Advance {}.to_bytes(),
+        }
+    }
+
+    pub fn build_pause_ix(
+        &self,
+        signer: Pubkey
+    ) -> Instruction {
+        Instruction {
+            program_id: self.program_id,
+            accounts: vec![
+                AccountMeta::new(signer, true),
+                AccountMeta::new(self.config_address(), false),
+            ],
+            data: Pause {}.to_bytes(),
+        }
+    }
+
+    pub fn build_resume_ix(
+        &self,
+        signer: Pubkey
+    ) -> Instruction {
+        Instruction {
+            program_id: self.program_id,
+            accounts: vec![
+                AccountMeta::new(signer, true),
+                AccountMeta::new(self.config_address(), false),
+            ],
+            data: Resume {}.to_bytes(),
+        }
+    }
+
+    pub fn build_set_guardian_ix(
+        &self,
+        signer: Pubkey,
+        guardian: Pubkey,
+    ) -> Instruction {
+        Instruction {
+            program_id: self.program_id,
+            accounts: vec![
+                AccountMeta::new(signer, true),
+                AccountMeta::new(self.config_address(), false),
+            ],
+            data: SetGuardian {
+                guardian: guardian.to_bytes(),
+            }.to_bytes(),
+        }
+    }
+
+    /// Set the TAPE new miners lock at registration, zero for none.
+    pub fn build_set_miner_deposit_ix(
+        &self,
+        signer: Pubkey,
+        amount: u64,
+    ) -> Instruction {
+        Instruction {
+            program_id: self.program_id,
+            accounts: vec![
+                AccountMeta::new(signer, true),
+                AccountMeta::new(self.config_address(), false),
+            ],
+            data: SetMinerDeposit {
+                amount: amount.to_le_bytes(),
+            }.to_bytes(),
+        }
+    }
+
+    pub fn build_migrate_ix(
+        &self,
+        signer: Pubkey,
+        account: Pubkey,
+    ) -> Instruction {
+        Instruction {
+            program_id: self.program_id,
+            accounts: vec![
+                AccountMeta::new(signer, true),
+                AccountMeta::new(account, false),
+                AccountMeta::new_readonly(solana_program::system_program::ID, false),
+            ],
+            data: Migrate {}.to_bytes(),
+        }
+    }
+
+    /// Create the config account of a deployment initialized before it existed. Only the
+    /// program's upgrade authority can sign this, and becomes the guardian.
+    pub fn build_initialize_config_ix(
+        &self,
+        signer: Pubkey,
+    ) -> Instruction {
+        Instruction {
+            program_id: self.program_id,
+            accounts: vec![
+                AccountMeta::new(signer, true),
+                AccountMeta::new(self.config_address(), false),
+                AccountMeta::new_readonly(self.program_data_pda().0, false),
+                AccountMeta::new_readonly(solana_program::system_program::ID, false),
+            ],
+            data: InitializeConfig {}.to_bytes(),
+        }
+    }
+}
+
+// The free functions build instructions for the default deployment, see `ProgramContext` for others.
+
 pub fn build_create_ix(
     signer: Pubkey,
     name: &str,
     header: Option<[u8; HEADER_SIZE]>,
 ) -> Instruction {
-    let name = utils::to_name(name);
-    let header = header.unwrap_or([0; HEADER_SIZE]);
-
-    let (tape_address, _tape_bump) = tape_pda(signer, &name);
-    let (writer_address, _writer_bump) = writer_pda(tape_address);
-
-    Instruction {
-        program_id: crate::ID,
-        accounts: vec![
-            AccountMeta::new(signer, true),
-            AccountMeta::new(tape_address, false),
-            AccountMeta::new(writer_address, false),
-            AccountMeta::new_readonly(solana_program::system_program::ID, false),
-            AccountMeta::new_readonly(sysvar::rent::ID, false),
-            AccountMeta::new_readonly(sysvar::slot_hashes::ID, false),
-        ],
-        data: Create {
-            name,
-            header,
-        }.to_bytes(),
-    }
+    ProgramContext::default().build_create_ix(signer, name, header)
 }
 
 pub fn build_write_ix(
@@ -42,19 +442,7 @@ pub fn build_write_ix(
     writer: Pubkey,
     data: &[u8],
 ) -> Instruction {
-
-    let mut ix_data = Write{}.to_bytes();
-    ix_data.extend_from_slice(data);
-
-    Instruction {
-        program_id: crate::ID,
-        accounts: vec![
-            AccountMeta::new(signer, true),
-            AccountMeta::new(tape, false),
-            AccountMeta::new(writer, false),
-        ],
-        data: ix_data,
-    }
+    ProgramContext::default().build_write_ix(signer, tape, writer, data)
 }
 
 pub fn build_update_ix(
@@ -66,23 +454,7 @@ pub fn build_update_ix(
     new_data: [u8; SEGMENT_SIZE],
     proof: [[u8;32]; PROOF_LEN],
 ) -> Instruction {
-
-    let segment_number = segment_number.to_le_bytes();
-
-    Instruction {
-        program_id: crate::ID,
-        accounts: vec![
-            AccountMeta::new(signer, true),
-            AccountMeta::new(tape, false),
-            AccountMeta::new(writer, false),
-        ],
-        data: Update {
-            segment_number,
-            old_data,
-            new_data,
-            proof,
-        }.to_bytes(),
-    }
+    ProgramContext::default().build_update_ix(signer, tape, writer, segment_number, old_data, new_data, proof)
 }
 
 pub fn build_finalize_ix(
@@ -91,45 +463,23 @@ pub fn build_finalize_ix(
     writer: Pubkey,
     header: Option<[u8; HEADER_SIZE]>,
 ) -> Instruction {
-    let header = header.unwrap_or([0; HEADER_SIZE]);
-
-    Instruction {
-        program_id: crate::ID,
-        accounts: vec![
-            AccountMeta::new(signer, true),
-            AccountMeta::new(tape, false),
-            AccountMeta::new(writer, false),
-            AccountMeta::new(ARCHIVE_ADDRESS, false),
-            AccountMeta::new_readonly(solana_program::system_program::ID, false),
-            AccountMeta::new_readonly(sysvar::rent::ID, false),
-        ],
-        data: Finalize {
-            header,
-        }.to_bytes(),
-    }
+    ProgramContext::default().build_finalize_ix(signer, tape, writer, header)
 }
 
 pub fn build_register_ix(
     signer: Pubkey, 
     name: &str
 ) -> Instruction {
-    let name = utils::to_name(name);
-    let (miner_address, _bump) = miner_pda(signer, name);
-
-    Instruction {
-        program_id: crate::ID,
-        accounts: vec![
-            AccountMeta::new(signer, true),
-            AccountMeta::new(miner_address, false),
-            AccountMeta::new_readonly(ARCHIVE_ADDRESS, false),
-            AccountMeta::new_readonly(solana_program::system_program::ID, false),
-            AccountMeta::new_readonly(sysvar::rent::ID, false),
-            AccountMeta::new_readonly(sysvar::slot_hashes::ID, false),
-        ],
-        data: Register {
-            name,
-        }.to_bytes(),
-    }
+    ProgramContext::default().build_register_ix(signer, name)
+}
+
+pub fn build_register_with_lockup_ix(
+    signer: Pubkey, 
+    name: &str,
+    locked_percent: u64,
+    vest_epochs: u64,
+) -> Instruction {
+    ProgramContext::default().build_register_with_lockup_ix(signer, name, locked_percent, vest_epochs)
 }
 
 pub fn build_mine_ix(
@@ -141,24 +491,7 @@ pub fn build_mine_ix(
     recall_segment: [u8; SEGMENT_SIZE],
     recall_proof: [[u8;32]; PROOF_LEN],
 ) -> Instruction {
-    Instruction {
-        program_id: crate::ID,
-        accounts: vec![
-            AccountMeta::new(signer, true),
-            AccountMeta::new(spool, false),
-            AccountMeta::new(miner, false),
-            AccountMeta::new_readonly(tape, false),
-            AccountMeta::new_readonly(EPOCH_ADDRESS, false),
-            AccountMeta::new_readonly(ARCHIVE_ADDRESS, false),
-            AccountMeta::new_readonly(sysvar::slot_hashes::ID, false),
-        ],
-        data: Mine {
-            digest: solution.d,
-            nonce: solution.n,
-            recall_segment,
-            recall_proof,
-        }.to_bytes(),
-    }
+    ProgramContext::default().build_mine_ix(signer, miner, spool, tape, solution, recall_segment, recall_proof)
 }
 
 pub fn build_claim_ix(
@@ -167,113 +500,63 @@ pub fn build_claim_ix(
     beneficiary: Pubkey, 
     amount: u64
 ) -> Instruction {
-    Instruction {
-        program_id: crate::ID,
-        accounts: vec![
-            AccountMeta::new(signer, true),
-            AccountMeta::new(beneficiary, false),
-            AccountMeta::new(miner, false),
-            AccountMeta::new_readonly(TREASURY_ADDRESS, false),
-            AccountMeta::new(TREASURY_ATA, false),
-            AccountMeta::new_readonly(spl_token::ID, false),
-        ],
-        data: Claim {
-            amount: amount.to_le_bytes(),
-        }.to_bytes(),
-    }
+    ProgramContext::default().build_claim_ix(signer, miner, beneficiary, amount)
 }
 
 pub fn build_close_ix(
     signer: Pubkey,
     miner: Pubkey,
 ) -> Instruction {
-    Instruction {
-        program_id: crate::ID,
-        accounts: vec![
-            AccountMeta::new(signer, true),
-            AccountMeta::new(miner, false),
-            AccountMeta::new_readonly(solana_program::system_program::ID, false),
-        ],
-        data: Close {}.to_bytes(),
-    }
+    ProgramContext::default().build_close_ix(signer, miner)
 }
 
 pub fn build_initialize_ix(
     signer: Pubkey
 ) -> Instruction {
-    let spool_pdas = [
-        spool_pda(0).0,
-        spool_pda(1).0,
-        spool_pda(2).0,
-        spool_pda(3).0,
-        spool_pda(4).0,
-        spool_pda(5).0,
-        spool_pda(6).0,
-        spool_pda(7).0,
-    ];
-
-    let (archive_pda, _archive_bump) = archive_pda();
-    let (epoch_pda, _epoch_bump) = epoch_pda();
-    let (mint_pda, _mint_bump) = mint_pda();
-    let (treasury_pda, _treasury_bump) = treasury_pda();
-    let (treasury_ata, _treasury_ata_bump) = treasury_ata();
-    let (metadata_pda, _metadata_bump) = metadata_pda(mint_pda);
-
-    assert_eq!(archive_pda, ARCHIVE_ADDRESS);
-    assert_eq!(epoch_pda, EPOCH_ADDRESS);
-    assert_eq!(mint_pda, MINT_ADDRESS);
-    assert_eq!(treasury_pda, TREASURY_ADDRESS);
-    assert_eq!(treasury_ata, TREASURY_ATA);
-
-    Instruction {
-        program_id: crate::ID,
-        accounts: vec![
-            AccountMeta::new(signer, true),
-            AccountMeta::new(spool_pdas[0], false),
-            AccountMeta::new(spool_pdas[1], false),
-            AccountMeta::new(spool_pdas[2], false),
-            AccountMeta::new(spool_pdas[3], false),
-            AccountMeta::new(spool_pdas[4], false),
-            AccountMeta::new(spool_pdas[5], false),
-            AccountMeta::new(spool_pdas[6], false),
-            AccountMeta::new(spool_pdas[7], false),
-            AccountMeta::new(archive_pda, false),
-            AccountMeta::new(epoch_pda, false),
-            AccountMeta::new(metadata_pda, false),
-            AccountMeta::new(mint_pda, false),
-            AccountMeta::new(treasury_pda, false),
-            AccountMeta::new(treasury_ata, false),
-            AccountMeta::new_readonly(system_program::ID, false),
-            AccountMeta::new_readonly(spl_token::ID, false),
-            AccountMeta::new_readonly(spl_associated_token_account::ID, false),
-            AccountMeta::new_readonly(mpl_token_metadata::ID, false),
-            AccountMeta::new_readonly(sysvar::rent::ID, false),
-        ],
-        data: Initialize {}.to_bytes(),
-    }
+    ProgramContext::default().build_initialize_ix(signer)
 }
 
 pub fn build_advance_ix(
     signer: Pubkey
 ) -> Instruction {
-    Instruction {
-        program_id: crate::ID,
-        accounts: vec![
-            AccountMeta::new(signer, true),
-            AccountMeta::new(SPOOL_ADDRESSES[0], false),
-            AccountMeta::new(SPOOL_ADDRESSES[1], false),
-            AccountMeta::new(SPOOL_ADDRESSES[2], false),
-            AccountMeta::new(SPOOL_ADDRESSES[3], false),
-            AccountMeta::new(SPOOL_ADDRESSES[4], false),
-            AccountMeta::new(SPOOL_ADDRESSES[5], false),
-            AccountMeta::new(SPOOL_ADDRESSES[6], false),
-            AccountMeta::new(SPOOL_ADDRESSES[7], false),
-            AccountMeta::new(EPOCH_ADDRESS, false),
-            AccountMeta::new(MINT_ADDRESS, false),
-            AccountMeta::new(TREASURY_ADDRESS, false),
-            AccountMeta::new(TREASURY_ATA, false),
-            AccountMeta::new_readonly(spl_token::ID, false),
-        ],
-        data: Advance {}.to_bytes(),
-    }
+    ProgramContext::default().build_advance_ix(signer)
+}
+
+pub fn build_pause_ix(
+    signer: Pubkey
+) -> Instruction {
+    ProgramContext::default().build_pause_ix(signer)
+}
+
+pub fn build_resume_ix(
+    signer: Pubkey
+) -> Instruction {
+    ProgramContext::default().build_resume_ix(signer)
+}
+
+pub fn build_set_guardian_ix(
+    signer: Pubkey,
+    guardian: Pubkey,
+) -> Instruction {
+    ProgramContext::default().build_set_guardian_ix(signer, guardian)
+}
+
+pub fn build_set_miner_deposit_ix(
+    signer: Pubkey,
+    amount: u64,
+) -> Instruction {
+    ProgramContext::default().build_set_miner_deposit_ix(signer, amount)
+}
+
+pub fn build_migrate_ix(
+    signer: Pubkey,
+    account: Pubkey,
+) -> Instruction {
+    ProgramContext::default().build_migrate_ix(signer, account)
+}
+
+pub fn build_initialize_config_ix(
+    signer: Pubkey,
+) -> Instruction {
+    ProgramContext::default().build_initialize_config_ix(signer)
 }
@@ -6,6 +6,10 @@ use crate::state;
 #[derive(Clone, Copy, Debug, PartialEq, Pod, Zeroable)]
 pub struct Archive {
     pub tapes_stored: u64,
+
+    pub version: u8,
+    pub _padding: [u8; 7],
+    pub reserved: [u8; 64],
 }
 
 state!(AccountType, Archive);
@@ -0,0 +1,29 @@
+use steel::*;
+use super::AccountType;
+use crate::state;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Pod, Zeroable)]
+pub struct Config {
+    /// Authority allowed to pause and resume the program.
+    pub guardian: Pubkey,
+
+    /// Non-zero while Write, Update and Mine are paused.
+    pub paused: u64,
+
+    pub version: u8,
+    pub _padding: [u8; 7],
+
+    /// TAPE a miner locks at registration, set by the guardian. Zero for none.
+    pub miner_deposit: u64,
+
+    pub reserved: [u8; 56],
+}
+
+impl Config {
+    pub fn is_paused(&self) -> bool {
+        self.paused != 0
+    }
+}
+
+state!(AccountType, Config);
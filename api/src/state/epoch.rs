@@ -10,6 +10,10 @@ pub struct Epoch {
     pub last_epoch_at: i64,
     pub base_rate: u64,
     pub target_rate: u64,
+
+    pub version: u8,
+    pub _padding: [u8; 7],
+    pub reserved: [u8; 64],
 }
 
 state!(AccountType, Epoch);
@@ -19,6 +19,25 @@ pub struct Miner {
     pub last_proof_at: i64,
     pub total_proofs: u64,
     pub total_rewards: u64,
+
+    pub version: u8,
+    pub _padding: [u8; 7],
+
+    /// Percent of each reward locked when it's mined, set at registration. Zero for none.
+    pub locked_percent: u64,
+    /// Epochs a locked reward takes to unlock.
+    pub vest_epochs: u64,
+    /// Part of `unclaimed_rewards` still locked as of `vested_at`.
+    pub locked_rewards: u64,
+    /// When `locked_rewards` was last brought up to date.
+    pub vested_at: i64,
+    /// When the locked rewards are fully unlocked, they unlock evenly until then.
+    pub vest_end: i64,
+
+    /// TAPE locked at registration, refunded when the miner is closed after the cooldown.
+    pub deposit: u64,
+
+    pub reserved: [u8; 16],
 }
 
 state!(AccountType, Miner);
@@ -5,6 +5,7 @@ mod tape;
 mod treasury;
 mod writer;
 mod miner;
+mod config;
 
 pub use archive::*;
 pub use epoch::*;
@@ -13,6 +14,7 @@ pub use tape::*;
 pub use treasury::*;
 pub use writer::*;
 pub use miner::*;
+pub use config::*;
 
 use steel::*;
 
@@ -27,4 +29,69 @@ pub enum AccountType {
     Miner,
     Epoch,
     Treasury,
+    Config,
+}
+
+impl AccountType {
+    /// Size of an account of this type in the current layout, discriminator included.
+    pub fn account_size(&self) -> Option<usize> {
+        match self {
+            AccountType::Archive => Some(Archive::get_size()),
+            AccountType::Spool => Some(Spool::get_size()),
+            AccountType::Writer => Some(Writer::get_size()),
+            AccountType::Tape => Some(Tape::get_size()),
+            AccountType::Miner => Some(Miner::get_size()),
+            AccountType::Epoch => Some(Epoch::get_size()),
+            AccountType::Treasury => Some(Treasury::get_size()),
+            AccountType::Config => Some(Config::get_size()),
+            AccountType::Unknown => None,
+        }
+    }
+}
+
+/// Whether `data` is a program account created before its layout grew. Until the `Migrate`
+/// instruction brings it up to size, the program rejects it and `unpack` fails on it.
+pub fn needs_migration(data: &[u8]) -> bool {
+    data.first()
+        .and_then(|d| AccountType::try_from(*d).ok())
+        .and_then(|t| t.account_size())
+        .is_some_and(|size| data.len() < size)
+}
+
+/// Every state struct ends with the same tail:
+///
+/// - `version` (1 byte)   -> layout version, `ACCOUNT_VERSION` when created
+/// - `_padding` (7 bytes) -> keeps the struct 8-byte aligned
+/// - `reserved` (64 bytes) -> zeroed space for future fields
+///
+/// New fields are carved out of `reserved` (shrinking it by the same amount) so the account size
+/// doesn't change, and `ACCOUNT_VERSION` is bumped when their meaning needs to be told apart.
+/// When a layout has to grow past that, accounts are brought up to date with the `Migrate`
+/// instruction, which reallocs them to the current size (the signer tops up rent, new bytes are
+/// zeroed) and stamps the current version. Processors can then check `version()` before relying
+/// on a field.
+pub trait Versioned {
+    fn version(&self) -> u8;
+    fn set_version(&mut self, version: u8);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_legacy_account() {
+        let mut current = vec![0; Miner::get_size()];
+        current[0] = AccountType::Miner as u8;
+        assert!(!needs_migration(&current));
+        assert!(Miner::unpack(&current).is_ok());
+
+        // Created before the versioned tail existed
+        let legacy = &current[..Miner::get_size() - 72];
+        assert!(needs_migration(legacy));
+        assert_eq!(Miner::unpack(legacy).err(), Some(ProgramError::AccountDataTooSmall));
+
+        assert!(!needs_migration(&[]));
+        assert!(!needs_migration(&[AccountType::Unknown as u8; 8]));
+    }
 }
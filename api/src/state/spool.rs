@@ -8,6 +8,10 @@ pub struct Spool {
     pub id: u64,
     pub available_rewards: u64,
     pub theoretical_rewards: u64,
+
+    pub version: u8,
+    pub _padding: [u8; 7],
+    pub reserved: [u8; 64],
 }
 
 state!(AccountType, Spool);
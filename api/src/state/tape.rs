@@ -18,6 +18,10 @@ pub struct Tape {
 
     pub total_segments: u64,
     pub total_size: u64,
+
+    pub version: u8,
+    pub _padding: [u8; 7],
+    pub reserved: [u8; 64],
 }
 
 #[repr(u64)]
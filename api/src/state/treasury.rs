@@ -4,6 +4,10 @@ use crate::state;
 
 #[repr(C)]
 #[derive(Clone, Copy, Debug, PartialEq, Pod, Zeroable)]
-pub struct Treasury {}
+pub struct Treasury {
+    pub version: u8,
+    pub _padding: [u8; 7],
+    pub reserved: [u8; 64],
+}
 
 state!(AccountType, Treasury);
@@ -9,6 +9,10 @@ use brine_tree::MerkleTree;
 pub struct Writer {
     pub tape: Pubkey,
     pub state: MerkleTree<{TREE_HEIGHT}>, 
+
+    pub version: u8,
+    pub _padding: [u8; 7],
+    pub reserved: [u8; 64],
 }
 
 state!(AccountType, Writer);
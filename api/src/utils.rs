@@ -1,8 +1,16 @@
 use steel::*;
 use crate::consts::*;
 use crate::error::*;
+use crate::state::{Miner, Tape};
 use brine_tree::{MerkleTree, Leaf};
 
+pub use crate::verify::{
+    compute_recall_segment,
+    compute_recall_tape,
+    compute_recall_tape_v1,
+    compute_recall_tape_v2,
+};
+
 /// Helper: check a condition is true and return an error if not
 #[inline(always)]
 pub fn check_condition<E>(condition: bool, err: E) -> ProgramResult
@@ -24,19 +32,19 @@ pub fn padded_array<const N: usize>(input: &[u8]) -> [u8; N] {
     out
 }
 
-/// Helper: convert a name to a fixed-size array
+/// Helper: convert a name to a fixed-size array, panicking if it's longer than `NAME_LEN`
 #[inline(always)]
 pub fn to_name(val: &str) -> [u8; NAME_LEN] {
     assert!(val.len() <= NAME_LEN, "name too long");
     padded_array::<NAME_LEN>(val.as_bytes())
 }
 
-/// Helper: convert a name to a string
+/// Helper: convert a name to a string, replacing invalid UTF-8 written by other clients
 #[inline(always)]
 pub fn from_name(val: &[u8; NAME_LEN]) -> String {
     let mut name_bytes = val.to_vec();
     name_bytes.retain(|&x| x != 0);
-    String::from_utf8(name_bytes).unwrap()
+    String::from_utf8_lossy(&name_bytes).into_owned()
 }
 
 /// Helper: compute a leaf from a segment id and segment data
@@ -53,6 +61,17 @@ pub fn compute_leaf(
     ])
 }
 
+/// Helper: whether a write of `len` bytes fits on a tape already holding `total_segments`
+/// segments and `total_size` bytes. Each write is padded to whole segments, and the writer tree
+/// has room for `MAX_SEGMENTS` of them.
+#[inline(always)]
+pub fn write_fits(total_segments: u64, total_size: u64, len: usize) -> bool {
+    let segments = len.div_ceil(SEGMENT_SIZE) as u64;
+
+    total_segments.saturating_add(segments) <= MAX_SEGMENTS as u64 &&
+    total_size.saturating_add(len as u64) <= MAX_TAPE_SIZE as u64
+}
+
 /// Helper: write segment to the Merkle tree
 #[inline(always)]
 pub fn write_segment(
@@ -99,33 +118,265 @@ pub fn update_segment(
     Ok(())
 }
 
-/// Helper: compute the recall tape number from a given challenge
+/// Helper: the tape and segment a miner has to prove next. The recall tape was picked when the
+/// miner's current challenge was set, the segment comes from the same challenge. The program,
+/// miners and tests all go through this so they can't disagree on what is being proven.
 #[inline(always)]
-pub fn compute_recall_tape(
-    challenge: &[u8; 32],
-    total_tapes: u64,
-) -> u64 {
-    // Prevent division by zero
-    if total_tapes == 0 {
-        return 1;
+pub fn compute_recall_indices(miner: &Miner, tape: &Tape) -> (u64, u64) {
+    (
+        miner.recall_tape,
+        compute_recall_segment(&miner.current_challenge, tape.total_segments),
+    )
+}
+
+/// Helper: the part of a miner's rewards still locked at `now`. Locked rewards unlock evenly
+/// from `vested_at` until `vest_end`, rounded so nothing unlocks early.
+#[inline(always)]
+pub fn locked_rewards_at(miner: &Miner, now: i64) -> u64 {
+    if now >= miner.vest_end {
+        return 0;
     }
 
-    // Compute the tape number from the challenge, tape 0 
-    // is invalid and reprseents no tape
-    (u64::from_le_bytes(challenge[0..8].try_into().unwrap()) % total_tapes)
-        .max(1)
+    // Nothing unlocks before `vested_at`, and if that's already at or past the end (a schedule
+    // with no epochs, or the clock going back), it all stays locked until the end
+    let now = now.max(miner.vested_at);
+    if now >= miner.vest_end {
+        return miner.locked_rewards;
+    }
+
+    let left = (miner.vest_end - now) as u128;
+    let span = (miner.vest_end - miner.vested_at) as u128;
+
+    (miner.locked_rewards as u128 * left).div_ceil(span) as u64
+}
+
+/// Helper: the part of a miner's unclaimed rewards it can claim at `now`.
+#[inline(always)]
+pub fn claimable_rewards(miner: &Miner, now: i64) -> u64 {
+    miner.unclaimed_rewards.saturating_sub(locked_rewards_at(miner, now))
 }
 
-/// Helper: compute the recall segment number from a given challenge
+/// Helper: bring a miner's locked rewards up to `now`, releasing what has unlocked since.
 #[inline(always)]
-pub fn compute_recall_segment(
-    challenge: &[u8; 32],
-    total_segments: u64,
-) -> u64 {
-    // Prevent division by zero
-    if total_segments == 0 {
+pub fn unlock_rewards(miner: &mut Miner, now: i64) {
+    miner.locked_rewards = locked_rewards_at(miner, now);
+    miner.vested_at = now.max(miner.vested_at);
+}
+
+/// Helper: lock the miner's share of a new `reward` at `now`, returning the amount locked. The
+/// new lock unlocks over `vest_epochs` epochs, rewards already locked keep their pace, and the
+/// end of the schedule moves to the average of the two weighted by amount.
+#[inline(always)]
+pub fn lock_reward(miner: &mut Miner, reward: u64, now: i64) -> u64 {
+    unlock_rewards(miner, now);
+
+    let locked = (reward as u128 * miner.locked_percent.min(100) as u128 / 100) as u64;
+    if locked == 0 || miner.vest_epochs == 0 {
         return 0;
     }
 
-    u64::from_le_bytes(challenge[8..16].try_into().unwrap()) % total_segments
+    let held = miner.locked_rewards as i128;
+    let end = now + miner.vest_epochs as i64 * EPOCH_SECONDS;
+    let vest_end = (held * miner.vest_end.max(now) as i128 + locked as i128 * end as i128)
+        .div_euclid(held + locked as i128);
+
+    miner.vest_end = vest_end as i64;
+    miner.locked_rewards += locked;
+    locked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recall_indices_follow_the_current_challenge() {
+        let mut challenge = [0u8; 32];
+        challenge[0..8].copy_from_slice(&17u64.to_le_bytes());
+        challenge[8..16].copy_from_slice(&1234u64.to_le_bytes());
+
+        let mut miner: Miner = bytemuck::Zeroable::zeroed();
+        miner.current_challenge = challenge;
+        miner.recall_tape = compute_recall_tape_v1(&challenge, 10);
+
+        let mut tape: Tape = bytemuck::Zeroable::zeroed();
+        tape.total_segments = 100;
+
+        assert_eq!(compute_recall_indices(&miner, &tape), (7, 34));
+
+        miner.recall_tape = compute_recall_tape_v2(&challenge, 10);
+        assert_eq!(compute_recall_indices(&miner, &tape), (8, 34));
+
+        // The segment doesn't depend on the previous proof or anything else on the miner
+        miner.last_proof_hash = [9; 32];
+        assert_eq!(compute_recall_indices(&miner, &tape).1, 34);
+
+        // Empty tapes don't divide by zero
+        tape.total_segments = 0;
+        assert_eq!(compute_recall_indices(&miner, &tape).1, 0);
+    }
+
+    #[test]
+    fn test_lock_rewards() {
+        let mut miner: Miner = bytemuck::Zeroable::zeroed();
+
+        // Nothing is locked without a lockup
+        miner.unclaimed_rewards = 100;
+        assert_eq!(lock_reward(&mut miner, 100, 0), 0);
+        assert_eq!(claimable_rewards(&miner, 0), 100);
+
+        // 40% of the next reward unlocks over 4 epochs
+        miner.locked_percent = 40;
+        miner.vest_epochs = 4;
+        miner.unclaimed_rewards += 1_000;
+        assert_eq!(lock_reward(&mut miner, 1_000, 0), 400);
+        assert_eq!(claimable_rewards(&miner, 0), 700);
+        assert_eq!(claimable_rewards(&miner, EPOCH_SECONDS), 800);
+        assert_eq!(claimable_rewards(&miner, 2 * EPOCH_SECONDS), 900);
+        assert_eq!(claimable_rewards(&miner, 4 * EPOCH_SECONDS), 1_100);
+
+        // Unlocking part way doesn't change the pace
+        unlock_rewards(&mut miner, EPOCH_SECONDS);
+        assert_eq!(miner.locked_rewards, 300);
+        assert_eq!(locked_rewards_at(&miner, 2 * EPOCH_SECONDS), 200);
+        assert_eq!(locked_rewards_at(&miner, 0), 300);
+
+        // A new lock moves the end by its weight
+        miner.unclaimed_rewards += 750;
+        assert_eq!(lock_reward(&mut miner, 750, 2 * EPOCH_SECONDS), 300);
+        assert_eq!(miner.locked_rewards, 500);
+        assert_eq!(miner.vest_end, (200 * 4 + 300 * 6) * EPOCH_SECONDS / 500);
+        assert_eq!(locked_rewards_at(&miner, miner.vest_end), 0);
+    }
+
+    #[test]
+    fn test_locked_rewards_edges() {
+        let mut miner: Miner = bytemuck::Zeroable::zeroed();
+
+        // A miner without a lockup has a schedule that starts and ends at registration
+        miner.vested_at = 100;
+        miner.vest_end = 100;
+        miner.unclaimed_rewards = 50;
+        assert_eq!(locked_rewards_at(&miner, 50), 0);
+        assert_eq!(claimable_rewards(&miner, 50), 50);
+
+        // Before `vested_at` nothing has unlocked yet
+        miner.vest_end = 500;
+        miner.locked_rewards = 400;
+        assert_eq!(locked_rewards_at(&miner, 0), 400);
+        assert_eq!(locked_rewards_at(&miner, 300), 200);
+        unlock_rewards(&mut miner, 0);
+        assert_eq!((miner.locked_rewards, miner.vested_at), (400, 100));
+
+        // Unlocked up to past the end, then the clock went back
+        miner.vested_at = 600;
+        assert_eq!(locked_rewards_at(&miner, 300), 400);
+        assert_eq!(locked_rewards_at(&miner, 500), 0);
+    }
+
+    #[test]
+    fn test_write_fits() {
+        let max = MAX_SEGMENTS as u64;
+
+        assert!(write_fits(0, 0, SEGMENT_SIZE));
+        assert!(write_fits(max - 1, 0, SEGMENT_SIZE));
+        assert!(!write_fits(max - 1, 0, SEGMENT_SIZE + 1));
+        assert!(!write_fits(max, 0, 1));
+
+        // Padding counts against the segments, not the size
+        assert!(write_fits(max - 2, MAX_TAPE_SIZE as u64 - 2, 2));
+        assert!(!write_fits(0, MAX_TAPE_SIZE as u64, 1));
+        assert!(!write_fits(u64::MAX, u64::MAX, 1));
+    }
+
+    #[test]
+    fn test_verify_matches_the_program_tree() {
+        let mut tree = MerkleTree::<{TREE_HEIGHT}>::new(&[b"seed".as_ref()]);
+        let segments: Vec<[u8; SEGMENT_SIZE]> = (0..5u8).map(|i| [i; SEGMENT_SIZE]).collect();
+
+        let mut leaves = Vec::new();
+        for (i, segment) in segments.iter().enumerate() {
+            write_segment(&mut tree, i as u64, segment).unwrap();
+            leaves.push(compute_leaf(i as u64, segment));
+        }
+
+        let root = tree.get_root().to_bytes();
+        for (i, segment) in segments.iter().enumerate() {
+            assert_eq!(crate::verify::leaf_hash(i as u64, segment), leaves[i].to_bytes());
+
+            let proof: Vec<[u8; 32]> = tree
+                .get_merkle_proof(&leaves, i)
+                .iter()
+                .map(|h| h.to_bytes())
+                .collect();
+            assert!(crate::verify::verify_segment(&root, i as u64, segment, &proof));
+        }
+    }
+
+    /// Pseudo-random challenges (splitmix64), so the tests don't depend on a RNG crate.
+    fn challenges(count: usize) -> impl Iterator<Item = [u8; 32]> {
+        let mut state = 0x9e37_79b9_7f4a_7c15u64;
+        (0..count).map(move |_| {
+            state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+            let mut z = state;
+            z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+            z ^= z >> 31;
+
+            let mut challenge = [0u8; 32];
+            challenge[0..8].copy_from_slice(&z.to_le_bytes());
+            challenge
+        })
+    }
+
+    /// How often each tape number in `0..=total + 1` is picked.
+    fn histogram(f: fn(&[u8; 32], u64) -> u64, total: u64, samples: usize) -> Vec<usize> {
+        let mut counts = vec![0; total as usize + 2];
+        for challenge in challenges(samples) {
+            counts[f(&challenge, total) as usize] += 1;
+        }
+        counts
+    }
+
+    /// Pearson's chi-squared statistic of `observed` against a uniform distribution.
+    fn chi_squared(observed: &[usize]) -> f64 {
+        let total: usize = observed.iter().sum();
+        let expected = total as f64 / observed.len() as f64;
+        observed
+            .iter()
+            .map(|&o| (o as f64 - expected).powi(2) / expected)
+            .sum()
+    }
+
+    #[test]
+    fn test_recall_tape_v2_is_uniform() {
+        let samples = 100_000;
+
+        for total in [1u64, 2, 3, 7, 10, 64] {
+            let counts = histogram(compute_recall_tape_v2, total, samples);
+
+            // Only 1..=total, and every one of them
+            assert_eq!(counts[0], 0);
+            assert_eq!(counts[total as usize + 1], 0);
+            let in_range = &counts[1..=total as usize];
+            assert!(in_range.iter().all(|&c| c > 0), "total {}: {:?}", total, counts);
+
+            // Well under the 99.9% critical value for 63 degrees of freedom (103.4)
+            let chi = chi_squared(in_range);
+            assert!(chi < 100.0, "total {}: chi-squared {} for {:?}", total, chi, in_range);
+        }
+
+        assert_eq!(compute_recall_tape_v2(&[0; 32], 0), 1);
+    }
+
+    #[test]
+    fn test_recall_tape_v1_is_skewed() {
+        let total = 10;
+        let counts = histogram(compute_recall_tape_v1, total, 100_000);
+
+        // Tape 1 takes the share of tape 0, and the last tape is never picked
+        assert_eq!(counts[0], 0);
+        assert_eq!(counts[total as usize], 0);
+        assert!(counts[1] > counts[2] * 3 / 2);
+    }
 }
@@ -0,0 +1,142 @@
+//! Segment proof and recall checks with nothing but Keccak underneath. Built `no_std` and without
+//! the Solana stack when the crate's `solana` feature is off, so hardware wallets and embedded
+//! verifiers can check what the program checks.
+
+use sha3::{Digest, Keccak256};
+
+/// Helper: Keccak-256 of the concatenated inputs
+#[inline(always)]
+fn hashv(data: &[&[u8]]) -> [u8; 32] {
+    let mut hasher = Keccak256::new();
+    for d in data {
+        hasher.update(d);
+    }
+    hasher.finalize().into()
+}
+
+/// Helper: the Merkle leaf of a segment, the same leaf the program adds to a tape's tree. The
+/// segment must be padded to the full segment size, as it is on-chain.
+#[inline(always)]
+pub fn leaf_hash(segment_id: u64, segment: &[u8]) -> [u8; 32] {
+    hashv(&[b"LEAF", &segment_id.to_le_bytes(), segment])
+}
+
+/// Helper: the parent of two nodes. Pairs are hashed in sorted order, so proofs don't carry
+/// which side each sibling is on.
+#[inline(always)]
+pub fn node_hash(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+    if a <= b {
+        hashv(&[b"NODE", a, b])
+    } else {
+        hashv(&[b"NODE", b, a])
+    }
+}
+
+/// Helper: the root a leaf and its proof lead to
+#[inline(always)]
+pub fn proof_root(leaf: &[u8; 32], proof: &[[u8; 32]]) -> [u8; 32] {
+    proof.iter().fold(*leaf, |node, sibling| node_hash(&node, sibling))
+}
+
+/// Helper: check a segment is part of the tape with Merkle root `root`
+#[inline(always)]
+pub fn verify_segment(
+    root: &[u8; 32],
+    segment_id: u64,
+    segment: &[u8],
+    proof: &[[u8; 32]],
+) -> bool {
+    proof_root(&leaf_hash(segment_id, segment), proof) == *root
+}
+
+/// Helper: compute the recall tape number from a given challenge. Tape numbers start at 1, tape 0
+/// is invalid and represents no tape.
+///
+/// Builds with the `recall-v2` feature use the uniform mapping of [`compute_recall_tape_v2`],
+/// others the original one of [`compute_recall_tape_v1`]. The program and anything checking its
+/// choices must be built the same way, so the switch ships with a program upgrade.
+#[inline(always)]
+pub fn compute_recall_tape(
+    challenge: &[u8; 32],
+    total_tapes: u64,
+) -> u64 {
+    if cfg!(feature = "recall-v2") {
+        compute_recall_tape_v2(challenge, total_tapes)
+    } else {
+        compute_recall_tape_v1(challenge, total_tapes)
+    }
+}
+
+/// Helper: the original recall tape mapping, `challenge % total` clamped to at least 1. Tape 1
+/// is picked for both 0 and 1, twice as often as any other, and the last tape is never picked.
+#[inline(always)]
+pub fn compute_recall_tape_v1(
+    challenge: &[u8; 32],
+    total_tapes: u64,
+) -> u64 {
+    // Prevent division by zero
+    if total_tapes == 0 {
+        return 1;
+    }
+
+    (challenge_word(challenge, 0) % total_tapes).max(1)
+}
+
+/// Helper: a uniform recall tape mapping over `1..=total`.
+#[inline(always)]
+pub fn compute_recall_tape_v2(
+    challenge: &[u8; 32],
+    total_tapes: u64,
+) -> u64 {
+    // Prevent division by zero
+    if total_tapes == 0 {
+        return 1;
+    }
+
+    challenge_word(challenge, 0) % total_tapes + 1
+}
+
+/// Helper: compute the recall segment number from a given challenge
+#[inline(always)]
+pub fn compute_recall_segment(
+    challenge: &[u8; 32],
+    total_segments: u64,
+) -> u64 {
+    // Prevent division by zero
+    if total_segments == 0 {
+        return 0;
+    }
+
+    challenge_word(challenge, 1) % total_segments
+}
+
+/// Helper: the `i`th little-endian u64 of a challenge
+#[inline(always)]
+fn challenge_word(challenge: &[u8; 32], i: usize) -> u64 {
+    let mut word = [0u8; 8];
+    word.copy_from_slice(&challenge[i * 8..i * 8 + 8]);
+    u64::from_le_bytes(word)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verify_segment() {
+        let segments = [[1u8; 16], [2u8; 16], [3u8; 16], [4u8; 16]];
+        let leaves: [[u8; 32]; 4] = core::array::from_fn(|i| leaf_hash(i as u64, &segments[i]));
+
+        let left = node_hash(&leaves[0], &leaves[1]);
+        let right = node_hash(&leaves[2], &leaves[3]);
+        let root = node_hash(&left, &right);
+
+        // Sibling order doesn't matter
+        assert_eq!(node_hash(&left, &right), node_hash(&right, &left));
+
+        assert!(verify_segment(&root, 2, &segments[2], &[leaves[3], left]));
+        assert!(!verify_segment(&root, 3, &segments[2], &[leaves[3], left]));
+        assert!(!verify_segment(&root, 2, &segments[3], &[leaves[3], left]));
+        assert!(!verify_segment(&root, 2, &segments[2], &[leaves[2], left]));
+    }
+}
@@ -1,6 +1,10 @@
 use clap::{Parser, Subcommand};
+use clap_complete::Shell;
 use std::str::FromStr;
 use std::path::PathBuf;
+use std::time::Duration;
+use tape_client::{consts::MAX_IN_FLIGHT, DurableNonce};
+use tape_network::mine::DEFAULT_MAX_DRIFT;
 
 #[derive(Parser)]
 #[command(
@@ -12,9 +16,24 @@ pub struct Cli {
     #[command(subcommand)]
     pub command: Commands,
 
-    #[arg(short = 'k', long = "keypair", global = true)]
+    #[arg(
+        short = 'k',
+        long = "keypair",
+        global = true,
+        help = "Keypair file, usb://ledger, or the URL of a remote signer"
+    )]
     pub keypair_path: Option<PathBuf>,
 
+    #[arg(long = "mnemonic", help = "Derive the keypair from a BIP39 seed phrase (prompted)", global = true)]
+    pub mnemonic: bool,
+
+    #[arg(
+        long = "derivation-path",
+        help = "Derivation path for --mnemonic or a Ledger, e.g. m/44'/501'/0'/0'",
+        global = true
+    )]
+    pub derivation_path: Option<String>,
+
     #[arg(
         short = 'u', 
         long = "cluster", 
@@ -24,8 +43,47 @@ pub struct Cli {
     )]
     pub cluster: Cluster,
 
+    #[arg(
+        long = "program-id",
+        global = true,
+        help = "Tape program to use instead of the default, e.g. a fork or local deployment\n (or set TAPE_PROGRAM_ID)"
+    )]
+    pub program_id: Option<String>,
+
     #[arg(short = 'v', long = "verbose", help = "Print verbose output", global = true)]
     pub verbose: bool,
+
+    #[arg(
+        short = 'y',
+        long = "yes",
+        help = "Answer yes to every prompt, for CI and scripts (also assumed when stdin isn't a terminal)",
+        global = true
+    )]
+    pub yes: bool,
+
+    #[arg(
+        short = 'q',
+        long = "quiet",
+        help = "Print only errors and the result, e.g. a written tape's address",
+        global = true,
+        conflicts_with = "verbose"
+    )]
+    pub quiet: bool,
+
+    #[arg(
+        long = "skip-simulation",
+        help = "Send transactions without simulating them first",
+        global = true
+    )]
+    pub skip_simulation: bool,
+
+    #[arg(
+        long = "log-file",
+        value_name = "PATH",
+        help = "Append everything the command prints to this file instead, reopened by the adminRotateLogs\n method so it can be rotated",
+        global = true
+    )]
+    pub log_file: Option<PathBuf>,
 }
 
 #[derive(Subcommand)]
@@ -35,28 +93,197 @@ pub enum Commands {
 
     Write {
         #[arg(
-            help = "File to write, message text, or remote URL",
-            required_unless_present_any = ["filename", "message", "remote"],
-            conflicts_with_all = ["message", "remote"]
+            help = "File to write, message text, or remote URL. A quoted glob pattern (e.g. 'photos/**/*.jpg') writes each matching file as its own tape",
+            required_unless_present_any = ["filename", "message", "remote", "stdin"],
+            conflicts_with_all = ["message", "remote", "stdin"]
         )]
         filename: Option<String>,
 
-        #[arg(short = 'm', long = "message", conflicts_with_all = ["filename", "remote"])]
+        #[arg(short = 'm', long = "message", conflicts_with_all = ["filename", "remote", "stdin"])]
         message: Option<String>,
 
-        #[arg(short = 'r', long = "remote", conflicts_with_all = ["filename", "message"])]
+        #[arg(short = 'r', long = "remote", conflicts_with_all = ["filename", "message", "stdin"])]
         remote: Option<String>,
 
-        #[arg(short = 'n', long = "tape-name", help = "Custom name for the tape (defaults to timestamp)")]
+        #[arg(long = "stdin", help = "Write binary data piped to stdin", conflicts_with_all = ["filename", "message", "remote"])]
+        stdin: bool,
+
+        #[arg(long = "mime", help = "MIME type of the data, instead of guessing it (e.g. image/png)")]
+        mime: Option<String>,
+
+        #[arg(long = "flags", value_delimiter = ',', help = "Header flags instead of the default (none, linked, wide), comma separated")]
+        flags: Option<Vec<String>>,
+
+        #[arg(long = "meta", help = "User metadata key=value to store in the tape header (can be repeated, 10 bytes total)")]
+        meta: Vec<String>,
+
+        #[arg(short = 'n', long = "tape-name", help = "Custom name for the tape (defaults to timestamp). With a glob pattern, a template like {stem}-{date}, see write-batch --name-template")]
         tape_name: Option<String>,
+
+        #[arg(long = "lookup-table", help = "Write larger chunks through an address lookup table (v0 transactions)")]
+        lookup_table: bool,
+
+        #[arg(long = "max-in-flight", default_value_t = MAX_IN_FLIGHT, help = "Upper bound on unconfirmed writes per tape, the actual number adapts to the cluster")]
+        max_in_flight: usize,
+
+        #[arg(long = "ordered", num_args = 0..=1, default_missing_value = "1", value_name = "WINDOW", conflicts_with = "max_in_flight", help = "Keep segments in the order they're written, for on-chain readers: at most WINDOW writes in flight (default 1, the exact order), sliding only as the oldest confirms")]
+        ordered: Option<usize>,
+
+        #[arg(long = "cache", help = "Directory to record every chunk sent and its signature in, for `tapedrive audit`")]
+        cache: Option<PathBuf>,
+
+        #[arg(long = "offline", requires = "out", conflicts_with_all = ["lookup_table", "cache"], help = "Build the transactions without sending anything, for `tapedrive submit`")]
+        offline: bool,
+
+        #[arg(long = "out", requires = "offline", help = "File to save the offline bundle to")]
+        out: Option<PathBuf>,
+
+        #[arg(long = "nonce", requires = "offline", help = "Durable nonce <account>:<blockhash> to sign one transaction of the bundle with (repeat once per transaction)")]
+        nonce: Vec<DurableNonce>,
+
+        #[arg(long = "recipient", value_name = "KEY", help = "Seal the data to an X25519 public key or a key in the keyring (see `tapedrive seal-key` and `tapedrive keys`), so only its holder can read it")]
+        recipient: Option<String>,
+
+        #[arg(long = "dedup", value_name = "INDEX", conflicts_with_all = ["offline", "recipient"], help = "Only write chunks the index file hasn't seen, plus a recipe tape to rebuild the data from (for repeated backups)")]
+        dedup: Option<PathBuf>,
+
+        #[arg(long = "seekable", conflicts_with = "dedup", help = "Compress in independent frames, so readers can fetch byte ranges without the whole tape")]
+        seekable: bool,
+
+        #[arg(long = "manifest", requires = "filename", help = "With a glob pattern, JSON file to save the path, name, MIME type and address of every written tape to")]
+        manifest: Option<PathBuf>,
+    },
+
+    Submit {
+        #[arg(help = "Bundle made by `tapedrive write --offline`")]
+        bundle: PathBuf,
+
+        #[arg(long = "from", default_value_t = 0, help = "Index of the first transaction to send, to resume a failed submit")]
+        from: usize,
+    },
+
+    WriteBatch {
+        #[arg(help = "CSV job file (path,name[,mime] per line), a directory of files, or a quoted glob pattern (e.g. 'photos/**/*.jpg')")]
+        input: PathBuf,
+
+        #[arg(short = 'c', long = "concurrency", default_value_t = 4, help = "Number of tapes to write at once")]
+        concurrency: usize,
+
+        #[arg(long = "state", help = "Job state file used to resume the batch (defaults to <input>.tapedrive.json)")]
+        state: Option<PathBuf>,
+
+        #[arg(long = "name-template", help = "Name tapes after a template of {stem}, {ext}, {name}, {path}, {date} (the file's last modified day) and {index}, e.g. {stem}-{date}")]
+        name_template: Option<String>,
+
+        #[arg(long = "manifest", help = "JSON file to save the path, name, MIME type and address of every written tape to")]
+        manifest: Option<PathBuf>,
+
+        #[arg(long = "lookup-table", help = "Write larger chunks through an address lookup table (v0 transactions)")]
+        lookup_table: bool,
+
+        #[arg(long = "max-in-flight", default_value_t = MAX_IN_FLIGHT, help = "Upper bound on unconfirmed writes per tape, the actual number adapts to the cluster")]
+        max_in_flight: usize,
+
+        #[arg(long = "ordered", num_args = 0..=1, default_missing_value = "1", value_name = "WINDOW", conflicts_with = "max_in_flight", help = "Keep segments in the order they're written, for on-chain readers: at most WINDOW writes in flight (default 1, the exact order), sliding only as the oldest confirms")]
+        ordered: Option<usize>,
+    },
+
+    SweepWriters {},
+
+    Backup {
+        #[arg(long = "watch", help = "Directory to back up")]
+        watch: PathBuf,
+
+        #[arg(long = "interval", default_value = "1h", help = "Time between backups, e.g. 30m, 1h or 1d")]
+        interval: Interval,
+
+        #[arg(long = "once", help = "Back up once and exit, instead of on a schedule")]
+        once: bool,
+
+        #[arg(long = "catalog", help = "Directory to keep the list of backups and the chunk index in (defaults to <DIR>.tapedrive-backup)")]
+        catalog: Option<PathBuf>,
+
+        #[arg(long = "max-in-flight", default_value_t = MAX_IN_FLIGHT, help = "Upper bound on unconfirmed writes per tape, the actual number adapts to the cluster")]
+        max_in_flight: usize,
+    },
+
+    Restore {
+        #[arg(help = "Directory that was backed up", required_unless_present = "catalog")]
+        dir: Option<PathBuf>,
+
+        #[arg(long = "catalog", help = "Catalog of the backups, if not next to the directory")]
+        catalog: Option<PathBuf>,
+
+        #[arg(long = "generation", help = "Backup to restore (defaults to the latest)")]
+        generation: Option<u64>,
+
+        #[arg(long = "to", required_unless_present = "list", help = "Empty directory to restore into")]
+        to: Option<PathBuf>,
+
+        #[arg(long = "list", help = "List the backups instead of restoring one")]
+        list: bool,
     },
 
     Read {
-        #[arg(help = "Tape account to read")]
-        tape: String,
+        #[arg(help = "Tape account to read", required_unless_present = "number", conflicts_with = "number")]
+        tape: Option<String>,
+
+        #[arg(long = "number", help = "Tape number to read, instead of an address")]
+        number: Option<u64>,
+
+        #[arg(long = "local", help = "Read from the local archive store instead of the chain")]
+        local: bool,
+
+        #[arg(long = "force", help = "Recover what's present even if segments are missing", requires = "local")]
+        force: bool,
+
+        #[arg(long = "peer", value_name = "URL", help = "Read from a trusted peer's web RPC, after the local store with --local")]
+        peer: Option<String>,
+
+        #[arg(long = "peer-first", requires = "peer", help = "Try the peer before the local store")]
+        peer_first: bool,
+
+        #[arg(long = "source-timeout", value_name = "SECS", default_value_t = 30, help = "Seconds to give the local store and the peer each before moving on")]
+        source_timeout: u64,
+
+        #[arg(long = "no-rpc-fallback", help = "Fail instead of reading from the chain when the local store and the peer can't serve the tape")]
+        no_rpc_fallback: bool,
 
         #[arg(short = 'o', long = "output", help = "Output file")]
         output: Option<String>,
+
+        #[arg(long = "key", value_name = "NAME", help = "Key in the keyring to open a sealed tape with, instead of the keypair's")]
+        key: Option<String>,
+    },
+
+    History {
+        #[arg(help = "Tape account to replay")]
+        tape: String,
+
+        #[arg(long = "json", help = "Print the history as JSON")]
+        json: bool,
+    },
+
+    Audit {
+        #[arg(help = "Tape account to audit")]
+        tape: String,
+
+        #[arg(long = "cache", help = "Directory the tape's chunks were recorded in by `tapedrive write --cache`")]
+        cache: PathBuf,
+    },
+
+    Diff {
+        #[arg(help = "Tape account to compare")]
+        tape: String,
+
+        #[arg(help = "Slot to compare from")]
+        slot_a: u64,
+
+        #[arg(help = "Slot to compare to")]
+        slot_b: u64,
+
+        #[arg(long = "json", help = "Print the changed segments as JSON")]
+        json: bool,
     },
 
     // Miner Commands
@@ -65,8 +292,32 @@ pub enum Commands {
         #[arg(help = "Miner account public key")]
         miner: String,
 
-        #[arg(help = "Amount of tokens to claim")]
-        amount: u64,
+        #[arg(help = "Amount of TAPE to claim, e.g. 1.5")]
+        amount: String,
+
+        #[arg(long = "raw", help = "The amount is in base units (10^10 per TAPE) instead of TAPE")]
+        raw: bool,
+
+        #[arg(
+            short = 'b',
+            long = "beneficiary",
+            help = "Wallet or TAPE token account to send rewards to, overrides set-beneficiary"
+        )]
+        beneficiary: Option<String>,
+    },
+
+    SetBeneficiary {
+        #[arg(help = "Miner account public key")]
+        miner: String,
+
+        #[arg(
+            help = "Wallet or TAPE token account to send the miner's rewards to",
+            required_unless_present = "clear"
+        )]
+        beneficiary: Option<String>,
+
+        #[arg(long = "clear", help = "Send rewards to the signer's own account again", conflicts_with = "beneficiary")]
+        clear: bool,
     },
 
     // Network Commands
@@ -77,6 +328,30 @@ pub enum Commands {
 
         #[arg(help = "Trusted peer to connect to")]
         trusted_peer: Option<String>,
+
+        #[arg(long = "thumbnails", help = "Generate thumbnails for image tapes as they are archived")]
+        thumbnails: bool,
+
+        #[arg(long = "max-disk-bytes", help = "Evict least recently accessed tapes once the store holds more than this many bytes")]
+        max_disk_bytes: Option<u64>,
+
+        #[arg(long = "pin", help = "Tape address to never evict (can be repeated)", requires = "max_disk_bytes")]
+        pin: Vec<String>,
+
+        #[arg(long = "leaderboard", help = "Index mine events for the getLeaderboard RPC method")]
+        leaderboard: bool,
+
+        #[arg(long = "prefilter", help = "Fetch each block's account lists first, and the full block only if it has tape transactions")]
+        prefilter: bool,
+
+        #[arg(long = "compaction-rate", help = "Limit store flushes and compactions to this many bytes per second")]
+        compaction_rate: Option<u64>,
+
+        #[arg(long = "idle-compaction", help = "Compact the store only while the local miner is idle between proofs")]
+        idle_compaction: bool,
+
+        #[arg(long = "alerts", help = "JSON file of where to send alerts (webhook, email) and when")]
+        alerts: Option<PathBuf>,
     },
     Mine {
         #[arg(help = "Miner account public key", conflicts_with = "name")]
@@ -84,24 +359,82 @@ pub enum Commands {
 
         #[arg(help = "Name of the miner you're mining with", conflicts_with = "pubkey", short = 'n', long = "name")]
         name: Option<String>,
+
+        #[arg(long = "web-port", help = "Also serve the web RPC service on this port, sharing the miner's store")]
+        web_port: Option<u16>,
+
+        #[arg(long = "max-drift", default_value_t = DEFAULT_MAX_DRIFT, help = "Skip mining while the local store is more than this many slots behind the cluster")]
+        max_drift: u64,
+
+        #[arg(long = "pool", requires = "worker", conflicts_with_all = ["pubkey", "name", "web_port"], help = "Mine as a worker of the pool coordinator at this URL, instead of with your own miner")]
+        pool: Option<String>,
+
+        #[arg(long = "worker", requires = "pool", help = "Name the pool credits your shares to")]
+        worker: Option<String>,
+
+        #[arg(long = "peer", value_name = "URL", conflicts_with = "pool", help = "Prove from a trusted peer's web RPC while the local store resyncs a recall tape")]
+        peer: Option<String>,
+
+        #[arg(long = "alerts", conflicts_with = "pool", help = "JSON file of where to send alerts (webhook, email) and when")]
+        alerts: Option<PathBuf>,
     },
     Register {
         #[arg(help = "The name of the miner you're registering")]
         name: String,
+
+        #[arg(long = "locked-percent", default_value_t = 0, requires = "vest_epochs", help = "Percent of each reward to lock when it's mined")]
+        locked_percent: u64,
+
+        #[arg(long = "vest-epochs", default_value_t = 0, requires = "locked_percent", help = "Epochs locked rewards take to unlock, evenly")]
+        vest_epochs: u64,
     },
     Web {
         #[arg(help = "Port to run the web RPC service on")]
         port: Option<u16>,
     },
+    Snapshot {
+        #[command(subcommand)]
+        command: SnapshotCommands,
+    },
 
     // Admin Commands
 
     #[command(hide = true)]
     Initialize {},
 
+    #[command(hide = true)]
+    InitializeConfig {},
+
     #[command(hide = true)]
     Epoch {},
 
+    #[command(hide = true)]
+    Pause {},
+
+    #[command(hide = true)]
+    Resume {},
+
+    #[command(hide = true)]
+    SetGuardian {
+        #[arg(help = "Public key of the new guardian")]
+        guardian: String,
+    },
+
+    #[command(hide = true)]
+    SetMinerDeposit {
+        #[arg(help = "Amount of TAPE new miners lock at registration, e.g. 10, or 0 for none")]
+        amount: String,
+
+        #[arg(long = "raw", help = "The amount is in base units (10^10 per TAPE) instead of TAPE")]
+        raw: bool,
+    },
+
+    #[command(hide = true)]
+    Migrate {
+        #[arg(help = "Program accounts to migrate, every account that needs it if none are given")]
+        accounts: Vec<String>,
+    },
+
     // Misc Commands
 
     GetTape {
@@ -112,17 +445,220 @@ pub enum Commands {
         #[arg(help = "Tape number to find")]
         number: u64,
     },
+    Info {
+        #[command(subcommand)]
+        command: InfoCommands,
+    },
     GetMiner {
         #[arg(help = "Miner account public key")]
         pubkey: String,
     },
+    GetRewards {
+        #[arg(help = "Miner account public key")]
+        pubkey: String,
+
+        #[arg(long = "late", help = "Seconds after the target time the proof lands", default_value_t = 0)]
+        late: i64,
+    },
     GetSpool {
         #[arg(help = "Spool account public key")]
         number: u8,
     },
     GetArchive { },
-    GetEpoch {},
+    GetEpoch {
+        #[arg(long = "history", help = "List past epochs recorded by the local archive")]
+        history: bool,
+
+        #[arg(long = "from", help = "First epoch to list", requires = "history")]
+        from: Option<u64>,
+
+        #[arg(long = "to", help = "Last epoch to list", requires = "history")]
+        to: Option<u64>,
+    },
+    GetConfig {},
+    SealKey {},
+
+    // Key Commands
+
+    Keys {
+        #[command(subcommand)]
+        command: KeyCommands,
+    },
+
+    // Setup Commands
+
+    Completions {
+        #[arg(help = "Shell to generate completions for")]
+        shell: Shell,
+    },
+    Doctor {},
+    Airdrop {
+        #[arg(default_value_t = 1.0, help = "SOL to request from the cluster's faucet (devnet, testnet or localnet)")]
+        sol: f64,
+    },
+    Dev {
+        #[command(subcommand)]
+        command: DevCommands,
+    },
+
+}
+
+#[derive(Subcommand)]
+pub enum KeyCommands {
+    Generate {
+        #[arg(help = "Name to store the key under")]
+        name: String,
+    },
+
+    List {},
+
+    Export {
+        #[arg(help = "Name of the key")]
+        name: String,
+
+        #[arg(long = "secret", help = "Print the secret key instead of the public one, to import it elsewhere")]
+        secret: bool,
+    },
+
+    Import {
+        #[arg(help = "Name to store the key under")]
+        name: String,
+
+        #[arg(long = "public", value_name = "KEY", help = "Store someone else's public key to write to, instead of prompting for a secret key")]
+        public: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum SnapshotCommands {
+    Gc {
+        #[arg(long = "max-disk-bytes", help = "Maximum bytes of segment data to keep in the local store")]
+        max_disk_bytes: u64,
+
+        #[arg(long = "pin", help = "Tape address to never evict (can be repeated)")]
+        pin: Vec<String>,
+
+        #[arg(long = "dry-run", help = "Only report which tapes would be evicted")]
+        dry_run: bool,
+    },
+
+    ExportFs {
+        #[arg(help = "Directory to export decoded tapes into")]
+        dir: PathBuf,
+
+        #[arg(long = "incremental", help = "Only export tapes missing from the directory's index.json")]
+        incremental: bool,
+    },
+
+    Stats {
+        #[arg(long = "detailed", help = "Also list the tapes this node's operator labelled")]
+        detailed: bool,
+    },
+
+    Gaps {
+        #[arg(long = "repair", help = "Retry the gap slots now (stop the archive first, it repairs them on its own)")]
+        repair: bool,
+
+        #[arg(long = "limit", default_value_t = 100, help = "Number of gaps to list or repair, oldest first")]
+        limit: usize,
+    },
+
+    Verify {
+        #[arg(help = "Snapshot directory, as written by the adminSnapshot RPC method")]
+        path: PathBuf,
+
+        #[arg(long = "sample", default_value_t = 10, help = "Number of tapes to check the Merkle root of, spread across the snapshot")]
+        sample: usize,
+
+        #[arg(long = "all", help = "Check the Merkle root of every tape", conflicts_with = "sample")]
+        all: bool,
+    },
+
+    Compare {
+        #[arg(long = "peer", help = "Web RPC endpoint of the node to compare with, e.g. http://host:3000/api")]
+        peer: String,
+    },
+
+    Scrub {
+        #[arg(long = "peer", help = "Web RPC endpoint of a trusted node to re-fetch corrupt segments from before the chain")]
+        peer: Option<String>,
+
+        #[arg(long = "dry-run", help = "Only report corrupt segments, works while the archive is running")]
+        dry_run: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum DevCommands {
+    Seed {
+        #[arg(long = "tapes", default_value_t = 50, help = "Number of tapes to create and finalize")]
+        tapes: u64,
+
+        #[arg(long = "segments", default_value_t = 100, value_parser = clap::value_parser!(u64).range(1..), help = "Most segments a tape gets, each gets a random number up to this")]
+        segments: u64,
 
+        #[arg(long = "miners", default_value_t = 3, help = "Number of miners to register")]
+        miners: u64,
+
+        #[arg(long = "seed", default_value_t = 0, help = "Seed for the tape data and sizes, the same seed makes the same tapes")]
+        seed: u64,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum InfoCommands {
+    Miners {
+        #[arg(long = "limit", default_value_t = 50, help = "Number of miners to list, most rewarded first")]
+        limit: usize,
+    },
+
+    Tapes {
+        #[arg(long = "limit", default_value_t = 20, help = "Number of tapes to list, newest first")]
+        limit: usize,
+
+        #[arg(long = "offset", default_value_t = 0, help = "Number of tapes to skip")]
+        offset: usize,
+
+        #[arg(long = "name-prefix", help = "Only list tapes whose name starts with this")]
+        name_prefix: Option<String>,
+
+        #[arg(long = "recent", conflicts_with_all = ["offset", "name_prefix"], help = "List the tapes the local archive saw most recently, including unfinalized ones")]
+        recent: bool,
+    },
+
+    Balance {
+        #[arg(long = "owner", help = "Wallet to show the TAPE balance of, instead of the keypair's")]
+        owner: Option<String>,
+    },
+
+    Treasury {},
+
+    Sync {},
+}
+
+/// A duration written as a number and a unit, e.g. `90s`, `30m`, `1h` or `1d`.
+#[derive(Debug, Clone, Copy)]
+pub struct Interval(pub Duration);
+
+impl FromStr for Interval {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid interval: '{}'. Use a number and s, m, h or d, e.g. 1h", s);
+
+        let (number, unit) = s.split_at(s.len().saturating_sub(1));
+        let number: u64 = number.parse().map_err(|_| invalid())?;
+        let scale = match unit {
+            "s" => 1,
+            "m" => 60,
+            "h" => 60 * 60,
+            "d" => 60 * 60 * 24,
+            _ => return Err(invalid()),
+        };
+
+        let seconds = number.checked_mul(scale).filter(|&s| s > 0).ok_or_else(invalid)?;
+        Ok(Interval(Duration::from_secs(seconds)))
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -144,6 +680,18 @@ impl Cluster {
             Cluster::Custom(url) => url.clone(),
         }
     }
+
+    /// Whether this is a validator on this machine, e.g. `solana-test-validator`.
+    pub fn is_local(&self) -> bool {
+        match self {
+            Cluster::Localnet => true,
+            Cluster::Custom(url) => {
+                let host = url.split("://").nth(1).unwrap_or_default();
+                host.starts_with("127.0.0.1") || host.starts_with("localhost")
+            }
+            _ => false,
+        }
+    }
 }
 
 impl FromStr for Cluster {
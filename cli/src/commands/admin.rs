@@ -1,39 +1,106 @@
 use anyhow::Result;
-use dialoguer::{theme::ColorfulTheme, Confirm};
+use std::str::FromStr;
 use solana_client::nonblocking::rpc_client::RpcClient;
-use solana_sdk::signature::Keypair;
+use solana_sdk::{pubkey::Pubkey, signer::Signer};
 
 use crate::cli::{Cli, Commands};
 use crate::log;
 
-pub async fn handle_admin_commands(cli: Cli, client: RpcClient, payer: Keypair) -> Result<()> {
+pub async fn handle_admin_commands(cli: Cli, client: RpcClient, payer: Box<dyn Signer>) -> Result<()> {
 
     log::print_divider();
-    let proceed = Confirm::with_theme(&ColorfulTheme::default())
-        .with_prompt("→ Are you sure?")
-        .default(false)
-        .interact()
-        .map_err(|e| anyhow::anyhow!("Failed to get user input: {}", e))?;
-    if !proceed {
+    if !log::confirm("→ Are you sure?", cli.yes)? {
         log::print_error("Write operation cancelled");
         return Ok(());
     }
 
     match cli.command {
         Commands::Initialize {} => {
-            let signature = tape_client::initialize(&client, &payer).await?;
+            let signature = tape_client::initialize(&client, payer.as_ref()).await?;
             log::print_section_header("Program Initialized");
             log::print_message(&format!("Signature: {}", signature));
             log::print_divider();
         }
 
+        Commands::InitializeConfig {} => {
+            let signature = tape_client::initialize_config(&client, payer.as_ref()).await?;
+            log::print_section_header("Config Initialized");
+            log::print_message(&format!("Guardian: {}", payer.pubkey()));
+            log::print_message(&format!("Signature: {}", signature));
+            log::print_divider();
+        }
+
         Commands::Epoch {} => {
-            let signature = tape_client::advance(&client, &payer).await?;
+            let signature = tape_client::advance(&client, payer.as_ref()).await?;
             log::print_section_header("Epoch Advanced");
             log::print_message(&format!("Signature: {}", signature));
             log::print_divider();
         }
 
+        Commands::Pause {} => {
+            let signature = tape_client::pause(&client, payer.as_ref()).await?;
+            log::print_section_header("Program Paused");
+            log::print_message(&format!("Signature: {}", signature));
+            log::print_divider();
+        }
+
+        Commands::Resume {} => {
+            let signature = tape_client::resume(&client, payer.as_ref()).await?;
+            log::print_section_header("Program Resumed");
+            log::print_message(&format!("Signature: {}", signature));
+            log::print_divider();
+        }
+
+        Commands::SetGuardian { guardian } => {
+            let guardian = Pubkey::from_str(&guardian)
+                .map_err(|e| anyhow::anyhow!("Invalid guardian pubkey '{}': {}", guardian, e))?;
+            let signature = tape_client::set_guardian(&client, payer.as_ref(), guardian).await?;
+            log::print_section_header("Guardian Updated");
+            log::print_message(&format!("Guardian: {}", guardian));
+            log::print_message(&format!("Signature: {}", signature));
+            log::print_divider();
+        }
+
+        Commands::SetMinerDeposit { amount, raw } => {
+            let amount = if raw {
+                amount
+                    .trim()
+                    .parse::<u64>()
+                    .map_err(|e| anyhow::anyhow!("Invalid raw amount '{}': {}", amount, e))?
+            } else {
+                tape_client::parse_tape_amount(&amount)?
+            };
+            let signature = tape_client::set_miner_deposit(&client, payer.as_ref(), amount).await?;
+            log::print_section_header("Miner Deposit Updated");
+            log::print_message(&format!("Deposit: {} TAPE", tape_client::format_tape_amount(amount)));
+            log::print_message(&format!("Signature: {}", signature));
+            log::print_divider();
+        }
+
+        Commands::Migrate { accounts } => {
+            let accounts = if accounts.is_empty() {
+                tape_client::get_legacy_accounts(&client).await?
+            } else {
+                accounts
+                    .iter()
+                    .map(|a| {
+                        Pubkey::from_str(a)
+                            .map_err(|e| anyhow::anyhow!("Invalid account pubkey '{}': {}", a, e))
+                    })
+                    .collect::<Result<Vec<_>>>()?
+            };
+
+            log::print_section_header("Accounts Migrated");
+            if accounts.is_empty() {
+                log::print_message("Every account is up to date");
+            }
+            for account in accounts {
+                let signature = tape_client::migrate_account(&client, payer.as_ref(), account).await?;
+                log::print_message(&format!("{}: {}", account, signature));
+            }
+            log::print_divider();
+        }
+
         _ => {}
     }
     Ok(())
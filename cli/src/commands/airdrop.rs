@@ -0,0 +1,87 @@
+use anyhow::Result;
+use std::io::IsTerminal;
+use dialoguer::{theme::ColorfulTheme, Confirm};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    native_token::{lamports_to_sol, sol_to_lamports, LAMPORTS_PER_SOL},
+    signer::Signer,
+};
+
+use tape_client::{is_mainnet, request_airdrop};
+
+use crate::cli::{Cli, Commands};
+use crate::log;
+
+/// Balance below which write and mine offer an airdrop first, enough for a handful of
+/// transactions and a writer's rent.
+pub(crate) const LOW_BALANCE: u64 = LAMPORTS_PER_SOL / 100;
+
+/// What the low-balance prompt asks the faucet for, within what devnet hands out per request.
+const TOP_UP_SOL: f64 = 1.0;
+
+pub async fn handle_airdrop_command(cli: Cli, client: RpcClient, payer: Box<dyn Signer>) -> Result<()> {
+    if let Commands::Airdrop { sol } = cli.command {
+        if !sol.is_finite() || sol <= 0.0 {
+            anyhow::bail!("Airdrop amount must be more than 0 SOL");
+        }
+
+        log::print_divider();
+        log::print_info(&format!("Requesting {} SOL for {}...", sol, payer.pubkey()));
+
+        airdrop(&client, payer.as_ref(), sol).await?;
+    }
+
+    Ok(())
+}
+
+/// Offer an airdrop before a command that pays fees, if the payer is nearly out of SOL on a
+/// cluster with a faucet. Only asks on a terminal, and never stops the command: if the balance
+/// can't be read or the airdrop fails, the command goes on and reports its own errors.
+pub async fn offer_airdrop(client: &RpcClient, payer: &dyn Signer) {
+    if !std::io::stdin().is_terminal() {
+        return;
+    }
+
+    let Ok(balance) = client.get_balance(&payer.pubkey()).await else {
+        return;
+    };
+    if balance >= LOW_BALANCE || is_mainnet(client).await.unwrap_or(true) {
+        return;
+    }
+
+    let proceed = Confirm::with_theme(&ColorfulTheme::default())
+        .with_prompt(format!(
+            "→ {} holds {} SOL, request an airdrop of {} SOL?",
+            payer.pubkey(),
+            lamports_to_sol(balance),
+            TOP_UP_SOL
+        ))
+        .default(true)
+        .interact()
+        .unwrap_or(false);
+    if !proceed {
+        return;
+    }
+
+    if let Err(e) = airdrop(client, payer, TOP_UP_SOL).await {
+        log::print_error(&format!("{}", e));
+    }
+}
+
+async fn airdrop(client: &RpcClient, payer: &dyn Signer, sol: f64) -> Result<()> {
+    let signature = request_airdrop(client, &payer.pubkey(), sol_to_lamports(sol)).await?;
+
+    // The client reads finalized state, the airdrop has only just confirmed
+    let balance = client
+        .get_balance_with_commitment(&payer.pubkey(), CommitmentConfig::confirmed())
+        .await?
+        .value;
+
+    log::print_section_header("Airdrop Confirmed");
+    log::print_message(&format!("Signature: {}", signature));
+    log::print_message(&format!("Balance: {} SOL", lamports_to_sol(balance)));
+    log::print_divider();
+
+    Ok(())
+}
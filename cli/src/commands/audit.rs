@@ -0,0 +1,43 @@
+use anyhow::{anyhow, Result};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+
+use crate::cli::{Cli, Commands};
+use crate::log;
+use tape_client::{verify_upload, DirUploadCache, UploadCache};
+
+pub async fn handle_audit_command(cli: Cli, client: RpcClient) -> Result<()> {
+    let Commands::Audit { tape, cache } = cli.command else {
+        return Ok(());
+    };
+
+    let tape_address = Pubkey::from_str(&tape)
+        .map_err(|_| anyhow!("Invalid tape address: {}", tape))?;
+
+    let cache = DirUploadCache::new(&cache)?;
+    let chunks = cache.get_chunks(&tape_address)?;
+    let bytes: usize = chunks.iter().map(|chunk| chunk.data.len()).sum();
+
+    log::print_section_header("Upload Audit");
+    log::print_message(&format!("Tape: {}", tape_address));
+    log::print_count(&format!("Cached Chunks: {}", chunks.len()));
+    log::print_count(&format!("Cached Bytes: {}", bytes));
+    log::print_divider();
+
+    log::print_message("Checking every chunk against its confirmed write...");
+    let mismatched = verify_upload(&client, &cache, &tape_address).await?;
+
+    log::print_divider();
+    if mismatched.is_empty() {
+        log::print_info(&format!("All {} chunks match what was confirmed on-chain", chunks.len()));
+    } else {
+        for index in &mismatched {
+            log::print_error(&format!("Chunk {} doesn't match its transaction", index));
+        }
+        log::print_error(&format!("{} of {} chunks don't match", mismatched.len(), chunks.len()));
+    }
+    log::print_divider();
+
+    Ok(())
+}
@@ -0,0 +1,233 @@
+use anyhow::{anyhow, bail, Result};
+use chrono::{TimeZone, Utc};
+use mime::Mime;
+use serde::{Deserialize, Serialize};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{hash::hash, pubkey::Pubkey, signer::Signer};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::cli::{Cli, Commands};
+use crate::commands::write::{new_header, write_deduped, InFlight};
+use crate::keypair::{get_keypair_path, signing_pool};
+use crate::log;
+use tape_client::{
+    ensure_not_paused, fetch_tape_with, pack_files, read_dir_files, unpack_files, write_dir_files,
+    SigningPool, DIR_MIME,
+};
+
+/// File in a catalog directory listing its backups.
+const CATALOG_FILE: &str = "catalog.json";
+
+/// File in a catalog directory holding the chunk index backups are deduplicated against.
+const INDEX_FILE: &str = "index.json";
+
+/// One backup of a directory.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Generation {
+    pub number: u64,
+    pub created_at: i64,
+
+    /// The recipe tape to restore from.
+    pub tape_address: String,
+    pub files: usize,
+    pub bytes: u64,
+
+    /// Bytes of chunks this backup had to write, the rest were stored before.
+    pub new_bytes: u64,
+
+    /// SHA-256 of the packed directory, to skip backups with nothing new.
+    pub checksum: String,
+}
+
+/// Every backup taken of a directory, oldest first.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Catalog {
+    pub generations: Vec<Generation>,
+}
+
+impl Catalog {
+    fn load(dir: &Path) -> Result<Self> {
+        let path = dir.join(CATALOG_FILE);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let json = fs::read_to_string(&path)
+            .map_err(|e| anyhow!("Failed to read {}: {}", path.display(), e))?;
+        serde_json::from_str(&json)
+            .map_err(|e| anyhow!("Failed to parse {}: {}", path.display(), e))
+    }
+
+    fn save(&self, dir: &Path) -> Result<()> {
+        fs::create_dir_all(dir)
+            .map_err(|e| anyhow!("Failed to create {}: {}", dir.display(), e))?;
+
+        let path = dir.join(CATALOG_FILE);
+        let json = serde_json::to_string_pretty(self)?;
+        fs::write(&path, json)
+            .map_err(|e| anyhow!("Failed to write {}: {}", path.display(), e))
+    }
+
+    fn get(&self, number: Option<u64>) -> Option<&Generation> {
+        match number {
+            Some(number) => self.generations.iter().find(|g| g.number == number),
+            None => self.generations.last(),
+        }
+    }
+}
+
+pub async fn handle_backup_command(cli: Cli, client: RpcClient, payer: Box<dyn Signer>) -> Result<()> {
+    let pool = signing_pool(&get_keypair_path(cli.keypair_path.clone()), cli.mnemonic, payer.as_ref());
+
+    let Commands::Backup { watch, interval, once, catalog, max_in_flight } = cli.command else {
+        return Ok(());
+    };
+
+    let catalog = match catalog {
+        Some(catalog) => catalog,
+        None => default_catalog(&watch)?,
+    };
+    let in_flight = InFlight::new(max_in_flight, None);
+
+    log::print_section_header("Backup");
+    log::print_message(&format!("Directory: {}", watch.display()));
+    log::print_message(&format!("Catalog: {}", catalog.display()));
+    log::print_divider();
+
+    loop {
+        match backup(&client, payer.as_ref(), pool.as_ref(), &watch, &catalog, in_flight).await {
+            Ok(()) => {}
+            Err(e) if once => return Err(e),
+            // A failed backup is tried again next time, the schedule keeps going
+            Err(e) => log::print_error(&format!("Backup failed: {}", e)),
+        }
+
+        if once {
+            return Ok(());
+        }
+
+        log::print_info(&format!("Next backup in {}s", interval.0.as_secs()));
+        tokio::time::sleep(interval.0).await;
+    }
+}
+
+/// Take one backup of `dir`, unless nothing changed since the last one.
+async fn backup(
+    client: &RpcClient,
+    payer: &dyn Signer,
+    pool: Option<&SigningPool>,
+    dir: &Path,
+    catalog_dir: &Path,
+    in_flight: InFlight,
+) -> Result<()> {
+    let files = read_dir_files(dir)?;
+    let data = pack_files(&files)?;
+    let checksum = hash(&data).to_string();
+
+    let mut catalog = Catalog::load(catalog_dir)?;
+    if let Some(last) = catalog.generations.last().filter(|g| g.checksum == checksum) {
+        log::print_message(&format!("No changes since generation {}", last.number));
+        return Ok(());
+    }
+
+    ensure_not_paused(client).await?;
+
+    let created_at = Utc::now().timestamp();
+    let tape_name = format!("backup.{}", created_at);
+    let header = new_header(&Mime::from_str(DIR_MIME)?, false);
+
+    fs::create_dir_all(catalog_dir)
+        .map_err(|e| anyhow!("Failed to create {}: {}", catalog_dir.display(), e))?;
+    let written = write_deduped(
+        client, payer, pool, &data, header, &tape_name, &catalog_dir.join(INDEX_FILE), in_flight, None, true,
+    )
+    .await?
+    .ok_or_else(|| anyhow!("Backup wasn't written"))?;
+
+    let generation = Generation {
+        number: catalog.generations.last().map_or(1, |g| g.number + 1),
+        created_at,
+        tape_address: written.tape_address.to_string(),
+        files: files.len(),
+        bytes: data.len() as u64,
+        new_bytes: written.new_bytes,
+        checksum,
+    };
+
+    log::print_count(&format!(
+        "Generation {}: {} files, {} bytes ({} new) at {}",
+        generation.number, generation.files, generation.bytes, generation.new_bytes, generation.tape_address
+    ));
+
+    catalog.generations.push(generation);
+    catalog.save(catalog_dir)
+}
+
+pub async fn handle_restore_command(cli: Cli, client: RpcClient) -> Result<()> {
+    let Commands::Restore { dir, catalog, generation, to, list } = cli.command else {
+        return Ok(());
+    };
+
+    let catalog_dir = match (catalog, dir) {
+        (Some(catalog), _) => catalog,
+        (None, Some(dir)) => default_catalog(&dir)?,
+        (None, None) => bail!("Either a directory or --catalog is required"),
+    };
+    let catalog = Catalog::load(&catalog_dir)?;
+    if catalog.generations.is_empty() {
+        bail!("No backups in {}", catalog_dir.display());
+    }
+
+    if list {
+        log::print_section_header("Backups");
+        for g in &catalog.generations {
+            let created_at = Utc.timestamp_opt(g.created_at, 0).single()
+                .map(|t| t.to_rfc3339())
+                .unwrap_or_else(|| g.created_at.to_string());
+            log::print_message(&format!(
+                "{}: {}, {} files, {} bytes ({} new), {}",
+                g.number, created_at, g.files, g.bytes, g.new_bytes, g.tape_address
+            ));
+        }
+        return Ok(());
+    }
+
+    let Some(to) = to else {
+        bail!("--to is required to restore");
+    };
+    let Some(generation) = catalog.get(generation) else {
+        bail!("No generation {} in {}", generation.unwrap_or_default(), catalog_dir.display());
+    };
+
+    if to.exists() && fs::read_dir(&to)?.next().is_some() {
+        bail!("{} isn't empty, restore into an empty directory", to.display());
+    }
+
+    log::print_message(&format!("Restoring generation {} to {}...", generation.number, to.display()));
+    let tape_address = Pubkey::from_str(&generation.tape_address)
+        .map_err(|_| anyhow!("Invalid tape address {} in the catalog", generation.tape_address))?;
+    let tape = fetch_tape_with(&client, &tape_address, None, |_, _| {}).await?;
+    if tape.mime != DIR_MIME {
+        bail!("Tape {} doesn't hold a backup", tape_address);
+    }
+
+    let files = unpack_files(&tape.bytes)?;
+    write_dir_files(&to, &files)?;
+
+    log::print_divider();
+    log::print_count(&format!("Restored {} files", files.len()));
+    Ok(())
+}
+
+/// Where the catalog of a directory goes by default, next to it: `<dir>.tapedrive-backup`.
+fn default_catalog(dir: &Path) -> Result<PathBuf> {
+    let dir = fs::canonicalize(dir).unwrap_or_else(|_| dir.to_path_buf());
+    let mut name = dir
+        .file_name()
+        .ok_or_else(|| anyhow!("Can't name a catalog for {}, pass --catalog", dir.display()))?
+        .to_os_string();
+    name.push(".tapedrive-backup");
+    Ok(dir.with_file_name(name))
+}
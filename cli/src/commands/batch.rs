@@ -0,0 +1,551 @@
+use anyhow::{anyhow, bail, Result};
+use chrono::{DateTime, Utc};
+use futures::stream::{self, StreamExt};
+use indicatif::{MultiProgress, ProgressBar, ProgressDrawTarget, ProgressStyle};
+use mime::Mime;
+use mime_guess::MimeGuess;
+use serde::{Deserialize, Serialize};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{native_token::lamports_to_sol, pubkey::Pubkey, signer::Signer};
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use tape_api::prelude::*;
+use tape_client::{ensure_not_paused, get_config_account, normalize_name, SigningPool};
+
+use crate::cli::{Cli, Commands};
+use crate::commands::airdrop::LOW_BALANCE;
+use crate::commands::write::{new_header, write_payload, EncodedPayload, InFlight};
+use crate::keypair::{get_keypair_path, signing_pool};
+use crate::log;
+use crate::progress::WriteProgress;
+
+/// A single file to write as its own tape.
+#[derive(Clone, Debug)]
+struct Job {
+    path: PathBuf,
+    name: String,
+    mime: Mime,
+}
+
+/// Progress of a batch, saved after every tape so an interrupted batch can be resumed.
+#[derive(Serialize, Deserialize, Default)]
+struct BatchState {
+    /// Tape name -> address of every tape written so far.
+    written: BTreeMap<String, String>,
+}
+
+/// One written tape in a batch's manifest file.
+#[derive(Serialize)]
+struct ManifestEntry {
+    path: String,
+    name: String,
+    mime: String,
+    address: String,
+}
+
+/// Tapes written at once when `write` is given a glob pattern.
+const GLOB_CONCURRENCY: usize = 4;
+
+/// How a batch writes its jobs.
+struct BatchOptions {
+    concurrency: usize,
+    state: PathBuf,
+    manifest: Option<PathBuf>,
+    lookup_table: bool,
+    in_flight: InFlight,
+    /// Write without asking first.
+    yes: bool,
+}
+
+pub async fn handle_write_batch_command(cli: Cli, client: RpcClient, payer: Box<dyn Signer>) -> Result<()> {
+    // Shared by every tape in flight
+    let pool = signing_pool(&get_keypair_path(cli.keypair_path.clone()), cli.mnemonic, payer.as_ref());
+
+    let Commands::WriteBatch { input, concurrency, state, name_template, manifest, lookup_table, max_in_flight, ordered } = cli.command else {
+        return Ok(());
+    };
+
+    if concurrency == 0 {
+        bail!("Concurrency must be at least 1");
+    }
+
+    let (jobs, state_name) = if input.is_dir() {
+        (jobs_from_dir(&input)?, input.clone())
+    } else if !input.exists() && is_glob(&input.to_string_lossy()) {
+        let pattern = input.to_string_lossy();
+        (jobs_from_glob(&pattern, None)?, glob_base(&pattern))
+    } else {
+        (jobs_from_csv(&input)?, input.clone())
+    };
+    let jobs = match &name_template {
+        Some(template) => apply_template(jobs, template)?,
+        None => jobs,
+    };
+
+    let options = BatchOptions {
+        concurrency,
+        state: state.unwrap_or_else(|| default_state_path(&state_name)),
+        manifest,
+        lookup_table,
+        in_flight: InFlight::new(max_in_flight, ordered),
+        yes: cli.yes,
+    };
+    write_batch(&client, payer.as_ref(), pool.as_ref(), &input.to_string_lossy(), jobs, options).await
+}
+
+/// `write` given a glob pattern instead of a file: each matching file becomes a tape, named
+/// with `--tape-name` as a template and typed with `--mime` or by its extension.
+pub async fn handle_write_glob_command(cli: Cli, client: RpcClient, payer: Box<dyn Signer>) -> Result<()> {
+    let pool = signing_pool(&get_keypair_path(cli.keypair_path.clone()), cli.mnemonic, payer.as_ref());
+
+    let Commands::Write {
+        filename: Some(pattern),
+        mime,
+        flags,
+        meta,
+        tape_name,
+        lookup_table,
+        max_in_flight,
+        ordered,
+        cache,
+        offline,
+        recipient,
+        dedup,
+        seekable,
+        manifest,
+        ..
+    } = cli.command else {
+        return Ok(());
+    };
+
+    if flags.is_some() || !meta.is_empty() || cache.is_some() || offline || recipient.is_some() || dedup.is_some() || seekable {
+        bail!("Glob patterns write each file as a plain tape, --flags, --meta, --cache, --offline, --recipient, --dedup and --seekable only work with a single file");
+    }
+
+    let mime = mime
+        .map(|mime| mime.parse::<Mime>().map_err(|e| anyhow!("Invalid MIME type {}: {}", mime, e)))
+        .transpose()?;
+    let jobs = jobs_from_glob(&pattern, mime)?;
+    let jobs = match &tape_name {
+        Some(template) => apply_template(jobs, template)?,
+        None => jobs,
+    };
+
+    let options = BatchOptions {
+        concurrency: GLOB_CONCURRENCY,
+        state: default_state_path(&glob_base(&pattern)),
+        manifest,
+        lookup_table,
+        in_flight: InFlight::new(max_in_flight, ordered),
+        yes: cli.yes,
+    };
+    write_batch(&client, payer.as_ref(), pool.as_ref(), &pattern, jobs, options).await
+}
+
+/// Write every job not in the batch's state file yet as its own tape, a few at a time.
+async fn write_batch(
+    client: &RpcClient,
+    payer: &dyn Signer,
+    pool: Option<&SigningPool>,
+    input: &str,
+    jobs: Vec<Job>,
+    options: BatchOptions,
+) -> Result<()> {
+    let BatchOptions { concurrency, state: state_path, manifest, lookup_table, in_flight, yes } = options;
+
+    check_names(&jobs)?;
+    let mut batch_state = load_state(&state_path)?;
+
+    let pending: Vec<Job> = jobs
+        .iter()
+        .filter(|job| !batch_state.written.contains_key(&job.name))
+        .cloned()
+        .collect();
+
+    // Fail before asking for anything if the guardian has paused writes
+    ensure_not_paused(client).await?;
+
+    log::print_section_header("Batch Write");
+    log::print_message(&format!("Input: {}", input));
+    log::print_message(&format!("State: {}", state_path.display()));
+    log::print_count(&format!("Already Written: {}", batch_state.written.len()));
+    log::print_count(&format!("Pending: {}", pending.len()));
+    log::print_divider();
+
+    if pending.is_empty() {
+        log::print_info("Nothing to write");
+        return Ok(());
+    }
+
+    if !log::confirm(&format!("→ Write {} files as tapes?", pending.len()), yes)? {
+        log::print_error("Write operation cancelled");
+        return Ok(());
+    }
+    log::print_divider();
+
+    // One bar for the whole batch, plus one per tape in flight
+    let mp = if log::is_quiet() {
+        MultiProgress::with_draw_target(ProgressDrawTarget::hidden())
+    } else {
+        MultiProgress::new()
+    };
+    let overall = mp.add(ProgressBar::new(pending.len() as u64));
+    overall.set_style(
+        ProgressStyle::default_bar()
+            .template("{spinner:.green} [{bar:40.white/gray}] {pos}/{len} tapes {wide_msg}")
+            .expect("Failed to set progress style"),
+    );
+    overall.enable_steady_tick(std::time::Duration::from_millis(100));
+
+    let mp = &mp;
+    // Set once a failure means every tape after it would fail too
+    let stopped = AtomicBool::new(false);
+    let stopped = &stopped;
+
+    let mut results = stream::iter(pending)
+        .map(|job| async move {
+            // Tapes in flight are left to finish, cutting one short would leave it half written
+            if stopped.load(Ordering::Relaxed) {
+                return (job, None);
+            }
+
+            let pb = WriteProgress::new(mp.add(ProgressBar::new(0)));
+            let result = write_job(client, payer, pool, &pb, &job, lookup_table, in_flight).await;
+            pb.finish_and_clear();
+            mp.remove(&pb);
+            (job, Some(result))
+        })
+        .buffer_unordered(concurrency);
+
+    let mut failed = Vec::new();
+    let mut not_started = Vec::new();
+    while let Some((job, result)) = results.next().await {
+        overall.inc(1);
+        match result {
+            Some(Ok(address)) => {
+                batch_state.written.insert(job.name.clone(), address.to_string());
+                save_state(&state_path, &batch_state)?;
+            }
+            Some(Err(e)) => {
+                overall.suspend(|| log::print_error(&format!("Failed to write {}: {}", job.path.display(), e)));
+                failed.push(job);
+
+                if !stopped.load(Ordering::Relaxed) {
+                    if let Some(reason) = batch_blocked(client, &payer.pubkey()).await {
+                        overall.suspend(|| log::print_error(&format!("Not starting any more tapes: {}", reason)));
+                        stopped.store(true, Ordering::Relaxed);
+                    }
+                }
+            }
+            None => not_started.push(job),
+        }
+    }
+    overall.finish_with_message("");
+
+    log::print_divider();
+    log::print_section_header("Tapes");
+    let entries = manifest_entries(&jobs, &batch_state);
+    for entry in &entries {
+        log::print_message(&format!("{} → {} ({}, {})", entry.name, entry.address, entry.path, entry.mime));
+        log::print_result(&format!("{} {}", entry.name, entry.address));
+    }
+
+    if let Some(manifest) = &manifest {
+        let json = serde_json::to_string_pretty(&entries)
+            .map_err(|e| anyhow!("Failed to serialize manifest: {}", e))?;
+        fs::write(manifest, json)
+            .map_err(|e| anyhow!("Failed to write {}: {}", manifest.display(), e))?;
+        log::print_message(&format!("Manifest: {}", manifest.display()));
+    }
+
+    log::print_divider();
+    if failed.is_empty() && not_started.is_empty() {
+        log::print_info(&format!("Wrote {} tapes", batch_state.written.len()));
+    } else {
+        for job in &failed {
+            log::print_error(&format!("Failed: {} ({})", job.path.display(), job.name));
+        }
+        for job in &not_started {
+            log::print_error(&format!("Not started: {} ({})", job.path.display(), job.name));
+        }
+        log::print_error(&format!(
+            "{} of the batch failed and {} wasn't started, run the same command again to retry them",
+            failed.len(),
+            not_started.len()
+        ));
+    }
+    log::print_divider();
+
+    Ok(())
+}
+
+/// Why the rest of a batch would fail too, checked after a tape fails: the guardian paused
+/// writes, or the payer is nearly out of SOL. A failing RPC isn't a reason, the next tape may
+/// get through.
+async fn batch_blocked(client: &RpcClient, payer: &Pubkey) -> Option<String> {
+    if let Ok((config, _)) = get_config_account(client).await {
+        if config.is_paused() {
+            return Some(format!("the tape program is paused by its guardian ({})", config.guardian));
+        }
+    }
+
+    match client.get_balance(payer).await {
+        Ok(balance) if balance < LOW_BALANCE => Some(format!(
+            "{} is nearly out of SOL ({} SOL left)",
+            payer,
+            lamports_to_sol(balance)
+        )),
+        _ => None,
+    }
+}
+
+async fn write_job(
+    client: &RpcClient,
+    payer: &dyn Signer,
+    pool: Option<&SigningPool>,
+    pb: &WriteProgress,
+    job: &Job,
+    lookup_table: bool,
+    in_flight: InFlight,
+) -> Result<Pubkey> {
+    let data = fs::read(&job.path)
+        .map_err(|e| anyhow!("Failed to read {}: {}", job.path.display(), e))?;
+
+    let payload = EncodedPayload::new(&data, new_header(&job.mime, lookup_table), None)?;
+    pb.set_length(payload.total_chunks() as u64);
+
+    write_payload(client, payer, pool, pb, &job.name, &payload, in_flight, None).await
+}
+
+/// The written tapes among `jobs`, in job order, including those of earlier runs of the batch.
+fn manifest_entries(jobs: &[Job], state: &BatchState) -> Vec<ManifestEntry> {
+    jobs.iter()
+        .filter_map(|job| {
+            let address = state.written.get(&job.name)?;
+            Some(ManifestEntry {
+                path: job.path.display().to_string(),
+                name: job.name.clone(),
+                mime: job.mime.to_string(),
+                address: address.clone(),
+            })
+        })
+        .collect()
+}
+
+/// Whether `input` is a glob pattern rather than a path.
+pub fn is_glob(input: &str) -> bool {
+    input.contains(['*', '?', '['])
+}
+
+/// The directory a glob pattern matches under, its leading components without wildcards.
+fn glob_base(pattern: &str) -> PathBuf {
+    Path::new(pattern)
+        .components()
+        .take_while(|component| !is_glob(&component.as_os_str().to_string_lossy()))
+        .collect()
+}
+
+/// Every regular file matching a glob pattern, `**` matching any number of directories. Files
+/// are named after their path relative to the pattern's base directory, and typed with `mime`
+/// or by their extension.
+fn jobs_from_glob(pattern: &str, mime: Option<Mime>) -> Result<Vec<Job>> {
+    let base = glob_base(pattern);
+    let mut paths = Vec::new();
+    for entry in glob::glob(pattern).map_err(|e| anyhow!("Invalid glob pattern {}: {}", pattern, e))? {
+        let path = entry?;
+        if path.is_file() {
+            paths.push(path);
+        }
+    }
+    if paths.is_empty() {
+        bail!("No files match {}", pattern);
+    }
+    paths.sort();
+
+    Ok(paths
+        .into_iter()
+        .map(|path| {
+            let name = relative_name(&base, &path);
+            let mime = mime.clone().unwrap_or_else(|| MimeGuess::from_path(&path).first_or_octet_stream());
+            Job { path, name, mime }
+        })
+        .collect())
+}
+
+/// Rename jobs after a template of `{stem}`, `{ext}`, `{name}` (the file name), `{path}` (the
+/// job's default name), `{date}` (the file's last modified day, YYYY-MM-DD) and `{index}` (its
+/// position in the batch, from 1).
+fn apply_template(jobs: Vec<Job>, template: &str) -> Result<Vec<Job>> {
+    jobs.into_iter()
+        .enumerate()
+        .map(|(i, job)| {
+            let name = expand_template(template, &job, i + 1)?;
+            Ok(Job { name: normalize_name(&name), ..job })
+        })
+        .collect()
+}
+
+fn expand_template(template: &str, job: &Job, index: usize) -> Result<String> {
+    let mut name = String::new();
+    let mut rest = template;
+
+    while let Some(start) = rest.find('{') {
+        name.push_str(&rest[..start]);
+        let end = rest[start..]
+            .find('}')
+            .ok_or_else(|| anyhow!("Unclosed placeholder in name template {}", template))?;
+        let placeholder = &rest[start + 1..start + end];
+        let lossy = |s: Option<&std::ffi::OsStr>| s.map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+
+        match placeholder {
+            "stem" => name.push_str(&lossy(job.path.file_stem())),
+            "ext" => name.push_str(&lossy(job.path.extension())),
+            "name" => name.push_str(&lossy(job.path.file_name())),
+            "path" => name.push_str(&job.name),
+            "date" => {
+                let modified = fs::metadata(&job.path)
+                    .and_then(|m| m.modified())
+                    .map_err(|e| anyhow!("Failed to read the modified time of {}: {}", job.path.display(), e))?;
+                name.push_str(&DateTime::<Utc>::from(modified).format("%Y-%m-%d").to_string());
+            }
+            "index" => name.push_str(&index.to_string()),
+            other => bail!(
+                "Unknown placeholder {{{}}} in name template {}, expected stem, ext, name, path, date or index",
+                other, template
+            ),
+        }
+
+        rest = &rest[start + end + 1..];
+    }
+    name.push_str(rest);
+
+    Ok(name)
+}
+
+fn relative_name(base: &Path, path: &Path) -> String {
+    path.strip_prefix(base)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .replace(std::path::MAIN_SEPARATOR, "/")
+}
+
+/// Every regular file under `dir`, named after its path relative to `dir`.
+fn jobs_from_dir(dir: &Path) -> Result<Vec<Job>> {
+    let mut paths = Vec::new();
+    collect_files(dir, &mut paths)?;
+    paths.sort();
+
+    paths
+        .into_iter()
+        .map(|path| {
+            let name = relative_name(dir, &path);
+            let mime = MimeGuess::from_path(&path).first_or_octet_stream();
+            Ok(Job { path, name, mime })
+        })
+        .collect()
+}
+
+fn collect_files(dir: &Path, paths: &mut Vec<PathBuf>) -> Result<()> {
+    let entries = fs::read_dir(dir)
+        .map_err(|e| anyhow!("Failed to read {}: {}", dir.display(), e))?;
+
+    for entry in entries {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_files(&path, paths)?;
+        } else if path.is_file() {
+            paths.push(path);
+        }
+    }
+
+    Ok(())
+}
+
+/// Jobs from a CSV file with one `path,name[,mime]` line per tape. Paths are relative to the CSV
+/// file, the name defaults to the file name and the MIME type is guessed from the extension if
+/// left out. Fields can't be quoted, blank lines, `#` comments and a `path,...` header line are
+/// skipped.
+fn jobs_from_csv(csv: &Path) -> Result<Vec<Job>> {
+    let data = fs::read_to_string(csv)
+        .map_err(|e| anyhow!("Failed to read {}: {}", csv.display(), e))?;
+    let base = csv.parent().unwrap_or(Path::new("."));
+
+    let mut jobs = Vec::new();
+    for (i, line) in data.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || (i == 0 && line.starts_with("path,")) {
+            continue;
+        }
+
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        if fields.len() > 3 || fields[0].is_empty() {
+            bail!("{}:{}: expected path,name[,mime]", csv.display(), i + 1);
+        }
+
+        let path = base.join(fields[0]);
+        let name = match fields.get(1) {
+            Some(name) if !name.is_empty() => name.to_string(),
+            _ => path
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .ok_or_else(|| anyhow!("{}:{}: no file name in {}", csv.display(), i + 1, fields[0]))?,
+        };
+        let mime = match fields.get(2) {
+            Some(mime) if !mime.is_empty() => mime
+                .parse()
+                .map_err(|e| anyhow!("{}:{}: invalid MIME type {}: {}", csv.display(), i + 1, mime, e))?,
+            _ => MimeGuess::from_path(&path).first_or_octet_stream(),
+        };
+
+        jobs.push(Job { path, name, mime });
+    }
+
+    Ok(jobs)
+}
+
+/// Tape addresses derive from the name, so names must be unique and fit on-chain.
+fn check_names(jobs: &[Job]) -> Result<()> {
+    let mut seen = HashSet::new();
+    for job in jobs {
+        if job.name.len() > NAME_LEN {
+            bail!(
+                "Tape name {} for {} is longer than {} bytes",
+                job.name, job.path.display(), NAME_LEN
+            );
+        }
+        if !seen.insert(&job.name) {
+            bail!("Tape name {} is used more than once", job.name);
+        }
+    }
+    Ok(())
+}
+
+fn default_state_path(input: &Path) -> PathBuf {
+    let name = input
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "batch".to_string());
+
+    PathBuf::from(format!("{}.tapedrive.json", name))
+}
+
+fn load_state(path: &Path) -> Result<BatchState> {
+    if !path.exists() {
+        return Ok(BatchState::default());
+    }
+
+    let data = fs::read_to_string(path)
+        .map_err(|e| anyhow!("Failed to read {}: {}", path.display(), e))?;
+    serde_json::from_str(&data)
+        .map_err(|e| anyhow!("Failed to parse {}: {}", path.display(), e))
+}
+
+fn save_state(path: &Path, state: &BatchState) -> Result<()> {
+    let json = serde_json::to_string_pretty(state)
+        .map_err(|e| anyhow!("Failed to serialize batch state: {}", e))?;
+    fs::write(path, json)
+        .map_err(|e| anyhow!("Failed to write {}: {}", path.display(), e))
+}
@@ -1,19 +1,22 @@
 use anyhow::{anyhow, Result};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
 use std::str::FromStr;
-use dialoguer::{theme::ColorfulTheme, Confirm};
 use solana_client::nonblocking::rpc_client::RpcClient;
-use solana_sdk::{signature::Keypair, signer::Signer, pubkey::Pubkey};
+use solana_sdk::{signer::Signer, pubkey::Pubkey};
 
 use crate::cli::{Cli, Commands};
 use crate::log;
-use tape_client::{claim::claim_rewards, utils::create_ata};
+use tape_client::claim::{claim_rewards, resolve_beneficiary};
+use tape_client::parse_tape_amount;
 
 pub async fn handle_claim_command(
     cli: Cli,
     client: RpcClient,
-    payer: Keypair,
+    payer: Box<dyn Signer>,
 ) -> Result<()> {
-    if let Commands::Claim { miner, amount } = cli.command {
+    if let Commands::Claim { miner, amount, raw, beneficiary } = cli.command {
         log::print_divider();
         log::print_info("Claiming rewards...");
 
@@ -21,34 +24,46 @@ pub async fn handle_claim_command(
         let miner_pubkey = Pubkey::from_str(&miner)
             .map_err(|e| anyhow!("Invalid miner pubkey '{}': {}", miner, e))?;
 
-        // Ensure payer's associated token account (ATA) exists for the mint
-        let (beneficiary_ata, ata_sig) = create_ata(&client, &payer)
-            .await
-            .map_err(|e| anyhow!("Failed to create/ensure ATA for payer {}: {}", payer.pubkey(), e))?;
+        let amount = if raw {
+            amount
+                .trim()
+                .parse::<u64>()
+                .map_err(|e| anyhow!("Invalid raw amount '{}': {}", amount, e))?
+        } else {
+            parse_tape_amount(&amount)?
+        };
 
-        // Log ATA creation
-        if ata_sig != solana_sdk::signature::Signature::default() {
-            log::print_message(&format!("Created ATA {} (payer), signature {}", beneficiary_ata, ata_sig));
-        }
+        // Rewards go to --beneficiary, else the one set for this miner, else the payer
+        let beneficiary = match beneficiary {
+            Some(beneficiary) => parse_pubkey("beneficiary", &beneficiary)?,
+            None => load_beneficiaries()?
+                .get(&miner_pubkey.to_string())
+                .map(|b| parse_pubkey("beneficiary", b))
+                .transpose()?
+                .unwrap_or_else(|| payer.pubkey()),
+        };
+
+        // The beneficiary's token account, created along with the claim if it's missing
+        let (beneficiary_ata, missing) = resolve_beneficiary(&client, &beneficiary)
+            .await
+            .map_err(|e| anyhow!("Failed to resolve token account for beneficiary {}: {}", beneficiary, e))?;
 
         log::print_message(&format!(
-            "Miner: {}\n→ Beneficiary ATA (payer): {}\n→ Amount: {}",
-            miner_pubkey, beneficiary_ata, amount
+            "Miner: {}\n→ Beneficiary: {}\n→ Beneficiary ATA: {}{}\n→ Amount: {}",
+            miner_pubkey,
+            beneficiary,
+            beneficiary_ata,
+            if missing { " (created with the claim)" } else { "" },
+            log::tape_amount(amount)
         ));
 
         // Confirm action with the user
-        let proceed = Confirm::with_theme(&ColorfulTheme::default())
-            .with_prompt("→ Proceed with claim?")
-            .default(false)
-            .interact()
-            .map_err(|e| anyhow!("Failed to get user input: {}", e))?;
-        if !proceed {
+        if !log::confirm("→ Proceed with claim?", cli.yes)? {
             log::print_error("Claim cancelled");
             return Ok(());
         }
 
-        // Execute claim using the ensured ATA
-        let signature = claim_rewards(&client, &payer, miner_pubkey, beneficiary_ata, amount)
+        let signature = claim_rewards(&client, payer.as_ref(), miner_pubkey, beneficiary, amount)
             .await
             .map_err(|e| anyhow!("Failed to claim rewards: {}", e))?;
 
@@ -58,3 +73,63 @@ pub async fn handle_claim_command(
     }
     Ok(())
 }
+
+pub fn handle_set_beneficiary_command(cli: Cli) -> Result<()> {
+    if let Commands::SetBeneficiary { miner, beneficiary, clear } = cli.command {
+        let miner_pubkey = parse_pubkey("miner", &miner)?;
+        let mut beneficiaries = load_beneficiaries()?;
+
+        log::print_divider();
+        if clear {
+            beneficiaries.remove(&miner_pubkey.to_string());
+            log::print_info(&format!("Rewards for {} go to the signer's account", miner_pubkey));
+        } else if let Some(beneficiary) = beneficiary {
+            let beneficiary = parse_pubkey("beneficiary", &beneficiary)?;
+            beneficiaries.insert(miner_pubkey.to_string(), beneficiary.to_string());
+            log::print_info(&format!("Rewards for {} go to {}", miner_pubkey, beneficiary));
+        }
+
+        save_beneficiaries(&beneficiaries)?;
+        log::print_message(&format!("Saved to {}", beneficiaries_path()?.display()));
+        log::print_divider();
+    }
+    Ok(())
+}
+
+fn parse_pubkey(label: &str, value: &str) -> Result<Pubkey> {
+    Pubkey::from_str(value).map_err(|e| anyhow!("Invalid {} pubkey '{}': {}", label, value, e))
+}
+
+/// Beneficiaries are kept per miner in `~/.config/tapedrive/beneficiaries.json`, so the hot key
+/// that mines can claim straight to a cold wallet.
+fn beneficiaries_path() -> Result<PathBuf> {
+    let dir = dirs::home_dir()
+        .ok_or_else(|| anyhow!("Could not find home directory"))?
+        .join(".config/tapedrive");
+    Ok(dir.join("beneficiaries.json"))
+}
+
+fn load_beneficiaries() -> Result<BTreeMap<String, String>> {
+    let path = beneficiaries_path()?;
+    if !path.exists() {
+        return Ok(BTreeMap::new());
+    }
+
+    let data = fs::read_to_string(&path)
+        .map_err(|e| anyhow!("Failed to read {}: {}", path.display(), e))?;
+    serde_json::from_str(&data)
+        .map_err(|e| anyhow!("Failed to parse {}: {}", path.display(), e))
+}
+
+fn save_beneficiaries(beneficiaries: &BTreeMap<String, String>) -> Result<()> {
+    let path = beneficiaries_path()?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .map_err(|e| anyhow!("Failed to create {}: {}", dir.display(), e))?;
+    }
+
+    let json = serde_json::to_string_pretty(beneficiaries)
+        .map_err(|e| anyhow!("Failed to serialize beneficiaries: {}", e))?;
+    fs::write(&path, json)
+        .map_err(|e| anyhow!("Failed to write {}: {}", path.display(), e))
+}
@@ -0,0 +1,140 @@
+use anyhow::{bail, Result};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{
+    native_token::{sol_to_lamports, LAMPORTS_PER_SOL},
+    pubkey::Pubkey,
+    signer::Signer,
+};
+
+use tape_api::prelude::*;
+use tape_client::{
+    chunk_size,
+    create_tape,
+    encode_tape,
+    finalize_tape,
+    get_archive_account,
+    get_miner_account,
+    get_tape_account,
+    program_context,
+    register::register_miner,
+    request_airdrop,
+    write_to_tape,
+    CompressionAlgo,
+    EncryptionAlgo,
+    MimeType,
+    TapeFlags,
+    TapeHeader,
+};
+
+use crate::cli::{Cli, Commands, DevCommands};
+use crate::log;
+
+/// Balance below which seeding tops up the payer from the localnet faucet first.
+const MIN_BALANCE: u64 = LAMPORTS_PER_SOL;
+
+/// What seeding asks the faucet for, plenty for a few thousand writes.
+const TOP_UP_SOL: f64 = 10.0;
+
+pub async fn handle_dev_commands(cli: Cli, client: RpcClient, payer: Box<dyn Signer>) -> Result<()> {
+    let Commands::Dev { command } = cli.command else {
+        return Ok(());
+    };
+
+    // Seeding spends SOL freely and leaves tapes nobody wants, keep it off real clusters
+    if !cli.cluster.is_local() {
+        bail!("Dev commands only run against a local validator, use -u l or a localhost URL");
+    }
+
+    match command {
+        DevCommands::Seed { tapes, segments, miners, seed } => {
+            seed_localnet(&client, payer.as_ref(), tapes, segments, miners, seed).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Initialize the program if needed, then create and finalize `tapes` tapes of random data, each
+/// up to `max_segments` segments, and register `miners` miners. Names, sizes and data all come
+/// from `seed`, so a fresh validator seeded twice ends up the same. Tapes and miners already
+/// there from an earlier run are skipped.
+async fn seed_localnet(
+    client: &RpcClient,
+    payer: &dyn Signer,
+    tapes: u64,
+    max_segments: u64,
+    miners: u64,
+    seed: u64,
+) -> Result<()> {
+    log::print_divider();
+
+    if client.get_balance(&payer.pubkey()).await? < MIN_BALANCE {
+        log::print_info(&format!("Requesting {} SOL for {}...", TOP_UP_SOL, payer.pubkey()));
+        request_airdrop(client, &payer.pubkey(), sol_to_lamports(TOP_UP_SOL)).await?;
+    }
+
+    if get_archive_account(client).await.is_err() {
+        log::print_info("Initializing the program...");
+        tape_client::initialize(client, payer).await?;
+    }
+
+    let mut rng = StdRng::seed_from_u64(seed);
+    let header = TapeHeader::new(MimeType::Unknown, CompressionAlgo::None, EncryptionAlgo::None, TapeFlags::None);
+
+    for i in 0..tapes {
+        let name = format!("dev-{}-{}", seed, i);
+        let segment_count = rng.gen_range(1..=max_segments);
+        let mut data = vec![0u8; segment_count as usize * SEGMENT_SIZE];
+        rng.fill(&mut data[..]);
+
+        let address = seed_tape(client, payer, &name, &data, header).await?;
+        log::print_message(&format!("Tape {}/{} {}: {} segments ({})", i + 1, tapes, name, segment_count, address));
+    }
+
+    for i in 0..miners {
+        let name = format!("dev-miner-{}", i);
+        let (miner_address, _) = program_context().miner_pda(payer.pubkey(), to_name(&name));
+        if get_miner_account(client, &miner_address).await.is_err() {
+            register_miner(client, payer, &name).await?;
+        }
+        log::print_message(&format!("Miner {}: {}", name, miner_address));
+    }
+
+    log::print_section_header("Localnet Seeded");
+    log::print_message(&format!("Tapes: {}", tapes));
+    log::print_message(&format!("Miners: {}", miners));
+    log::print_divider();
+
+    Ok(())
+}
+
+/// Write `data` to a new unlinked tape and finalize it, unless a finalized tape by that name is
+/// already there.
+async fn seed_tape(
+    client: &RpcClient,
+    payer: &dyn Signer,
+    name: &str,
+    data: &[u8],
+    header: TapeHeader,
+) -> Result<Pubkey> {
+    let (tape_address, _) = program_context().tape_pda(payer.pubkey(), &to_name(name));
+    if let Ok((tape, _)) = get_tape_account(client, &tape_address).await {
+        if tape.state == u64::from(TapeState::Finalized) {
+            return Ok(tape_address);
+        }
+        bail!(
+            "Tape {} was left unfinalized by an earlier run, seed with another --seed or reset the validator",
+            name
+        );
+    }
+
+    let encoded = encode_tape(data, &header)?;
+    let (tape_address, writer_address, _) = create_tape(client, payer, name, header).await?;
+    for chunk in encoded.chunks(chunk_size(&header)) {
+        write_to_tape(client, payer, tape_address, writer_address, chunk).await?;
+    }
+    finalize_tape(client, payer, tape_address, writer_address, header).await?;
+
+    Ok(tape_address)
+}
@@ -0,0 +1,115 @@
+use anyhow::Result;
+use std::path::PathBuf;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{native_token::lamports_to_sol, signer::Signer};
+
+use tape_client::{get_archive_account, program_context};
+
+use crate::commands::network::cluster_namespace;
+use crate::cli::Cli;
+use crate::keypair::load_signer;
+use crate::log;
+
+/// Drift (in slots) beyond which the local store is considered behind.
+const MAX_DRIFT: u64 = 200;
+
+pub async fn handle_doctor_command(cli: Cli, client: RpcClient, keypair_path: PathBuf) -> Result<()> {
+    log::print_section_header("Doctor");
+
+    let mut failures = 0;
+    let mut check = |label: &str, result: Result<String>| {
+        match result {
+            Ok(detail) => log::print_check(label, true, &detail),
+            Err(e) => {
+                failures += 1;
+                log::print_check(label, false, &e.to_string());
+            }
+        }
+    };
+
+    // Keypair and balance
+    match load_signer(&keypair_path, cli.mnemonic, cli.derivation_path.as_deref()).await {
+        Ok(payer) => {
+            check("Keypair", Ok(format!("{} ({})", payer.pubkey(), keypair_path.display())));
+            check("Balance", check_balance(&client, &payer.pubkey()).await);
+        }
+        Err(e) => {
+            check("Keypair", Err(e));
+            check("Balance", Err(anyhow::anyhow!("Skipped, no keypair")));
+        }
+    }
+
+    // Cluster
+    let rpc_ok = match client.get_version().await {
+        Ok(version) => {
+            check("RPC", Ok(format!("{} (solana-core {})", client.url(), version.solana_core)));
+            true
+        }
+        Err(e) => {
+            check("RPC", Err(anyhow::anyhow!("{} unreachable: {}", client.url(), e)));
+            false
+        }
+    };
+
+    if rpc_ok {
+        check("Program", check_program(&client).await);
+        check("Store", check_store(&client).await);
+    } else {
+        check("Program", Err(anyhow::anyhow!("Skipped, RPC unreachable")));
+        check("Store", Err(anyhow::anyhow!("Skipped, RPC unreachable")));
+    }
+
+    log::print_divider();
+    if failures == 0 {
+        log::print_info("All checks passed");
+    } else {
+        log::print_error(&format!("{} check(s) failed", failures));
+    }
+
+    Ok(())
+}
+
+async fn check_balance(client: &RpcClient, pubkey: &solana_sdk::pubkey::Pubkey) -> Result<String> {
+    let lamports = client.get_balance(pubkey).await?;
+    if lamports == 0 {
+        anyhow::bail!("0 SOL, fund the keypair to write tapes or mine");
+    }
+
+    Ok(format!("{} SOL", lamports_to_sol(lamports)))
+}
+
+async fn check_program(client: &RpcClient) -> Result<String> {
+    let program_id = program_context().program_id;
+    let program = client
+        .get_account(&program_id)
+        .await
+        .map_err(|_| anyhow::anyhow!("{} is not deployed on this cluster", program_id))?;
+
+    if !program.executable {
+        anyhow::bail!("{} exists but isn't executable", program_id);
+    }
+
+    let (archive, _) = get_archive_account(client)
+        .await
+        .map_err(|_| anyhow::anyhow!("{} is deployed but not initialized", program_id))?;
+
+    Ok(format!("{} ({} tapes)", program_id, archive.tapes_stored))
+}
+
+async fn check_store(client: &RpcClient) -> Result<String> {
+    let namespace = cluster_namespace(client).await?;
+    let store = tape_network::store::secondary(&namespace)
+        .map_err(|e| anyhow::anyhow!("Can't open the local store ({}), is an archiver running here?", e))?;
+
+    let (last_processed_slot, _) = store
+        .get_health()
+        .map_err(|_| anyhow::anyhow!("Store has no health data yet"))?;
+
+    let slot = client.get_slot().await?;
+    let drift = slot.saturating_sub(last_processed_slot);
+    if drift > MAX_DRIFT {
+        anyhow::bail!("Store is {} slots behind the cluster", drift);
+    }
+
+    Ok(format!("{} ({} slots behind)", namespace, drift))
+}
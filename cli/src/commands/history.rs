@@ -0,0 +1,172 @@
+use anyhow::{anyhow, Result};
+use chrono::DateTime;
+use serde_json::json;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+
+use crate::cli::{Cli, Commands};
+use crate::log;
+use tape_client::{diff_tape, fetch_tape_history, SegmentDiff, TapeAction, TapeHistory};
+
+pub async fn handle_history_command(cli: Cli, client: RpcClient) -> Result<()> {
+    let Commands::History { tape, json } = cli.command else {
+        return Ok(());
+    };
+
+    let tape_address = Pubkey::from_str(&tape)
+        .map_err(|_| anyhow!("Invalid tape address: {}", tape))?;
+
+    log::print_message("Replaying tape history...");
+    let history = fetch_tape_history(&client, &tape_address).await?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&history_json(&tape_address, &history))?);
+        return Ok(());
+    }
+
+    log::print_section_header("Tape History");
+    for entry in &history.entries {
+        let time = entry
+            .block_time
+            .and_then(|t| DateTime::from_timestamp(t, 0))
+            .map(|t| t.format("%Y-%m-%d %H:%M:%S UTC").to_string())
+            .unwrap_or_else(|| "-".into());
+
+        log::print_message(&format!(
+            "Slot {} ({}) {}\n  {}\n  Root {} → {}",
+            entry.slot,
+            time,
+            describe(&entry.action),
+            entry.signature,
+            to_hex(&entry.old_root),
+            to_hex(&entry.new_root),
+        ));
+    }
+
+    log::print_divider();
+    log::print_count(&format!("Instructions: {}", history.entries.len()));
+    if history.verified {
+        log::print_info(&format!("Replay matches the on-chain root {}", to_hex(&history.merkle_root)));
+    } else {
+        log::print_error(&format!(
+            "Replay doesn't match the on-chain root {}, the history may be incomplete",
+            to_hex(&history.merkle_root)
+        ));
+    }
+    log::print_divider();
+
+    Ok(())
+}
+
+pub async fn handle_diff_command(cli: Cli, client: RpcClient) -> Result<()> {
+    let Commands::Diff { tape, slot_a, slot_b, json } = cli.command else {
+        return Ok(());
+    };
+
+    let tape_address = Pubkey::from_str(&tape)
+        .map_err(|_| anyhow!("Invalid tape address: {}", tape))?;
+
+    log::print_message("Replaying tape history...");
+    let diff = diff_tape(&client, &tape_address, slot_a, slot_b).await?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&diff_json(&tape_address, slot_a, slot_b, &diff))?);
+        return Ok(());
+    }
+
+    log::print_section_header(&format!("Tape Diff (slot {} → {})", slot_a, slot_b));
+    for change in &diff {
+        match &change.before {
+            Some(before) => log::print_message(&format!(
+                "Segment {} changed
+  - {}
+  + {}",
+                change.segment,
+                to_hex(before),
+                to_hex(&change.after),
+            )),
+            None => log::print_message(&format!(
+                "Segment {} added
+  + {}",
+                change.segment,
+                to_hex(&change.after),
+            )),
+        }
+    }
+
+    log::print_divider();
+    log::print_count(&format!("Changed Segments: {}", diff.len()));
+    log::print_divider();
+
+    Ok(())
+}
+
+fn describe(action: &TapeAction) -> String {
+    match action {
+        TapeAction::Create => "Create".to_string(),
+        TapeAction::Write { first, count } => match count {
+            0 => "Write (no segments)".to_string(),
+            1 => format!("Write segment {}", first),
+            _ => format!("Write segments {}-{}", first, first + count - 1),
+        },
+        TapeAction::Update { segment } => format!("Update segment {}", segment),
+        TapeAction::Finalize => "Finalize".to_string(),
+    }
+}
+
+fn history_json(tape_address: &Pubkey, history: &TapeHistory) -> serde_json::Value {
+    let entries: Vec<serde_json::Value> = history
+        .entries
+        .iter()
+        .map(|entry| {
+            let (action, segments) = match &entry.action {
+                TapeAction::Create => ("create", vec![]),
+                TapeAction::Write { first, count } => ("write", (*first..first + count).collect()),
+                TapeAction::Update { segment } => ("update", vec![*segment]),
+                TapeAction::Finalize => ("finalize", vec![]),
+            };
+
+            json!({
+                "slot": entry.slot,
+                "block_time": entry.block_time,
+                "signature": entry.signature.to_string(),
+                "action": action,
+                "segments": segments,
+                "old_root": to_hex(&entry.old_root),
+                "new_root": to_hex(&entry.new_root),
+            })
+        })
+        .collect();
+
+    json!({
+        "tape": tape_address.to_string(),
+        "merkle_root": to_hex(&history.merkle_root),
+        "verified": history.verified,
+        "entries": entries,
+    })
+}
+
+fn diff_json(tape_address: &Pubkey, slot_a: u64, slot_b: u64, diff: &[SegmentDiff]) -> serde_json::Value {
+    let segments: Vec<serde_json::Value> = diff
+        .iter()
+        .map(|change| {
+            json!({
+                "segment": change.segment,
+                "before": change.before.as_ref().map(|b| to_hex(b)),
+                "after": to_hex(&change.after),
+            })
+        })
+        .collect();
+
+    json!({
+        "tape": tape_address.to_string(),
+        "from_slot": slot_a,
+        "to_slot": slot_b,
+        "segments": segments,
+    })
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
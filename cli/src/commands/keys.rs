@@ -0,0 +1,87 @@
+use anyhow::{anyhow, bail, Result};
+use dialoguer::{theme::ColorfulTheme, Password};
+use solana_sdk::pubkey::Pubkey;
+
+use crate::cli::{Cli, Commands, KeyCommands};
+use crate::keyring::{get_key, keyring_path, load_keyring, parse_key, save_keyring, KeyEntry};
+use crate::log;
+use tape_client::SealKeypair;
+
+pub fn handle_keys_command(cli: Cli) -> Result<()> {
+    let Commands::Keys { command } = cli.command else {
+        return Ok(());
+    };
+
+    match command {
+        KeyCommands::Generate { name } => {
+            let keypair = SealKeypair::generate();
+            add_key(&name, KeyEntry::new(&keypair, &new_passphrase()?)?)?;
+
+            log::print_section_header("New Key");
+            log::print_message(&format!("Name: {}", name));
+            log::print_message(&format!("Public Key: {}", Pubkey::new_from_array(keypair.public)));
+            log::print_info(&format!("Others can write tapes only you can read with `tapedrive write --recipient <KEY>`, read them with `tapedrive read --key {}`", name));
+        }
+        KeyCommands::List {} => {
+            let keys = load_keyring()?;
+
+            log::print_section_header("Keyring");
+            if keys.is_empty() {
+                log::print_info("No keys yet, add one with `tapedrive keys generate <name>`");
+            }
+            for (name, entry) in &keys {
+                let kind = if entry.locked.is_some() { "own" } else { "public only" };
+                log::print_message(&format!("{}: {} ({})", name, entry.public, kind));
+            }
+        }
+        KeyCommands::Export { name, secret: false } => {
+            // Public keys go to stdout as-is, for passing along
+            println!("{}", get_key(&name)?.public);
+        }
+        KeyCommands::Export { name, secret: true } => {
+            let keypair = get_key(&name)?.unlock(&name)?;
+
+            log::print_error("Anyone with this secret key can read every tape sealed to it");
+            // Base58, like the public key
+            println!("{}", bs58::encode(keypair.secret).into_string());
+        }
+        KeyCommands::Import { name, public: Some(public) } => {
+            add_key(&name, KeyEntry::public_only(&parse_key(&public)?))?;
+            log::print_message(&format!("Imported {}, write to it with `tapedrive write --recipient {}`", name, name));
+        }
+        KeyCommands::Import { name, public: None } => {
+            // Prompted rather than passed, so it stays out of the shell history
+            let secret = Password::with_theme(&ColorfulTheme::default())
+                .with_prompt("Secret key")
+                .interact()
+                .map_err(|e| anyhow!("Failed to get user input: {}", e))?;
+            let keypair = SealKeypair::from_secret(parse_key(secret.trim())?);
+
+            add_key(&name, KeyEntry::new(&keypair, &new_passphrase()?)?)?;
+            log::print_message(&format!("Imported {} with public key {}", name, Pubkey::new_from_array(keypair.public)));
+        }
+    }
+
+    Ok(())
+}
+
+/// Store a key under a new name, refusing to replace one.
+fn add_key(name: &str, entry: KeyEntry) -> Result<()> {
+    let mut keys = load_keyring()?;
+    if keys.contains_key(name) {
+        bail!("There's already a key named {}", name);
+    }
+
+    keys.insert(name.to_string(), entry);
+    save_keyring(&keys)?;
+    log::print_message(&format!("Saved to {}", keyring_path()?.display()));
+    Ok(())
+}
+
+fn new_passphrase() -> Result<String> {
+    Password::with_theme(&ColorfulTheme::default())
+        .with_prompt("Passphrase to lock the key with")
+        .with_confirmation("Repeat the passphrase", "Passphrases don't match")
+        .interact()
+        .map_err(|e| anyhow!("Failed to get user input: {}", e))
+}
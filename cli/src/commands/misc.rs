@@ -1,11 +1,16 @@
 use anyhow::Result;
+use chrono::{DateTime, Utc};
 use solana_client::nonblocking::rpc_client::RpcClient;
-use solana_sdk::pubkey::Pubkey;
-use crate::cli::{Cli, Commands};
+use solana_sdk::{pubkey::Pubkey, signer::Signer};
+use crate::cli::{Cli, Commands, InfoCommands};
+use crate::commands::network::cluster_namespace;
+use crate::keypair::{get_keypair_path, get_payer, seal_keypair};
 use crate::log;
 use tape_client as tapedrive;
-use tape_api::utils::from_name;
+use tape_api::prelude::{MAX_SUPPLY, MINER_DEPOSIT_COOLDOWN};
+use tape_api::utils::{claimable_rewards, from_name, locked_rewards_at};
 use tape_client::TapeHeader;
+use tape_network::store::SyncEstimate;
 
 pub async fn handle_misc_commands(cli: Cli, client: RpcClient) -> Result<()> {
     match cli.command {
@@ -14,7 +19,23 @@ pub async fn handle_misc_commands(cli: Cli, client: RpcClient) -> Result<()> {
             log::print_section_header("Archive Account");
             log::print_message(&format!("Tapes: {}", archive.tapes_stored));
         }
-        Commands::GetEpoch {} => {
+        Commands::GetEpoch { history: true, from, to } => {
+            let namespace = cluster_namespace(&client).await?;
+            let store = tape_network::store::secondary(&namespace)?;
+            let epochs = store.get_epoch_history(from.unwrap_or(0), to.unwrap_or(u64::MAX), usize::MAX)?;
+
+            log::print_section_header("Epoch History");
+            if epochs.is_empty() {
+                log::print_info("No epochs recorded yet, they are saved while `tapedrive archive` runs");
+            }
+            for epoch in epochs {
+                log::print_message(&format!(
+                    "Epoch {}: difficulty {}, base rate {}, target rate {}, started {} (slot {})",
+                    epoch.number, epoch.difficulty, epoch.base_rate, epoch.target_rate, epoch.last_epoch_at, epoch.slot
+                ));
+            }
+        }
+        Commands::GetEpoch { .. } => {
             let (epoch, _address) = tapedrive::get_epoch_account(&client).await?;
             log::print_section_header("Epoch Account");
             log::print_message(&format!("Current Epoch: {}", epoch.number));
@@ -23,11 +44,23 @@ pub async fn handle_misc_commands(cli: Cli, client: RpcClient) -> Result<()> {
             log::print_message(&format!("Base Rate: {}", epoch.base_rate));
             log::print_message(&format!("Target Rate: {}", epoch.target_rate));
         }
+        Commands::GetConfig {} => {
+            let (config, _address) = tapedrive::get_config_account(&client).await?;
+            log::print_section_header("Config Account");
+            log::print_message(&format!("Guardian: {}", config.guardian));
+            log::print_message(&format!("Paused: {}", config.is_paused()));
+        }
+        Commands::SealKey {} => {
+            let keypair = seal_keypair(&get_keypair_path(cli.keypair_path))?;
+            log::print_section_header("Seal Key");
+            log::print_message(&format!("Public Key: {}", Pubkey::new_from_array(keypair.public)));
+            log::print_info("Others can write tapes only you can read with `tapedrive write --recipient <KEY>`");
+        }
         Commands::GetSpool { number } => {
             let (spool, _address) = tapedrive::get_spool_account(&client, number).await?;
             log::print_section_header("Spool Account");
-            log::print_message(&format!("Available Rewards: {}", spool.available_rewards));
-            log::print_message(&format!("Theoretical Rewards: {}", spool.theoretical_rewards));
+            log::print_message(&format!("Available Rewards: {}", log::tape_amount(spool.available_rewards)));
+            log::print_message(&format!("Theoretical Rewards: {}", log::tape_amount(spool.theoretical_rewards)));
         }
         Commands::FindTape { number } => {
             let res = tapedrive::find_tape_account(&client, number).await?;
@@ -57,9 +90,131 @@ pub async fn handle_misc_commands(cli: Cli, client: RpcClient) -> Result<()> {
             log::print_message(&format!("Total Segments: {}", tape.total_segments));
             log::print_message(&format!("Total Size: {} bytes", tape.total_size));
             log::print_message(&format!("State: {}", tape.state));
+            if !header.meta().is_empty() {
+                log::print_message(&format!("Meta: {}", String::from_utf8_lossy(header.meta())));
+            }
             log::print_message(&format!("{:?}", header));
             log::print_divider();
         }
+        Commands::Info { command: InfoCommands::Miners { limit } } => {
+            let mut miners = tapedrive::get_all_miner_accounts(&client).await?;
+            miners.sort_by(|a, b| b.1.total_rewards.cmp(&a.1.total_rewards).then(a.0.cmp(&b.0)));
+
+            log::print_section_header("Miners");
+            for (address, miner) in miners.iter().take(limit) {
+                log::print_message(&format!(
+                    "{} {}: {} proofs, {} earned, {} unclaimed",
+                    address,
+                    from_name(&miner.name),
+                    miner.total_proofs,
+                    log::tape_amount(miner.total_rewards),
+                    log::tape_amount(miner.unclaimed_rewards),
+                ));
+            }
+            log::print_divider();
+            log::print_count(&format!("Total Miners: {}", miners.len()));
+        }
+        Commands::Info { command: InfoCommands::Tapes { limit, recent: true, .. } } => {
+            let namespace = cluster_namespace(&client).await?;
+            let store = tape_network::store::secondary(&namespace)?;
+            let tapes = store.get_recently_seen(limit)?;
+
+            log::print_section_header("Recent Tapes");
+            if tapes.is_empty() {
+                log::print_info("No tapes seen yet, they are recorded while `tapedrive archive` runs");
+            }
+            for (address, seen) in tapes {
+                let number = match store.get_tape_number(&address) {
+                    Ok(number) => number.to_string(),
+                    Err(_) => "-".to_string(),
+                };
+                log::print_message(&format!("{} {}: first seen at slot {} ({})", number, address, seen.slot, seen.time));
+            }
+            log::print_divider();
+        }
+        Commands::Info { command: InfoCommands::Tapes { limit, offset, name_prefix, .. } } => {
+            let page = tapedrive::Page { offset, limit };
+            let tapes = match name_prefix {
+                Some(prefix) => {
+                    let prefix = tapedrive::normalize_name(&prefix);
+                    tapedrive::get_tape_accounts_by_name_prefix(&client, &prefix, page).await?
+                }
+                None => tapedrive::get_all_tape_accounts(&client, page).await?,
+            };
+
+            log::print_section_header("Tapes");
+            for (address, tape) in &tapes {
+                let number = match tape.number {
+                    0 => "-".to_string(),
+                    number => number.to_string(),
+                };
+                log::print_message(&format!(
+                    "{} {} {}: {} segments, {} bytes",
+                    number,
+                    address,
+                    from_name(&tape.name),
+                    tape.total_segments,
+                    tape.total_size,
+                ));
+            }
+            log::print_divider();
+        }
+        Commands::Info { command: InfoCommands::Balance { owner } } => {
+            let owner = match owner {
+                Some(owner) => owner.parse::<Pubkey>()
+                    .map_err(|e| anyhow::anyhow!("Invalid owner {}: {}", owner, e))?,
+                None => {
+                    let keypair_path = get_keypair_path(cli.keypair_path);
+                    get_payer(keypair_path, cli.mnemonic, cli.derivation_path.as_deref()).await?.pubkey()
+                }
+            };
+            let ata = tapedrive::tape_ata(&owner);
+
+            log::print_section_header("TAPE Balance");
+            log::print_message(&format!("Owner: {}", owner));
+            log::print_message(&format!("Token Account: {}", ata));
+            match tapedrive::get_token_balance(&client, &ata).await? {
+                Some(balance) => log::print_message(&format!("Balance: {}", log::tape_amount(balance))),
+                None => log::print_message("Balance: none, the token account doesn't exist yet"),
+            }
+            log::print_divider();
+        }
+        Commands::Info { command: InfoCommands::Treasury {} } => {
+            let treasury = tapedrive::get_treasury_info(&client).await?;
+            let (epoch, _) = tapedrive::get_epoch_account(&client).await?;
+
+            log::print_section_header("Treasury");
+            log::print_message(&format!("Token Account: {}", treasury.address));
+            log::print_message(&format!("Balance: {}", log::tape_amount(treasury.balance)));
+            log::print_message(&format!("Minted: {}", log::tape_amount(treasury.supply)));
+            log::print_message(&format!("Circulating: {}", log::tape_amount(treasury.circulating())));
+            log::print_message(&format!(
+                "Max Supply: {} ({:.2}% minted)",
+                log::tape_amount(MAX_SUPPLY),
+                treasury.supply as f64 / MAX_SUPPLY as f64 * 100.0
+            ));
+            log::print_message(&format!("Base Rate: {} per proof", log::tape_amount(epoch.base_rate)));
+            log::print_message(&format!("Target Rate: {} per minute", log::tape_amount(epoch.target_rate)));
+            log::print_divider();
+        }
+        Commands::Info { command: InfoCommands::Sync {} } => {
+            let namespace = cluster_namespace(&client).await?;
+            let store = tape_network::store::secondary(&namespace)?;
+            let Ok((last_processed_slot, drift)) = store.get_health() else {
+                log::print_info("The archive hasn't run yet, start it with `tapedrive archive`");
+                return Ok(());
+            };
+            let samples = store.get_health_samples()?;
+
+            log::print_section_header("Archive Sync");
+            log::print_message(&format!("Last Processed Slot: {}", last_processed_slot));
+            log::print_message(&format!("Behind Tip: {} slots", drift));
+            match SyncEstimate::from_samples(&samples) {
+                Some(estimate) => log::print_message(&format!("Progress: {}", estimate)),
+                None => log::print_message("Progress: not enough history yet, check again in a minute"),
+            }
+            log::print_divider();
+        }
         Commands::GetMiner { pubkey } => {
             let miner_address: Pubkey = pubkey.parse()?;
             let (miner, _) = tapedrive::get_miner_account(&client, &miner_address).await?;
@@ -67,13 +222,61 @@ pub async fn handle_misc_commands(cli: Cli, client: RpcClient) -> Result<()> {
             log::print_message(&format!("Name: {}", from_name(&miner.name)));
             log::print_message(&format!("Address: {}", miner_address));
             log::print_message(&format!("Owner: {}", miner.authority));
-            log::print_message(&format!("Unclaimed Rewards: {}", miner.unclaimed_rewards));
+            log::print_message(&format!("Unclaimed Rewards: {}", log::tape_amount(miner.unclaimed_rewards)));
+            if miner.locked_percent > 0 {
+                // The cluster clock decides, local time is close enough to show
+                let now = Utc::now().timestamp();
+                let locked = locked_rewards_at(&miner, now);
+                log::print_message(&format!(
+                    "Lockup: {}% of each reward, unlocking over {} epochs",
+                    miner.locked_percent, miner.vest_epochs
+                ));
+                log::print_message(&format!("Claimable Rewards: {}", log::tape_amount(claimable_rewards(&miner, now))));
+                log::print_message(&format!("Locked Rewards: {}", log::tape_amount(locked)));
+                if locked > 0 {
+                    let unlocked_at = DateTime::from_timestamp(miner.vest_end, 0)
+                        .map(|t| t.to_rfc3339())
+                        .unwrap_or_else(|| miner.vest_end.to_string());
+                    log::print_message(&format!("Fully Unlocked At: {}", unlocked_at));
+                }
+            }
+            if miner.deposit > 0 {
+                let refundable_at = miner.last_proof_at.saturating_add(MINER_DEPOSIT_COOLDOWN);
+                let refundable_at = DateTime::from_timestamp(refundable_at, 0)
+                    .map(|t| t.to_rfc3339())
+                    .unwrap_or_else(|| refundable_at.to_string());
+                log::print_message(&format!("Deposit: {}", log::tape_amount(miner.deposit)));
+                log::print_message(&format!("Deposit Refundable At: {}", refundable_at));
+            }
             log::print_message(&format!("Recall Tape: {}", miner.recall_tape));
             log::print_message(&format!("Multiplier: {}", miner.multiplier));
             log::print_message(&format!("Last Proof At: {}", miner.last_proof_at));
             log::print_message(&format!("Total Proofs: {}", miner.total_proofs));
             log::print_divider();
         }
+        Commands::GetRewards { pubkey, late } => {
+            let miner_address: Pubkey = pubkey.parse()?;
+            let (miner, _) = tapedrive::get_miner_account(&client, &miner_address).await?;
+            let (epoch, _) = tapedrive::get_epoch_account(&client).await?;
+
+            log::print_section_header("Reward Estimate");
+            log::print_message(&format!("Epoch Difficulty: {}", epoch.difficulty));
+            log::print_message(&format!("Multiplier: {}", miner.multiplier));
+            log::print_message(&format!("Late By: {}s", late.max(0)));
+            log::print_divider();
+
+            for difficulty in epoch.difficulty..epoch.difficulty + 8 {
+                let reward = tapedrive::mine::mine::estimate_rewards(&epoch, &miner, difficulty as u32, late);
+                log::print_message(&format!(
+                    "Difficulty {}: {}",
+                    difficulty,
+                    log::tape_amount(reward)
+                ));
+            }
+
+            log::print_divider();
+            log::print_info("Estimates are capped by the epoch's target rate, the spool paying out can cap them further");
+        }
         _ => {}
     }
     Ok(())
@@ -1,6 +1,17 @@
 pub mod admin;
 pub mod read;
+pub mod history;
+pub mod audit;
 pub mod write;
+pub mod submit;
+pub mod batch;
+pub mod sweep;
+pub mod backup;
 pub mod misc;
 pub mod network;
 pub mod claim;
+pub mod keys;
+pub mod snapshot;
+pub mod doctor;
+pub mod airdrop;
+pub mod dev;
@@ -1,23 +1,37 @@
 use anyhow::Result;
+use std::path::Path;
 use std::str::FromStr;
-use dialoguer::{theme::ColorfulTheme, Confirm};
 use solana_client::nonblocking::rpc_client::RpcClient;
-use solana_sdk::{signature::Keypair, signer::Signer, pubkey::Pubkey};
+use solana_sdk::{signer::Signer, pubkey::Pubkey};
 
 use tape_api::prelude::*;
-use tape_client::{register::register_miner, get_miner_account};
+use tape_client::{register::{register_miner, register_miner_with_lockup}, check_name, get_miner_account, program_context, PeerSource, SegmentSource};
+use tape_client::pool::{solve_job, PoolClient, POOL_TOKEN_ENV};
+use tokio::time::{sleep, Duration};
 use tape_network::{
+    admin::{AdminConfig, AdminQueue, ADMIN_TOKEN_ENV},
+    alerts::Alerter,
     archive::archive_loop,
+    compaction::{compaction_loop, CompactionPolicy, MinerActivity},
     mine::mine_loop,
     web::web_loop,
+    thumbnail::thumbnail_loop,
+    gc::gc_loop,
+    shared::SecondaryStoreHandle,
+    store::StoreOptions,
 };
 
 const DEVNET: &str = "https://devnet.tapedrive.io/api";
 
+/// How long a pool worker waits before asking an unreachable pool for work again.
+const POOL_RETRY_DELAY: Duration = Duration::from_secs(5);
+
 use crate::cli::{Cli, Commands};
+use crate::commands::airdrop::offer_airdrop;
+use crate::commands::snapshot::gc_policy;
 use crate::log;
 
-pub async fn handle_network_commands(cli: Cli, client: RpcClient, payer: Keypair) -> Result<()> {
+pub async fn handle_network_commands(cli: Cli, client: RpcClient, payer: Box<dyn Signer>) -> Result<()> {
 
     log::print_divider();
 
@@ -29,11 +43,23 @@ pub async fn handle_network_commands(cli: Cli, client: RpcClient, payer: Keypair
             log::print_info("Starting web RPC service...");
             log::print_message(format!("Listening on port {}", port).as_str());
 
-            let secondary_store = tape_network::store::secondary()?;
-            web_loop(secondary_store, port).await?;
+            let namespace = cluster_namespace(&client).await?;
+            let store = SecondaryStoreHandle::open(&namespace)?;
+            web_loop(store, client, port, admin_config(&namespace)).await?;
         }
 
-        Commands::Archive { starting_slot, trusted_peer } => {
+        Commands::Archive {
+            starting_slot,
+            trusted_peer,
+            thumbnails,
+            max_disk_bytes,
+            pin,
+            leaderboard,
+            prefilter,
+            compaction_rate,
+            idle_compaction,
+            alerts,
+        } => {
 
             // Use the public devnet peer if none is provided
             let trusted_peer = match client.url() {
@@ -45,11 +71,70 @@ pub async fn handle_network_commands(cli: Cli, client: RpcClient, payer: Keypair
 
             log::print_info("Starting archive service...");
 
-            let primary_store = tape_network::store::primary()?;
-            archive_loop(&primary_store, &client, starting_slot, trusted_peer).await?;
+            let namespace = cluster_namespace(&client).await?;
+            let options = StoreOptions {
+                rate_limit: compaction_rate,
+                manual_compaction: idle_compaction,
+            };
+            let primary_store = tape_network::store::primary_with_options(&namespace, &options)?;
+            let admin_queue = AdminQueue::open(&namespace)?;
+            let activity = MinerActivity::open(&namespace)?;
+            let alerts = alerter(alerts.as_deref())?;
+
+            let policy = match max_disk_bytes {
+                Some(max_disk_bytes) => Some(gc_policy(max_disk_bytes, &pin)?),
+                None => None,
+            };
+
+            if thumbnails {
+                log::print_message("Generating thumbnails for image tapes");
+            }
+            if leaderboard {
+                log::print_message("Indexing mine events for the leaderboard");
+            }
+            if prefilter {
+                log::print_message("Fetching full blocks only for slots with tape transactions");
+            }
+            if let Some(policy) = &policy {
+                log::print_message(&format!(
+                    "Keeping the store under {} bytes ({} pinned tapes)",
+                    policy.max_disk_bytes,
+                    policy.pinned.len()
+                ));
+            }
+            if let Some(rate) = compaction_rate {
+                log::print_message(&format!("Limiting flushes and compactions to {} bytes/s", rate));
+            }
+            if idle_compaction {
+                log::print_message("Compacting the store while the miner is idle");
+            }
+
+            tokio::try_join!(
+                archive_loop(&primary_store, &client, starting_slot, trusted_peer, leaderboard, prefilter, Some(&admin_queue), &alerts),
+                async {
+                    if thumbnails {
+                        thumbnail_loop(&primary_store, &client).await
+                    } else {
+                        Ok(())
+                    }
+                },
+                async {
+                    match &policy {
+                        Some(policy) => gc_loop(&primary_store, policy).await,
+                        None => Ok(()),
+                    }
+                },
+                async {
+                    if idle_compaction {
+                        compaction_loop(&primary_store, &activity, &CompactionPolicy::default()).await
+                    } else {
+                        Ok(())
+                    }
+                },
+            )?;
         }
 
-        Commands::Mine { pubkey, name } => {
+        Commands::Mine { pubkey, name, web_port, max_drift, alerts, peer, .. } => {
             log::print_info("Starting mining service...");
 
             // Either the user provided a pubkey or a name, but not both. A name is optional. If
@@ -62,11 +147,11 @@ pub async fn handle_network_commands(cli: Cli, client: RpcClient, payer: Keypair
                     miner_address
                 }
                 (_, Some(name)) => {
-                    let (miner_address, _) = miner_pda(payer.pubkey(), to_name(&name));
+                    let (miner_address, _) = program_context().miner_pda(payer.pubkey(), to_name(&name));
                     miner_address
                 }
                 _ => {
-                    let (miner_address, _) = miner_pda(payer.pubkey(), to_name("default"));
+                    let (miner_address, _) = program_context().miner_pda(payer.pubkey(), to_name("default"));
                     miner_address
                 }
             };
@@ -76,35 +161,56 @@ pub async fn handle_network_commands(cli: Cli, client: RpcClient, payer: Keypair
 
             log::print_message(&format!("Using miner address: {}", miner_address));
 
+            // Proofs and registration are paid by the signer
+            offer_airdrop(&client, payer.as_ref()).await;
+
             let miner_account = get_miner_account(&client, &miner_address).await;
             if miner_account.is_err() {
                 log::print_message("Miner not registered, registering now...");
                 let name = name.unwrap_or_else(|| "default".to_string());
-                register_miner(&client, &payer, &name).await?;
+                register_miner(&client, payer.as_ref(), &name).await?;
                 log::print_message("Miner registered successfully");
                 log::print_message(&format!("Name: {}", name));
             }
 
-            let secondary_store = tape_network::store::secondary()?;
-            mine_loop(&secondary_store, &client, &miner_address, &payer).await?;
+            let namespace = cluster_namespace(&client).await?;
+            let store = SecondaryStoreHandle::open(&namespace)?;
+            let activity = MinerActivity::open(&namespace)?;
+            let admin_queue = AdminQueue::open(&namespace)?;
+            let alerts = alerter(alerts.as_deref())?;
+            let peer = peer.as_deref().map(PeerSource::new);
+            let peer = peer.as_ref().map(|peer| peer as &dyn SegmentSource);
+
+            match web_port {
+                Some(port) => {
+                    // One secondary store for both, two handles on a directory don't catch up cleanly
+                    log::print_message(&format!("Serving the web RPC service on port {}", port));
+                    tokio::try_join!(
+                        mine_loop(&store, &client, &miner_address, payer.as_ref(), max_drift, &activity, &admin_queue, peer, &alerts),
+                        web_loop(store.clone(), RpcClient::new_with_commitment(client.url(), client.commitment()), port, admin_config(&namespace)),
+                    )?;
+                }
+                None => mine_loop(&store, &client, &miner_address, payer.as_ref(), max_drift, &activity, &admin_queue, peer, &alerts).await?,
+            }
         }
 
-        Commands::Register { name } => {
+        Commands::Register { name, locked_percent, vest_epochs } => {
             log::print_info("Registering miner...");
 
-            let (miner_address, _) = miner_pda(payer.pubkey(), to_name(&name));
+            let (miner_address, _) = program_context().miner_pda(payer.pubkey(), check_name(&name)?);
+            if locked_percent > 0 {
+                log::print_message(&format!(
+                    "Lockup: {}% of each reward, unlocking over {} epochs",
+                    locked_percent, vest_epochs
+                ));
+            }
 
-            let proceed = Confirm::with_theme(&ColorfulTheme::default())
-                .with_prompt("→ Are you sure?")
-                .default(false)
-                .interact()
-                .map_err(|e| anyhow::anyhow!("Failed to get user input: {}", e))?;
-            if !proceed {
+            if !log::confirm("→ Are you sure?", cli.yes)? {
                 log::print_error("Write operation cancelled");
                 return Ok(());
             }
 
-            register_miner(&client, &payer, &name).await?;
+            register_miner_with_lockup(&client, payer.as_ref(), &name, locked_percent, vest_epochs).await?;
 
             log::print_section_header("Miner Registered");
             log::print_message(&format!("Name: {}", name));
@@ -121,3 +227,72 @@ pub async fn handle_network_commands(cli: Cli, client: RpcClient, payer: Keypair
     Ok(())
 }
 
+/// Admin settings for a web node, enabled by setting the admin token in the environment.
+fn admin_config(namespace: &str) -> Option<AdminConfig> {
+    let admin = AdminConfig::from_env(namespace);
+    if admin.is_some() {
+        log::print_message(&format!("Admin methods enabled (token from {})", ADMIN_TOKEN_ENV));
+    }
+    admin
+}
+
+/// Alerts from the file at `path`, or none without one.
+fn alerter(path: Option<&Path>) -> Result<Alerter> {
+    let Some(path) = path else {
+        return Ok(Alerter::default());
+    };
+
+    let alerts = Alerter::load(path)?;
+    log::print_message(&format!("Sending alerts as configured in {}", path.display()));
+    Ok(alerts)
+}
+
+/// Mine as a worker of the pool at `url`: solve the nonces of each job it hands out and submit
+/// the shares found, credited to `worker`, who authenticates with the token in the environment.
+pub async fn pool_worker(url: &str, worker: &str) -> Result<()> {
+    log::print_divider();
+    log::print_info("Starting pool worker...");
+
+    let token = std::env::var(POOL_TOKEN_ENV)
+        .ok()
+        .filter(|token| !token.is_empty())
+        .ok_or_else(|| anyhow::anyhow!("Set {} to the token the pool gave worker {}", POOL_TOKEN_ENV, worker))?;
+    let pool = PoolClient::new(url, worker, &token)?;
+    log::print_message(&format!("Mining for pool {} as {}", pool.url(), pool.worker()));
+
+    loop {
+        let job = match pool.get_job().await {
+            Ok(job) => job,
+            Err(e) => {
+                log::print_error(&format!("Failed to get a job: {}", e));
+                sleep(POOL_RETRY_DELAY).await;
+                continue;
+            }
+        };
+
+        let shares = {
+            let job = job.clone();
+            tokio::task::spawn_blocking(move || solve_job(&job)).await?
+        };
+
+        for solution in shares {
+            match pool.submit_share(job.job, &solution).await {
+                Ok(total) => log::print_count(&format!(
+                    "Share of difficulty {} accepted, {} this round",
+                    solution.difficulty(), total
+                )),
+                Err(e) => log::print_error(&format!("Share rejected: {}", e)),
+            }
+        }
+    }
+}
+
+/// The local store namespace for the connected cluster, keyed by its genesis hash.
+pub async fn cluster_namespace(client: &RpcClient) -> Result<String> {
+    let genesis_hash = client
+        .get_genesis_hash()
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to fetch cluster genesis hash: {}", e))?;
+
+    Ok(genesis_hash.to_string())
+}
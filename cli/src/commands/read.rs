@@ -1,30 +1,47 @@
 use anyhow::Result;
 use solana_client::nonblocking::rpc_client::RpcClient;
-use solana_sdk::{signature::Signature, pubkey::Pubkey};
+use solana_sdk::pubkey::Pubkey;
 use std::{
     fs,
     io::{self, Write},
 };
 use std::str::FromStr;
-use indicatif::{ProgressBar, ProgressStyle};
+use indicatif::ProgressStyle;
 use tokio::{task, time::Duration};
 
 use crate::cli::{Cli, Commands};
+use crate::commands::network::cluster_namespace;
+use crate::keypair::{get_keypair_path, seal_keypair};
+use crate::keyring::get_key;
 use crate::log;
+use crate::progress;
 use tape_client::{
-    decode_tape, get_tape_account, read_linked_chunk, TapeHeader
+    decode_tape_partial, fetch_tape_opened_with, find_tape_account, get_tape_account, recover_segments,
+    PeerSource, RecoveredTape, SegmentSource, SourceChain, TapeHeader,
 };
+use tape_network::store::TapeStore;
 
 pub async fn handle_read_command(cli: Cli, client: RpcClient) -> Result<()> {
     match cli.command {
-        Commands::Read { tape, output } => {
-            let tape_address = Pubkey::from_str(&tape)
-                .map_err(|_| anyhow::anyhow!("Invalid tape address: {}", tape))?;
+        Commands::Read { tape, number, local, force, peer, peer_first, source_timeout, no_rpc_fallback, output, key } => {
+            let store = if local {
+                let namespace = cluster_namespace(&client).await?;
+                Some(tape_network::store::secondary(&namespace)?)
+            } else {
+                None
+            };
+
+            let tape_address = match (tape, number) {
+                (Some(tape), _) => Pubkey::from_str(&tape)
+                    .map_err(|_| anyhow::anyhow!("Invalid tape address: {}", tape))?,
+                (None, Some(number)) => resolve_tape_number(&client, store.as_ref(), number).await?,
+                (None, None) => anyhow::bail!("Either a tape address or --number is required"),
+            };
 
             log::print_message("Reading tape...");
             log::print_divider();
 
-            let pb = ProgressBar::new_spinner();
+            let pb = progress::new_spinner();
             pb.set_style(
                 ProgressStyle::default_spinner()
                     .template("{spinner:.green} {wide_msg}")
@@ -40,59 +57,57 @@ pub async fn handle_read_command(cli: Cli, client: RpcClient) -> Result<()> {
                 }
             });
 
-            // Fetch tape metadata
-            pb.set_message("Fetching tape metadata...");
-            let (tape, _) = get_tape_account(&client, &tape_address).await?;
-            let header = &TapeHeader::try_from_bytes(&tape.header)?;
-
-            // Read segments
-            pb.set_style(
-                ProgressStyle::default_bar()
-                    .template("{spinner:.green} [{bar:40.white/gray}] {pos}/{len} {wide_msg}")
-                    .expect("Failed to set progress style"),
-            );
-            pb.set_length(tape.total_segments);
-            pb.set_position(0);
-            pb.set_message("");
-
-            let empty_signature = Signature::default();
-            let mut current_signature = Signature::from(header.tail_signature);
-            let mut chunks = Vec::new();
-            let mut chunk_index = 0;
-
-            loop {
-                if current_signature.eq(&empty_signature) {
-                    break; // No more segments to read
-                }
-
-                let (data, prev_signature) =
-                    read_linked_chunk(&client, &current_signature).await?;
-
-                chunks.push(data.clone());
-                chunk_index += (data.len() + 64) / 128;
-
-                pb.set_position(chunk_index as u64);
-                current_signature = prev_signature;
+            // Read from the local store and the peer if asked, in the order asked, then the chain
+            let peer = peer.as_deref().map(PeerSource::new);
+            let timeout = Duration::from_secs(source_timeout);
+            let mut sources = vec![
+                store.as_ref().map(|s| s as &dyn SegmentSource),
+                peer.as_ref().map(|p| p as &dyn SegmentSource),
+            ];
+            if peer_first {
+                sources.reverse();
             }
+            let chain = sources
+                .into_iter()
+                .flatten()
+                .fold(SourceChain::new(), |chain, source| chain.with_source(source, timeout))
+                .with_rpc_fallback(!no_rpc_fallback);
+            let source = (!chain.is_empty()).then_some(&chain as &dyn SegmentSource);
+
+            // Sealed tapes open with the key asked for, else the keypair's if there's one on disk
+            let seal_key = match &key {
+                Some(name) => Some(get_key(name)?.unlock(name)?),
+                None => seal_keypair(&get_keypair_path(cli.keypair_path.clone())).ok(),
+            };
 
-            chunks.reverse();
-            let data: Vec<u8> = chunks.into_iter().flatten().collect();
-
-            // Process data
-            pb.set_style(
-                ProgressStyle::default_spinner()
-                    .template("{spinner:.green} {wide_msg}")
-                    .expect("Failed to set progress style"),
-            );
-            pb.set_message("Verifying and decompressing data...");
-
-            let result = decode_tape(data, &header)?;
+            pb.set_message("Fetching tape metadata...");
+            let (result, mime) = match &store {
+                Some(store) if force => {
+                    let (recovered, tape) = recover_local_tape(&client, store, &tape_address).await?;
+                    pb.finish_with_message("");
+                    log::print_divider();
+                    print_recovery_report(&recovered);
+                    (decode_tape_partial(recovered.bytes, &tape)?, tape.mime())
+                }
+                _ => {
+                    let tape = fetch_tape_opened_with(&client, &tape_address, source, seal_key.as_ref(), |read, total| {
+                        if pb.length() != Some(total) {
+                            pb.set_style(progress::read_style());
+                            pb.set_length(total);
+                            pb.set_message("");
+                        }
+                        pb.set_position(read);
+                    }).await?;
+                    (tape.bytes, tape.mime)
+                }
+            };
 
             pb.finish_with_message("");
             log::print_divider();
             if cli.verbose {
                 log::print_section_header("Metadata");
                 log::print_count(&format!("Size: {} bytes", result.len()));
+                log::print_count(&format!("MIME Type: {}", mime));
                 log::print_divider();
             }
 
@@ -113,3 +128,58 @@ pub async fn handle_read_command(cli: Cli, client: RpcClient) -> Result<()> {
     }
     Ok(())
 }
+
+/// Look up a tape address by its number, either in the local store or on-chain.
+async fn resolve_tape_number(
+    client: &RpcClient,
+    store: Option<&TapeStore>,
+    number: u64,
+) -> Result<Pubkey> {
+    if let Some(store) = store {
+        let address = store
+            .get_tape_address(number)
+            .map_err(|e| anyhow::anyhow!("Tape {} not found in the local store: {}", number, e))?;
+        return Ok(address);
+    }
+
+    match find_tape_account(client, number).await? {
+        Some((address, _)) => Ok(address),
+        None => anyhow::bail!("Tape {} not found", number),
+    }
+}
+
+/// Reassemble whatever segments of a tape the local store holds, tolerating gaps.
+async fn recover_local_tape(
+    client: &RpcClient,
+    store: &TapeStore,
+    tape_address: &Pubkey,
+) -> Result<(RecoveredTape, TapeHeader)> {
+    let (tape, _) = get_tape_account(client, tape_address).await?;
+    let header = *TapeHeader::try_from_bytes(&tape.header)?;
+    let segments = store.get_tape_segments(tape_address)?;
+
+    Ok((recover_segments(segments, &header, tape.total_segments), header))
+}
+
+fn print_recovery_report(recovered: &RecoveredTape) {
+    if recovered.is_complete() {
+        log::print_message("All segments present");
+        return;
+    }
+
+    for range in &recovered.missing_ranges {
+        log::print_error(&format!(
+            "Missing segments {}..{} ({} segments, zero-filled)",
+            range.start, range.end, range.end - range.start
+        ));
+    }
+
+    for segment_number in &recovered.duplicate_conflicts {
+        log::print_error(&format!(
+            "Segment {} has conflicting copies, kept the first",
+            segment_number
+        ));
+    }
+
+    log::print_divider();
+}
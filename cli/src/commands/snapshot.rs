@@ -0,0 +1,660 @@
+use anyhow::{anyhow, bail, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{hash::Hash, pubkey::Pubkey};
+
+use tape_api::prelude::*;
+use tape_client::{
+    decode_tape, fetch_tape_with, get_tape_account, unprefix_segments, PeerSource, SegmentSource,
+    TapeHeader, TapeManifest,
+};
+use tape_network::archive::{repair_gaps, resync_tape};
+use tape_network::gc::{apply_gc, plan_gc, GcPolicy};
+use tape_network::snapshot::{check_snapshot, open_snapshot, tape_root};
+use tape_network::store::TapeStore;
+
+use crate::cli::{Cli, Commands, SnapshotCommands};
+use crate::commands::network::cluster_namespace;
+use crate::log;
+
+pub async fn handle_snapshot_commands(cli: Cli, client: RpcClient) -> Result<()> {
+    let Commands::Snapshot { command } = cli.command else {
+        return Ok(());
+    };
+
+    match command {
+        SnapshotCommands::Gc { max_disk_bytes, pin, dry_run } => {
+            let policy = gc_policy(max_disk_bytes, &pin)?;
+
+            // Opening the primary store fails while an archiver holds the lock
+            let namespace = cluster_namespace(&client).await?;
+            let store = tape_network::store::primary(&namespace)?;
+            let plan = plan_gc(&store, &policy)?;
+
+            log::print_section_header("Garbage Collection");
+            log::print_message(&format!("Stored: {} bytes", plan.total_bytes));
+            log::print_message(&format!("Limit: {} bytes", policy.max_disk_bytes));
+            log::print_message(&format!("Pinned: {} tapes", policy.pinned.len()));
+
+            if plan.evict.is_empty() {
+                log::print_divider();
+                log::print_info("Nothing to collect");
+                return Ok(());
+            }
+
+            log::print_divider();
+            for candidate in &plan.evict {
+                let last_access = candidate
+                    .last_access
+                    .map(|t| t.to_string())
+                    .unwrap_or_else(|| "never".to_string());
+
+                log::print_message(&format!(
+                    "{} ({} bytes, last access: {})",
+                    candidate.address, candidate.bytes, last_access
+                ));
+            }
+            log::print_divider();
+
+            if dry_run {
+                log::print_info(&format!(
+                    "Dry run: would evict {} tapes, leaving {} bytes",
+                    plan.evict.len(),
+                    plan.remaining_bytes
+                ));
+                return Ok(());
+            }
+
+            apply_gc(&store, &plan)?;
+
+            log::print_info(&format!(
+                "Evicted {} tapes, {} bytes remaining",
+                plan.evict.len(),
+                plan.remaining_bytes
+            ));
+        }
+
+        SnapshotCommands::ExportFs { dir, incremental } => {
+            let namespace = cluster_namespace(&client).await?;
+            let store = tape_network::store::secondary(&namespace)?;
+
+            log::print_section_header("Export");
+            log::print_message(&format!("Directory: {}", dir.display()));
+
+            let exported = export_fs(&client, &store, &dir, incremental).await?;
+
+            log::print_divider();
+            log::print_info(&format!("Exported {} tapes", exported));
+        }
+
+        SnapshotCommands::Stats { detailed } => {
+            // Works with or without an archiver running, it holds the primary's lock
+            let namespace = cluster_namespace(&client).await?;
+            let store = tape_network::store::read_only(&namespace)?;
+
+            let sizes = store.get_tape_sizes()?;
+            let bytes: u64 = sizes.values().sum();
+            let gaps = store.get_gaps(usize::MAX)?;
+
+            log::print_section_header("Store");
+            log::print_message(&format!("Namespace: {}", namespace));
+            log::print_count(&format!("Tapes With Data: {}", sizes.len()));
+            log::print_count(&format!("Segment Bytes: {}", bytes));
+            log::print_count(&format!("Gaps: {}", gaps.len()));
+
+            match store.get_health() {
+                Ok((last_processed_slot, drift)) => log::print_message(&format!(
+                    "Last Processed Slot: {} ({} slots behind)",
+                    last_processed_slot, drift
+                )),
+                Err(_) => log::print_message("Last Processed Slot: none yet"),
+            }
+            if let Some((state, failures)) = store.get_rpc_status()? {
+                log::print_message(&format!("RPC: {} ({} consecutive failures)", state, failures));
+            }
+
+            if detailed {
+                let labels = store.get_labels()?;
+
+                log::print_section_header("Labels");
+                for (address, label) in &labels {
+                    let number = store
+                        .get_tape_number(address)
+                        .map(|n| format!("#{}", n))
+                        .unwrap_or_else(|_| "not archived".to_string());
+                    log::print_message(&format!("{} ({}): {}", address, number, label.label));
+                    if !label.note.is_empty() {
+                        log::print_info(&format!("    {}", label.note));
+                    }
+                }
+                log::print_count(&format!("Labelled Tapes: {}", labels.len()));
+            }
+            log::print_divider();
+        }
+
+        SnapshotCommands::Gaps { repair, limit } => {
+            let namespace = cluster_namespace(&client).await?;
+
+            if repair {
+                // Opening the primary store fails while an archiver holds the lock
+                let store = tape_network::store::primary(&namespace)?;
+
+                log::print_message("Retrying gap slots...");
+                let (repaired, failed) = repair_gaps(&store, &client, limit, false).await?;
+
+                log::print_divider();
+                log::print_info(&format!("Repaired {} gaps, {} still failing", repaired, failed));
+                return Ok(());
+            }
+
+            let store = tape_network::store::secondary(&namespace)?;
+            let gaps = store.get_gaps(limit)?;
+
+            log::print_section_header("Gaps");
+            if gaps.is_empty() {
+                log::print_info("No gaps, every block with tape data was archived");
+                return Ok(());
+            }
+
+            for gap in &gaps {
+                log::print_message(&format!(
+                    "Slot {} ({} attempts): {}",
+                    gap.slot, gap.attempts, gap.error
+                ));
+            }
+
+            log::print_divider();
+            log::print_count(&format!("Gaps: {}", gaps.len()));
+            log::print_info("Run `tapedrive snapshot gaps --repair` to retry them");
+        }
+
+        SnapshotCommands::Verify { path, sample, all } => {
+            let (manifest, store) = open_snapshot(&path)?;
+
+            log::print_section_header("Snapshot");
+            log::print_message(&format!("Path: {}", path.display()));
+            log::print_message(&format!("Namespace: {}", manifest.namespace));
+            log::print_message(&format!("Store Format: {}", manifest.format_version));
+            log::print_message(&format!("Created At: {}", manifest.created_at));
+            if let Some(slot) = manifest.last_processed_slot {
+                log::print_message(&format!("Last Processed Slot: {}", slot));
+            }
+            log::print_count(&format!("Tapes: {}", manifest.counts.tapes));
+            log::print_count(&format!("Segments: {}", manifest.counts.segments));
+            log::print_count(&format!("Segment Bytes: {}", manifest.counts.segment_bytes));
+            log::print_count(&format!("Expected Disk Bytes: {}", manifest.disk_bytes));
+            log::print_divider();
+
+            let mut problems = check_snapshot(&path, &manifest, &store)?;
+
+            // Roots are checked against the chain, which has to be the one the snapshot is of
+            let namespace = cluster_namespace(&client).await?;
+            if namespace != manifest.namespace {
+                bail!(
+                    "Snapshot is of namespace {}, the RPC serves {}, roots can't be checked",
+                    manifest.namespace, namespace
+                );
+            }
+
+            let tapes = store.iter_tapes()?.collect::<Result<Vec<_>, _>>()?;
+            let picked = if all { tapes } else { spread(tapes, sample) };
+
+            log::print_message(&format!("Checking the Merkle roots of {} tapes...", picked.len()));
+            let mut checked = 0;
+            for (number, address) in picked {
+                match verify_root(&client, &store, &address).await {
+                    Ok(RootCheck::Matches) => checked += 1,
+                    Ok(RootCheck::Skipped(reason)) => {
+                        log::print_message(&format!("Tape {} skipped: {}", number, reason));
+                    }
+                    Err(e) => problems.push(format!("Tape {} ({}): {}", number, address, e)),
+                }
+            }
+            log::print_divider();
+
+            if !problems.is_empty() {
+                for problem in &problems {
+                    log::print_error(problem);
+                }
+                bail!("Snapshot failed verification with {} problems", problems.len());
+            }
+
+            log::print_info(&format!("Snapshot is intact, {} tape roots match the chain", checked));
+        }
+
+        SnapshotCommands::Compare { peer } => {
+            let namespace = cluster_namespace(&client).await?;
+            let store = tape_network::store::read_only(&namespace)?;
+            let http = reqwest::Client::new();
+
+            log::print_section_header("Compare");
+            log::print_message(&format!("Peer: {}", peer));
+
+            let mut compared = 0;
+            let mut divergences = Vec::new();
+            let mut last_number = 0;
+
+            for item in store.iter_tapes()? {
+                let (number, address) = item?;
+                last_number = number;
+                compared += 1;
+
+                match compare_tape(&client, &http, &peer, &store, number, &address).await {
+                    Ok(None) => {}
+                    Ok(Some(divergence)) => {
+                        log::print_error(&format!("Tape {} ({}): {}", number, address, divergence));
+                        divergences.push(number);
+                    }
+                    Err(e) => log::print_error(&format!("Tape {} skipped: {}", number, e)),
+                }
+            }
+
+            // The peer may be ahead, the loop above only sees tapes this node knows about
+            if peer_call(&http, &peer, "getTapeAddress", json!({ "tape_number": last_number + 1 })).await.is_ok() {
+                log::print_error(&format!("Peer holds tapes past {}, the last one here", last_number));
+                divergences.push(last_number + 1);
+            }
+
+            log::print_divider();
+            if !divergences.is_empty() {
+                bail!("{} of {} tapes diverge from the peer", divergences.len(), compared);
+            }
+
+            log::print_info(&format!("All {} tapes agree with the peer", compared));
+        }
+
+        SnapshotCommands::Scrub { peer, dry_run } => {
+            let namespace = cluster_namespace(&client).await?;
+            // Repairs write to the store, which fails while an archiver holds the lock
+            let store = if dry_run {
+                tape_network::store::read_only(&namespace)?
+            } else {
+                tape_network::store::primary(&namespace)?
+            };
+
+            log::print_section_header("Scrub");
+            log::print_message("Checking every segment against its checksum...");
+            let report = store.scrub()?;
+
+            log::print_count(&format!("Intact: {}", report.checked));
+            log::print_count(&format!("Without Checksum: {}", report.unchecked));
+            log::print_count(&format!("Corrupt: {}", report.corrupt.len()));
+            log::print_divider();
+
+            if report.corrupt.is_empty() {
+                log::print_info("No corrupt segments");
+                return Ok(());
+            }
+
+            let mut by_tape = BTreeMap::<Pubkey, Vec<u64>>::new();
+            for (address, segment_number) in &report.corrupt {
+                log::print_error(&format!("Tape {} segment {}", address, segment_number));
+                by_tape.entry(*address).or_default().push(*segment_number);
+            }
+            log::print_divider();
+
+            if dry_run {
+                bail!(
+                    "{} segments are corrupt, run `tapedrive snapshot scrub` with the archive stopped to re-fetch them",
+                    report.corrupt.len()
+                );
+            }
+
+            let peer = peer.as_deref().map(PeerSource::new);
+            let mut failed = 0;
+            for (address, segments) in &by_tape {
+                match refetch_segments(&client, &store, peer.as_ref(), address, segments).await {
+                    Ok(source) => log::print_message(&format!(
+                        "Tape {}: re-fetched {} segments from {}",
+                        address, segments.len(), source
+                    )),
+                    Err(e) => {
+                        log::print_error(&format!("Tape {}: {}", address, e));
+                        failed += segments.len();
+                    }
+                }
+            }
+            log::print_divider();
+
+            if failed > 0 {
+                bail!("{} of {} corrupt segments couldn't be re-fetched", failed, report.corrupt.len());
+            }
+            log::print_info(&format!("Re-fetched {} corrupt segments", report.corrupt.len()));
+        }
+    }
+
+    Ok(())
+}
+
+/// Replace the corrupt `segments` of a tape, from `peer` if it holds all of them and otherwise
+/// by resyncing the tape from the chain. Returns where they came from.
+async fn refetch_segments(
+    client: &RpcClient,
+    store: &TapeStore,
+    peer: Option<&PeerSource>,
+    address: &Pubkey,
+    segments: &[u64],
+) -> Result<String> {
+    // Dropped first, so a resync stores them again whatever slot they were last written at
+    for &segment_number in segments {
+        store.drop_segment(address, segment_number)?;
+    }
+
+    if let Some(peer) = peer {
+        match peer.read_segments(address).await {
+            Ok(held) => {
+                let wanted = held
+                    .into_iter()
+                    .filter(|(number, _)| segments.contains(number))
+                    .collect::<Vec<_>>();
+                if wanted.len() == segments.len() {
+                    for (segment_number, data) in wanted {
+                        store.add_segment(address, segment_number, data)?;
+                    }
+                    return Ok(peer.name());
+                }
+                log::print_message(&format!(
+                    "Tape {}: {} only holds {} of {} corrupt segments, resyncing from the chain",
+                    address, peer.name(), wanted.len(), segments.len()
+                ));
+            }
+            Err(e) => log::print_message(&format!(
+                "Tape {}: {} failed ({}), resyncing from the chain",
+                address, peer.name(), e
+            )),
+        }
+    }
+
+    let slots = resync_tape(store, client, address).await?;
+    for &segment_number in segments {
+        store
+            .get_segment(address, segment_number)
+            .map_err(|e| anyhow!("Segment {} wasn't restored by the resync: {}", segment_number, e))?;
+    }
+
+    Ok(format!("the chain ({} slots)", slots))
+}
+
+/// Outcome of checking one tape of a snapshot against the chain.
+enum RootCheck {
+    Matches,
+    /// Not comparable, e.g. the tape is still being written.
+    Skipped(String),
+}
+
+/// Rebuild the root of a tape from a snapshot and compare it with the tape account. Fails on a
+/// root that doesn't match.
+async fn verify_root(client: &RpcClient, store: &TapeStore, address: &Pubkey) -> Result<RootCheck> {
+    let (tape, _) = get_tape_account(client, address).await?;
+    if tape.state != u64::from(TapeState::Finalized) {
+        return Ok(RootCheck::Skipped("not finalized".to_string()));
+    }
+
+    let (root, segments) = tape_root(store, address, &tape.merkle_seed)?;
+    if (segments as u64) < tape.total_segments {
+        // Evicted by garbage collection, or never fully archived
+        return Ok(RootCheck::Skipped(format!(
+            "holds {} of {} segments",
+            segments, tape.total_segments
+        )));
+    }
+
+    if root != tape.merkle_root {
+        bail!("Merkle root doesn't match the tape account");
+    }
+
+    Ok(RootCheck::Matches)
+}
+
+/// Compare one tape with a peer: its number, how many segments each node holds, and the Merkle
+/// root rebuilt from them. Returns how they diverge, if they do.
+async fn compare_tape(
+    client: &RpcClient,
+    http: &reqwest::Client,
+    peer: &str,
+    store: &TapeStore,
+    number: u64,
+    address: &Pubkey,
+) -> Result<Option<String>> {
+    let peer_address = match peer_call(http, peer, "getTapeAddress", json!({ "tape_number": number })).await {
+        Ok(value) => value.as_str().unwrap_or_default().to_string(),
+        Err(e) => return Ok(Some(format!("missing on the peer ({})", e))),
+    };
+    if peer_address != address.to_string() {
+        return Ok(Some(format!("the peer has tape {} at {}", number, peer_address)));
+    }
+
+    // Both sides build the tree from the seed on the tape account
+    let (tape, _) = get_tape_account(client, address).await?;
+    let (root, segments) = tape_root(store, address, &tape.merkle_seed)?;
+
+    let params = json!({
+        "tape_address": address.to_string(),
+        "merkle_seed": Hash::new_from_array(tape.merkle_seed).to_string(),
+    });
+    let remote = peer_call(http, peer, "getTapeRoot", params).await?;
+    let peer_segments = remote["segments"]
+        .as_u64()
+        .ok_or_else(|| anyhow!("Invalid getTapeRoot response: {}", remote))?;
+    let peer_root = remote["root"]
+        .as_str()
+        .and_then(|root| Hash::from_str(root).ok())
+        .ok_or_else(|| anyhow!("Invalid getTapeRoot response: {}", remote))?;
+
+    if peer_segments != segments as u64 {
+        return Ok(Some(format!("{} segments here, {} on the peer", segments, peer_segments)));
+    }
+
+    if peer_root.to_bytes() != root {
+        // Say which side has it right, if the tape is complete enough to tell
+        let local_ok = root == tape.merkle_root && segments as u64 == tape.total_segments;
+        let side = if local_ok { ", the peer's doesn't match the chain" } else { "" };
+        return Ok(Some(format!("Merkle roots differ{}", side)));
+    }
+
+    Ok(None)
+}
+
+/// Call a JSON-RPC method on another node's web service and return its result.
+async fn peer_call(http: &reqwest::Client, peer: &str, method: &str, params: Value) -> Result<Value> {
+    let response = http
+        .post(peer)
+        .header("Content-Type", "application/json")
+        .body(json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params }).to_string())
+        .send()
+        .await
+        .map_err(|e| anyhow!("{} failed on the peer: {}", method, e))?
+        .json::<Value>()
+        .await
+        .map_err(|e| anyhow!("Invalid {} response from the peer: {}", method, e))?;
+
+    if let Some(error) = response.get("error") {
+        bail!("{} failed on the peer: {}", method, error["message"].as_str().unwrap_or("unknown error"));
+    }
+
+    response
+        .get("result")
+        .cloned()
+        .ok_or_else(|| anyhow!("Invalid {} response from the peer: {}", method, response))
+}
+
+/// Up to `count` items spread evenly across `items`, always including the first.
+fn spread<T>(items: Vec<T>, count: usize) -> Vec<T> {
+    if count == 0 {
+        return Vec::new();
+    }
+
+    let step = items.len().div_ceil(count).max(1);
+    items.into_iter().step_by(step).collect()
+}
+
+/// One exported tape in `index.json`.
+#[derive(Serialize, Deserialize)]
+struct ExportEntry {
+    number: u64,
+    address: String,
+    name: String,
+    mime: String,
+    size: usize,
+    path: String,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct ExportIndex {
+    tapes: Vec<ExportEntry>,
+}
+
+/// A finalized tape in the local store, with its on-chain metadata.
+struct ExportTape {
+    number: u64,
+    address: Pubkey,
+    tape: Tape,
+    header: TapeHeader,
+}
+
+/// Write every finalized tape in the store to `dir` as a decoded file, grouped in
+/// sub-directories of 1000 tapes, and list them in `dir/index.json`. Manifest tapes are exported
+/// as the reassembled payload, their parts are skipped. With `incremental`, tapes already in the
+/// index are left alone. Returns the number of tapes exported.
+async fn export_fs(
+    client: &RpcClient,
+    store: &TapeStore,
+    dir: &Path,
+    incremental: bool,
+) -> Result<usize> {
+    let index_path = dir.join("index.json");
+    let mut index = if incremental && index_path.exists() {
+        let data = fs::read_to_string(&index_path)
+            .map_err(|e| anyhow!("Failed to read {}: {}", index_path.display(), e))?;
+        serde_json::from_str(&data)
+            .map_err(|e| anyhow!("Failed to parse {}: {}", index_path.display(), e))?
+    } else {
+        ExportIndex::default()
+    };
+
+    let done: HashSet<u64> = index.tapes.iter().map(|t| t.number).collect();
+
+    // Oldest first, so manifests come after their parts
+    let numbered = store.iter_tapes()?.collect::<Result<Vec<_>, _>>()?;
+
+    let mut tapes = Vec::new();
+    let mut parts = HashSet::new();
+
+    for (number, address) in numbered {
+        if done.contains(&number) {
+            continue;
+        }
+
+        let (tape, _) = match get_tape_account(client, &address).await {
+            Ok(tape) => tape,
+            Err(e) => {
+                log::print_error(&format!("Skipping tape {}: {}", number, e));
+                continue;
+            }
+        };
+
+        if tape.state != u64::from(TapeState::Finalized) {
+            continue;
+        }
+
+        let header = *TapeHeader::try_from_bytes(&tape.header)?;
+        if TapeManifest::is_manifest(&header) {
+            match decode_local(store, &address, &tape, &header) {
+                Ok(bytes) => parts.extend(TapeManifest::try_from_bytes(&bytes)?.parts.iter().map(|p| p.address)),
+                Err(e) => {
+                    log::print_error(&format!("Skipping manifest {}: {}", number, e));
+                    continue;
+                }
+            }
+        }
+
+        tapes.push(ExportTape { number, address, tape, header });
+    }
+
+    let mut exported = 0;
+    for export in tapes.iter().filter(|t| !parts.contains(&t.address)) {
+        let decoded = if TapeManifest::is_manifest(&export.header) {
+            fetch_tape_with(client, &export.address, Some(store as &dyn SegmentSource), |_, _| {})
+                .await
+                .map(|tape| (tape.bytes, tape.mime))
+        } else {
+            decode_local(store, &export.address, &export.tape, &export.header)
+                .map(|bytes| (bytes, export.header.mime()))
+        };
+
+        let (bytes, mime) = match decoded {
+            Ok(decoded) => decoded,
+            Err(e) => {
+                log::print_error(&format!("Skipping tape {}: {}", export.number, e));
+                continue;
+            }
+        };
+
+        let name = file_stem(&export.tape.name);
+        let path = PathBuf::from(format!("{:03}", export.number / 1000))
+            .join(format!("{}_{}.{}", export.number, name, get_extension(&mime)));
+
+        let full_path = dir.join(&path);
+        if let Some(parent) = full_path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| anyhow!("Failed to create {}: {}", parent.display(), e))?;
+        }
+        fs::write(&full_path, &bytes)
+            .map_err(|e| anyhow!("Failed to write {}: {}", full_path.display(), e))?;
+
+        log::print_message(&format!("{} -> {}", export.address, path.display()));
+
+        index.tapes.push(ExportEntry {
+            number: export.number,
+            address: export.address.to_string(),
+            name,
+            mime,
+            size: bytes.len(),
+            path: path.to_string_lossy().into_owned(),
+        });
+        exported += 1;
+    }
+
+    index.tapes.sort_by_key(|t| t.number);
+    fs::create_dir_all(dir)
+        .map_err(|e| anyhow!("Failed to create {}: {}", dir.display(), e))?;
+    let json = serde_json::to_string_pretty(&index)
+        .map_err(|e| anyhow!("Failed to serialize index: {}", e))?;
+    fs::write(&index_path, json)
+        .map_err(|e| anyhow!("Failed to write {}: {}", index_path.display(), e))?;
+
+    Ok(exported)
+}
+
+/// Decode a single tape from the segments held in the local store.
+fn decode_local(
+    store: &TapeStore,
+    address: &Pubkey,
+    tape: &Tape,
+    header: &TapeHeader,
+) -> Result<Vec<u8>> {
+    let segments = store.get_tape_segments(address)?;
+    if (segments.len() as u64) < tape.total_segments {
+        bail!(
+            "Local store only holds {} of {} segments",
+            segments.len(), tape.total_segments
+        );
+    }
+
+    decode_tape(unprefix_segments(segments, header)?, header)
+}
+
+/// Build a GC policy from command line arguments.
+pub fn gc_policy(max_disk_bytes: u64, pin: &[String]) -> Result<GcPolicy> {
+    let pinned = pin
+        .iter()
+        .map(|p| Pubkey::from_str(p).map_err(|e| anyhow!("Invalid pinned tape {}: {}", p, e)))
+        .collect::<Result<HashSet<_>>>()?;
+
+    Ok(GcPolicy { max_disk_bytes, pinned })
+}
@@ -0,0 +1,52 @@
+use anyhow::{anyhow, Result};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use std::path::PathBuf;
+
+use crate::cli::{Cli, Commands};
+use crate::keypair::get_payer;
+use crate::log;
+use tape_client::{submit_bundle, TapeBundle};
+
+pub async fn handle_submit_command(cli: Cli, client: RpcClient, keypair_path: PathBuf) -> Result<()> {
+    let Commands::Submit { bundle: path, from } = cli.command else {
+        return Ok(());
+    };
+
+    let json = std::fs::read_to_string(&path)
+        .map_err(|e| anyhow!("Failed to read {}: {}", path.display(), e))?;
+    let bundle = TapeBundle::from_json(&json)?;
+    let total = bundle.transactions.len();
+
+    log::print_section_header("Submit Bundle");
+    log::print_count(&format!("Tape Address: {}", bundle.tape_address));
+    log::print_count(&format!("Transactions: {}", total));
+    log::print_divider();
+
+    // Signed bundles are ready to send, only unsigned ones need the keypair
+    let payer = if bundle.signed {
+        None
+    } else {
+        log::print_message(&format!("Using keypair from {}", keypair_path.display()));
+        Some(get_payer(keypair_path, cli.mnemonic, cli.derivation_path.as_deref()).await?)
+    };
+
+    let mut sent = from;
+    let result = submit_bundle(&client, &bundle, payer.as_deref(), from, |i| {
+        sent = i + 1;
+        log::print_message(&format!("Sent {}/{}", sent, total));
+    }).await;
+
+    if let Err(e) = result {
+        log::print_error(&format!("{}", e));
+        log::print_info("To pick up where it stopped, run:");
+        log::print_title(&format!("tapedrive submit {} --from {}", path.display(), sent));
+        return Err(e);
+    }
+
+    log::print_divider();
+    log::print_info("To read the tape, run:");
+    log::print_title(&format!("tapedrive read {}", bundle.tape_address));
+    log::print_divider();
+
+    Ok(())
+}
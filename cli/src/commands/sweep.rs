@@ -0,0 +1,85 @@
+use anyhow::Result;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{native_token::lamports_to_sol, signer::Signer};
+use tape_api::prelude::*;
+use tape_client::{
+    fetch_tape_history, finalize_tape, get_unfinalized_tapes, TapeFlags, TapeHeader,
+    UnfinalizedTape,
+};
+
+use crate::cli::{Cli, Commands};
+use crate::log;
+
+pub async fn handle_sweep_writers_command(cli: Cli, client: RpcClient, payer: Box<dyn Signer>) -> Result<()> {
+    let Commands::SweepWriters {} = cli.command else {
+        return Ok(());
+    };
+
+    log::print_info("Looking for writers of unfinalized tapes...");
+    let mut open = get_unfinalized_tapes(&client, &payer.pubkey()).await?;
+    open.sort_by_key(|t| from_name(&t.tape.name));
+
+    log::print_section_header("Open Writers");
+    for t in &open {
+        log::print_message(&format!(
+            "{} {}: {} segments, {} SOL in its writer",
+            t.tape_address,
+            from_name(&t.tape.name),
+            t.tape.total_segments,
+            lamports_to_sol(t.writer_lamports),
+        ));
+    }
+    let held: u64 = open.iter().map(|t| t.writer_lamports).sum();
+    log::print_divider();
+    log::print_count(&format!("Open Writers: {} holding {} SOL", open.len(), lamports_to_sol(held)));
+
+    let mut reclaimed = 0;
+    for t in &open {
+        if let Some(lamports) = sweep(&client, payer.as_ref(), t, cli.yes).await? {
+            reclaimed += lamports;
+        }
+    }
+
+    log::print_divider();
+    log::print_count(&format!("Reclaimed: {} SOL", lamports_to_sol(reclaimed)));
+    Ok(())
+}
+
+/// Offer to finalize one tape, returning the rent reclaimed if it was.
+async fn sweep(client: &RpcClient, payer: &dyn Signer, t: &UnfinalizedTape, yes: bool) -> Result<Option<u64>> {
+    let name = from_name(&t.tape.name);
+    log::print_section_header(&name);
+
+    // The program only finalizes tapes with something on them
+    if t.tape.state != u64::from(TapeState::Writing) {
+        log::print_message("Nothing was written, skipping");
+        return Ok(None);
+    }
+
+    let mut header = *TapeHeader::try_from_bytes(&t.tape.header)?;
+    let linked = header.flags & u8::from(TapeFlags::Linked) != 0;
+
+    let history = fetch_tape_history(client, &t.tape_address).await?;
+    let Some(tail) = history.tail(linked) else {
+        log::print_error("Its writes don't link back to the first one, some are missing. Write it again instead");
+        return Ok(None);
+    };
+
+    log::print_message(&format!("Segments: {}", t.tape.total_segments));
+    log::print_message(&format!("Size: {} bytes", t.tape.total_size));
+    log::print_message(&format!("Tail: {}", tail));
+    if !linked {
+        log::print_message("Unlinked, check the segments above are all of it");
+    }
+
+    let prompt = format!("→ Finalize {} and reclaim {} SOL?", name, lamports_to_sol(t.writer_lamports));
+    if !log::confirm(&prompt, yes)? {
+        return Ok(None);
+    }
+
+    header.tail_signature = tail.into();
+    finalize_tape(client, payer, t.tape_address, t.writer_address, header).await?;
+
+    log::print_message("Finalized");
+    Ok(Some(t.writer_lamports))
+}
@@ -1,11 +1,12 @@
 use anyhow::{Result, bail};
-use dialoguer::{theme::ColorfulTheme, Confirm};
 use solana_client::nonblocking::rpc_client::RpcClient;
-use solana_sdk::signature::{Keypair, Signature};
+use solana_sdk::{pubkey::Pubkey, signature::Signature, signer::Signer};
 use chrono::Utc;
 use std::io::Read;
+use std::path::Path;
+use std::time::Instant;
 use tokio::{task, time::Duration};
-use indicatif::{ProgressBar, ProgressStyle};
+use indicatif::HumanBytes;
 
 use mime::Mime;
 use mime_guess::MimeGuess;
@@ -17,54 +18,195 @@ use tape_client::{
     EncryptionAlgo,
     TapeFlags,
     TapeHeader,
+    META_LEN,
+    ManifestPart,
+    TapeManifest,
+    TapeRecipe,
+    DedupIndex,
+    ChunkSource,
     encode_tape,
+    encode_sealed_tape,
+    SEAL_OVERHEAD,
+    split_into_tapes,
     create_tape,
+    create_small_tape,
     write_linked_chunk,
+    write_to_tape,
+    write_linked_chunk_with_lookup,
+    check_writes_fit,
+    sign_write,
+    send_signed_write,
+    create_tape_lookup_table,
+    chunk_size,
+    program_context,
+    check_name,
+    normalize_name,
     finalize_tape,
     get_tape_account,
+    ensure_not_paused,
+    Congestion,
+    DirUploadCache,
+    UploadCache,
+    BundleBuilder,
+    DurableNonce,
+    TapeBundle,
+    BlockhashCache,
+    SignedWrite,
+    SigningPool,
+    consts::{ATOMIC_WRITE_SIZE, SAFE_SIZE, SIGNATURE_SIZE},
 };
 
 use crate::cli::{Cli, Commands};
+use crate::keypair::{get_keypair_path, signing_pool};
+use crate::keyring::resolve_recipient;
 use crate::log;
+use crate::progress::{self, WriteProgress};
 
 const VERIFY_EVERY: usize       = 500;
 const WAIT_TIME: u64            = 32;
-const SEGMENTS_PER_TX: usize    = 7; // 7 x 128 = 896 bytes
-const SAFE_SIZE : usize         = SEGMENT_SIZE * SEGMENTS_PER_TX;
 
-pub async fn handle_write_command(cli: Cli, client: RpcClient, payer: Keypair) -> Result<()> {
+/// Writes signed within this long of each other share a blockhash.
+const BLOCKHASH_MAX_AGE: Duration = Duration::from_secs(20);
+
+/// How many writes of a tape may be unconfirmed at once.
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum InFlight {
+    /// Adapts to how quickly the cluster confirms, up to this many.
+    Adaptive(usize),
+    /// A fixed window that keeps segments close to the order they're written in, exactly in it
+    /// with a window of 1.
+    Ordered(usize),
+}
+
+impl InFlight {
+    pub(crate) fn new(max_in_flight: usize, ordered: Option<usize>) -> Self {
+        match ordered {
+            Some(window) => InFlight::Ordered(window),
+            None => InFlight::Adaptive(max_in_flight),
+        }
+    }
+
+    fn congestion(self) -> Congestion {
+        match self {
+            InFlight::Adaptive(max_in_flight) => Congestion::new(max_in_flight),
+            InFlight::Ordered(window) => Congestion::ordered(window),
+        }
+    }
+}
+
+pub async fn handle_write_command(cli: Cli, client: RpcClient, payer: Box<dyn Signer>) -> Result<()> {
+    let pool = signing_pool(&get_keypair_path(cli.keypair_path.clone()), cli.mnemonic, payer.as_ref());
+
     match cli.command {
         Commands::Write {
             filename,
             message,
             remote,
+            stdin,
+            mime,
+            flags,
+            meta,
             tape_name,
+            lookup_table,
+            max_in_flight,
+            ordered,
+            cache,
+            offline,
+            out,
+            nonce,
+            recipient,
+            dedup,
+            seekable,
+            manifest,
         } => {
+            if manifest.is_some() {
+                bail!("--manifest only applies to glob patterns, which write a tape per file");
+            }
 
-            let (data, source, mime) = process_input(filename, message, remote).await?;
-            let (mime_type, mime_str) = mime_to_type(&mime);
+            let (data, source, guessed) = process_input(filename, message, remote, stdin).await?;
+            let mime = match mime {
+                Some(mime) => mime
+                    .parse::<Mime>()
+                    .map_err(|e| anyhow::anyhow!("Invalid MIME type {}: {}", mime, e))?,
+                None => guessed,
+            };
+            let mut header = new_header(&mime, lookup_table);
+            let recipient = recipient.as_deref().map(resolve_recipient).transpose()?;
+            let compression_algo = if seekable { CompressionAlgo::GzipChunked } else { CompressionAlgo::Gzip };
+            header.compression = compression_algo.into();
+            let encryption_algo = if recipient.is_some() { EncryptionAlgo::SealedBox } else { EncryptionAlgo::None };
+
+            if let Some(flags) = flags {
+                header.flags = parse_flags(&flags)?;
+                if lookup_table {
+                    header.flags |= u8::from(TapeFlags::Wide);
+                }
+            }
+            if header.flags & u8::from(TapeFlags::Wide) != 0 && header.flags & u8::from(TapeFlags::Linked) == 0 {
+                bail!("Wide tapes must also be linked");
+            }
+            if !meta.is_empty() {
+                header.set_meta(encode_meta(&meta)?.as_bytes())?;
+            }
 
-            let compression_algo = CompressionAlgo::Gzip;
-            let encryption_algo  = EncryptionAlgo::None; // No encryption for now
-            let flags = TapeFlags::Linked;
+            let tape_name = tape_name
+                .map(|name| normalize_name(&name))
+                .unwrap_or_else(|| Utc::now().timestamp().to_string());
+            check_name(&tape_name)?;
 
-            let mut header = TapeHeader::new(
-                mime_type, 
-                compression_algo,
-                encryption_algo,
-                flags 
-            );
+            if offline {
+                let Some(out) = out else {
+                    bail!("--offline needs --out <FILE> to save the bundle to");
+                };
 
-            header.mime_str = mime_str;
+                // Without nonces nothing is signed, so there are no signatures to link
+                if nonce.is_empty() && header.flags & u8::from(TapeFlags::Linked) != 0 {
+                    log::print_info("Unsigned bundles can't link their writes, writing the tape unlinked");
+                    header.flags &= !u8::from(TapeFlags::Linked);
+                }
 
-            let encoded = encode_tape(&data, &header)?;
-            let chunks : Vec<_> = encoded
-                .chunks(SAFE_SIZE)
-                .map(|c| c.to_vec())
-                .collect();
+                let payload = EncodedPayload::new(&data, header, recipient)?;
+                let bundle = bundle_payload(payer.as_ref(), nonce, &tape_name, &payload)?;
+                std::fs::write(&out, bundle.to_json()?)
+                    .map_err(|e| anyhow::anyhow!("Failed to write {}: {}", out.display(), e))?;
 
-            let tape_name = tape_name
-                .unwrap_or_else(|| Utc::now().timestamp().to_string());
+                log::print_section_header("Offline Bundle");
+                log::print_message(&format!("Source: {}", source));
+                log::print_count(&format!("Tape Address: {}", bundle.tape_address));
+                log::print_count(&format!("Transactions: {}", bundle.transactions.len()));
+                log::print_message(if bundle.signed { "Signed with durable nonces" } else { "Unsigned" });
+                log::print_divider();
+                log::print_info("To send it, run:");
+                log::print_title(&format!("tapedrive submit {}", out.display()));
+                log::print_divider();
+                return Ok(());
+            }
+
+            let cache = cache.map(DirUploadCache::new).transpose()?;
+
+            // Fail before asking for anything if the guardian has paused writes
+            ensure_not_paused(&client).await?;
+
+            if let Some(index_path) = dedup {
+                let cache = cache.as_ref().map(|c| c as &dyn UploadCache);
+                let written = write_deduped(
+                    &client, payer.as_ref(), pool.as_ref(), &data, header, &tape_name, &index_path,
+                    InFlight::new(max_in_flight, ordered), cache, cli.yes,
+                ).await?;
+
+                if let Some(written) = written {
+                    log::print_result(&written.tape_address.to_string());
+                    log::print_divider();
+                    log::print_info("To read the tape, run:");
+                    log::print_title(&format!("tapedrive read {}", written.tape_address));
+                    log::print_divider();
+                }
+                return Ok(());
+            }
+
+            let payload = EncodedPayload::new(&data, header, recipient)?;
+            let parts = &payload.parts;
+            let total_chunks = payload.total_chunks();
 
             if cli.verbose {
                 log::print_section_header("Tape Write");
@@ -73,30 +215,29 @@ pub async fn handle_write_command(cli: Cli, client: RpcClient, payer: Keypair) -
                 log::print_message(&format!("MIME Type: {}", mime));
                 log::print_message(&format!("Compression: {:?}", compression_algo));
                 log::print_message(&format!("Encryption: {:?}", encryption_algo));
-                log::print_message(&format!("Flags: {:?}", flags));
+                if let Some(recipient) = &recipient {
+                    log::print_message(&format!("Recipient: {}", Pubkey::new_from_array(*recipient)));
+                }
+                log::print_message(&format!("Flags: {}", flag_names(header.flags)));
+                if !header.meta().is_empty() {
+                    log::print_message(&format!("Meta: {}", String::from_utf8_lossy(header.meta())));
+                }
+            }
+            if parts.len() > 1 {
+                log::print_count(&format!("Total Tapes: {} (plus a manifest)", parts.len()));
             }
-            log::print_count(&format!("Total Chunks: {}", chunks.len()));
+            log::print_count(&format!("Total Chunks: {}", total_chunks));
             log::print_divider();
 
             // Ask for confirmation before proceeding
-            let proceed = Confirm::with_theme(&ColorfulTheme::default())
-                .with_prompt("→ Begin writing to tape?")
-                .default(false)
-                .interact()
-                .map_err(|e| anyhow::anyhow!("Failed to get user input: {}", e))?;
-            if !proceed {
+            if !log::confirm("→ Begin writing to tape?", cli.yes)? {
                 log::print_error("Write operation cancelled");
                 return Ok(());
             }
             log::print_divider();
 
             // Create a progress bar
-            let pb = ProgressBar::new(chunks.len() as u64);
-            pb.set_style(
-                ProgressStyle::default_bar()
-                    .template("{spinner:.green} {wide_msg}")
-                    .expect("Failed to set progress style"),
-            );
+            let pb = WriteProgress::new(progress::new_bar(total_chunks as u64));
             let pb_clone = pb.clone();
             task::spawn(async move {
                 while !pb_clone.is_finished() {
@@ -105,93 +246,30 @@ pub async fn handle_write_command(cli: Cli, client: RpcClient, payer: Keypair) -
                 }
             });
 
-            // Create the tape
-            pb.set_message("Creating new tape (please wait)...");
-            let (tape_address, writer_address, _sig) =
-                create_tape(&client, &payer, &tape_name, header).await?;
-
-            // Write the tape
-            pb.set_message("");
-            pb.set_style(
-                ProgressStyle::default_bar()
-                    .template("{spinner:.green} [{bar:40.white/gray}] {pos}/{len} {wide_msg}")
-                    .expect("Failed to set progress style"),
-            );
-
-            
-            let mut i = 0;
-            let mut last_sig = Signature::default();
-            let mut expected_segments = 0;
-            let mut last_good_chunk = 0;
-            let mut last_good_segments = 0;
-            let mut last_good_sig = last_sig;
-
-            while i < chunks.len() {
-                let chunk = &chunks[i];
-                let (new_sig, used) = write_linked_chunk(
-                    &client, 
-                    &payer, 
-                    tape_address, 
-                    writer_address, 
-                    last_sig, 
-                    chunk
-                ).await?;
-
-                last_sig = new_sig;
-                expected_segments += used as usize;
-
-                i += 1;
-                pb.set_position(i as u64);
-
-                let is_checkpoint = i % VERIFY_EVERY == 0;
-                let is_last_write = i == chunks.len();
-
-                if is_checkpoint || is_last_write {
-                    pb.set_message("Verifying...");
-                    tokio::time::sleep(Duration::from_secs(WAIT_TIME)).await;
-
-                    let (acct, _) = get_tape_account(&client, &tape_address).await?;
-                    let onchain = acct.total_segments as usize;
-
-                    if onchain == expected_segments {
-                        last_good_chunk = i;
-                        last_good_segments = expected_segments;
-                        last_good_sig = last_sig;
-                    } else {
-                        log::print_info(&format!(
-                            "Verification failed at chunk {}; onchain {}, expected {}",
-                            i, onchain, expected_segments
-                        ));
-                        i = last_good_chunk;
-                        expected_segments = last_good_segments;
-                        last_sig = last_good_sig;
-                        pb.set_position(i as u64);
-                        log::print_message(&format!("Retrying from chunk {}", i));
-                    }
-
-                    pb.set_message("");
-                }
-            }
-
-            header.tail_signature = last_sig.into();
-
-            // Finalize the tape (prevents further writes and reclaims sol)
-            finalize_tape(
-                &client,
-                &payer,
-                tape_address,
-                writer_address,
-                header,
+            let cache = cache.as_ref().map(|c| c as &dyn UploadCache);
+            let started = Instant::now();
+            let tape_address = write_payload(
+                &client, payer.as_ref(), pool.as_ref(), &pb, &tape_name, &payload, InFlight::new(max_in_flight, ordered), cache,
             ).await?;
 
             pb.finish_with_message("");
             log::print_divider();
+            let elapsed = started.elapsed().as_secs_f64();
+            log::print_count(&format!(
+                "Throughput: {:.1} tx/s, {}/s",
+                total_chunks as f64 / elapsed,
+                HumanBytes((pb.bytes() as f64 / elapsed) as u64)
+            ));
+            if pb.failures() > 0 {
+                log::print_count(&format!("Failed Writes: {} (sent again)", pb.failures()));
+            }
+            log::print_result(&tape_address.to_string());
 
             if cli.verbose {
                 log::print_divider();
                 log::print_section_header("Metadata");
                 log::print_count(&format!("Tape Address: {}", tape_address));
-                log::print_count(&format!("Total Chunks: {}", chunks.len()));
+                log::print_count(&format!("Total Chunks: {}", total_chunks));
             }
 
             log::print_divider();
@@ -279,14 +357,514 @@ pub async fn handle_write_command(cli: Cli, client: RpcClient, payer: Keypair) -
     Ok(())
 }
 
+/// The header for a new tape holding data of the given MIME type.
+pub(crate) fn new_header(mime: &Mime, wide: bool) -> TapeHeader {
+    let (mime_type, mime_str) = mime_to_type(mime);
+
+    let mut header = TapeHeader::new(
+        mime_type,
+        CompressionAlgo::Gzip,
+        EncryptionAlgo::None, // No encryption for now
+        TapeFlags::Linked,
+    );
+
+    header.mime_str = mime_str;
+
+    // Wide tapes fit more data per write, but cost a lookup table per tape
+    if wide {
+        header.flags |= u8::from(TapeFlags::Wide);
+    }
+
+    header
+}
+
+/// Header flags from their names, e.g. `linked,wide`.
+fn parse_flags(names: &[String]) -> Result<u8> {
+    let mut flags = 0u8;
+    for name in names {
+        let flag = match name.trim().to_ascii_lowercase().as_str() {
+            "none" => TapeFlags::None,
+            "linked" => TapeFlags::Linked,
+            "wide" => TapeFlags::Wide,
+            other => bail!("Unknown tape flag {}, expected none, linked or wide", other),
+        };
+        flags |= u8::from(flag);
+    }
+
+    Ok(flags)
+}
+
+/// Names of the flags set in a header, e.g. `Linked | Wide`.
+fn flag_names(flags: u8) -> String {
+    let names: Vec<&str> = [(TapeFlags::Linked, "Linked"), (TapeFlags::Wide, "Wide")]
+        .into_iter()
+        .filter(|(flag, _)| flags & u8::from(*flag) != 0)
+        .map(|(_, name)| name)
+        .collect();
+
+    if names.is_empty() { "None".to_string() } else { names.join(" | ") }
+}
+
+/// Joins `key=value` pairs as `key=value&key2=value2` for the header's metadata field.
+fn encode_meta(pairs: &[String]) -> Result<String> {
+    for pair in pairs {
+        match pair.split_once('=') {
+            Some((key, _)) if !key.is_empty() && !pair.contains('&') => {}
+            _ => bail!("Invalid metadata {}, expected key=value", pair),
+        }
+    }
+
+    let meta = pairs.join("&");
+    if meta.len() > META_LEN {
+        bail!("Metadata {} is {} bytes, the header holds at most {}", meta, meta.len(), META_LEN);
+    }
+
+    Ok(meta)
+}
+
+/// A payload encoded and ready to write. Payloads too large for a single tape are sharded, and
+/// linked by a manifest tape.
+pub(crate) struct EncodedPayload<'a> {
+    pub header: TapeHeader,
+    pub parts: Vec<&'a [u8]>,
+    pub encoded_parts: Vec<Vec<u8>>,
+    /// The X25519 key every tape of the payload is sealed to, if any.
+    pub recipient: Option<[u8; 32]>,
+}
+
+impl<'a> EncodedPayload<'a> {
+    pub fn new(data: &'a [u8], mut header: TapeHeader, recipient: Option<[u8; 32]>) -> Result<Self> {
+        if let Some(recipient) = &recipient {
+            header.seal_for(recipient);
+        }
+
+        let parts = split_into_tapes(data, &header);
+        let encoded_parts = parts
+            .iter()
+            .map(|part| match &recipient {
+                Some(recipient) => encode_sealed_tape(part, &header, recipient),
+                None => encode_tape(part, &header),
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self { header, parts, encoded_parts, recipient })
+    }
+
+    /// The manifest linking the written parts, sealed like them so their checksums don't give
+    /// anything away.
+    pub fn manifest(&self, mut header: TapeHeader, parts: Vec<ManifestPart>) -> Result<(TapeHeader, Vec<u8>)> {
+        let manifest = TapeManifest::new(parts).to_bytes();
+        let Some(recipient) = &self.recipient else {
+            return Ok((header, manifest));
+        };
+
+        header.seal_for(recipient);
+        let sealed = encode_sealed_tape(&manifest, &header, recipient)?;
+        Ok((header, sealed))
+    }
+
+    /// The number of writes needed, including the manifest's.
+    pub fn total_chunks(&self) -> usize {
+        let mut total_chunks: usize = self.encoded_parts
+            .iter()
+            .map(|encoded| encoded.len().div_ceil(chunk_size(&self.header)))
+            .sum();
+
+        if self.parts.len() > 1 {
+            let overhead = if self.recipient.is_some() { SEAL_OVERHEAD } else { 0 };
+            total_chunks += (TapeManifest::encoded_len(self.parts.len()) + overhead).div_ceil(SAFE_SIZE);
+        }
+
+        total_chunks
+    }
+}
+
+/// Write an encoded payload under the given name, in a single transaction if it's small enough.
+/// Returns the address to read it from, the manifest's if it was sharded. Chunks are recorded in
+/// `cache` as they're sent, if given, and signed on `pool` if there is one.
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn write_payload(
+    client: &RpcClient,
+    payer: &dyn Signer,
+    pool: Option<&SigningPool>,
+    pb: &WriteProgress,
+    tape_name: &str,
+    payload: &EncodedPayload<'_>,
+    in_flight: InFlight,
+    cache: Option<&dyn UploadCache>,
+) -> Result<Pubkey> {
+    let header = payload.header;
+    let parts = &payload.parts;
+    let encoded_parts = &payload.encoded_parts;
+
+    let tape_address = if parts.len() == 1 && encoded_parts[0].len() <= ATOMIC_WRITE_SIZE {
+        // Small enough to create, write and finalize in one transaction
+        pb.set_message("Writing tape (please wait)...");
+        let (tape_address, _sig) =
+            create_small_tape(client, payer, tape_name, header, &encoded_parts[0]).await?;
+        pb.add_bytes(encoded_parts[0].len());
+        pb.inc(payload.total_chunks() as u64);
+        tape_address
+    } else if parts.len() == 1 {
+        write_tape(client, payer, pool, pb, tape_name, header, &encoded_parts[0], in_flight, cache).await?
+    } else {
+        let mut manifest_parts = Vec::with_capacity(parts.len());
+
+        for (i, (part, encoded)) in parts.iter().zip(encoded_parts).enumerate() {
+            let part_name = format!("{}.{}", tape_name, i);
+            if part_name.len() > NAME_LEN {
+                bail!("Tape name {} is too long to name its parts", tape_name);
+            }
+
+            let part_address =
+                write_tape(client, payer, pool, pb, &part_name, header, encoded, in_flight, cache).await?;
+            manifest_parts.push(ManifestPart::new(part_address, part));
+        }
+
+        let (manifest_header, manifest) = payload.manifest(TapeManifest::header(), manifest_parts)?;
+        write_tape(client, payer, pool, pb, tape_name, manifest_header, &manifest, in_flight, cache).await?
+    };
+
+    Ok(tape_address)
+}
+
+/// What a deduplicated write stored.
+pub(crate) struct DedupWrite {
+    /// The recipe tape, the address to read the data from.
+    pub tape_address: Pubkey,
+
+    /// Bytes of new chunks written, before compression.
+    pub new_bytes: u64,
+}
+
+/// Write only the chunks of `data` the index at `index_path` doesn't know yet, packed into one
+/// payload, then a recipe tape that rebuilds `data` from them and the ones written before.
+/// Asks before writing unless `yes` is set, returning None if the answer is no.
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn write_deduped(
+    client: &RpcClient,
+    payer: &dyn Signer,
+    pool: Option<&SigningPool>,
+    data: &[u8],
+    header: TapeHeader,
+    tape_name: &str,
+    index_path: &Path,
+    in_flight: InFlight,
+    cache: Option<&dyn UploadCache>,
+    yes: bool,
+) -> Result<Option<DedupWrite>> {
+    let index = DedupIndex::load(index_path)?;
+    let plan = index.plan(data);
+
+    // The pack's address is known up front, so the recipe can be encoded before writing anything
+    let pack_name = format!("{}.chunks", tape_name);
+    if pack_name.len() > NAME_LEN {
+        bail!("Tape name {} is too long to name its chunk pack", tape_name);
+    }
+    let pack = (!plan.pack.is_empty()).then(|| EncodedPayload::new(&plan.pack, header, None)).transpose()?;
+    let pack_address = pack.as_ref().map(|_| program_context().tape_pda(payer.pubkey(), &to_name(&pack_name)).0);
+
+    let mut updated = index.clone();
+    let recipe = plan.finish(pack_address, &header.mime(), &mut updated)?.to_bytes();
+    let recipe = EncodedPayload::new(&recipe, TapeRecipe::header(), None)?;
+
+    let total_chunks = recipe.total_chunks() + pack.as_ref().map_or(0, |p| p.total_chunks());
+
+    log::print_section_header("Deduplicated Write");
+    log::print_count(&format!("Chunks: {} ({} already stored)", plan.chunks.len(),
+        plan.chunks.iter().filter(|c| matches!(c.source, ChunkSource::Stored(_))).count()));
+    log::print_count(&format!("New Data: {} of {} bytes", plan.pack.len(), data.len()));
+    log::print_count(&format!("Total Chunks: {}", total_chunks));
+    log::print_divider();
+
+    if !log::confirm("→ Begin writing to tape?", yes)? {
+        log::print_error("Write operation cancelled");
+        return Ok(None);
+    }
+    log::print_divider();
+
+    let pb = WriteProgress::new(progress::new_bar(total_chunks as u64));
+    let pb_clone = pb.clone();
+    task::spawn(async move {
+        while !pb_clone.is_finished() {
+            pb_clone.tick();
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+    });
+
+    if let Some(pack) = &pack {
+        write_payload(client, payer, pool, &pb, &pack_name, pack, in_flight, cache).await?;
+
+        // Record the new chunks as soon as they're on tape, so a failed recipe doesn't cost them
+        updated.save(index_path)?;
+    }
+
+    let tape_address = write_payload(client, payer, pool, &pb, tape_name, &recipe, in_flight, cache).await?;
+    pb.finish_with_message("");
+
+    Ok(Some(DedupWrite { tape_address, new_bytes: plan.pack.len() as u64 }))
+}
+
+/// Build the transactions for an encoded payload without a network connection, signed against
+/// `nonces` if any are given.
+fn bundle_payload(
+    payer: &dyn Signer,
+    nonces: Vec<DurableNonce>,
+    tape_name: &str,
+    payload: &EncodedPayload<'_>,
+) -> Result<TapeBundle> {
+    let mut builder = if nonces.is_empty() {
+        BundleBuilder::unsigned(payer.pubkey())
+    } else {
+        BundleBuilder::with_nonces(payer, nonces)
+    };
+
+    let parts = &payload.parts;
+    let encoded_parts = &payload.encoded_parts;
+
+    let tape_address = if parts.len() == 1 {
+        builder.add_tape(tape_name, payload.header, &encoded_parts[0])?
+    } else {
+        let mut manifest_parts = Vec::with_capacity(parts.len());
+
+        for (i, (part, encoded)) in parts.iter().zip(encoded_parts).enumerate() {
+            let part_name = format!("{}.{}", tape_name, i);
+            if part_name.len() > NAME_LEN {
+                bail!("Tape name {} is too long to name its parts", tape_name);
+            }
+
+            let part_address = builder.add_tape(&part_name, payload.header, encoded)?;
+            manifest_parts.push(ManifestPart::new(part_address, part));
+        }
+
+        // The manifest is only linked if its parts could be
+        let mut manifest_header = TapeManifest::header();
+        if payload.header.flags & u8::from(TapeFlags::Linked) == 0 {
+            manifest_header.flags &= !u8::from(TapeFlags::Linked);
+        }
+
+        let (manifest_header, manifest) = payload.manifest(manifest_header, manifest_parts)?;
+        builder.add_tape(tape_name, manifest_header, &manifest)?
+    };
+
+    Ok(builder.finish(tape_address))
+}
+
+/// Create a tape, write the encoded data to it as linked chunks (verifying along the way), and
+/// finalize it. Returns the tape address.
+///
+/// With a signing pool, each chunk is signed on the pool while the one before it is being sent.
+/// Wide tapes are signed inline, their writes are versioned transactions.
+#[allow(clippy::too_many_arguments)]
+async fn write_tape(
+    client: &RpcClient,
+    payer: &dyn Signer,
+    pool: Option<&SigningPool>,
+    pb: &WriteProgress,
+    tape_name: &str,
+    mut header: TapeHeader,
+    encoded: &[u8],
+    in_flight: InFlight,
+    cache: Option<&dyn UploadCache>,
+) -> Result<Pubkey> {
+    let chunks : Vec<_> = encoded
+        .chunks(chunk_size(&header))
+        .map(|c| c.to_vec())
+        .collect();
+
+    // Progress is shared across every tape of a write
+    let base = pb.position();
+
+    // Create the tape
+    pb.show_spinner();
+    pb.set_message("Creating new tape (please wait)...");
+    let (tape_address, writer_address, _sig) =
+        create_tape(client, payer, tape_name, header).await?;
+
+    // Wide tapes load their accounts from a lookup table to make room for the larger chunks
+    let lookup_table = if header.flags & u8::from(TapeFlags::Wide) != 0 {
+        pb.set_message("Creating lookup table (please wait)...");
+        Some(create_tape_lookup_table(client, payer, tape_address, writer_address).await?)
+    } else {
+        None
+    };
+
+    // Write the tape
+    pb.set_message("");
+    pb.show_bar();
+
+    let mut i = 0;
+    let mut last_sig = Signature::default();
+    let mut expected_segments = 0;
+    let mut last_good_chunk = 0;
+    let mut last_good_segments = 0;
+    let mut last_good_sig = last_sig;
+
+    // Bounds unconfirmed writes, adapting to how quickly the cluster confirms them
+    let mut congestion = in_flight.congestion();
+    // Where the write before the last one sent left off, to send the last one again
+    let mut before_last = (i, last_sig, expected_segments);
+
+    let linked = header.flags & u8::from(TapeFlags::Linked) != 0;
+    let mut blockhashes = BlockhashCache::new(BLOCKHASH_MAX_AGE);
+    // The next chunk, signed while the current one was sent
+    let mut ahead: Option<(usize, SignedWrite)> = None;
+
+    while i < chunks.len() {
+        let failed = congestion.wait_for_room(client).await?;
+        pb.add_failures(failed);
+
+        if failed > 0 && congestion.is_ordered() {
+            // Writes sent after the one that failed may have landed ahead of it
+            if congestion.limit() > 1 {
+                bail!(
+                    "A write to {} failed with others in flight, its segments may be out of order. \
+                     Write it again, with a smaller --ordered window",
+                    tape_address
+                );
+            }
+
+            // Nothing was sent after it, send it again
+            (i, last_sig, expected_segments) = before_last;
+            ahead = None;
+            pb.set_position(base + i as u64);
+            continue;
+        }
+
+        let chunk = &chunks[i];
+        let (new_sig, used) = match (&lookup_table, pool) {
+            (None, Some(pool)) => {
+                let prev = linked.then_some(last_sig);
+                let blockhash = blockhashes.get(client).await?;
+
+                // Signed ahead against a signature that didn't land if its write was retried
+                let write = match ahead.take() {
+                    Some((index, write)) if index == i && write.prev_signature == prev => write,
+                    _ => sign_write(pool, tape_address, writer_address, prev, chunk, blockhash).await?,
+                };
+
+                let sign_next = async {
+                    match chunks.get(i + 1) {
+                        Some(next) => {
+                            let next_prev = linked.then(|| write.signature());
+                            sign_write(pool, tape_address, writer_address, next_prev, next, blockhash)
+                                .await
+                                .map(Some)
+                        }
+                        None => Ok(None),
+                    }
+                };
+
+                let (sent, next) = futures::join!(send_signed_write(client, pool, &write), sign_next);
+                ahead = next?.map(|next| (i + 1, next));
+                sent?
+            }
+            _ if !linked => write_to_tape(
+                client,
+                payer,
+                tape_address,
+                writer_address,
+                chunk,
+            ).await?,
+            (Some(lookup_table), _) => write_linked_chunk_with_lookup(
+                client,
+                payer,
+                tape_address,
+                writer_address,
+                last_sig,
+                chunk,
+                lookup_table,
+            ).await?,
+            (None, None) => write_linked_chunk(
+                client, 
+                payer, 
+                tape_address, 
+                writer_address, 
+                last_sig, 
+                chunk
+            ).await?,
+        };
+
+        congestion.sent(new_sig);
+        pb.add_bytes(chunk.len());
+        before_last = (i, last_sig, expected_segments);
+        if let Some(cache) = cache {
+            cache.put_chunk(&tape_address, i as u64, chunk, &new_sig)?;
+        }
+        last_sig = new_sig;
+        expected_segments += used as usize;
+
+        i += 1;
+        pb.set_position(base + i as u64);
+
+        let is_checkpoint = i % VERIFY_EVERY == 0;
+        let is_last_write = i == chunks.len();
+
+        if is_checkpoint || is_last_write {
+            // Verifying takes a while, don't send a write signed with a blockhash from before it
+            ahead = None;
+
+            pb.set_message("Verifying...");
+            let failed = congestion.drain(client).await?;
+            pb.add_failures(failed);
+            tokio::time::sleep(Duration::from_secs(WAIT_TIME)).await;
+
+            let (acct, _) = get_tape_account(client, &tape_address).await?;
+            let onchain = acct.total_segments as usize;
+
+            if onchain == expected_segments {
+                last_good_chunk = i;
+                last_good_segments = expected_segments;
+                last_good_sig = last_sig;
+            } else {
+                log::print_info(&format!(
+                    "Verification failed at chunk {}; onchain {}, expected {}",
+                    i, onchain, expected_segments
+                ));
+                pb.add_failures(i - last_good_chunk);
+                i = last_good_chunk;
+                expected_segments = last_good_segments;
+                last_sig = last_good_sig;
+
+                // Writes that landed out of order still take up room on the tape
+                let prefix = if linked { SIGNATURE_SIZE } else { 0 };
+                check_writes_fit(&acct, chunks[i..].iter().map(|chunk| prefix + chunk.len()))?;
+
+                pb.set_position(base + i as u64);
+                log::print_message(&format!("Retrying from chunk {}", i));
+            }
+
+            pb.set_message("");
+        }
+    }
+
+    header.tail_signature = last_sig.into();
+
+    // Finalize the tape (prevents further writes and reclaims sol)
+    finalize_tape(
+        client,
+        payer,
+        tape_address,
+        writer_address,
+        header,
+    ).await?;
+
+    Ok(tape_address)
+}
+
 /// Helper function to process input based on the provided parameters. 
 /// Returns the data, source description, and MIME type.
 pub async fn process_input(
     filename: Option<String>,
     message: Option<String>,
     remote: Option<String>,
+    stdin: bool,
 ) -> Result<(Vec<u8>, String, Mime)> {
 
+    if stdin {
+        return read_stdin_input();
+    }
+
     match (filename, message, remote) {
         // File on disk
         (Some(path_str), None, None) => {
@@ -303,15 +881,8 @@ pub async fn process_input(
         // Inline message or piped stdin
         (None, Some(m), None) => {
             if m == "-" {
-                // read all of stdin
-                let stdin_data = read_from_stdin()?;
-                if stdin_data.is_empty() {
-                    bail!("No data provided via piped input");
-                }
-                let source = "piped input".to_string();
-                // Treat piped stdin as binary/octet 
-                let mime = default_octet();
-                return Ok((stdin_data, source, mime));
+                // Same as --stdin
+                return read_stdin_input();
             } else {
                 // plain command‐line string
                 let data = m.as_bytes().to_vec();
@@ -351,11 +922,23 @@ pub async fn process_input(
 
         // Anything else (zero or more than one provided)
         _ => bail!(
-            "Must provide exactly one of: <FILE>, -m <MSG>, or -r <URL>"
+            "Must provide exactly one of: <FILE>, -m <MSG>, -r <URL> or --stdin"
         ),
     }
 }
 
+/// Reads binary data piped to stdin, as-is.
+fn read_stdin_input() -> Result<(Vec<u8>, String, Mime)> {
+    let stdin_data = read_from_stdin()?;
+    if stdin_data.is_empty() {
+        bail!("No data provided via piped input");
+    }
+    let source = "piped input".to_string();
+    // Treat piped stdin as binary/octet unless --mime says otherwise
+    let mime = default_octet();
+    Ok((stdin_data, source, mime))
+}
+
 /// Reads data from stdin into a vector of bytes.
 fn read_from_stdin() -> std::io::Result<Vec<u8>> {
     let mut buffer = Vec::new();
@@ -368,81 +951,3 @@ fn default_octet() -> Mime {
     // application/octet-stream
     "application/octet-stream".parse().unwrap()
 }
-
-/// Given a `Mime`, return:
-/// - the `u8` code for a matching `MimeType` variant, or `MimeType::Custom as u8` if none match,
-/// - plus a `[u8; NAME_LEN]` buffer containing either all zeros (for known types),
-///   or a null‐padded ASCII‐lowercase copy of the full MIME string (for Custom).
-fn mime_to_type(mime: &Mime) -> (MimeType, [u8; NAME_LEN]) {
-
-    let empty = [0u8; NAME_LEN];
-    let (t, s) = (mime.type_().as_str(), mime.subtype().as_str());
-    let t = t.to_ascii_lowercase();
-    let s = s.to_ascii_lowercase();
-
-    let code: MimeType = match (t.as_str(), s.as_str()) {
-        // Unknown or octet-stream -> keep Unknown (0)
-        ("application", "octet-stream") => MimeType::Unknown,
-
-        // Image formats
-        ("image", "png") => MimeType::ImagePng,
-        ("image", "jpeg") | ("image", "jpg") => MimeType::ImageJpeg,
-        ("image", "gif") => MimeType::ImageGif,
-        ("image", "webp") => MimeType::ImageWebp,
-        ("image", "bmp") => MimeType::ImageBmp,
-        ("image", "tiff") | ("image", "tif") => MimeType::ImageTiff,
-
-        // Document formats
-        ("application", "pdf") => MimeType::ApplicationPdf,
-        ("application", "msword") => MimeType::ApplicationMsword,
-        ("application", "vnd.openxmlformats-officedocument.wordprocessingml.document") => MimeType::ApplicationDocx,
-        ("application", "vnd.oasis.opendocument.text") => MimeType::ApplicationOdt,
-
-        // Text formats
-        ("text", "plain") => MimeType::TextPlain,
-        ("text", "html") => MimeType::TextHtml,
-        ("text", "css") => MimeType::TextCss,
-        ("text", "javascript") | ("application", "javascript") => MimeType::TextJavascript,
-        ("text", "csv") => MimeType::TextCsv,
-        ("text", "markdown") | ("text", "md") => MimeType::TextMarkdown,
-
-        // Audio formats
-        ("audio", "mpeg") | ("audio", "mp3") => MimeType::AudioMpeg,
-        ("audio", "wav") => MimeType::AudioWav,
-        ("audio", "ogg") => MimeType::AudioOgg,
-        ("audio", "flac") => MimeType::AudioFlac,
-
-        // Video formats
-        ("video", "mp4") => MimeType::VideoMp4,
-        ("video", "webm") => MimeType::VideoWebm,
-        ("video", "mpeg") => MimeType::VideoMpeg,
-        ("video", "x-msvideo") | ("video", "avi") => MimeType::VideoAvi,
-
-        // Application formats
-        ("application", "json") => MimeType::ApplicationJson,
-        ("application", "xml") | ("text", "xml") => MimeType::ApplicationXml,
-        ("application", "zip") => MimeType::ApplicationZip,
-        ("application", "gzip") | ("application", "x-gzip") => MimeType::ApplicationGzip,
-        ("application", "x-tar") | ("application", "tar") => MimeType::ApplicationTar,
-
-        // Font formats
-        ("font", "woff") => MimeType::FontWoff,
-        ("font", "woff2") => MimeType::FontWoff2,
-        ("font", "ttf") | ("application", "font-sfnt") => MimeType::FontTtf,
-        ("font", "otf") => MimeType::FontOtf,
-
-        // Miscellaneous
-        ("application", "rtf") => MimeType::ApplicationRtf,
-        ("application", "sql") => MimeType::ApplicationSql,
-        ("application", "x-yaml") | ("text", "yaml") => MimeType::ApplicationYaml,
-
-        // Everything else -> mark as Custom
-        _ => {
-            let full_mime = mime.as_ref().to_ascii_lowercase();
-            let mime_str = to_name(&full_mime);
-            return (MimeType::Custom, mime_str);
-        }
-    };
-
-    (code, empty)
-}
@@ -1,8 +1,19 @@
-use solana_sdk::signature::Keypair;
+use solana_sdk::{
+    derivation_path::DerivationPath,
+    signature::Keypair,
+    signer::{
+        keypair::{keypair_from_seed, keypair_from_seed_and_derivation_path},
+        Signer,
+    },
+};
 use std::path::PathBuf;
 use anyhow::{Result, anyhow};
+use bip39::{Language, Mnemonic, Seed};
+use dialoguer::{theme::ColorfulTheme, Password};
 use std::fs;
 
+use tape_client::{RemoteSigner, SealKeypair, SigningPool};
+
 pub fn create_keypair(path: &PathBuf) -> Result<Keypair> {
     let keypair = Keypair::new();
     let bytes = keypair.to_bytes().to_vec();
@@ -22,6 +33,12 @@ pub fn load_keypair(path: &PathBuf) -> Result<Keypair> {
         .map_err(|e| anyhow!("Failed to create keypair from bytes: {}", e))
 }
 
+/// The X25519 keypair tapes are sealed to for the wallet in a keypair file.
+pub fn seal_keypair(path: &PathBuf) -> Result<SealKeypair> {
+    let keypair = load_keypair(path)?;
+    Ok(SealKeypair::from_seed(&keypair.to_bytes()[..32]))
+}
+
 /// Loads the keypair from a specified path or the default Solana keypair location.
 pub fn get_keypair_path(keypair_path: Option<PathBuf>) -> PathBuf {
     keypair_path.unwrap_or_else(|| {
@@ -31,12 +48,126 @@ pub fn get_keypair_path(keypair_path: Option<PathBuf>) -> PathBuf {
     })
 }
 
-pub fn get_payer(keypair_path: PathBuf) -> Result<Keypair> {
+/// Loads the payer, creating a new keypair file if there's none at the path.
+pub async fn get_payer(
+    keypair_path: PathBuf,
+    mnemonic: bool,
+    derivation_path: Option<&str>,
+) -> Result<Box<dyn Signer>> {
+    if mnemonic || is_uri(&keypair_path) {
+        return load_signer(&keypair_path, mnemonic, derivation_path).await;
+    }
+
     let payer = match load_keypair(&keypair_path) {
         Ok(payer) => payer,
         Err(_) => {
             create_keypair(&keypair_path)?
         }
     };
-    Ok(payer)
+    Ok(Box::new(payer))
+}
+
+/// Loads a signer without creating anything. The keypair path may be:
+/// - a JSON keypair file
+/// - `usb://ledger` (optionally with a wallet pubkey, e.g. `usb://ledger/<pubkey>`)
+/// - an `http(s)://` URL of a remote signer (see `tape_client::RemoteSigner`)
+///
+/// With `mnemonic`, the keypair path is ignored and a BIP39 seed phrase is prompted for instead.
+pub async fn load_signer(
+    keypair_path: &PathBuf,
+    mnemonic: bool,
+    derivation_path: Option<&str>,
+) -> Result<Box<dyn Signer>> {
+    let derivation_path = derivation_path
+        .map(|p| {
+            DerivationPath::from_absolute_path_str(p)
+                .map_err(|e| anyhow!("Invalid derivation path {}: {}", p, e))
+        })
+        .transpose()?;
+
+    if mnemonic {
+        return Ok(Box::new(keypair_from_mnemonic(derivation_path)?));
+    }
+
+    let path = keypair_path.to_string_lossy();
+    if path.starts_with("usb://") {
+        return ledger_signer(&path, derivation_path);
+    }
+
+    if path.starts_with("http://") || path.starts_with("https://") {
+        return Ok(Box::new(RemoteSigner::connect(&path).await?));
+    }
+
+    Ok(Box::new(load_keypair(keypair_path)?))
+}
+
+/// A pool to sign writes on, when the payer is a keypair file. Other signers sign inline.
+pub fn signing_pool(keypair_path: &PathBuf, mnemonic: bool, payer: &dyn Signer) -> Option<SigningPool> {
+    if mnemonic || is_uri(keypair_path) {
+        return None;
+    }
+
+    let keypair = load_keypair(keypair_path).ok()?;
+    (keypair.pubkey() == payer.pubkey()).then(|| SigningPool::with_default_workers(keypair))
+}
+
+fn is_uri(keypair_path: &PathBuf) -> bool {
+    keypair_path.to_string_lossy().contains("://")
+}
+
+/// Prompts for a BIP39 seed phrase (and optional passphrase) and derives a keypair from it.
+/// Without a derivation path, this matches `solana-keygen recover prompt://`.
+fn keypair_from_mnemonic(derivation_path: Option<DerivationPath>) -> Result<Keypair> {
+    let phrase = Password::with_theme(&ColorfulTheme::default())
+        .with_prompt("Seed phrase")
+        .interact()
+        .map_err(|e| anyhow!("Failed to get user input: {}", e))?;
+
+    let mnemonic = Mnemonic::from_phrase(phrase.trim(), Language::English)
+        .map_err(|e| anyhow!("Invalid seed phrase: {}", e))?;
+
+    let passphrase = Password::with_theme(&ColorfulTheme::default())
+        .with_prompt("Passphrase (empty for none)")
+        .allow_empty_password(true)
+        .interact()
+        .map_err(|e| anyhow!("Failed to get user input: {}", e))?;
+
+    let seed = Seed::new(&mnemonic, &passphrase);
+    let keypair = match derivation_path {
+        Some(path) => keypair_from_seed_and_derivation_path(seed.as_bytes(), Some(path)),
+        None => keypair_from_seed(seed.as_bytes()),
+    };
+
+    keypair.map_err(|e| anyhow!("Failed to derive keypair from seed phrase: {}", e))
+}
+
+#[cfg(feature = "ledger")]
+fn ledger_signer(path: &str, derivation_path: Option<DerivationPath>) -> Result<Box<dyn Signer>> {
+    use solana_remote_wallet::{
+        locator::Locator,
+        remote_keypair::generate_remote_keypair,
+        remote_wallet::maybe_wallet_manager,
+    };
+
+    let locator = Locator::new_from_path(path)
+        .map_err(|e| anyhow!("Invalid Ledger path {}: {}", path, e))?;
+    let wallet_manager = maybe_wallet_manager()
+        .map_err(|e| anyhow!("Failed to connect to Ledger: {}", e))?
+        .ok_or_else(|| anyhow!("No Ledger found, is it plugged in and unlocked?"))?;
+
+    let keypair = generate_remote_keypair(
+        locator,
+        derivation_path.unwrap_or_default(),
+        &wallet_manager,
+        false,
+        "keypair",
+    )
+    .map_err(|e| anyhow!("Failed to load Ledger keypair: {}", e))?;
+
+    Ok(Box::new(keypair))
+}
+
+#[cfg(not(feature = "ledger"))]
+fn ledger_signer(_path: &str, _derivation_path: Option<DerivationPath>) -> Result<Box<dyn Signer>> {
+    Err(anyhow!("Ledger support isn't enabled, reinstall with `--features ledger`"))
 }
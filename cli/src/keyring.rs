@@ -0,0 +1,120 @@
+use anyhow::{anyhow, bail, Result};
+use dialoguer::{theme::ColorfulTheme, Password};
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+use tape_client::{LockedKey, SealKeypair};
+
+/// A key in the keyring. Keys of your own hold their secret locked with a passphrase, keys of
+/// others you write to only their public key.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct KeyEntry {
+    /// X25519 public key, base58.
+    pub public: String,
+
+    /// The locked secret key (see `LockedKey`), base64. None for someone else's key.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub locked: Option<String>,
+}
+
+impl KeyEntry {
+    pub fn new(keypair: &SealKeypair, passphrase: &str) -> Result<Self> {
+        Ok(Self {
+            public: Pubkey::new_from_array(keypair.public).to_string(),
+            locked: Some(base64::encode(keypair.lock(passphrase)?.to_bytes())),
+        })
+    }
+
+    pub fn public_only(public: &[u8; 32]) -> Self {
+        Self {
+            public: Pubkey::new_from_array(*public).to_string(),
+            locked: None,
+        }
+    }
+
+    pub fn public_key(&self) -> Result<[u8; 32]> {
+        parse_key(&self.public)
+    }
+
+    /// Prompt for the passphrase and unlock the secret key.
+    pub fn unlock(&self, name: &str) -> Result<SealKeypair> {
+        let Some(locked) = &self.locked else {
+            bail!("Key {} is someone else's, only its public key is stored", name);
+        };
+
+        let locked = base64::decode(locked)
+            .map_err(|e| anyhow!("Invalid locked key {}: {}", name, e))?;
+        let locked = LockedKey::try_from_bytes(&locked)?;
+
+        let passphrase = Password::with_theme(&ColorfulTheme::default())
+            .with_prompt(format!("Passphrase for {}", name))
+            .interact()
+            .map_err(|e| anyhow!("Failed to get user input: {}", e))?;
+
+        SealKeypair::unlock(&locked, &self.public_key()?, &passphrase)
+    }
+}
+
+/// Encryption keys are kept by name in `~/.config/tapedrive/keys.json`, so writes and reads can
+/// refer to them instead of raw key files.
+pub fn keyring_path() -> Result<PathBuf> {
+    let dir = dirs::home_dir()
+        .ok_or_else(|| anyhow!("Could not find home directory"))?
+        .join(".config/tapedrive");
+    Ok(dir.join("keys.json"))
+}
+
+pub fn load_keyring() -> Result<BTreeMap<String, KeyEntry>> {
+    let path = keyring_path()?;
+    if !path.exists() {
+        return Ok(BTreeMap::new());
+    }
+
+    let data = fs::read_to_string(&path)
+        .map_err(|e| anyhow!("Failed to read {}: {}", path.display(), e))?;
+    serde_json::from_str(&data)
+        .map_err(|e| anyhow!("Failed to parse {}: {}", path.display(), e))
+}
+
+pub fn save_keyring(keys: &BTreeMap<String, KeyEntry>) -> Result<()> {
+    let path = keyring_path()?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .map_err(|e| anyhow!("Failed to create {}: {}", dir.display(), e))?;
+    }
+
+    let json = serde_json::to_string_pretty(keys)
+        .map_err(|e| anyhow!("Failed to serialize keyring: {}", e))?;
+    fs::write(&path, json)
+        .map_err(|e| anyhow!("Failed to write {}: {}", path.display(), e))
+}
+
+/// The key in the keyring with this name.
+pub fn get_key(name: &str) -> Result<KeyEntry> {
+    load_keyring()?
+        .remove(name)
+        .ok_or_else(|| anyhow!("No key named {} in the keyring, see `tapedrive keys list`", name))
+}
+
+/// A public key to seal to, either the name of a key in the keyring or the key itself.
+pub fn resolve_recipient(name_or_key: &str) -> Result<[u8; 32]> {
+    match load_keyring()?.get(name_or_key) {
+        Some(entry) => entry.public_key(),
+        None => parse_key(name_or_key).map_err(|_| anyhow!(
+            "{} is neither a key in the keyring nor a public key",
+            name_or_key
+        )),
+    }
+}
+
+/// A 32-byte key, base58 encoded like an address.
+pub fn parse_key(key: &str) -> Result<[u8; 32]> {
+    bs58::decode(key)
+        .into_vec()
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| anyhow!("Invalid key {}, expected 32 bytes in base58", key))
+}
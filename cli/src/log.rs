@@ -1,36 +1,110 @@
+use anyhow::{anyhow, Result};
 use colored::Colorize;
+use dialoguer::{theme::ColorfulTheme, Confirm};
+use std::io::IsTerminal;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static TO_STDERR: AtomicBool = AtomicBool::new(false);
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+/// Sends all further output to stderr, keeping stdout for data (e.g. `read` without `-o`).
+pub fn use_stderr() {
+    TO_STDERR.store(true, Ordering::Relaxed);
+}
+
+/// Drops all further output but errors and results, for scripts (`--quiet`).
+pub fn use_quiet() {
+    QUIET.store(true, Ordering::Relaxed);
+}
+
+pub fn is_quiet() -> bool {
+    QUIET.load(Ordering::Relaxed)
+}
+
+fn emit(line: impl std::fmt::Display) {
+    if is_quiet() {
+        return;
+    }
+    if TO_STDERR.load(Ordering::Relaxed) {
+        eprintln!("{}", line);
+    } else {
+        println!("{}", line);
+    }
+}
+
+/// Asks `prompt` and returns the answer. Without a terminal on stdin (CI, cron) there's nobody to
+/// answer, so it's taken as yes, as it is with `yes` (`--yes`).
+pub fn confirm(prompt: &str, yes: bool) -> Result<bool> {
+    if yes || !std::io::stdin().is_terminal() {
+        return Ok(true);
+    }
+
+    Confirm::with_theme(&ColorfulTheme::default())
+        .with_prompt(prompt)
+        .default(false)
+        .interact()
+        .map_err(|e| anyhow!("Failed to get user input: {}", e))
+}
 
 /// Prints a bold, prominent title for major sections of output.
 pub fn print_title(text: &str) {
-    println!("{}", format!("\n{}", text).bold());
+    emit(format!("\n{}", text).bold());
 }
 
 /// Prints a plain informational message.
 pub fn print_info(text: &str) {
-    println!("{}", text);
+    emit(text);
 }
 
 /// Prints an empty line to separate sections of output.
 pub fn print_divider() {
-    println!();
+    emit("");
 }
 
 /// Prints a highlighted section header with yellow bold text and surrounding markers.
 pub fn print_section_header(text: &str) {
-    println!("{}", format!("\n=== {} ===", text).yellow().bold());
+    emit(format!("\n=== {} ===", text).yellow().bold());
 }
 
 /// Prints an informational message with a cyan arrow prefix for emphasis.
 pub fn print_message(text: &str) {
-    println!("{}", format!("→ {}", text).cyan());
+    emit(format!("→ {}", text).cyan());
 }
 
 /// Prints a count or metric with a blue diamond prefix for quantitative data.
 pub fn print_count(text: &str) {
-    println!("{}", format!("⟐ {}", text).blue());
+    emit(format!("⟐ {}", text).blue());
 }
 
-/// Prints an error message with a red cross prefix to indicate failure.
+/// Prints an error message with a red cross prefix to indicate failure. Quiet mode still prints
+/// it, to stderr.
 pub fn print_error(text: &str) {
-    println!("{}", format!("✗ {}", text).red());
+    if is_quiet() {
+        eprintln!("{}", format!("✗ {}", text).red());
+    } else {
+        emit(format!("✗ {}", text).red());
+    }
+}
+
+/// Prints what a command produced, e.g. a written tape's address, bare and only in quiet mode,
+/// where it's the one line on stdout. Otherwise the command shows it its own way.
+pub fn print_result(text: &str) {
+    if is_quiet() {
+        println!("{}", text);
+    }
+}
+
+/// Prints a pass/fail row with a green check or red cross, a padded label, and details.
+pub fn print_check(label: &str, passed: bool, detail: &str) {
+    let row = format!("{} {:<12} {}", if passed { "✓" } else { "✗" }, label, detail);
+    if passed {
+        emit(row.green());
+    } else {
+        emit(row.red());
+    }
+}
+
+/// Formats base units of TAPE in both units, e.g. `1.5 TAPE (15000000000)`.
+pub fn tape_amount(units: u64) -> String {
+    format!("{} TAPE ({})", tape_client::format_tape_amount(units), units)
 }
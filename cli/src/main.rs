@@ -1,32 +1,78 @@
 mod cli;
 mod keypair;
+mod keyring;
 mod log;
+mod progress;
 mod commands;
 
 use anyhow::Result;
-use clap::Parser;
+use clap::{CommandFactory, Parser};
 use solana_client::nonblocking::rpc_client::RpcClient;
 use solana_sdk::commitment_config::CommitmentConfig;
+use tape_api::prelude::ProgramContext;
 
-use cli::{Cli, Commands};
+use cli::{Cli, Commands, KeyCommands};
 use keypair::{ get_payer, get_keypair_path };
-use commands::{admin, read, write, misc, network, claim};
+use commands::{admin, backup, read, history, audit, write, submit, batch, sweep, misc, network, claim, keys, snapshot, doctor, airdrop, dev};
 
 #[tokio::main]
 async fn main() -> Result<()> {
+    let cli = Cli::parse();
+
+    // Completions go to stdout as-is, so handle them before printing anything else
+    if let Commands::Completions { shell } = cli.command {
+        clap_complete::generate(shell, &mut Cli::command(), "tapedrive", &mut std::io::stdout());
+        return Ok(());
+    }
+
+    if let Some(path) = &cli.log_file {
+        tape_network::logfile::init(path)?;
+    }
+    tape_network::logfile::init_logger()?;
+
+    // Reading to stdout (or printing JSON) keeps stdout for the output alone
+    if let Commands::Read { output: None, .. } | Commands::History { json: true, .. }
+        | Commands::Diff { json: true, .. } | Commands::Keys { command: KeyCommands::Export { .. } } = cli.command {
+        log::use_stderr();
+    }
+    if cli.quiet {
+        log::use_quiet();
+    }
+
     log::print_title("⊙⊙ TAPEDRIVE");
 
-    let cli = Cli::parse();
+    tape_client::set_simulation(!cli.skip_simulation);
+
+    let program = match &cli.program_id {
+        Some(program_id) => tape_client::parse_program_context(program_id)?,
+        None => ProgramContext::from_env()?,
+    };
+    tape_client::set_program_context(program)?;
+    if !program.is_default() {
+        log::print_message(&format!("Using program {}", program.program_id));
+    }
+
     let rpc_url = cli.cluster.rpc_url();
     let rpc_client = RpcClient::new_with_commitment(rpc_url.clone(), CommitmentConfig::finalized());
     let keypair_path = get_keypair_path(cli.keypair_path.clone());
 
     match cli.command {
         Commands::Initialize { .. } |
+        Commands::InitializeConfig { .. } |
         Commands::Epoch { .. } |
+        Commands::Pause { .. } |
+        Commands::Resume { .. } |
+        Commands::SetGuardian { .. } |
+        Commands::SetMinerDeposit { .. } |
+        Commands::Migrate { .. } |
         Commands::Write { .. } | 
+        Commands::WriteBatch { .. } |
+        Commands::SweepWriters { .. } |
+        Commands::Backup { .. } |
         Commands::Register { .. } |
-        Commands::Mine { .. }
+        Commands::Mine { .. } |
+        Commands::Airdrop { .. } |
+        Commands::Dev { .. }
         => {
             log::print_message(&format!(
                 "Using keypair from {}",
@@ -42,8 +88,14 @@ async fn main() -> Result<()> {
         // Admin Commands
 
         Commands::Initialize { .. } | 
-        Commands::Epoch { .. } => {
-            let payer = get_payer(keypair_path)?;
+        Commands::InitializeConfig { .. } |
+        Commands::Epoch { .. } |
+        Commands::Pause { .. } |
+        Commands::Resume { .. } |
+        Commands::SetGuardian { .. } |
+        Commands::SetMinerDeposit { .. } |
+        Commands::Migrate { .. } => {
+            let payer = get_payer(keypair_path, cli.mnemonic, cli.derivation_path.as_deref()).await?;
             admin::handle_admin_commands(cli, rpc_client, payer).await?;
         }
 
@@ -52,28 +104,96 @@ async fn main() -> Result<()> {
         Commands::Read { .. } => {
             read::handle_read_command(cli, rpc_client).await?;
         }
-        Commands::Write { .. } => {
-            let payer = get_payer(keypair_path)?;
+        Commands::History { .. } => {
+            history::handle_history_command(cli, rpc_client).await?;
+        }
+        Commands::Audit { .. } => {
+            audit::handle_audit_command(cli, rpc_client).await?;
+        }
+        Commands::Diff { .. } => {
+            history::handle_diff_command(cli, rpc_client).await?;
+        }
+        Commands::Write { filename: Some(ref pattern), .. } if batch::is_glob(pattern) && !std::path::Path::new(pattern).exists() => {
+            let payer = get_payer(keypair_path, cli.mnemonic, cli.derivation_path.as_deref()).await?;
+            airdrop::offer_airdrop(&rpc_client, payer.as_ref()).await;
+            batch::handle_write_glob_command(cli, rpc_client, payer).await?;
+        }
+                Commands::Write { offline, .. } => {
+            let payer = get_payer(keypair_path, cli.mnemonic, cli.derivation_path.as_deref()).await?;
+            if !offline {
+                airdrop::offer_airdrop(&rpc_client, payer.as_ref()).await;
+            }
             write::handle_write_command(cli, rpc_client, payer).await?;
         }
+        Commands::Submit { .. } => {
+            submit::handle_submit_command(cli, rpc_client, keypair_path).await?;
+        }
+        Commands::WriteBatch { .. } => {
+            let payer = get_payer(keypair_path, cli.mnemonic, cli.derivation_path.as_deref()).await?;
+            batch::handle_write_batch_command(cli, rpc_client, payer).await?;
+        }
+        Commands::SweepWriters { .. } => {
+            let payer = get_payer(keypair_path, cli.mnemonic, cli.derivation_path.as_deref()).await?;
+            sweep::handle_sweep_writers_command(cli, rpc_client, payer).await?;
+        }
+        Commands::Backup { .. } => {
+            let payer = get_payer(keypair_path, cli.mnemonic, cli.derivation_path.as_deref()).await?;
+            backup::handle_backup_command(cli, rpc_client, payer).await?;
+        }
+        Commands::Restore { .. } => {
+            backup::handle_restore_command(cli, rpc_client).await?;
+        }
 
         // Miner Commands
 
         Commands::Claim { .. } => {
-            let payer = get_payer(keypair_path)?;
+            let payer = get_payer(keypair_path, cli.mnemonic, cli.derivation_path.as_deref()).await?;
             claim::handle_claim_command(cli, rpc_client, payer).await?;
         }
+        Commands::SetBeneficiary { .. } => {
+            claim::handle_set_beneficiary_command(cli)?;
+        }
 
         // Network Commands
 
+        // Pool workers only solve, the pool's miner signs and pays
+        Commands::Mine { pool: Some(ref url), worker: Some(ref worker), .. } => {
+            network::pool_worker(url, worker).await?;
+        }
         Commands::Register { .. } |
         Commands::Web { .. } |
         Commands::Archive { .. } |
         Commands::Mine { .. } => {
-            let payer = get_payer(keypair_path)?;
+            let payer = get_payer(keypair_path, cli.mnemonic, cli.derivation_path.as_deref()).await?;
             network::handle_network_commands(cli, rpc_client, payer).await?;
         }
 
+        // Snapshot Commands
+
+        Commands::Snapshot { .. } => {
+            snapshot::handle_snapshot_commands(cli, rpc_client).await?;
+        }
+
+        // Key Commands
+
+        Commands::Keys { .. } => {
+            keys::handle_keys_command(cli)?;
+        }
+
+        // Setup Commands
+
+        Commands::Doctor {} => {
+            doctor::handle_doctor_command(cli, rpc_client, keypair_path).await?;
+        }
+        Commands::Airdrop { .. } => {
+            let payer = get_payer(keypair_path, cli.mnemonic, cli.derivation_path.as_deref()).await?;
+            airdrop::handle_airdrop_command(cli, rpc_client, payer).await?;
+        }
+        Commands::Dev { .. } => {
+            let payer = get_payer(keypair_path, cli.mnemonic, cli.derivation_path.as_deref()).await?;
+            dev::handle_dev_commands(cli, rpc_client, payer).await?;
+        }
+
         // Miscellaneous Commands
 
         _ => {
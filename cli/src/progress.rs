@@ -0,0 +1,128 @@
+use indicatif::{HumanBytes, ProgressBar, ProgressDrawTarget, ProgressState, ProgressStyle};
+use std::fmt::Write;
+use std::ops::Deref;
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
+
+use crate::log;
+
+/// A progress bar of `len` steps, drawn nowhere in quiet mode.
+pub fn new_bar(len: u64) -> ProgressBar {
+    if log::is_quiet() {
+        ProgressBar::with_draw_target(Some(len), ProgressDrawTarget::hidden())
+    } else {
+        ProgressBar::new(len)
+    }
+}
+
+/// A spinner with no length yet, drawn nowhere in quiet mode.
+pub fn new_spinner() -> ProgressBar {
+    if log::is_quiet() {
+        ProgressBar::hidden()
+    } else {
+        ProgressBar::new_spinner()
+    }
+}
+
+/// Style for reading a tape: segments read, how fast, and how long until done.
+pub fn read_style() -> ProgressStyle {
+    ProgressStyle::default_bar()
+        .template("{spinner:.green} [{bar:40.white/gray}] {pos}/{len} segments {rate} ETA {eta} {wide_msg}")
+        .expect("Failed to set progress style")
+        .with_key("rate", |state: &ProgressState, w: &mut dyn Write| {
+            let bytes = state.pos() * tape_api::consts::SEGMENT_SIZE as u64;
+            let _ = write!(w, "{}/s", HumanBytes(per_second(bytes, state)));
+        })
+}
+
+/// The progress bar of a write, one step per transaction. Alongside the bar it counts the bytes
+/// sent and the writes that failed, to show the rates, an ETA and the failures so far.
+#[derive(Clone)]
+pub struct WriteProgress {
+    pb: ProgressBar,
+    bytes: Arc<AtomicU64>,
+    failures: Arc<AtomicU64>,
+}
+
+impl WriteProgress {
+    pub fn new(pb: ProgressBar) -> Self {
+        let progress = Self {
+            pb,
+            bytes: Arc::new(AtomicU64::new(0)),
+            failures: Arc::new(AtomicU64::new(0)),
+        };
+        progress.show_spinner();
+        progress
+    }
+
+    /// Record `bytes` sent, whether or not they land.
+    pub fn add_bytes(&self, bytes: usize) {
+        self.bytes.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    /// Record `count` writes that didn't land, or had to be sent again.
+    pub fn add_failures(&self, count: usize) {
+        self.failures.fetch_add(count as u64, Ordering::Relaxed);
+    }
+
+    pub fn bytes(&self) -> u64 {
+        self.bytes.load(Ordering::Relaxed)
+    }
+
+    pub fn failures(&self) -> u64 {
+        self.failures.load(Ordering::Relaxed)
+    }
+
+    /// Just a spinner and the message, while there's nothing to count (e.g. creating a tape).
+    pub fn show_spinner(&self) {
+        self.pb.set_style(
+            ProgressStyle::default_bar()
+                .template("{spinner:.green} {wide_msg}")
+                .expect("Failed to set progress style"),
+        );
+    }
+
+    /// The bar, with transactions and bytes a second, the time left and any failures.
+    pub fn show_bar(&self) {
+        let bytes = self.bytes.clone();
+        let failures = self.failures.clone();
+
+        self.pb.set_style(
+            ProgressStyle::default_bar()
+                .template("{spinner:.green} [{bar:40.white/gray}] {pos}/{len} {rates} ETA {eta}{failures} {wide_msg}")
+                .expect("Failed to set progress style")
+                .with_key("rates", move |state: &ProgressState, w: &mut dyn Write| {
+                    let sent = bytes.load(Ordering::Relaxed);
+                    let _ = write!(w, "{:.1} tx/s {}/s", state.per_sec(), HumanBytes(per_second(sent, state)));
+                })
+                .with_key("failures", move |_: &ProgressState, w: &mut dyn Write| {
+                    match failures.load(Ordering::Relaxed) {
+                        0 => {}
+                        n => {
+                            let _ = write!(w, " ({} failed)", n);
+                        }
+                    }
+                }),
+        );
+    }
+}
+
+impl Deref for WriteProgress {
+    type Target = ProgressBar;
+
+    fn deref(&self) -> &ProgressBar {
+        &self.pb
+    }
+}
+
+/// `amount` over the time the bar has been running.
+fn per_second(amount: u64, state: &ProgressState) -> u64 {
+    let elapsed = state.elapsed().as_secs_f64();
+    if elapsed > 0.0 {
+        (amount as f64 / elapsed) as u64
+    } else {
+        0
+    }
+}
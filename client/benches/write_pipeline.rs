@@ -0,0 +1,214 @@
+//! Throughput of the write path, from encoding a payload to sending its writes. Run with
+//! `cargo bench -p tape-client`.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use futures::stream::{self, StreamExt};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{
+    hash::Hash,
+    pubkey::Pubkey,
+    signature::{Keypair, Signature, Signer},
+    transaction::Transaction,
+};
+use std::time::Duration;
+use tape_api::prelude::*;
+use tape_client::{
+    chunk_size,
+    encode_tape,
+    send_signed_write,
+    set_simulation,
+    sign_write,
+    write_linked_chunk,
+    write_to_tape,
+    SigningPool,
+    consts::{SAFE_SIZE, SEGMENTS_PER_TX},
+    CompressionAlgo,
+    EncryptionAlgo,
+    MimeType,
+    TapeFlags,
+    TapeHeader,
+};
+
+const SIZES: [usize; 3] = [4 * 1024, 256 * 1024, 4 * 1024 * 1024];
+
+/// Writes sent per iteration of the pipeline benchmark.
+const WRITES: usize = 32;
+
+/// How long the mock RPC takes to confirm a write.
+const CONFIRM_LATENCY: Duration = Duration::from_millis(20);
+
+const CONCURRENCY: [usize; 4] = [1, 4, 16, 64];
+
+/// Newline separated JSON records, which compress about as well as typical uploads.
+fn payload(len: usize) -> Vec<u8> {
+    let mut data = Vec::with_capacity(len + 64);
+    let mut i = 0u64;
+    while data.len() < len {
+        data.extend_from_slice(format!("{{\"id\":{},\"value\":{}}}\n", i, i * 7919 % 10007).as_bytes());
+        i += 1;
+    }
+    data.truncate(len);
+    data
+}
+
+fn header(compression: CompressionAlgo) -> TapeHeader {
+    TapeHeader::new(MimeType::ApplicationJson, compression, EncryptionAlgo::None, TapeFlags::Linked)
+}
+
+fn bench_encode(c: &mut Criterion) {
+    let mut group = c.benchmark_group("encode_tape");
+
+    for compression in [CompressionAlgo::None, CompressionAlgo::Gzip] {
+        let header = header(compression);
+        for size in SIZES {
+            let data = payload(size);
+            group.throughput(Throughput::Bytes(size as u64));
+            group.bench_with_input(BenchmarkId::new(format!("{:?}", compression), size), &data, |b, data| {
+                b.iter(|| encode_tape(data, &header).unwrap())
+            });
+        }
+    }
+
+    group.finish();
+}
+
+fn bench_chunking(c: &mut Criterion) {
+    let mut group = c.benchmark_group("chunking");
+    let header = header(CompressionAlgo::None);
+    let last_sig = Signature::default();
+
+    for size in SIZES {
+        let encoded = encode_tape(&payload(size), &header).unwrap();
+        group.throughput(Throughput::Bytes(size as u64));
+
+        // Split into linked chunks, the same way write_tape does
+        group.bench_with_input(BenchmarkId::from_parameter(size), &encoded, |b, encoded| {
+            b.iter(|| {
+                encoded
+                    .chunks(chunk_size(&header))
+                    .map(|chunk| [last_sig.as_ref(), chunk].concat())
+                    .collect::<Vec<_>>()
+            })
+        });
+    }
+
+    group.finish();
+}
+
+fn bench_write_instruction(c: &mut Criterion) {
+    let mut group = c.benchmark_group("write_instruction");
+    let payer = Keypair::new();
+    let tape_address = Pubkey::new_unique();
+    let writer_address = Pubkey::new_unique();
+    let chunk = payload(SAFE_SIZE);
+    let blockhash = Hash::new_unique();
+
+    group.throughput(Throughput::Bytes(SAFE_SIZE as u64));
+    group.bench_function("sign_and_serialize", |b| {
+        b.iter(|| {
+            let ix = build_write_ix(payer.pubkey(), tape_address, writer_address, &chunk);
+            let tx = Transaction::new_signed_with_payer(&[ix], Some(&payer.pubkey()), &[&payer], blockhash);
+            bincode::serialize(&tx).unwrap()
+        })
+    });
+
+    group.finish();
+}
+
+/// Segments per second through `write_to_tape` at different numbers of writes in flight. The RPC
+/// is mocked, so this measures the client's own overhead against a fixed confirmation latency,
+/// not a real cluster.
+fn bench_pipeline(c: &mut Criterion) {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let client = RpcClient::new_mock("succeeds".to_string());
+    let payer = Keypair::new();
+    let tape_address = Pubkey::new_unique();
+    let writer_address = Pubkey::new_unique();
+    let chunk = payload(SAFE_SIZE);
+
+    set_simulation(false);
+
+    let mut group = c.benchmark_group("write_pipeline");
+    group.sample_size(10);
+    group.throughput(Throughput::Elements((WRITES * SEGMENTS_PER_TX) as u64));
+
+    for concurrency in CONCURRENCY {
+        group.bench_function(BenchmarkId::new("in_flight", concurrency), |b| {
+            b.to_async(&runtime).iter(|| async {
+                stream::iter(0..WRITES)
+                    .map(|_| async {
+                        write_to_tape(&client, &payer, tape_address, writer_address, &chunk)
+                            .await
+                            .unwrap();
+                        tokio::time::sleep(CONFIRM_LATENCY).await;
+                    })
+                    .buffer_unordered(concurrency)
+                    .collect::<Vec<_>>()
+                    .await
+            })
+        });
+    }
+
+    group.finish();
+}
+
+/// Linked writes per second signed inline on the task sending them, as `write_linked_chunk`
+/// does, against signed on a `SigningPool` while the write before is sent. The RPC is mocked, so
+/// this only shows what taking signing off the async task saves.
+fn bench_signing(c: &mut Criterion) {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let client = RpcClient::new_mock("succeeds".to_string());
+    let payer = Keypair::new();
+    let pool = SigningPool::with_default_workers(payer.insecure_clone());
+    let tape_address = Pubkey::new_unique();
+    let writer_address = Pubkey::new_unique();
+    let chunk = payload(SAFE_SIZE - 64);
+    let blockhash = Hash::new_unique();
+
+    set_simulation(false);
+
+    let mut group = c.benchmark_group("linked_writes");
+    group.sample_size(10);
+    group.throughput(Throughput::Elements(WRITES as u64));
+
+    group.bench_function("sign_inline", |b| {
+        b.to_async(&runtime).iter(|| async {
+            let mut last_sig = Signature::default();
+            for _ in 0..WRITES {
+                (last_sig, _) = write_linked_chunk(&client, &payer, tape_address, writer_address, last_sig, &chunk)
+                    .await
+                    .unwrap();
+            }
+        })
+    });
+
+    group.bench_function("sign_on_pool", |b| {
+        b.to_async(&runtime).iter(|| async {
+            let mut write = sign_write(&pool, tape_address, writer_address, Some(Signature::default()), &chunk, blockhash)
+                .await
+                .unwrap();
+
+            for i in 0..WRITES {
+                let sign_next = async {
+                    if i + 1 == WRITES {
+                        return None;
+                    }
+                    let prev = Some(write.signature());
+                    Some(sign_write(&pool, tape_address, writer_address, prev, &chunk, blockhash).await.unwrap())
+                };
+
+                let (sent, next) = futures::join!(send_signed_write(&client, &pool, &write), sign_next);
+                sent.unwrap();
+                match next {
+                    Some(next) => write = next,
+                    None => break,
+                }
+            }
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_encode, bench_chunking, bench_write_instruction, bench_pipeline, bench_signing);
+criterion_main!(benches);
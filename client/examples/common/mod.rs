@@ -0,0 +1,56 @@
+//! Cluster settings shared by the examples, read from the environment:
+//!
+//! - `TAPE_RPC_URL`, the cluster's RPC, localnet by default
+//! - `TAPE_WS_URL`, its websocket, derived from the RPC URL by default
+//! - `TAPE_KEYPAIR`, the keypair paying for transactions, the Solana CLI's by default
+//! - `TAPE_PROGRAM_ID`, a deployment of the tape program other than the default
+
+#![allow(dead_code)]
+
+use anyhow::{anyhow, Result};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    signature::{read_keypair_file, Keypair},
+};
+use std::env;
+
+const DEFAULT_RPC_URL: &str = "http://127.0.0.1:8899";
+
+pub fn rpc_url() -> String {
+    env::var("TAPE_RPC_URL").unwrap_or_else(|_| DEFAULT_RPC_URL.to_string())
+}
+
+/// The websocket of the cluster, which on localnet listens on the port after the RPC.
+pub fn ws_url() -> String {
+    if let Ok(url) = env::var("TAPE_WS_URL") {
+        return url;
+    }
+
+    rpc_url()
+        .replacen("http", "ws", 1)
+        .replace(":8899", ":8900")
+}
+
+pub fn client() -> RpcClient {
+    RpcClient::new_with_commitment(rpc_url(), CommitmentConfig::confirmed())
+}
+
+pub fn keypair() -> Result<Keypair> {
+    let path = match env::var("TAPE_KEYPAIR") {
+        Ok(path) => path,
+        Err(_) => {
+            let home = env::var("HOME").map_err(|_| anyhow!("Set TAPE_KEYPAIR, there's no HOME to look in"))?;
+            format!("{}/.config/solana/id.json", home)
+        }
+    };
+
+    read_keypair_file(&path).map_err(|e| anyhow!("Failed to read keypair {}: {}", path, e))
+}
+
+/// The positional argument at `index`, or a usage error.
+pub fn arg(index: usize, usage: &str) -> Result<String> {
+    env::args()
+        .nth(index)
+        .ok_or_else(|| anyhow!("Usage: {}", usage))
+}
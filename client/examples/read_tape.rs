@@ -0,0 +1,35 @@
+//! Read a tape back over RPC and save its data, named after the tape.
+//!
+//! ```text
+//! cargo run -p tape-client --example read_tape -- <tape address> [output path]
+//! ```
+//!
+//! See `common` for the cluster settings. Manifests and recipes are reassembled on the way, sealed
+//! tapes need `fetch_tape_opened_with` and the recipient's key.
+
+mod common;
+
+use anyhow::Result;
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+use tape_client::{fetch_tape_with, file_name, get_tape_account};
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let tape_address = Pubkey::from_str(&common::arg(1, "read_tape <tape address> [output path]")?)?;
+    let client = common::client();
+
+    let (tape, _) = get_tape_account(&client, &tape_address).await?;
+    let decoded = fetch_tape_with(&client, &tape_address, None, |read, total| {
+        println!("Read {} of {} segments", read, total);
+    })
+    .await?;
+
+    let output = std::env::args()
+        .nth(2)
+        .unwrap_or_else(|| file_name(&tape.name, &decoded.mime));
+    std::fs::write(&output, &decoded.bytes)?;
+
+    println!("Saved {} bytes of {} to {}", decoded.bytes.len(), decoded.mime, output);
+    Ok(())
+}
@@ -0,0 +1,86 @@
+//! Mine without an archive of your own: fetch each recall tape from a trusted peer running
+//! `tapedrive web`, prove and solve the challenge, and submit it. Runs until interrupted.
+//!
+//! ```text
+//! cargo run -p tape-client --release --example run_miner_light -- <miner address> <peer url>
+//! ```
+//!
+//! See `common` for the cluster settings, the keypair must be the miner's authority. Register a
+//! miner first with `tapedrive register`.
+
+mod common;
+
+use anyhow::{anyhow, bail, Result};
+use chrono::Utc;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{pubkey::Pubkey, signer::Signer};
+use std::{str::FromStr, time::Duration};
+use tape_api::prelude::*;
+use tape_client::{
+    advance,
+    ensure_not_paused,
+    find_tape_account,
+    get_epoch_account,
+    get_miner_account,
+    get_tape_account,
+    mine::{
+        mine::perform_mining,
+        recall::{proof_matches_root, recall_proof, solve_challenge},
+    },
+    PeerSource,
+    SegmentSource,
+};
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let usage = "run_miner_light <miner address> <peer url>";
+    let miner_address = Pubkey::from_str(&common::arg(1, usage)?)?;
+    let peer = PeerSource::new(&common::arg(2, usage)?);
+
+    let client = common::client();
+    let keypair = common::keypair()?;
+
+    loop {
+        if let Err(e) = mine_once(&client, &keypair, &miner_address, &peer).await {
+            eprintln!("Mining failed: {:?}", e);
+        }
+        tokio::time::sleep(Duration::from_secs(60)).await;
+    }
+}
+
+async fn mine_once(client: &RpcClient, signer: &dyn Signer, miner_address: &Pubkey, peer: &PeerSource) -> Result<()> {
+    ensure_not_paused(client).await?;
+
+    // Anyone can advance a stale epoch, the miner does so it isn't held up
+    let (epoch, _) = get_epoch_account(client).await?;
+    if Utc::now().timestamp() > epoch.last_epoch_at + EPOCH_SECONDS {
+        advance(client, signer).await?;
+    }
+
+    let (miner, _) = get_miner_account(client, miner_address).await?;
+    let (tape_address, _) = find_tape_account(client, miner.recall_tape)
+        .await?
+        .ok_or_else(|| anyhow!("Recall tape {} not found", miner.recall_tape))?;
+    let (tape, _) = get_tape_account(client, &tape_address).await?;
+
+    let mut segments = peer.read_tape(&tape_address, tape.total_segments).await?;
+    segments.sort_by_key(|(segment_number, _)| *segment_number);
+
+    let numbered = segments.iter().map(|(segment_number, data)| (*segment_number, data.as_slice()));
+    let (segment, proof) = recall_proof(&tape, &miner, numbered)?;
+
+    // Solving takes minutes, check the proof would land before spending them
+    let (_, segment_number) = compute_recall_indices(&miner, &tape);
+    if !proof_matches_root(&tape, segment_number, &segment, &proof) {
+        bail!("{} served a stale copy of tape {}", peer.url(), tape_address);
+    }
+
+    println!("Solving challenge on segment {} of tape {}...", segment_number, tape_address);
+    let solution = solve_challenge(miner.current_challenge, &segment, epoch.difficulty)
+        .map_err(|e| anyhow!("Failed to solve challenge: {:?}", e))?;
+
+    let signature = perform_mining(client, signer, *miner_address, tape_address, solution, segment, proof).await?;
+    println!("Proof landed: {}", signature);
+
+    Ok(())
+}
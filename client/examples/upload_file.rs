@@ -0,0 +1,82 @@
+//! Write a file to a new linked tape, one chunk at a time, and finalize it. Files small enough
+//! go on in a single transaction instead.
+//!
+//! ```text
+//! cargo run -p tape-client --example upload_file -- <path> [name]
+//! ```
+//!
+//! See `common` for the cluster settings. The CLI's `tapedrive write` does the same with
+//! concurrent writes, retries and files bigger than a tape.
+
+mod common;
+
+use anyhow::{bail, Result};
+use mime_guess::MimeGuess;
+use std::path::Path;
+use tape_client::{
+    chunk_size,
+    consts::ATOMIC_WRITE_SIZE,
+    create_small_tape,
+    create_tape,
+    encode_tape,
+    finalize_tape,
+    mime_to_type,
+    tape_capacity,
+    write_linked_chunk,
+    CompressionAlgo,
+    EncryptionAlgo,
+    TapeFlags,
+    TapeHeader,
+};
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let usage = "upload_file <path> [name]";
+    let path = common::arg(1, usage)?;
+    let name = match std::env::args().nth(2) {
+        Some(name) => name,
+        None => Path::new(&path)
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "example".to_string()),
+    };
+
+    let client = common::client();
+    let keypair = common::keypair()?;
+    let data = std::fs::read(&path)?;
+
+    let (mime_type, mime_str) = mime_to_type(&MimeGuess::from_path(&path).first_or_octet_stream());
+    let mut header = TapeHeader::new(mime_type, CompressionAlgo::Gzip, EncryptionAlgo::None, TapeFlags::Linked);
+    header.mime_str = mime_str;
+
+    let encoded = encode_tape(&data, &header)?;
+
+    if encoded.len() <= ATOMIC_WRITE_SIZE {
+        let (tape_address, signature) = create_small_tape(&client, &keypair, &name, header, &encoded).await?;
+        println!("Wrote {} in one transaction {}", tape_address, signature);
+        return Ok(());
+    }
+
+    if encoded.len() > tape_capacity(&header) {
+        bail!(
+            "{} is {} bytes encoded, more than a tape holds; split it with `split_into_tapes`",
+            path, encoded.len()
+        );
+    }
+
+    let (tape_address, writer_address, mut signature) = create_tape(&client, &keypair, &name, header).await?;
+    println!("Created tape {}", tape_address);
+
+    // Each write links back to the one before, the first to the create transaction
+    let chunks = encoded.chunks(chunk_size(&header)).collect::<Vec<_>>();
+    for (i, chunk) in chunks.iter().enumerate() {
+        (signature, _) = write_linked_chunk(&client, &keypair, tape_address, writer_address, signature, chunk).await?;
+        println!("Wrote chunk {} of {}", i + 1, chunks.len());
+    }
+
+    header.tail_signature = signature.into();
+    finalize_tape(&client, &keypair, tape_address, writer_address, header).await?;
+
+    println!("Finalized {}, read it back with the read_tape example", tape_address);
+    Ok(())
+}
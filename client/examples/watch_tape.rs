@@ -0,0 +1,48 @@
+//! Print changes to a tape as the cluster confirms them, until it's finalized or interrupted.
+//!
+//! ```text
+//! cargo run -p tape-client --example watch_tape -- <tape address>
+//! ```
+//!
+//! See `common` for the cluster settings. Start it before running the upload_file example to
+//! watch the tape fill up.
+
+mod common;
+
+use anyhow::Result;
+use futures::StreamExt;
+use solana_client::nonblocking::pubsub_client::PubsubClient;
+use solana_sdk::pubkey::Pubkey;
+use std::{str::FromStr, sync::Arc};
+use tape_api::prelude::*;
+use tape_client::{watch_tape, TapeChange};
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let tape_address = Pubkey::from_str(&common::arg(1, "watch_tape <tape address>")?)?;
+    let client_ws = Arc::new(PubsubClient::new(&common::ws_url()).await?);
+
+    let changes = watch_tape(client_ws, &tape_address).await?;
+    futures::pin_mut!(changes);
+
+    println!("Watching {}", tape_address);
+    while let Some(change) = changes.next().await {
+        match change {
+            TapeChange::Account { slot, tape } => {
+                println!("[{}] {} segments, {} bytes", slot, tape.total_segments, tape.total_size);
+                if tape.state == u64::from(TapeState::Finalized) {
+                    println!("Finalized");
+                    break;
+                }
+            }
+            TapeChange::Write { slot, signature, event } => {
+                println!("[{}] {} segments written by {}", slot, event.num_added, signature);
+            }
+            TapeChange::Update { slot, signature, event } => {
+                println!("[{}] Segment {} replaced by {}", slot, event.segment_number, signature);
+            }
+        }
+    }
+
+    Ok(())
+}
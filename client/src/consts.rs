@@ -1,4 +1,13 @@
+use tape_api::consts::SEGMENT_SIZE;
+
 pub const MAX_RETRIES: u32 = 30;
 pub const VERIFY_EVERY: usize = 500;
 pub const WAIT_TIME: u64 = 32; // seconds
 pub const LAMPORTS_PER_TX: u64 = 5000;
+pub const MAX_IN_FLIGHT: usize = 64; // Upper bound on unconfirmed writes per tape
+
+pub const SEGMENTS_PER_TX: usize = 7; // 7 x 128 = 896 bytes
+pub const SAFE_SIZE: usize = SEGMENT_SIZE * SEGMENTS_PER_TX;
+pub const WIDE_SIZE: usize = 952; // Bytes (chunk size when writing through an address lookup table)
+pub const SIGNATURE_SIZE: usize = 64; // Bytes (link prefix on linked tapes)
+pub const ATOMIC_WRITE_SIZE: usize = 512; // Bytes (create+write+finalize fit in one transaction)
@@ -1,10 +1,16 @@
 pub mod consts;
-pub mod program;
 pub mod utils;
+#[cfg(feature = "rpc")]
+pub mod program;
+#[cfg(feature = "encoding")]
 pub mod tape;
+#[cfg(feature = "rpc")]
 pub mod mine;
 
 pub use utils::*;
+#[cfg(feature = "rpc")]
 pub use program::*;
+#[cfg(feature = "encoding")]
 pub use tape::*;
+#[cfg(feature = "rpc")]
 pub use mine::*;
@@ -1,31 +1,56 @@
 use anyhow::{anyhow, Result};
 use solana_sdk::{
     compute_budget::ComputeBudgetInstruction,
-    signature::{Keypair, Signature, Signer},
+    signature::{Signature, Signer},
     transaction::Transaction,
     pubkey::Pubkey,
     instruction::Instruction,
+    program_pack::Pack,
 };
 use solana_client::nonblocking::rpc_client::RpcClient;
+use spl_associated_token_account::instruction::create_associated_token_account_idempotent;
 
 use tape_api::prelude::*;
 use crate::utils::*;
 
+/// Compute units for the claim itself.
+const CLAIM_UNITS: u32 = 50_000;
+
+/// Extra compute units when the beneficiary's token account is created in the same transaction.
+const CREATE_ATA_UNITS: u32 = 40_000;
+
+/// Claims `amount` of a miner's rewards to `beneficiary`, either a TAPE token account or a
+/// wallet. A wallet without a TAPE token account gets one in the same transaction, paid for by
+/// the signer, so first-time claims need no separate setup.
 pub async fn claim_rewards(
     client: &RpcClient,
-    signer: &Keypair,
+    signer: &dyn Signer,
     miner: Pubkey,
     beneficiary: Pubkey,
     amount: u64,
 ) -> Result<Signature> {
+    let (token_account, missing) = resolve_beneficiary(client, &beneficiary).await?;
+    let program = program_context();
 
-    let compute_budget_ix = ComputeBudgetInstruction::set_compute_unit_limit(50_000);
-    let claim_ix: Instruction = build_claim_ix(signer.pubkey(), miner, beneficiary, amount);
+    let mut instructions: Vec<Instruction> = Vec::with_capacity(3);
+    if missing {
+        instructions.push(ComputeBudgetInstruction::set_compute_unit_limit(CLAIM_UNITS + CREATE_ATA_UNITS));
+        // Idempotent, in case someone else creates it first
+        instructions.push(create_associated_token_account_idempotent(
+            &signer.pubkey(),
+            &beneficiary,
+            &program.mint_address(),
+            &spl_token::ID,
+        ));
+    } else {
+        instructions.push(ComputeBudgetInstruction::set_compute_unit_limit(CLAIM_UNITS));
+    }
+    instructions.push(program.build_claim_ix(signer.pubkey(), miner, token_account, amount));
 
     let blockhash_bytes = get_latest_blockhash(client).await?;
     let recent_blockhash = deserialize(&blockhash_bytes)?;
     let tx = Transaction::new_signed_with_payer(
-        &[compute_budget_ix, claim_ix],
+        &instructions,
         Some(&signer.pubkey()),
         &[signer],
         recent_blockhash,
@@ -39,3 +64,40 @@ pub async fn claim_rewards(
 
     Ok(signature)
 }
+
+/// Resolves where claimed rewards go. `beneficiary` may be a TAPE token account, used as-is, or
+/// a wallet (or an address with no account yet), whose associated token account is used. Returns
+/// the token account and whether it still has to be created.
+pub async fn resolve_beneficiary(
+    client: &RpcClient,
+    beneficiary: &Pubkey,
+) -> Result<(Pubkey, bool)> {
+    // Only a missing account means a wallet; any other failure must not fall through to an ATA
+    let account = client
+        .get_account_with_commitment(beneficiary, client.commitment())
+        .await
+        .map_err(|e| anyhow!("Failed to fetch beneficiary {}: {}", beneficiary, e))?
+        .value;
+
+    if let Some(account) = account {
+        if account.owner == spl_token::ID {
+            let token_account = spl_token::state::Account::unpack(&account.data)
+                .map_err(|e| anyhow!("Failed to unpack token account {}: {}", beneficiary, e))?;
+
+            let mint_address = program_context().mint_address();
+            if token_account.mint != mint_address {
+                return Err(anyhow!(
+                    "Token account {} holds {}, not TAPE ({})",
+                    beneficiary, token_account.mint, mint_address
+                ));
+            }
+
+            return Ok((*beneficiary, false));
+        }
+    }
+
+    let ata = tape_ata(beneficiary);
+    let exists = get_token_balance(client, &ata).await?.is_some();
+
+    Ok((ata, !exists))
+}
@@ -1,7 +1,7 @@
 use anyhow::{anyhow, Result};
 use solana_sdk::{
     compute_budget::ComputeBudgetInstruction,
-    signature::{Keypair, Signature, Signer},
+    signature::{Signature, Signer},
     transaction::Transaction,
     pubkey::Pubkey,
 };
@@ -9,12 +9,13 @@ use solana_client::nonblocking::rpc_client::RpcClient;
 
 use crankx::Solution;
 use tape_api::prelude::*;
+use tape_api::curve::*;
 use crate::utils::*;
 use rand::Rng;
 
 pub async fn perform_mining(
     client: &RpcClient,
-    signer: &Keypair,
+    signer: &dyn Signer,
     miner_address: Pubkey,
     tape_address: Pubkey,
     solution: Solution,
@@ -22,10 +23,11 @@ pub async fn perform_mining(
     merkle_proof: [[u8; 32]; TREE_HEIGHT],
 ) -> Result<Signature> {
     let spool_number = rand::thread_rng().gen_range(0..SPOOL_COUNT);
-    let (spool_address, _spool_bump) = spool_pda(spool_number as u8);
+    let program = program_context();
+    let (spool_address, _spool_bump) = program.spool_pda(spool_number as u8);
 
     let compute_budget_ix = ComputeBudgetInstruction::set_compute_unit_limit(700_000);
-    let mine_ix = build_mine_ix(
+    let mine_ix = program.build_mine_ix(
         signer.pubkey(),
         miner_address,
         spool_address,
@@ -54,3 +56,23 @@ pub async fn perform_mining(
 }
 
 
+
+/// Estimate what the miner's next proof would earn, using the same math as the program.
+///
+/// `lateness` is how many seconds after the target time (one minute after the last proof) the
+/// proof lands, zero or negative means on time. A proof easier than the epoch difficulty earns
+/// nothing. The reward is capped by the epoch's target rate, the spool it's paid from can cap it
+/// further.
+pub fn estimate_rewards(epoch: &Epoch, miner: &Miner, difficulty_attempt: u32, lateness: i64) -> u64 {
+    if (difficulty_attempt as u64) < epoch.difficulty {
+        return 0;
+    }
+
+    let current_time = miner.last_proof_at + ONE_MINUTE + lateness;
+    let multiplier = update_multiplier(miner.multiplier, miner.last_proof_at, current_time);
+
+    let base_reward = compute_base_reward(multiplier, epoch.base_rate, epoch.difficulty, difficulty_attempt);
+    let penalized_reward = penalize_lateness(base_reward, miner.last_proof_at, current_time);
+
+    compute_final_reward(penalized_reward, u64::MAX, epoch.target_rate)
+}
@@ -1,3 +1,5 @@
 pub mod register;
 pub mod mine;
 pub mod claim;
+pub mod pool;
+pub mod recall;
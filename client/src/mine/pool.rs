@@ -0,0 +1,201 @@
+use std::ops::Range;
+
+use anyhow::{anyhow, bail, Result};
+use crankx::{equix::SolverMemory, solve_with_memory, Solution};
+use reqwest::Client as HttpClient;
+use serde_json::{json, Value};
+use tape_api::consts::SEGMENT_SIZE;
+
+use crate::utils::json_rpc;
+
+/// Longest worker name a pool accepts.
+pub const MAX_WORKER_NAME_LEN: usize = 32;
+
+/// Environment variable holding the token a worker authenticates with, as given to it by the
+/// pool's operator.
+pub const POOL_TOKEN_ENV: &str = "TAPEDRIVE_POOL_TOKEN";
+
+/// Check `name` can name a pool worker: 1 to `MAX_WORKER_NAME_LEN` ASCII letters, digits, `-`,
+/// `_` or `.`, so it's safe to key shares and payout logs by.
+pub fn check_worker_name(name: &str) -> Result<()> {
+    if name.is_empty() {
+        bail!("Worker name is empty");
+    }
+    if name.len() > MAX_WORKER_NAME_LEN {
+        bail!("Worker name {:?} is longer than {} characters", name, MAX_WORKER_NAME_LEN);
+    }
+    if let Some(c) = name.chars().find(|c| !(c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))) {
+        bail!("Worker name {:?} holds {:?}, only letters, digits, '-', '_' and '.' are allowed", name, c);
+    }
+    Ok(())
+}
+
+/// A slice of a pool's round handed to one worker: the pool miner's challenge and recall
+/// segment, and the nonces this worker should try.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PoolJob {
+    pub job: u64,
+    pub challenge: [u8; 32],
+    pub segment: [u8; SEGMENT_SIZE],
+    /// Lowest difficulty the pool counts as a share.
+    pub share_difficulty: u32,
+    pub nonces: Range<u64>,
+}
+
+impl PoolJob {
+    pub fn to_json(&self) -> Value {
+        json!({
+            "job": self.job,
+            "challenge": base64::encode(self.challenge),
+            "segment": base64::encode(self.segment),
+            "share_difficulty": self.share_difficulty,
+            "nonce_start": self.nonces.start,
+            "nonce_count": self.nonces.end - self.nonces.start,
+        })
+    }
+
+    pub fn from_json(value: &Value) -> Result<Self> {
+        let u64_field = |name: &str| {
+            value.get(name)
+                .and_then(Value::as_u64)
+                .ok_or_else(|| anyhow!("Pool job has no {}", name))
+        };
+        let bytes_field = |name: &str| {
+            value.get(name)
+                .and_then(Value::as_str)
+                .ok_or_else(|| anyhow!("Pool job has no {}", name))
+                .and_then(|s| base64::decode(s).map_err(|e| anyhow!("Invalid {}: {}", name, e)))
+        };
+
+        let challenge = bytes_field("challenge")?
+            .try_into()
+            .map_err(|_| anyhow!("Pool job challenge isn't 32 bytes"))?;
+        let segment = bytes_field("segment")?
+            .try_into()
+            .map_err(|_| anyhow!("Pool job segment isn't {} bytes", SEGMENT_SIZE))?;
+        let share_difficulty = u32::try_from(u64_field("share_difficulty")?)
+            .map_err(|_| anyhow!("Pool job share difficulty is out of range"))?;
+        let start = u64_field("nonce_start")?;
+        let end = start
+            .checked_add(u64_field("nonce_count")?)
+            .ok_or_else(|| anyhow!("Pool job nonces are out of range"))?;
+
+        Ok(Self {
+            job: u64_field("job")?,
+            challenge,
+            segment,
+            share_difficulty,
+            nonces: start..end,
+        })
+    }
+}
+
+/// Try every nonce of a job, keeping the solutions the pool counts as shares. Blocks for as long
+/// as the nonces take to solve.
+pub fn solve_job(job: &PoolJob) -> Vec<Solution> {
+    let mut memory = SolverMemory::new();
+    job.nonces
+        .clone()
+        .filter_map(|nonce| solve_with_memory(&mut memory, &job.challenge, &job.segment, &nonce.to_le_bytes()).ok())
+        .filter(|solution| solution.difficulty() >= job.share_difficulty)
+        .collect()
+}
+
+/// A worker of a mining pool. The pool's miner submits the best solution its workers find and
+/// credits each one with the shares it found, for payouts made off-chain.
+///
+/// The pool speaks JSON-RPC 2.0 over HTTP POST, with three methods:
+/// - `getJob` (`{"worker": <name>, "token": <token>}`) -> a job, see `PoolJob::to_json`
+/// - `submitShare` (`{"worker": <name>, "token": <token>, "job": <id>, "solution": <base64>}`)
+///   -> `{"difficulty": <u32>, "shares": <u64>}`, the share's difficulty and the worker's shares
+///   this round
+/// - `getShares` (no params) -> shares of every worker, this round and in total
+///
+/// Solutions are base64 of `Solution::to_bytes`, and only count for nonces the pool handed the
+/// worker. See `tape_network::pool` for the coordinator.
+pub struct PoolClient {
+    url: String,
+    worker: String,
+    token: String,
+    http: HttpClient,
+}
+
+impl PoolClient {
+    pub fn new(url: &str, worker: &str, token: &str) -> Result<Self> {
+        check_worker_name(worker)?;
+        Ok(Self {
+            url: url.to_string(),
+            worker: worker.to_string(),
+            token: token.to_string(),
+            http: HttpClient::new(),
+        })
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn worker(&self) -> &str {
+        &self.worker
+    }
+
+    /// Ask the pool for the next nonces to try.
+    pub async fn get_job(&self) -> Result<PoolJob> {
+        let params = json!({ "worker": self.worker, "token": self.token });
+        let result = json_rpc(&self.http, &self.url, "Pool", "getJob", params).await?;
+        PoolJob::from_json(&result)
+    }
+
+    /// Submit a solution found for `job`, returning the worker's shares this round.
+    pub async fn submit_share(&self, job: u64, solution: &Solution) -> Result<u64> {
+        let params = json!({
+            "worker": self.worker,
+            "token": self.token,
+            "job": job,
+            "solution": base64::encode(solution.to_bytes()),
+        });
+        let result = json_rpc(&self.http, &self.url, "Pool", "submitShare", params).await?;
+        result
+            .get("shares")
+            .and_then(Value::as_u64)
+            .ok_or_else(|| anyhow!("Pool returned no share count"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_worker_name() {
+        assert!(check_worker_name("rig-01").is_ok());
+        assert!(check_worker_name("gpu_box.2").is_ok());
+        assert!(check_worker_name(&"a".repeat(MAX_WORKER_NAME_LEN)).is_ok());
+
+        assert!(check_worker_name("").is_err());
+        assert!(check_worker_name(&"a".repeat(MAX_WORKER_NAME_LEN + 1)).is_err());
+        assert!(check_worker_name("rig 01").is_err());
+        assert!(check_worker_name("rig\n").is_err());
+        assert!(check_worker_name("фото").is_err());
+    }
+
+    #[test]
+    fn test_pool_job_json() {
+        let job = PoolJob {
+            job: 7,
+            challenge: [1; 32],
+            segment: [2; SEGMENT_SIZE],
+            share_difficulty: 4,
+            nonces: 4096..8192,
+        };
+        assert_eq!(PoolJob::from_json(&job.to_json()).unwrap(), job);
+
+        let mut value = job.to_json();
+        value["challenge"] = json!(base64::encode([1; 31]));
+        assert!(PoolJob::from_json(&value).is_err());
+
+        let mut value = job.to_json();
+        value["nonce_start"] = json!(u64::MAX);
+        assert!(PoolJob::from_json(&value).is_err());
+    }
+}
@@ -0,0 +1,158 @@
+use anyhow::{anyhow, bail, Result};
+use brine_tree::{Leaf, MerkleTree};
+use crankx::equix::SolverMemory;
+use crankx::{solve_with_memory, CrankXError, Solution};
+use tape_api::prelude::*;
+
+/// Builds the recall proof for a miner's challenge as a tape's segments are fed in, so callers
+/// can prove straight from wherever the segments live without copying the tape out as a whole.
+/// Segments must be added in order, every one of them.
+pub struct RecallProver {
+    tape_total_segments: u64,
+    segment_number: u64,
+    segment: [u8; SEGMENT_SIZE],
+    leaves: Vec<Leaf>,
+    tree: MerkleTree<{ TREE_HEIGHT }>,
+}
+
+impl RecallProver {
+    pub fn new(tape: &Tape, miner: &Miner) -> Self {
+        let (_, segment_number) = compute_recall_indices(miner, tape);
+
+        Self {
+            tape_total_segments: tape.total_segments,
+            segment_number,
+            segment: [0; SEGMENT_SIZE],
+            leaves: Vec::with_capacity(tape.total_segments as usize),
+            tree: MerkleTree::new(&[tape.merkle_seed.as_ref()]),
+        }
+    }
+
+    /// The segment the challenge recalls.
+    pub fn segment_number(&self) -> u64 {
+        self.segment_number
+    }
+
+    pub fn add_segment(&mut self, segment_number: u64, data: &[u8]) -> Result<()> {
+        if segment_number != self.leaves.len() as u64 {
+            bail!("Expected segment {}, got {}", self.leaves.len(), segment_number);
+        }
+
+        // Leaves are built from the canonical segment of exactly SEGMENT_SIZE bytes
+        let data = padded_array::<SEGMENT_SIZE>(data);
+        if segment_number == self.segment_number {
+            self.segment = data;
+        }
+
+        let leaf = compute_leaf(segment_number, &data);
+        self.leaves.push(leaf);
+
+        // TODO: only the proof is needed from the tree, building the whole
+        // thing is left over from debugging
+        self.tree
+            .try_add_leaf(leaf)
+            .map_err(|e| anyhow!("Failed to add leaf to Merkle tree: {:?}", e))
+    }
+
+    /// The recall segment and its merkle proof, once every segment of the tape is in.
+    pub fn finish(self) -> Result<([u8; SEGMENT_SIZE], [[u8; 32]; TREE_HEIGHT])> {
+        if self.leaves.len() as u64 != self.tape_total_segments {
+            bail!(
+                "Invalid number of segments: expected {}, got {}",
+                self.tape_total_segments, self.leaves.len()
+            );
+        }
+
+        let proof = self
+            .tree
+            .get_merkle_proof(&self.leaves, self.segment_number as usize)
+            .iter()
+            .map(|v| v.to_bytes())
+            .collect::<Vec<_>>()
+            .try_into()
+            .map_err(|_| anyhow!("Merkle proof isn't {} levels deep", TREE_HEIGHT))?;
+
+        Ok((self.segment, proof))
+    }
+}
+
+/// The recall segment of a tape and its merkle proof, from all of its segments in order.
+pub fn recall_proof<'a>(
+    tape: &Tape,
+    miner: &Miner,
+    segments: impl IntoIterator<Item = (u64, &'a [u8])>,
+) -> Result<([u8; SEGMENT_SIZE], [[u8; 32]; TREE_HEIGHT])> {
+    let mut prover = RecallProver::new(tape, miner);
+    for (segment_number, data) in segments {
+        prover.add_segment(segment_number, data)?;
+    }
+    prover.finish()
+}
+
+/// Whether a recall segment and its proof hash up to the tape's root on-chain, which is what the
+/// program checks before accepting a solution.
+pub fn proof_matches_root(
+    tape: &Tape,
+    segment_number: u64,
+    segment: &[u8; SEGMENT_SIZE],
+    proof: &[[u8; 32]; TREE_HEIGHT],
+) -> bool {
+    brine_tree::verify(tape.merkle_root, proof, compute_leaf(segment_number, segment))
+}
+
+/// Search nonces until one solves `challenge` over `data` at `difficulty` or better. Runs until
+/// it does, which takes minutes at the network's difficulty.
+pub fn solve_challenge<const N: usize>(
+    challenge: [u8; 32],
+    data: &[u8; N],
+    difficulty: u64,
+) -> Result<Solution, CrankXError> {
+    let mut memory = SolverMemory::new();
+    let mut nonce: u64 = 0;
+
+    loop {
+        if let Ok(solution) = solve_with_memory(&mut memory, &challenge, data, &nonce.to_le_bytes()) {
+            if solution.difficulty() >= difficulty as u32 {
+                return Ok(solution);
+            }
+        }
+        nonce += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytemuck::Zeroable;
+
+    #[test]
+    fn test_recall_proof() {
+        let segments: Vec<Vec<u8>> = (0..5u8).map(|i| vec![i; SEGMENT_SIZE - i as usize]).collect();
+
+        let mut tape = Tape::zeroed();
+        tape.merkle_seed = [3; 32];
+        tape.total_segments = segments.len() as u64;
+
+        let mut tree = MerkleTree::<{ TREE_HEIGHT }>::new(&[tape.merkle_seed.as_ref()]);
+        for (i, segment) in segments.iter().enumerate() {
+            tree.try_add_leaf(compute_leaf(i as u64, &padded_array::<SEGMENT_SIZE>(segment))).unwrap();
+        }
+        tape.merkle_root = tree.get_root().to_bytes();
+
+        let mut miner = Miner::zeroed();
+        miner.current_challenge = [9; 32];
+
+        let numbered = || segments.iter().enumerate().map(|(i, s)| (i as u64, s.as_slice()));
+        let (segment, proof) = recall_proof(&tape, &miner, numbered()).unwrap();
+        let (_, segment_number) = compute_recall_indices(&miner, &tape);
+        assert_eq!(segment, padded_array::<SEGMENT_SIZE>(&segments[segment_number as usize]));
+        assert!(proof_matches_root(&tape, segment_number, &segment, &proof));
+
+        // A stale root, a missing segment or segments out of order don't prove
+        let mut stale = tape;
+        stale.merkle_root = [0; 32];
+        assert!(!proof_matches_root(&stale, segment_number, &segment, &proof));
+        assert!(recall_proof(&tape, &miner, numbered().take(4)).is_err());
+        assert!(recall_proof(&tape, &miner, numbered().skip(1)).is_err());
+    }
+}
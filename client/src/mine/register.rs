@@ -1,7 +1,7 @@
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, bail, Result};
 use solana_sdk::{
     compute_budget::ComputeBudgetInstruction,
-    signature::{Keypair, Signature, Signer},
+    signature::{Signature, Signer},
     transaction::Transaction,
 };
 use solana_client::nonblocking::rpc_client::RpcClient;
@@ -11,12 +11,52 @@ use crate::utils::*;
 
 pub async fn register_miner(
     client: &RpcClient,
-    signer: &Keypair,
+    signer: &dyn Signer,
     name: &str,
 ) -> Result<Signature> {
+    register_miner_with_lockup(client, signer, name, 0, 0).await
+}
+
+/// Registers a miner that locks `locked_percent` of each reward it mines, unlocking it evenly
+/// over `vest_epochs` epochs. Both are zero for no lockup. If the guardian asks for a miner
+/// deposit, it's paid from the signer's TAPE token account and refunded when the miner closes.
+pub async fn register_miner_with_lockup(
+    client: &RpcClient,
+    signer: &dyn Signer,
+    name: &str,
+    locked_percent: u64,
+    vest_epochs: u64,
+) -> Result<Signature> {
+    if locked_percent > 100 {
+        bail!("Locked percent is {}, at most 100", locked_percent);
+    }
+    if vest_epochs > MAX_VEST_EPOCHS {
+        bail!("Vesting takes {} epochs, at most {}", vest_epochs, MAX_VEST_EPOCHS);
+    }
+    if (locked_percent == 0) != (vest_epochs == 0) {
+        bail!("A lockup needs both a locked percent and the epochs it vests over");
+    }
+
+    let (config, _) = get_config_account(client).await?;
+    if config.miner_deposit > 0 {
+        let balance = get_tape_balance(client, &signer.pubkey()).await?.unwrap_or(0);
+        if balance < config.miner_deposit {
+            bail!(
+                "Registering locks a deposit of {} TAPE, {} holds {}",
+                format_tape_amount(config.miner_deposit),
+                signer.pubkey(),
+                format_tape_amount(balance)
+            );
+        }
+    }
 
     let compute_budget_ix = ComputeBudgetInstruction::set_compute_unit_limit(50_000);
-    let register_ix = build_register_ix(signer.pubkey(), name);
+    let register_ix = program_context().build_register_with_lockup_ix(
+        signer.pubkey(),
+        name,
+        locked_percent,
+        vest_epochs,
+    );
 
     let blockhash_bytes = get_latest_blockhash(client).await?;
     let recent_blockhash = deserialize(&blockhash_bytes)?;
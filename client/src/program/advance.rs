@@ -1,7 +1,7 @@
 use anyhow::{anyhow, Result};
 use solana_sdk::{
     compute_budget::ComputeBudgetInstruction,
-    signature::{Keypair, Signature, Signer},
+    signature::{Signature, Signer},
     transaction::Transaction,
 };
 use solana_client::nonblocking::rpc_client::RpcClient;
@@ -9,9 +9,9 @@ use solana_client::nonblocking::rpc_client::RpcClient;
 use tape_api::prelude::*;
 use crate::utils::*;
 
-pub async fn advance(client: &RpcClient, signer: &Keypair) -> Result<Signature> {
+pub async fn advance(client: &RpcClient, signer: &dyn Signer) -> Result<Signature> {
     let compute_budget_ix = ComputeBudgetInstruction::set_compute_unit_limit(250_000);
-    let advance_ix = build_advance_ix(signer.pubkey());
+    let advance_ix = program_context().build_advance_ix(signer.pubkey());
 
     let blockhash_bytes = get_latest_blockhash(client).await?;
     let recent_blockhash = deserialize(&blockhash_bytes)?;
@@ -0,0 +1,79 @@
+use anyhow::{anyhow, Result};
+use solana_sdk::{
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{Signature, Signer},
+    transaction::Transaction,
+};
+use solana_client::nonblocking::rpc_client::RpcClient;
+
+use tape_api::prelude::*;
+use crate::utils::*;
+
+/// Pauses Write, Update and Mine processing. Only the guardian can sign this.
+pub async fn pause(client: &RpcClient, signer: &dyn Signer) -> Result<Signature> {
+    let pause_ix = program_context().build_pause_ix(signer.pubkey());
+    send_guardian_ix(client, signer, pause_ix)
+        .await
+        .map_err(|e| anyhow!("Failed to pause program: {}", e))
+}
+
+/// Resumes a paused program. Only the guardian can sign this.
+pub async fn resume(client: &RpcClient, signer: &dyn Signer) -> Result<Signature> {
+    let resume_ix = program_context().build_resume_ix(signer.pubkey());
+    send_guardian_ix(client, signer, resume_ix)
+        .await
+        .map_err(|e| anyhow!("Failed to resume program: {}", e))
+}
+
+/// Hands the guardian role to a new authority. Only the current guardian can sign this.
+pub async fn set_guardian(
+    client: &RpcClient,
+    signer: &dyn Signer,
+    guardian: Pubkey,
+) -> Result<Signature> {
+    let set_guardian_ix = program_context().build_set_guardian_ix(signer.pubkey(), guardian);
+    send_guardian_ix(client, signer, set_guardian_ix)
+        .await
+        .map_err(|e| anyhow!("Failed to set guardian: {}", e))
+}
+
+/// Sets the TAPE new miners lock at registration, zero for none. Only the guardian can sign this.
+pub async fn set_miner_deposit(
+    client: &RpcClient,
+    signer: &dyn Signer,
+    amount: u64,
+) -> Result<Signature> {
+    if amount > MAX_MINER_DEPOSIT {
+        return Err(anyhow!(
+            "Miner deposit of {} TAPE is above the cap of {}",
+            format_tape_amount(amount),
+            format_tape_amount(MAX_MINER_DEPOSIT)
+        ));
+    }
+
+    let set_miner_deposit_ix = program_context().build_set_miner_deposit_ix(signer.pubkey(), amount);
+    send_guardian_ix(client, signer, set_miner_deposit_ix)
+        .await
+        .map_err(|e| anyhow!("Failed to set miner deposit: {}", e))
+}
+
+async fn send_guardian_ix(
+    client: &RpcClient,
+    signer: &dyn Signer,
+    instruction: Instruction,
+) -> Result<Signature> {
+    let blockhash_bytes = get_latest_blockhash(client).await?;
+    let recent_blockhash = deserialize(&blockhash_bytes)?;
+    let tx = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&signer.pubkey()),
+        &[signer],
+        recent_blockhash,
+    );
+
+    let signature_bytes = send_and_confirm_transaction(client, &tx).await?;
+    let signature: Signature = deserialize(&signature_bytes)?;
+
+    Ok(signature)
+}
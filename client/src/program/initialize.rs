@@ -1,7 +1,7 @@
 use anyhow::{anyhow, Result};
 use solana_sdk::{
     compute_budget::ComputeBudgetInstruction,
-    signature::{Keypair, Signature, Signer},
+    signature::{Signature, Signer},
     transaction::Transaction,
 };
 use solana_client::nonblocking::rpc_client::RpcClient;
@@ -9,9 +9,9 @@ use solana_client::nonblocking::rpc_client::RpcClient;
 use tape_api::prelude::*;
 use crate::utils::*;
 
-pub async fn initialize(client: &RpcClient, signer: &Keypair) -> Result<Signature> {
+pub async fn initialize(client: &RpcClient, signer: &dyn Signer) -> Result<Signature> {
     let compute_budget_ix = ComputeBudgetInstruction::set_compute_unit_limit(250_000);
-    let create_ix = build_initialize_ix(signer.pubkey());
+    let create_ix = program_context().build_initialize_ix(signer.pubkey());
 
     let blockhash_bytes = get_latest_blockhash(client).await?;
     let recent_blockhash = deserialize(&blockhash_bytes)?;
@@ -31,3 +31,24 @@ pub async fn initialize(client: &RpcClient, signer: &Keypair) -> Result<Signatur
     let signature: Signature = deserialize(&signature_bytes)?;
     Ok(signature)
 }
+
+/// Creates the config account of a deployment initialized before it existed. Only the program's
+/// upgrade authority can sign this, and becomes the guardian.
+pub async fn initialize_config(client: &RpcClient, signer: &dyn Signer) -> Result<Signature> {
+    let initialize_config_ix = program_context().build_initialize_config_ix(signer.pubkey());
+
+    let blockhash_bytes = get_latest_blockhash(client).await?;
+    let recent_blockhash = deserialize(&blockhash_bytes)?;
+    let tx = Transaction::new_signed_with_payer(
+        &[initialize_config_ix],
+        Some(&signer.pubkey()),
+        &[signer],
+        recent_blockhash,
+    );
+
+    let signature_bytes = send_and_confirm_transaction(client, &tx)
+        .await
+        .map_err(|e| anyhow!("Failed to initialize config: {}", e))?;
+    let signature: Signature = deserialize(&signature_bytes)?;
+    Ok(signature)
+}
@@ -0,0 +1,61 @@
+use anyhow::{anyhow, Result};
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig};
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::{Signature, Signer},
+    transaction::Transaction,
+};
+
+use tape_api::prelude::*;
+use crate::utils::*;
+
+/// Every program account created before its layout grew. The program rejects them until
+/// they're migrated, see [`migrate_account`].
+pub async fn get_legacy_accounts(client: &RpcClient) -> Result<Vec<Pubkey>> {
+    let config = RpcProgramAccountsConfig {
+        filters: None,
+        account_config: RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            data_slice: None,
+            commitment: None,
+            min_context_slot: None,
+        },
+        with_context: None,
+        sort_results: true.into(),
+    };
+
+    let accounts = get_program_account(client, config).await?;
+
+    Ok(accounts
+        .into_iter()
+        .filter(|(_, account)| needs_migration(&account.data))
+        .map(|(address, _)| address)
+        .collect())
+}
+
+/// Brings a program account up to the current layout. Anyone can sign this, the signer tops up
+/// the rent for the bytes it grows by.
+pub async fn migrate_account(
+    client: &RpcClient,
+    signer: &dyn Signer,
+    account: Pubkey,
+) -> Result<Signature> {
+    let migrate_ix = program_context().build_migrate_ix(signer.pubkey(), account);
+
+    let blockhash_bytes = get_latest_blockhash(client).await?;
+    let recent_blockhash = deserialize(&blockhash_bytes)?;
+    let tx = Transaction::new_signed_with_payer(
+        &[migrate_ix],
+        Some(&signer.pubkey()),
+        &[signer],
+        recent_blockhash,
+    );
+
+    let signature_bytes = send_and_confirm_transaction(client, &tx)
+        .await
+        .map_err(|e| anyhow!("Failed to migrate account {}: {}", account, e))?;
+    let signature: Signature = deserialize(&signature_bytes)?;
+    Ok(signature)
+}
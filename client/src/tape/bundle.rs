@@ -0,0 +1,330 @@
+use anyhow::{anyhow, bail, Result};
+use serde::{Deserialize, Serialize};
+use solana_sdk::{
+    hash::Hash,
+    instruction::Instruction,
+    packet::PACKET_DATA_SIZE,
+    pubkey::Pubkey,
+    signature::{Signature, Signer},
+    system_instruction,
+    transaction::Transaction,
+};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use std::collections::VecDeque;
+use std::str::FromStr;
+use tape_api::prelude::*;
+use crate::{consts::*, utils::*};
+
+use super::{chunk_size, TapeFlags, TapeHeader};
+
+/// A durable nonce to sign one transaction with, so it stays valid until it's sent. The nonce
+/// authority must be the signer of the bundle.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DurableNonce {
+    pub account: Pubkey,
+    pub blockhash: Hash,
+}
+
+impl FromStr for DurableNonce {
+    type Err = anyhow::Error;
+
+    /// Parses `<nonce account>:<nonce blockhash>`.
+    fn from_str(s: &str) -> Result<Self> {
+        let (account, blockhash) = s
+            .split_once(':')
+            .ok_or_else(|| anyhow!("Invalid nonce {}, expected <account>:<blockhash>", s))?;
+
+        Ok(Self {
+            account: account.parse().map_err(|e| anyhow!("Invalid nonce account {}: {}", account, e))?,
+            blockhash: blockhash.parse().map_err(|e| anyhow!("Invalid nonce blockhash {}: {}", blockhash, e))?,
+        })
+    }
+}
+
+/// One transaction of a bundle, for the tape it acts on.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BundleTransaction {
+    pub tape_address: String,
+    /// The bincode serialized transaction, base64 encoded.
+    pub transaction: String,
+}
+
+impl BundleTransaction {
+    pub fn decode(&self) -> Result<Transaction> {
+        let bytes = base64::decode(&self.transaction)
+            .map_err(|e| anyhow!("Invalid transaction in bundle: {}", e))?;
+        deserialize(&bytes)
+    }
+}
+
+/// Every transaction needed to write one or more tapes, in the order they must land. Either all
+/// unsigned, to be signed with a fresh blockhash when they're submitted, or all signed against
+/// durable nonces.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TapeBundle {
+    pub authority: String,
+    /// The tape to read the payload from, the manifest's if it was sharded.
+    pub tape_address: String,
+    pub signed: bool,
+    pub transactions: Vec<BundleTransaction>,
+}
+
+impl TapeBundle {
+    pub fn from_json(json: &str) -> Result<Self> {
+        serde_json::from_str(json).map_err(|e| anyhow!("Invalid tape bundle: {}", e))
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+}
+
+/// Builds a [`TapeBundle`] without a network connection. Unsigned bundles can't link their
+/// writes, since the signatures aren't known until submitting, so they only take unlinked tapes.
+/// Wide tapes need a lookup table created on-chain first and aren't supported either.
+pub struct BundleBuilder<'a> {
+    authority: Pubkey,
+    signer: Option<&'a dyn Signer>,
+    nonces: VecDeque<DurableNonce>,
+    transactions: Vec<BundleTransaction>,
+}
+
+impl<'a> BundleBuilder<'a> {
+    /// A bundle of unsigned transactions paid for by `authority`.
+    pub fn unsigned(authority: Pubkey) -> Self {
+        Self {
+            authority,
+            signer: None,
+            nonces: VecDeque::new(),
+            transactions: Vec::new(),
+        }
+    }
+
+    /// A bundle signed by `signer`, one durable nonce per transaction.
+    pub fn with_nonces(signer: &'a dyn Signer, nonces: Vec<DurableNonce>) -> Self {
+        Self {
+            authority: signer.pubkey(),
+            signer: Some(signer),
+            nonces: nonces.into(),
+            transactions: Vec::new(),
+        }
+    }
+
+    /// The number of transactions [`Self::add_tape`] needs for `encoded`.
+    pub fn transactions_needed(header: &TapeHeader, encoded: &[u8]) -> usize {
+        if encoded.len() <= ATOMIC_WRITE_SIZE {
+            1
+        } else {
+            2 + encoded.len().div_ceil(chunk_size(header))
+        }
+    }
+
+    /// Add the transactions that create, write and finalize a tape. Returns the tape address.
+    pub fn add_tape(&mut self, name: &str, mut header: TapeHeader, encoded: &[u8]) -> Result<Pubkey> {
+        if header.flags & u8::from(TapeFlags::Wide) != 0 {
+            bail!("Wide tapes need a lookup table on-chain and can't be bundled offline");
+        }
+
+        let program = program_context();
+        let (tape_address, _tape_bump) = program.tape_pda(self.authority, &check_name(name)?);
+        let (writer_address, _writer_bump) = program.writer_pda(tape_address);
+
+        if encoded.len() <= ATOMIC_WRITE_SIZE {
+            // Same as create_small_tape, the tail signature can't be known before signing
+            header.flags &= !u8::from(TapeFlags::Linked);
+            let header_data = header_bytes(&header)?;
+
+            self.push(tape_address, &[
+                program.build_create_ix(self.authority, name, Some(header_data)),
+                program.build_write_ix(self.authority, tape_address, writer_address, encoded),
+                program.build_finalize_ix(self.authority, tape_address, writer_address, Some(header_data)),
+            ])?;
+
+            return Ok(tape_address);
+        }
+
+        let linked = header.flags & u8::from(TapeFlags::Linked) != 0;
+        if linked && self.signer.is_none() {
+            bail!("Unsigned bundles can't link their writes, write the tape unlinked");
+        }
+
+        let create_ix = program.build_create_ix(self.authority, name, Some(header_bytes(&header)?));
+        self.push(tape_address, &[create_ix])?;
+
+        let mut last_sig = Signature::default();
+        for chunk in encoded.chunks(chunk_size(&header)) {
+            let payload = if linked {
+                [last_sig.as_ref(), chunk].concat()
+            } else {
+                chunk.to_vec()
+            };
+
+            let write_ix = program.build_write_ix(self.authority, tape_address, writer_address, &payload);
+            if let Some(signature) = self.push(tape_address, &[write_ix])? {
+                last_sig = signature;
+            }
+        }
+
+        if linked {
+            header.tail_signature = last_sig.into();
+        }
+        let finalize_ix = program.build_finalize_ix(self.authority, tape_address, writer_address, Some(header_bytes(&header)?));
+        self.push(tape_address, &[finalize_ix])?;
+
+        Ok(tape_address)
+    }
+
+    /// The finished bundle, read from `tape_address`.
+    pub fn finish(self, tape_address: Pubkey) -> TapeBundle {
+        TapeBundle {
+            authority: self.authority.to_string(),
+            tape_address: tape_address.to_string(),
+            signed: self.signer.is_some(),
+            transactions: self.transactions,
+        }
+    }
+
+    /// Add a transaction, signing it against the next nonce if the bundle is signed. Returns the
+    /// signature if it was signed.
+    fn push(&mut self, tape_address: Pubkey, instructions: &[Instruction]) -> Result<Option<Signature>> {
+        let (tx, signature) = match self.signer {
+            None => (Transaction::new_with_payer(instructions, Some(&self.authority)), None),
+            Some(signer) => {
+                let Some(nonce) = self.nonces.pop_front() else {
+                    bail!(
+                        "Ran out of durable nonces after {} transactions, a signed bundle needs one per transaction",
+                        self.transactions.len()
+                    );
+                };
+
+                let advance_ix = system_instruction::advance_nonce_account(&nonce.account, &self.authority);
+                let instructions = [&[advance_ix][..], instructions].concat();
+                let tx = Transaction::new_signed_with_payer(
+                    &instructions,
+                    Some(&self.authority),
+                    &[signer],
+                    nonce.blockhash,
+                );
+                let signature = tx.signatures[0];
+                (tx, Some(signature))
+            }
+        };
+
+        let bytes = serialize(&tx)?;
+        if bytes.len() > PACKET_DATA_SIZE {
+            bail!("Transaction is {} bytes, over the {} byte limit", bytes.len(), PACKET_DATA_SIZE);
+        }
+
+        self.transactions.push(BundleTransaction {
+            tape_address: tape_address.to_string(),
+            transaction: base64::encode(bytes),
+        });
+
+        Ok(signature)
+    }
+}
+
+/// Send the transactions of a bundle in order, starting at `from`, waiting for each to confirm
+/// before the next. Unsigned bundles are signed by `signer` with a fresh blockhash. `on_sent` is
+/// called with the index of every transaction that landed, so a failed submit can pick up where
+/// it stopped.
+pub async fn submit_bundle(
+    client: &RpcClient,
+    bundle: &TapeBundle,
+    signer: Option<&dyn Signer>,
+    from: usize,
+    mut on_sent: impl FnMut(usize),
+) -> Result<()> {
+    let authority: Pubkey = bundle.authority
+        .parse()
+        .map_err(|e| anyhow!("Invalid bundle authority {}: {}", bundle.authority, e))?;
+
+    let signer = match signer {
+        _ if bundle.signed => None,
+        Some(signer) if signer.pubkey() == authority => Some(signer),
+        Some(signer) => bail!("Bundle was built for {}, not {}", authority, signer.pubkey()),
+        None => bail!("Bundle is unsigned, a keypair for {} is needed to submit it", authority),
+    };
+
+    for (i, entry) in bundle.transactions.iter().enumerate().skip(from) {
+        let mut tx = entry.decode()?;
+
+        if let Some(signer) = signer {
+            let blockhash_bytes = get_latest_blockhash(client).await?;
+            let recent_blockhash = deserialize(&blockhash_bytes)?;
+            tx.try_sign(&[signer], recent_blockhash)
+                .map_err(|e| anyhow!("Failed to sign transaction {}: {}", i, e))?;
+        }
+
+        send_and_confirm(client, &tx)
+            .await
+            .map_err(|e| anyhow!("Transaction {} of {} failed: {}", i, bundle.transactions.len(), e))?;
+        on_sent(i);
+    }
+
+    Ok(())
+}
+
+fn header_bytes(header: &TapeHeader) -> Result<[u8; HEADER_SIZE]> {
+    header.to_bytes().try_into()
+        .map_err(|_| anyhow!("Failed to convert header to bytes"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::signature::Keypair;
+    use crate::{CompressionAlgo, EncryptionAlgo, MimeType};
+
+    fn header(flags: TapeFlags) -> TapeHeader {
+        TapeHeader::new(MimeType::Unknown, CompressionAlgo::None, EncryptionAlgo::None, flags)
+    }
+
+    #[test]
+    fn test_unsigned_bundle() -> Result<()> {
+        let authority = Pubkey::new_unique();
+        let data = vec![7u8; SAFE_SIZE * 2 + 1];
+
+        let mut builder = BundleBuilder::unsigned(authority);
+        assert!(builder.add_tape("linked", header(TapeFlags::Linked), &data).is_err());
+
+        let tape_address = builder.add_tape("unlinked", header(TapeFlags::None), &data)?;
+        let bundle = TapeBundle::from_json(&builder.finish(tape_address).to_json()?)?;
+
+        assert!(!bundle.signed);
+        assert_eq!(bundle.transactions.len(), BundleBuilder::transactions_needed(&header(TapeFlags::None), &data));
+        assert_eq!(bundle.transactions.len(), 5);
+
+        let tx = bundle.transactions[1].decode()?;
+        assert_eq!(tx.message.account_keys[0], authority);
+        assert_eq!(tx.signatures, vec![Signature::default()]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_signed_bundle() -> Result<()> {
+        let signer = Keypair::new();
+        let data = vec![7u8; SAFE_SIZE + 1];
+        let nonce = |_| DurableNonce { account: Pubkey::new_unique(), blockhash: Hash::new_unique() };
+
+        // Two writes, plus create and finalize
+        let mut builder = BundleBuilder::with_nonces(&signer, (0..3).map(nonce).collect());
+        assert!(builder.add_tape("short", header(TapeFlags::Linked), &data).is_err());
+
+        let mut builder = BundleBuilder::with_nonces(&signer, (0..4).map(nonce).collect());
+        let tape_address = builder.add_tape("linked", header(TapeFlags::Linked), &data)?;
+        let bundle = builder.finish(tape_address);
+
+        assert!(bundle.signed);
+        let first = bundle.transactions[1].decode()?;
+        let second = bundle.transactions[2].decode()?;
+        first.verify()?;
+
+        // The second write carries the signature of the first
+        let data = &second.message.instructions[1].data;
+        assert_eq!(&data[1..65], first.signatures[0].as_ref());
+
+        Ok(())
+    }
+}
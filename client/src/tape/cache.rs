@@ -0,0 +1,193 @@
+use anyhow::{anyhow, bail, Result};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{pubkey::Pubkey, signature::Signature};
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tape_api::prelude::InstructionType;
+use crate::{utils::*, consts::*};
+use super::history::tape_instructions;
+
+/// A chunk as it was sent to a tape, with the signature of the confirmed write.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CachedChunk {
+    pub index: u64,
+    pub data: Vec<u8>,
+    pub signature: Signature,
+}
+
+/// Somewhere to keep a record of every chunk written to a tape, so uploads can be audited and
+/// re-verified later without encoding the source again.
+pub trait UploadCache: Send + Sync {
+    /// Record the chunk at `index` of a tape. A chunk that is written again (after a failed
+    /// verification) replaces the earlier record.
+    fn put_chunk(&self, tape_address: &Pubkey, index: u64, data: &[u8], signature: &Signature) -> Result<()>;
+
+    /// Every chunk recorded for a tape, in order.
+    fn get_chunks(&self, tape_address: &Pubkey) -> Result<Vec<CachedChunk>>;
+}
+
+/// Keeps uploads in memory, for tests and short-lived tools.
+#[derive(Default)]
+pub struct MemoryUploadCache {
+    tapes: Mutex<BTreeMap<Pubkey, BTreeMap<u64, CachedChunk>>>,
+}
+
+impl UploadCache for MemoryUploadCache {
+    fn put_chunk(&self, tape_address: &Pubkey, index: u64, data: &[u8], signature: &Signature) -> Result<()> {
+        let chunk = CachedChunk { index, data: data.to_vec(), signature: *signature };
+        self.tapes
+            .lock()
+            .map_err(|_| anyhow!("Upload cache lock poisoned"))?
+            .entry(*tape_address)
+            .or_default()
+            .insert(index, chunk);
+        Ok(())
+    }
+
+    fn get_chunks(&self, tape_address: &Pubkey) -> Result<Vec<CachedChunk>> {
+        let tapes = self.tapes.lock().map_err(|_| anyhow!("Upload cache lock poisoned"))?;
+        Ok(tapes
+            .get(tape_address)
+            .map(|chunks| chunks.values().cloned().collect())
+            .unwrap_or_default())
+    }
+}
+
+/// Keeps uploads on disk, one append-only file per tape named after its address. Each record is
+/// the chunk index (8 bytes LE), the signature (64 bytes), the data length (4 bytes LE) and the
+/// data, later records for an index win.
+pub struct DirUploadCache {
+    dir: PathBuf,
+}
+
+impl DirUploadCache {
+    pub fn new(dir: impl Into<PathBuf>) -> Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)
+            .map_err(|e| anyhow!("Failed to create upload cache {}: {}", dir.display(), e))?;
+        Ok(Self { dir })
+    }
+
+    fn path(&self, tape_address: &Pubkey) -> PathBuf {
+        self.dir.join(format!("{}.chunks", tape_address))
+    }
+}
+
+impl UploadCache for DirUploadCache {
+    fn put_chunk(&self, tape_address: &Pubkey, index: u64, data: &[u8], signature: &Signature) -> Result<()> {
+        let mut record = Vec::with_capacity(76 + data.len());
+        record.extend_from_slice(&index.to_le_bytes());
+        record.extend_from_slice(signature.as_ref());
+        record.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        record.extend_from_slice(data);
+
+        let path = self.path(tape_address);
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .map_err(|e| anyhow!("Failed to open {}: {}", path.display(), e))?;
+
+        // One write per record, so a crash can only ever cut off the last one
+        file.write_all(&record)
+            .map_err(|e| anyhow!("Failed to write {}: {}", path.display(), e))
+    }
+
+    fn get_chunks(&self, tape_address: &Pubkey) -> Result<Vec<CachedChunk>> {
+        let path = self.path(tape_address);
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+
+        let bytes = fs::read(&path)
+            .map_err(|e| anyhow!("Failed to read {}: {}", path.display(), e))?;
+
+        let mut chunks = BTreeMap::new();
+        let mut rest = &bytes[..];
+        while rest.len() >= 76 {
+            let index = u64::from_le_bytes(rest[0..8].try_into()?);
+            let signature = Signature::try_from(&rest[8..72])?;
+            let len = u32::from_le_bytes(rest[72..76].try_into()?) as usize;
+
+            // A record cut off by a crash, everything before it is still good
+            let Some(data) = rest.get(76..76 + len) else {
+                break;
+            };
+
+            chunks.insert(index, CachedChunk { index, data: data.to_vec(), signature });
+            rest = &rest[76 + len..];
+        }
+
+        Ok(chunks.into_values().collect())
+    }
+}
+
+/// Check every cached chunk of a tape against the write that was confirmed on-chain. Returns the
+/// indices of chunks whose transaction doesn't carry the cached bytes.
+pub async fn verify_upload(
+    client: &RpcClient,
+    cache: &dyn UploadCache,
+    tape_address: &Pubkey,
+) -> Result<Vec<u64>> {
+    let chunks = cache.get_chunks(tape_address)?;
+    if chunks.is_empty() {
+        bail!("No cached chunks for tape {}", tape_address);
+    }
+
+    let mut mismatched = Vec::new();
+    for chunk in &chunks {
+        let tx = get_transaction_with_retry(client, &chunk.signature, MAX_RETRIES).await?;
+
+        // Linked writes carry the previous signature in front of the chunk
+        let found = tape_instructions(&tx.message, tape_address)
+            .into_iter()
+            .any(|(ix_type, data)| ix_type == InstructionType::Write && data.ends_with(&chunk.data));
+
+        if !found {
+            mismatched.push(chunk.index);
+        }
+    }
+
+    Ok(mismatched)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempdir::TempDir;
+
+    #[test]
+    fn test_dir_upload_cache() -> Result<()> {
+        let dir = TempDir::new("uploads")?;
+        let cache = DirUploadCache::new(dir.path())?;
+        let tape = Pubkey::new_unique();
+
+        assert!(cache.get_chunks(&tape)?.is_empty());
+
+        let sig_a = Signature::from([1u8; 64]);
+        let sig_b = Signature::from([2u8; 64]);
+        cache.put_chunk(&tape, 0, b"hello", &sig_a)?;
+        cache.put_chunk(&tape, 1, b"world", &sig_a)?;
+        cache.put_chunk(&tape, 1, b"again", &sig_b)?;
+
+        let chunks = cache.get_chunks(&tape)?;
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks[0].data, b"hello");
+        assert_eq!(chunks[1], CachedChunk { index: 1, data: b"again".to_vec(), signature: sig_b });
+
+        // A torn record at the end is ignored
+        let path = dir.path().join(format!("{}.chunks", tape));
+        let mut bytes = fs::read(&path)?;
+        bytes.extend_from_slice(&5u64.to_le_bytes());
+        bytes.extend_from_slice(&[3u8; 64]);
+        bytes.extend_from_slice(&1000u32.to_le_bytes());
+        bytes.extend_from_slice(b"torn");
+        fs::write(&path, &bytes)?;
+        assert_eq!(cache.get_chunks(&tape)?.len(), 2);
+
+        Ok(())
+    }
+}
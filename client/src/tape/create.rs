@@ -1,19 +1,20 @@
-use anyhow::Result;
+use anyhow::{Result, bail};
 use solana_sdk::{
-    signature::{Keypair, Signer, Signature},
+    packet::PACKET_DATA_SIZE,
+    signature::{Signer, Signature},
     transaction::Transaction,
     pubkey::Pubkey,
 };
 use tape_api::prelude::*;
 use solana_client::nonblocking::rpc_client::RpcClient;
-use crate::utils::*;
+use crate::{consts::*, utils::*};
 
-use super::TapeHeader;
+use super::{TapeFlags, TapeHeader};
 
 /// Creates a new tape and returns the tape address, writer address, and initial signature.
 pub async fn create_tape(
     client: &RpcClient,
-    signer: &Keypair,
+    signer: &dyn Signer,
     name: &str,
     header: TapeHeader,
 ) -> Result<(Pubkey, Pubkey, Signature)> {
@@ -21,10 +22,11 @@ pub async fn create_tape(
     let header_data = header.to_bytes().try_into()
         .map_err(|_| anyhow::anyhow!("Failed to convert header to bytes"))?;
 
-    let (tape_address, _tape_bump) = tape_pda(signer.pubkey(), &to_name(name));
-    let (writer_address, _writer_bump) = writer_pda(tape_address);
+    let program = program_context();
+    let (tape_address, _tape_bump) = program.tape_pda(signer.pubkey(), &check_name(name)?);
+    let (writer_address, _writer_bump) = program.writer_pda(tape_address);
 
-    let create_ix = build_create_ix(
+    let create_ix = program.build_create_ix(
         signer.pubkey(), 
         name, 
         Some(header_data)
@@ -44,3 +46,54 @@ pub async fn create_tape(
     Ok((tape_address, writer_address, signature))
 }
 
+/// Creates a tape, writes `data` to it and finalizes it in a single transaction, returning the
+/// tape address and signature. Only for payloads up to `ATOMIC_WRITE_SIZE` bytes. The tail
+/// signature can't be known before signing, so the tape is written unlinked.
+pub async fn create_small_tape(
+    client: &RpcClient,
+    signer: &dyn Signer,
+    name: &str,
+    mut header: TapeHeader,
+    data: &[u8],
+) -> Result<(Pubkey, Signature)> {
+    if data.len() > ATOMIC_WRITE_SIZE {
+        bail!(
+            "Data is {} bytes, single transaction tapes hold at most {} bytes",
+            data.len(), ATOMIC_WRITE_SIZE
+        );
+    }
+
+    header.flags &= !u8::from(TapeFlags::Linked);
+    header.flags &= !u8::from(TapeFlags::Wide);
+
+    let header_data: [u8; HEADER_SIZE] = header.to_bytes().try_into()
+        .map_err(|_| anyhow::anyhow!("Failed to convert header to bytes"))?;
+
+    let program = program_context();
+    let (tape_address, _tape_bump) = program.tape_pda(signer.pubkey(), &check_name(name)?);
+    let (writer_address, _writer_bump) = program.writer_pda(tape_address);
+
+    let instructions = [
+        program.build_create_ix(signer.pubkey(), name, Some(header_data)),
+        program.build_write_ix(signer.pubkey(), tape_address, writer_address, data),
+        program.build_finalize_ix(signer.pubkey(), tape_address, writer_address, Some(header_data)),
+    ];
+
+    let blockhash_bytes = get_latest_blockhash(client).await?;
+    let recent_blockhash = deserialize(&blockhash_bytes)?;
+    let tx = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&signer.pubkey()),
+        &[signer],
+        recent_blockhash,
+    );
+
+    let tx_size = bincode::serialized_size(&tx)? as usize;
+    if tx_size > PACKET_DATA_SIZE {
+        bail!("Transaction is {} bytes, over the {} byte limit", tx_size, PACKET_DATA_SIZE);
+    }
+
+    let signature = send_and_confirm(client, &tx).await?;
+
+    Ok((tape_address, signature))
+}
\ No newline at end of file
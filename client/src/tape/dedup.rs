@@ -0,0 +1,463 @@
+use anyhow::{Result, anyhow, bail};
+use serde::{Deserialize, Serialize};
+use solana_sdk::{hash::{hash, Hash}, pubkey::Pubkey};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use super::{TapeHeader, MimeType, CompressionAlgo, EncryptionAlgo, TapeFlags, MIME_STR_LEN};
+
+/// Chunks are never cut shorter than this, except at the end of the data.
+pub const MIN_CHUNK: usize = 16 * 1024;
+
+/// Chunks are always cut at this size, even without a boundary.
+pub const MAX_CHUNK: usize = 256 * 1024;
+
+/// A boundary is found when the top `CHUNK_BITS` of the rolling hash are zero, so chunks average
+/// about `MIN_CHUNK + 2^CHUNK_BITS` bytes.
+const CHUNK_BITS: u32 = 16;
+
+/// A 4-byte "magic" prefix to identify a recipe.
+pub const RECIPE_MAGIC: [u8; 4] = *b"TRCP";
+
+/// The version of the recipe format.
+pub const RECIPE_VERSION: u8 = 1;
+
+/// The custom MIME string recipe tapes are written with.
+pub const RECIPE_MIME: &str = "application/x-tape-recipe";
+
+/// Size of a single encoded `RecipeChunk`.
+const CHUNK_LEN: usize = 32 + 8 + 4 + 32;
+
+/// Random values the rolling hash adds for each byte, fixed so every client cuts the same data
+/// at the same places.
+const GEAR: [u64; 256] = gear_table();
+
+const fn gear_table() -> [u64; 256] {
+    let mut table = [0u64; 256];
+    let mut state: u64 = 0;
+    let mut i = 0;
+
+    // SplitMix64
+    while i < 256 {
+        state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        table[i] = z ^ (z >> 31);
+        i += 1;
+    }
+
+    table
+}
+
+/// Split data into chunks at boundaries picked by its content (a Gear rolling hash over the last
+/// 64 bytes), so an edit only changes the chunks around it and the rest still match earlier
+/// copies.
+pub fn content_chunks(data: &[u8]) -> Vec<&[u8]> {
+    let mut chunks = Vec::new();
+    let mut start = 0;
+
+    while start < data.len() {
+        let end = start + next_boundary(&data[start..]);
+        chunks.push(&data[start..end]);
+        start = end;
+    }
+
+    chunks
+}
+
+fn next_boundary(data: &[u8]) -> usize {
+    if data.len() <= MIN_CHUNK {
+        return data.len();
+    }
+
+    let max = data.len().min(MAX_CHUNK);
+    let mut rolling = 0u64;
+    for (i, &byte) in data.iter().enumerate().take(max).skip(MIN_CHUNK) {
+        rolling = (rolling << 1).wrapping_add(GEAR[byte as usize]);
+        if rolling >> (64 - CHUNK_BITS) == 0 {
+            return i + 1;
+        }
+    }
+
+    max
+}
+
+/// Where a chunk is stored: `len` bytes at `offset` in the decoded data of a tape.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ChunkLocation {
+    pub tape: Pubkey,
+    pub offset: u64,
+    pub len: u32,
+}
+
+/// The chunks written so far, by the SHA-256 of their data, kept on the writer's machine so
+/// later writes can skip them.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DedupIndex {
+    pub chunks: HashMap<[u8; 32], ChunkLocation>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoredChunk {
+    tape: String,
+    offset: u64,
+    len: u32,
+}
+
+impl DedupIndex {
+    /// Load an index saved with `save`, or an empty one if there's no file yet.
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let json = fs::read_to_string(path)
+            .map_err(|e| anyhow!("Failed to read {}: {}", path.display(), e))?;
+        let stored: BTreeMap<String, StoredChunk> = serde_json::from_str(&json)
+            .map_err(|e| anyhow!("Failed to parse {}: {}", path.display(), e))?;
+
+        let mut chunks = HashMap::with_capacity(stored.len());
+        for (key, chunk) in stored {
+            let digest = Hash::from_str(&key)
+                .map_err(|_| anyhow!("Invalid chunk hash {} in {}", key, path.display()))?;
+            let tape = Pubkey::from_str(&chunk.tape)
+                .map_err(|_| anyhow!("Invalid tape {} in {}", chunk.tape, path.display()))?;
+            chunks.insert(digest.to_bytes(), ChunkLocation { tape, offset: chunk.offset, len: chunk.len });
+        }
+
+        Ok(Self { chunks })
+    }
+
+    /// Save the index to `path`, replacing it only once it's fully written.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let stored: BTreeMap<String, StoredChunk> = self.chunks
+            .iter()
+            .map(|(digest, location)| {
+                (Hash::new_from_array(*digest).to_string(), StoredChunk {
+                    tape: location.tape.to_string(),
+                    offset: location.offset,
+                    len: location.len,
+                })
+            })
+            .collect();
+
+        let json = serde_json::to_string(&stored)?;
+        let partial = path.with_extension("partial");
+        fs::write(&partial, json)
+            .map_err(|e| anyhow!("Failed to write {}: {}", partial.display(), e))?;
+        fs::rename(&partial, path)
+            .map_err(|e| anyhow!("Failed to write {}: {}", path.display(), e))
+    }
+
+    /// Work out which chunks of `data` are already stored and which have to be written.
+    pub fn plan(&self, data: &[u8]) -> DedupPlan {
+        let mut plan = DedupPlan::default();
+        let mut packed: HashMap<[u8; 32], u64> = HashMap::new();
+
+        for chunk in content_chunks(data) {
+            let digest = hash(chunk).to_bytes();
+            let len = chunk.len() as u32;
+
+            let source = match self.chunks.get(&digest) {
+                Some(location) => ChunkSource::Stored(*location),
+                None => {
+                    // Repeats within the data are packed once too
+                    let offset = *packed.entry(digest).or_insert_with(|| {
+                        let offset = plan.pack.len() as u64;
+                        plan.pack.extend_from_slice(chunk);
+                        offset
+                    });
+                    ChunkSource::Packed(offset)
+                }
+            };
+
+            plan.chunks.push(PlannedChunk { digest, len, source });
+        }
+
+        plan
+    }
+}
+
+/// Where a chunk of a plan comes from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChunkSource {
+    /// Written before, see the index.
+    Stored(ChunkLocation),
+
+    /// New, at this offset in the pack.
+    Packed(u64),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PlannedChunk {
+    pub digest: [u8; 32],
+    pub len: u32,
+    pub source: ChunkSource,
+}
+
+/// The chunks of some data and the new ones among them, packed together to be written as one
+/// payload.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DedupPlan {
+    pub chunks: Vec<PlannedChunk>,
+    pub pack: Vec<u8>,
+}
+
+impl DedupPlan {
+    /// Bytes of chunks that were already stored.
+    pub fn reused_bytes(&self) -> u64 {
+        self.chunks
+            .iter()
+            .filter(|c| matches!(c.source, ChunkSource::Stored(_)))
+            .map(|c| c.len as u64)
+            .sum()
+    }
+
+    /// The recipe for the data, once the pack is written to `pack_address` (none if there
+    /// were no new chunks). Records the new chunks in `index`.
+    pub fn finish(&self, pack_address: Option<Pubkey>, mime: &str, index: &mut DedupIndex) -> Result<TapeRecipe> {
+        let mut chunks = Vec::with_capacity(self.chunks.len());
+
+        for chunk in &self.chunks {
+            let location = match chunk.source {
+                ChunkSource::Stored(location) => location,
+                ChunkSource::Packed(offset) => {
+                    let tape = pack_address.ok_or_else(|| anyhow!("The plan has new chunks, but no pack was written"))?;
+                    let location = ChunkLocation { tape, offset, len: chunk.len };
+                    index.chunks.insert(chunk.digest, location);
+                    location
+                }
+            };
+
+            chunks.push(RecipeChunk { location, digest: chunk.digest });
+        }
+
+        Ok(TapeRecipe { mime: mime.to_string(), chunks })
+    }
+}
+
+/// One chunk of a recipe, and the SHA-256 it must match.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RecipeChunk {
+    pub location: ChunkLocation,
+    pub digest: [u8; 32],
+}
+
+/// Rebuilds data from chunks stored on other tapes, written by deduplicated writes.
+///
+/// Layout:
+/// - `magic` (4 bytes)       -> always `b"TRCP"`
+/// - `version` (1 byte)      -> format version (`1`)
+/// - `mime_len` (1 byte)     -> length of `mime`
+/// - `mime` (`mime_len`)     -> MIME type of the rebuilt data
+/// - `count` (4 bytes, LE)   -> number of chunks
+/// - `chunks` (76 bytes each) -> in order, each:
+///   - `tape` (32 bytes)     -> tape holding the chunk
+///   - `offset` (8 bytes, LE) -> where it starts in the tape's decoded data
+///   - `len` (4 bytes, LE)   -> its length
+///   - `digest` (32 bytes)   -> SHA-256 of the chunk
+#[derive(Clone, Debug, PartialEq)]
+pub struct TapeRecipe {
+    pub mime: String,
+    pub chunks: Vec<RecipeChunk>,
+}
+
+impl TapeRecipe {
+    /// The header recipe tapes are written with.
+    pub fn header() -> TapeHeader {
+        let mut header = TapeHeader::new(
+            MimeType::Unknown,
+            CompressionAlgo::Gzip,
+            EncryptionAlgo::None,
+            TapeFlags::Linked,
+        );
+
+        let mut mime_str = [0; MIME_STR_LEN];
+        mime_str[..RECIPE_MIME.len()].copy_from_slice(RECIPE_MIME.as_bytes());
+
+        header.mime_type = MimeType::Custom.into();
+        header.mime_str = mime_str;
+        header
+    }
+
+    /// Returns true if the header belongs to a recipe tape.
+    pub fn is_recipe(header: &TapeHeader) -> bool {
+        header.mime() == RECIPE_MIME
+    }
+
+    /// Size of the rebuilt data, in bytes.
+    pub fn total_size(&self) -> u64 {
+        self.chunks.iter().map(|c| c.location.len as u64).sum()
+    }
+
+    /// The tapes chunks are read from, each once.
+    pub fn tapes(&self) -> Vec<Pubkey> {
+        let mut tapes: Vec<Pubkey> = self.chunks.iter().map(|c| c.location.tape).collect();
+        tapes.sort();
+        tapes.dedup();
+        tapes
+    }
+
+    /// Rebuild the data from the decoded tapes it refers to, checking every chunk.
+    pub fn assemble(&self, tapes: &HashMap<Pubkey, Vec<u8>>) -> Result<Vec<u8>> {
+        let mut data = Vec::with_capacity(self.total_size() as usize);
+
+        for chunk in &self.chunks {
+            let location = &chunk.location;
+            let tape = tapes
+                .get(&location.tape)
+                .ok_or_else(|| anyhow!("Tape {} wasn't read", location.tape))?;
+
+            let start = location.offset as usize;
+            let bytes = tape
+                .get(start..start + location.len as usize)
+                .ok_or_else(|| anyhow!("Chunk at {} is past the end of tape {}", start, location.tape))?;
+
+            if hash(bytes).to_bytes() != chunk.digest {
+                bail!("Chunk at {} of tape {} doesn't match the recipe checksum", start, location.tape);
+            }
+
+            data.extend_from_slice(bytes);
+        }
+
+        Ok(data)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut data = Vec::with_capacity(10 + self.mime.len() + self.chunks.len() * CHUNK_LEN);
+        data.extend_from_slice(&RECIPE_MAGIC);
+        data.push(RECIPE_VERSION);
+        data.push(self.mime.len() as u8);
+        data.extend_from_slice(self.mime.as_bytes());
+        data.extend_from_slice(&(self.chunks.len() as u32).to_le_bytes());
+        for chunk in &self.chunks {
+            data.extend_from_slice(&chunk.location.tape.to_bytes());
+            data.extend_from_slice(&chunk.location.offset.to_le_bytes());
+            data.extend_from_slice(&chunk.location.len.to_le_bytes());
+            data.extend_from_slice(&chunk.digest);
+        }
+        data
+    }
+
+    pub fn try_from_bytes(data: &[u8]) -> Result<Self> {
+        if data.len() < 6 {
+            bail!("Data too short for TapeRecipe ({} < 6)", data.len());
+        }
+
+        if data[0..4] != RECIPE_MAGIC {
+            bail!("Invalid magic number in TapeRecipe");
+        }
+
+        if data[4] != RECIPE_VERSION {
+            bail!(
+                "Unsupported TapeRecipe version: found {}, expected {}",
+                data[4], RECIPE_VERSION
+            );
+        }
+
+        let mime_end = 6 + data[5] as usize;
+        if data.len() < mime_end + 4 {
+            bail!("TapeRecipe is too short for its MIME type");
+        }
+        let mime = String::from_utf8(data[6..mime_end].to_vec())
+            .map_err(|_| anyhow!("Invalid MIME type in TapeRecipe"))?;
+
+        let count = u32::from_le_bytes(data[mime_end..mime_end + 4].try_into()?) as usize;
+        let chunks = &data[mime_end + 4..];
+        if chunks.len() != count * CHUNK_LEN {
+            bail!("TapeRecipe lists {} chunks but holds {} bytes", count, chunks.len());
+        }
+
+        let chunks = chunks
+            .chunks(CHUNK_LEN)
+            .map(|c| {
+                Ok(RecipeChunk {
+                    location: ChunkLocation {
+                        tape: Pubkey::try_from(&c[..32])
+                            .map_err(|e| anyhow!("Invalid chunk tape: {}", e))?,
+                        offset: u64::from_le_bytes(c[32..40].try_into()?),
+                        len: u32::from_le_bytes(c[40..44].try_into()?),
+                    },
+                    digest: c[44..76].try_into()?,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self { mime, chunks })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, RngCore, SeedableRng};
+    use tempdir::TempDir;
+
+    fn random_data(len: usize) -> Vec<u8> {
+        let mut data = vec![0u8; len];
+        StdRng::seed_from_u64(7).fill_bytes(&mut data);
+        data
+    }
+
+    #[test]
+    fn test_content_chunks() {
+        let data = random_data(2 * 1024 * 1024);
+        let chunks = content_chunks(&data);
+
+        assert_eq!(chunks.concat(), data);
+        assert!(chunks.iter().all(|c| c.len() <= MAX_CHUNK));
+        assert!(chunks[..chunks.len() - 1].iter().all(|c| c.len() >= MIN_CHUNK));
+
+        // An insertion only moves the boundaries around it
+        let mut edited = data.clone();
+        edited.splice(1_000_000..1_000_000, [1u8; 10]);
+        let edited_chunks = content_chunks(&edited);
+        let changed = edited_chunks.iter().filter(|c| !chunks.contains(c)).count();
+        assert!(changed <= 2, "{} chunks changed", changed);
+    }
+
+    #[test]
+    fn test_dedup_plan() -> Result<()> {
+        let data = random_data(1024 * 1024);
+        let mut index = DedupIndex::default();
+
+        // Nothing is stored at first
+        let plan = index.plan(&data);
+        assert_eq!(plan.pack, data);
+        assert_eq!(plan.reused_bytes(), 0);
+
+        let first_pack = Pubkey::new_unique();
+        let recipe = plan.finish(Some(first_pack), "text/plain", &mut index)?;
+        let mut tapes = HashMap::from([(first_pack, plan.pack.clone())]);
+        assert_eq!(recipe.assemble(&tapes)?, data);
+
+        // An edited copy only packs the chunks around the edit
+        let mut edited = data.clone();
+        edited[500_000] ^= 1;
+        let plan = index.plan(&edited);
+        assert!(!plan.pack.is_empty() && plan.pack.len() <= 2 * MAX_CHUNK);
+        assert_eq!(plan.reused_bytes() + plan.pack.len() as u64, edited.len() as u64);
+
+        let second_pack = Pubkey::new_unique();
+        let recipe = plan.finish(Some(second_pack), "text/plain", &mut index)?;
+        tapes.insert(second_pack, plan.pack.clone());
+        assert_eq!(recipe.assemble(&tapes)?, edited);
+        assert_eq!(recipe.tapes().len(), 2);
+
+        // The recipe and index survive a round trip
+        assert_eq!(TapeRecipe::try_from_bytes(&recipe.to_bytes())?, recipe);
+        let dir = TempDir::new("dedup")?;
+        let path = dir.path().join("index.json");
+        index.save(&path)?;
+        assert_eq!(DedupIndex::load(&path)?, index);
+
+        // A chunk that doesn't match is caught
+        tapes.get_mut(&second_pack).unwrap()[0] ^= 1;
+        assert!(recipe.assemble(&tapes).is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,229 @@
+use anyhow::{Result, anyhow, bail};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+/// A 4-byte "magic" prefix to identify a packed directory.
+pub const DIR_MAGIC: [u8; 4] = *b"TDIR";
+
+/// The version of the packed directory format.
+pub const DIR_VERSION: u8 = 1;
+
+/// The MIME type packed directories are written with.
+pub const DIR_MIME: &str = "application/x-tape-dir";
+
+/// A file in a packed directory.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PackedFile {
+    /// Path relative to the directory, `/` separated.
+    pub path: String,
+
+    /// Unix permission bits, 0 where there are none.
+    pub mode: u32,
+
+    pub data: Vec<u8>,
+}
+
+/// The regular files under `dir`, sorted by path so the same tree always packs the same way.
+/// Symlinks and empty directories aren't kept.
+pub fn read_dir_files(dir: &Path) -> Result<Vec<PackedFile>> {
+    let mut files = Vec::new();
+    collect_files(dir, dir, &mut files)?;
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(files)
+}
+
+fn collect_files(root: &Path, dir: &Path, files: &mut Vec<PackedFile>) -> Result<()> {
+    let entries = fs::read_dir(dir).map_err(|e| anyhow!("Failed to read {}: {}", dir.display(), e))?;
+
+    for entry in entries {
+        let entry = entry?;
+        let path = entry.path();
+        let file_type = entry.file_type()?;
+
+        if file_type.is_dir() {
+            collect_files(root, &path, files)?;
+        } else if file_type.is_file() {
+            let relative = path
+                .strip_prefix(root)?
+                .components()
+                .map(|c| c.as_os_str().to_str().ok_or_else(|| anyhow!("Path {} isn't UTF-8", path.display())))
+                .collect::<Result<Vec<_>>>()?
+                .join("/");
+
+            let data = fs::read(&path).map_err(|e| anyhow!("Failed to read {}: {}", path.display(), e))?;
+            files.push(PackedFile { path: relative, mode: file_mode(&entry.metadata()?), data });
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(unix)]
+fn file_mode(metadata: &fs::Metadata) -> u32 {
+    use std::os::unix::fs::PermissionsExt;
+    metadata.permissions().mode() & 0o7777
+}
+
+#[cfg(not(unix))]
+fn file_mode(_metadata: &fs::Metadata) -> u32 {
+    0
+}
+
+/// Packs files into one payload.
+///
+/// Layout:
+/// - `magic` (4 bytes)        -> always `b"TDIR"`
+/// - `version` (1 byte)       -> format version (`1`)
+/// - `count` (4 bytes, LE)    -> number of files
+/// - `files` -> in order, each:
+///   - `path_len` (2 bytes, LE) -> length of `path`
+///   - `path` (`path_len`)      -> relative, `/` separated
+///   - `mode` (4 bytes, LE)     -> Unix permission bits
+///   - `size` (8 bytes, LE)     -> length of `data`
+///   - `data` (`size`)
+pub fn pack_files(files: &[PackedFile]) -> Result<Vec<u8>> {
+    let size: usize = files.iter().map(|f| 14 + f.path.len() + f.data.len()).sum();
+    let mut data = Vec::with_capacity(9 + size);
+    data.extend_from_slice(&DIR_MAGIC);
+    data.push(DIR_VERSION);
+    data.extend_from_slice(&(files.len() as u32).to_le_bytes());
+
+    for file in files {
+        let path_len = u16::try_from(file.path.len())
+            .map_err(|_| anyhow!("Path {} is too long to pack", file.path))?;
+        data.extend_from_slice(&path_len.to_le_bytes());
+        data.extend_from_slice(file.path.as_bytes());
+        data.extend_from_slice(&file.mode.to_le_bytes());
+        data.extend_from_slice(&(file.data.len() as u64).to_le_bytes());
+        data.extend_from_slice(&file.data);
+    }
+
+    Ok(data)
+}
+
+pub fn unpack_files(data: &[u8]) -> Result<Vec<PackedFile>> {
+    if data.len() < 9 {
+        bail!("Data too short for a packed directory ({} < 9)", data.len());
+    }
+
+    if data[0..4] != DIR_MAGIC {
+        bail!("Invalid magic number in packed directory");
+    }
+
+    if data[4] != DIR_VERSION {
+        bail!(
+            "Unsupported packed directory version: found {}, expected {}",
+            data[4], DIR_VERSION
+        );
+    }
+
+    let count = u32::from_le_bytes(data[5..9].try_into()?) as usize;
+    let mut rest = &data[9..];
+    let mut files = Vec::with_capacity(count.min(rest.len() / 14));
+
+    for _ in 0..count {
+        let path_len = u16::from_le_bytes(take(&mut rest, 2)?.try_into()?) as usize;
+        let path = String::from_utf8(take(&mut rest, path_len)?.to_vec())
+            .map_err(|_| anyhow!("Invalid path in packed directory"))?;
+        let mode = u32::from_le_bytes(take(&mut rest, 4)?.try_into()?);
+        let size = u64::from_le_bytes(take(&mut rest, 8)?.try_into()?) as usize;
+        let data = take(&mut rest, size)?.to_vec();
+
+        files.push(PackedFile { path, mode, data });
+    }
+
+    if !rest.is_empty() {
+        bail!("Packed directory has {} bytes after its last file", rest.len());
+    }
+
+    Ok(files)
+}
+
+fn take<'a>(data: &mut &'a [u8], len: usize) -> Result<&'a [u8]> {
+    if data.len() < len {
+        bail!("Packed directory is cut short");
+    }
+
+    let (head, tail) = data.split_at(len);
+    *data = tail;
+    Ok(head)
+}
+
+/// Write unpacked files under `dir`, creating directories as needed. Paths that would land
+/// outside `dir` are refused.
+pub fn write_dir_files(dir: &Path, files: &[PackedFile]) -> Result<()> {
+    for file in files {
+        let path = safe_join(dir, &file.path)?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| anyhow!("Failed to create {}: {}", parent.display(), e))?;
+        }
+
+        fs::write(&path, &file.data).map_err(|e| anyhow!("Failed to write {}: {}", path.display(), e))?;
+        set_file_mode(&path, file.mode)?;
+    }
+
+    Ok(())
+}
+
+fn safe_join(dir: &Path, relative: &str) -> Result<PathBuf> {
+    let relative = Path::new(relative);
+    if relative.components().any(|c| !matches!(c, Component::Normal(_))) {
+        bail!("Refusing to unpack {} outside the target directory", relative.display());
+    }
+
+    Ok(dir.join(relative))
+}
+
+#[cfg(unix)]
+fn set_file_mode(path: &Path, mode: u32) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    if mode != 0 {
+        fs::set_permissions(path, fs::Permissions::from_mode(mode))?;
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn set_file_mode(_path: &Path, _mode: u32) -> Result<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempdir::TempDir;
+
+    #[test]
+    fn test_pack_dir() -> Result<()> {
+        let source = TempDir::new("source")?;
+        fs::create_dir_all(source.path().join("nested/deeper"))?;
+        fs::write(source.path().join("b.txt"), b"second")?;
+        fs::write(source.path().join("a.txt"), b"first")?;
+        fs::write(source.path().join("nested/deeper/c.bin"), [0u8, 1, 2])?;
+
+        let files = read_dir_files(source.path())?;
+        let paths: Vec<&str> = files.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(paths, ["a.txt", "b.txt", "nested/deeper/c.bin"]);
+
+        let packed = pack_files(&files)?;
+        assert_eq!(unpack_files(&packed)?, files);
+        assert!(unpack_files(&packed[..packed.len() - 1]).is_err());
+
+        let target = TempDir::new("target")?;
+        write_dir_files(target.path(), &files)?;
+        assert_eq!(read_dir_files(target.path())?, files);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_unpack_outside_dir() -> Result<()> {
+        let target = TempDir::new("target")?;
+        let files = [PackedFile { path: "../escape.txt".to_string(), mode: 0, data: vec![1] }];
+
+        assert!(write_dir_files(&target.path().join("inner"), &files).is_err());
+        assert!(!target.path().join("escape.txt").exists());
+
+        Ok(())
+    }
+}
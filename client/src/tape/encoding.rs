@@ -1,35 +1,771 @@
-use anyhow::{Result, anyhow};
-use crate::utils::*;
-use super::{TapeHeader, CompressionAlgo};
+use anyhow::{Result, anyhow, bail};
+use std::collections::BTreeMap;
+use std::ops::Range;
+use tape_api::consts::{MAX_TAPE_SIZE, SEGMENT_SIZE};
+use crate::{consts::*, utils::*};
+use super::{key_id, open_sealed, seal, SealKeypair, TapeHeader, TapeFlags, CompressionAlgo};
 
-/// Encodes data into a tape format, applying compression if specified in the header.
+/// Encodes data into a tape format, applying compression if specified in the header. Fails if the
+/// encoded data wouldn't fit on a single tape, see `split_into_tapes` for larger payloads.
 pub fn encode_tape(data: &[u8], header: &TapeHeader) -> Result<Vec<u8>> {
+    if header.is_sealed() {
+        bail!("Sealed tapes need a recipient, encode them with `encode_sealed_tape`");
+    }
 
+    let encoded = compress_payload(data, header)?;
+    check_capacity(&encoded, header)?;
+
+    Ok(encoded)
+}
+
+/// Encodes data like `encode_tape`, then seals it to `recipient` (see `seal`). The header must
+/// have been marked with `TapeHeader::seal_for` the same recipient.
+pub fn encode_sealed_tape(data: &[u8], header: &TapeHeader, recipient: &[u8; 32]) -> Result<Vec<u8>> {
+    if !header.is_sealed() || header.iv != key_id(recipient) {
+        bail!("Header isn't sealed for this recipient, see `TapeHeader::seal_for`");
+    }
+
+    // Compress first, ciphertext doesn't compress
+    let sealed = seal(&compress_payload(data, header)?, recipient)?;
+    check_capacity(&sealed, header)?;
+
+    Ok(sealed)
+}
+
+fn compress_payload(data: &[u8], header: &TapeHeader) -> Result<Vec<u8>> {
     let compression_algo = CompressionAlgo::try_from(header.compression)
         .map_err(|_| anyhow!("Invalid compression algorithm"))? ;
 
-    let compressed = match compression_algo {
+    match compression_algo {
         CompressionAlgo::None => Ok(data.to_vec()),
         CompressionAlgo::Gzip => compress(data),
+        CompressionAlgo::GzipChunked => compress_chunked(data, FRAME_SIZE),
+    }
+}
+
+/// The number of raw bytes in each independently compressed frame of a `GzipChunked` tape.
+pub const FRAME_SIZE: usize = 64 * 1024;
+
+/// The index at the front of a `GzipChunked` payload, ahead of the frames it lists:
+///
+///     | frame_size (u32) | frame_count (u32) | compressed length of each frame (u32) | frames… |
+///
+/// Every frame but the last holds `frame_size` raw bytes, so the frames covering a byte range,
+/// and where they sit in the payload, follow from the index alone.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FrameIndex {
+    pub frame_size: u64,
+    pub frame_lengths: Vec<u64>,
+}
+
+impl FrameIndex {
+    /// The bytes ahead of the frame lengths.
+    pub const PREFIX_LEN: usize = 8;
+
+    /// The length of the index at the front of `data`, known from its prefix alone.
+    pub fn encoded_len(data: &[u8]) -> Result<usize> {
+        let count = data.get(4..8)
+            .ok_or_else(|| anyhow!("Frame index is truncated"))?;
+        let count = u32::from_le_bytes(count.try_into()?) as usize;
+        Ok(Self::PREFIX_LEN + count * 4)
+    }
+
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        let len = Self::encoded_len(data)?;
+        let Some(index) = data.get(..len) else {
+            bail!("Frame index is truncated");
+        };
+
+        let frame_size = u32::from_le_bytes(index[..4].try_into()?) as u64;
+        if frame_size == 0 {
+            bail!("Frame index has an empty frame size");
+        }
+        let frame_lengths = index[Self::PREFIX_LEN..]
+            .chunks_exact(4)
+            .map(|b| u32::from_le_bytes(b.try_into().unwrap()) as u64)
+            .collect();
+
+        Ok(Self { frame_size, frame_lengths })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(Self::PREFIX_LEN + self.frame_lengths.len() * 4);
+        bytes.extend_from_slice(&(self.frame_size as u32).to_le_bytes());
+        bytes.extend_from_slice(&(self.frame_lengths.len() as u32).to_le_bytes());
+        for len in &self.frame_lengths {
+            bytes.extend_from_slice(&(*len as u32).to_le_bytes());
+        }
+        bytes
+    }
+
+    /// Where frame `frame` sits in the payload.
+    pub fn frame_range(&self, frame: usize) -> Range<u64> {
+        let start = (Self::PREFIX_LEN + self.frame_lengths.len() * 4) as u64
+            + self.frame_lengths[..frame].iter().sum::<u64>();
+        start..start + self.frame_lengths[frame]
+    }
+
+    /// The frames holding raw bytes `offset..offset + len`, or `None` if the range starts past
+    /// the last frame.
+    pub fn frames_for(&self, offset: u64, len: u64) -> Option<Range<usize>> {
+        let first = (offset / self.frame_size) as usize;
+        if len == 0 || first >= self.frame_lengths.len() {
+            return None;
+        }
+
+        let last = ((offset.saturating_add(len) - 1) / self.frame_size) as usize;
+        Some(first..(last + 1).min(self.frame_lengths.len()))
+    }
+}
+
+/// Compresses `data` in independent gzip frames of `frame_size` raw bytes, behind a
+/// `FrameIndex`.
+fn compress_chunked(data: &[u8], frame_size: usize) -> Result<Vec<u8>> {
+    let frames = data
+        .chunks(frame_size)
+        .map(compress)
+        .collect::<Result<Vec<_>>>()?;
+
+    let index = FrameIndex {
+        frame_size: frame_size as u64,
+        frame_lengths: frames.iter().map(|f| f.len() as u64).collect(),
     };
 
-    // Add encryption, etc...
+    let mut encoded = index.to_bytes();
+    for frame in frames {
+        encoded.extend_from_slice(&frame);
+    }
+    Ok(encoded)
+}
+
+fn decompress_chunked(data: &[u8]) -> Result<Vec<u8>> {
+    let index = FrameIndex::parse(data)?;
+    let mut decompressed = Vec::new();
+    for frame in 0..index.frame_lengths.len() {
+        let range = index.frame_range(frame);
+        let Some(frame_data) = data.get(range.start as usize..range.end as usize) else {
+            bail!("Frame {} is truncated", frame);
+        };
+        decompressed.extend(decompress(frame_data)?);
+    }
+    Ok(decompressed)
+}
+
+/// Decompresses every frame up to the first one that doesn't decode. Frames are independent,
+/// but the bytes past a bad one would land at the wrong offsets.
+fn decompress_chunked_partial(data: &[u8]) -> Vec<u8> {
+    let Ok(index) = FrameIndex::parse(data) else {
+        return Vec::new();
+    };
+
+    let mut decompressed = Vec::new();
+    for frame in 0..index.frame_lengths.len() {
+        let range = index.frame_range(frame);
+        let frame_data = data.get(range.start as usize..(range.end as usize).min(data.len())).unwrap_or_default();
+        let raw = decompress_partial(frame_data);
+        let whole = raw.len() as u64 == index.frame_size;
+        decompressed.extend(raw);
+        if !whole {
+            break;
+        }
+    }
+    decompressed
+}
+
+/// Decompresses the frames of a `GzipChunked` payload read by `FrameIndex::frame_range`, from
+/// the start of `frames.start` to the end of the last one.
+pub fn decompress_frames(data: &[u8], index: &FrameIndex, frames: Range<usize>) -> Result<Vec<u8>> {
+    let base = index.frame_range(frames.start).start;
+    let mut decompressed = Vec::new();
+    for frame in frames {
+        let range = index.frame_range(frame);
+        let Some(frame_data) = data.get((range.start - base) as usize..(range.end - base) as usize) else {
+            bail!("Frame {} is truncated", frame);
+        };
+        decompressed.extend(decompress(frame_data)?);
+    }
+    Ok(decompressed)
+}
+
+/// Decodes a tape's encoded data as it's read, in order, so decompressing keeps pace with the
+/// reads instead of starting after the last one. `Gzip` streams through a single decoder and
+/// `GzipChunked` frames are decompressed as soon as they're whole. Sealed tapes can only be
+/// opened whole, collect them with `CompressionAlgo::None` and open them with
+/// `decode_sealed_tape`.
+pub struct StreamDecoder {
+    state: DecoderState,
+}
+
+enum DecoderState {
+    Raw(Vec<u8>),
+    Gzip(flate2::write::GzDecoder<Vec<u8>>),
+    Chunked {
+        /// Read but not decompressed yet, at most the index or a frame and a read.
+        pending: Vec<u8>,
+        index: Option<FrameIndex>,
+        next_frame: usize,
+        decoded: Vec<u8>,
+    },
+}
+
+impl StreamDecoder {
+    pub fn new(compression: CompressionAlgo) -> Self {
+        let state = match compression {
+            CompressionAlgo::None => DecoderState::Raw(Vec::new()),
+            CompressionAlgo::Gzip => DecoderState::Gzip(flate2::write::GzDecoder::new(Vec::new())),
+            CompressionAlgo::GzipChunked => DecoderState::Chunked {
+                pending: Vec::new(),
+                index: None,
+                next_frame: 0,
+                decoded: Vec::new(),
+            },
+        };
+        Self { state }
+    }
+
+    /// Decode the next `data` of the payload, as much of it as can be so far.
+    pub fn push(&mut self, mut data: &[u8]) -> Result<()> {
+        match &mut self.state {
+            DecoderState::Raw(decoded) => decoded.extend_from_slice(data),
+            DecoderState::Gzip(decoder) => {
+                while !data.is_empty() {
+                    // Bytes past the end of the stream are ignored, as `decompress` does
+                    let n = std::io::Write::write(decoder, data)?;
+                    if n == 0 {
+                        break;
+                    }
+                    data = &data[n..];
+                }
+            }
+            DecoderState::Chunked { pending, index, next_frame, decoded } => {
+                pending.extend_from_slice(data);
+
+                if index.is_none() {
+                    match FrameIndex::encoded_len(pending) {
+                        Ok(len) if pending.len() >= len => {
+                            *index = Some(FrameIndex::parse(pending)?);
+                            pending.drain(..len);
+                        }
+                        _ => return Ok(()),
+                    }
+                }
+
+                let Some(index) = index else {
+                    return Ok(());
+                };
+                while let Some(&len) = index.frame_lengths.get(*next_frame) {
+                    if (pending.len() as u64) < len {
+                        break;
+                    }
+                    decoded.extend(decompress(&pending[..len as usize])?);
+                    pending.drain(..len as usize);
+                    *next_frame += 1;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The decoded payload, failing if what was pushed ended early.
+    pub fn finish(self) -> Result<Vec<u8>> {
+        match self.state {
+            DecoderState::Raw(decoded) => Ok(decoded),
+            DecoderState::Gzip(decoder) => decoder.finish().map_err(Into::into),
+            DecoderState::Chunked { index, next_frame, decoded, .. } => {
+                let Some(index) = index else {
+                    bail!("Frame index is truncated");
+                };
+                if next_frame < index.frame_lengths.len() {
+                    bail!("Frame {} is truncated", next_frame);
+                }
+                Ok(decoded)
+            }
+        }
+    }
+}
+
+fn check_capacity(encoded: &[u8], header: &TapeHeader) -> Result<()> {
+    let capacity = tape_capacity(header);
+    if encoded.len() > capacity {
+        bail!(
+            "Encoded data is {} bytes, but a single tape holds at most {} bytes; \
+             split it across several tapes (see `split_into_tapes`)",
+            encoded.len(), capacity
+        );
+    }
+
+    Ok(())
+}
+
+/// The number of encoded bytes each write carries, `WIDE_SIZE` for wide tapes and `SAFE_SIZE`
+/// otherwise.
+pub fn chunk_size(header: &TapeHeader) -> usize {
+    if header.flags & u8::from(TapeFlags::Wide) != 0 {
+        WIDE_SIZE
+    } else {
+        SAFE_SIZE
+    }
+}
 
-    compressed
+/// The number of bytes each full write puts on the tape, its chunk plus the previous signature on
+/// linked tapes.
+pub fn write_size(header: &TapeHeader) -> usize {
+    if header.flags & u8::from(TapeFlags::Linked) != 0 {
+        SIGNATURE_SIZE + chunk_size(header)
+    } else {
+        chunk_size(header)
+    }
+}
+
+/// The maximum number of encoded bytes a single tape can hold. Every write is padded to whole
+/// segments on-chain, and linked tapes spend part of each write on the previous signature.
+pub fn tape_capacity(header: &TapeHeader) -> usize {
+    let max_segments = MAX_TAPE_SIZE / SEGMENT_SIZE;
+    let segments_per_write = write_size(header).div_ceil(SEGMENT_SIZE);
+    (max_segments / segments_per_write) * chunk_size(header)
+}
+
+/// Splits a payload that is too large for one tape into parts of raw data, each small enough to
+/// fit on its own tape once encoded with `header`. Write each part to a tape in order, then link
+/// them together with a `TapeManifest`.
+pub fn split_into_tapes<'a>(data: &'a [u8], header: &TapeHeader) -> Vec<&'a [u8]> {
+    let capacity = tape_capacity(header);
+
+    // Leave headroom for compression overhead on incompressible data
+    let part_size = capacity - capacity / 100;
+    if data.len() <= part_size {
+        return vec![data];
+    }
+
+    data.chunks(part_size).collect()
 }
 
 /// Decodes a tape format into raw data, decompressing if necessary based on the header.
 pub fn decode_tape(data: Vec<u8>, header: &TapeHeader) -> Result<Vec<u8>> {
+    if header.is_sealed() {
+        bail!("Tape is sealed to a recipient, open it with `decode_sealed_tape`");
+    }
+
+    decompress_payload(data, header)
+}
+
+/// Decodes a tape sealed to `keypair`, failing if it was sealed to someone else. Unsealed tapes
+/// are decoded as usual.
+pub fn decode_sealed_tape(data: Vec<u8>, header: &TapeHeader, keypair: &SealKeypair) -> Result<Vec<u8>> {
+    if !header.is_sealed() {
+        return decode_tape(data, header);
+    }
 
+    if header.iv != keypair.id() {
+        bail!("Tape is sealed to another key");
+    }
+
+    decompress_payload(open_sealed(&data, keypair)?, header)
+}
+
+fn decompress_payload(data: Vec<u8>, header: &TapeHeader) -> Result<Vec<u8>> {
     let compression_algo = CompressionAlgo::try_from(header.compression)
         .map_err(|_| anyhow!("Invalid compression algorithm"))?;
 
-    let decompressed = match compression_algo {
+    match compression_algo {
         CompressionAlgo::None => Ok(data),
         CompressionAlgo::Gzip => decompress(&data),
+        CompressionAlgo::GzipChunked => decompress_chunked(&data),
+    }
+}
+
+/// Reassembles the encoded tape data from its segments (as returned by TAPENET), stripping the
+/// signature prepended to every write if the tape is linked. Segments must be sorted and
+/// contiguous, starting at zero.
+pub fn unprefix_segments(segments: Vec<(u64, Vec<u8>)>, header: &TapeHeader) -> Result<Vec<u8>> {
+    let mut data = Vec::with_capacity(segments.len() * SEGMENT_SIZE);
+
+    for (expected, (segment_number, segment)) in segments.into_iter().enumerate() {
+        if segment_number != expected as u64 {
+            bail!("Missing segment {} (found segment {})", expected, segment_number);
+        }
+        data.extend_from_slice(&segment);
+    }
+
+    if header.flags & u8::from(TapeFlags::Linked) == 0 {
+        return Ok(data);
+    }
+
+    // Every linked write is <prev_sig><chunk>, where all but the last chunk are full size
+    let mut unprefixed = Vec::with_capacity(data.len());
+    for chunk in data.chunks(SIGNATURE_SIZE + chunk_size(header)) {
+        if chunk.len() < SIGNATURE_SIZE {
+            bail!("Linked chunk is too short to contain a signature");
+        }
+        unprefixed.extend_from_slice(&chunk[SIGNATURE_SIZE..]);
+    }
+
+    Ok(unprefixed)
+}
+
+/// A tape reassembled from an incomplete or inconsistent set of segments.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RecoveredTape {
+    /// The encoded tape data, with missing segments zero-filled.
+    pub bytes: Vec<u8>,
+
+    /// Ranges of segment numbers that were missing.
+    pub missing_ranges: Vec<Range<u64>>,
+
+    /// Segment numbers that were seen more than once with different data (the first copy is kept).
+    pub duplicate_conflicts: Vec<u64>,
+}
+
+impl RecoveredTape {
+    /// Returns true if every segment was present and consistent.
+    pub fn is_complete(&self) -> bool {
+        self.missing_ranges.is_empty() && self.duplicate_conflicts.is_empty()
+    }
+}
+
+/// Lenient version of `unprefix_segments` that reassembles whatever is present instead of failing
+/// on the first gap. Missing segments (up to `total_segments`) are zero-filled at the size they'd
+/// have in a full write, so the data around them stays in place, and reported in the result.
+pub fn recover_segments(
+    mut segments: Vec<(u64, Vec<u8>)>,
+    header: &TapeHeader,
+    total_segments: u64,
+) -> RecoveredTape {
+    let linked = header.flags & u8::from(TapeFlags::Linked) != 0;
+    let chunk_size = chunk_size(header);
+    let write_size = if linked { SIGNATURE_SIZE + chunk_size } else { chunk_size };
+    let segments_per_write = write_size.div_ceil(SEGMENT_SIZE);
+
+    // A stable sort keeps the first copy of a duplicate first
+    segments.sort_by_key(|(segment_number, _)| *segment_number);
+
+    let mut unique: BTreeMap<u64, Vec<u8>> = BTreeMap::new();
+    let mut duplicate_conflicts = Vec::new();
+    for (segment_number, segment) in segments {
+        match unique.get(&segment_number) {
+            None => {
+                unique.insert(segment_number, segment);
+            }
+            Some(existing) => {
+                if *existing != segment && !duplicate_conflicts.contains(&segment_number) {
+                    duplicate_conflicts.push(segment_number);
+                }
+            }
+        }
+    }
+
+    let last = unique.keys().next_back().map(|n| n + 1).unwrap_or(0);
+    let total = total_segments.max(last);
+
+    let mut data = Vec::with_capacity(total as usize * SEGMENT_SIZE);
+    let mut missing_ranges: Vec<Range<u64>> = Vec::new();
+
+    for segment_number in 0..total {
+        if let Some(segment) = unique.remove(&segment_number) {
+            data.extend_from_slice(&segment);
+            continue;
+        }
+
+        match missing_ranges.last_mut() {
+            Some(range) if range.end == segment_number => range.end += 1,
+            _ => missing_ranges.push(segment_number..segment_number + 1),
+        }
+
+        let offset = (segment_number as usize % segments_per_write) * SEGMENT_SIZE;
+        data.resize(data.len() + SEGMENT_SIZE.min(write_size - offset), 0);
+    }
+
+    let bytes = if linked {
+        data.chunks(write_size)
+            .filter(|chunk| chunk.len() > SIGNATURE_SIZE)
+            .flat_map(|chunk| chunk[SIGNATURE_SIZE..].to_vec())
+            .collect()
+    } else {
+        data
     };
 
-    // Add encryption, etc...
+    RecoveredTape {
+        bytes,
+        missing_ranges,
+        duplicate_conflicts,
+    }
+}
 
-    decompressed
+/// Lenient version of `decode_tape` that returns as much data as can be decoded, for use on
+/// recovered tapes. A gap in compressed data cuts the output short at the gap. Sealed tapes can't
+/// be opened with a gap, their tag covers the whole payload.
+pub fn decode_tape_partial(data: Vec<u8>, header: &TapeHeader) -> Result<Vec<u8>> {
+    if header.is_sealed() {
+        bail!("Sealed tapes can't be partially decoded");
+    }
+
+    let compression_algo = CompressionAlgo::try_from(header.compression)
+        .map_err(|_| anyhow!("Invalid compression algorithm"))?;
+
+    let decompressed = match compression_algo {
+        CompressionAlgo::None => data,
+        CompressionAlgo::Gzip => decompress_partial(&data),
+        CompressionAlgo::GzipChunked => decompress_chunked_partial(&data),
+    };
+
+    Ok(decompressed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MimeType, EncryptionAlgo};
+
+    fn to_segments(data: &[u8]) -> Vec<(u64, Vec<u8>)> {
+        data.chunks(SEGMENT_SIZE)
+            .enumerate()
+            .map(|(i, s)| (i as u64, s.to_vec()))
+            .collect()
+    }
+
+    #[test]
+    fn test_unprefix_linked_segments() {
+        let header = TapeHeader::new(
+            MimeType::Unknown,
+            CompressionAlgo::None,
+            EncryptionAlgo::None,
+            TapeFlags::Linked,
+        );
+
+        let original: Vec<u8> = (0..2000).map(|i| i as u8).collect();
+        let mut written = Vec::new();
+        for chunk in original.chunks(SAFE_SIZE) {
+            written.extend_from_slice(&[7u8; SIGNATURE_SIZE]);
+            written.extend_from_slice(chunk);
+        }
+
+        let data = unprefix_segments(to_segments(&written), &header).unwrap();
+        assert_eq!(data, original);
+    }
+
+    #[test]
+    fn test_tape_capacity() {
+        let linked = TapeHeader::new(
+            MimeType::Unknown,
+            CompressionAlgo::None,
+            EncryptionAlgo::None,
+            TapeFlags::Linked,
+        );
+
+        // 8 segments per linked write, 7 of them payload
+        assert_eq!(tape_capacity(&linked), MAX_TAPE_SIZE / 8 / SEGMENT_SIZE * SAFE_SIZE);
+        assert!(encode_tape(&vec![0u8; tape_capacity(&linked)], &linked).is_ok());
+        assert!(encode_tape(&vec![0u8; tape_capacity(&linked) + 1], &linked).is_err());
+    }
+
+    #[test]
+    fn test_wide_tapes() {
+        let mut wide = TapeHeader::new(
+            MimeType::Unknown,
+            CompressionAlgo::None,
+            EncryptionAlgo::None,
+            TapeFlags::Linked,
+        );
+        wide.flags |= u8::from(TapeFlags::Wide);
+
+        // Still 8 segments per linked write, but more of each is payload
+        assert_eq!(chunk_size(&wide), WIDE_SIZE);
+        assert_eq!(tape_capacity(&wide), MAX_TAPE_SIZE / 8 / SEGMENT_SIZE * WIDE_SIZE);
+
+        let original: Vec<u8> = (0..3000).map(|i| i as u8).collect();
+        let mut written = Vec::new();
+        for chunk in original.chunks(WIDE_SIZE) {
+            written.extend_from_slice(&[7u8; SIGNATURE_SIZE]);
+            written.extend_from_slice(chunk);
+        }
+
+        let data = unprefix_segments(to_segments(&written), &wide).unwrap();
+        assert_eq!(data, original);
+    }
+
+    #[test]
+    fn test_split_into_tapes() {
+        let header = TapeHeader::new(
+            MimeType::Unknown,
+            CompressionAlgo::None,
+            EncryptionAlgo::None,
+            TapeFlags::Linked,
+        );
+
+        let data: Vec<u8> = (0..tape_capacity(&header) * 2).map(|i| i as u8).collect();
+        let parts = split_into_tapes(&data, &header);
+
+        assert_eq!(parts.len(), 3);
+        assert!(parts.iter().all(|p| encode_tape(p, &header).is_ok()));
+        assert_eq!(parts.concat(), data);
+    }
+
+    /// Segments as the archive stores them, each write split separately.
+    fn to_write_segments(data: &[u8]) -> Vec<(u64, Vec<u8>)> {
+        let mut segments = Vec::new();
+        for write in data.chunks(SIGNATURE_SIZE + SAFE_SIZE) {
+            for segment in write.chunks(SEGMENT_SIZE) {
+                segments.push((segments.len() as u64, segment.to_vec()));
+            }
+        }
+        segments
+    }
+
+    #[test]
+    fn test_recover_missing_segments() {
+        let header = TapeHeader::new(
+            MimeType::Unknown,
+            CompressionAlgo::None,
+            EncryptionAlgo::None,
+            TapeFlags::Linked,
+        );
+
+        let original: Vec<u8> = (0..2000).map(|i| (i % 251 + 1) as u8).collect();
+        let mut written = Vec::new();
+        for chunk in original.chunks(SAFE_SIZE) {
+            written.extend_from_slice(&[7u8; SIGNATURE_SIZE]);
+            written.extend_from_slice(chunk);
+        }
+
+        let mut segments = to_write_segments(&written);
+        let total = segments.len() as u64;
+        segments.remove(3);
+
+        let recovered = recover_segments(segments.clone(), &header, total);
+        assert_eq!(recovered.missing_ranges, vec![3..4]);
+        assert!(recovered.duplicate_conflicts.is_empty());
+        assert_eq!(recovered.bytes.len(), original.len());
+
+        // Segment 3 covers bytes 384..512 of the first write, 320..448 once unlinked
+        assert!(recovered.bytes[320..448].iter().all(|&b| b == 0));
+        assert_eq!(recovered.bytes[..320], original[..320]);
+        assert_eq!(recovered.bytes[448..], original[448..]);
+
+        // Trailing segments are reported too
+        let recovered = recover_segments(segments, &header, total + 2);
+        assert_eq!(recovered.missing_ranges, vec![3..4, total..total + 2]);
+    }
+
+    #[test]
+    fn test_recover_duplicate_conflicts() {
+        let header = TapeHeader::new(
+            MimeType::Unknown,
+            CompressionAlgo::None,
+            EncryptionAlgo::None,
+            TapeFlags::None,
+        );
+
+        let mut segments = to_segments(&[1u8; 300]);
+        segments.push((0, vec![1u8; SEGMENT_SIZE]));
+        segments.push((1, vec![9u8; SEGMENT_SIZE]));
+
+        let recovered = recover_segments(segments, &header, 3);
+        assert!(recovered.missing_ranges.is_empty());
+        assert_eq!(recovered.duplicate_conflicts, vec![1]);
+        assert_eq!(recovered.bytes, vec![1u8; 300]);
+    }
+
+    #[test]
+    fn test_sealed_tape() -> Result<()> {
+        let recipient = SealKeypair::generate();
+        let mut header = TapeHeader::new(
+            MimeType::Unknown,
+            CompressionAlgo::Gzip,
+            EncryptionAlgo::None,
+            TapeFlags::Linked,
+        );
+        header.seal_for(&recipient.public);
+
+        let data = vec![42u8; 5000];
+        let encoded = encode_sealed_tape(&data, &header, &recipient.public)?;
+        assert!(encoded.len() < data.len());
+        assert_eq!(decode_sealed_tape(encoded.clone(), &header, &recipient)?, data);
+
+        // Plain decoding refuses, as does anyone else's key
+        assert!(decode_tape(encoded.clone(), &header).is_err());
+        assert!(decode_sealed_tape(encoded, &header, &SealKeypair::generate()).is_err());
+
+        // The header has to name the recipient
+        assert!(encode_tape(&data, &header).is_err());
+        assert!(encode_sealed_tape(&data, &header, &SealKeypair::generate().public).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_unprefix_missing_segment() {
+        let header = TapeHeader::new(
+            MimeType::Unknown,
+            CompressionAlgo::None,
+            EncryptionAlgo::None,
+            TapeFlags::None,
+        );
+
+        let mut segments = to_segments(&[1u8; 1000]);
+        segments.remove(3);
+
+        assert!(unprefix_segments(segments, &header).is_err());
+    }
+
+    #[test]
+    fn test_chunked_compression() -> Result<()> {
+        let header = TapeHeader::new(
+            MimeType::Unknown,
+            CompressionAlgo::GzipChunked,
+            EncryptionAlgo::None,
+            TapeFlags::Linked,
+        );
+
+        let data: Vec<u8> = (0..3 * FRAME_SIZE + 100).map(|i| (i / 7 % 251) as u8).collect();
+        let encoded = encode_tape(&data, &header)?;
+        assert!(encoded.len() < data.len());
+        assert_eq!(decode_tape(encoded.clone(), &header)?, data);
+
+        let index = FrameIndex::parse(&encoded)?;
+        assert_eq!(index.frame_lengths.len(), 4);
+        assert_eq!(FrameIndex::encoded_len(&encoded[..8])?, 8 + 4 * 4);
+
+        // A range only needs the frames holding it
+        let offset = FRAME_SIZE as u64 + 10;
+        let frames = index.frames_for(offset, FRAME_SIZE as u64).unwrap();
+        assert_eq!(frames, 1..3);
+
+        let start = index.frame_range(1).start as usize;
+        let end = index.frame_range(2).end as usize;
+        let raw = decompress_frames(&encoded[start..end], &index, frames)?;
+        assert_eq!(raw, data[FRAME_SIZE..3 * FRAME_SIZE]);
+
+        assert_eq!(index.frames_for(4 * FRAME_SIZE as u64, 1), None);
+
+        // Losing the end of the payload keeps the whole frames before it
+        let partial = decode_tape_partial(encoded[..end + 5].to_vec(), &header)?;
+        assert_eq!(partial, data[..3 * FRAME_SIZE]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_stream_decoder() -> Result<()> {
+        let data: Vec<u8> = (0..2 * FRAME_SIZE + 300).map(|i| (i / 5 % 251) as u8).collect();
+
+        for compression in [CompressionAlgo::None, CompressionAlgo::Gzip, CompressionAlgo::GzipChunked] {
+            let header = TapeHeader::new(MimeType::Unknown, compression, EncryptionAlgo::None, TapeFlags::Linked);
+            let encoded = encode_tape(&data, &header)?;
+
+            // Pushed a write at a time, the index and frames split across them
+            let mut decoder = StreamDecoder::new(compression);
+            for chunk in encoded.chunks(SAFE_SIZE) {
+                decoder.push(chunk)?;
+            }
+            assert_eq!(decoder.finish()?, data, "{:?}", compression);
+
+            // Ending early is an error, but not for raw data, which has no length to check
+            let mut decoder = StreamDecoder::new(compression);
+            decoder.push(&encoded[..encoded.len() - 10])?;
+            assert_eq!(decoder.finish().is_err(), compression != CompressionAlgo::None, "{:?}", compression);
+        }
+
+        Ok(())
+    }
 }
@@ -1,6 +1,6 @@
 use anyhow::Result;
 use solana_sdk::{
-    signature::{Keypair, Signer},
+    signature::Signer,
     transaction::Transaction,
     pubkey::Pubkey,
 };
@@ -13,7 +13,7 @@ use super::TapeHeader;
 /// Finalizes the tape with the last segment's signature.
 pub async fn finalize_tape(
     client: &RpcClient,
-    signer: &Keypair,
+    signer: &dyn Signer,
     tape_address: Pubkey,
     writer_address: Pubkey,
     header: TapeHeader,
@@ -21,7 +21,7 @@ pub async fn finalize_tape(
     let header_data = header.to_bytes().try_into()
         .map_err(|_| anyhow::anyhow!("Failed to convert header to bytes"))?;
 
-    let finalize_ix = build_finalize_ix(
+    let finalize_ix = program_context().build_finalize_ix(
         signer.pubkey(),
         tape_address,
         writer_address,
@@ -12,6 +12,9 @@ pub const HEADER_VERSION: u8 = 1;
 /// If `mime_type == MimeType::Custom as u8`, then `mime_str` holds the real text.
 pub const MIME_STR_LEN: usize = 32;
 
+/// How many bytes of user-defined metadata fit in the header.
+pub const META_LEN: usize = 10;
+
 #[repr(u8)]
 #[derive(Clone, Copy, Debug, Eq, PartialEq, IntoPrimitive, TryFromPrimitive)]
 /// Flags for the tape data header.
@@ -36,6 +39,10 @@ pub enum TapeFlags {
     /// since a writer can rewind to the last known good segment if finalization hasn’t completed.
     Linked = 1 << 0,
 
+    /// Writes carry `WIDE_SIZE` byte chunks instead of `SAFE_SIZE`. These only fit when the
+    /// tape, writer and config accounts come from an address lookup table (v0 transactions).
+    Wide = 1 << 1,
+
     // Extend as needed...
 }
 
@@ -103,6 +110,52 @@ pub enum MimeType {
     Custom                 = 255, // Reserved for custom MIME types
 }
 
+impl MimeType {
+    /// The MIME string for a predefined type. `Custom` has no fixed string, see `TapeHeader::mime`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MimeType::Unknown           => "application/octet-stream",
+            MimeType::ImagePng          => "image/png",
+            MimeType::ImageJpeg         => "image/jpeg",
+            MimeType::ImageGif          => "image/gif",
+            MimeType::ImageWebp         => "image/webp",
+            MimeType::ImageBmp          => "image/bmp",
+            MimeType::ImageTiff         => "image/tiff",
+            MimeType::ApplicationPdf    => "application/pdf",
+            MimeType::ApplicationMsword => "application/msword",
+            MimeType::ApplicationDocx   => "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+            MimeType::ApplicationOdt    => "application/vnd.oasis.opendocument.text",
+            MimeType::TextPlain         => "text/plain",
+            MimeType::TextHtml          => "text/html",
+            MimeType::TextCss           => "text/css",
+            MimeType::TextJavascript    => "text/javascript",
+            MimeType::TextCsv           => "text/csv",
+            MimeType::TextMarkdown      => "text/markdown",
+            MimeType::AudioMpeg         => "audio/mpeg",
+            MimeType::AudioWav          => "audio/wav",
+            MimeType::AudioOgg          => "audio/ogg",
+            MimeType::AudioFlac         => "audio/flac",
+            MimeType::VideoMp4          => "video/mp4",
+            MimeType::VideoWebm         => "video/webm",
+            MimeType::VideoMpeg         => "video/mpeg",
+            MimeType::VideoAvi          => "video/x-msvideo",
+            MimeType::ApplicationJson   => "application/json",
+            MimeType::ApplicationXml    => "application/xml",
+            MimeType::ApplicationZip    => "application/zip",
+            MimeType::ApplicationGzip   => "application/gzip",
+            MimeType::ApplicationTar    => "application/x-tar",
+            MimeType::FontWoff          => "font/woff",
+            MimeType::FontWoff2         => "font/woff2",
+            MimeType::FontTtf           => "font/ttf",
+            MimeType::FontOtf           => "font/otf",
+            MimeType::ApplicationRtf    => "application/rtf",
+            MimeType::ApplicationSql    => "application/sql",
+            MimeType::ApplicationYaml   => "application/x-yaml",
+            MimeType::Custom            => "application/octet-stream",
+        }
+    }
+}
+
 #[repr(u8)]
 #[derive(Clone, Copy, Debug, Eq, PartialEq, IntoPrimitive, TryFromPrimitive)]
 /// Compression algorithm used on the payload (if any).
@@ -110,6 +163,10 @@ pub enum CompressionAlgo {
     None   = 0,
     Gzip   = 1,
 
+    /// Gzip in independent frames behind an index, so byte ranges can be read without the rest
+    /// of the tape, see `FrameIndex`.
+    GzipChunked = 2,
+
     // Extend as needed...
 }
 
@@ -119,6 +176,10 @@ pub enum CompressionAlgo {
 pub enum EncryptionAlgo {
     None               = 0,
 
+    /// Sealed to a recipient's X25519 public key, see `seal`. The payload starts with the
+    /// ephemeral public key and `iv` holds the recipient's `key_id`.
+    SealedBox          = 1,
+
     // Extend as needed...
 }
 
@@ -134,7 +195,8 @@ pub enum EncryptionAlgo {
 /// - `compression` (1 byte)      -> see `CompressionAlgo`
 /// - `encryption_algo` (1 byte)  -> see `EncryptionAlgo`
 /// - `iv` (12 bytes)             -> IV/nonce if encrypted; all zeros otherwise
-/// - `_unused` (11 bytes)        -> padding for 128-byte size
+/// - `meta_len` (1 byte)         -> length of `meta` in use
+/// - `meta` (10 bytes)           -> user-defined metadata (e.g. an app ID or schema version)
 /// - `tail_signature` (64 bytes) -> 64-byte blockchain signature (the “tail” end of the tape)
 #[repr(C)]
 #[derive(Clone, Copy, PartialEq, Pod, Zeroable)]
@@ -165,7 +227,12 @@ pub struct TapeHeader {
     /// If `encryption_algo == None`, this should be all zeros.
     pub iv: [u8; 12],
 
-    _unused: [u8; 11], // Ensure 128-byte size
+    /// Number of bytes of `meta` in use.
+    pub meta_len: u8,
+
+    /// User-defined metadata, zero-padded. Use `meta()` and `set_meta()` rather than reading it
+    /// directly.
+    pub meta: [u8; META_LEN],
 
     /// 64-byte signature from the tail end of the on-chain data.
     pub tail_signature: [u8; 64],
@@ -193,7 +260,8 @@ impl TapeHeader {
             encryption_algo  : encryption_algo.into(),
 
             iv               : [0; 12], // empty IV/nonce
-            _unused          : [0; 11], // padding to ensure 128-byte size
+            meta_len         : 0,
+            meta             : [0; META_LEN], // no user metadata
             tail_signature   : [0; 64], // empty signature
         }
     }
@@ -229,6 +297,62 @@ impl TapeHeader {
 
         Ok(header)
     }
+
+    /// The MIME string of the tape data, falling back to `application/octet-stream` if unknown.
+    pub fn mime(&self) -> String {
+        match MimeType::try_from(self.mime_type) {
+            Ok(MimeType::Custom) => {
+                let end = self.mime_str.iter().position(|&b| b == 0).unwrap_or(MIME_STR_LEN);
+                String::from_utf8_lossy(&self.mime_str[..end]).into_owned()
+            }
+            Ok(mime_type) => mime_type.as_str().to_string(),
+            Err(_) => MimeType::Unknown.as_str().to_string(),
+        }
+    }
+
+    /// The user-defined metadata stored in the header, empty if none.
+    pub fn meta(&self) -> &[u8] {
+        let len = (self.meta_len as usize).min(META_LEN);
+        &self.meta[..len]
+    }
+
+    /// Store user-defined metadata in the header, at most `META_LEN` bytes.
+    pub fn set_meta(&mut self, meta: &[u8]) -> Result<()> {
+        if meta.len() > META_LEN {
+            bail!("Metadata is {} bytes, the header holds at most {}", meta.len(), META_LEN);
+        }
+
+        self.meta = [0; META_LEN];
+        self.meta[..meta.len()].copy_from_slice(meta);
+        self.meta_len = meta.len() as u8;
+        Ok(())
+    }
+
+    /// Mark the payload as sealed to `recipient`, recording its key id in `iv`. The ephemeral
+    /// key doesn't fit in the header, so it goes in front of the payload instead.
+    pub fn seal_for(&mut self, recipient: &[u8; 32]) {
+        self.encryption_algo = EncryptionAlgo::SealedBox.into();
+        self.iv = super::key_id(recipient);
+    }
+
+    /// Returns true if the payload is sealed to a recipient.
+    pub fn is_sealed(&self) -> bool {
+        self.encryption_algo == u8::from(EncryptionAlgo::SealedBox)
+    }
+
+    /// Returns true if the tape holds image data, either as a predefined or custom MIME type.
+    pub fn is_image(&self) -> bool {
+        match MimeType::try_from(self.mime_type) {
+            Ok(MimeType::ImagePng)  |
+            Ok(MimeType::ImageJpeg) |
+            Ok(MimeType::ImageGif)  |
+            Ok(MimeType::ImageWebp) |
+            Ok(MimeType::ImageBmp)  |
+            Ok(MimeType::ImageTiff) => true,
+            Ok(MimeType::Custom) => self.mime_str.starts_with(b"image/"),
+            _ => false,
+        }
+    }
 }
 
 impl std::fmt::Debug for TapeHeader {
@@ -241,6 +365,7 @@ impl std::fmt::Debug for TapeHeader {
             .field("compression", &self.compression)
             .field("encryption_algo", &self.encryption_algo)
             .field("iv", &self.iv)
+            .field("meta", &String::from_utf8_lossy(self.meta()))
             .field("tail_signature", &self.tail_signature)
             .finish()
     }
@@ -270,6 +395,44 @@ mod tests {
         assert_eq!(header.tail_signature, [0; 64]);
     }
 
+    #[test]
+    fn test_tape_header_mime() {
+        let header = TapeHeader::new(
+            MimeType::ImageJpeg,
+            CompressionAlgo::None,
+            EncryptionAlgo::None,
+            TapeFlags::None,
+        );
+        assert_eq!(header.mime(), "image/jpeg");
+
+        let mut header = header;
+        header.mime_type = MimeType::Custom.into();
+        header.mime_str[..10].copy_from_slice(b"model/gltf");
+        assert_eq!(header.mime(), "model/gltf");
+    }
+
+    #[test]
+    fn test_tape_header_meta() {
+        let mut header = TapeHeader::new(
+            MimeType::TextPlain,
+            CompressionAlgo::Gzip,
+            EncryptionAlgo::None,
+            TapeFlags::Linked,
+        );
+        assert!(header.meta().is_empty());
+
+        header.set_meta(b"app=x&v=2").unwrap();
+        let bytes = header.to_bytes();
+        let parsed = TapeHeader::try_from_bytes(&bytes).unwrap();
+        assert_eq!(parsed.meta(), b"app=x&v=2");
+
+        header.set_meta(b"v=3").unwrap();
+        assert_eq!(header.meta(), b"v=3");
+        assert_eq!(&header.meta[3..], &[0; META_LEN - 3]);
+
+        assert!(header.set_meta(&[1; META_LEN + 1]).is_err());
+    }
+
     #[test]
     fn test_tape_header_to_bytes() {
         let header = TapeHeader::new(
@@ -0,0 +1,314 @@
+use anyhow::{anyhow, bail, Result};
+use brine_tree::MerkleTree;
+use std::collections::{BTreeMap, HashMap};
+use solana_sdk::{message::VersionedMessage, pubkey::Pubkey, signature::Signature};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use tape_api::prelude::*;
+use crate::{utils::*, consts::*};
+
+/// What a single instruction did to a tape.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TapeAction {
+    Create,
+    /// `count` segments were appended, starting at segment `first`.
+    Write { first: u64, count: u64 },
+    Update { segment: u64 },
+    Finalize,
+}
+
+/// One instruction in a tape's history, with the Merkle root before and after it.
+#[derive(Clone, Debug)]
+pub struct TapeHistoryEntry {
+    pub slot: u64,
+    pub block_time: Option<i64>,
+    pub signature: Signature,
+    pub action: TapeAction,
+    pub old_root: [u8; 32],
+    pub new_root: [u8; 32],
+    /// New contents of the segments this instruction wrote.
+    pub segments: Vec<(u64, [u8; SEGMENT_SIZE])>,
+}
+
+/// The full write history of a tape, oldest first.
+#[derive(Clone, Debug)]
+pub struct TapeHistory {
+    pub entries: Vec<TapeHistoryEntry>,
+    /// The root on-chain right now.
+    pub merkle_root: [u8; 32],
+    /// Whether replaying the history reproduces the on-chain root.
+    pub verified: bool,
+}
+
+/// Rebuild every Create, Write, Update and Finalize applied to a tape by replaying the
+/// transactions that touched the tape account into a fresh Merkle tree, the same way the program
+/// does. Unlike reading, this covers updates too, which the linked chunks of a tape skip.
+pub async fn fetch_tape_history(client: &RpcClient, tape_address: &Pubkey) -> Result<TapeHistory> {
+    let (tape, _) = get_tape_account(client, tape_address).await?;
+
+    let mut signatures = Vec::new();
+    let mut before = None;
+
+    loop {
+        let page = get_signatures_for_address(client, tape_address, before, None, None).await?;
+        let Some(last) = page.last() else {
+            break;
+        };
+
+        before = Some(last.signature.parse::<Signature>()?);
+        signatures.extend(page.into_iter().filter(|s| s.err.is_none()));
+    }
+
+    let mut tree: MerkleTree<{ TREE_HEIGHT }> = MerkleTree::new(&[tape.merkle_seed.as_ref()]);
+    let mut root = [0u8; 32];
+    let mut total_segments = 0u64;
+    let mut entries = Vec::new();
+
+    for status in signatures.iter().rev() {
+        let signature: Signature = status.signature.parse()?;
+        let tx = get_transaction_with_retry(client, &signature, MAX_RETRIES).await?;
+
+        for (ix_type, data) in tape_instructions(&tx.message, tape_address) {
+            let old_root = root;
+            let mut segments = Vec::new();
+
+            let action = match ix_type {
+                InstructionType::Create => TapeAction::Create,
+                InstructionType::Write => {
+                    let first = total_segments;
+                    for (i, segment) in data.chunks(SEGMENT_SIZE).enumerate() {
+                        let segment = padded_array::<SEGMENT_SIZE>(segment);
+                        write_segment(&mut tree, first + i as u64, &segment)
+                            .map_err(|e| anyhow!("Failed to replay write {}: {}", signature, e))?;
+                        segments.push((first + i as u64, segment));
+                    }
+
+                    total_segments += data.len().div_ceil(SEGMENT_SIZE) as u64;
+                    root = tree.get_root().to_bytes();
+                    TapeAction::Write { first, count: total_segments - first }
+                }
+                InstructionType::Update => {
+                    let update = Update::try_from_bytes(data)
+                        .map_err(|e| anyhow!("Invalid update in {}: {}", signature, e))?;
+                    let segment = u64::from_le_bytes(update.segment_number);
+
+                    update_segment(&mut tree, segment, &update.old_data, &update.new_data, &update.proof)
+                        .map_err(|e| anyhow!("Failed to replay update {}: {}", signature, e))?;
+
+                    root = tree.get_root().to_bytes();
+                    segments.push((segment, update.new_data));
+                    TapeAction::Update { segment }
+                }
+                InstructionType::Finalize => TapeAction::Finalize,
+                _ => continue,
+            };
+
+            entries.push(TapeHistoryEntry {
+                slot: status.slot,
+                block_time: status.block_time,
+                signature,
+                action,
+                old_root,
+                new_root: root,
+                segments,
+            });
+        }
+    }
+
+    Ok(TapeHistory {
+        entries,
+        merkle_root: tape.merkle_root,
+        verified: root == tape.merkle_root,
+    })
+}
+
+/// A segment that differs between two points in a tape's history. `before` is `None` for
+/// segments written after the first point.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SegmentDiff {
+    pub segment: u64,
+    pub before: Option<[u8; SEGMENT_SIZE]>,
+    pub after: [u8; SEGMENT_SIZE],
+}
+
+impl TapeHistory {
+    /// The contents of every segment as of `slot`, after all instructions up to and including
+    /// that slot.
+    pub fn segments_at(&self, slot: u64) -> BTreeMap<u64, [u8; SEGMENT_SIZE]> {
+        let mut segments = BTreeMap::new();
+        for entry in self.entries.iter().take_while(|e| e.slot <= slot) {
+            segments.extend(entry.segments.iter().copied());
+        }
+        segments
+    }
+
+    /// The segments that changed between `slot_a` and `slot_b`, in segment order.
+    pub fn diff(&self, slot_a: u64, slot_b: u64) -> Vec<SegmentDiff> {
+        let before = self.segments_at(slot_a);
+        let after = self.segments_at(slot_b);
+
+        // Segments are only ever appended or replaced, so every segment at slot_a still exists
+        after
+            .into_iter()
+            .filter(|(segment, data)| before.get(segment) != Some(data))
+            .map(|(segment, data)| SegmentDiff {
+                segment,
+                before: before.get(&segment).copied(),
+                after: data,
+            })
+            .collect()
+    }
+
+    /// The signature of the last write, the tail to finalize a tape with that was written but
+    /// never finalized. On a linked tape it's `None` unless the writes link back from it all the
+    /// way to the first one, so a tape with a missing write isn't finalized as if it were whole.
+    pub fn tail(&self, linked: bool) -> Option<Signature> {
+        let writes: Vec<&TapeHistoryEntry> = self
+            .entries
+            .iter()
+            .filter(|e| matches!(e.action, TapeAction::Write { .. }))
+            .collect();
+
+        let tail = writes.last()?.signature;
+        if !linked {
+            return Some(tail);
+        }
+
+        // Each linked write starts with the signature of the one before it
+        let prev: HashMap<Signature, Signature> = writes
+            .iter()
+            .filter_map(|e| {
+                let (_, first) = e.segments.first()?;
+                Some((e.signature, Signature::try_from(&first[..SIGNATURE_SIZE]).ok()?))
+            })
+            .collect();
+
+        let mut at = tail;
+        for _ in 0..prev.len() {
+            match prev.get(&at) {
+                Some(p) if *p == Signature::default() => return Some(tail),
+                Some(p) => at = *p,
+                None => return None,
+            }
+        }
+
+        None
+    }
+}
+
+/// The segments of a tape that changed between two slots, with their contents before and after.
+pub async fn diff_tape(
+    client: &RpcClient,
+    tape_address: &Pubkey,
+    slot_a: u64,
+    slot_b: u64,
+) -> Result<Vec<SegmentDiff>> {
+    if slot_a > slot_b {
+        bail!("The first slot ({}) must not be after the second ({})", slot_a, slot_b);
+    }
+
+    let history = fetch_tape_history(client, tape_address).await?;
+    Ok(history.diff(slot_a, slot_b))
+}
+
+/// The tape instructions in a message that act on `tape_address`, with their data minus the
+/// discriminator.
+pub(crate) fn tape_instructions<'a>(
+    message: &'a VersionedMessage,
+    tape_address: &Pubkey,
+) -> Vec<(InstructionType, &'a [u8])> {
+    let keys = message.static_account_keys();
+
+    message
+        .instructions()
+        .iter()
+        .filter(|ix| *ix.program_id(keys) == program_context().program_id)
+        .filter(|ix| {
+            // The tape is always the second account. Writes through a lookup table load it from
+            // the table, any such transaction came from the tape's own history.
+            match ix.accounts.get(1).map(|&i| keys.get(i as usize)) {
+                Some(Some(key)) => key == tape_address,
+                Some(None) => true,
+                None => false,
+            }
+        })
+        .filter_map(|ix| {
+            let ix_type = InstructionType::try_from(*ix.data.first()?).ok()?;
+            Some((ix_type, &ix.data[1..]))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(slot: u64, segments: &[(u64, u8)]) -> TapeHistoryEntry {
+        TapeHistoryEntry {
+            slot,
+            block_time: None,
+            signature: Signature::default(),
+            action: TapeAction::Finalize,
+            old_root: [0; 32],
+            new_root: [0; 32],
+            segments: segments.iter().map(|&(n, b)| (n, [b; SEGMENT_SIZE])).collect(),
+        }
+    }
+
+    #[test]
+    fn test_diff() {
+        let history = TapeHistory {
+            entries: vec![
+                entry(10, &[(0, 1), (1, 1)]),
+                entry(20, &[(1, 2)]),
+                entry(30, &[(2, 3)]),
+            ],
+            merkle_root: [0; 32],
+            verified: true,
+        };
+
+        assert_eq!(history.segments_at(5).len(), 0);
+        assert_eq!(history.segments_at(25)[&1], [2; SEGMENT_SIZE]);
+
+        let diff = history.diff(10, 30);
+        assert_eq!(diff.len(), 2);
+        assert_eq!(diff[0].segment, 1);
+        assert_eq!(diff[0].before, Some([1; SEGMENT_SIZE]));
+        assert_eq!(diff[0].after, [2; SEGMENT_SIZE]);
+        assert_eq!(diff[1].segment, 2);
+        assert_eq!(diff[1].before, None);
+
+        assert!(history.diff(20, 25).is_empty());
+    }
+
+    fn write(signature: Signature, prev: Signature) -> TapeHistoryEntry {
+        let mut segment = [0u8; SEGMENT_SIZE];
+        segment[..SIGNATURE_SIZE].copy_from_slice(prev.as_ref());
+        TapeHistoryEntry {
+            signature,
+            action: TapeAction::Write { first: 0, count: 1 },
+            segments: vec![(0, segment)],
+            ..entry(0, &[])
+        }
+    }
+
+    #[test]
+    fn test_tail() {
+        let sigs: Vec<Signature> = (1..=3u8).map(|i| Signature::from([i; 64])).collect();
+        let history = |entries| TapeHistory { entries, merkle_root: [0; 32], verified: true };
+
+        let whole = history(vec![
+            entry(0, &[]),
+            write(sigs[0], Signature::default()),
+            write(sigs[1], sigs[0]),
+            write(sigs[2], sigs[1]),
+        ]);
+        assert_eq!(whole.tail(true), Some(sigs[2]));
+
+        // The second write never landed
+        let broken = history(vec![write(sigs[0], Signature::default()), write(sigs[2], sigs[1])]);
+        assert_eq!(broken.tail(true), None);
+        assert_eq!(broken.tail(false), Some(sigs[2]));
+
+        assert_eq!(history(vec![entry(0, &[])]).tail(false), None);
+    }
+}
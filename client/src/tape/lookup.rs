@@ -0,0 +1,77 @@
+use anyhow::{anyhow, Result};
+use solana_sdk::{
+    address_lookup_table::{
+        instruction::{create_lookup_table, extend_lookup_table},
+        state::AddressLookupTable,
+        AddressLookupTableAccount,
+    },
+    pubkey::Pubkey,
+    signature::Signer,
+    transaction::Transaction,
+};
+use tape_api::prelude::*;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use tokio::time::{sleep, Duration};
+use crate::utils::*;
+
+/// Creates an address lookup table holding the tape, writer and config accounts, so wide writes
+/// can reference them by a one byte index. Returns once the table is usable.
+pub async fn create_tape_lookup_table(
+    client: &RpcClient,
+    signer: &dyn Signer,
+    tape_address: Pubkey,
+    writer_address: Pubkey,
+) -> Result<AddressLookupTableAccount> {
+    let recent_slot = get_slot(client).await?;
+    let (create_ix, table_address) =
+        create_lookup_table(signer.pubkey(), signer.pubkey(), recent_slot);
+
+    let addresses = vec![tape_address, writer_address, program_context().config_address()];
+    let extend_ix = extend_lookup_table(
+        table_address,
+        signer.pubkey(),
+        Some(signer.pubkey()),
+        addresses.clone(),
+    );
+
+    let blockhash_bytes = get_latest_blockhash(client).await?;
+    let recent_blockhash = deserialize(&blockhash_bytes)?;
+    let tx = Transaction::new_signed_with_payer(
+        &[create_ix, extend_ix],
+        Some(&signer.pubkey()),
+        &[signer],
+        recent_blockhash,
+    );
+
+    send_and_confirm(client, &tx).await?;
+    let extended_slot = get_slot(client).await?;
+
+    // Addresses can't be loaded from a table in the slot they were added
+    while get_slot(client).await? <= extended_slot {
+        sleep(Duration::from_millis(400)).await;
+    }
+
+    Ok(AddressLookupTableAccount {
+        key: table_address,
+        addresses,
+    })
+}
+
+/// Fetches an address lookup table by address.
+pub async fn get_lookup_table(
+    client: &RpcClient,
+    table_address: &Pubkey,
+) -> Result<AddressLookupTableAccount> {
+    let account = client
+        .get_account(table_address)
+        .await
+        .map_err(|e| anyhow!("Lookup table {} not found: {}", table_address, e))?;
+
+    let table = AddressLookupTable::deserialize(&account.data)
+        .map_err(|e| anyhow!("Invalid lookup table {}: {}", table_address, e))?;
+
+    Ok(AddressLookupTableAccount {
+        key: *table_address,
+        addresses: table.addresses.to_vec(),
+    })
+}
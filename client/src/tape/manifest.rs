@@ -0,0 +1,187 @@
+use anyhow::{Result, bail};
+use solana_sdk::{hash::hash, pubkey::Pubkey};
+
+use super::{TapeHeader, MimeType, CompressionAlgo, EncryptionAlgo, TapeFlags, MIME_STR_LEN};
+
+/// A 4-byte "magic" prefix to identify a manifest.
+pub const MANIFEST_MAGIC: [u8; 4] = *b"TMAN";
+
+/// The version of the manifest format.
+pub const MANIFEST_VERSION: u8 = 1;
+
+/// The custom MIME string manifest tapes are written with.
+pub const MANIFEST_MIME: &str = "application/x-tape-manifest";
+
+/// Size of a single encoded `ManifestPart`.
+const PART_LEN: usize = 32 + 8 + 32;
+
+/// One tape in a manifest.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ManifestPart {
+    /// Address of the tape holding this part.
+    pub address: Pubkey,
+
+    /// Size of the part once decoded, in bytes.
+    pub size: u64,
+
+    /// SHA-256 of the decoded part.
+    pub checksum: [u8; 32],
+}
+
+impl ManifestPart {
+    pub fn new(address: Pubkey, data: &[u8]) -> Self {
+        Self {
+            address,
+            size: data.len() as u64,
+            checksum: hash(data).to_bytes(),
+        }
+    }
+
+    /// Check decoded part data against the size and checksum recorded in the manifest.
+    pub fn verify(&self, data: &[u8]) -> Result<()> {
+        if data.len() as u64 != self.size {
+            bail!(
+                "Part {} is {} bytes, manifest expects {}",
+                self.address, data.len(), self.size
+            );
+        }
+
+        if hash(data).to_bytes() != self.checksum {
+            bail!("Part {} doesn't match the manifest checksum", self.address);
+        }
+
+        Ok(())
+    }
+}
+
+/// Links several tapes holding the parts of one payload, in order.
+///
+/// Layout:
+/// - `magic` (4 bytes)       -> always `b"TMAN"`
+/// - `version` (1 byte)      -> format version (`1`)
+/// - `count` (4 bytes, LE)   -> number of parts
+/// - `parts` (72 bytes each) -> in order, each:
+///   - `address` (32 bytes)  -> tape holding the part
+///   - `size` (8 bytes, LE)  -> decoded size of the part
+///   - `checksum` (32 bytes) -> SHA-256 of the decoded part
+#[derive(Clone, Debug, PartialEq)]
+pub struct TapeManifest {
+    pub parts: Vec<ManifestPart>,
+}
+
+impl TapeManifest {
+    pub fn new(parts: Vec<ManifestPart>) -> Self {
+        Self { parts }
+    }
+
+    /// Size of an encoded manifest with `count` parts, in bytes.
+    pub fn encoded_len(count: usize) -> usize {
+        9 + count * PART_LEN
+    }
+
+    /// Total size of the reassembled payload, in bytes.
+    pub fn total_size(&self) -> u64 {
+        self.parts.iter().map(|p| p.size).sum()
+    }
+
+    /// The header manifest tapes are written with.
+    pub fn header() -> TapeHeader {
+        let mut header = TapeHeader::new(
+            MimeType::Unknown,
+            CompressionAlgo::None,
+            EncryptionAlgo::None,
+            TapeFlags::Linked,
+        );
+
+        let mut mime_str = [0; MIME_STR_LEN];
+        mime_str[..MANIFEST_MIME.len()].copy_from_slice(MANIFEST_MIME.as_bytes());
+
+        header.mime_type = MimeType::Custom.into();
+        header.mime_str = mime_str;
+        header
+    }
+
+    /// Returns true if the header belongs to a manifest tape.
+    pub fn is_manifest(header: &TapeHeader) -> bool {
+        header.mime() == MANIFEST_MIME
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut data = Vec::with_capacity(Self::encoded_len(self.parts.len()));
+        data.extend_from_slice(&MANIFEST_MAGIC);
+        data.push(MANIFEST_VERSION);
+        data.extend_from_slice(&(self.parts.len() as u32).to_le_bytes());
+        for part in &self.parts {
+            data.extend_from_slice(&part.address.to_bytes());
+            data.extend_from_slice(&part.size.to_le_bytes());
+            data.extend_from_slice(&part.checksum);
+        }
+        data
+    }
+
+    pub fn try_from_bytes(data: &[u8]) -> Result<Self> {
+        if data.len() < 9 {
+            bail!("Data too short for TapeManifest ({} < 9)", data.len());
+        }
+
+        if data[0..4] != MANIFEST_MAGIC {
+            bail!("Invalid magic number in TapeManifest");
+        }
+
+        if data[4] != MANIFEST_VERSION {
+            bail!(
+                "Unsupported TapeManifest version: found {}, expected {}",
+                data[4], MANIFEST_VERSION
+            );
+        }
+
+        let count = u32::from_le_bytes(data[5..9].try_into()?) as usize;
+        let parts = &data[9..];
+        if parts.len() != count * PART_LEN {
+            bail!("TapeManifest lists {} parts but holds {} bytes", count, parts.len());
+        }
+
+        let parts = parts
+            .chunks(PART_LEN)
+            .map(|p| {
+                Ok(ManifestPart {
+                    address: Pubkey::try_from(&p[..32])
+                        .map_err(|e| anyhow::anyhow!("Invalid part address: {}", e))?,
+                    size: u64::from_le_bytes(p[32..40].try_into()?),
+                    checksum: p[40..72].try_into()?,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self { parts })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_manifest_roundtrip() {
+        let manifest = TapeManifest::new(vec![
+            ManifestPart::new(Pubkey::new_unique(), &[1, 2, 3]),
+            ManifestPart::new(Pubkey::new_unique(), &[4, 5]),
+        ]);
+        let bytes = manifest.to_bytes();
+
+        assert_eq!(TapeManifest::try_from_bytes(&bytes).unwrap(), manifest);
+        assert_eq!(manifest.total_size(), 5);
+        assert_eq!(bytes.len(), TapeManifest::encoded_len(2));
+        assert!(TapeManifest::try_from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        assert!(TapeManifest::is_manifest(&TapeManifest::header()));
+    }
+
+    #[test]
+    fn test_manifest_part_verify() {
+        let part = ManifestPart::new(Pubkey::new_unique(), &[1, 2, 3]);
+
+        assert!(part.verify(&[1, 2, 3]).is_ok());
+        assert!(part.verify(&[1, 2]).is_err());
+        assert!(part.verify(&[1, 2, 4]).is_err());
+    }
+}
@@ -1,14 +1,66 @@
+#[cfg(feature = "encoding")]
 mod header;
+#[cfg(feature = "encoding")]
 mod encoding;
+#[cfg(feature = "rpc")]
 mod create;
+#[cfg(feature = "rpc")]
 mod read;
+#[cfg(feature = "rpc")]
+mod source;
+#[cfg(feature = "rpc")]
 mod write;
+#[cfg(feature = "rpc")]
 mod finalize;
+#[cfg(feature = "encoding")]
+mod manifest;
+#[cfg(feature = "rpc")]
+mod lookup;
+#[cfg(feature = "rpc")]
+mod history;
+#[cfg(feature = "rpc")]
+mod watch;
+#[cfg(feature = "rpc")]
+mod cache;
+#[cfg(feature = "rpc")]
+mod bundle;
+#[cfg(feature = "encoding")]
+mod seal;
+#[cfg(feature = "encoding")]
+mod dedup;
+#[cfg(feature = "encoding")]
+mod dirpack;
 
+#[cfg(feature = "encoding")]
 pub use header::*;
+#[cfg(feature = "encoding")]
 pub use encoding::*;
+#[cfg(feature = "rpc")]
 pub use create::*;
+#[cfg(feature = "rpc")]
 pub use read::*;
+#[cfg(feature = "rpc")]
+pub use source::*;
+#[cfg(feature = "rpc")]
 pub use write::*;
+#[cfg(feature = "rpc")]
 pub use finalize::*;
+#[cfg(feature = "encoding")]
+pub use manifest::*;
+#[cfg(feature = "rpc")]
+pub use lookup::*;
+#[cfg(feature = "rpc")]
+pub use history::*;
+#[cfg(feature = "rpc")]
+pub use watch::*;
+#[cfg(feature = "rpc")]
+pub use cache::*;
+#[cfg(feature = "rpc")]
+pub use bundle::*;
+#[cfg(feature = "encoding")]
+pub use seal::*;
+#[cfg(feature = "encoding")]
+pub use dedup::*;
+#[cfg(feature = "encoding")]
+pub use dirpack::*;
 
@@ -1,37 +1,527 @@
-use anyhow::{Result, anyhow};
-use solana_sdk::signature::Signature;
+use anyhow::{Result, anyhow, bail};
+use async_trait::async_trait;
+use solana_sdk::{message::VersionedMessage, pubkey::Pubkey, signature::Signature};
 use tape_api::prelude::*;
 use solana_client::nonblocking::rpc_client::RpcClient;
 use crate::{utils::*, consts::*};
+use futures::{stream, StreamExt};
+use std::collections::HashMap;
+use std::ops::Range;
+use tokio::sync::mpsc;
+use super::{
+    chunk_size, decode_sealed_tape, decompress_frames, unprefix_segments, write_size,
+    CompressionAlgo, FrameIndex, SealKeypair, StreamDecoder, TapeFlags, TapeHeader, TapeManifest,
+    TapeRecipe,
+};
+
+/// Writes of an unlinked tape fetched ahead of the one being decoded.
+const READ_AHEAD: usize = 16;
+
+/// Reads queued for the decoder, beyond which reading waits for it to catch up.
+const DECODE_QUEUE: usize = 64;
+
+/// A tape read back and decoded into its original bytes.
+#[derive(Clone, Debug)]
+pub struct DecodedTape {
+    pub bytes: Vec<u8>,
+    pub header: TapeHeader,
+    pub mime: String,
+}
+
+/// A source of archived tape segments other than the chain, such as an archiver's store or a
+/// trusted peer. See `SourceChain` to try several in turn.
+#[async_trait]
+pub trait SegmentSource: Send + Sync {
+    async fn read_segments(&self, tape_address: &Pubkey) -> Result<Vec<(u64, Vec<u8>)>>;
+
+    /// The segments it holds numbered within `range`, sorted. Sources that can look segments up
+    /// one at a time should, the default reads them all.
+    async fn read_segment_range(&self, tape_address: &Pubkey, range: Range<u64>) -> Result<Vec<(u64, Vec<u8>)>> {
+        let mut segments = self.read_segments(tape_address).await?;
+        segments.retain(|(number, _)| range.contains(number));
+        segments.sort_by_key(|(number, _)| *number);
+        Ok(segments)
+    }
+
+    /// Every segment of a tape with `total_segments`, failing if the source holds fewer.
+    async fn read_tape(&self, tape_address: &Pubkey, total_segments: u64) -> Result<Vec<(u64, Vec<u8>)>> {
+        let segments = self.read_segments(tape_address).await?;
+        if (segments.len() as u64) < total_segments {
+            bail!(
+                "{} only holds {} of {} segments for tape {}",
+                self.name(), segments.len(), total_segments, tape_address
+            );
+        }
+        Ok(segments)
+    }
+
+    /// What the source is called in errors.
+    fn name(&self) -> String {
+        "Segment source".to_string()
+    }
+
+    /// Whether reads it can't serve should walk the chain over RPC instead of failing.
+    fn rpc_fallback(&self) -> bool {
+        false
+    }
+}
+
+/// Fetch a tape over RPC, following the linked chunks from its tail, and decode it.
+pub async fn fetch_tape(client: &RpcClient, tape_address: &Pubkey) -> Result<DecodedTape> {
+    fetch_tape_with(client, tape_address, None, |_, _| {}).await
+}
+
+/// Fetch and decode a tape, reading segments from `source` if given instead of walking the
+/// chain. `on_progress` is called with the number of segments read so far and the total. If the
+/// tape is a manifest, its parts are fetched, verified and reassembled into the full payload. If
+/// it's a recipe, the data is rebuilt from the chunks it lists.
+pub async fn fetch_tape_with(
+    client: &RpcClient,
+    tape_address: &Pubkey,
+    source: Option<&dyn SegmentSource>,
+    on_progress: impl FnMut(u64, u64),
+) -> Result<DecodedTape> {
+    fetch_tape_opened_with(client, tape_address, source, None, on_progress).await
+}
+
+/// Like `fetch_tape_with`, opening tapes sealed to `keypair` on the way. Sealed tapes fail to
+/// read without it.
+pub async fn fetch_tape_opened_with(
+    client: &RpcClient,
+    tape_address: &Pubkey,
+    source: Option<&dyn SegmentSource>,
+    keypair: Option<&SealKeypair>,
+    mut on_progress: impl FnMut(u64, u64),
+) -> Result<DecodedTape> {
+    let tape = fetch_payload(client, tape_address, source, keypair, &mut on_progress).await?;
+    if !TapeRecipe::is_recipe(&tape.header) {
+        return Ok(tape);
+    }
+
+    // Each tape the chunks are on is read once, however many it holds
+    let recipe = TapeRecipe::try_from_bytes(&tape.bytes)?;
+    let mut tapes = HashMap::new();
+    for address in recipe.tapes() {
+        let decoded = fetch_payload(client, &address, source, keypair, &mut on_progress).await?;
+        tapes.insert(address, decoded.bytes);
+    }
+
+    Ok(DecodedTape {
+        bytes: recipe.assemble(&tapes)?,
+        mime: recipe.mime,
+        header: tape.header,
+    })
+}
+
+/// Fetch and decode a tape, reassembling it if it's a manifest.
+async fn fetch_payload(
+    client: &RpcClient,
+    tape_address: &Pubkey,
+    source: Option<&dyn SegmentSource>,
+    keypair: Option<&SealKeypair>,
+    on_progress: &mut impl FnMut(u64, u64),
+) -> Result<DecodedTape> {
+    let tape = fetch_single_tape(client, tape_address, source, keypair, on_progress).await?;
+    if !TapeManifest::is_manifest(&tape.header) {
+        return Ok(tape);
+    }
+
+    let manifest = TapeManifest::try_from_bytes(&tape.bytes)?;
+    let mut bytes = Vec::with_capacity(manifest.total_size() as usize);
+    let mut first = None;
+
+    for part in &manifest.parts {
+        let decoded = fetch_single_tape(client, &part.address, source, keypair, on_progress).await?;
+        part.verify(&decoded.bytes)?;
+
+        bytes.extend_from_slice(&decoded.bytes);
+        first.get_or_insert(decoded.header);
+    }
+
+    // Parts carry the header of the original payload
+    let header = first.ok_or_else(|| anyhow!("Manifest {} has no parts", tape_address))?;
+
+    Ok(DecodedTape {
+        bytes,
+        mime: header.mime(),
+        header,
+    })
+}
+
+/// Fetch and decode a single tape, without following manifests.
+///
+/// Reading and decoding run as a pipeline: the encoded data goes to a decoder on a blocking
+/// thread as it's read, over a bounded queue, so the tape is decompressed while the rest of it is
+/// still being fetched.
+async fn fetch_single_tape(
+    client: &RpcClient,
+    tape_address: &Pubkey,
+    source: Option<&dyn SegmentSource>,
+    keypair: Option<&SealKeypair>,
+    on_progress: &mut impl FnMut(u64, u64),
+) -> Result<DecodedTape> {
+    let (tape, _) = get_tape_account(client, tape_address).await?;
+    let header = *TapeHeader::try_from_bytes(&tape.header)?;
+
+    // Check before reading anything
+    if header.is_sealed() && keypair.is_none() {
+        bail!("Tape {} is sealed to a recipient, it can only be read with their key", tape_address);
+    }
+
+    // Sealed tapes are opened whole, the decoder only collects them
+    let compression = match header.is_sealed() {
+        true => CompressionAlgo::None,
+        false => CompressionAlgo::try_from(header.compression)
+            .map_err(|_| anyhow!("Invalid compression algorithm"))?,
+    };
+
+    let (chunks, mut queue) = mpsc::channel::<Vec<u8>>(DECODE_QUEUE);
+    let decoder = tokio::task::spawn_blocking(move || {
+        let mut decoder = StreamDecoder::new(compression);
+        while let Some(chunk) = queue.blocking_recv() {
+            decoder.push(&chunk)?;
+        }
+        decoder.finish()
+    });
+
+    let read = read_encoded(client, tape_address, &tape, &header, source, on_progress, chunks).await;
+    let decoded = decoder
+        .await
+        .map_err(|e| anyhow!("Decoding tape {} failed: {}", tape_address, e))?;
+
+    // A read that failed leaves the decoder short, its error is the one that matters
+    read?;
+    let decoded = decoded?;
+
+    let bytes = match keypair {
+        Some(keypair) if header.is_sealed() => decode_sealed_tape(decoded, &header, keypair)?,
+        _ => decoded,
+    };
+
+    Ok(DecodedTape {
+        bytes,
+        mime: header.mime(),
+        header,
+    })
+}
+
+/// The writes holding a byte range of a tape's encoded data.
+#[derive(Debug, PartialEq)]
+struct RangePlan {
+    writes: Range<u64>,
+    segments: Range<u64>,
+
+    /// Bytes to skip from the start of the first write.
+    skip: usize,
+    len: usize,
+}
+
+/// Which writes hold bytes `offset..offset + len` of a tape's encoded data, or `None` if the range
+/// starts past its end. Every write but the last holds a full chunk.
+fn plan_range(header: &TapeHeader, total_segments: u64, offset: u64, len: u64) -> Option<RangePlan> {
+    let chunk_size = chunk_size(header) as u64;
+    let segments_per_write = write_size(header).div_ceil(SEGMENT_SIZE) as u64;
+    let total_writes = total_segments.div_ceil(segments_per_write);
+
+    let first = offset / chunk_size;
+    if len == 0 || first >= total_writes {
+        return None;
+    }
+
+    let last = (offset.saturating_add(len) - 1) / chunk_size;
+    let writes = first..(last + 1).min(total_writes);
+
+    Some(RangePlan {
+        segments: writes.start * segments_per_write..(writes.end * segments_per_write).min(total_segments),
+        writes,
+        skip: (offset - first * chunk_size) as usize,
+        len: len.min(usize::MAX as u64) as usize,
+    })
+}
+
+/// The encoded bytes of the planned writes from their segments, cut down to the range.
+fn slice_range(segments: Vec<(u64, Vec<u8>)>, header: &TapeHeader, plan: &RangePlan) -> Result<Vec<u8>> {
+    let mut data = Vec::new();
+    let mut segments = segments.into_iter();
+    for expected in plan.segments.clone() {
+        match segments.next() {
+            Some((segment_number, segment)) if segment_number == expected => data.extend_from_slice(&segment),
+            _ => bail!("Missing segment {}", expected),
+        }
+    }
+
+    if header.flags & u8::from(TapeFlags::Linked) != 0 {
+        data = data
+            .chunks(write_size(header))
+            .flat_map(|write| write.get(SIGNATURE_SIZE..).unwrap_or_default().to_vec())
+            .collect();
+    }
+
+    Ok(cut(data, plan))
+}
+
+fn cut(data: Vec<u8>, plan: &RangePlan) -> Vec<u8> {
+    let start = plan.skip.min(data.len());
+    let end = start.saturating_add(plan.len).min(data.len());
+    data[start..end].to_vec()
+}
+
+/// Read `len` bytes of a tape's data starting at `offset`, fetching only the writes that hold
+/// them, so apps can seek within large tapes. Returns fewer bytes if the range runs past the end
+/// of the tape.
+///
+/// Works on unsealed tapes that are uncompressed, where the data on the tape is the data written,
+/// or compressed with `GzipChunked`, where only the frames holding the range are read after the
+/// frame index. Segments come from `source` if given. Without one, linked tapes are walked back
+/// from their tail only as far as the range, and unlinked tapes can't be read by range at all.
+pub async fn read_range(
+    client: &RpcClient,
+    tape_address: &Pubkey,
+    source: Option<&dyn SegmentSource>,
+    offset: u64,
+    len: u64,
+) -> Result<Vec<u8>> {
+    let (tape, _) = get_tape_account(client, tape_address).await?;
+    let header = *TapeHeader::try_from_bytes(&tape.header)?;
+
+    if header.is_sealed() {
+        bail!("Tape {} is sealed, only whole tapes can be read", tape_address);
+    }
+    if TapeManifest::is_manifest(&header) || TapeRecipe::is_recipe(&header) {
+        bail!("Tape {} is a manifest or recipe, read the tapes it lists instead", tape_address);
+    }
+
+    let read = |offset, len| read_encoded_range(client, tape_address, source, &header, tape.total_segments, offset, len);
+
+    match CompressionAlgo::try_from(header.compression) {
+        Ok(CompressionAlgo::None) => read(offset, len).await,
+        Ok(CompressionAlgo::GzipChunked) => {
+            // The index usually fits in the first write
+            let mut index = read(0, chunk_size(&header) as u64).await?;
+            let index_len = FrameIndex::encoded_len(&index)?;
+            if index.len() < index_len {
+                index = read(0, index_len as u64).await?;
+            }
+            let index = FrameIndex::parse(&index)?;
+
+            let Some(frames) = index.frames_for(offset, len) else {
+                return Ok(Vec::new());
+            };
+            let start = index.frame_range(frames.start).start;
+            let end = index.frame_range(frames.end - 1).end;
+            let data = decompress_frames(&read(start, end - start).await?, &index, frames.clone())?;
+
+            let skip = ((offset - frames.start as u64 * index.frame_size) as usize).min(data.len());
+            let end = skip.saturating_add(len.min(usize::MAX as u64) as usize).min(data.len());
+            Ok(data[skip..end].to_vec())
+        }
+        _ => bail!("Tape {} is compressed as a whole, only whole tapes can be read", tape_address),
+    }
+}
+
+/// Read `len` bytes of a tape's encoded data starting at `offset`, see `read_range`.
+async fn read_encoded_range(
+    client: &RpcClient,
+    tape_address: &Pubkey,
+    source: Option<&dyn SegmentSource>,
+    header: &TapeHeader,
+    total_segments: u64,
+    offset: u64,
+    len: u64,
+) -> Result<Vec<u8>> {
+    let Some(plan) = plan_range(header, total_segments, offset, len) else {
+        return Ok(Vec::new());
+    };
+
+    if let Some(source) = source {
+        match source.read_segment_range(tape_address, plan.segments.clone()).await {
+            Ok(segments) => return slice_range(segments, header, &plan),
+            Err(_) if source.rpc_fallback() => {}
+            Err(e) => return Err(e),
+        }
+    }
+
+    if header.flags & u8::from(TapeFlags::Linked) == 0 {
+        bail!("Unlinked tapes can only be read by range from a segment source");
+    }
+
+    let total_writes = total_segments.div_ceil(write_size(header).div_ceil(SEGMENT_SIZE) as u64);
+    let mut current_signature = Signature::from(header.tail_signature);
+    let mut chunks = Vec::new();
+
+    // Walk back from the last write to the first one in the range
+    for write in (plan.writes.start..total_writes).rev() {
+        if current_signature == Signature::default() {
+            bail!("Tape {} ends early, at write {}", tape_address, write + 1);
+        }
+
+        let (data, prev_signature) = read_linked_chunk(client, &current_signature).await?;
+        if plan.writes.contains(&write) {
+            chunks.push(data);
+        }
+        current_signature = prev_signature;
+    }
+
+    chunks.reverse();
+    Ok(cut(chunks.concat(), &plan))
+}
+
+/// Read a tape's encoded data, in order, into `chunks`: from `source` if it holds the tape,
+/// otherwise from the chain. Stops early without an error if the decoder has gone, having failed
+/// on what it was sent.
+async fn read_encoded(
+    client: &RpcClient,
+    tape_address: &Pubkey,
+    tape: &Tape,
+    header: &TapeHeader,
+    source: Option<&dyn SegmentSource>,
+    on_progress: &mut impl FnMut(u64, u64),
+    chunks: mpsc::Sender<Vec<u8>>,
+) -> Result<()> {
+    let segments = match source {
+        Some(source) => match source.read_tape(tape_address, tape.total_segments).await {
+            Ok(segments) => Some(segments),
+            Err(_) if source.rpc_fallback() => None,
+            Err(e) => return Err(e),
+        },
+        None => None,
+    };
+
+    match segments {
+        Some(segments) => {
+            on_progress(tape.total_segments, tape.total_segments);
+            let _ = chunks.send(unprefix_segments(segments, header)?).await;
+        }
+        None if header.flags & u8::from(TapeFlags::Linked) != 0 => {
+            // Walked from the tail, so nothing can be decoded before the first write is read
+            let encoded = read_linked_chunks(client, header, tape.total_segments, on_progress).await?;
+            let _ = chunks.send(encoded).await;
+        }
+        None => {
+            read_unlinked_chunks(client, tape_address, tape.total_segments, on_progress, &chunks).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Read an unlinked tape by collecting every transaction that touched the tape account, then
+/// sending their data into `chunks` oldest first, fetching `READ_AHEAD` writes ahead of the one
+/// sent. Slow for large tapes, but the only option without a tail signature.
+async fn read_unlinked_chunks(
+    client: &RpcClient,
+    tape_address: &Pubkey,
+    total_segments: u64,
+    on_progress: &mut impl FnMut(u64, u64),
+    chunks: &mpsc::Sender<Vec<u8>>,
+) -> Result<()> {
+    let mut signatures = Vec::new();
+    let mut before = None;
+
+    loop {
+        let page = get_signatures_for_address(client, tape_address, before, None, None).await?;
+        let Some(last) = page.last() else {
+            break;
+        };
+
+        before = Some(last.signature.parse::<Signature>()?);
+        signatures.extend(
+            page.into_iter()
+                .filter(|s| s.err.is_none())
+                .map(|s| s.signature),
+        );
+    }
+
+    let mut writes = stream::iter(signatures.iter().rev())
+        .map(|signature| async move { read_write_data(client, &signature.parse()?).await })
+        .buffered(READ_AHEAD);
+    let mut segments_read = 0;
+
+    while let Some(chunk) = writes.next().await {
+        // Create or finalize on their own hold no data
+        let Some(chunk) = chunk? else {
+            continue;
+        };
+
+        segments_read += chunk.len().div_ceil(SEGMENT_SIZE) as u64;
+        on_progress(segments_read.min(total_segments), total_segments);
+
+        if chunks.send(chunk).await.is_err() {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+/// Walk a linked tape from its tail signature back to the start, returning the encoded data.
+async fn read_linked_chunks(
+    client: &RpcClient,
+    header: &TapeHeader,
+    total_segments: u64,
+    on_progress: &mut impl FnMut(u64, u64),
+) -> Result<Vec<u8>> {
+    let empty_signature = Signature::default();
+    let mut current_signature = Signature::from(header.tail_signature);
+    let mut chunks = Vec::new();
+    let mut segments_read = 0;
+
+    while current_signature != empty_signature {
+        let (data, prev_signature) = read_linked_chunk(client, &current_signature).await?;
+
+        segments_read += (data.len() + SIGNATURE_SIZE).div_ceil(SEGMENT_SIZE) as u64;
+        on_progress(segments_read.min(total_segments), total_segments);
+
+        chunks.push(data);
+        current_signature = prev_signature;
+    }
+
+    chunks.reverse();
+    Ok(chunks.into_iter().flatten().collect())
+}
 
 pub async fn read_from_tape(
     client: &RpcClient,
     signature: &Signature,
 ) -> Result<Vec<u8>> {
-    let tx = get_transaction_with_retry(client, signature, MAX_RETRIES).await?;
+    read_write_data(client, signature)
+        .await?
+        .ok_or_else(|| anyhow!("No tape instruction found in chunk: {}", signature))
+}
 
-    let instruction = tx
-        .message
-        .instructions()
-        .into_iter()
-        .find(|ix| *ix.program_id(&tx.message.static_account_keys()) == tape_api::ID)
-        .ok_or_else(|| anyhow!("No tape instruction found in chunk: {}", signature))?;
+/// Returns the data written by a transaction, or `None` if it holds no tape write.
+async fn read_write_data(
+    client: &RpcClient,
+    signature: &Signature,
+) -> Result<Option<Vec<u8>>> {
+    let tx = get_transaction_with_retry(client, signature, MAX_RETRIES).await?;
+    write_data(&tx.message, signature)
+}
 
-    let ix_type = InstructionType::try_from(instruction.data[0])
-        .map_err(|_| anyhow!("Invalid instruction type"))?;
+/// The data a transaction's message writes to a tape, or `None` if it holds no tape write. An
+/// update replaces a segment written earlier, which reading writes in order can't apply, so it's
+/// an error rather than data.
+fn write_data(message: &VersionedMessage, signature: &Signature) -> Result<Option<Vec<u8>>> {
+    let keys = message.static_account_keys();
 
-    match ix_type {
-        InstructionType::Update => {
-            // Updates are a bit more complex and not implemented yet. If you need this, use
-            // TAPENET to fetch the entire tape in one go.
+    // Skip create/finalize instructions packed into the same transaction
+    let instructions = message
+        .instructions()
+        .iter()
+        .filter(|ix| *ix.program_id(keys) == program_context().program_id);
 
-            todo!("Update instruction not implemented yet");
+    for ix in instructions {
+        match ix.data.first().map(|&b| InstructionType::try_from(b)) {
+            Some(Ok(InstructionType::Write)) => return Ok(Some(ix.data[1..].to_vec())),
+            Some(Ok(InstructionType::Update)) => bail!(
+                "Transaction {} updates the tape, which reading from the chain doesn't support, read it from TAPENET or a local store",
+                signature
+            ),
+            _ => {}
         }
-        InstructionType::Write => {
-            Ok(instruction.data[1..].to_vec())
-        }
-        _ => Err(anyhow!("Unexpected instruction type: {:?}", ix_type)),
     }
+
+    Ok(None)
 }
 
 pub async fn read_linked_chunk(
@@ -54,3 +544,76 @@ pub async fn read_linked_chunk(
     Ok((chunk, prev_sig))
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{EncryptionAlgo, MimeType};
+
+    /// The segments a tape of `data` ends up with, each write split on its own.
+    fn write_segments(data: &[u8], header: &TapeHeader) -> Vec<(u64, Vec<u8>)> {
+        let linked = header.flags & u8::from(TapeFlags::Linked) != 0;
+        data.chunks(chunk_size(header))
+            .flat_map(|chunk| {
+                let mut write = if linked { vec![7u8; SIGNATURE_SIZE] } else { Vec::new() };
+                write.extend_from_slice(chunk);
+                write.chunks(SEGMENT_SIZE).map(<[u8]>::to_vec).collect::<Vec<_>>()
+            })
+            .enumerate()
+            .map(|(i, segment)| (i as u64, segment))
+            .collect()
+    }
+
+    #[test]
+    fn test_read_range() {
+        let original: Vec<u8> = (0..5000).map(|i| (i % 251) as u8).collect();
+
+        for flags in [TapeFlags::None, TapeFlags::Linked] {
+            let header = TapeHeader::new(MimeType::Unknown, CompressionAlgo::None, EncryptionAlgo::None, flags);
+            let segments = write_segments(&original, &header);
+            let total_segments = segments.len() as u64;
+
+            for (offset, len) in [(0, 10), (890, 20), (1000, 2000), (4990, 100), (0, 5000)] {
+                let plan = plan_range(&header, total_segments, offset, len).unwrap();
+                let needed: Vec<_> = segments.iter().filter(|(n, _)| plan.segments.contains(n)).cloned().collect();
+
+                let end = (offset + len).min(original.len() as u64) as usize;
+                assert_eq!(slice_range(needed, &header, &plan).unwrap(), original[offset as usize..end]);
+            }
+
+            // Only the writes holding the range are read
+            let plan = plan_range(&header, total_segments, SAFE_SIZE as u64 + 1, 10).unwrap();
+            assert_eq!(plan.writes, 1..2);
+
+            assert!(plan_range(&header, total_segments, 6000, 10).is_none());
+            assert!(plan_range(&header, total_segments, 0, 0).is_none());
+
+            let plan = plan_range(&header, total_segments, 1000, 10).unwrap();
+            assert!(slice_range(Vec::new(), &header, &plan).is_err());
+        }
+    }
+
+    #[test]
+    fn test_write_data() {
+        use solana_sdk::message::Message;
+
+        let program = program_context();
+        let (signer, tape, writer) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let message = |ixs: &[_]| VersionedMessage::Legacy(Message::new(ixs, Some(&signer)));
+        let signature = Signature::default();
+
+        // Create and finalize in the same transaction are skipped
+        let write = program.build_write_ix(signer, tape, writer, b"hello");
+        let finalize = program.build_finalize_ix(signer, tape, writer, None);
+        let data = write_data(&message(&[write, finalize.clone()]), &signature).unwrap();
+        assert_eq!(data.as_deref(), Some(&b"hello"[..]));
+
+        assert_eq!(write_data(&message(&[finalize]), &signature).unwrap(), None);
+
+        // Valid on-chain data, but not something a read in write order can apply
+        let update = program.build_update_ix(
+            signer, tape, writer, 0, [0; SEGMENT_SIZE], [1; SEGMENT_SIZE], [[0; 32]; PROOF_LEN],
+        );
+        assert!(write_data(&message(&[update]), &signature).is_err());
+    }
+}
@@ -0,0 +1,245 @@
+use aes_gcm_siv::{aead::{Aead, Payload}, Aes256GcmSiv, KeyInit, Nonce};
+use anyhow::{Result, anyhow, bail};
+use curve25519_dalek::montgomery::MontgomeryPoint;
+use hmac::Hmac;
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+
+/// Domain separator for the key a payload is sealed with.
+const SEAL_DOMAIN: &[u8] = b"tapedrive-seal-v1";
+
+/// Domain separator for seal keys derived from a wallet keypair.
+const SEAL_KEY_DOMAIN: &[u8] = b"tapedrive-seal-key-v1";
+
+/// PBKDF2 rounds a passphrase is stretched with when locking a key.
+pub const LOCK_ROUNDS: u32 = 600_000;
+
+/// Bytes sealing adds to a payload: the ephemeral public key in front and the tag behind.
+pub const SEAL_OVERHEAD: usize = 32 + 16;
+
+/// An X25519 keypair tapes can be sealed to. Share `public`, anyone can seal a tape to it, but
+/// only `secret` opens it again.
+#[derive(Clone)]
+pub struct SealKeypair {
+    pub secret: [u8; 32],
+    pub public: [u8; 32],
+}
+
+impl SealKeypair {
+    pub fn generate() -> Self {
+        let mut secret = [0u8; 32];
+        rand::thread_rng().fill_bytes(&mut secret);
+        Self::from_secret(secret)
+    }
+
+    pub fn from_secret(secret: [u8; 32]) -> Self {
+        let public = MontgomeryPoint::mul_base_clamped(secret).to_bytes();
+        Self { secret, public }
+    }
+
+    /// The seal keypair of a wallet, from its Ed25519 seed (the first half of a Solana keypair
+    /// file). Hashed rather than reused, so the signing key never doubles as an X25519 scalar.
+    pub fn from_seed(seed: &[u8]) -> Self {
+        let secret = Sha256::new()
+            .chain_update(SEAL_KEY_DOMAIN)
+            .chain_update(seed)
+            .finalize()
+            .into();
+        Self::from_secret(secret)
+    }
+
+    /// The id sealed tapes record for this keypair, see [`key_id`].
+    pub fn id(&self) -> [u8; 12] {
+        key_id(&self.public)
+    }
+
+    /// Encrypt the secret key with a passphrase, for keeping it on disk.
+    pub fn lock(&self, passphrase: &str) -> Result<LockedKey> {
+        self.lock_with_rounds(passphrase, LOCK_ROUNDS)
+    }
+
+    fn lock_with_rounds(&self, passphrase: &str, rounds: u32) -> Result<LockedKey> {
+        let mut salt = [0u8; 16];
+        rand::thread_rng().fill_bytes(&mut salt);
+
+        let ciphertext = lock_cipher(passphrase, &salt, rounds)
+            .encrypt(&Nonce::default(), Payload { msg: &self.secret, aad: &self.public })
+            .map_err(|_| anyhow!("Failed to lock key"))?;
+
+        Ok(LockedKey { rounds, salt, ciphertext })
+    }
+
+    /// Decrypt a key locked with [`SealKeypair::lock`], failing on the wrong passphrase.
+    pub fn unlock(locked: &LockedKey, public: &[u8; 32], passphrase: &str) -> Result<Self> {
+        let secret = lock_cipher(passphrase, &locked.salt, locked.rounds)
+            .decrypt(&Nonce::default(), Payload { msg: &locked.ciphertext, aad: public })
+            .map_err(|_| anyhow!("Wrong passphrase"))?;
+
+        let keypair = Self::from_secret(
+            secret.try_into().map_err(|_| anyhow!("Locked key has the wrong length"))?,
+        );
+        if keypair.public != *public {
+            bail!("Locked key doesn't match its public key");
+        }
+
+        Ok(keypair)
+    }
+}
+
+/// A secret key encrypted with a passphrase. The public key isn't part of it, but is checked on
+/// unlocking, so keep the two together.
+///
+/// Layout:
+/// - `rounds` (4 bytes, LE)  -> PBKDF2-HMAC-SHA256 rounds
+/// - `salt` (16 bytes)       -> random, new every time a key is locked
+/// - `ciphertext` (48 bytes) -> the secret key and its tag
+#[derive(Clone, Debug, PartialEq)]
+pub struct LockedKey {
+    pub rounds: u32,
+    pub salt: [u8; 16],
+    pub ciphertext: Vec<u8>,
+}
+
+impl LockedKey {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut data = Vec::with_capacity(20 + self.ciphertext.len());
+        data.extend_from_slice(&self.rounds.to_le_bytes());
+        data.extend_from_slice(&self.salt);
+        data.extend_from_slice(&self.ciphertext);
+        data
+    }
+
+    pub fn try_from_bytes(data: &[u8]) -> Result<Self> {
+        if data.len() != 20 + 32 + 16 {
+            bail!("Locked key is {} bytes, expected {}", data.len(), 20 + 32 + 16);
+        }
+
+        Ok(Self {
+            rounds: u32::from_le_bytes(data[..4].try_into()?),
+            salt: data[4..20].try_into()?,
+            ciphertext: data[20..].to_vec(),
+        })
+    }
+}
+
+/// A short id for a recipient's public key, stored in the header of tapes sealed to it so a
+/// reader can tell whether they hold the right key before trying it.
+pub fn key_id(public: &[u8; 32]) -> [u8; 12] {
+    let digest = Sha256::digest(public);
+    let mut id = [0u8; 12];
+    id.copy_from_slice(&digest[..12]);
+    id
+}
+
+/// Encrypt `data` so only the holder of the secret key behind `recipient` can read it. Returns
+/// `<ephemeral public key><ciphertext + tag>`.
+///
+/// Every payload is sealed with a fresh ephemeral key, so the key it's encrypted under is never
+/// used twice and the nonce can be fixed.
+pub fn seal(data: &[u8], recipient: &[u8; 32]) -> Result<Vec<u8>> {
+    let ephemeral = SealKeypair::generate();
+    let shared = MontgomeryPoint(*recipient).mul_clamped(ephemeral.secret).to_bytes();
+
+    // A low-order recipient key would give a shared secret anyone can guess
+    if shared == [0u8; 32] {
+        bail!("Invalid recipient key");
+    }
+
+    let cipher = cipher(&shared, &ephemeral.public, recipient);
+    let ciphertext = cipher
+        .encrypt(&Nonce::default(), data)
+        .map_err(|_| anyhow!("Failed to seal payload"))?;
+
+    let mut sealed = Vec::with_capacity(32 + ciphertext.len());
+    sealed.extend_from_slice(&ephemeral.public);
+    sealed.extend_from_slice(&ciphertext);
+    Ok(sealed)
+}
+
+/// Decrypt a payload sealed with [`seal`], failing if it wasn't sealed to `keypair` or was
+/// tampered with.
+pub fn open_sealed(sealed: &[u8], keypair: &SealKeypair) -> Result<Vec<u8>> {
+    if sealed.len() < SEAL_OVERHEAD {
+        bail!("Sealed payload is too short ({} < {})", sealed.len(), SEAL_OVERHEAD);
+    }
+
+    let (ephemeral, ciphertext) = sealed.split_at(32);
+    let ephemeral: [u8; 32] = ephemeral.try_into()?;
+    let shared = MontgomeryPoint(ephemeral).mul_clamped(keypair.secret).to_bytes();
+
+    cipher(&shared, &ephemeral, &keypair.public)
+        .decrypt(&Nonce::default(), ciphertext)
+        .map_err(|_| anyhow!("Failed to open sealed payload, wrong key or corrupted data"))
+}
+
+/// The cipher for one sealed payload, keyed on the shared secret and both public keys.
+fn cipher(shared: &[u8; 32], ephemeral: &[u8; 32], recipient: &[u8; 32]) -> Aes256GcmSiv {
+    let key = Sha256::new()
+        .chain_update(SEAL_DOMAIN)
+        .chain_update(shared)
+        .chain_update(ephemeral)
+        .chain_update(recipient)
+        .finalize();
+
+    Aes256GcmSiv::new(&key)
+}
+
+/// The cipher a key is locked under, keyed on the stretched passphrase. A fresh salt gives every
+/// lock its own key, so the nonce can be fixed here too.
+fn lock_cipher(passphrase: &str, salt: &[u8; 16], rounds: u32) -> Aes256GcmSiv {
+    let mut key = [0u8; 32];
+    pbkdf2::pbkdf2::<Hmac<Sha256>>(passphrase.as_bytes(), salt, rounds, &mut key);
+    Aes256GcmSiv::new(&key.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seal_roundtrip() -> Result<()> {
+        let recipient = SealKeypair::generate();
+        let data = b"only for the recipient".to_vec();
+
+        let sealed = seal(&data, &recipient.public)?;
+        assert_eq!(sealed.len(), data.len() + SEAL_OVERHEAD);
+        assert_eq!(open_sealed(&sealed, &recipient)?, data);
+
+        // Sealing twice gives different ciphertexts
+        assert_ne!(seal(&data, &recipient.public)?, sealed);
+
+        // Nobody else can open it, and tampering is caught
+        assert!(open_sealed(&sealed, &SealKeypair::generate()).is_err());
+        let mut tampered = sealed.clone();
+        *tampered.last_mut().unwrap() ^= 1;
+        assert!(open_sealed(&tampered, &recipient).is_err());
+
+        // Low-order keys are refused
+        assert!(seal(&data, &[0u8; 32]).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_lock_key() -> Result<()> {
+        let keypair = SealKeypair::generate();
+        let locked = keypair.lock_with_rounds("hunter2", 1000)?;
+        assert_eq!(LockedKey::try_from_bytes(&locked.to_bytes())?, locked);
+
+        let unlocked = SealKeypair::unlock(&locked, &keypair.public, "hunter2")?;
+        assert_eq!(unlocked.secret, keypair.secret);
+
+        assert!(SealKeypair::unlock(&locked, &keypair.public, "hunter3").is_err());
+        assert!(SealKeypair::unlock(&locked, &SealKeypair::generate().public, "hunter2").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_seal_key_from_seed() {
+        let a = SealKeypair::from_seed(&[1u8; 32]);
+        assert_eq!(a.public, SealKeypair::from_seed(&[1u8; 32]).public);
+        assert_ne!(a.public, SealKeypair::from_seed(&[2u8; 32]).public);
+        assert_eq!(a.id(), key_id(&a.public));
+    }
+}
@@ -0,0 +1,290 @@
+use std::ops::Range;
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, Result};
+use async_trait::async_trait;
+use reqwest::Client as HttpClient;
+use serde_json::{json, Value};
+use solana_sdk::pubkey::Pubkey;
+use tokio::time::timeout;
+
+use crate::utils::json_rpc;
+use super::SegmentSource;
+
+/// How long a source in a `SourceChain` gets before the next one is tried.
+pub const DEFAULT_SOURCE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The segments of a `getTape` result and the cursor of the page after it, null on the last
+/// page. Peers from before getTape was paged answer with a bare array of every segment, which
+/// reads as a single last page.
+pub fn get_tape_page(result: &Value) -> Option<(&[Value], Value)> {
+    match result {
+        Value::Array(items) => Some((items, Value::Null)),
+        _ => {
+            let items = result.get("items")?.as_array()?;
+            Some((items, result.get("cursor").cloned().unwrap_or(Value::Null)))
+        }
+    }
+}
+
+/// Segments from a trusted peer's web RPC, as served by `tapedrive web`. Reads a tape a page of
+/// `getTape` at a time.
+pub struct PeerSource {
+    url: String,
+    http: HttpClient,
+}
+
+impl PeerSource {
+    pub fn new(url: &str) -> Self {
+        Self {
+            url: url.to_string(),
+            http: HttpClient::new(),
+        }
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+}
+
+#[async_trait]
+impl SegmentSource for PeerSource {
+    async fn read_segments(&self, tape_address: &Pubkey) -> Result<Vec<(u64, Vec<u8>)>> {
+        let mut segments = Vec::new();
+        let mut cursor = Value::Null;
+
+        loop {
+            let params = json!({ "tape_address": tape_address.to_string(), "cursor": cursor });
+            let result = json_rpc(&self.http, &self.url, "Peer", "getTape", params).await?;
+            let (items, next) = get_tape_page(&result)
+                .ok_or_else(|| anyhow!("Peer {} returned no segments for tape {}", self.url, tape_address))?;
+
+            for item in items {
+                let segment_number = item
+                    .get("segment_number")
+                    .and_then(Value::as_u64)
+                    .ok_or_else(|| anyhow!("Peer {} returned a segment without a number", self.url))?;
+                let data = item
+                    .get("data")
+                    .and_then(Value::as_str)
+                    .ok_or_else(|| anyhow!("Peer {} returned segment {} without data", self.url, segment_number))?;
+                let data = base64::decode(data)
+                    .map_err(|e| anyhow!("Peer {} returned invalid segment {}: {}", self.url, segment_number, e))?;
+                segments.push((segment_number, data));
+            }
+
+            cursor = next;
+            if cursor.is_null() {
+                break;
+            }
+        }
+
+        Ok(segments)
+    }
+
+    fn name(&self) -> String {
+        format!("Peer {}", self.url)
+    }
+}
+
+/// Segment sources tried in order, each with its own timeout, e.g. the local store and then a
+/// trusted peer. A source that fails, times out or holds only part of what's asked for is passed
+/// over for the next one. With `with_rpc_fallback`, reads none of them can serve walk the chain
+/// over RPC, the source of last resort.
+#[derive(Default)]
+pub struct SourceChain<'a> {
+    sources: Vec<(&'a dyn SegmentSource, Duration)>,
+    rpc_fallback: bool,
+}
+
+impl<'a> SourceChain<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Try `source` after those already added, giving up on it after `timeout`.
+    pub fn with_source(mut self, source: &'a dyn SegmentSource, timeout: Duration) -> Self {
+        self.sources.push((source, timeout));
+        self
+    }
+
+    pub fn with_rpc_fallback(mut self, rpc_fallback: bool) -> Self {
+        self.rpc_fallback = rpc_fallback;
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sources.is_empty()
+    }
+
+    /// The first source's answer to `read` that holds everything asked for, or every source's
+    /// error. Without the tape's size, a source holding part of it can't be told from one
+    /// holding all of it, so `Read::All` asks every source and keeps the answer with the most
+    /// segments.
+    async fn first(&self, tape_address: &Pubkey, read: Read) -> Result<Vec<(u64, Vec<u8>)>> {
+        let mut errors = Vec::new();
+        let mut most: Vec<(u64, Vec<u8>)> = Vec::new();
+
+        for &(source, limit) in &self.sources {
+            let segments = match &read {
+                Read::All => source.read_segments(tape_address),
+                Read::Range(range) => source.read_segment_range(tape_address, range.clone()),
+                Read::Tape(total_segments) => source.read_tape(tape_address, *total_segments),
+            };
+            let segments = match timeout(limit, segments).await {
+                Ok(Ok(segments)) => segments,
+                Ok(Err(e)) => {
+                    errors.push(e.to_string());
+                    continue;
+                }
+                Err(_) => {
+                    errors.push(format!("{} timed out after {}s", source.name(), limit.as_secs_f32()));
+                    continue;
+                }
+            };
+
+            let expected = match &read {
+                Read::All => {
+                    if segments.is_empty() {
+                        errors.push(format!("{} holds no segments for tape {}", source.name(), tape_address));
+                    } else if segments.len() > most.len() {
+                        most = segments;
+                    }
+                    continue;
+                }
+                Read::Range(range) => range.end.saturating_sub(range.start),
+                Read::Tape(total_segments) => *total_segments,
+            };
+            if (segments.len() as u64) < expected {
+                errors.push(format!(
+                    "{} only holds {} of {} segments for tape {}",
+                    source.name(), segments.len(), expected, tape_address
+                ));
+                continue;
+            }
+
+            return Ok(segments);
+        }
+
+        if !most.is_empty() {
+            return Ok(most);
+        }
+        if errors.is_empty() {
+            bail!("No segment sources to read tape {} from", tape_address);
+        }
+        bail!("No segment source could serve tape {}: {}", tape_address, errors.join("; "))
+    }
+}
+
+/// What a `SourceChain` asks each source for.
+enum Read {
+    All,
+    Range(Range<u64>),
+    Tape(u64),
+}
+
+#[async_trait]
+impl SegmentSource for SourceChain<'_> {
+    async fn read_segments(&self, tape_address: &Pubkey) -> Result<Vec<(u64, Vec<u8>)>> {
+        self.first(tape_address, Read::All).await
+    }
+
+    async fn read_segment_range(&self, tape_address: &Pubkey, range: Range<u64>) -> Result<Vec<(u64, Vec<u8>)>> {
+        self.first(tape_address, Read::Range(range)).await
+    }
+
+    async fn read_tape(&self, tape_address: &Pubkey, total_segments: u64) -> Result<Vec<(u64, Vec<u8>)>> {
+        self.first(tape_address, Read::Tape(total_segments)).await
+    }
+
+    fn name(&self) -> String {
+        let names = self.sources.iter().map(|(source, _)| source.name()).collect::<Vec<_>>();
+        format!("Sources ({})", names.join(", "))
+    }
+
+    fn rpc_fallback(&self) -> bool {
+        self.rpc_fallback
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A source holding the first `held` segments of every tape, after `delay`.
+    struct Partial {
+        held: u64,
+        delay: Duration,
+    }
+
+    #[async_trait]
+    impl SegmentSource for Partial {
+        async fn read_segments(&self, _tape_address: &Pubkey) -> Result<Vec<(u64, Vec<u8>)>> {
+            tokio::time::sleep(self.delay).await;
+            Ok((0..self.held).map(|n| (n, vec![n as u8])).collect())
+        }
+
+        fn name(&self) -> String {
+            format!("Partial {}", self.held)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_source_chain() {
+        let tape = Pubkey::new_unique();
+        let (empty, partial, full) = (
+            Partial { held: 0, delay: Duration::ZERO },
+            Partial { held: 2, delay: Duration::ZERO },
+            Partial { held: 4, delay: Duration::ZERO },
+        );
+        let slow = Partial { held: 4, delay: Duration::from_secs(5) };
+
+        // Sources that only hold part of the tape are passed over
+        let chain = SourceChain::new()
+            .with_source(&partial, DEFAULT_SOURCE_TIMEOUT)
+            .with_source(&full, DEFAULT_SOURCE_TIMEOUT);
+        assert_eq!(chain.read_tape(&tape, 4).await.unwrap().len(), 4);
+        assert_eq!(chain.read_segment_range(&tape, 1..2).await.unwrap(), vec![(1, vec![1])]);
+        assert_eq!(chain.read_segment_range(&tape, 1..4).await.unwrap().len(), 3);
+        assert!(!chain.rpc_fallback());
+
+        // Without the tape's size, the most complete answer wins
+        assert_eq!(chain.read_segments(&tape).await.unwrap().len(), 4);
+        let chain = SourceChain::new()
+            .with_source(&full, DEFAULT_SOURCE_TIMEOUT)
+            .with_source(&partial, DEFAULT_SOURCE_TIMEOUT);
+        assert_eq!(chain.read_segments(&tape).await.unwrap().len(), 4);
+
+        // So are slow ones, and every error is reported
+        let chain = SourceChain::new()
+            .with_source(&slow, Duration::from_millis(10))
+            .with_source(&empty, DEFAULT_SOURCE_TIMEOUT)
+            .with_rpc_fallback(true);
+        let err = chain.read_tape(&tape, 4).await.unwrap_err().to_string();
+        assert!(err.contains("Partial 4 timed out after 0.01s"), "{}", err);
+        assert!(err.contains("Partial 0 only holds 0 of 4"), "{}", err);
+        assert!(chain.read_segments(&tape).await.is_err());
+        assert!(chain.rpc_fallback());
+
+        assert!(SourceChain::new().read_tape(&tape, 1).await.is_err());
+    }
+
+    #[test]
+    fn test_get_tape_page() {
+        let segment = json!({ "segment_number": 0, "data": "AA==" });
+
+        let paged = json!({ "items": [segment], "cursor": "1000", "limit": 1000, "total": 1001 });
+        let (items, cursor) = get_tape_page(&paged).unwrap();
+        assert_eq!(items, &[segment.clone()]);
+        assert_eq!(cursor, json!("1000"));
+
+        // Older peers send every segment at once
+        let legacy = json!([segment]);
+        let (items, cursor) = get_tape_page(&legacy).unwrap();
+        assert_eq!(items, &[segment]);
+        assert!(cursor.is_null());
+
+        assert!(get_tape_page(&json!({ "cursor": null })).is_none());
+        assert!(get_tape_page(&Value::Null).is_none());
+    }
+}
@@ -0,0 +1,160 @@
+use anyhow::{anyhow, Result};
+use futures::channel::mpsc;
+use futures::stream::{self, Stream, StreamExt};
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::nonblocking::pubsub_client::{PubsubClient, PubsubClientError};
+use solana_client::rpc_config::{RpcAccountInfoConfig, RpcTransactionLogsConfig, RpcTransactionLogsFilter};
+use solana_client::rpc_response::{Response, RpcLogsResponse};
+use solana_sdk::{account::Account, commitment_config::CommitmentConfig, pubkey::Pubkey, signature::Signature};
+use std::sync::Arc;
+use tape_api::prelude::*;
+use tokio::sync::oneshot;
+
+/// A change to a watched tape, pushed as soon as the cluster confirms it.
+#[derive(Clone, Debug)]
+pub enum TapeChange {
+    /// The tape account changed, e.g. a new root, segment count or state.
+    Account { slot: u64, tape: Tape },
+    /// Segments were appended to the tape.
+    Write { slot: u64, signature: Signature, event: WriteEvent },
+    /// A segment of the tape was replaced.
+    Update { slot: u64, signature: Signature, event: UpdateEvent },
+}
+
+/// Watch a tape for changes without polling. Combines an account subscription on the tape with a
+/// logs subscription for its Write and Update events. The stream ends when the websocket closes,
+/// dropping it unsubscribes.
+pub async fn watch_tape(
+    client_ws: Arc<PubsubClient>,
+    tape_address: &Pubkey,
+) -> Result<impl Stream<Item = TapeChange>> {
+    let tape_address = *tape_address;
+    let (tx, rx) = mpsc::unbounded();
+    let (ready_tx, ready_rx) = oneshot::channel::<Result<(), PubsubClientError>>();
+
+    tokio::spawn(async move {
+        let account_config = RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            commitment: Some(CommitmentConfig::confirmed()),
+            ..Default::default()
+        };
+        let logs_config = RpcTransactionLogsConfig {
+            commitment: Some(CommitmentConfig::confirmed()),
+        };
+        let logs_filter = RpcTransactionLogsFilter::Mentions(vec![tape_address.to_string()]);
+
+        let subscriptions = async {
+            let accounts = client_ws.account_subscribe(&tape_address, Some(account_config)).await?;
+            let logs = client_ws.logs_subscribe(logs_filter, logs_config).await?;
+            Ok((accounts, logs))
+        };
+
+        let ((accounts, unsubscribe_accounts), (logs, unsubscribe_logs)) = match subscriptions.await {
+            Ok(subscriptions) => {
+                let _ = ready_tx.send(Ok(()));
+                subscriptions
+            }
+            Err(e) => {
+                let _ = ready_tx.send(Err(e));
+                return;
+            }
+        };
+
+        let accounts = accounts.filter_map(|response| async move {
+            let account = response.value.decode::<Account>()?;
+            let tape = Tape::unpack(&account.data).ok()?;
+            Some(TapeChange::Account { slot: response.context.slot, tape: *tape })
+        });
+        let logs = logs.flat_map(|response| stream::iter(log_changes(&tape_address, &response)));
+
+        let mut changes = stream::select(accounts, logs);
+        while let Some(change) = changes.next().await {
+            // The receiver is gone, nobody is watching anymore
+            if tx.unbounded_send(change).is_err() {
+                break;
+            }
+        }
+
+        drop(changes);
+        unsubscribe_accounts().await;
+        unsubscribe_logs().await;
+    });
+
+    ready_rx
+        .await
+        .map_err(|_| anyhow!("Tape watcher stopped before subscribing"))?
+        .map_err(|e| anyhow!("Failed to subscribe to tape {}: {}", tape_address, e))?;
+
+    Ok(rx)
+}
+
+/// The Write and Update events for `tape_address` in the logs of a successful transaction.
+fn log_changes(tape_address: &Pubkey, response: &Response<RpcLogsResponse>) -> Vec<TapeChange> {
+    let logs = &response.value;
+    if logs.err.is_some() {
+        return Vec::new();
+    }
+
+    let Ok(signature) = logs.signature.parse::<Signature>() else {
+        return Vec::new();
+    };
+    let slot = response.context.slot;
+
+    logs.logs
+        .iter()
+        .filter_map(|log| log.strip_prefix("Program data: "))
+        .filter_map(|data| base64::decode(data).ok())
+        .filter_map(|data| match EventType::try_from(*data.first()?).ok()? {
+            EventType::WriteEvent => {
+                let event = *WriteEvent::try_from_bytes(&data).ok()?;
+                (event.address == tape_address.to_bytes())
+                    .then_some(TapeChange::Write { slot, signature, event })
+            }
+            EventType::UpdateEvent => {
+                let event = *UpdateEvent::try_from_bytes(&data).ok()?;
+                (event.address == tape_address.to_bytes())
+                    .then_some(TapeChange::Update { slot, signature, event })
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_client::rpc_response::RpcResponseContext;
+
+    fn logs_response(logs: Vec<String>) -> Response<RpcLogsResponse> {
+        Response {
+            context: RpcResponseContext { slot: 42, api_version: None },
+            value: RpcLogsResponse {
+                signature: Signature::default().to_string(),
+                err: None,
+                logs,
+            },
+        }
+    }
+
+    #[test]
+    fn test_log_changes() {
+        let tape = Pubkey::new_unique();
+        let other = Pubkey::new_unique();
+
+        let write = WriteEvent { num_added: 2, num_total: 5, address: tape.to_bytes() };
+        let update = UpdateEvent { segment_number: 3, address: other.to_bytes() };
+
+        let response = logs_response(vec![
+            format!("Program {} invoke [1]", tape_api::ID),
+            format!("Program data: {}", base64::encode(write.to_bytes())),
+            format!("Program data: {}", base64::encode(update.to_bytes())),
+        ]);
+
+        let changes = log_changes(&tape, &response);
+        assert_eq!(changes.len(), 1);
+        assert!(matches!(
+            changes[0],
+            TapeChange::Write { slot: 42, event, .. } if event == write
+        ));
+    }
+}
@@ -1,7 +1,11 @@
-use anyhow::Result;
+use anyhow::{bail, Result};
 use solana_sdk::{
-    signature::{Keypair, Signer, Signature},
+    address_lookup_table::AddressLookupTableAccount,
+    hash::Hash,
+    instruction::Instruction,
+    signature::{Signer, Signature},
     pubkey::Pubkey,
+    transaction::Transaction,
 };
 use tape_api::prelude::*;
 use solana_client::nonblocking::rpc_client::RpcClient;
@@ -10,11 +14,32 @@ use crate::{
     utils::*,
 };
 
+/// Checks writes of the given lengths, made in order, still fit on `tape`. A full tape fails here
+/// with the numbers involved, rather than on-chain with `TapeError::TapeFull` part way through.
+pub fn check_writes_fit(tape: &Tape, writes: impl IntoIterator<Item = usize>) -> Result<()> {
+    let mut segments = tape.total_segments;
+    let mut size = tape.total_size;
+
+    for len in writes {
+        if !write_fits(segments, size, len) {
+            bail!(
+                "Tape holds {} of {} segments, the remaining writes don't fit; \
+                 split the data across several tapes (see `split_into_tapes`)",
+                tape.total_segments, MAX_SEGMENTS
+            );
+        }
+        segments += len.div_ceil(SEGMENT_SIZE) as u64;
+        size += len as u64;
+    }
+
+    Ok(())
+}
+
 /// Writes a chunk of data to an unlinked tape, returning the signature and the estimated 
 /// segment count.
 pub async fn write_to_tape(
     client: &RpcClient,
-    signer: &Keypair,
+    signer: &dyn Signer,
     tape_address: Pubkey,
     writer_address: Pubkey,
     data: &[u8],
@@ -22,7 +47,7 @@ pub async fn write_to_tape(
 
     let segment_count = (data.len() + SEGMENT_SIZE - 1) / SEGMENT_SIZE;
 
-    let instruction = build_write_ix(
+    let instruction = program_context().build_write_ix(
         signer.pubkey(),
         tape_address,
         writer_address,
@@ -38,7 +63,7 @@ pub async fn write_to_tape(
 /// segment count.
 pub async fn write_linked_chunk(
     client: &RpcClient,
-    signer: &Keypair,
+    signer: &dyn Signer,
     tape_address: Pubkey,
     writer_address: Pubkey,
     prev_signature: Signature,
@@ -63,3 +88,124 @@ pub async fn write_linked_chunk(
         payload,
     ).await
 }
+
+/// Writes a chunk of data to a linked tape in a v0 transaction, loading the tape, writer and
+/// config accounts from `lookup_table`. This leaves room for `WIDE_SIZE` byte chunks. Returns
+/// the signature and the estimated segment count.
+pub async fn write_linked_chunk_with_lookup(
+    client: &RpcClient,
+    signer: &dyn Signer,
+    tape_address: Pubkey,
+    writer_address: Pubkey,
+    prev_signature: Signature,
+    data: &[u8],
+    lookup_table: &AddressLookupTableAccount,
+) -> Result<(Signature, usize)> {
+
+    let payload = [prev_signature.as_ref(), data].concat();
+    let segment_count = payload.len().div_ceil(SEGMENT_SIZE);
+
+    let instruction = program_context().build_write_ix(
+        signer.pubkey(),
+        tape_address,
+        writer_address,
+        &payload,
+    );
+
+    let signature = send_versioned_with_retry(
+        client,
+        &instruction,
+        signer,
+        std::slice::from_ref(lookup_table),
+        MAX_RETRIES,
+    ).await?;
+
+    Ok((signature, segment_count))
+}
+
+/// A write signed on a [`SigningPool`], ready to send.
+pub struct SignedWrite {
+    pub transaction: Transaction,
+    /// The signature the write links to, on a linked tape.
+    pub prev_signature: Option<Signature>,
+    pub segment_count: usize,
+    instruction: Instruction,
+}
+
+impl SignedWrite {
+    pub fn signature(&self) -> Signature {
+        self.transaction.signatures[0]
+    }
+}
+
+/// Signs a write of a chunk on `pool`, linked to `prev_signature` if one is given. Signing the
+/// next chunk of a linked tape only needs the signature of this one, not for it to be sent, so
+/// the two can overlap.
+pub async fn sign_write(
+    pool: &SigningPool,
+    tape_address: Pubkey,
+    writer_address: Pubkey,
+    prev_signature: Option<Signature>,
+    data: &[u8],
+    blockhash: Hash,
+) -> Result<SignedWrite> {
+
+    let payload = match prev_signature {
+        Some(prev) => [prev.as_ref(), data].concat(),
+        None => data.to_vec(),
+    };
+
+    let instruction = program_context().build_write_ix(
+        pool.pubkey(),
+        tape_address,
+        writer_address,
+        &payload,
+    );
+
+    let transaction = pool.sign(vec![instruction.clone()], blockhash).await?;
+
+    Ok(SignedWrite {
+        transaction,
+        prev_signature,
+        segment_count: payload.len().div_ceil(SEGMENT_SIZE),
+        instruction,
+    })
+}
+
+/// Sends a write signed on `pool`, returning the signature that landed and the estimated
+/// segment count. Retries are signed again on the pool, so the signature can differ from
+/// [`SignedWrite::signature`], and a next chunk linked to that one has to be signed again.
+pub async fn send_signed_write(
+    client: &RpcClient,
+    pool: &SigningPool,
+    write: &SignedWrite,
+) -> Result<(Signature, usize)> {
+
+    let resign = |blockhash| pool.sign(vec![write.instruction.clone()], blockhash);
+    let signature = send_signed_with_retry(
+        client,
+        write.transaction.clone(),
+        resign,
+        MAX_RETRIES,
+    ).await?;
+
+    Ok((signature, write.segment_count))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_writes_fit() {
+        let mut tape: Tape = bytemuck::Zeroable::zeroed();
+        assert!(check_writes_fit(&tape, [SAFE_SIZE; 4]).is_ok());
+
+        // Every write is padded to whole segments
+        tape.total_segments = MAX_SEGMENTS as u64 - 2;
+        assert!(check_writes_fit(&tape, [1, 1]).is_ok());
+        assert!(check_writes_fit(&tape, [1, 1, 1]).is_err());
+        assert!(check_writes_fit(&tape, [SEGMENT_SIZE + 1]).is_ok());
+        assert!(check_writes_fit(&tape, [2 * SEGMENT_SIZE + 1]).is_err());
+    }
+}
@@ -1,12 +1,18 @@
 use anyhow::{Result, anyhow};
+use futures::StreamExt;
+use solana_client::nonblocking::pubsub_client::{PubsubClient, PubsubClientError};
 use solana_client::nonblocking::rpc_client::RpcClient;
 use solana_client::rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig};
 use solana_client::rpc_filter::{Memcmp, MemcmpEncodedBytes, RpcFilterType};
 use solana_account_decoder::{UiAccountEncoding, UiDataSliceConfig};
-use solana_sdk::{pubkey::Pubkey, account::Account};
-use tape_api::pda::{archive_pda, epoch_pda, spool_pda};
-use tape_api::state::{Tape, Writer, Miner, Spool, Epoch, Archive};
-use crate::utils::{deserialize, get_account, get_program_account};
+use solana_sdk::{pubkey::Pubkey, account::Account, commitment_config::CommitmentConfig};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+use tokio::sync::oneshot;
+use tape_api::consts::NAME_LEN;
+use tape_api::state::{Tape, Writer, Miner, Spool, Epoch, Archive, Config};
+use crate::utils::{deserialize, get_account, get_program_account, program_context};
 
 pub async fn get_tape_account(client: &RpcClient, tape_address: &Pubkey) -> Result<(Tape, Pubkey)> {
     let account_bytes = get_account(client, tape_address).await?;
@@ -47,6 +53,194 @@ pub async fn find_tape_account(client: &RpcClient, number: u64) -> Result<Option
     Ok(accounts.into_iter().next())
 }
 
+/// A page of results, skipping `offset` and keeping at most `limit`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Page {
+    pub offset: usize,
+    pub limit: usize,
+}
+
+/// Every miner registered with the program.
+pub async fn get_all_miner_accounts(client: &RpcClient) -> Result<Vec<(Pubkey, Miner)>> {
+    let config = RpcProgramAccountsConfig {
+        filters: Some(vec![RpcFilterType::DataSize(Miner::get_size() as u64)]),
+        account_config: RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            data_slice: None,
+            commitment: None,
+            min_context_slot: None,
+        },
+        with_context: None,
+        sort_results: true.into(),
+    };
+
+    let accounts = get_program_account(client, config).await?;
+
+    // Accounts of another type that happen to have the same size don't unpack
+    Ok(accounts
+        .into_iter()
+        .filter_map(|(address, account)| {
+            Miner::unpack(&account.data).ok().map(|miner| (address, *miner))
+        })
+        .collect())
+}
+
+/// A page of tapes, highest tape number first, unfinalized tapes (number 0) last. Only the tape
+/// numbers of all tapes are fetched to sort them, the full accounts just for the page.
+pub async fn get_all_tape_accounts(client: &RpcClient, page: Page) -> Result<Vec<(Pubkey, Tape)>> {
+    let config = RpcProgramAccountsConfig {
+        filters: Some(vec![RpcFilterType::DataSize(Tape::get_size() as u64)]),
+        account_config: RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            data_slice: Some(UiDataSliceConfig { offset: 8, length: 8 }), // `number` field
+            commitment: None,
+            min_context_slot: None,
+        },
+        with_context: None,
+        sort_results: true.into(),
+    };
+
+    let mut numbers: Vec<(u64, Pubkey)> = get_program_account(client, config)
+        .await?
+        .into_iter()
+        .filter_map(|(address, account)| {
+            let number = u64::from_le_bytes(account.data.get(..8)?.try_into().ok()?);
+            Some((number, address))
+        })
+        .collect();
+    numbers.sort_by(|a, b| b.cmp(a));
+
+    let addresses: Vec<Pubkey> = numbers
+        .into_iter()
+        .skip(page.offset)
+        .take(page.limit)
+        .map(|(_, address)| address)
+        .collect();
+
+    get_tape_accounts(client, &addresses).await
+}
+
+/// The tape accounts at `addresses`, in order, fetched 100 to a request. Addresses without a tape
+/// account are left out.
+pub async fn get_tape_accounts(client: &RpcClient, addresses: &[Pubkey]) -> Result<Vec<(Pubkey, Tape)>> {
+    let mut tapes = Vec::with_capacity(addresses.len());
+    for batch in addresses.chunks(100) {
+        let accounts = client
+            .get_multiple_accounts(batch)
+            .await
+            .map_err(|e| anyhow!("Failed to fetch tape accounts: {}", e))?;
+
+        for (address, account) in batch.iter().zip(accounts) {
+            // Closed since it was listed
+            let Some(account) = account else {
+                continue;
+            };
+            if let Ok(tape) = Tape::unpack(&account.data) {
+                tapes.push((*address, *tape));
+            }
+        }
+    }
+
+    Ok(tapes)
+}
+
+/// Tapes whose name starts with `prefix`, newest first, filtered by the RPC node.
+pub async fn get_tape_accounts_by_name_prefix(client: &RpcClient, prefix: &str, page: Page) -> Result<Vec<(Pubkey, Tape)>> {
+    if prefix.len() > NAME_LEN {
+        return Ok(Vec::new());
+    }
+
+    let config = RpcProgramAccountsConfig {
+        filters: Some(vec![
+            RpcFilterType::DataSize(Tape::get_size() as u64),
+            RpcFilterType::Memcmp(Memcmp::new(
+                56, // Offset of `name` field
+                MemcmpEncodedBytes::Base64(base64::encode(prefix.as_bytes())),
+            )),
+        ]),
+        account_config: RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            data_slice: None,
+            commitment: None,
+            min_context_slot: None,
+        },
+        with_context: None,
+        sort_results: true.into(),
+    };
+
+    let mut tapes: Vec<(Pubkey, Tape)> = get_program_account(client, config)
+        .await?
+        .into_iter()
+        .filter_map(|(address, account)| Some((address, *Tape::unpack(&account.data).ok()?)))
+        .collect();
+    tapes.sort_by(|a, b| b.1.number.cmp(&a.1.number));
+
+    Ok(tapes.into_iter().skip(page.offset).take(page.limit).collect())
+}
+
+/// A tape that was never finalized, paired with the writer account still open for it.
+#[derive(Clone, Copy, Debug)]
+pub struct UnfinalizedTape {
+    pub tape_address: Pubkey,
+    pub tape: Tape,
+    pub writer_address: Pubkey,
+    /// Rent held by the writer, returned to the authority once the tape is finalized.
+    pub writer_lamports: u64,
+}
+
+/// Every writer account whose tape belongs to `authority`, paired with the state of that tape.
+/// Only the tape each writer points at is fetched, not the writers' Merkle trees.
+pub async fn get_unfinalized_tapes(client: &RpcClient, authority: &Pubkey) -> Result<Vec<UnfinalizedTape>> {
+    let writer_config = RpcProgramAccountsConfig {
+        filters: Some(vec![RpcFilterType::DataSize(Writer::get_size() as u64)]),
+        account_config: RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            data_slice: Some(UiDataSliceConfig { offset: 8, length: 32 }), // `tape` field
+            commitment: None,
+            min_context_slot: None,
+        },
+        with_context: None,
+        sort_results: true.into(),
+    };
+
+    let writers: HashMap<Pubkey, (Pubkey, u64)> = get_program_account(client, writer_config)
+        .await?
+        .into_iter()
+        .filter_map(|(address, account)| {
+            let tape = Pubkey::try_from(account.data.as_slice()).ok()?;
+            Some((tape, (address, account.lamports)))
+        })
+        .collect();
+
+    let tape_config = RpcProgramAccountsConfig {
+        filters: Some(vec![
+            RpcFilterType::DataSize(Tape::get_size() as u64),
+            RpcFilterType::Memcmp(Memcmp::new(
+                24, // Offset of `authority` field
+                MemcmpEncodedBytes::Base64(base64::encode(authority.as_ref())),
+            )),
+        ]),
+        account_config: RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            data_slice: None,
+            commitment: None,
+            min_context_slot: None,
+        },
+        with_context: None,
+        sort_results: true.into(),
+    };
+
+    Ok(get_program_account(client, tape_config)
+        .await?
+        .into_iter()
+        .filter_map(|(tape_address, account)| {
+            let tape = *Tape::unpack(&account.data).ok()?;
+            let (writer_address, writer_lamports) = *writers.get(&tape_address)?;
+            Some(UnfinalizedTape { tape_address, tape, writer_address, writer_lamports })
+        })
+        .collect())
+}
+
 pub async fn get_writer_account(client: &RpcClient, writer_address: &Pubkey) -> Result<(Writer, Pubkey)> {
     let account_bytes = get_account(client, writer_address).await?;
     let account: Account = deserialize(&account_bytes)?;
@@ -66,7 +260,7 @@ pub async fn get_miner_account(client: &RpcClient, miner_address: &Pubkey) -> Re
 }
 
 pub async fn get_spool_account(client: &RpcClient, spool: u8) -> Result<(Spool, Pubkey)> {
-    let (spool_address, _bump) = spool_pda(spool);
+    let (spool_address, _bump) = program_context().spool_pda(spool);
     let account_bytes = get_account(client, &spool_address).await?;
     let account: Account = deserialize(&account_bytes)?;
     let account = Spool::unpack(&account.data)
@@ -76,7 +270,7 @@ pub async fn get_spool_account(client: &RpcClient, spool: u8) -> Result<(Spool,
 }
 
 pub async fn get_epoch_account(client: &RpcClient) -> Result<(Epoch, Pubkey)> {
-    let (epoch_address, _bump) = epoch_pda();
+    let (epoch_address, _bump) = program_context().epoch_pda();
     let account_bytes = get_account(client, &epoch_address).await?;
     let account: Account = deserialize(&account_bytes)?;
     let account = Epoch::unpack(&account.data)
@@ -86,7 +280,7 @@ pub async fn get_epoch_account(client: &RpcClient) -> Result<(Epoch, Pubkey)> {
 }
 
 pub async fn get_archive_account(client: &RpcClient) -> Result<(Archive, Pubkey)> {
-    let (archive_address, _bump) = archive_pda();
+    let (archive_address, _bump) = program_context().archive_pda();
     let account_bytes = get_account(client, &archive_address).await?;
     let account: Account = deserialize(&account_bytes)?;
     let account = Archive::unpack(&account.data)
@@ -94,3 +288,214 @@ pub async fn get_archive_account(client: &RpcClient) -> Result<(Archive, Pubkey)
         .copied()?;
     Ok((account, archive_address))
 }
+
+pub async fn get_config_account(client: &RpcClient) -> Result<(Config, Pubkey)> {
+    let (config_address, _bump) = program_context().config_pda();
+    let account_bytes = get_account(client, &config_address).await?;
+    let account: Account = deserialize(&account_bytes)?;
+    let account = Config::unpack(&account.data)
+        .map_err(|e| anyhow!("Failed to unpack config account: {}", e))
+        .copied()?;
+    Ok((account, config_address))
+}
+
+/// Fails with a readable error if the guardian has paused the program, so writes and mining can
+/// stop early instead of failing on-chain.
+pub async fn ensure_not_paused(client: &RpcClient) -> Result<()> {
+    let (config, _) = get_config_account(client).await?;
+    if config.is_paused() {
+        return Err(anyhow!(
+            "The tape program is paused by its guardian ({}), try again later",
+            config.guardian
+        ));
+    }
+    Ok(())
+}
+
+/// How long `CachedAccounts` trusts an account it fetched, without a subscription to keep it
+/// fresh.
+pub const ACCOUNT_CACHE_TTL: Duration = Duration::from_secs(2);
+
+struct CachedAccount {
+    data: Vec<u8>,
+    slot: u64,
+    fetched_at: Instant,
+}
+
+#[derive(Default)]
+struct AccountCache {
+    accounts: HashMap<Pubkey, CachedAccount>,
+    /// Accounts with a live subscription, trusted until it ends.
+    subscribed: HashSet<Pubkey>,
+}
+
+/// A cache of the singleton program accounts (Epoch, Archive and Config), for loops that read
+/// them far more often than they change. Accounts are refetched once older than the TTL, or kept
+/// fresh by an `accountSubscribe` per account after `subscribe`. A newer account never gets
+/// replaced by one read at an older slot. Cheap to clone, clones share the cache.
+#[derive(Clone)]
+pub struct CachedAccounts {
+    ttl: Duration,
+    cache: Arc<Mutex<AccountCache>>,
+}
+
+impl Default for CachedAccounts {
+    fn default() -> Self {
+        Self::new(ACCOUNT_CACHE_TTL)
+    }
+}
+
+impl CachedAccounts {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            cache: Arc::default(),
+        }
+    }
+
+    /// Keep the cached accounts up to date from the cluster's pushes instead of the TTL, until
+    /// the websocket closes.
+    pub async fn subscribe(&self, client_ws: Arc<PubsubClient>) -> Result<()> {
+        let program = program_context();
+        for address in [program.epoch_address(), program.archive_address(), program.config_address()] {
+            let (ready_tx, ready_rx) = oneshot::channel::<Result<(), PubsubClientError>>();
+            let client_ws = client_ws.clone();
+            let cache = self.clone();
+
+            tokio::spawn(async move {
+                let config = RpcAccountInfoConfig {
+                    encoding: Some(UiAccountEncoding::Base64),
+                    commitment: Some(CommitmentConfig::confirmed()),
+                    ..Default::default()
+                };
+
+                let (mut updates, unsubscribe) = match client_ws.account_subscribe(&address, Some(config)).await {
+                    Ok(subscription) => {
+                        let _ = ready_tx.send(Ok(()));
+                        subscription
+                    }
+                    Err(e) => {
+                        let _ = ready_tx.send(Err(e));
+                        return;
+                    }
+                };
+
+                cache.lock().subscribed.insert(address);
+                while let Some(response) = updates.next().await {
+                    if let Some(account) = response.value.decode::<Account>() {
+                        cache.put(address, account.data, response.context.slot);
+                    }
+                }
+
+                // Pushes stopped, fall back to the TTL
+                cache.lock().subscribed.remove(&address);
+                drop(updates);
+                unsubscribe().await;
+            });
+
+            ready_rx
+                .await
+                .map_err(|_| anyhow!("Account subscription task ended early"))?
+                .map_err(|e| anyhow!("Failed to subscribe to account {}: {}", address, e))?;
+        }
+
+        Ok(())
+    }
+
+    /// Drop a cached account, e.g. after sending a transaction that changes it.
+    pub fn invalidate(&self, address: &Pubkey) {
+        self.lock().accounts.remove(address);
+    }
+
+    pub fn invalidate_all(&self) {
+        self.lock().accounts.clear();
+    }
+
+    pub async fn epoch(&self, client: &RpcClient) -> Result<Epoch> {
+        let data = self.fetch(client, &program_context().epoch_address()).await?;
+        Epoch::unpack(&data)
+            .map_err(|e| anyhow!("Failed to unpack epoch account: {}", e))
+            .copied()
+    }
+
+    pub async fn archive(&self, client: &RpcClient) -> Result<Archive> {
+        let data = self.fetch(client, &program_context().archive_address()).await?;
+        Archive::unpack(&data)
+            .map_err(|e| anyhow!("Failed to unpack archive account: {}", e))
+            .copied()
+    }
+
+    pub async fn config(&self, client: &RpcClient) -> Result<Config> {
+        let data = self.fetch(client, &program_context().config_address()).await?;
+        Config::unpack(&data)
+            .map_err(|e| anyhow!("Failed to unpack config account: {}", e))
+            .copied()
+    }
+
+    async fn fetch(&self, client: &RpcClient, address: &Pubkey) -> Result<Vec<u8>> {
+        if let Some(data) = self.cached(address) {
+            return Ok(data);
+        }
+
+        let response = client
+            .get_account_with_commitment(address, client.commitment())
+            .await
+            .map_err(|e| anyhow!("Failed to fetch account {}: {}", address, e))?;
+        let account = response.value.ok_or_else(|| anyhow!("Account {} not found", address))?;
+
+        self.put(*address, account.data.clone(), response.context.slot);
+        Ok(account.data)
+    }
+
+    /// The cached data of an account, if it's still fresh.
+    fn cached(&self, address: &Pubkey) -> Option<Vec<u8>> {
+        let cache = self.lock();
+        let account = cache.accounts.get(address)?;
+        let fresh = cache.subscribed.contains(address) || account.fetched_at.elapsed() < self.ttl;
+        fresh.then(|| account.data.clone())
+    }
+
+    fn put(&self, address: Pubkey, data: Vec<u8>, slot: u64) {
+        let mut cache = self.lock();
+        if cache.accounts.get(&address).is_some_and(|cached| cached.slot > slot) {
+            return;
+        }
+        cache.accounts.insert(address, CachedAccount { data, slot, fetched_at: Instant::now() });
+    }
+
+    fn lock(&self) -> MutexGuard<'_, AccountCache> {
+        // A panic mid-update leaves the cache as consistent as before it, keep using it
+        self.cache.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cached_accounts() {
+        let cache = CachedAccounts::new(Duration::from_secs(60));
+        let address = Pubkey::new_unique();
+        assert_eq!(cache.cached(&address), None);
+
+        cache.put(address, vec![1], 10);
+        assert_eq!(cache.cached(&address), Some(vec![1]));
+
+        // An older read doesn't roll the account back
+        cache.put(address, vec![0], 9);
+        assert_eq!(cache.cached(&address), Some(vec![1]));
+        cache.put(address, vec![2], 11);
+        assert_eq!(cache.cached(&address), Some(vec![2]));
+
+        cache.invalidate(&address);
+        assert_eq!(cache.cached(&address), None);
+
+        // Expired, unless a subscription keeps it fresh
+        let cache = CachedAccounts::new(Duration::ZERO);
+        cache.put(address, vec![1], 10);
+        assert_eq!(cache.cached(&address), None);
+        cache.lock().subscribed.insert(address);
+        assert_eq!(cache.cached(&address), Some(vec![1]));
+    }
+}
@@ -0,0 +1,57 @@
+use anyhow::{anyhow, bail, Result};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    hash::Hash,
+    pubkey::Pubkey,
+    signature::Signature,
+};
+use std::str::FromStr;
+use tokio::time::{sleep, Duration};
+
+/// Genesis hash of mainnet-beta, the one cluster without a faucet.
+pub const MAINNET_GENESIS_HASH: &str = "5eykt4UsFv8P8NJdTREpY1vzqKqZKvdpKuc147dw2N9d";
+
+/// How long to wait for an airdrop to confirm before giving up on it.
+const AIRDROP_TIMEOUT: Duration = Duration::from_secs(60);
+
+const AIRDROP_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Whether `client` points at mainnet, by its genesis hash so custom RPC URLs count too.
+pub async fn is_mainnet(client: &RpcClient) -> Result<bool> {
+    let genesis_hash = client
+        .get_genesis_hash()
+        .await
+        .map_err(|e| anyhow!("Failed to fetch cluster genesis hash: {}", e))?;
+
+    // The const always parses, compare hashes rather than strings
+    Ok(genesis_hash == Hash::from_str(MAINNET_GENESIS_HASH)?)
+}
+
+/// Ask the cluster's faucet for `lamports` and wait until the airdrop is confirmed. Only devnet,
+/// testnet and local validators have a faucet, and public ones cap what a request can ask for.
+pub async fn request_airdrop(client: &RpcClient, recipient: &Pubkey, lamports: u64) -> Result<Signature> {
+    if is_mainnet(client).await? {
+        bail!("Mainnet has no faucet, fund {} from another wallet", recipient);
+    }
+
+    let signature = client
+        .request_airdrop(recipient, lamports)
+        .await
+        .map_err(|e| anyhow!("Airdrop request failed, the faucet may be rate limiting you: {}", e))?;
+
+    let deadline = tokio::time::Instant::now() + AIRDROP_TIMEOUT;
+    loop {
+        let status = client
+            .get_signature_status_with_commitment(&signature, CommitmentConfig::confirmed())
+            .await?;
+        match status {
+            Some(Ok(())) => return Ok(signature),
+            Some(Err(e)) => bail!("Airdrop {} failed: {}", signature, e),
+            None if tokio::time::Instant::now() >= deadline => {
+                bail!("Airdrop {} wasn't confirmed within {}s", signature, AIRDROP_TIMEOUT.as_secs())
+            }
+            None => sleep(AIRDROP_POLL_INTERVAL).await,
+        }
+    }
+}
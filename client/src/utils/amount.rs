@@ -0,0 +1,78 @@
+use anyhow::{anyhow, bail, Result};
+use tape_api::consts::{ONE_TAPE, TOKEN_DECIMALS};
+
+/// Parse a decimal TAPE amount, e.g. `1.5`, `0.25 TAPE` or `3tape`, into base units. Amounts
+/// with more than `TOKEN_DECIMALS` decimal places or that don't fit a u64 are rejected rather
+/// than rounded.
+pub fn parse_tape_amount(input: &str) -> Result<u64> {
+    let trimmed = input.trim();
+    let number = match trimmed.len().checked_sub(4) {
+        Some(split) if trimmed.is_char_boundary(split) && trimmed[split..].eq_ignore_ascii_case("tape") => {
+            trimmed[..split].trim_end()
+        }
+        _ => trimmed,
+    };
+
+    let (whole, fraction) = number.split_once('.').unwrap_or((number, ""));
+    let is_digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+    if (whole.is_empty() && fraction.is_empty()) || !is_digits(whole) || !is_digits(fraction) {
+        bail!("Invalid TAPE amount {}, expected a decimal like 1.5", input);
+    }
+    if fraction.len() > TOKEN_DECIMALS as usize {
+        bail!("TAPE amount {} has more than {} decimal places", input, TOKEN_DECIMALS);
+    }
+
+    let whole: u64 = if whole.is_empty() { 0 } else { whole.parse()? };
+    let fraction: u64 = if fraction.is_empty() {
+        0
+    } else {
+        fraction.parse::<u64>()? * 10u64.pow(TOKEN_DECIMALS as u32 - fraction.len() as u32)
+    };
+
+    whole
+        .checked_mul(ONE_TAPE)
+        .and_then(|units| units.checked_add(fraction))
+        .ok_or_else(|| anyhow!("TAPE amount {} is too large", input))
+}
+
+/// Format base units as a decimal TAPE amount without trailing zeros, e.g. `1.5`.
+pub fn format_tape_amount(units: u64) -> String {
+    let whole = units / ONE_TAPE;
+    let fraction = units % ONE_TAPE;
+    if fraction == 0 {
+        return whole.to_string();
+    }
+
+    let fraction = format!("{:0width$}", fraction, width = TOKEN_DECIMALS as usize);
+    format!("{}.{}", whole, fraction.trim_end_matches('0'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_tape_amount() {
+        assert_eq!(parse_tape_amount("1").unwrap(), ONE_TAPE);
+        assert_eq!(parse_tape_amount("1.5").unwrap(), ONE_TAPE + ONE_TAPE / 2);
+        assert_eq!(parse_tape_amount(" 0.25 TAPE ").unwrap(), ONE_TAPE / 4);
+        assert_eq!(parse_tape_amount(".5tape").unwrap(), ONE_TAPE / 2);
+        assert_eq!(parse_tape_amount("0.0000000001").unwrap(), 1);
+
+        for invalid in ["", "TAPE", ".", "-1", "1e3", "1.2.3", "0.00000000001", "1,5", "99999999999"] {
+            assert!(parse_tape_amount(invalid).is_err(), "{} should be rejected", invalid);
+        }
+    }
+
+    #[test]
+    fn test_format_tape_amount() {
+        assert_eq!(format_tape_amount(0), "0");
+        assert_eq!(format_tape_amount(ONE_TAPE * 3), "3");
+        assert_eq!(format_tape_amount(ONE_TAPE + ONE_TAPE / 2), "1.5");
+        assert_eq!(format_tape_amount(1), "0.0000000001");
+
+        for units in [1, 12_345, ONE_TAPE / 3, u64::MAX] {
+            assert_eq!(parse_tape_amount(&format_tape_amount(units)).unwrap(), units);
+        }
+    }
+}
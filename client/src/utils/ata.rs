@@ -1,22 +1,32 @@
 use anyhow::{Result, anyhow};
 use solana_client::nonblocking::rpc_client::RpcClient;
 use solana_sdk::{
+    program_pack::Pack,
     pubkey::Pubkey,
-    signature::{Keypair, Signature, Signer},
+    signature::{Signature, Signer},
     transaction::Transaction,
 };
 use spl_associated_token_account::instruction::create_associated_token_account;
 
-use crate::utils::{deserialize, get_latest_blockhash, send_and_confirm_transaction};
+use crate::utils::{deserialize, get_latest_blockhash, program_context, send_and_confirm_transaction};
 
 pub async fn create_ata(
     client: &RpcClient,
-    payer: &Keypair,
+    payer: &dyn Signer,
+) -> Result<(Pubkey, Signature)> {
+    create_ata_for_owner(client, payer, &payer.pubkey()).await
+}
+
+/// Ensures the TAPE associated token account exists for `owner`, paid for by `payer`. Returns
+/// the ATA and the creation signature (default if it already existed).
+pub async fn create_ata_for_owner(
+    client: &RpcClient,
+    payer: &dyn Signer,
+    owner: &Pubkey,
 ) -> Result<(Pubkey, Signature)> {
     let token_program_id = &spl_token::ID;
-    let mint             = &tape_api::MINT_ADDRESS;
+    let mint             = &program_context().mint_address();
     let payer_pk         = payer.pubkey();
-    let owner            = &payer_pk;
 
     let ata = spl_associated_token_account::get_associated_token_address_with_program_id(
         owner,
@@ -80,3 +90,73 @@ pub async fn create_ata(
 
     Ok((ata, signature))
 }
+
+/// The TAPE associated token account of `owner`.
+pub fn tape_ata(owner: &Pubkey) -> Pubkey {
+    spl_associated_token_account::get_associated_token_address_with_program_id(
+        owner,
+        &program_context().mint_address(),
+        &spl_token::ID,
+    )
+}
+
+/// The balance of a TAPE token account in base units, `None` if the account doesn't exist.
+pub async fn get_token_balance(client: &RpcClient, token_account: &Pubkey) -> Result<Option<u64>> {
+    let Some(account) = client
+        .get_account_with_commitment(token_account, client.commitment())
+        .await?
+        .value
+    else {
+        return Ok(None);
+    };
+
+    let token = spl_token::state::Account::unpack(&account.data)
+        .map_err(|e| anyhow!("Failed to unpack token account {}: {}", token_account, e))?;
+    let mint_address = program_context().mint_address();
+    if token.mint != mint_address {
+        return Err(anyhow!(
+            "Token account {} holds {}, not TAPE ({})",
+            token_account, token.mint, mint_address
+        ));
+    }
+
+    Ok(Some(token.amount))
+}
+
+/// The TAPE balance of `owner`'s associated token account, `None` if they don't have one.
+pub async fn get_tape_balance(client: &RpcClient, owner: &Pubkey) -> Result<Option<u64>> {
+    get_token_balance(client, &tape_ata(owner)).await
+}
+
+/// Token totals of the program's treasury. Rewards are minted into the treasury each epoch and
+/// paid out from it on claims, so every token outside it is in circulation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TreasuryInfo {
+    pub address: Pubkey,
+    pub balance: u64,
+    pub supply: u64,
+}
+
+impl TreasuryInfo {
+    pub fn circulating(&self) -> u64 {
+        self.supply.saturating_sub(self.balance)
+    }
+}
+
+pub async fn get_treasury_info(client: &RpcClient) -> Result<TreasuryInfo> {
+    let program = program_context();
+    let address = program.treasury_ata_address();
+    let balance = get_token_balance(client, &address)
+        .await?
+        .ok_or_else(|| anyhow!("Treasury token account {} not found", address))?;
+
+    let mint_address = program.mint_address();
+    let mint = client
+        .get_account(&mint_address)
+        .await
+        .map_err(|e| anyhow!("Failed to fetch mint {}: {}", mint_address, e))?;
+    let mint = spl_token::state::Mint::unpack(&mint.data)
+        .map_err(|e| anyhow!("Failed to unpack mint {}: {}", mint_address, e))?;
+
+    Ok(TreasuryInfo { address, balance, supply: mint.supply })
+}
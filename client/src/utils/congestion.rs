@@ -0,0 +1,246 @@
+use anyhow::Result;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{commitment_config::CommitmentConfig, signature::Signature};
+use std::time::{Duration, Instant};
+use tokio::time::sleep;
+
+/// How often in-flight transactions are polled while waiting for room in the window.
+const POLL_INTERVAL: Duration = Duration::from_millis(400);
+
+/// Transactions unconfirmed for this long are assumed dropped (their blockhash has expired).
+const DROP_TIMEOUT: Duration = Duration::from_secs(90);
+
+/// Confirmations slower than this multiple of the fastest one seen count as congestion.
+const LATENCY_FACTOR: u32 = 2;
+
+/// Window shrink factor on a dropped or failed transaction.
+const DECREASE: f64 = 0.5;
+
+/// Window shrink factor on a slow confirmation.
+const SLOW_DECREASE: f64 = 0.9;
+
+/// Additive-increase/multiplicative-decrease (AIMD) control of how many transactions are in
+/// flight at once. The window grows by about one per round of healthy confirmations, halves when
+/// transactions fail or are dropped, and shrinks a little when confirmations slow down, so writes
+/// back off on a congested cluster and ramp up on a healthy one.
+///
+/// An [`ordered`](Self::ordered) window is fixed instead, and only slides past a transaction once
+/// it and every one sent before it have confirmed.
+pub struct Congestion {
+    window: f64,
+    max_in_flight: usize,
+    fastest: Option<Duration>,
+    in_flight: Vec<(Signature, Instant)>,
+    ordered: bool,
+}
+
+impl Congestion {
+    /// Start with a small window that can grow up to `max_in_flight`.
+    pub fn new(max_in_flight: usize) -> Self {
+        let max_in_flight = max_in_flight.max(1);
+        Self {
+            window: (max_in_flight as f64).min(4.0),
+            max_in_flight,
+            fastest: None,
+            in_flight: Vec::new(),
+            ordered: false,
+        }
+    }
+
+    /// A fixed window of `window` transactions that only frees room as the oldest ones confirm,
+    /// so a write lands at most `window - 1` places from the order it was sent in. A window of 1
+    /// keeps the exact order, each write is sent once the one before it has confirmed.
+    pub fn ordered(window: usize) -> Self {
+        let window = window.max(1);
+        Self {
+            window: window as f64,
+            max_in_flight: window,
+            fastest: None,
+            in_flight: Vec::new(),
+            ordered: true,
+        }
+    }
+
+    pub fn is_ordered(&self) -> bool {
+        self.ordered
+    }
+
+    /// The number of transactions currently allowed in flight.
+    pub fn limit(&self) -> usize {
+        (self.window as usize).clamp(1, self.max_in_flight)
+    }
+
+    /// The number of transactions sent but not yet confirmed or dropped.
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.len()
+    }
+
+    /// Record a transaction that was just sent.
+    pub fn sent(&mut self, signature: Signature) {
+        self.in_flight.push((signature, Instant::now()));
+    }
+
+    /// Record a transaction that confirmed after `latency`.
+    pub fn on_confirmed(&mut self, latency: Duration) {
+        if self.ordered {
+            return;
+        }
+
+        let fastest = *self.fastest.get_or_insert(latency);
+        self.fastest = Some(fastest.min(latency));
+
+        if latency > fastest * LATENCY_FACTOR {
+            self.window = (self.window * SLOW_DECREASE).max(1.0);
+        } else {
+            self.window = (self.window + 1.0 / self.window).min(self.max_in_flight as f64);
+        }
+    }
+
+    /// Record a transaction that failed to send, failed on-chain or was dropped.
+    pub fn on_failed(&mut self) {
+        if self.ordered {
+            return;
+        }
+
+        self.window = (self.window * DECREASE).max(1.0);
+    }
+
+    /// Wait until there's room in the window for another transaction. Returns the number of
+    /// in-flight transactions found failed or dropped along the way.
+    pub async fn wait_for_room(&mut self, client: &RpcClient) -> Result<usize> {
+        let mut failed = 0;
+        loop {
+            failed += self.poll(client).await?;
+            if self.in_flight.len() < self.limit() {
+                return Ok(failed);
+            }
+            sleep(POLL_INTERVAL).await;
+        }
+    }
+
+    /// Wait until every in-flight transaction has confirmed, failed or been dropped. Returns the
+    /// number that failed or were dropped.
+    pub async fn drain(&mut self, client: &RpcClient) -> Result<usize> {
+        let mut failed = 0;
+        loop {
+            failed += self.poll(client).await?;
+            if self.in_flight.is_empty() {
+                return Ok(failed);
+            }
+            sleep(POLL_INTERVAL).await;
+        }
+    }
+
+    /// Check on every in-flight transaction and update the window, returning how many failed
+    /// or were dropped.
+    async fn poll(&mut self, client: &RpcClient) -> Result<usize> {
+        if self.in_flight.is_empty() {
+            return Ok(0);
+        }
+
+        let signatures: Vec<Signature> = self.in_flight.iter().map(|(sig, _)| *sig).collect();
+        let mut statuses = Vec::with_capacity(signatures.len());
+
+        // The RPC accepts at most 256 signatures per call
+        for batch in signatures.chunks(256) {
+            statuses.extend(client.get_signature_statuses(batch).await?.value);
+        }
+
+        let mut failed = 0;
+        let mut confirmed = Vec::new();
+        let mut still_in_flight = Vec::new();
+
+        for ((signature, sent_at), status) in std::mem::take(&mut self.in_flight).into_iter().zip(statuses) {
+            let elapsed = sent_at.elapsed();
+            // An ordered window keeps confirmed transactions until the ones before them confirm
+            let blocked = self.ordered && !still_in_flight.is_empty();
+            match status {
+                Some(status) if status.err.is_some() => failed += 1,
+                Some(status) if status.satisfies_commitment(CommitmentConfig::confirmed()) && !blocked => {
+                    confirmed.push(elapsed);
+                }
+                Some(status) if status.satisfies_commitment(CommitmentConfig::confirmed()) => {
+                    still_in_flight.push((signature, sent_at));
+                }
+                _ if elapsed > DROP_TIMEOUT => failed += 1,
+                _ => still_in_flight.push((signature, sent_at)),
+            }
+        }
+
+        for latency in confirmed {
+            self.on_confirmed(latency);
+        }
+
+        // Losses found in the same poll are one congestion event
+        if failed > 0 {
+            self.on_failed();
+        }
+
+        self.in_flight = still_in_flight;
+        Ok(failed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_window_grows_when_healthy() {
+        let mut congestion = Congestion::new(16);
+        assert_eq!(congestion.limit(), 4);
+
+        for _ in 0..100 {
+            congestion.on_confirmed(Duration::from_millis(500));
+        }
+        assert!(congestion.limit() > 4);
+        assert!(congestion.limit() <= 16);
+    }
+
+    #[test]
+    fn test_window_backs_off() {
+        let mut congestion = Congestion::new(16);
+        for _ in 0..200 {
+            congestion.on_confirmed(Duration::from_millis(500));
+        }
+        assert_eq!(congestion.limit(), 16);
+
+        congestion.on_failed();
+        assert_eq!(congestion.limit(), 8);
+
+        // Slow confirmations shrink the window a little
+        congestion.on_confirmed(Duration::from_secs(5));
+        assert_eq!(congestion.limit(), 7);
+
+        for _ in 0..10 {
+            congestion.on_failed();
+        }
+        assert_eq!(congestion.limit(), 1);
+    }
+
+    #[test]
+    fn test_ordered_window_is_fixed() {
+        let mut congestion = Congestion::ordered(3);
+        assert!(congestion.is_ordered());
+        assert_eq!(congestion.limit(), 3);
+
+        for _ in 0..100 {
+            congestion.on_confirmed(Duration::from_millis(500));
+        }
+        congestion.on_failed();
+        assert_eq!(congestion.limit(), 3);
+
+        assert_eq!(Congestion::ordered(0).limit(), 1);
+    }
+
+    #[test]
+    fn test_max_in_flight_caps_window() {
+        let mut congestion = Congestion::new(2);
+        assert_eq!(congestion.limit(), 2);
+
+        for _ in 0..100 {
+            congestion.on_confirmed(Duration::from_millis(500));
+        }
+        assert_eq!(congestion.limit(), 2);
+    }
+}
@@ -0,0 +1,38 @@
+use anyhow::{anyhow, bail, Result};
+use std::sync::OnceLock;
+use tape_api::prelude::ProgramContext;
+
+static PROGRAM_CONTEXT: OnceLock<ProgramContext> = OnceLock::new();
+
+/// The tape program deployment every call in this process goes to. Unless set with
+/// [`set_program_context`], it's read from `TAPE_PROGRAM_ID` on first use, falling back to the
+/// program `tape_api` was built for.
+///
+/// Panics if `TAPE_PROGRAM_ID` isn't a valid pubkey; call [`set_program_context`] with
+/// `ProgramContext::from_env()?` at startup to get an error instead.
+pub fn program_context() -> &'static ProgramContext {
+    PROGRAM_CONTEXT.get_or_init(|| {
+        ProgramContext::from_env()
+            .unwrap_or_else(|e| panic!("Invalid {}: {}", ProgramContext::ENV_VAR, e))
+    })
+}
+
+/// Send every call in this process to another deployment of the tape program, e.g. a fork or a
+/// local test deployment. Fails if a different one is already in use.
+pub fn set_program_context(context: ProgramContext) -> Result<()> {
+    let current = PROGRAM_CONTEXT.get_or_init(|| context);
+    if *current != context {
+        bail!("Already using program {}", current.program_id);
+    }
+
+    Ok(())
+}
+
+/// Parse a program ID override, as passed on the command line.
+pub fn parse_program_context(program_id: &str) -> Result<ProgramContext> {
+    program_id
+        .trim()
+        .parse()
+        .map(ProgramContext::new)
+        .map_err(|e| anyhow!("Invalid program ID {}: {}", program_id, e))
+}
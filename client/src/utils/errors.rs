@@ -0,0 +1,143 @@
+use solana_sdk::pubkey::Pubkey;
+use tape_api::prelude::{InstructionType, TapeError, MINER_DEPOSIT_COOLDOWN, ONE_MINUTE};
+use super::program_context;
+
+/// Find the tape program error a failed transaction's logs end with, if the tape program is what
+/// failed.
+pub fn tape_error_from_logs(logs: &[String]) -> Option<TapeError> {
+    let failed = format!("Program {} failed: custom program error: 0x", program_context().program_id);
+
+    logs.iter()
+        .rev()
+        .find_map(|log| log.strip_prefix(&failed))
+        .and_then(|code| u32::from_str_radix(code.trim(), 16).ok())
+        .and_then(|code| TapeError::try_from(code).ok())
+}
+
+/// Explain a tape program error in plain words, with a hint on what to do about it. Details the
+/// program logged before failing (like the difficulty of a rejected solution) are included when
+/// present.
+pub fn explain_error(error: TapeError, logs: &[String]) -> String {
+    let hint = match error {
+        TapeError::UnknownError => "the program failed without saying why".to_string(),
+        TapeError::UnexpectedState => {
+            "the tape isn't in the state this instruction expects, it may already be finalized".to_string()
+        }
+        TapeError::WriteFailed => {
+            "the data couldn't be written to the tape, check the segment number and size".to_string()
+        }
+        TapeError::SolutionInvalid => {
+            "the proof doesn't match the miner's challenge or recall tape, make sure your archive \
+             holds the recall tape and is caught up"
+                .to_string()
+        }
+        TapeError::SolutionTooEasy => match find_numbers(logs, "Solution difficulty ") {
+            Some([found, target]) => format!(
+                "your solver's difficulty {} is below the epoch target {}",
+                found, target
+            ),
+            _ => "your solver's difficulty is below the epoch target, see `tapedrive get-epoch`".to_string(),
+        },
+        TapeError::SolutionTooEarly => match find_numbers::<1>(logs, "Solution is ") {
+            Some([seconds]) => format!(
+                "proofs are accepted once a minute, try again in {} seconds",
+                seconds
+            ),
+            _ => "proofs are accepted once a minute, try again shortly".to_string(),
+        },
+        TapeError::ClaimTooLarge => {
+            "the amount is more than the miner's unclaimed rewards, see `tapedrive get-miner <miner>`".to_string()
+        }
+        TapeError::StaleEpoch => {
+            "the epoch has ended and hasn't been advanced yet, try again in a minute".to_string()
+        }
+        TapeError::ClockInvalid => "the cluster clock is invalid, try again later".to_string(),
+        TapeError::MaxSupply => "all TAPE has been minted, no more rewards can be paid".to_string(),
+        TapeError::Paused => {
+            "the guardian has paused writes and mining, try again once it resumes".to_string()
+        }
+        TapeError::TapeFull => {
+            "the tape has no room left for this write, split the data across several tapes".to_string()
+        }
+        TapeError::SegmentTooLarge => {
+            "the segment hasn't been written yet, only written segments can be updated".to_string()
+        }
+        TapeError::RewardsLocked => match find_numbers(logs, " of the unclaimed rewards are locked until ") {
+            Some([locked, until]) => format!(
+                "{} of the miner's rewards are locked until unix time {}, see `tapedrive get-miner <miner>`",
+                locked, until
+            ),
+            _ => "part of the miner's rewards are still locked, see `tapedrive get-miner <miner>`".to_string(),
+        },
+        TapeError::DepositLocked => format!(
+            "the deposit is refunded {} days after the miner's last proof, see `tapedrive get-miner <miner>`",
+            MINER_DEPOSIT_COOLDOWN / (24 * 60 * ONE_MINUTE)
+        ),
+    };
+
+    format!("{:?}: {}", error, hint)
+}
+
+/// Name an instruction for error messages, e.g. `tape Write` for the tape program's instructions
+/// and the program ID for anything else.
+pub fn describe_instruction(program_id: &Pubkey, data: &[u8]) -> String {
+    if *program_id != program_context().program_id {
+        return format!("program {}", program_id);
+    }
+
+    match data.first().map(|&d| InstructionType::try_from(d)) {
+        Some(Ok(instruction)) => format!("tape {:?}", instruction),
+        _ => "tape unknown instruction".to_string(),
+    }
+}
+
+/// The first `N` numbers in the last log line containing `marker`.
+fn find_numbers<const N: usize>(logs: &[String], marker: &str) -> Option<[u64; N]> {
+    let line = logs.iter().rev().find(|log| log.contains(marker))?;
+
+    let numbers: Vec<u64> = line
+        .split(|c: char| !c.is_ascii_digit())
+        .filter_map(|word| word.parse().ok())
+        .take(N)
+        .collect();
+
+    numbers.try_into().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn logs(lines: &[&str]) -> Vec<String> {
+        lines.iter().map(|line| line.to_string()).collect()
+    }
+
+    #[test]
+    fn test_tape_error_from_logs() {
+        let failed = logs(&[
+            &format!("Program {} invoke [1]", tape_api::ID),
+            "Program log: Solution difficulty 8 is below the epoch difficulty 10",
+            &format!("Program {} failed: custom program error: 0x4", tape_api::ID),
+        ]);
+        assert_eq!(tape_error_from_logs(&failed), Some(TapeError::SolutionTooEasy));
+        assert_eq!(
+            explain_error(TapeError::SolutionTooEasy, &failed),
+            "SolutionTooEasy: your solver's difficulty 8 is below the epoch target 10"
+        );
+
+        // Errors of other programs aren't ours to explain
+        let other = logs(&["Program Tokenkeg failed: custom program error: 0x4"]);
+        assert_eq!(tape_error_from_logs(&other), None);
+    }
+
+    #[test]
+    fn test_explain_without_details() {
+        assert_eq!(
+            explain_error(TapeError::SolutionTooEarly, &[]),
+            "SolutionTooEarly: proofs are accepted once a minute, try again shortly"
+        );
+
+        let early = logs(&["Program log: Solution is 12 seconds early"]);
+        assert!(explain_error(TapeError::SolutionTooEarly, &early).ends_with("try again in 12 seconds"));
+    }
+}
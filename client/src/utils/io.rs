@@ -24,6 +24,22 @@ pub fn decompress(data: &[u8]) -> Result<Vec<u8>> {
     Ok(decompressed)
 }
 
+/// Decompresses as much as possible, returning everything decoded before the first error.
+pub fn decompress_partial(data: &[u8]) -> Vec<u8> {
+    let mut decoder = GzDecoder::new(data);
+    let mut decompressed = Vec::new();
+    let mut buf = [0u8; 4096];
+
+    loop {
+        match decoder.read(&mut buf) {
+            Ok(0) | Err(_) => break,
+            Ok(n) => decompressed.extend_from_slice(&buf[..n]),
+        }
+    }
+
+    decompressed
+}
+
 pub fn estimate_chunks(data_len: usize) -> usize {
     data_len / SEGMENT_SIZE + if data_len % SEGMENT_SIZE != 0 { 1 } else { 0 }
 }
@@ -0,0 +1,145 @@
+use mime_guess::Mime;
+use tape_api::{consts::NAME_LEN, utils::to_name};
+
+use crate::tape::MimeType;
+
+/// Extensions for common types where the first one `mime_guess` lists isn't the usual one.
+const PREFERRED_EXTENSIONS: &[(&str, &str)] = &[
+    ("text/plain", "txt"),
+    ("text/html", "html"),
+    ("text/javascript", "js"),
+    ("text/markdown", "md"),
+    ("image/jpeg", "jpg"),
+    ("image/tiff", "tiff"),
+    ("audio/mpeg", "mp3"),
+    ("video/mp4", "mp4"),
+    ("video/mpeg", "mpeg"),
+    ("application/x-yaml", "yaml"),
+];
+
+/// The usual file extension for a MIME type, or `bin` if there's none.
+pub fn get_extension(mime: &str) -> &'static str {
+    if let Some((_, ext)) = PREFERRED_EXTENSIONS.iter().find(|(m, _)| *m == mime) {
+        return ext;
+    }
+
+    mime_guess::get_mime_extensions_str(mime)
+        .and_then(|exts| exts.first().copied())
+        .unwrap_or("bin")
+}
+
+/// A tape name made safe to use in a file name.
+pub fn file_stem(name: &[u8; NAME_LEN]) -> String {
+    let name: String = String::from_utf8_lossy(name)
+        .trim_end_matches('\0')
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect();
+
+    if name.is_empty() { "tape".to_string() } else { name }
+}
+
+/// A file name for a tape's data, its name with the extension of its MIME type.
+pub fn file_name(name: &[u8; NAME_LEN], mime: &str) -> String {
+    format!("{}.{}", file_stem(name), get_extension(mime))
+}
+
+/// The header's `MimeType` for a MIME type, with the `mime_str` to go with it: all zeros for
+/// known types, or a null-padded lowercase copy of the full MIME string for `Custom` ones.
+pub fn mime_to_type(mime: &Mime) -> (MimeType, [u8; NAME_LEN]) {
+
+    let empty = [0u8; NAME_LEN];
+    let (t, s) = (mime.type_().as_str(), mime.subtype().as_str());
+    let t = t.to_ascii_lowercase();
+    let s = s.to_ascii_lowercase();
+
+    let code: MimeType = match (t.as_str(), s.as_str()) {
+        // Unknown or octet-stream -> keep Unknown (0)
+        ("application", "octet-stream") => MimeType::Unknown,
+
+        // Image formats
+        ("image", "png") => MimeType::ImagePng,
+        ("image", "jpeg") | ("image", "jpg") => MimeType::ImageJpeg,
+        ("image", "gif") => MimeType::ImageGif,
+        ("image", "webp") => MimeType::ImageWebp,
+        ("image", "bmp") => MimeType::ImageBmp,
+        ("image", "tiff") | ("image", "tif") => MimeType::ImageTiff,
+
+        // Document formats
+        ("application", "pdf") => MimeType::ApplicationPdf,
+        ("application", "msword") => MimeType::ApplicationMsword,
+        ("application", "vnd.openxmlformats-officedocument.wordprocessingml.document") => MimeType::ApplicationDocx,
+        ("application", "vnd.oasis.opendocument.text") => MimeType::ApplicationOdt,
+
+        // Text formats
+        ("text", "plain") => MimeType::TextPlain,
+        ("text", "html") => MimeType::TextHtml,
+        ("text", "css") => MimeType::TextCss,
+        ("text", "javascript") | ("application", "javascript") => MimeType::TextJavascript,
+        ("text", "csv") => MimeType::TextCsv,
+        ("text", "markdown") | ("text", "md") => MimeType::TextMarkdown,
+
+        // Audio formats
+        ("audio", "mpeg") | ("audio", "mp3") => MimeType::AudioMpeg,
+        ("audio", "wav") => MimeType::AudioWav,
+        ("audio", "ogg") => MimeType::AudioOgg,
+        ("audio", "flac") => MimeType::AudioFlac,
+
+        // Video formats
+        ("video", "mp4") => MimeType::VideoMp4,
+        ("video", "webm") => MimeType::VideoWebm,
+        ("video", "mpeg") => MimeType::VideoMpeg,
+        ("video", "x-msvideo") | ("video", "avi") => MimeType::VideoAvi,
+
+        // Application formats
+        ("application", "json") => MimeType::ApplicationJson,
+        ("application", "xml") | ("text", "xml") => MimeType::ApplicationXml,
+        ("application", "zip") => MimeType::ApplicationZip,
+        ("application", "gzip") | ("application", "x-gzip") => MimeType::ApplicationGzip,
+        ("application", "x-tar") | ("application", "tar") => MimeType::ApplicationTar,
+
+        // Font formats
+        ("font", "woff") => MimeType::FontWoff,
+        ("font", "woff2") => MimeType::FontWoff2,
+        ("font", "ttf") | ("application", "font-sfnt") => MimeType::FontTtf,
+        ("font", "otf") => MimeType::FontOtf,
+
+        // Miscellaneous
+        ("application", "rtf") => MimeType::ApplicationRtf,
+        ("application", "sql") => MimeType::ApplicationSql,
+        ("application", "x-yaml") | ("text", "yaml") => MimeType::ApplicationYaml,
+
+        // Everything else -> mark as Custom
+        _ => {
+            let full_mime = mime.as_ref().to_ascii_lowercase();
+            // Too long for the header, a truncated type would be wrong
+            if full_mime.len() > NAME_LEN {
+                return (MimeType::Unknown, empty);
+            }
+            return (MimeType::Custom, to_name(&full_mime));
+        }
+    };
+
+    (code, empty)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_name() {
+        assert_eq!(file_name(&to_name("holiday photo"), "image/png"), "holiday_photo.png");
+        assert_eq!(file_name(&to_name("../etc/passwd"), "text/plain"), "___etc_passwd.txt");
+        assert_eq!(file_name(&to_name("song"), "audio/mpeg"), "song.mp3");
+        assert_eq!(file_name(&[0; NAME_LEN], "application/x-unknown"), "tape.bin");
+    }
+
+    #[test]
+    fn test_mime_to_type() {
+        let mime = |s: &str| s.parse::<Mime>().unwrap();
+        assert_eq!(mime_to_type(&mime("image/PNG")), (MimeType::ImagePng, [0; NAME_LEN]));
+        assert_eq!(mime_to_type(&mime("application/octet-stream")).0, MimeType::Unknown);
+        assert_eq!(mime_to_type(&mime("model/gltf+json")), (MimeType::Custom, to_name("model/gltf+json")));
+    }
+}
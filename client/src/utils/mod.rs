@@ -1,11 +1,53 @@
+#[cfg(feature = "rpc")]
 mod account;
-mod rpc; 
+#[cfg(feature = "rpc")]
+mod rpc;
+#[cfg(feature = "encoding")]
 mod io;
+#[cfg(feature = "rpc")]
 mod retry;
+#[cfg(feature = "rpc")]
 mod ata;
+#[cfg(feature = "rpc")]
+mod signer;
+#[cfg(feature = "rpc")]
+mod congestion;
+mod errors;
+mod amount;
+#[cfg(feature = "rpc")]
+mod signing;
+#[cfg(feature = "rpc")]
+mod relayer;
+mod context;
+#[cfg(feature = "encoding")]
+mod mime;
+mod name;
+#[cfg(feature = "rpc")]
+mod airdrop;
 
+#[cfg(feature = "rpc")]
 pub use account::*;
+#[cfg(feature = "rpc")]
 pub use rpc::*;
+#[cfg(feature = "encoding")]
 pub use io::*;
+#[cfg(feature = "rpc")]
 pub use retry::*;
+#[cfg(feature = "rpc")]
 pub use ata::*;
+#[cfg(feature = "rpc")]
+pub use signer::*;
+#[cfg(feature = "rpc")]
+pub use congestion::*;
+pub use errors::*;
+pub use amount::*;
+#[cfg(feature = "rpc")]
+pub use signing::*;
+#[cfg(feature = "rpc")]
+pub use relayer::*;
+pub use context::*;
+#[cfg(feature = "encoding")]
+pub use mime::*;
+pub use name::*;
+#[cfg(feature = "rpc")]
+pub use airdrop::*;
@@ -0,0 +1,64 @@
+use anyhow::{bail, Result};
+use tape_api::consts::NAME_LEN;
+use tape_api::utils::padded_array;
+
+/// Characters that render as nothing or reorder the text around them, so two names that look
+/// the same can point at different tapes.
+fn is_invisible(c: char) -> bool {
+    matches!(c,
+        '\u{00AD}'              | // soft hyphen
+        '\u{200B}'..='\u{200F}' | // zero-width spaces, joiners and direction marks
+        '\u{202A}'..='\u{202E}' | // bidi embeddings and overrides
+        '\u{2060}'..='\u{2064}' | // word joiner and invisible operators
+        '\u{2066}'..='\u{2069}' | // bidi isolates
+        '\u{FEFF}'                // byte order mark
+    )
+}
+
+/// The form names are stored in: trimmed, with every run of whitespace turned into one space.
+pub fn normalize_name(name: &str) -> String {
+    name.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Check `name` can name a tape or miner, and pad it to the on-chain field. Names are at most
+/// `NAME_LEN` bytes of UTF-8, already normalized (see `normalize_name`), without control or
+/// invisible characters.
+pub fn check_name(name: &str) -> Result<[u8; NAME_LEN]> {
+    if name.is_empty() {
+        bail!("Name is empty");
+    }
+    if name.len() > NAME_LEN {
+        bail!("Name {:?} is {} bytes, names hold at most {}", name, name.len(), NAME_LEN);
+    }
+    if let Some(c) = name.chars().find(|&c| c.is_control() || is_invisible(c)) {
+        bail!("Name {:?} holds the unprintable character U+{:04X}", name, c as u32);
+    }
+    if normalize_name(name) != name {
+        bail!("Name {:?} has leading, trailing or repeated whitespace", name);
+    }
+
+    Ok(padded_array::<NAME_LEN>(name.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_name() {
+        assert_eq!(normalize_name("  holiday \t photos\n"), "holiday photos");
+
+        assert!(check_name("holiday photos").is_ok());
+        assert!(check_name("photos.0").is_ok());
+        assert!(check_name("фото").is_ok());
+        assert_eq!(check_name(&"a".repeat(NAME_LEN)).unwrap(), [b'a'; NAME_LEN]);
+
+        assert!(check_name("").is_err());
+        assert!(check_name(&"a".repeat(NAME_LEN + 1)).is_err());
+        assert!(check_name(&"é".repeat(17)).is_err());
+        assert!(check_name("a\0b").is_err());
+        assert!(check_name("pay\u{202E}fdp.exe").is_err());
+        assert!(check_name("a\u{200B}").is_err());
+        assert!(check_name(" photos").is_err());
+    }
+}
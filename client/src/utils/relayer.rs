@@ -0,0 +1,108 @@
+use anyhow::{anyhow, Result};
+use reqwest::Client as HttpClient;
+use serde_json::{json, Value};
+use solana_sdk::{
+    hash::Hash,
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::Signature,
+    signer::Signer,
+    transaction::Transaction,
+};
+use std::str::FromStr;
+
+use super::signer::json_rpc;
+
+/// A relayer that pays the fees of transactions it's sent, so users can write tapes without
+/// holding SOL.
+///
+/// The relayer speaks JSON-RPC 2.0 over HTTP POST, with three methods:
+/// - `getFeePayer` (no params) -> base-58 pubkey transactions must be paid by
+/// - `signTransaction` (`{"transaction": <base64>}`) -> `{"transaction": <base64>}`, with the
+///   fee payer's signature added
+/// - `sendTransaction` (`{"transaction": <base64>}`) -> base-58 signature of the transaction,
+///   signed and sent by the relayer
+///
+/// Transactions are bincode-serialized legacy transactions, paid by the relayer's fee payer and
+/// already signed by everyone else. A relayer refuses anything its policy doesn't pay for, see
+/// `tape_network::relay` for the reference one.
+pub struct Relayer {
+    url: String,
+    fee_payer: Pubkey,
+    http: HttpClient,
+}
+
+impl Relayer {
+    /// Connect to a relayer and fetch the fee payer it signs with.
+    pub async fn connect(url: &str) -> Result<Self> {
+        let http = HttpClient::new();
+        let result = json_rpc(&http, url, "Relayer", "getFeePayer", json!({})).await?;
+        let fee_payer = result
+            .as_str()
+            .ok_or_else(|| anyhow!("Relayer returned an invalid fee payer"))
+            .and_then(|s| Pubkey::from_str(s).map_err(|e| anyhow!("Invalid fee payer: {}", e)))?;
+
+        Ok(Self {
+            url: url.to_string(),
+            fee_payer,
+            http,
+        })
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn fee_payer(&self) -> Pubkey {
+        self.fee_payer
+    }
+
+    /// A transaction paid by the relayer, signed by `signers` but not yet by the fee payer.
+    pub fn build(&self, instructions: &[Instruction], signers: &[&dyn Signer], blockhash: Hash) -> Result<Transaction> {
+        let mut tx = Transaction::new_with_payer(instructions, Some(&self.fee_payer));
+        tx.try_partial_sign(signers, blockhash)
+            .map_err(|e| anyhow!("Failed to sign transaction: {}", e))?;
+        Ok(tx)
+    }
+
+    /// Have the relayer add the fee payer's signature, returning the fully signed transaction to
+    /// send yourself.
+    pub async fn sign(&self, tx: &Transaction) -> Result<Transaction> {
+        let params = json!({ "transaction": encode_transaction(tx)? });
+        let result = json_rpc(&self.http, &self.url, "Relayer", "signTransaction", params).await?;
+        let signed = result
+            .get("transaction")
+            .and_then(Value::as_str)
+            .ok_or_else(|| anyhow!("Relayer returned no transaction"))
+            .and_then(decode_transaction)?;
+
+        // The relayer may only add its own signature
+        if signed.message != tx.message {
+            return Err(anyhow!("Relayer changed the transaction"));
+        }
+        signed.verify().map_err(|e| anyhow!("Relayer returned an invalid signature: {}", e))?;
+
+        Ok(signed)
+    }
+
+    /// Have the relayer sign and send the transaction, returning its signature. The relayer
+    /// doesn't wait for it to confirm.
+    pub async fn send(&self, tx: &Transaction) -> Result<Signature> {
+        let params = json!({ "transaction": encode_transaction(tx)? });
+        let result = json_rpc(&self.http, &self.url, "Relayer", "sendTransaction", params).await?;
+        result
+            .as_str()
+            .ok_or_else(|| anyhow!("Relayer returned an invalid signature"))
+            .and_then(|s| Signature::from_str(s).map_err(|e| anyhow!("Invalid signature: {}", e)))
+    }
+}
+
+/// A transaction as relayers take it, base64 of its bincode serialization.
+pub fn encode_transaction(tx: &Transaction) -> Result<String> {
+    Ok(base64::encode(bincode::serialize(tx)?))
+}
+
+pub fn decode_transaction(data: &str) -> Result<Transaction> {
+    let bytes = base64::decode(data).map_err(|e| anyhow!("Invalid base64 transaction: {}", e))?;
+    bincode::deserialize(&bytes).map_err(|e| anyhow!("Invalid transaction: {}", e))
+}
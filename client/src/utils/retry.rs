@@ -7,6 +7,8 @@ use solana_client::{
 use solana_sdk::signature::Signature;
 use tokio::time::Duration;
 
+use super::errors::{explain_error, tape_error_from_logs};
+
 const MAX_RETRIES: u32 = 8;
 const INITIAL_BACKOFF: u64 = 200;
 const TIMEOUT: Duration = Duration::from_secs(8);
@@ -28,16 +30,16 @@ where
                 return Ok(result);
             }
             Ok(Err(e)) if attempt == MAX_RETRIES - 1 => {
-                println!("Attempt {} failed with error: {:?}", attempt + 1, e);
+                eprintln!("Attempt {} failed with error: {:?}", attempt + 1, e);
                 return Err(e);
             }
             Err(_) if attempt == MAX_RETRIES - 1 => {
-                println!("Attempt {} timed out after {:?}", attempt + 1, TIMEOUT);
+                eprintln!("Attempt {} timed out after {:?}", attempt + 1, TIMEOUT);
                 return Err(anyhow::anyhow!("Retry failed"));
             }
             _ => {
-                println!("Attempt {} failed, retrying after backoff", attempt + 1);
-                println!("Waiting for backoff: {:?}", backoff);
+                eprintln!("Attempt {} failed, retrying after backoff", attempt + 1);
+                eprintln!("Waiting for backoff: {:?}", backoff);
 
                 tokio::time::sleep(backoff).await;
                 backoff *= 2; // Exponential backoff
@@ -48,7 +50,8 @@ where
     Err(anyhow::anyhow!("All retry attempts failed"))
 }
 
-/// Handles transaction simulation logs for failed transactions.
+/// Handles transaction simulation logs for failed transactions. Tape program errors are
+/// explained in plain words, anything else prints the simulation logs.
 pub fn with_logs(res: ClientResult<Signature>) -> Result<Signature> {
     match res {
         Ok(signature) => Ok(signature),
@@ -60,6 +63,10 @@ pub fn with_logs(res: ClientResult<Signature>) -> Result<Signature> {
                 if let solana_client::rpc_request::RpcResponseErrorData::SendTransactionPreflightFailure(
                     RpcSimulateTransactionResult { logs: Some(logs), .. }
                 ) = data {
+                    if let Some(error) = tape_error_from_logs(logs) {
+                        return Err(anyhow!("Transaction failed: {}", explain_error(error, logs)));
+                    }
+
                     eprintln!("Transaction simulation failed:");
                     for log in logs {
                         eprintln!("  {}", log);
@@ -3,30 +3,101 @@ use base64;
 use solana_client::{
     nonblocking::rpc_client::RpcClient,
     rpc_client::GetConfirmedSignaturesForAddress2Config,
-    rpc_config::{RpcBlockConfig, RpcProgramAccountsConfig, RpcTransactionConfig},
+    rpc_config::{RpcProgramAccountsConfig, RpcSendTransactionConfig, RpcTransactionConfig},
     rpc_response::RpcConfirmedTransactionStatusWithSignature,
 };
 
+#[cfg(feature = "block-parsing")]
+use solana_client::rpc_config::RpcBlockConfig;
+#[cfg(feature = "block-parsing")]
 use solana_transaction_status::UiConfirmedBlock;
+#[cfg(feature = "block-parsing")]
 use solana_transaction_status_client_types::TransactionDetails;
 use solana_sdk::{
     account::Account,
+    address_lookup_table::AddressLookupTableAccount,
     commitment_config::CommitmentConfig,
     hash::Hash,
     instruction::Instruction,
+    message::{v0, VersionedMessage},
     pubkey::Pubkey,
-    signature::{Keypair, Signature, Signer},
-    transaction::{Transaction, VersionedTransaction},
+    signature::{Signature, Signer},
+    transaction::{Transaction, TransactionError, VersionedTransaction},
 };
+use std::sync::atomic::{AtomicBool, Ordering};
 
 use solana_transaction_status::{EncodedConfirmedTransactionWithStatusMeta, UiTransactionEncoding};
 use tokio::time::{sleep, Duration};
 
-use crate::utils::{deserialize, serialize, retry, with_logs};
+use crate::utils::{deserialize, serialize, program_context, retry, with_logs, describe_instruction, explain_error, tape_error_from_logs};
 
 /// Initial backoff duration for retries (milliseconds).
 const INITIAL_BACKOFF: u64 = 200;
 
+/// Whether transactions are simulated before they're sent, on by default.
+static SIMULATE: AtomicBool = AtomicBool::new(true);
+
+/// Turn simulating transactions before sending them on or off. With it off, transactions are
+/// sent without any preflight check at all.
+pub fn set_simulation(enabled: bool) {
+    SIMULATE.store(enabled, Ordering::Relaxed);
+}
+
+fn simulation_enabled() -> bool {
+    SIMULATE.load(Ordering::Relaxed)
+}
+
+/// Simulates a transaction, and on failure prints the program logs and the instruction that
+/// failed, so a doomed transaction never costs a fee.
+pub async fn simulate(client: &RpcClient, tx: &VersionedTransaction) -> Result<()> {
+    let result = client
+        .simulate_transaction(tx)
+        .await
+        .map_err(|e| anyhow!("Failed to simulate transaction: {}", e))?
+        .value;
+
+    let Some(err) = result.err else {
+        return Ok(());
+    };
+
+    let logs = result.logs.unwrap_or_default();
+    if let Some(error) = tape_error_from_logs(&logs) {
+        return Err(anyhow!("Simulation failed: {}", explain_error(error, &logs)));
+    }
+
+    eprintln!("Transaction simulation failed:");
+    for log in &logs {
+        eprintln!("  {}", log);
+    }
+
+    if let TransactionError::InstructionError(index, ref ix_err) = err {
+        let message = &tx.message;
+        if let Some(ix) = message.instructions().get(index as usize) {
+            let program_id = message.static_account_keys()[ix.program_id_index as usize];
+            eprintln!(
+                "Instruction {} ({}) failed: {}",
+                index,
+                describe_instruction(&program_id, &ix.data),
+                ix_err
+            );
+        }
+    }
+
+    Err(anyhow!("Simulation failed: {}", err))
+}
+
+/// Simulates a transaction and only sends it if the simulation succeeded. The RPC node's own
+/// preflight check is skipped since it would repeat the simulation.
+pub async fn simulate_and_send(client: &RpcClient, tx: &Transaction) -> Result<Signature> {
+    simulate(client, &VersionedTransaction::from(tx.clone())).await?;
+
+    let config = RpcSendTransactionConfig {
+        skip_preflight: true,
+        ..Default::default()
+    };
+    with_logs(client.send_transaction_with_config(tx, config).await)
+}
+
 /// Returns the default transaction configuration for RPC calls.
 pub fn rpc_tx_config() -> RpcTransactionConfig {
     RpcTransactionConfig {
@@ -38,12 +109,24 @@ pub fn rpc_tx_config() -> RpcTransactionConfig {
 
 /// Sends a transaction and returns its serialized signature.
 pub async fn send_transaction(client: &RpcClient, tx: &Transaction) -> Result<Vec<u8>> {
-    let signature: Signature = with_logs(client.send_transaction(tx).await)?;
+    let signature: Signature = if simulation_enabled() {
+        simulate_and_send(client, tx).await?
+    } else {
+        let config = RpcSendTransactionConfig {
+            skip_preflight: true,
+            ..Default::default()
+        };
+        with_logs(client.send_transaction_with_config(tx, config).await)?
+    };
     serialize(&signature)
 }
 
 /// Sends and confirms a transaction, returning its serialized signature.
 pub async fn send_and_confirm_transaction(client: &RpcClient, tx: &Transaction) -> Result<Vec<u8>> {
+    if simulation_enabled() {
+        simulate(client, &VersionedTransaction::from(tx.clone())).await?;
+    }
+
     let signature: Signature = with_logs(client.send_and_confirm_transaction(tx).await)?;
     serialize(&signature)
 }
@@ -86,7 +169,7 @@ pub async fn send_and_confirm(client: &RpcClient, tx: &Transaction) -> Result<Si
 pub async fn send_with_retry(
     client: &RpcClient,
     instruction: &Instruction,
-    payer: &Keypair,
+    payer: &dyn Signer,
     max_retries: u32,
 ) -> Result<Signature> {
     let mut attempts = 0;
@@ -107,7 +190,7 @@ pub async fn send_with_retry(
                 attempts += 1;
                 let delay_ms = INITIAL_BACKOFF * (1 << attempts);
 
-                println!(
+                eprintln!(
                     "DEBUG: send_with_retry attempt {}/{}, waiting {}ms: {}",
                     attempts, max_retries, delay_ms, e
                 );
@@ -126,6 +209,104 @@ pub async fn send_with_retry(
     }
 }
 
+/// Sends a transaction that was signed ahead of time, with the same retry logic as
+/// [`send_with_retry`]. Every retry signs it again through `resign` with a fresh blockhash, so
+/// the signature that lands can differ from the one it was first signed with. Returns the one
+/// that landed.
+pub async fn send_signed_with_retry<F, Fut>(
+    client: &RpcClient,
+    tx: Transaction,
+    resign: F,
+    max_retries: u32,
+) -> Result<Signature>
+where
+    F: Fn(Hash) -> Fut,
+    Fut: std::future::Future<Output = Result<Transaction>>,
+{
+    let mut tx = tx;
+    let mut attempts = 0;
+    loop {
+        match send(client, &tx).await {
+            Ok(signature) => return Ok(signature),
+            Err(e) if attempts < max_retries => {
+                attempts += 1;
+                let delay_ms = INITIAL_BACKOFF * (1 << attempts);
+
+                eprintln!(
+                    "DEBUG: send_signed_with_retry attempt {}/{}, waiting {}ms: {}",
+                    attempts, max_retries, delay_ms, e
+                );
+
+                sleep(Duration::from_millis(delay_ms)).await;
+
+                let blockhash_bytes = get_latest_blockhash(client).await?;
+                tx = resign(deserialize(&blockhash_bytes)?).await?;
+            }
+            Err(e) => {
+                return Err(anyhow!(
+                    "Failed to send transaction after {} attempts: {}",
+                    max_retries,
+                    e
+                ))
+            }
+        }
+    }
+}
+
+/// Sends a v0 transaction that loads accounts from the given lookup tables, with retry logic,
+/// returning its signature.
+pub async fn send_versioned_with_retry(
+    client: &RpcClient,
+    instruction: &Instruction,
+    payer: &dyn Signer,
+    lookup_tables: &[AddressLookupTableAccount],
+    max_retries: u32,
+) -> Result<Signature> {
+    let mut attempts = 0;
+    loop {
+        let blockhash_bytes = get_latest_blockhash(client).await?;
+        let recent_blockhash = deserialize(&blockhash_bytes)?;
+
+        let message = v0::Message::try_compile(
+            &payer.pubkey(),
+            &[instruction.clone()],
+            lookup_tables,
+            recent_blockhash,
+        )
+        .map_err(|e| anyhow!("Failed to compile v0 message: {}", e))?;
+
+        let tx = VersionedTransaction::try_new(VersionedMessage::V0(message), &[payer])
+            .map_err(|e| anyhow!("Failed to sign v0 transaction: {}", e))?;
+
+        if simulation_enabled() {
+            simulate(client, &tx).await?;
+        }
+
+        match with_logs(client.send_transaction(&tx).await) {
+            Ok(signature) => return Ok(signature),
+            Err(e) if attempts < max_retries => {
+                attempts += 1;
+                let delay_ms = INITIAL_BACKOFF * (1 << attempts);
+
+                eprintln!(
+                    "DEBUG: send_versioned_with_retry attempt {}/{}, waiting {}ms: {}",
+                    attempts, max_retries, delay_ms, e
+                );
+
+                sleep(Duration::from_millis(delay_ms)).await;
+                continue;
+            }
+            Err(e) => {
+                return Err(anyhow!(
+                    "Failed to send transaction after {} attempts: {}",
+                    max_retries,
+                    e
+                ))
+            }
+        }
+    }
+}
+
 /// Fetches a transaction with retry logic, returning the deserialized transaction.
 pub async fn get_transaction_with_retry(
     client: &RpcClient,
@@ -139,7 +320,7 @@ pub async fn get_transaction_with_retry(
             Err(e) if attempts < max_retries => {
                 attempts += 1;
                 let delay_ms = INITIAL_BACKOFF * (1 << attempts);
-                println!(
+                eprintln!(
                     "DEBUG: get_transaction_with_retry attempt {}/{}, waiting {}ms: {}",
                     attempts, max_retries, delay_ms, e
                 );
@@ -170,12 +351,13 @@ pub async fn get_program_account(
     config: RpcProgramAccountsConfig,
 ) -> Result<Vec<(Pubkey, Account)>> {
     client
-        .get_program_accounts_with_config(&tape_api::ID, config)
+        .get_program_accounts_with_config(&program_context().program_id, config)
         .await
         .map_err(|e| anyhow!("Failed to fetch program accounts: {}", e))
 }
 
 /// Fetches a block by slot number with retry logic, using the specified transaction details.
+#[cfg(feature = "block-parsing")]
 pub async fn get_block_by_number(
     client: &RpcClient,
     slot_number: u64,
@@ -0,0 +1,101 @@
+use anyhow::{anyhow, Result};
+use std::str::FromStr;
+use reqwest::Client as HttpClient;
+use serde_json::{json, Value};
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::Signature,
+    signer::{Signer, SignerError},
+};
+use tokio::runtime::Handle;
+
+/// A signer that forwards messages to a remote service, so authority keys can live on
+/// another machine.
+///
+/// The service speaks JSON-RPC 2.0 over HTTP POST, with two methods:
+/// - `getPubkey` (no params) -> base-58 pubkey of the key it signs with
+/// - `signMessage` (`{"message": <base64>}`) -> base-58 signature over the message
+///
+/// Every returned signature is verified against the pubkey before it's used.
+///
+/// Signing blocks on the current tokio runtime, which must be multi-threaded.
+pub struct RemoteSigner {
+    url: String,
+    pubkey: Pubkey,
+    http: HttpClient,
+}
+
+impl RemoteSigner {
+    /// Connect to a remote signer and fetch the pubkey it signs for.
+    pub async fn connect(url: &str) -> Result<Self> {
+        let http = HttpClient::new();
+        let result = json_rpc(&http, url, "Remote signer", "getPubkey", json!({})).await?;
+        let pubkey = result
+            .as_str()
+            .ok_or_else(|| anyhow!("Remote signer returned an invalid pubkey"))
+            .and_then(|s| Pubkey::from_str(s).map_err(|e| anyhow!("Invalid remote pubkey: {}", e)))?;
+
+        Ok(Self {
+            url: url.to_string(),
+            pubkey,
+            http,
+        })
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    async fn sign(&self, message: &[u8]) -> Result<Signature> {
+        let params = json!({ "message": base64::encode(message) });
+        let result = json_rpc(&self.http, &self.url, "Remote signer", "signMessage", params).await?;
+        let signature = result
+            .as_str()
+            .ok_or_else(|| anyhow!("Remote signer returned an invalid signature"))
+            .and_then(|s| Signature::from_str(s).map_err(|e| anyhow!("Invalid remote signature: {}", e)))?;
+
+        if !signature.verify(self.pubkey.as_ref(), message) {
+            return Err(anyhow!("Remote signature doesn't match {}", self.pubkey));
+        }
+
+        Ok(signature)
+    }
+}
+
+impl Signer for RemoteSigner {
+    fn try_pubkey(&self) -> Result<Pubkey, SignerError> {
+        Ok(self.pubkey)
+    }
+
+    fn try_sign_message(&self, message: &[u8]) -> Result<Signature, SignerError> {
+        tokio::task::block_in_place(|| Handle::current().block_on(self.sign(message)))
+            .map_err(|e| SignerError::Connection(e.to_string()))
+    }
+
+    fn is_interactive(&self) -> bool {
+        false
+    }
+}
+
+/// Call a JSON-RPC 2.0 method on a service such as a remote signer or a relayer, named `service`
+/// in errors.
+pub(crate) async fn json_rpc(http: &HttpClient, url: &str, service: &str, method: &str, params: Value) -> Result<Value> {
+    let resp = http.post(url)
+        .header("Content-Type", "application/json")
+        .body(json!({
+            "jsonrpc": "2.0", "id": 1,
+            "method": method,
+            "params": params
+        }).to_string())
+        .send().await
+        .map_err(|e| anyhow!("{} {} unreachable: {}", service, url, e))?
+        .json::<Value>().await?;
+
+    if let Some(error) = resp.get("error") {
+        return Err(anyhow!("{} {} failed: {}", service, method, error));
+    }
+
+    resp.get("result")
+        .cloned()
+        .ok_or_else(|| anyhow!("{} {} returned no result", service, method))
+}
@@ -0,0 +1,118 @@
+use anyhow::{anyhow, Result};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{
+    hash::Hash,
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    transaction::Transaction,
+};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Semaphore;
+
+use crate::utils::{deserialize, get_latest_blockhash};
+
+/// Most threads a pool signs on by default.
+const DEFAULT_WORKERS: usize = 4;
+
+/// A few threads that build and sign transactions for a local keypair. Ed25519 signing is CPU
+/// bound, so doing it for thousands of writes on the async tasks sending them holds up their IO.
+///
+/// Only local keypairs can sign on a pool. Hardware wallets and remote signers sign one message
+/// at a time anyway, and keep signing inline.
+#[derive(Clone)]
+pub struct SigningPool {
+    keypair: Arc<Keypair>,
+    workers: Arc<Semaphore>,
+}
+
+impl SigningPool {
+    /// A pool signing with `keypair` on up to `workers` threads at once.
+    pub fn new(keypair: Keypair, workers: usize) -> Self {
+        Self {
+            keypair: Arc::new(keypair),
+            workers: Arc::new(Semaphore::new(workers.max(1))),
+        }
+    }
+
+    /// A pool with one worker per core, up to 4.
+    pub fn with_default_workers(keypair: Keypair) -> Self {
+        let cores = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+        Self::new(keypair, cores.min(DEFAULT_WORKERS))
+    }
+
+    pub fn pubkey(&self) -> Pubkey {
+        self.keypair.pubkey()
+    }
+
+    /// Build and sign a transaction paid for by the pool's keypair, off the async runtime.
+    pub async fn sign(&self, instructions: Vec<Instruction>, blockhash: Hash) -> Result<Transaction> {
+        let _permit = self.workers.acquire().await?;
+        let keypair = Arc::clone(&self.keypair);
+
+        tokio::task::spawn_blocking(move || {
+            Transaction::new_signed_with_payer(
+                &instructions,
+                Some(&keypair.pubkey()),
+                &[keypair.as_ref()],
+                blockhash,
+            )
+        })
+        .await
+        .map_err(|e| anyhow!("Signing worker failed: {}", e))
+    }
+}
+
+/// The latest blockhash, fetched again once it's `max_age` old. A blockhash stays valid for
+/// about a minute, so writes signed in quick succession can share one instead of each asking
+/// the RPC.
+pub struct BlockhashCache {
+    max_age: Duration,
+    latest: Option<(Hash, Instant)>,
+}
+
+impl BlockhashCache {
+    pub fn new(max_age: Duration) -> Self {
+        Self { max_age, latest: None }
+    }
+
+    pub async fn get(&mut self, client: &RpcClient) -> Result<Hash> {
+        match self.latest {
+            Some((hash, at)) if at.elapsed() < self.max_age => Ok(hash),
+            _ => {
+                let hash: Hash = deserialize(&get_latest_blockhash(client).await?)?;
+                self.latest = Some((hash, Instant::now()));
+                Ok(hash)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::system_instruction;
+
+    #[tokio::test]
+    async fn test_sign_on_pool() -> Result<()> {
+        let keypair = Keypair::new();
+        let expected_payer = keypair.pubkey();
+        let pool = SigningPool::new(keypair, 2);
+        let blockhash = Hash::new_unique();
+
+        let ix = system_instruction::transfer(&expected_payer, &Pubkey::new_unique(), 1);
+        let signed = futures::future::try_join_all(
+            (0..8).map(|_| pool.sign(vec![ix.clone()], blockhash)),
+        )
+        .await?;
+
+        for tx in signed {
+            tx.verify()?;
+            assert_eq!(tx.message.account_keys[0], expected_payer);
+            assert_eq!(tx.message.recent_blockhash, blockhash);
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,256 @@
+use anyhow::{anyhow, Result};
+use solana_sdk::pubkey::Pubkey;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::store::TapeLabel;
+
+/// Environment variable holding the token admin methods on the web node are authenticated with.
+/// Admin methods are disabled when it isn't set.
+pub const ADMIN_TOKEN_ENV: &str = "TAPEDRIVE_ADMIN_TOKEN";
+
+/// Settings for the admin methods of a web node.
+#[derive(Clone)]
+pub struct AdminConfig {
+    /// Callers must send `Authorization: Bearer <token>`.
+    pub token: String,
+    pub namespace: String,
+}
+
+impl AdminConfig {
+    /// Admin settings from the environment, `None` if no token is set.
+    pub fn from_env(namespace: &str) -> Option<Self> {
+        let token = env::var(ADMIN_TOKEN_ENV).ok()?;
+        if token.is_empty() {
+            return None;
+        }
+
+        Some(Self { token, namespace: namespace.to_string() })
+    }
+
+    /// Whether an `Authorization` header carries the admin token.
+    pub fn authorize(&self, authorization: Option<&str>) -> bool {
+        let Some(token) = authorization.and_then(|h| h.strip_prefix("Bearer ")) else {
+            return false;
+        };
+
+        tokens_match(token.trim(), &self.token)
+    }
+}
+
+/// Whether a token a caller sent is the expected one. Compares every byte, so the time taken
+/// doesn't leak how much of the token matched.
+pub(crate) fn tokens_match(sent: &str, expected: &str) -> bool {
+    let (a, b) = (sent.as_bytes(), expected.as_bytes());
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Work a web node asks the archive to do. The web node only has a secondary view of the store
+/// and can't write to it, so anything that changes the store goes through the archive.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AdminTask {
+    /// Archive every slot with a transaction on the tape again.
+    Resync(Pubkey),
+    /// Set or clear the label of a tape.
+    Label(Pubkey, TapeLabel),
+    /// Stop serving a tape, for the reason given.
+    Block(Pubkey, String),
+    /// Serve a blocked tape again, for the reason given.
+    Unblock(Pubkey, String),
+    /// Write a snapshot of the store to the directory given. Secondaries can't take one.
+    Snapshot(PathBuf),
+    /// Reopen the archive's log file, once it's been moved aside for rotation.
+    RotateLogs,
+    /// Record that a tape was read at the given unix time, so garbage collection keeps it.
+    Touch(Pubkey, u64),
+}
+
+impl AdminTask {
+    fn to_file_name(&self) -> String {
+        match self {
+            AdminTask::Resync(tape) => format!("resync-{}", tape),
+            AdminTask::Label(tape, _) => format!("label-{}", tape),
+            AdminTask::Block(tape, _) => format!("block-{}", tape),
+            AdminTask::Unblock(tape, _) => format!("unblock-{}", tape),
+            AdminTask::Snapshot(_) => "snapshot".to_string(),
+            AdminTask::RotateLogs => "rotatelogs".to_string(),
+            AdminTask::Touch(tape, _) => format!("touch-{}", tape),
+        }
+    }
+
+    /// What goes in the task's file, for arguments that don't fit in its name.
+    fn contents(&self) -> Vec<u8> {
+        match self {
+            AdminTask::Resync(_) => Vec::new(),
+            AdminTask::Label(_, label) => label.to_bytes(),
+            AdminTask::Block(_, reason) | AdminTask::Unblock(_, reason) => reason.as_bytes().to_vec(),
+            AdminTask::Snapshot(path) => path.to_string_lossy().into_owned().into_bytes(),
+            AdminTask::RotateLogs => Vec::new(),
+            AdminTask::Touch(_, at) => at.to_string().into_bytes(),
+        }
+    }
+
+    fn from_file(name: &str, contents: &[u8]) -> Option<Self> {
+        let (_queued_at, task) = name.split_once('-')?;
+        match task {
+            "snapshot" => return Some(AdminTask::Snapshot(PathBuf::from(String::from_utf8_lossy(contents).into_owned()))),
+            "rotatelogs" => return Some(AdminTask::RotateLogs),
+            _ => {}
+        }
+
+        let (task, tape) = task.split_once('-')?;
+        let tape = Pubkey::from_str(tape).ok()?;
+
+        match task {
+            "resync" => Some(AdminTask::Resync(tape)),
+            "label" => TapeLabel::from_bytes(contents).ok().map(|label| AdminTask::Label(tape, label)),
+            "block" => Some(AdminTask::Block(tape, String::from_utf8_lossy(contents).into_owned())),
+            "unblock" => Some(AdminTask::Unblock(tape, String::from_utf8_lossy(contents).into_owned())),
+            "touch" => std::str::from_utf8(contents).ok()?.parse().ok().map(|at| AdminTask::Touch(tape, at)),
+            _ => None,
+        }
+    }
+}
+
+/// Pending admin tasks for a namespace, one file per task named
+/// `<queued at, in nanoseconds>-<task>-<argument>` so they're taken in the order they came in.
+pub struct AdminQueue {
+    dir: PathBuf,
+}
+
+impl AdminQueue {
+    pub fn new(dir: impl Into<PathBuf>) -> Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)
+            .map_err(|e| anyhow!("Failed to create admin queue {}: {}", dir.display(), e))?;
+        Ok(Self { dir })
+    }
+
+    /// The queue for a namespace, next to its store.
+    pub fn open(namespace: &str) -> Result<Self> {
+        Self::new(env::current_dir()?.join("db_tapestore_admin").join(namespace))
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn push(&self, task: AdminTask) -> Result<()> {
+        let queued_at = SystemTime::now().duration_since(UNIX_EPOCH)?.as_nanos();
+        let name = format!("{:024}-{}", queued_at, task.to_file_name());
+
+        // Written under another name first, so the archive never picks up a half written task
+        let partial = self.dir.join(format!(".{}", name));
+        let path = self.dir.join(name);
+        fs::write(&partial, task.contents())
+            .and_then(|_| fs::rename(&partial, &path))
+            .map_err(|e| anyhow!("Failed to queue {}: {}", path.display(), e))?;
+        Ok(())
+    }
+
+    /// Number of tasks waiting for the archive.
+    pub fn pending(&self) -> Result<usize> {
+        Ok(self.entries()?.len())
+    }
+
+    /// Whether `task` is already waiting for the archive, so repeated requests for it can be
+    /// dropped.
+    pub fn is_queued(&self, task: &AdminTask) -> Result<bool> {
+        let suffix = format!("-{}", task.to_file_name());
+        Ok(self.entries()?.iter().any(|(name, _)| name.ends_with(&suffix)))
+    }
+
+    /// Take every pending task, oldest first. Files that aren't tasks are dropped as well.
+    pub fn drain(&self) -> Result<Vec<AdminTask>> {
+        let mut tasks = Vec::new();
+
+        for (name, path) in self.entries()? {
+            let contents = fs::read(&path).unwrap_or_default();
+            if let Some(task) = AdminTask::from_file(&name, &contents) {
+                tasks.push(task);
+            }
+            fs::remove_file(&path)
+                .map_err(|e| anyhow!("Failed to remove {}: {}", path.display(), e))?;
+        }
+
+        Ok(tasks)
+    }
+
+    /// Queued task files, oldest first. Tasks still being written are left out.
+    fn entries(&self) -> Result<Vec<(String, PathBuf)>> {
+        let mut entries = fs::read_dir(&self.dir)
+            .map_err(|e| anyhow!("Failed to read admin queue {}: {}", self.dir.display(), e))?
+            .flatten()
+            .filter_map(|entry| Some((entry.file_name().into_string().ok()?, entry.path())))
+            .filter(|(name, _)| !name.starts_with('.'))
+            .collect::<Vec<_>>();
+
+        entries.sort();
+        Ok(entries)
+    }
+}
+
+/// A fresh directory to write a snapshot of a namespace's store to.
+pub fn snapshot_path(namespace: &str) -> Result<PathBuf> {
+    let taken_at = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    Ok(env::current_dir()?
+        .join("db_tapestore_snapshots")
+        .join(namespace)
+        .join(taken_at.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempdir::TempDir;
+
+    #[test]
+    fn test_admin_queue() -> Result<()> {
+        let dir = TempDir::new("admin")?;
+        let queue = AdminQueue::new(dir.path())?;
+        let (a, b) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+        let label = TapeLabel { label: "spam".to_string(), note: String::new(), updated_at: 1 };
+
+        queue.push(AdminTask::Resync(a))?;
+        queue.push(AdminTask::Label(b, label.clone()))?;
+        queue.push(AdminTask::Block(a, "takedown".to_string()))?;
+        queue.push(AdminTask::Snapshot(dir.path().join("snapshots/1")))?;
+        queue.push(AdminTask::RotateLogs)?;
+        queue.push(AdminTask::Touch(b, 1_700_000_000))?;
+        fs::write(dir.path().join("not-a-task"), b"")?;
+        assert_eq!(queue.pending()?, 7);
+        assert!(queue.is_queued(&AdminTask::Resync(a))?);
+        assert!(!queue.is_queued(&AdminTask::Resync(b))?);
+
+        assert_eq!(
+            queue.drain()?,
+            vec![
+                AdminTask::Resync(a),
+                AdminTask::Label(b, label),
+                AdminTask::Block(a, "takedown".to_string()),
+                AdminTask::Snapshot(dir.path().join("snapshots/1")),
+                AdminTask::RotateLogs,
+                AdminTask::Touch(b, 1_700_000_000),
+            ]
+        );
+        assert_eq!(queue.pending()?, 0);
+        assert!(queue.drain()?.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_authorize() {
+        let admin = AdminConfig { token: "secret".to_string(), namespace: "test".to_string() };
+
+        assert!(admin.authorize(Some("Bearer secret")));
+        assert!(!admin.authorize(Some("Bearer secreT")));
+        assert!(!admin.authorize(Some("Bearer secrets")));
+        assert!(!admin.authorize(Some("secret")));
+        assert!(!admin.authorize(None));
+    }
+}
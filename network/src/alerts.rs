@@ -0,0 +1,421 @@
+use anyhow::{anyhow, bail, Result};
+use reqwest::Client as HttpClient;
+use serde::Deserialize;
+use serde_json::json;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tape_client::{get_miner_account, get_slot};
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+use super::store::StoreError;
+
+/// Least time between two looks at the free disk space.
+const DISK_CHECK_INTERVAL: u64 = 60;
+
+/// Least time between two looks at the miner's proof count, which costs two RPC calls.
+const PROOF_CHECK_INTERVAL: u64 = 60;
+
+/// Where alerts go and when they fire, read from a JSON file. Every threshold has a default, so
+/// a file with just a webhook or an email address is enough. With neither, nothing is sent.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AlertConfig {
+    /// URL alerts are POSTed to as JSON. The message is in `text`, which chat webhooks show.
+    pub webhook: Option<String>,
+    /// Address alerts are mailed to, through the local `sendmail`.
+    pub email: Option<EmailConfig>,
+    /// Slots the archive may trail the cluster by.
+    pub max_drift: u64,
+    /// Slots the miner may go without a proof landing, about an hour by default.
+    pub max_slots_without_proof: u64,
+    /// Consecutive iterations that may fail on the store.
+    pub max_store_errors: u32,
+    /// Free bytes left on the store's disk before it counts as nearly full.
+    pub min_free_disk_bytes: u64,
+    /// Seconds before an alert that's still firing is sent again.
+    pub repeat_secs: u64,
+}
+
+impl Default for AlertConfig {
+    fn default() -> Self {
+        Self {
+            webhook: None,
+            email: None,
+            max_drift: 500,
+            max_slots_without_proof: 9_000,
+            max_store_errors: 5,
+            min_free_disk_bytes: 10_000_000_000,
+            repeat_secs: 3_600,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EmailConfig {
+    pub to: String,
+    #[serde(default = "default_email_from")]
+    pub from: String,
+    #[serde(default = "default_sendmail")]
+    pub sendmail: PathBuf,
+}
+
+fn default_email_from() -> String {
+    "tapedrive@localhost".to_string()
+}
+
+fn default_sendmail() -> PathBuf {
+    PathBuf::from("/usr/sbin/sendmail")
+}
+
+/// Something an alert watches for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Alert {
+    Drift,
+    NoProof,
+    StoreErrors,
+    DiskSpace,
+}
+
+impl fmt::Display for Alert {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Alert::Drift => write!(f, "drift"),
+            Alert::NoProof => write!(f, "no-proof"),
+            Alert::StoreErrors => write!(f, "store-errors"),
+            Alert::DiskSpace => write!(f, "disk-space"),
+        }
+    }
+}
+
+/// What gets sent about an alert.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Notice {
+    Firing,
+    Resolved,
+}
+
+impl fmt::Display for Notice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Notice::Firing => write!(f, "firing"),
+            Notice::Resolved => write!(f, "resolved"),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct AlertState {
+    /// Firing alerts with when they were last sent.
+    firing: HashMap<Alert, u64>,
+    store_errors: u32,
+    last_disk_check: u64,
+    last_proof_check: u64,
+    /// The miner's proof count and the slot it was first seen at.
+    proofs: Option<(u64, u64)>,
+}
+
+/// Alerts shared by the node's loops, which report what they see and leave the thresholds and
+/// sending to it. An alert is sent when it starts firing, again every `repeat_secs` while it
+/// keeps firing, and once more when it clears. Failing to send is logged, never returned, so
+/// alerting can't stop a loop.
+#[derive(Debug, Default)]
+pub struct Alerter {
+    config: AlertConfig,
+    http: HttpClient,
+    state: Mutex<AlertState>,
+}
+
+impl Alerter {
+    pub fn new(config: AlertConfig) -> Self {
+        Self {
+            config,
+            http: HttpClient::new(),
+            state: Mutex::new(AlertState::default()),
+        }
+    }
+
+    /// Read the alert config from a JSON file.
+    pub fn load(path: &Path) -> Result<Self> {
+        let json = fs::read_to_string(path)
+            .map_err(|e| anyhow!("Failed to read {}: {}", path.display(), e))?;
+        let config: AlertConfig = serde_json::from_str(&json)
+            .map_err(|e| anyhow!("Invalid alert config {}: {}", path.display(), e))?;
+        if config.webhook.is_none() && config.email.is_none() {
+            bail!("Alert config {} has neither a webhook nor an email address", path.display());
+        }
+
+        Ok(Self::new(config))
+    }
+
+    /// Whether alerts go anywhere, checks are skipped if not.
+    pub fn enabled(&self) -> bool {
+        self.config.webhook.is_some() || self.config.email.is_some()
+    }
+
+    /// Report how many slots the archive trails the cluster by.
+    pub async fn check_drift(&self, drift: u64) {
+        let message = format!(
+            "The archive is {} slots behind the cluster (max {})",
+            drift, self.config.max_drift
+        );
+        self.update(Alert::Drift, drift > self.config.max_drift, &message).await;
+    }
+
+    /// Report the outcome of a loop iteration. Iterations failing on the store in a row fire an
+    /// alert, other failures (the RPC, the chain) are left to the loop's circuit breaker.
+    pub async fn record_iteration<T>(&self, result: &Result<T>) {
+        if !self.enabled() {
+            return;
+        }
+
+        let errors = {
+            let mut state = self.state.lock().unwrap();
+            match result {
+                Err(e) if e.chain().any(|cause| cause.is::<StoreError>()) => state.store_errors += 1,
+                Err(_) => return,
+                Ok(_) => state.store_errors = 0,
+            }
+            state.store_errors
+        };
+
+        let message = match result {
+            Err(e) => format!("{} iterations in a row failed on the store, the last with: {}", errors, e),
+            Ok(_) => "The store is working again".to_string(),
+        };
+        self.update(Alert::StoreErrors, errors >= self.config.max_store_errors, &message).await;
+    }
+
+    /// Look up a miner's proof count at most once a minute, firing an alert once it hasn't
+    /// changed for too many slots.
+    pub async fn watch_proofs(&self, client: &RpcClient, miner_address: &Pubkey) {
+        if !self.enabled() {
+            return;
+        }
+
+        {
+            let mut state = self.state.lock().unwrap();
+            if now().saturating_sub(state.last_proof_check) < PROOF_CHECK_INTERVAL {
+                return;
+            }
+            state.last_proof_check = now();
+        }
+
+        let (miner, slot) = match (get_miner_account(client, miner_address).await, get_slot(client).await) {
+            (Ok((miner, _)), Ok(slot)) => (miner, slot),
+            // The RPC is down, which the loop reports on its own
+            _ => return,
+        };
+
+        let since = {
+            let mut state = self.state.lock().unwrap();
+            match state.proofs {
+                Some((total, since)) if total == miner.total_proofs => since,
+                _ => {
+                    state.proofs = Some((miner.total_proofs, slot));
+                    slot
+                }
+            }
+        };
+
+        let slots = slot.saturating_sub(since);
+        let message = format!(
+            "Miner {} hasn't landed a proof in {} slots (max {})",
+            miner_address, slots, self.config.max_slots_without_proof
+        );
+        self.update(Alert::NoProof, slots > self.config.max_slots_without_proof, &message).await;
+    }
+
+    /// Check the free space on the disk holding `path`, at most once a minute.
+    pub async fn check_disk(&self, path: &Path) {
+        if !self.enabled() {
+            return;
+        }
+
+        {
+            let mut state = self.state.lock().unwrap();
+            if now().saturating_sub(state.last_disk_check) < DISK_CHECK_INTERVAL {
+                return;
+            }
+            state.last_disk_check = now();
+        }
+
+        let free = match free_disk_bytes(path).await {
+            Ok(free) => free,
+            Err(e) => {
+                eprintln!("ERROR: Failed to check free disk space: {:?}", e);
+                return;
+            }
+        };
+
+        let message = format!(
+            "The disk holding {} has {} bytes free (min {})",
+            path.display(), free, self.config.min_free_disk_bytes
+        );
+        self.update(Alert::DiskSpace, free < self.config.min_free_disk_bytes, &message).await;
+    }
+
+    async fn update(&self, alert: Alert, firing: bool, message: &str) {
+        if !self.enabled() {
+            return;
+        }
+
+        if let Some(notice) = self.transition(alert, firing, now()) {
+            self.send(alert, notice, message).await;
+        }
+    }
+
+    /// Record whether `alert` is firing at `time`, returning what to send about it, if anything.
+    fn transition(&self, alert: Alert, firing: bool, time: u64) -> Option<Notice> {
+        let mut state = self.state.lock().unwrap();
+
+        match (firing, state.firing.get(&alert).copied()) {
+            (true, Some(sent)) if time.saturating_sub(sent) < self.config.repeat_secs => None,
+            (true, _) => {
+                state.firing.insert(alert, time);
+                Some(Notice::Firing)
+            }
+            (false, Some(_)) => {
+                state.firing.remove(&alert);
+                Some(Notice::Resolved)
+            }
+            (false, None) => None,
+        }
+    }
+
+    async fn send(&self, alert: Alert, notice: Notice, message: &str) {
+        println!("DEBUG: Alert {} {}: {}", alert, notice, message);
+
+        if let Some(url) = &self.config.webhook {
+            let body = json!({
+                "alert": alert.to_string(),
+                "state": notice.to_string(),
+                "text": format!("[tapedrive] {} {}: {}", alert, notice, message),
+                "time": now(),
+            });
+            let result = self
+                .http
+                .post(url)
+                .json(&body)
+                .send()
+                .await
+                .and_then(|response| response.error_for_status());
+            if let Err(e) = result {
+                eprintln!("ERROR: Failed to send alert to the webhook: {:?}", e);
+            }
+        }
+
+        if let Some(email) = &self.config.email {
+            if let Err(e) = send_email(email, &format!("[tapedrive] {} {}", alert, notice), message).await {
+                eprintln!("ERROR: Failed to email alert: {:?}", e);
+            }
+        }
+    }
+}
+
+/// Mail a message through `sendmail`, which takes the recipients from the headers.
+async fn send_email(email: &EmailConfig, subject: &str, body: &str) -> Result<()> {
+    let mut child = Command::new(&email.sendmail)
+        .arg("-t")
+        .stdin(Stdio::piped())
+        .spawn()
+        .map_err(|e| anyhow!("Failed to run {}: {}", email.sendmail.display(), e))?;
+
+    let mail = format!("To: {}\nFrom: {}\nSubject: {}\n\n{}\n", email.to, email.from, subject, body);
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(mail.as_bytes()).await?;
+    }
+
+    let status = child.wait().await?;
+    if !status.success() {
+        bail!("{} exited with {}", email.sendmail.display(), status);
+    }
+
+    Ok(())
+}
+
+/// Bytes available on the filesystem holding `path`, as POSIX `df` reports them.
+async fn free_disk_bytes(path: &Path) -> Result<u64> {
+    let output = Command::new("df").arg("-Pk").arg(path).output().await?;
+    if !output.status.success() {
+        bail!("df exited with {}", output.status);
+    }
+
+    parse_df(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// The available column of `df -Pk` output, in bytes.
+fn parse_df(output: &str) -> Result<u64> {
+    let available = output
+        .lines()
+        .nth(1)
+        .and_then(|line| line.split_whitespace().nth(3))
+        .ok_or_else(|| anyhow!("Unexpected df output: {}", output))?;
+    let kilobytes: u64 = available
+        .parse()
+        .map_err(|_| anyhow!("Unexpected df output: {}", output))?;
+
+    Ok(kilobytes * 1024)
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transition() {
+        let alerter = Alerter::new(AlertConfig { repeat_secs: 600, ..AlertConfig::default() });
+        let start = 1_000_000;
+
+        assert_eq!(alerter.transition(Alert::Drift, false, start), None);
+        assert_eq!(alerter.transition(Alert::Drift, true, start), Some(Notice::Firing));
+
+        // Not again until the repeat interval passes
+        assert_eq!(alerter.transition(Alert::Drift, true, start + 300), None);
+        assert_eq!(alerter.transition(Alert::Drift, true, start + 600), Some(Notice::Firing));
+
+        // Alerts are independent
+        assert_eq!(alerter.transition(Alert::DiskSpace, true, start + 600), Some(Notice::Firing));
+
+        assert_eq!(alerter.transition(Alert::Drift, false, start + 700), Some(Notice::Resolved));
+        assert_eq!(alerter.transition(Alert::Drift, false, start + 800), None);
+        assert_eq!(alerter.transition(Alert::Drift, true, start + 900), Some(Notice::Firing));
+    }
+
+    #[test]
+    fn test_config() {
+        let config: AlertConfig = serde_json::from_str(r#"{ "webhook": "http://localhost/hook", "max_drift": 100 }"#).unwrap();
+        assert_eq!(config.max_drift, 100);
+        assert_eq!(config.repeat_secs, AlertConfig::default().repeat_secs);
+        assert!(Alerter::new(config).enabled());
+
+        let config: AlertConfig = serde_json::from_str(r#"{ "email": { "to": "ops@example.com" } }"#).unwrap();
+        assert_eq!(config.email.unwrap().sendmail, default_sendmail());
+
+        assert!(serde_json::from_str::<AlertConfig>(r#"{ "max_drfit": 100 }"#).is_err());
+        assert!(!Alerter::default().enabled());
+    }
+
+    #[test]
+    fn test_parse_df() {
+        let output = "Filesystem     1024-blocks      Used Available Capacity Mounted on\n\
+                      /dev/nvme0n1p2   959786032 512345678 398615218      57% /\n";
+        assert_eq!(parse_df(output).unwrap(), 398_615_218 * 1024);
+        assert!(parse_df("").is_err());
+    }
+}
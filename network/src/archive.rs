@@ -1,22 +1,61 @@
 use anyhow::{anyhow, Result};
 use solana_transaction_status_client_types::TransactionDetails;
 use solana_client::nonblocking::rpc_client::RpcClient;
-use solana_sdk::pubkey::Pubkey;
+use solana_sdk::{pubkey::Pubkey, signature::Signature};
+use std::collections::{BTreeSet, HashSet};
+use std::fs;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
 use tokio::time::{sleep, Duration};
-use tape_client::{get_slot, get_blocks_with_limit, get_block_by_number, get_archive_account};
+use tape_client::{
+    get_slot, get_blocks_with_limit, get_block_by_number, get_archive_account, get_epoch_account,
+    get_signatures_for_address, get_tape_account, get_tape_page, program_context,
+};
 use reqwest::Client as HttpClient;
 use serde_json::json;
 use base64::decode;
 
-use super::block::{process_block, ProcessedBlock};
-use super::store::TapeStore;
+use super::admin::{AdminQueue, AdminTask};
+use super::alerts::Alerter;
+use super::block::{block_loads_program, stream_block, BlockWrite};
+use super::breaker::CircuitBreaker;
+use super::logfile;
+use super::snapshot::{write_manifest, SnapshotManifest};
+use super::store::{EpochRecord, FirstSeen, SegmentConflict, StoreError, SyncEstimate, TapeStore};
+
+/// Gaps retried every 10 iterations of the archive loop.
+const GAPS_PER_REPAIR: usize = 10;
+
+/// Consecutive failed iterations before the archive backs off the RPC.
+const BREAKER_THRESHOLD: u32 = 5;
+
+/// Longest wait between iterations while the RPC keeps failing.
+const MAX_BACKOFF: Duration = Duration::from_secs(300);
+
+/// Blocks the pre-filter checked and how many of them it fetched in full, since the archive
+/// started.
+#[derive(Debug, Default)]
+struct PrefilterStats {
+    checked: u64,
+    fetched: u64,
+}
 
-/// Archive loop that continuously fetches and processes blocks from the Solana network.
+/// Archive loop that continuously fetches and processes blocks from the Solana network. Tasks
+/// queued by a web node in `admin_queue` are run between iterations. Drift, store errors and
+/// the free space on the store's disk are reported to `alerts`.
+///
+/// With `prefilter`, each block is first fetched with only its account lists, and fetched in
+/// full only if a transaction in it loads the tape program.
+#[allow(clippy::too_many_arguments)]
 pub async fn archive_loop(
     store: &TapeStore,
     client: &RpcClient,
     starting_slot: Option<u64>,
     trusted_peer: Option<String>,
+    leaderboard: bool,
+    prefilter: bool,
+    admin_queue: Option<&AdminQueue>,
+    alerts: &Alerter,
 ) -> Result<()> {
     // If a trusted peer is provided, sync with it first
     if let Some(peer_url) = trusted_peer.clone() {
@@ -24,6 +63,8 @@ pub async fn archive_loop(
         println!("DEBUG: Syncing with trusted peer");
         println!("DEBUG: This may take a while... please be patient");
         sync_with_trusted_peer(store, client, &peer_url).await?;
+        // A bulk import, don't leave secondaries to replay it all from the log
+        store.flush()?;
     }
 
     let interval = Duration::from_secs(2);
@@ -43,21 +84,43 @@ pub async fn archive_loop(
         .unwrap_or(latest_slot);
 
     let mut iteration_count = 0;
+    let mut prefilter_stats = prefilter.then(PrefilterStats::default);
+    let mut breaker = CircuitBreaker::new(BREAKER_THRESHOLD, interval, MAX_BACKOFF);
 
     loop {
-        match try_archive_iteration(
+        if let Some(queue) = admin_queue {
+            run_admin_tasks(store, client, queue).await;
+        }
+
+        breaker.before_attempt();
+
+        let result = try_archive_iteration(
             store,
             client,
             &mut latest_slot,
             &mut last_processed_slot,
             &mut iteration_count,
-        ).await {
+            leaderboard,
+            prefilter_stats.as_mut(),
+        ).await;
+
+        match &result {
             Ok(()) => println!("DEBUG: Block processing iteration completed successfully"),
             Err(e) => eprintln!("ERROR: Block processing iteration failed: {:?}", e),
         }
 
+        if let Some(state) = breaker.record(&result) {
+            println!("DEBUG: RPC circuit breaker {} after {} consecutive failures", state, breaker.failures());
+        }
+        if let Err(e) = store.update_rpc_status(breaker.state(), breaker.failures()) {
+            println!("ERROR: failed to write RPC status: {:?}", e);
+        }
+
         drift_status(store, latest_slot, last_processed_slot);
-        sleep(interval).await;
+        alerts.record_iteration(&result).await;
+        alerts.check_drift(latest_slot.saturating_sub(last_processed_slot)).await;
+        alerts.check_disk(store.path()).await;
+        sleep(breaker.delay()).await;
     }
 }
 
@@ -68,6 +131,8 @@ async fn try_archive_iteration(
     latest_slot: &mut u64,
     last_processed_slot: &mut u64,
     iteration_count: &mut u64,
+    leaderboard: bool,
+    mut prefilter: Option<&mut PrefilterStats>,
 ) -> Result<()> {
     *iteration_count += 1;
 
@@ -79,34 +144,291 @@ async fn try_archive_iteration(
         } else {
             println!("DEBUG: Failed to get slot tip");
         }
+
+        if let Err(e) = snapshot_epoch(store, client, *latest_slot).await {
+            println!("DEBUG: Failed to snapshot epoch: {:?}", e);
+        }
+
+        match repair_gaps(store, client, GAPS_PER_REPAIR, leaderboard).await {
+            Ok((0, 0)) => {}
+            Ok((repaired, failed)) => println!("DEBUG: Repaired {} gaps, {} still failing", repaired, failed),
+            Err(e) => println!("DEBUG: Failed to repair gaps: {:?}", e),
+        }
     }
 
-    // Fetch up to 100 new slots starting just above what we've processed
+    // Fetch up to 100 new slots starting just above what we've processed. Only slots with a
+    // block are returned, the ones in between were skipped by their leader.
     let start = *last_processed_slot + 1;
     let slots = get_blocks_with_limit(client, start, 100).await?;
     println!("DEBUG: Fetched {} new slots from {}", slots.len(), start);
 
+    let total = slots.len();
+    let mut failed = 0;
+    let mut skipped = 0;
+    let mut previous = *last_processed_slot;
     for slot in slots {
-        let block = get_block_by_number(client, slot, TransactionDetails::Full).await?;
-        let processed = process_block(block, slot)?;
-
-        if !processed.tapes.is_empty() || !processed.writes.is_empty() {
-            archive_block(store, &processed)?;
+        skipped += slot.saturating_sub(previous + 1);
+        previous = slot;
+
+        let result = match prefilter.as_deref_mut() {
+            Some(stats) => archive_prefiltered_slot(store, client, slot, leaderboard, stats).await,
+            None => archive_slot(store, client, slot, leaderboard).await,
+        };
+
+        // A block that fails to archive becomes a gap to repair later, instead of holding up
+        // (or dropping) the rest of the batch
+        if let Err(e) = result {
+            eprintln!("ERROR: Failed to archive slot {}, recorded as a gap: {:?}", slot, e);
+            store.add_gap(slot, &e.to_string())?;
+            failed += 1;
         }
 
         *last_processed_slot = slot;
     }
 
+    if skipped > 0 {
+        println!("DEBUG: {} skipped slots (no block) up to {}", skipped, previous);
+    }
+
+    if let Some(stats) = prefilter.filter(|stats| stats.checked > 0) {
+        println!(
+            "DEBUG: Pre-filter fetched {} of {} blocks in full ({:.1}%)",
+            stats.fetched,
+            stats.checked,
+            stats.fetched as f64 * 100.0 / stats.checked as f64
+        );
+    }
+
+    // Every block failing points at the RPC rather than the blocks, let the breaker know
+    if total > 0 && failed == total {
+        return Err(anyhow!("All {} blocks from slot {} failed to archive", total, start));
+    }
+
     Ok(())
 }
 
-fn archive_block(store: &TapeStore, block: &ProcessedBlock) -> Result<()> {
-    for (address, number) in &block.tapes {
-        store.add_tape(*number, address)?;
+/// Fetch, process and store a single block. Writes go to the store as they're found, so a block
+/// full of large writes is never held in memory a second time.
+async fn archive_slot(store: &TapeStore, client: &RpcClient, slot: u64, leaderboard: bool) -> Result<()> {
+    let block = get_block_by_number(client, slot, TransactionDetails::Full).await?;
+    let seen = FirstSeen { slot, time: block.block_time.unwrap_or_else(unix_now) };
+    let mut sighted = HashSet::new();
+
+    let streamed = stream_block(program_context(), block, slot, |write| -> Result<()> {
+        let address = match write {
+            BlockWrite::Tape { address, number } => {
+                store.finalize_tape(&address, number)?;
+                address
+            }
+            BlockWrite::Segment { address, segment_number, data, .. } => {
+                store.add_segment_at(&address, segment_number, data.to_vec(), slot)?;
+                address
+            }
+        };
+        if sighted.insert(address) {
+            store.record_first_seen(&address, seen)?;
+            store.add_tape_slot(&address, slot)?;
+        }
+        Ok(())
+    })?;
+
+    for duplicate in streamed.duplicates {
+        store.add_conflict(&duplicate.address, &SegmentConflict {
+            segment_number: duplicate.segment_number,
+            slot,
+            identical: duplicate.identical,
+            data: duplicate.data,
+        })?;
     }
 
-    for ((tape, segment), data) in &block.writes {
-        store.add_segment(tape, *segment, data.clone())?;
+    if leaderboard && !streamed.mines.is_empty() {
+        store.record_mines(slot, &streamed.mines)?;
+    }
+
+    Ok(())
+}
+
+/// Archive a slot only if a transaction in its block loads the tape program, checked on the
+/// block's account lists before fetching it in full. Most blocks have no tape transactions, and
+/// their account lists are a small part of a full block with instruction data and logs.
+async fn archive_prefiltered_slot(
+    store: &TapeStore,
+    client: &RpcClient,
+    slot: u64,
+    leaderboard: bool,
+    stats: &mut PrefilterStats,
+) -> Result<()> {
+    // Not `Signatures`, which leaves out the account keys the check needs
+    let accounts = get_block_by_number(client, slot, TransactionDetails::Accounts).await?;
+    stats.checked += 1;
+    if !block_loads_program(&program_context().program_id, &accounts) {
+        return Ok(());
+    }
+
+    stats.fetched += 1;
+    archive_slot(store, client, slot, leaderboard).await
+}
+
+/// Retry up to `limit` of the oldest gaps. Returns how many were repaired and how many failed
+/// again. Mines in a repaired slot aren't added to the leaderboard, which has moved past it.
+pub async fn repair_gaps(
+    store: &TapeStore,
+    client: &RpcClient,
+    limit: usize,
+    leaderboard: bool,
+) -> Result<(usize, usize)> {
+    let (mut repaired, mut failed) = (0, 0);
+
+    for gap in store.get_gaps(limit)? {
+        match archive_slot(store, client, gap.slot, leaderboard).await {
+            Ok(()) => {
+                store.remove_gap(gap.slot)?;
+                repaired += 1;
+            }
+            Err(e) => {
+                store.add_gap(gap.slot, &e.to_string())?;
+                failed += 1;
+            }
+        }
+    }
+
+    Ok((repaired, failed))
+}
+
+/// Archive every slot with a successful transaction on a tape again, to repair segments that are
+/// missing or stale in the store. Returns the number of slots archived.
+///
+/// When the store holds every segment of the tape, the slots the archive already indexed for it
+/// are enough to bring stale ones up to date, and the tape's history isn't fetched from the RPC.
+pub async fn resync_tape(store: &TapeStore, client: &RpcClient, tape_address: &Pubkey) -> Result<usize> {
+    let known = store.get_tape_slots(tape_address)?;
+    let slots = if !known.is_empty() && holds_every_segment(store, client, tape_address).await? {
+        known.into_iter().collect()
+    } else {
+        tape_slots_from_rpc(client, tape_address).await?
+    };
+
+    // Oldest first, so later writes to a segment win
+    for &slot in &slots {
+        archive_slot(store, client, slot, false).await?;
+    }
+
+    Ok(slots.len())
+}
+
+/// Whether the store holds as many segments of a tape as its account says were written.
+async fn holds_every_segment(store: &TapeStore, client: &RpcClient, tape_address: &Pubkey) -> Result<bool> {
+    let (tape, _) = get_tape_account(client, tape_address).await?;
+    let held = store.for_each_segment(tape_address, |_, _| Ok::<_, StoreError>(()))?;
+
+    Ok(held as u64 >= tape.total_segments)
+}
+
+/// Every slot with a successful transaction on a tape, walking its signatures through the RPC.
+async fn tape_slots_from_rpc(client: &RpcClient, tape_address: &Pubkey) -> Result<BTreeSet<u64>> {
+    let mut slots = BTreeSet::new();
+    let mut before = None;
+
+    loop {
+        let page = get_signatures_for_address(client, tape_address, before, None, None).await?;
+        let Some(last) = page.last() else {
+            break;
+        };
+
+        before = Some(last.signature.parse::<Signature>()?);
+        slots.extend(page.iter().filter(|s| s.err.is_none()).map(|s| s.slot));
+    }
+
+    Ok(slots)
+}
+
+/// Run the tasks web nodes have queued. Failures are logged, the task isn't retried.
+async fn run_admin_tasks(store: &TapeStore, client: &RpcClient, queue: &AdminQueue) {
+    let tasks = match queue.drain() {
+        Ok(tasks) => tasks,
+        Err(e) => {
+            eprintln!("ERROR: Failed to read admin tasks: {:?}", e);
+            return;
+        }
+    };
+
+    for task in tasks {
+        match task {
+            AdminTask::Resync(tape_address) => match resync_tape(store, client, &tape_address).await {
+                Ok(slots) => println!("DEBUG: Resynced tape {} from {} slots", tape_address, slots),
+                Err(e) => eprintln!("ERROR: Failed to resync tape {}: {:?}", tape_address, e),
+            },
+            AdminTask::Label(tape_address, label) => match store.put_label(&tape_address, &label) {
+                Ok(()) => println!("DEBUG: Labelled tape {} {:?}", tape_address, label.label),
+                Err(e) => eprintln!("ERROR: Failed to label tape {}: {:?}", tape_address, e),
+            },
+            AdminTask::Block(tape_address, reason) => match store.block_tape(&tape_address, &reason) {
+                Ok(()) => println!("DEBUG: Blocked tape {}: {}", tape_address, reason),
+                Err(e) => eprintln!("ERROR: Failed to block tape {}: {:?}", tape_address, e),
+            },
+            AdminTask::Unblock(tape_address, reason) => match store.unblock_tape(&tape_address, &reason) {
+                Ok(()) => println!("DEBUG: Unblocked tape {}: {}", tape_address, reason),
+                Err(e) => eprintln!("ERROR: Failed to unblock tape {}: {:?}", tape_address, e),
+            },
+            AdminTask::Snapshot(path) => match snapshot_store(store, path.clone()).await {
+                Ok(manifest) => println!(
+                    "DEBUG: Wrote snapshot {} ({} tapes, {} segments)",
+                    path.display(), manifest.counts.tapes, manifest.counts.segments
+                ),
+                Err(e) => eprintln!("ERROR: Failed to write snapshot {}: {:?}", path.display(), e),
+            },
+            AdminTask::RotateLogs => match logfile::reopen() {
+                Ok(Some(path)) => println!("DEBUG: Reopened log file {}", path.display()),
+                Ok(None) => println!("DEBUG: Logging to stdout, no log file to reopen"),
+                Err(e) => eprintln!("ERROR: Failed to reopen log file: {:?}", e),
+            },
+            AdminTask::Touch(tape_address, at) => {
+                if let Err(e) = store.touch_tape_at(&tape_address, at) {
+                    eprintln!("ERROR: Failed to record a read of tape {}: {:?}", tape_address, e);
+                }
+            }
+        }
+    }
+}
+
+/// Snapshot the store to `path`. The checkpoint only flushes and hard-links table files, but
+/// the manifest counts every segment of the copy, so that runs on a blocking thread instead of
+/// holding up the archive's runtime.
+async fn snapshot_store(store: &TapeStore, path: PathBuf) -> Result<SnapshotManifest> {
+    let namespace = store
+        .get_namespace()?
+        .ok_or_else(|| anyhow!("Store has no namespace to record in the snapshot"))?;
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    store.checkpoint(&path)?;
+
+    tokio::task::spawn_blocking(move || write_manifest(&namespace, &path)).await?
+}
+
+/// Save the Epoch account to the store when it changed, the account itself is overwritten every
+/// epoch so this is the only record of past difficulty and rates.
+async fn snapshot_epoch(store: &TapeStore, client: &RpcClient, slot: u64) -> Result<()> {
+    let (epoch, _) = get_epoch_account(client).await?;
+
+    let record = EpochRecord {
+        number: epoch.number,
+        difficulty: epoch.difficulty,
+        last_epoch_at: epoch.last_epoch_at,
+        base_rate: epoch.base_rate,
+        target_rate: epoch.target_rate,
+        slot,
+    };
+
+    let changed = match store.get_latest_epoch()? {
+        Some(latest) => EpochRecord { slot, ..latest } != record,
+        None => true,
+    };
+
+    if changed {
+        println!("DEBUG: Epoch {} difficulty {}", record.number, record.difficulty);
+        store.put_epoch(&record)?;
     }
 
     Ok(())
@@ -147,36 +469,45 @@ async fn sync_with_trusted_peer(
             .ok_or_else(|| anyhow!("Invalid getTapeAddress response: {:?}", addr_resp))?;
         let tape_address: Pubkey = addr_str.parse()?;
 
-        // Store the tape record
-        store.add_tape(tape_number, &tape_address)?;
-
-        // Fetch all segments for this tape
-        let seg_resp = http.post(trusted_peer_url)
-            .header("Content-Type", "application/json")
-            .body(json!({
-                "jsonrpc": "2.0", "id": 4,
-                "method": "getTape",
-                "params": { "tape_address": addr_str }
-            }).to_string())
-            .send().await?
-            .json::<serde_json::Value>().await?;
-
         println!("DEBUG: Syncing tape {}, address {}", tape_number, tape_address);
 
-        let segments = seg_resp["result"].as_array()
-            .ok_or_else(|| anyhow!("Invalid getTape response: {:?}", seg_resp))?;
-
-        for seg in segments {
-            let seg_num = seg["segment_number"]
-                .as_u64()
-                .ok_or_else(|| anyhow!("Invalid segment_number: {:?}", seg))?;
-            let data_b64 = seg["data"]
-                .as_str()
-                .ok_or_else(|| anyhow!("Invalid data field: {:?}", seg))?;
-            let data = decode(data_b64)?;
-
-            store.add_segment(&tape_address, seg_num, data)?;
+        // Fetch all segments for this tape, a page at a time
+        let mut cursor = serde_json::Value::Null;
+        loop {
+            let seg_resp = http.post(trusted_peer_url)
+                .header("Content-Type", "application/json")
+                .body(json!({
+                    "jsonrpc": "2.0", "id": 4,
+                    "method": "getTape",
+                    "params": { "tape_address": addr_str, "cursor": cursor }
+                }).to_string())
+                .send().await?
+                .json::<serde_json::Value>().await?;
+
+            let (segments, next) = get_tape_page(&seg_resp["result"])
+                .ok_or_else(|| anyhow!("Invalid getTape response: {:?}", seg_resp))?;
+
+            for seg in segments {
+                let seg_num = seg["segment_number"]
+                    .as_u64()
+                    .ok_or_else(|| anyhow!("Invalid segment_number: {:?}", seg))?;
+                let data_b64 = seg["data"]
+                    .as_str()
+                    .ok_or_else(|| anyhow!("Invalid data field: {:?}", seg))?;
+                let data = decode(data_b64)?;
+
+                store.add_mutable_segment(&tape_address, seg_num, data)?;
+            }
+
+            cursor = next;
+            if cursor.is_null() {
+                break;
+            }
         }
+
+        // Seals the segments only once every one is in, a sync cut short is picked up again next
+        // time and is never seen half done
+        store.finalize_tape(&tape_address, tape_number)?;
     }
 
     Ok(())
@@ -206,4 +537,19 @@ fn drift_status(
         "DEBUG: Drift {} slots behind tip ({}), status: {}",
         drift, latest_slot, health_status
     );
+
+    // Worth knowing while bootstrapping, whether the rest takes hours or weeks
+    if drift >= 50 {
+        if let Some(estimate) = store.get_health_samples().ok().and_then(|s| SyncEstimate::from_samples(&s)) {
+            println!("DEBUG: Syncing at {}", estimate);
+        }
+    }
+}
+
+/// Unix time now, for blocks the RPC returns without a time.
+fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
 }
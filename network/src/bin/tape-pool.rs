@@ -0,0 +1,88 @@
+//! A reference mining pool coordinator, splitting the work of one miner across workers.
+
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Result};
+use clap::Parser;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    signature::read_keypair_file,
+    signer::Signer,
+};
+use tape_api::prelude::*;
+use tape_client::{get_miner_account, program_context};
+use tape_network::{
+    admin::AdminQueue,
+    alerts::Alerter,
+    mine::DEFAULT_MAX_DRIFT,
+    pool::{load_workers, pool_loop, PoolPolicy, DEFAULT_SHARE_DIFFICULTY},
+    shared::SecondaryStoreHandle,
+};
+
+#[derive(Parser)]
+#[command(name = "tape-pool", about = "Hand out mining work to pool workers and submit their best solution")]
+struct Args {
+    #[arg(help = "Keypair file of the pool's miner authority, which also pays for proofs")]
+    keypair: PathBuf,
+
+    #[arg(long, default_value = "default", help = "Name the pool's miner was registered under")]
+    name: String,
+
+    #[arg(long, default_value = "http://127.0.0.1:8899", help = "RPC to read the miner and send proofs through")]
+    rpc: String,
+
+    #[arg(long, default_value_t = 3002, help = "Port to serve workers on")]
+    port: u16,
+
+    #[arg(long = "max-drift", default_value_t = DEFAULT_MAX_DRIFT, help = "Skip rounds while the local store is more than this many slots behind the cluster")]
+    max_drift: u64,
+
+    #[arg(long = "share-difficulty", default_value_t = DEFAULT_SHARE_DIFFICULTY, help = "Lowest difficulty counted as a share")]
+    share_difficulty: u32,
+
+    #[arg(long, help = "JSON file of the workers allowed to mine for the pool, each name to its token")]
+    workers: PathBuf,
+
+    #[arg(long = "payout-log", help = "Append the shares of each mined round to this file, one JSON line per round")]
+    payout_log: Option<PathBuf>,
+
+    #[arg(long, help = "JSON file of where to send alerts (webhook, email) and when")]
+    alerts: Option<PathBuf>,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args = Args::parse();
+
+    let signer = read_keypair_file(&args.keypair)
+        .map_err(|e| anyhow!("Failed to read keypair file {}: {}", args.keypair.display(), e))?;
+    let client = RpcClient::new_with_commitment(args.rpc, CommitmentConfig::confirmed());
+
+    let (miner_address, _) = program_context().miner_pda(signer.pubkey(), to_name(&args.name));
+    get_miner_account(&client, &miner_address)
+        .await
+        .map_err(|_| anyhow!("Miner {} isn't registered, run `tapedrive register {}` first", miner_address, args.name))?;
+
+    // The archive's store for this cluster, the same one `tapedrive archive` fills
+    let namespace = client
+        .get_genesis_hash()
+        .await
+        .map_err(|e| anyhow!("Failed to fetch cluster genesis hash: {}", e))?
+        .to_string();
+    let store = SecondaryStoreHandle::open(&namespace)?;
+    let admin_queue = AdminQueue::open(&namespace)?;
+    let alerts = match &args.alerts {
+        Some(path) => Alerter::load(path)?,
+        None => Alerter::default(),
+    };
+
+    println!("Coordinating pool for miner {} on port {}", miner_address, args.port);
+
+    let policy = PoolPolicy {
+        share_difficulty: args.share_difficulty,
+        payout_log: args.payout_log,
+        workers: load_workers(&args.workers)?,
+    };
+    pool_loop(&store, &client, &miner_address, &signer, args.max_drift, policy, args.port, &admin_queue, &alerts).await
+}
@@ -0,0 +1,42 @@
+//! A reference relayer, paying the fees of tape writes for users without SOL.
+
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Result};
+use clap::Parser;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    signature::read_keypair_file,
+    signer::Signer,
+};
+use tape_network::relay::{relay_loop, RelayPolicy};
+
+#[derive(Parser)]
+#[command(name = "tape-relayer", about = "Pay the fees of tape transactions sent to it")]
+struct Args {
+    #[arg(help = "Keypair file of the fee payer")]
+    keypair: PathBuf,
+
+    #[arg(long, default_value = "http://127.0.0.1:8899", help = "RPC to send transactions through")]
+    rpc: String,
+
+    #[arg(long, default_value_t = 3001, help = "Port to serve the relayer on")]
+    port: u16,
+
+    #[arg(long = "max-cu-price", default_value_t = 0, help = "Highest priority fee to pay, in micro-lamports per compute unit")]
+    max_cu_price: u64,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args = Args::parse();
+
+    let payer = read_keypair_file(&args.keypair)
+        .map_err(|e| anyhow!("Failed to read keypair file {}: {}", args.keypair.display(), e))?;
+    let rpc = RpcClient::new_with_commitment(args.rpc, CommitmentConfig::confirmed());
+
+    println!("Relaying for fee payer {} on port {}", payer.pubkey(), args.port);
+
+    relay_loop(rpc, payer, RelayPolicy { max_cu_price: args.max_cu_price }, args.port).await
+}
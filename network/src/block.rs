@@ -4,6 +4,7 @@ use std::collections::HashMap;
 use solana_sdk::{
     pubkey::Pubkey, 
     bs58, 
+    keccak,
 };
 use solana_transaction_status::{
     option_serializer::OptionSerializer,
@@ -12,6 +13,8 @@ use solana_transaction_status::{
     UiCompiledInstruction,
     UiInstruction,
     UiMessage,
+    UiParsedInstruction,
+    UiPartiallyDecodedInstruction,
     UiTransactionStatusMeta,
     UiConfirmedBlock
 };
@@ -21,9 +24,12 @@ use tape_api::prelude::{
     WriteEvent,
     UpdateEvent,
     FinalizeEvent,
+    MineEvent,
+    ClaimEvent,
     InstructionType,
     EventType,
     Update,
+    ProgramContext,
 };
 
 #[derive(Error, Debug)]
@@ -48,6 +54,13 @@ pub enum TapeEvent {
     Finalize(FinalizeEvent),
 }
 
+// Pulled out of logs, these have no matching tape instruction
+#[derive(Debug)]
+pub enum MinerEvent {
+    Mine(MineEvent),
+    Claim(ClaimEvent),
+}
+
 // Pulled out of instruction data
 #[derive(Debug)]
 pub enum TapeInstruction {
@@ -66,6 +79,7 @@ pub enum TapeInstruction {
 pub struct TapeBlock {
     pub events: Vec<TapeEvent>,
     pub instructions: Vec<TapeInstruction>,
+    pub miner_events: Vec<MinerEvent>,
 }
 
 #[derive(Debug, Default)]
@@ -73,36 +87,196 @@ pub struct ProcessedBlock {
     pub slot: u64,
     pub tapes: HashMap<Pubkey, u64>,
     pub writes: HashMap<(Pubkey, u64), Vec<u8>>,
+    pub mines: Vec<MineEvent>,
+    pub claims: Vec<ClaimEvent>,
+}
+
+/// A change to the store found in a block.
+#[derive(Debug, PartialEq, Eq)]
+pub enum BlockWrite<'a> {
+    /// A tape was finalized and given its number.
+    Tape { address: Pubkey, number: u64 },
+    /// A segment was written, or updated if `update` is set.
+    Segment { address: Pubkey, segment_number: u64, data: &'a [u8], update: bool },
+}
+
+/// A write to a segment that an earlier transaction in the same block already wrote. It isn't
+/// passed on, the first write wins.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuplicateWrite {
+    pub address: Pubkey,
+    pub segment_number: u64,
+    /// Whether it carried the same data as the write that was kept.
+    pub identical: bool,
+    pub data: Vec<u8>,
 }
 
-pub fn process_block(block: UiConfirmedBlock, slot: u64) -> Result<ProcessedBlock, BlockError> {
+/// What [`stream_block`] found in a block, besides the writes it passed on.
+#[derive(Debug, Default)]
+pub struct StreamedBlock {
+    pub slot: u64,
+    pub tapes: usize,
+    pub segments: usize,
+    pub duplicates: Vec<DuplicateWrite>,
+    pub mines: Vec<MineEvent>,
+    pub claims: Vec<ClaimEvent>,
+}
+
+/// The segments written so far in a block, by a hash of their data so the block's segments
+/// aren't held in memory.
+#[derive(Default)]
+struct WrittenSegments {
+    seen: HashMap<(Pubkey, u64), keccak::Hash>,
+}
+
+impl WrittenSegments {
+    /// Record a write, or return it as a duplicate if the segment was already written. Updates
+    /// are meant to replace a segment and always go through.
+    fn check(&mut self, write: &BlockWrite<'_>) -> Option<DuplicateWrite> {
+        let BlockWrite::Segment { address, segment_number, data, update } = *write else {
+            return None;
+        };
+
+        let hash = keccak::hash(data);
+        if update {
+            self.seen.insert((address, segment_number), hash);
+            return None;
+        }
+
+        match self.seen.get(&(address, segment_number)) {
+            Some(kept) => Some(DuplicateWrite {
+                address,
+                segment_number,
+                identical: *kept == hash,
+                data: data.to_vec(),
+            }),
+            None => {
+                self.seen.insert((address, segment_number), hash);
+                None
+            }
+        }
+    }
+}
+
+/// Collect every write in a block. Holds all of the block's segments in memory at once, the
+/// archive uses [`stream_block`] instead.
+pub fn process_block(program: &ProgramContext, block: UiConfirmedBlock, slot: u64) -> Result<ProcessedBlock, BlockError> {
+    let mut tapes = HashMap::new();
+    let mut writes = HashMap::new();
+
+    let streamed = stream_block(program, block, slot, |write| {
+        match write {
+            BlockWrite::Tape { address, number } => {
+                tapes.insert(address, number);
+            }
+            BlockWrite::Segment { address, segment_number, data, .. } => {
+                writes.insert((address, segment_number), data.to_vec());
+            }
+        }
+        Ok::<_, BlockError>(())
+    })?;
+
+    Ok(ProcessedBlock {
+        slot,
+        tapes,
+        writes,
+        mines: streamed.mines,
+        claims: streamed.claims,
+    })
+}
+
+/// Whether a block holds a successful transaction that loads `program_id`, checked on the
+/// account lists of a block fetched with `TransactionDetails::Accounts`. A transaction has to
+/// load every program it invokes, through a CPI too, so a block failing this has nothing for
+/// the archive. Transactions fetched with full details are decoded and checked the same way.
+pub fn block_loads_program(program_id: &Pubkey, block: &UiConfirmedBlock) -> bool {
+    let program_id = program_id.to_string();
+    block.transactions.iter().flatten().any(|tx| {
+        if is_failed_transaction(tx) {
+            return false;
+        }
+
+        match &tx.transaction {
+            // Accounts lists include the keys loaded from lookup tables
+            EncodedTransaction::Accounts(list) => list.account_keys.iter().any(|key| key.pubkey == program_id),
+            _ => decode_message(tx).is_some_and(|(account_keys, _)| account_keys.contains(&program_id)),
+        }
+    })
+}
+
+/// Pass every write in a block to `sink` as it's found, in the order the block applied them,
+/// so a later write to a segment comes after an earlier one. Transactions are processed and
+/// dropped one at a time, so only a single transaction's tape data is buffered no matter how
+/// large the block is.
+///
+/// A segment written more than once in the block, say by a transaction that was retried and
+/// landed twice, keeps the first write. The rest are returned in [`StreamedBlock::duplicates`].
+///
+/// A transaction whose instructions and events don't line up fails the whole block, but writes
+/// from transactions before it have already been passed on. Processing the block again writes
+/// the same data.
+pub fn stream_block<E, F>(program: &ProgramContext, block: UiConfirmedBlock, slot: u64, mut sink: F) -> Result<StreamedBlock, E>
+where
+    E: From<BlockError>,
+    F: FnMut(BlockWrite<'_>) -> Result<(), E>,
+{
     let transactions = block.transactions.ok_or(BlockError::NoTransactions)?;
-    let mut tape_block = TapeBlock::default();
+    let mut streamed = StreamedBlock { slot, ..Default::default() };
+    let mut written = WrittenSegments::default();
+    let (mut num_writes, mut num_updates, mut num_finalize) = (0, 0, 0);
 
     for tx in transactions {
-        process_transaction(&tx, &mut tape_block)?;
-    }
+        let mut tape_block = TapeBlock::default();
+        process_transaction(&program.program_id, &tx, &mut tape_block)?;
+        drop(tx);
+
+        let (writes, updates, finalizes) = verify_counts(&tape_block)?;
+        num_writes += writes;
+        num_updates += updates;
+        num_finalize += finalizes;
+
+        merge_events_and_instructions(&tape_block, &mut |write| {
+            if let Some(duplicate) = written.check(&write) {
+                println!(
+                    "DEBUG: dropping duplicate write of segment {} of tape {} in slot {} ({})",
+                    duplicate.segment_number,
+                    duplicate.address,
+                    slot,
+                    if duplicate.identical { "identical" } else { "conflicting" },
+                );
+                streamed.duplicates.push(duplicate);
+                return Ok(());
+            }
+
+            match write {
+                BlockWrite::Tape { .. } => streamed.tapes += 1,
+                BlockWrite::Segment { .. } => streamed.segments += 1,
+            }
+            sink(write)
+        })?;
 
-    let (num_writes, num_updates, num_finalize) = verify_counts(&tape_block)?;
-    let (tapes, writes) = merge_events_and_instructions(&tape_block)?;
+        for event in tape_block.miner_events {
+            match event {
+                MinerEvent::Mine(event) => streamed.mines.push(event),
+                MinerEvent::Claim(event) => streamed.claims.push(event),
+            }
+        }
+    }
 
-    if !(tapes.is_empty() && writes.is_empty()) {
+    if streamed.tapes > 0 || streamed.segments > 0 || !streamed.duplicates.is_empty() {
         println!(
-            "DEBUG: TapeBlock {}: {} write, {} update, {} finalize, {} tapes, {} writes",
+            "DEBUG: TapeBlock {}: {} write, {} update, {} finalize, {} tapes, {} writes, {} duplicates",
             slot,
             num_writes,
             num_updates,
             num_finalize,
-            tapes.len(),
-            writes.len()
+            streamed.tapes,
+            streamed.segments,
+            streamed.duplicates.len()
         );
     }
 
-    Ok(ProcessedBlock {
-        slot,
-        tapes,
-        writes,
-    })
+    Ok(streamed)
 }
 
 fn verify_counts(tape_block: &TapeBlock) -> Result<(u64, u64, u64), BlockError> {
@@ -151,75 +325,84 @@ fn verify_counts(tape_block: &TapeBlock) -> Result<(u64, u64, u64), BlockError>
     Ok((write_events, update_events, finalize_events))
 }
 
-fn merge_events_and_instructions(
+fn merge_events_and_instructions<E>(
     tape_block: &TapeBlock,
-) -> Result<(HashMap<Pubkey, u64>, HashMap<(Pubkey, u64), Vec<u8>>), BlockError> {
+    emit: &mut impl FnMut(BlockWrite<'_>) -> Result<(), E>,
+) -> Result<(), E>
+where
+    E: From<BlockError>,
+{
     if tape_block.events.len() != tape_block.instructions.len() {
-        return Err(BlockError::CountMismatch("events and instructions"));
+        return Err(BlockError::CountMismatch("events and instructions").into());
     }
 
-    let mut tapes = HashMap::new();
-    let mut writes = HashMap::new();
-
     // Iterate over events and instructions in parallel
     for (event, instruction) in tape_block.events.iter().zip(&tape_block.instructions) {
         match (event, instruction) {
             (TapeEvent::Write(write_event), TapeInstruction::Write { address, data }) => {
                 if write_event.address != address.to_bytes() {
-                    return Err(BlockError::InvalidData("Write event and instruction address mismatch"));
+                    return Err(BlockError::InvalidData("Write event and instruction address mismatch").into());
                 }
 
                 let base = write_event
                     .num_total
                     .saturating_sub(write_event.num_added);
 
-                // A single write instruction can contain multiple segments
-                let segments: Vec<&[u8]> = data.chunks(SEGMENT_SIZE).collect();
-
-                // Sanity check: number of chunks must match num_added
-                if segments.len() as u64 != write_event.num_added {
-                    return Err(BlockError::InvalidData("Segment count does not match num_added"));
+                // A single write instruction can contain multiple segments, the number of chunks
+                // must match num_added
+                if data.len().div_ceil(SEGMENT_SIZE) as u64 != write_event.num_added {
+                    return Err(BlockError::InvalidData("Segment count does not match num_added").into());
                 }
 
-                for (i, segment) in segments.into_iter().enumerate() {
-                    let segment_number = base + i as u64;
-                    writes.insert((*address, segment_number), segment.to_vec());
+                for (i, segment) in data.chunks(SEGMENT_SIZE).enumerate() {
+                    emit(BlockWrite::Segment {
+                        address: *address,
+                        segment_number: base + i as u64,
+                        data: segment,
+                        update: false,
+                    })?;
                 }
             }
 
             (TapeEvent::Update(update_event), TapeInstruction::Update { address, segment_number, new_data, .. }) => {
                 if update_event.address != address.to_bytes() {
-                   return Err(BlockError::InvalidData("Update event/address mismatch"));
+                   return Err(BlockError::InvalidData("Update event/address mismatch").into());
                 }
 
                 if update_event.segment_number != *segment_number {
-                    return Err(BlockError::InvalidData("Update event segment number mismatch"));
+                    return Err(BlockError::InvalidData("Update event segment number mismatch").into());
                 }
 
                 println!("DEBUG: updating segment {} of tape {}", segment_number, address);
 
                 // Record the “new_data”, effectively overwriting that segment:
-                writes.insert((*address, *segment_number), new_data.to_vec());
+                emit(BlockWrite::Segment {
+                    address: *address,
+                    segment_number: *segment_number,
+                    data: new_data,
+                    update: true,
+                })?;
 
                 // (optional) verify old_data + proof
             }
 
             (TapeEvent::Finalize(finalize_event), TapeInstruction::Finalize { address }) => {
                 if finalize_event.address != address.to_bytes() {
-                    return Err(BlockError::InvalidData("Finalize event and instruction address mismatch"));
+                    return Err(BlockError::InvalidData("Finalize event and instruction address mismatch").into());
                 }
 
-                tapes.insert(*address, finalize_event.tape);
+                emit(BlockWrite::Tape { address: *address, number: finalize_event.tape })?;
             }
 
-            _ => return Err(BlockError::InvalidData("Event/instruction type mismatch")),
+            _ => return Err(BlockError::InvalidData("Event/instruction type mismatch").into()),
         }
     }
 
-    Ok((tapes, writes))
+    Ok(())
 }
 
 fn process_transaction(
+    program_id: &Pubkey,
     tx: &EncodedTransactionWithStatusMeta,
     tape_block: &mut TapeBlock,
 ) -> Result<(), BlockError> {
@@ -228,41 +411,129 @@ fn process_transaction(
         return Ok(());
     }
 
-    let encoded_tx = &tx.transaction;
-    let ui_transaction = match encoded_tx {
-        EncodedTransaction::Json(ui_tx) => ui_tx,
-        _ => {
-            println!("DEBUG: Skipping non-JSON encoded transaction");
-            return Ok(());
-        }
+    let Some((account_keys, instructions)) = decode_message(tx) else {
+        println!("DEBUG: Skipping transaction without instructions");
+        return Ok(());
     };
 
-    match &ui_transaction.message {
-        UiMessage::Raw(raw_message) => {
-            if let Some(meta) = &tx.meta {
-                if let OptionSerializer::Some(log_messages) = &meta.log_messages {
-                    process_log_messages(log_messages, tape_block)?;
-                } else {
-                    println!("DEBUG: meta has no log messages");
-                }
+    if let Some(meta) = &tx.meta {
+        if let OptionSerializer::Some(log_messages) = &meta.log_messages {
+            process_log_messages(program_id, log_messages, tape_block)?;
+        } else {
+            println!("DEBUG: meta has no log messages");
+        }
+    }
+
+    process_top_level_instructions(program_id, &account_keys, &instructions, tape_block)?;
+    process_inner_instructions(program_id, &account_keys, &tx.meta, tape_block)?;
+    Ok(())
+}
+
+/// The full account key list and top-level instructions of a transaction, whichever way the RPC
+/// encoded it: raw or parsed JSON, or a binary legacy or v0 transaction. Returns `None` for
+/// encodings that carry no instructions.
+fn decode_message(
+    tx: &EncodedTransactionWithStatusMeta,
+) -> Option<(Vec<String>, Vec<UiCompiledInstruction>)> {
+    match &tx.transaction {
+        EncodedTransaction::Json(ui_tx) => match &ui_tx.message {
+            UiMessage::Raw(raw_message) => {
+                // v0 transactions may load accounts (like the tape) from lookup tables
+                let account_keys = with_loaded_addresses(&raw_message.account_keys, &tx.meta);
+                Some((account_keys, raw_message.instructions.clone()))
+            }
+            UiMessage::Parsed(parsed_message) => {
+                // Parsed keys already include the ones loaded from lookup tables
+                let account_keys: Vec<String> = parsed_message
+                    .account_keys
+                    .iter()
+                    .map(|key| key.pubkey.clone())
+                    .collect();
+
+                let instructions = parsed_message
+                    .instructions
+                    .iter()
+                    .filter_map(|ix| compile_instruction(ix, &account_keys))
+                    .collect();
+
+                Some((account_keys, instructions))
             }
+        },
+        EncodedTransaction::Accounts(_) => None,
+        binary => {
+            let versioned_tx = binary.decode()?;
+            let static_keys: Vec<String> = versioned_tx
+                .message
+                .static_account_keys()
+                .iter()
+                .map(|key| key.to_string())
+                .collect();
+            let account_keys = with_loaded_addresses(&static_keys, &tx.meta);
+
+            let instructions = versioned_tx
+                .message
+                .instructions()
+                .iter()
+                .map(|ix| UiCompiledInstruction {
+                    program_id_index: ix.program_id_index,
+                    accounts: ix.accounts.clone(),
+                    data: bs58::encode(&ix.data).into_string(),
+                    stack_height: None,
+                })
+                .collect();
+
+            Some((account_keys, instructions))
+        }
+    }
+}
 
-            process_top_level_instructions(
-                &raw_message.account_keys,
-                &raw_message.instructions,
-                tape_block,
-            )?;
-            process_inner_instructions(&raw_message.account_keys, &tx.meta, tape_block)?;
-            Ok(())
+/// An instruction as compiled against `account_keys`. Instructions of programs the RPC knows how
+/// to parse are dropped, the tape program is never one of them.
+fn compile_instruction(ix: &UiInstruction, account_keys: &[String]) -> Option<UiCompiledInstruction> {
+    match ix {
+        UiInstruction::Compiled(compiled) => Some(compiled.clone()),
+        UiInstruction::Parsed(UiParsedInstruction::PartiallyDecoded(partial)) => {
+            compile_partially_decoded(partial, account_keys)
         }
-        _ => {
-            println!("DEBUG: Skipping non-raw message");
-            Ok(())
+        UiInstruction::Parsed(UiParsedInstruction::Parsed(_)) => None,
+    }
+}
+
+fn compile_partially_decoded(
+    ix: &UiPartiallyDecodedInstruction,
+    account_keys: &[String],
+) -> Option<UiCompiledInstruction> {
+    let index_of = |key: &String| -> Option<u8> {
+        account_keys.iter().position(|k| k == key)?.try_into().ok()
+    };
+
+    Some(UiCompiledInstruction {
+        program_id_index: index_of(&ix.program_id)?,
+        accounts: ix.accounts.iter().map(index_of).collect::<Option<Vec<_>>>()?,
+        data: ix.data.clone(),
+        stack_height: ix.stack_height,
+    })
+}
+
+/// Returns the full account key list of a transaction, the static keys followed by any keys
+/// loaded from address lookup tables (writable, then readonly), matching how instruction
+/// account indexes are resolved.
+fn with_loaded_addresses(
+    account_keys: &[String],
+    meta: &Option<UiTransactionStatusMeta>,
+) -> Vec<String> {
+    let mut keys = account_keys.to_vec();
+    if let Some(meta) = meta {
+        if let OptionSerializer::Some(loaded) = &meta.loaded_addresses {
+            keys.extend(loaded.writable.iter().cloned());
+            keys.extend(loaded.readonly.iter().cloned());
         }
     }
+    keys
 }
 
 fn process_log_messages(
+    tape_program_id: &Pubkey,
     log_messages: &[String],
     tape_block: &mut TapeBlock,
 ) -> Result<(), BlockError> {
@@ -278,7 +549,7 @@ fn process_log_messages(
             program_stack.pop();
         }
 
-        let is_tape_program = program_stack.last() == Some(&tape_api::ID);
+        let is_tape_program = program_stack.last() == Some(tape_program_id);
 
         if is_tape_program && is_program_data(log) {
             let event_data =
@@ -303,6 +574,16 @@ fn process_log_messages(
                         .map_err(|e| BlockError::Deserialization(e.to_string()))?;
                     events.push(TapeEvent::Finalize(*event));
                 }
+                EventType::MineEvent => {
+                    let event = MineEvent::try_from_bytes(&event_data)
+                        .map_err(|e| BlockError::Deserialization(e.to_string()))?;
+                    tape_block.miner_events.push(MinerEvent::Mine(*event));
+                }
+                EventType::ClaimEvent => {
+                    let event = ClaimEvent::try_from_bytes(&event_data)
+                        .map_err(|e| BlockError::Deserialization(e.to_string()))?;
+                    tape_block.miner_events.push(MinerEvent::Claim(*event));
+                }
                 _ => println!("DEBUG: Unknown event type"),
             }
         }
@@ -312,6 +593,7 @@ fn process_log_messages(
 }
 
 fn process_top_level_instructions(
+    tape_program_id: &Pubkey,
     account_keys: &[String],
     instructions: &[UiCompiledInstruction],
     tape_block: &mut TapeBlock,
@@ -326,7 +608,7 @@ fn process_top_level_instructions(
         let program_id = account_keys[program_id_index]
             .parse::<Pubkey>()
             .map_err(|_| BlockError::InvalidPubkey)?;
-        if program_id == tape_api::ID {
+        if program_id == *tape_program_id {
             let tape_ix = process_instruction(ix, account_keys)?;
             if let Some(ix) = tape_ix {
                 tape_block.instructions.push(ix);
@@ -338,6 +620,7 @@ fn process_top_level_instructions(
 }
 
 fn process_inner_instructions(
+    tape_program_id: &Pubkey,
     account_keys: &[String],
     meta: &Option<UiTransactionStatusMeta>,
     tape_block: &mut TapeBlock,
@@ -354,7 +637,7 @@ fn process_inner_instructions(
 
     for inner_ix_set in inner_instructions {
         for inner_ix in &inner_ix_set.instructions {
-            if let UiInstruction::Compiled(compiled_ix) = inner_ix {
+            if let Some(compiled_ix) = compile_instruction(inner_ix, account_keys) {
                 let program_id_index = compiled_ix.program_id_index as usize;
                 if program_id_index >= account_keys.len() {
                     //println!("DEBUG: Invalid program ID index in inner instruction");
@@ -364,8 +647,8 @@ fn process_inner_instructions(
                 let program_id = account_keys[program_id_index]
                     .parse::<Pubkey>()
                     .map_err(|_| BlockError::InvalidPubkey)?;
-                if program_id == tape_api::ID {
-                    let tape_ix = process_instruction(compiled_ix, account_keys)?;
+                if program_id == *tape_program_id {
+                    let tape_ix = process_instruction(&compiled_ix, account_keys)?;
                     if let Some(ix) = tape_ix {
                         tape_block.instructions.push(ix);
                     }
@@ -468,3 +751,145 @@ fn get_event_data(log: &str) -> Option<Vec<u8>> {
     let encoded_data = log.strip_prefix("Program data: ")?;
     base64::decode(encoded_data).ok()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::transaction::{Transaction, VersionedTransaction};
+    use solana_transaction_status::{
+        parse_accounts::ParsedAccount, TransactionBinaryEncoding, UiAccountsList, UiParsedMessage, UiTransaction,
+    };
+    use tape_api::prelude::build_write_ix;
+
+    fn write_tx(tape: Pubkey) -> Transaction {
+        let ix = build_write_ix(Pubkey::new_unique(), tape, Pubkey::new_unique(), b"hello");
+        let payer = ix.accounts[0].pubkey;
+        Transaction::new_with_payer(&[ix], Some(&payer))
+    }
+
+    fn assert_write(tx: EncodedTransaction, tape: Pubkey) {
+        let tx = EncodedTransactionWithStatusMeta { transaction: tx, meta: None, version: None };
+        let mut tape_block = TapeBlock::default();
+        process_transaction(&tape_api::ID, &tx, &mut tape_block).unwrap();
+
+        match tape_block.instructions.as_slice() {
+            [TapeInstruction::Write { address, data }] => {
+                assert_eq!(*address, tape);
+                // Archived as sent, discriminator first
+                assert_eq!(data[0], InstructionType::Write as u8);
+                assert_eq!(&data[1..], b"hello");
+            }
+            other => panic!("expected one write, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_binary_transaction() {
+        let tape = Pubkey::new_unique();
+        let tx = VersionedTransaction::from(write_tx(tape));
+        let bytes = bincode::serialize(&tx).unwrap();
+
+        assert_write(EncodedTransaction::Binary(base64::encode(&bytes), TransactionBinaryEncoding::Base64), tape);
+        assert_write(EncodedTransaction::LegacyBinary(bs58::encode(&bytes).into_string()), tape);
+    }
+
+    #[test]
+    fn test_parsed_transaction() {
+        let tape = Pubkey::new_unique();
+        let tx = write_tx(tape);
+        let keys = &tx.message.account_keys;
+        let ix = &tx.message.instructions[0];
+
+        let message = UiParsedMessage {
+            account_keys: keys
+                .iter()
+                .map(|key| ParsedAccount { pubkey: key.to_string(), writable: true, signer: false, source: None })
+                .collect(),
+            recent_blockhash: String::new(),
+            instructions: vec![UiInstruction::Parsed(UiParsedInstruction::PartiallyDecoded(
+                UiPartiallyDecodedInstruction {
+                    program_id: keys[ix.program_id_index as usize].to_string(),
+                    accounts: ix.accounts.iter().map(|&i| keys[i as usize].to_string()).collect(),
+                    data: bs58::encode(&ix.data).into_string(),
+                    stack_height: None,
+                },
+            ))],
+            address_table_lookups: None,
+        };
+
+        assert_write(
+            EncodedTransaction::Json(UiTransaction { signatures: vec![], message: UiMessage::Parsed(message) }),
+            tape,
+        );
+    }
+
+    #[test]
+    fn test_block_loads_program() {
+        let accounts = |keys: &[Pubkey]| EncodedTransactionWithStatusMeta {
+            transaction: EncodedTransaction::Accounts(UiAccountsList {
+                signatures: vec![],
+                account_keys: keys
+                    .iter()
+                    .map(|key| ParsedAccount { pubkey: key.to_string(), writable: false, signer: false, source: None })
+                    .collect(),
+            }),
+            meta: None,
+            version: None,
+        };
+        let block = |transactions| UiConfirmedBlock {
+            previous_blockhash: String::new(),
+            blockhash: String::new(),
+            parent_slot: 0,
+            transactions: Some(transactions),
+            signatures: None,
+            rewards: None,
+            num_reward_partitions: None,
+            block_time: None,
+            block_height: None,
+        };
+
+        let other = accounts(&[Pubkey::new_unique(), Pubkey::new_unique()]);
+        let tape = accounts(&[Pubkey::new_unique(), tape_api::ID]);
+        assert!(!block_loads_program(&tape_api::ID, &block(vec![other.clone()])));
+        assert!(block_loads_program(&tape_api::ID, &block(vec![other, tape])));
+        assert!(!block_loads_program(&tape_api::ID, &block(vec![])));
+
+        // Full transactions are checked too
+        let tx = VersionedTransaction::from(write_tx(Pubkey::new_unique()));
+        let full = EncodedTransactionWithStatusMeta {
+            transaction: EncodedTransaction::Binary(
+                base64::encode(bincode::serialize(&tx).unwrap()),
+                TransactionBinaryEncoding::Base64,
+            ),
+            meta: None,
+            version: None,
+        };
+        assert!(block_loads_program(&tape_api::ID, &block(vec![full])));
+    }
+
+    #[test]
+    fn test_duplicate_writes() {
+        let tape = Pubkey::new_unique();
+        let write = |segment_number, data, update| BlockWrite::Segment { address: tape, segment_number, data, update };
+        let mut written = WrittenSegments::default();
+
+        assert_eq!(written.check(&write(0, b"first", false)), None);
+        assert_eq!(written.check(&write(1, b"first", false)), None);
+        assert_eq!(written.check(&BlockWrite::Tape { address: tape, number: 1 }), None);
+
+        // A replayed write is dropped, whether or not it matches
+        let duplicate = written.check(&write(0, b"first", false)).unwrap();
+        assert!(duplicate.identical);
+        let duplicate = written.check(&write(0, b"second", false)).unwrap();
+        assert!(!duplicate.identical);
+        assert_eq!(duplicate.data, b"second");
+
+        // Updates always go through, and later writes are checked against them
+        assert_eq!(written.check(&write(1, b"updated", true)), None);
+        assert!(written.check(&write(1, b"updated", false)).unwrap().identical);
+
+        // The same segment of another tape isn't a duplicate
+        let other = BlockWrite::Segment { address: Pubkey::new_unique(), segment_number: 0, data: b"first", update: false };
+        assert_eq!(written.check(&other), None);
+    }
+}
@@ -0,0 +1,162 @@
+use std::fmt;
+use tokio::time::Duration;
+
+/// Where a circuit breaker is at.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BreakerState {
+    /// The RPC is working, iterations run at the normal interval.
+    Closed,
+    /// Too many consecutive failures, iterations back off exponentially.
+    Open,
+    /// The next iteration is a probe, one success closes the breaker again.
+    HalfOpen,
+}
+
+impl BreakerState {
+    pub fn to_u8(self) -> u8 {
+        match self {
+            BreakerState::Closed => 0,
+            BreakerState::Open => 1,
+            BreakerState::HalfOpen => 2,
+        }
+    }
+
+    pub fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(BreakerState::Closed),
+            1 => Some(BreakerState::Open),
+            2 => Some(BreakerState::HalfOpen),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for BreakerState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BreakerState::Closed => write!(f, "closed"),
+            BreakerState::Open => write!(f, "open"),
+            BreakerState::HalfOpen => write!(f, "half-open"),
+        }
+    }
+}
+
+/// Keeps a loop from hammering an RPC endpoint that keeps failing. After `threshold` consecutive
+/// failed iterations the breaker opens and the wait between iterations doubles on every further
+/// failure, up to `max_delay`. Each iteration while open is a probe, the first success closes it.
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    threshold: u32,
+    interval: Duration,
+    max_delay: Duration,
+    failures: u32,
+    state: BreakerState,
+}
+
+impl CircuitBreaker {
+    pub fn new(threshold: u32, interval: Duration, max_delay: Duration) -> Self {
+        Self {
+            threshold: threshold.max(1),
+            interval,
+            max_delay,
+            failures: 0,
+            state: BreakerState::Closed,
+        }
+    }
+
+    pub fn state(&self) -> BreakerState {
+        self.state
+    }
+
+    /// Consecutive failed iterations.
+    pub fn failures(&self) -> u32 {
+        self.failures
+    }
+
+    /// Call before an iteration, an open breaker lets it through as a probe.
+    pub fn before_attempt(&mut self) {
+        if self.state == BreakerState::Open {
+            self.state = BreakerState::HalfOpen;
+        }
+    }
+
+    /// Record the outcome of an iteration. Returns the new state if it changed.
+    pub fn record<T, E>(&mut self, result: &Result<T, E>) -> Option<BreakerState> {
+        let previous = self.state;
+
+        if result.is_ok() {
+            self.failures = 0;
+            self.state = BreakerState::Closed;
+        } else {
+            self.failures = self.failures.saturating_add(1);
+            if self.failures >= self.threshold {
+                self.state = BreakerState::Open;
+            }
+        }
+
+        // A failed probe leaves the breaker open, it was open before the probe too
+        let changed = match (previous, self.state) {
+            (BreakerState::HalfOpen, BreakerState::Open) => false,
+            (previous, state) => previous != state,
+        };
+        changed.then_some(self.state)
+    }
+
+    /// How long to wait before the next iteration.
+    pub fn delay(&self) -> Duration {
+        if self.state == BreakerState::Closed {
+            return self.interval;
+        }
+
+        let doublings = (self.failures - self.threshold + 1).min(31);
+        self.interval
+            .saturating_mul(1u32 << doublings)
+            .min(self.max_delay)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_breaker_opens_and_backs_off() {
+        let mut breaker = CircuitBreaker::new(3, Duration::from_secs(2), Duration::from_secs(30));
+        let fail: Result<(), ()> = Err(());
+        let ok: Result<(), ()> = Ok(());
+
+        assert_eq!(breaker.record(&fail), None);
+        assert_eq!(breaker.record(&fail), None);
+        assert_eq!(breaker.delay(), Duration::from_secs(2));
+
+        assert_eq!(breaker.record(&fail), Some(BreakerState::Open));
+        assert_eq!(breaker.delay(), Duration::from_secs(4));
+
+        // A failed probe keeps it open and doubles the wait
+        breaker.before_attempt();
+        assert_eq!(breaker.state(), BreakerState::HalfOpen);
+        assert_eq!(breaker.record(&fail), None);
+        assert_eq!(breaker.state(), BreakerState::Open);
+        assert_eq!(breaker.delay(), Duration::from_secs(8));
+
+        for _ in 0..10 {
+            breaker.before_attempt();
+            breaker.record(&fail);
+        }
+        assert_eq!(breaker.delay(), Duration::from_secs(30));
+
+        // One successful probe closes it
+        breaker.before_attempt();
+        assert_eq!(breaker.record(&ok), Some(BreakerState::Closed));
+        assert_eq!(breaker.failures(), 0);
+        assert_eq!(breaker.delay(), Duration::from_secs(2));
+    }
+
+    #[test]
+    fn test_breaker_state_bytes() {
+        for state in [BreakerState::Closed, BreakerState::Open, BreakerState::HalfOpen] {
+            assert_eq!(BreakerState::from_u8(state.to_u8()), Some(state));
+        }
+        assert_eq!(BreakerState::from_u8(3), None);
+    }
+}
@@ -0,0 +1,149 @@
+use anyhow::{anyhow, Result};
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::time::{sleep, Duration};
+
+use super::store::TapeStore;
+
+/// How long after a proof the miner is expected to stay idle. It mines once a minute, so the
+/// first half of the minute is safe to spend on IO.
+const IDLE_WINDOW: u64 = 30;
+
+/// A miner that hasn't proved for this long isn't running, the store is idle.
+const MINER_GONE: u64 = 600;
+
+/// When the archive compacts the store, if it compacts it manually.
+#[derive(Clone, Debug)]
+pub struct CompactionPolicy {
+    /// Time between compactions.
+    pub interval: Duration,
+    /// Compact even while the miner is busy once a compaction is this overdue, so the store
+    /// never goes uncompacted for long.
+    pub max_delay: Duration,
+}
+
+impl Default for CompactionPolicy {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(600),
+            max_delay: Duration::from_secs(1800),
+        }
+    }
+}
+
+/// When the miner for a namespace last submitted a proof, shared with the archive through a
+/// file since the two run in separate processes.
+pub struct MinerActivity {
+    path: PathBuf,
+}
+
+impl MinerActivity {
+    pub fn open(namespace: &str) -> Result<Self> {
+        let dir = env::current_dir()?.join("db_tapestore_activity").join(namespace);
+        fs::create_dir_all(&dir)
+            .map_err(|e| anyhow!("Failed to create {}: {}", dir.display(), e))?;
+        Ok(Self { path: dir.join("last_proof") })
+    }
+
+    /// Record a proof that just landed.
+    pub fn record_proof(&self) -> Result<()> {
+        fs::write(&self.path, now().to_string())
+            .map_err(|e| anyhow!("Failed to write {}: {}", self.path.display(), e))
+    }
+
+    /// Unix time of the last proof, `None` if the miner never recorded one.
+    pub fn last_proof(&self) -> Option<u64> {
+        fs::read_to_string(&self.path).ok()?.trim().parse().ok()
+    }
+}
+
+/// Whether a compaction should run at `now`. It's due `interval` after the last one, and runs in
+/// the idle window right after a proof (or with no miner around), unless it has waited past
+/// `max_delay`.
+pub fn should_compact(
+    now: u64,
+    last_compaction: u64,
+    last_proof: Option<u64>,
+    policy: &CompactionPolicy,
+) -> bool {
+    let waited = now.saturating_sub(last_compaction);
+    if waited < policy.interval.as_secs() {
+        return false;
+    }
+
+    let idle = match last_proof {
+        Some(at) => {
+            let since = now.saturating_sub(at);
+            since < IDLE_WINDOW || since > MINER_GONE
+        }
+        None => true,
+    };
+
+    idle || waited >= policy.interval.as_secs() + policy.max_delay.as_secs()
+}
+
+/// Compaction loop that compacts the store in the miner's idle windows. Only useful on a store
+/// opened with manual compaction, RocksDB compacts on its own otherwise. Archiving pauses while
+/// a compaction runs.
+pub async fn compaction_loop(
+    store: &TapeStore,
+    activity: &MinerActivity,
+    policy: &CompactionPolicy,
+) -> Result<()> {
+    let interval = Duration::from_secs(5);
+    let mut last_compaction = now();
+
+    loop {
+        if should_compact(now(), last_compaction, activity.last_proof(), policy) {
+            let started = now();
+            match tokio::task::block_in_place(|| store.compact()) {
+                Ok(()) => println!("DEBUG: Compacted the store in {}s", now() - started),
+                Err(e) => eprintln!("ERROR: Compaction failed: {:?}", e),
+            }
+            last_compaction = now();
+        }
+
+        sleep(interval).await;
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_should_compact() {
+        let policy = CompactionPolicy {
+            interval: Duration::from_secs(600),
+            max_delay: Duration::from_secs(1800),
+        };
+        let start = 1_000_000;
+
+        // Not due yet
+        assert!(!should_compact(start + 100, start, None, &policy));
+
+        // Due, no miner
+        assert!(should_compact(start + 600, start, None, &policy));
+
+        // Due, right after a proof
+        assert!(should_compact(start + 600, start, Some(start + 590), &policy));
+
+        // Due, but the next proof is coming up
+        assert!(!should_compact(start + 600, start, Some(start + 560), &policy));
+
+        // The miner stopped proving a while ago
+        assert!(should_compact(start + 2000, start, Some(start + 100), &policy));
+
+        // Overdue, runs anyway
+        assert!(should_compact(start + 2400, start, Some(start + 2350), &policy));
+    }
+}
@@ -0,0 +1,149 @@
+use std::str::FromStr;
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Deserialize;
+use serde_json::json;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+use tape_client::{fetch_tape_with, file_name, get_tape_account, SegmentSource, TapeHeader};
+
+use super::shared::SecondaryStoreHandle;
+use super::store::{StoreError, TapeStore};
+
+#[derive(Deserialize)]
+pub struct ContentQuery {
+    /// Serve the data as an attachment, so browsers save it instead of showing it.
+    #[serde(default)]
+    download: Option<String>,
+}
+
+impl ContentQuery {
+    fn download(&self) -> bool {
+        matches!(self.download.as_deref(), Some("1" | "true"))
+    }
+}
+
+/// Whether the caller asked for the raw segments as JSON rather than the decoded data.
+fn wants_segments(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::ACCEPT)
+        .and_then(|v| v.to_str().ok())
+        .map(|accept| {
+            accept
+                .split(',')
+                .any(|t| t.split(';').next().unwrap_or("").trim() == "application/json")
+        })
+        .unwrap_or(false)
+}
+
+/// Serve a tape's data, decoded into the bytes that were written, with its MIME type and a
+/// file name made from the tape's name. `?download=1` serves it as an attachment. Asking for
+/// `Accept: application/json` returns the raw segments instead, as `getTape` does.
+///
+/// Example invocation:
+/// ```bash
+/// curl -OJ 'http://127.0.0.1:3000/tape/<PUBKEY>?download=1'
+/// curl -H 'Accept: application/json' http://127.0.0.1:3000/tape/<PUBKEY>
+/// ```
+pub async fn tape_content_handler(
+    State(store): State<Arc<TapeStore>>,
+    State(handle): State<SecondaryStoreHandle>,
+    State(rpc): State<Arc<RpcClient>>,
+    Path(address): Path<String>,
+    Query(query): Query<ContentQuery>,
+    headers: HeaderMap,
+) -> Response {
+    let pk = match Pubkey::from_str(&address) {
+        Ok(pk) => pk,
+        Err(e) => return (StatusCode::BAD_REQUEST, format!("invalid pubkey: {}", e)).into_response(),
+    };
+
+    match store.is_blocked(&pk) {
+        Ok(false) => {}
+        Ok(true) => return (StatusCode::UNAVAILABLE_FOR_LEGAL_REASONS, "tape is not served by this node").into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+
+    let number = match store.get_tape_number(&pk) {
+        Ok(number) => number,
+        Err(StoreError::TapeNotFoundForAddress(_)) => {
+            return (StatusCode::NOT_FOUND, "tape not found").into_response()
+        }
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+    handle.touch_tape(&pk);
+
+    if wants_segments(&headers) {
+        return match store.get_tape_segments(&pk) {
+            Ok(segments) => Json(json!({
+                "tape_address": pk.to_string(),
+                "tape_number": number,
+                "segments": segments
+                    .iter()
+                    .map(|(n, data)| json!({ "segment_number": n, "data": base64::encode(data) }))
+                    .collect::<Vec<_>>(),
+            }))
+            .into_response(),
+            Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+        };
+    }
+
+    // The store only holds segments, the name and header are on the tape account
+    let tape = match get_tape_account(&rpc, &pk).await {
+        Ok((tape, _)) => tape,
+        Err(e) => return (StatusCode::BAD_GATEWAY, e.to_string()).into_response(),
+    };
+    match TapeHeader::try_from_bytes(&tape.header) {
+        Ok(header) if header.is_sealed() => {
+            return (StatusCode::FORBIDDEN, "tape is sealed to a recipient").into_response()
+        }
+        Ok(_) => {}
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+
+    let decoded = match fetch_tape_with(&rpc, &pk, Some(store.as_ref() as &dyn SegmentSource), |_, _| {}).await {
+        Ok(decoded) => decoded,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+
+    let disposition = if query.download() { "attachment" } else { "inline" };
+    let disposition = format!("{}; filename=\"{}\"", disposition, file_name(&tape.name, &decoded.mime));
+    let content_type = HeaderValue::from_str(&decoded.mime)
+        .unwrap_or(HeaderValue::from_static("application/octet-stream"));
+
+    (
+        [
+            (header::CONTENT_TYPE, content_type),
+            // The file name only holds [A-Za-z0-9_-.], always a valid header
+            (header::CONTENT_DISPOSITION, HeaderValue::from_str(&disposition).unwrap()),
+            (header::X_CONTENT_TYPE_OPTIONS, HeaderValue::from_static("nosniff")),
+            // Tapes are anyone's data, don't let an HTML one script this node's pages
+            (header::CONTENT_SECURITY_POLICY, HeaderValue::from_static("sandbox")),
+        ],
+        decoded.bytes,
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wants_segments() {
+        let mut headers = HeaderMap::new();
+        assert!(!wants_segments(&headers));
+
+        headers.insert(header::ACCEPT, HeaderValue::from_static("*/*"));
+        assert!(!wants_segments(&headers));
+
+        headers.insert(header::ACCEPT, HeaderValue::from_static("text/html, application/json;q=0.9"));
+        assert!(wants_segments(&headers));
+    }
+}
@@ -0,0 +1,264 @@
+use std::fmt::Write;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{Html, IntoResponse, Response},
+};
+use chrono::DateTime;
+use solana_sdk::pubkey::Pubkey;
+
+use super::store::{StoreError, TapeStore};
+
+/// Number of tapes listed on the explore page.
+const RECENT_TAPES: usize = 50;
+
+/// Number of segments listed on a tape page.
+const MAX_SEGMENTS_SHOWN: usize = 256;
+
+/// Number of bytes shown in a tape's preview.
+const PREVIEW_BYTES: usize = 1024;
+
+const STYLE: &str = "body{font-family:monospace;margin:2em;max-width:1100px}\
+table{border-collapse:collapse;width:100%}\
+td,th{text-align:left;padding:2px 12px 2px 0;vertical-align:top}\
+pre{background:#f4f4f4;padding:1em;overflow-x:auto;white-space:pre-wrap;word-break:break-all}\
+.muted{color:#888}";
+
+/// Lists the most recent tapes held by this node.
+///
+/// Example:
+/// ```bash
+/// open http://127.0.0.1:3000/explore
+/// ```
+pub async fn explore_handler(State(store): State<Arc<TapeStore>>) -> Response {
+    match render_explore(&store) {
+        Ok(html) => Html(html).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+/// Shows a tape's metadata, its segments and a preview of the data.
+///
+/// Example:
+/// ```bash
+/// open http://127.0.0.1:3000/tape/<PUBKEY>/view
+/// ```
+pub async fn tape_view_handler(
+    State(store): State<Arc<TapeStore>>,
+    Path(address): Path<String>,
+) -> Response {
+    let pk = match Pubkey::from_str(&address) {
+        Ok(pk) => pk,
+        Err(e) => return (StatusCode::BAD_REQUEST, format!("invalid pubkey: {}", e)).into_response(),
+    };
+
+    match store.is_blocked(&pk) {
+        Ok(false) => {}
+        Ok(true) => return (StatusCode::UNAVAILABLE_FOR_LEGAL_REASONS, "tape is not served by this node").into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+
+    match render_tape(&store, &pk) {
+        Ok(html) => Html(html).into_response(),
+        Err(StoreError::TapeNotFoundForAddress(_)) => {
+            (StatusCode::NOT_FOUND, "tape not found").into_response()
+        }
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+fn render_explore(store: &TapeStore) -> Result<String, StoreError> {
+    let mut body = String::new();
+
+    if let Ok((last_processed_slot, drift)) = store.get_health() {
+        let _ = writeln!(
+            body,
+            "<p class=\"muted\">Last processed slot {} (drift {})</p>",
+            last_processed_slot, drift
+        );
+    }
+
+    let tapes = store.get_recent_tapes(RECENT_TAPES)?;
+    if tapes.is_empty() {
+        body.push_str("<p>No tapes archived yet.</p>");
+        return Ok(page("Explore", &body));
+    }
+
+    body.push_str("<table><tr><th>Number</th><th>Address</th><th>Label</th><th>Segments</th><th>Last access</th></tr>");
+    // Blocked tapes aren't listed at all
+    for (number, address) in tapes {
+        if store.is_blocked(&address)? {
+            continue;
+        }
+
+        let segments = store.iter_segments(&address)?.count();
+        let last_access = store.get_last_access(&address)?;
+        let label = store.get_label(&address)?.map(|l| l.label).unwrap_or_default();
+        let _ = writeln!(
+            body,
+            "<tr><td>{}</td><td><a href=\"/tape/{}/view\">{}</a></td><td>{}</td><td>{}</td><td>{}</td></tr>",
+            number,
+            address,
+            address,
+            escape_html(&label),
+            segments,
+            format_time(last_access),
+        );
+    }
+    body.push_str("</table>");
+
+    Ok(page("Explore", &body))
+}
+
+fn render_tape(store: &TapeStore, address: &Pubkey) -> Result<String, StoreError> {
+    let number = store.get_tape_number(address)?;
+    let segments = store.get_tape_segments(address)?;
+    let last_access = store.get_last_access(address)?;
+    let has_thumbnail = store.get_thumbnail(address).is_ok();
+    let label = store.get_label(address)?;
+
+    let total_bytes: usize = segments.iter().map(|(_, data)| data.len()).sum();
+    let missing = segments
+        .last()
+        .map(|(last, _)| last + 1 - segments.len() as u64)
+        .unwrap_or(0);
+
+    let mut body = String::new();
+    body.push_str("<p><a href=\"/explore\">&larr; explore</a></p><table>");
+    let _ = writeln!(body, "<tr><th>Address</th><td>{}</td></tr>", address);
+    let _ = writeln!(body, "<tr><th>Number</th><td>{}</td></tr>", number);
+    let _ = writeln!(body, "<tr><th>Segments</th><td>{} ({} missing)</td></tr>", segments.len(), missing);
+    let _ = writeln!(body, "<tr><th>Size</th><td>{} bytes</td></tr>", total_bytes);
+    let _ = writeln!(body, "<tr><th>Last access</th><td>{}</td></tr>", format_time(last_access));
+    if let Some(label) = label {
+        let _ = writeln!(
+            body,
+            "<tr><th>Label</th><td>{} <span class=\"muted\">(set by this node {})</span></td></tr>",
+            escape_html(&label.label),
+            format_time(Some(label.updated_at)),
+        );
+        if !label.note.is_empty() {
+            let _ = writeln!(body, "<tr><th>Note</th><td>{}</td></tr>", escape_html(&label.note));
+        }
+    }
+    body.push_str("</table>");
+
+    if has_thumbnail {
+        let _ = writeln!(body, "<p><img src=\"/tape/{}/thumbnail\" alt=\"thumbnail\"></p>", address);
+    }
+
+    // Raw segment bytes, linked tapes still carry the previous signature in each write
+    let data: Vec<u8> = segments
+        .iter()
+        .flat_map(|(_, data)| data.iter().copied())
+        .take(PREVIEW_BYTES)
+        .collect();
+
+    body.push_str("<h2>Preview</h2>");
+    match std::str::from_utf8(&data) {
+        Ok(text) if is_printable(text) => {
+            let _ = writeln!(body, "<pre>{}</pre>", escape_html(text));
+        }
+        _ => {
+            let _ = writeln!(body, "<pre>{}</pre>", hex_dump(&data));
+        }
+    }
+
+    body.push_str("<h2>Segments</h2><table><tr><th>Number</th><th>Bytes</th><th>Data</th></tr>");
+    for (number, data) in segments.iter().take(MAX_SEGMENTS_SHOWN) {
+        let _ = writeln!(
+            body,
+            "<tr><td>{}</td><td>{}</td><td>{}</td></tr>",
+            number,
+            data.len(),
+            to_hex(&data[..data.len().min(32)]),
+        );
+    }
+    body.push_str("</table>");
+
+    if segments.len() > MAX_SEGMENTS_SHOWN {
+        let _ = writeln!(
+            body,
+            "<p class=\"muted\">{} more segments not shown, use the getTape RPC method to fetch them all.</p>",
+            segments.len() - MAX_SEGMENTS_SHOWN
+        );
+    }
+
+    Ok(page(&format!("Tape {}", number), &body))
+}
+
+fn page(title: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>{title} | tapedrive</title>\
+         <style>{STYLE}</style></head><body><h1>{title}</h1>{body}</body></html>",
+        title = escape_html(title),
+        body = body,
+    )
+}
+
+fn format_time(timestamp: Option<u64>) -> String {
+    timestamp
+        .and_then(|t| DateTime::from_timestamp(t as i64, 0))
+        .map(|t| t.format("%Y-%m-%d %H:%M:%S UTC").to_string())
+        .unwrap_or_else(|| "-".into())
+}
+
+fn is_printable(text: &str) -> bool {
+    text.chars().all(|c| !c.is_control() || c.is_whitespace())
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+pub(crate) fn to_hex(data: &[u8]) -> String {
+    data.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Classic 16 bytes per line hex dump with an ASCII column.
+fn hex_dump(data: &[u8]) -> String {
+    let mut out = String::new();
+    for (i, line) in data.chunks(16).enumerate() {
+        let hex: Vec<String> = line.iter().map(|b| format!("{:02x}", b)).collect();
+        let ascii: String = line
+            .iter()
+            .map(|&b| if b.is_ascii_graphic() || b == b' ' { b as char } else { '.' })
+            .collect();
+        let _ = writeln!(out, "{:08x}  {:<47}  {}", i * 16, hex.join(" "), escape_html(&ascii));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape_html() {
+        assert_eq!(escape_html("<a href=\"x\">&</a>"), "&lt;a href=&quot;x&quot;&gt;&amp;&lt;/a&gt;");
+    }
+
+    #[test]
+    fn test_hex_dump() {
+        let dump = hex_dump(b"hello, <world>\x00\x01\x02");
+        let lines: Vec<&str> = dump.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("00000000  68 65 6c 6c 6f"));
+        assert!(lines[0].ends_with("hello, &lt;world&gt;.."));
+        assert!(lines[1].starts_with("00000010  02"));
+    }
+}
@@ -0,0 +1,179 @@
+use anyhow::Result;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashSet;
+use tokio::time::{sleep, Duration};
+
+use super::store::TapeStore;
+
+/// Limits the local store is kept within by the garbage collector.
+#[derive(Clone, Debug)]
+pub struct GcPolicy {
+    /// Maximum bytes of segment data to keep on disk (measured uncompressed, so actual disk
+    /// usage stays below this).
+    pub max_disk_bytes: u64,
+
+    /// Tapes that are never collected, regardless of how long ago they were accessed.
+    pub pinned: HashSet<Pubkey>,
+}
+
+/// A tape selected for collection.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GcCandidate {
+    pub address: Pubkey,
+    pub bytes: u64,
+    pub last_access: Option<u64>,
+}
+
+/// The outcome of evaluating a policy against the store.
+#[derive(Clone, Debug, Default)]
+pub struct GcPlan {
+    /// Bytes of segment data currently held.
+    pub total_bytes: u64,
+
+    /// Bytes of segment data held once the plan is applied.
+    pub remaining_bytes: u64,
+
+    /// Tapes to evict, least recently accessed first.
+    pub evict: Vec<GcCandidate>,
+}
+
+/// Decide which tapes to evict so the store fits the policy. Least recently accessed tapes go
+/// first, pinned tapes are skipped. If the pinned tapes alone exceed the limit, the plan evicts
+/// everything else and leaves the store over budget.
+pub fn plan_gc(store: &TapeStore, policy: &GcPolicy) -> Result<GcPlan> {
+    let sizes = store.get_tape_sizes()?;
+    let total_bytes: u64 = sizes.values().sum();
+
+    let mut plan = GcPlan {
+        total_bytes,
+        remaining_bytes: total_bytes,
+        evict: Vec::new(),
+    };
+
+    if total_bytes <= policy.max_disk_bytes {
+        return Ok(plan);
+    }
+
+    let mut candidates = Vec::new();
+    for (address, bytes) in sizes {
+        if policy.pinned.contains(&address) {
+            continue;
+        }
+
+        let last_access = store.get_last_access(&address)?;
+        candidates.push(GcCandidate { address, bytes, last_access });
+    }
+
+    // Tapes without an access record sort first (None < Some)
+    candidates.sort_by_key(|c| (c.last_access, c.address));
+
+    for candidate in candidates {
+        if plan.remaining_bytes <= policy.max_disk_bytes {
+            break;
+        }
+
+        plan.remaining_bytes -= candidate.bytes;
+        plan.evict.push(candidate);
+    }
+
+    Ok(plan)
+}
+
+/// Evict every tape in the plan.
+pub fn apply_gc(store: &TapeStore, plan: &GcPlan) -> Result<()> {
+    for candidate in &plan.evict {
+        store.evict_tape(&candidate.address)?;
+        log::info!("Evicted tape {} ({} bytes)", candidate.address, candidate.bytes);
+    }
+
+    Ok(())
+}
+
+/// GC loop that periodically brings the store back within the policy limits.
+pub async fn gc_loop(store: &TapeStore, policy: &GcPolicy) -> Result<()> {
+    let interval = Duration::from_secs(60);
+
+    loop {
+        match plan_gc(store, policy).and_then(|plan| apply_gc(store, &plan)) {
+            Ok(()) => {}
+            Err(e) => log::error!("GC iteration failed: {:?}", e),
+        }
+
+        sleep(interval).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempdir::TempDir;
+
+    #[test]
+    fn test_plan_gc_skips_pinned() -> Result<()> {
+        let temp_dir = TempDir::new("gc_test")?;
+        let store = TapeStore::new(temp_dir.path())?;
+
+        let old = Pubkey::new_unique();
+        let pinned = Pubkey::new_unique();
+        let recent = Pubkey::new_unique();
+
+        store.add_segment(&old, 0, vec![0; 10])?;
+        store.add_segment(&pinned, 0, vec![0; 10])?;
+        store.add_segment(&recent, 0, vec![0; 10])?;
+
+        let policy = GcPolicy {
+            max_disk_bytes: 15,
+            pinned: HashSet::from([pinned, old]),
+        };
+
+        let plan = plan_gc(&store, &policy)?;
+        assert_eq!(plan.total_bytes, 30);
+        assert_eq!(plan.remaining_bytes, 20);
+        assert_eq!(plan.evict.len(), 1);
+        assert_eq!(plan.evict[0].address, recent);
+
+        let policy = GcPolicy {
+            max_disk_bytes: 30,
+            pinned: HashSet::new(),
+        };
+        assert!(plan_gc(&store, &policy)?.evict.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_plan_gc_evicts_least_recently_read() -> Result<()> {
+        let temp_dir = TempDir::new("gc_test")?;
+        let store = TapeStore::new(temp_dir.path())?;
+
+        let (a, b, c, never) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        for tape in [a, b, c, never] {
+            store.add_segment(&tape, 0, vec![0; 10])?;
+        }
+
+        // Read a, then c, then b
+        store.touch_tape_at(&a, 100)?;
+        store.touch_tape_at(&c, 200)?;
+        store.touch_tape_at(&b, 300)?;
+
+        let policy = GcPolicy {
+            max_disk_bytes: 10,
+            pinned: HashSet::new(),
+        };
+        let evicted = |plan: GcPlan| plan.evict.iter().map(|c| c.address).collect::<Vec<_>>();
+        assert_eq!(evicted(plan_gc(&store, &policy)?), vec![never, a, c]);
+
+        // Reading a again moves it to the back, a read queued earlier doesn't undo that
+        store.touch_tape_at(&a, 400)?;
+        store.touch_tape_at(&a, 150)?;
+        assert_eq!(store.get_last_access(&a)?, Some(400));
+        assert_eq!(evicted(plan_gc(&store, &policy)?), vec![never, c, b]);
+
+        let plan = plan_gc(&store, &policy)?;
+        apply_gc(&store, &plan)?;
+        assert_eq!(plan_gc(&store, &policy)?.total_bytes, 10);
+        assert!(store.get_tape_sizes()?.contains_key(&a));
+
+        Ok(())
+    }
+}
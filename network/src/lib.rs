@@ -3,3 +3,16 @@ pub mod mine;
 pub mod block;
 pub mod store;
 pub mod web;
+pub mod explore;
+pub mod content;
+pub mod thumbnail;
+pub mod gc;
+pub mod breaker;
+pub mod shared;
+pub mod admin;
+pub mod compaction;
+pub mod snapshot;
+pub mod relay;
+pub mod pool;
+pub mod alerts;
+pub mod logfile;
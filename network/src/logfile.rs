@@ -0,0 +1,151 @@
+use anyhow::{anyhow, Result};
+use std::env;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// Environment variable with the most detailed level of log lines to print, e.g. `info` to leave
+/// out debug lines. Debug lines and up are printed when it isn't set.
+pub const LOG_LEVEL_ENV: &str = "TAPEDRIVE_LOG";
+
+/// The file this process logs to, if it was started with one.
+static LOG_FILE: OnceLock<PathBuf> = OnceLock::new();
+
+static LOGGER: NodeLogger = NodeLogger;
+
+/// Prints `log` records as the node prints everything else, `LEVEL: message`, warnings and
+/// errors to stderr and the rest to stdout. Other crates only get their warnings and errors
+/// through, their debug output is too chatty to mix in.
+struct NodeLogger;
+
+impl log::Log for NodeLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= log::max_level()
+            && (metadata.target().starts_with("tape") || metadata.level() <= log::Level::Warn)
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        match record.level() {
+            log::Level::Error | log::Level::Warn => eprintln!("{}: {}", record.level(), record.args()),
+            _ => println!("{}: {}", record.level(), record.args()),
+        }
+    }
+
+    fn flush(&self) {
+        let _ = io::stdout().flush();
+        let _ = io::stderr().flush();
+    }
+}
+
+/// Print `log` records at the level set in `TAPEDRIVE_LOG`. Call once, before anything logs.
+pub fn init_logger() -> Result<()> {
+    let level = match env::var(LOG_LEVEL_ENV) {
+        Ok(level) if !level.trim().is_empty() => level
+            .trim()
+            .parse::<log::LevelFilter>()
+            .map_err(|_| anyhow!("Invalid {} level: {}", LOG_LEVEL_ENV, level))?,
+        _ => log::LevelFilter::Debug,
+    };
+
+    log::set_logger(&LOGGER).map_err(|e| anyhow!("Failed to set up logging: {}", e))?;
+    log::set_max_level(level);
+    Ok(())
+}
+
+/// Send everything the process prints, to stdout and stderr alike, to the end of `path`.
+pub fn init(path: &Path) -> Result<()> {
+    redirect_output(path)?;
+    LOG_FILE
+        .set(path.to_path_buf())
+        .map_err(|_| anyhow!("Already logging to {}", path.display()))
+}
+
+/// Open the log file again at its path. Once logrotate (or an operator) has moved the file
+/// aside, the process stops writing to the moved file and starts a fresh one. Returns the path,
+/// or `None` if the process logs to the terminal and there's nothing to reopen.
+pub fn reopen() -> Result<Option<PathBuf>> {
+    let Some(path) = LOG_FILE.get() else {
+        return Ok(None);
+    };
+
+    redirect_output(path)?;
+    Ok(Some(path.clone()))
+}
+
+#[cfg(unix)]
+fn redirect_output(path: &Path) -> Result<()> {
+    use std::os::fd::AsRawFd;
+
+    // What's buffered was printed before the switch, it belongs in the old file
+    io::stdout().flush()?;
+    io::stderr().flush()?;
+    redirect(path, &[io::stdout().as_raw_fd(), io::stderr().as_raw_fd()])
+}
+
+#[cfg(not(unix))]
+fn redirect_output(_path: &Path) -> Result<()> {
+    Err(anyhow!("Logging to a file is only supported on Unix"))
+}
+
+/// Point every descriptor in `fds` at the end of `path`, created if it's missing. The
+/// descriptors stay open throughout, so nothing writing to them notices the switch.
+#[cfg(unix)]
+fn redirect(path: &Path, fds: &[std::os::fd::RawFd]) -> Result<()> {
+    use std::fs::OpenOptions;
+    use std::os::fd::AsRawFd;
+
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| anyhow!("Failed to open log file {}: {}", path.display(), e))?;
+
+    for &fd in fds {
+        // SAFETY: dup2 only replaces what `fd` refers to, `file` stays valid for the call
+        if unsafe { libc::dup2(file.as_raw_fd(), fd) } < 0 {
+            return Err(anyhow!(
+                "Failed to send output to {}: {}",
+                path.display(),
+                io::Error::last_os_error()
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::fs::{self, File};
+    use std::io::Write;
+    use std::os::fd::AsRawFd;
+    use tempdir::TempDir;
+
+    #[test]
+    fn test_redirect_and_rotate() -> Result<()> {
+        let dir = TempDir::new("logfile")?;
+        let path = dir.path().join("node.log");
+
+        // Stands in for stdout, which the test harness captures
+        let mut output = File::create(dir.path().join("stdout"))?;
+
+        redirect(&path, &[output.as_raw_fd()])?;
+        writeln!(output, "first")?;
+
+        // Rotated: moved aside, then reopened at the same path
+        fs::rename(&path, dir.path().join("node.log.1"))?;
+        writeln!(output, "second")?;
+        redirect(&path, &[output.as_raw_fd()])?;
+        writeln!(output, "third")?;
+
+        assert_eq!(fs::read_to_string(dir.path().join("node.log.1"))?, "first\nsecond\n");
+        assert_eq!(fs::read_to_string(&path)?, "third\n");
+
+        Ok(())
+    }
+}
@@ -1,68 +1,122 @@
 use anyhow::{Result, anyhow};
 use chrono::Utc;
 use solana_client::nonblocking::rpc_client::RpcClient;
-use solana_sdk::{signature::Keypair, pubkey::Pubkey};
+use solana_sdk::{signer::Signer, pubkey::Pubkey};
 use tape_client::mine::mine::perform_mining;
+use tape_client::mine::recall::{proof_matches_root, solve_challenge, RecallProver};
+use tape_client::{SegmentSource, SourceChain, DEFAULT_SOURCE_TIMEOUT};
 use tokio::time::{sleep, Duration};
 
 use tape_client::utils::*;
 use tape_api::prelude::*;
 
-use brine_tree::MerkleTree;
-use crankx::equix::SolverMemory;
-use crankx::{
-    solve_with_memory,
-    Solution, 
-    CrankXError
-};
-
-use super::store::TapeStore;
-
+use super::admin::{AdminQueue, AdminTask};
+use super::alerts::Alerter;
+use super::breaker::CircuitBreaker;
+use super::compaction::MinerActivity;
+use super::store::{StoreError, TapeStore};
+use super::shared::SecondaryStoreHandle;
+
+/// Consecutive failed iterations before the miner backs off the RPC.
+const BREAKER_THRESHOLD: u32 = 3;
+
+/// Longest wait between iterations while the RPC keeps failing.
+const MAX_BACKOFF: Duration = Duration::from_secs(900);
+
+/// A catch-up by another subsystem sharing the store within this long is recent enough.
+const CATCH_UP_MAX_AGE: Duration = Duration::from_secs(15);
+
+/// How many slots the store may trail the cluster by before the miner waits, the point where
+/// the archive reports it's falling behind.
+pub const DEFAULT_MAX_DRIFT: u64 = 200;
+
+/// How soon to look at a recall tape again once the archive has been asked to resync it, well
+/// within the time the miner has to prove the challenge.
+const RESYNC_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Mine with the tapes in the store. Iterations are skipped while the store trails the cluster
+/// by more than `max_drift` slots or is missing part of the recall tape, since the proof would
+/// be built from stale data and fail on-chain. A recall tape with missing segments, or whose
+/// recall proof doesn't check out against the tape's root, is queued on `admin_queue` for the
+/// archive to resync, and the miner checks back every few seconds until it's whole. With a
+/// trusted `peer`, such a tape is proved from the peer's copy in the meantime, if it's whole
+/// and matches the tape's root. Proofs are recorded in `activity`, so the archive can compact
+/// the store while the miner is idle. A miner that stops landing proofs, or keeps failing on
+/// the store, fires `alerts`.
+#[allow(clippy::too_many_arguments)]
 pub async fn mine_loop(
-    store: &TapeStore, 
+    store: &SecondaryStoreHandle,
     client: &RpcClient, 
     miner_address: &Pubkey,
-    signer: &Keypair,
+    signer: &dyn Signer,
+    max_drift: u64,
+    activity: &MinerActivity,
+    admin_queue: &AdminQueue,
+    peer: Option<&dyn SegmentSource>,
+    alerts: &Alerter,
 ) -> Result<()> {
     let interval = Duration::from_secs(60);
+    let mut breaker = CircuitBreaker::new(BREAKER_THRESHOLD, interval, MAX_BACKOFF);
 
     loop {
-        match try_mine_iteration(store, client, miner_address, signer).await {
-            Ok(()) => println!("DEBUG: Mining iteration completed successfully"),
+        breaker.before_attempt();
+
+        let result = try_mine_iteration(store, client, miner_address, signer, max_drift, activity, admin_queue, peer).await;
+        match &result {
+            Ok(_) => println!("DEBUG: Mining iteration completed successfully"),
             Err(e) => {
                 // Log the error (you can use a proper logger like `log::error!` if set up)
                 eprintln!("ERROR: Mining iteration failed: {:?}", e);
             }
         }
 
-        println!("DEBUG: Waiting for next interval...");
-        sleep(interval).await;
+        if let Some(state) = breaker.record(&result) {
+            println!("DEBUG: RPC circuit breaker {} after {} consecutive failures", state, breaker.failures());
+        }
+        alerts.record_iteration(&result).await;
+        alerts.watch_proofs(client, miner_address).await;
+
+        // The challenge is still open while the recall tape is resynced, don't wait a whole interval
+        let delay = match result {
+            Ok(Iteration::Resyncing) => RESYNC_POLL_INTERVAL,
+            _ => breaker.delay(),
+        };
+        println!("DEBUG: Waiting {}s for next interval...", delay.as_secs());
+        sleep(delay).await;
     }
 }
 
+/// How a mining iteration ended, when it didn't fail.
+enum Iteration {
+    /// A proof was sent, or there was nothing to prove from yet.
+    Done,
+    /// The recall tape is being resynced, the challenge can be solved once it's whole.
+    Resyncing,
+}
+
+#[allow(clippy::too_many_arguments)]
 async fn try_mine_iteration(
-    store: &TapeStore,
+    handle: &SecondaryStoreHandle,
     client: &RpcClient,
     miner_address: &Pubkey,
-    signer: &Keypair,
-) -> Result<()> {
-    let current_time = Utc::now().timestamp();
+    signer: &dyn Signer,
+    max_drift: u64,
+    activity: &MinerActivity,
+    admin_queue: &AdminQueue,
+    peer: Option<&dyn SegmentSource>,
+) -> Result<Iteration> {
+    let store = handle.store();
 
     println!("DEBUG: Starting mine process...");
 
-    let epoch = get_epoch_account(client)
-        .await
-        .map_err(|e| anyhow!("Failed to get epoch account: {}", e))?.0;
-
-    println!("DEBUG: Current time: {}", current_time);
-    println!("DEBUG: Epoch.last_epoch_at: {}", epoch.last_epoch_at);
+    ensure_not_paused(client).await?;
 
-    if current_time > epoch.last_epoch_at + EPOCH_SECONDS {
-        println!("DEBUG: Epoch is stale, advancing...");
-        tape_client::advance(client, signer).await?;
-        println!("DEBUG: Advanced epoch to {}", current_time);
+    if !store_in_sync(handle, client, max_drift).await? {
+        return Ok(Iteration::Done);
     }
 
+    let epoch = advance_stale_epoch(client, signer).await?;
+
     //println!("DEBUG: Epoch account: {:?}", epoch);
 
     let miner = get_miner_account(client, miner_address)
@@ -71,142 +125,229 @@ async fn try_mine_iteration(
 
     //println!("DEBUG: Miner account: {:?}", miner);
 
-    let tape_number = miner.recall_tape;
+    let recall = match load_recall(&store, client, &miner, admin_queue, peer).await? {
+        RecallStatus::Ready(recall) => recall,
+        RecallStatus::Missing => return Ok(Iteration::Done),
+        RecallStatus::Resyncing => return Ok(Iteration::Resyncing),
+    };
+    handle.touch_tape(&recall.tape_address);
 
-    println!("DEBUG: Recall tape number: {:?}", tape_number);
+    let solution = solve_challenge(
+        miner.current_challenge, 
+        &recall.segment, 
+        epoch.difficulty
+    )?;
 
-    let tape_address = store.get_tape_address(tape_number);
+    println!("DEBUG: Solution difficulty: {:?}", solution.difficulty());
 
-    println!("DEBUG: Tape address: {:?}", tape_address);
+    solution.is_valid(&miner.current_challenge, &recall.segment)
+        .map_err(|_| anyhow!("Invalid solution"))?;
 
-    if let Ok(tape_address) = tape_address {
-        let tape = get_tape_account(client, &tape_address)
-            .await
-            .map_err(|e| anyhow!("Failed to get tape account: {}", e))?.0;
+    println!("DEBUG: Solution is valid!");
 
-        //println!("DEBUG: Tape account: {:?}", tape);
+    let sig = perform_mining(
+        client, 
+        signer, 
+        *miner_address, 
+        recall.tape_address, 
+        solution, 
+        recall.segment, 
+        recall.proof,
+    ).await?;
 
-        let segments = store.get_tape_segments(&tape_address)?;
-        if segments.len() != tape.total_segments as usize {
-            return Err(anyhow!("Invalid number of segments for tape {}: expected {}, got {}", 
-                tape_address, tape.total_segments, segments.len()));
-        }
+    println!("DEBUG: Mining successful! Signature: {:?}", sig);
 
-        let (solution, recall_segment, merkle_proof) = compute_challenge_solution(
-            &tape,
-            &miner,
-            segments,
-            epoch.difficulty,
-        )?;
-
-        let sig = perform_mining(
-            client, 
-            signer, 
-            *miner_address, 
-            tape_address, 
-            solution, 
-            recall_segment, 
-            merkle_proof,
-        ).await?;
-
-        println!("DEBUG: Mining successful! Signature: {:?}", sig);
-    } else {
-        println!("DEBUG: Tape not found, continuing...");
+    if let Err(e) = activity.record_proof() {
+        eprintln!("ERROR: Failed to record proof: {:?}", e);
     }
 
-    println!("DEBUG: Catching up with primary...");
-    store.catch_up_with_primary()?;
+    Ok(Iteration::Done)
+}
 
-    Ok(())
+/// Whether the store is close enough to the cluster to mine from, measured against the cluster
+/// now since the drift the archive recorded goes stale if it stops.
+pub(crate) async fn store_in_sync(
+    handle: &SecondaryStoreHandle,
+    client: &RpcClient,
+    max_drift: u64,
+) -> Result<bool> {
+    handle.catch_up(CATCH_UP_MAX_AGE).await?;
+    let (last_processed_slot, _) = handle
+        .store()
+        .get_health()
+        .map_err(|_| anyhow!("Store has no health record yet, is the archiver running?"))?;
+    let drift = get_slot(client).await?.saturating_sub(last_processed_slot);
+    if drift > max_drift {
+        println!(
+            "DEBUG: Store is {} slots behind the cluster (max {}), waiting for the archive to catch up...",
+            drift, max_drift
+        );
+        return Ok(false);
+    }
+
+    Ok(true)
 }
 
-fn compute_challenge_solution(
-    tape: &Tape,
-    miner: &Miner,
-    segments: Vec<(u64, Vec<u8>)>,
-    epoch_difficulty: u64,
-) -> Result<(Solution, [u8; SEGMENT_SIZE], [[u8; 32]; TREE_HEIGHT])> {
-    //println!("DEBUG: Segments: {:?}", segments);
+/// Advance the epoch if it's stale, returning the epoch as it was before.
+pub(crate) async fn advance_stale_epoch(client: &RpcClient, signer: &dyn Signer) -> Result<Epoch> {
+    let current_time = Utc::now().timestamp();
+    let epoch = get_epoch_account(client)
+        .await
+        .map_err(|e| anyhow!("Failed to get epoch account: {}", e))?.0;
 
-    let segment_number = compute_recall_segment(
-        &miner.current_challenge,
-        tape.total_segments
-    );
+    println!("DEBUG: Current time: {}", current_time);
+    println!("DEBUG: Epoch.last_epoch_at: {}", epoch.last_epoch_at);
 
-    let mut leaves = Vec::new();
-    let mut recall_segment = [0; SEGMENT_SIZE];
+    if current_time > epoch.last_epoch_at + EPOCH_SECONDS {
+        println!("DEBUG: Epoch is stale, advancing...");
+        tape_client::advance(client, signer).await?;
+        println!("DEBUG: Advanced epoch to {}", current_time);
+    }
 
-    let mut merkle_tree = MerkleTree::<{TREE_HEIGHT}>::new(&[tape.merkle_seed.as_ref()]);
+    Ok(epoch)
+}
 
-    for (segment_id, segment_data) in segments.iter() {
-        if *segment_id == segment_number {
-            recall_segment.copy_from_slice(segment_data);
-        }
+/// The segment a miner's challenge recalls, with its proof against the tape.
+pub(crate) struct Recall {
+    pub tape_address: Pubkey,
+    pub segment: [u8; SEGMENT_SIZE],
+    pub proof: [[u8; 32]; TREE_HEIGHT],
+}
 
-        // Create our canonical segment of exactly SEGMENT_SIZE bytes 
-        // and compute the merkle leaf
+/// Where the store stands on a miner's recall tape.
+pub(crate) enum RecallStatus {
+    /// The recall segment and its proof, ready to solve.
+    Ready(Recall),
+    /// The tape isn't in the store yet.
+    Missing,
+    /// The store's copy is missing segments or stale, and the archive has been asked to resync it.
+    Resyncing,
+}
 
-        let data = padded_array::<SEGMENT_SIZE>(segment_data);
-        let leaf = compute_leaf(
-            *segment_id,
-            &data,
-        );
+/// Look up the miner's recall tape in the store and prove its recall segment. A tape the store
+/// holds only part of, or whose proof doesn't verify against the root on-chain, is queued on
+/// `admin_queue` for the archive to resync, and proved from `peer` in the meantime if given.
+pub(crate) async fn load_recall(
+    store: &TapeStore,
+    client: &RpcClient,
+    miner: &Miner,
+    admin_queue: &AdminQueue,
+    peer: Option<&dyn SegmentSource>,
+) -> Result<RecallStatus> {
+    let tape_number = miner.recall_tape;
 
-        leaves.push(leaf);
+    println!("DEBUG: Recall tape number: {:?}", tape_number);
 
-        // TODO: we don't actually need to do this, this is just for 
-        // debugging and making sure the local root matches the tape root
-        merkle_tree.try_add_leaf(leaf).map_err(|e| {
-            anyhow!("Failed to add leaf to Merkle tree: {:?}", e)
-        })?;
-    }
+    let Ok(tape_address) = store.get_tape_address(tape_number) else {
+        println!("DEBUG: Recall tape {} isn't in the store yet, waiting for the archive to catch up...", tape_number);
+        return Ok(RecallStatus::Missing);
+    };
 
-    //println!("DEBUG: Merkle root: {:?}", merkle_tree.get_root());
+    println!("DEBUG: Tape address: {:?}", tape_address);
 
-    let merkle_proof = merkle_tree.get_merkle_proof(&leaves, segment_number as usize);
-    let merkle_proof = merkle_proof
-        .iter()
-        .map(|v| v.to_bytes())
-        .collect::<Vec<_>>()
-        .try_into()
-        .unwrap();
-
-    if merkle_tree.get_root() != tape.merkle_root.into() {
-        return Err(anyhow!("Merkle root mismatch"));
-    } else {
-        println!("DEBUG: Merkle root matches tape root!");
-    }
+    let tape = get_tape_account(client, &tape_address)
+        .await
+        .map_err(|e| anyhow!("Failed to get tape account: {}", e))?.0;
 
-    let solution = solve_challenge(
-        miner.current_challenge, 
-        &recall_segment, 
-        epoch_difficulty
-    )?;
+    //println!("DEBUG: Tape account: {:?}", tape);
 
-    println!("DEBUG: Solution difficulty: {:?}", solution.difficulty());
+    let stored = store.for_each_segment(&tape_address, |_, _| Ok::<_, StoreError>(()))?;
+    if stored < tape.total_segments as usize {
+        println!(
+            "DEBUG: Store holds {} of {} segments of recall tape {}",
+            stored, tape.total_segments, tape_address
+        );
+        queue_resync(admin_queue, &tape_address)?;
+        return recall_from_peer(peer, &tape_address, &tape, miner).await;
+    }
 
-    solution.is_valid(&miner.current_challenge, &recall_segment)
-        .map_err(|_| anyhow!("Invalid solution"))?;
+    let (segment, proof) = recall_proof(store, &tape_address, &tape, miner)?;
 
-    println!("DEBUG: Solution is valid!");
+    // Solving takes minutes, check the proof would land before spending them
+    let (_, segment_number) = compute_recall_indices(miner, &tape);
+    if !proof_matches_root(&tape, segment_number, &segment, &proof) {
+        println!(
+            "DEBUG: Recall segment {} of tape {} doesn't match the tape's root, the store's copy is stale",
+            segment_number, tape_address
+        );
+        queue_resync(admin_queue, &tape_address)?;
+        return recall_from_peer(peer, &tape_address, &tape, miner).await;
+    }
 
-    Ok((solution, recall_segment, merkle_proof))
+    Ok(RecallStatus::Ready(Recall { tape_address, segment, proof }))
 }
 
-fn solve_challenge<const N: usize>(
-    challenge: [u8; 32],
-    data: &[u8; N],
-    difficulty: u64,
-) -> Result<Solution, CrankXError> {
-    let mut memory = SolverMemory::new();
-    let mut nonce: u64 = 0;
+/// Prove the recall segment from a trusted peer's copy of the tape while the archive resyncs the
+/// store's. Without a peer, or with one that can't serve the whole tape in time or serves a
+/// stale copy, the miner waits on the resync.
+async fn recall_from_peer(
+    peer: Option<&dyn SegmentSource>,
+    tape_address: &Pubkey,
+    tape: &Tape,
+    miner: &Miner,
+) -> Result<RecallStatus> {
+    let Some(peer) = peer else {
+        return Ok(RecallStatus::Resyncing);
+    };
+
+    let chain = SourceChain::new().with_source(peer, DEFAULT_SOURCE_TIMEOUT);
+    let mut segments = match chain.read_tape(tape_address, tape.total_segments).await {
+        Ok(segments) => segments,
+        Err(e) => {
+            println!("DEBUG: Can't prove recall tape {} from the peer either: {}", tape_address, e);
+            return Ok(RecallStatus::Resyncing);
+        }
+    };
+    segments.sort_by_key(|(segment_number, _)| *segment_number);
 
-    loop {
-        if let Ok(solution) = solve_with_memory(&mut memory, &challenge, data, &nonce.to_le_bytes()) {
-            if solution.difficulty() >= difficulty as u32 {
-                return Ok(solution);
-            }
+    let mut prover = RecallProver::new(tape, miner);
+    let segment_number = prover.segment_number();
+    let proved = segments
+        .iter()
+        .try_for_each(|(number, data)| prover.add_segment(*number, data))
+        .and_then(|_| prover.finish());
+    let (segment, proof) = match proved {
+        Ok(proved) => proved,
+        Err(e) => {
+            println!("DEBUG: Failed to prove recall tape {} from the peer: {}", tape_address, e);
+            return Ok(RecallStatus::Resyncing);
         }
-        nonce += 1;
+    };
+
+    if !proof_matches_root(tape, segment_number, &segment, &proof) {
+        println!(
+            "DEBUG: The peer's copy of recall tape {} doesn't match the tape's root either",
+            tape_address
+        );
+        return Ok(RecallStatus::Resyncing);
     }
+
+    println!("DEBUG: Proving recall tape {} from {} while the store resyncs", tape_address, peer.name());
+    Ok(RecallStatus::Ready(Recall { tape_address: *tape_address, segment, proof }))
+}
+
+/// Ask the archive to resync a tape, unless it's already waiting to.
+fn queue_resync(admin_queue: &AdminQueue, tape_address: &Pubkey) -> Result<()> {
+    let task = AdminTask::Resync(*tape_address);
+    if !admin_queue.is_queued(&task)? {
+        admin_queue.push(task)?;
+        println!("DEBUG: Queued a resync of tape {} for the archive", tape_address);
+    }
+    Ok(())
+}
+
+/// Find the recall segment of a tape and its merkle proof. Leaves are built straight from the
+/// store's buffers, the tape's segments are never copied out as a whole.
+fn recall_proof(
+    store: &TapeStore,
+    tape_address: &Pubkey,
+    tape: &Tape,
+    miner: &Miner,
+) -> Result<([u8; SEGMENT_SIZE], [[u8; 32]; TREE_HEIGHT])> {
+    let mut prover = RecallProver::new(tape, miner);
+    store.for_each_segment(tape_address, |segment_id, segment_data| prover.add_segment(segment_id, segment_data))?;
+
+    prover
+        .finish()
+        .map_err(|e| anyhow!("Failed to prove recall tape {}: {}", tape_address, e))
 }
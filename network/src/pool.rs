@@ -0,0 +1,642 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::net::SocketAddr;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use anyhow::{anyhow, bail, Result};
+use axum::{extract::State, response::IntoResponse, routing::post, Json, Router};
+use chrono::Utc;
+use crankx::Solution;
+use serde::Serialize;
+use serde_json::{json, Value};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{pubkey::Pubkey, signature::Signature, signer::Signer};
+use tape_api::prelude::*;
+use tape_client::mine::{mine::perform_mining, pool::{check_worker_name, PoolJob}};
+use tape_client::utils::*;
+use tokio::time::{sleep, Duration};
+
+use super::admin::{tokens_match, AdminQueue};
+use super::alerts::Alerter;
+use super::mine::{advance_stale_epoch, load_recall, store_in_sync, Recall, RecallStatus};
+use super::shared::SecondaryStoreHandle;
+use super::web::{make_response, ErrorCode, RpcError, RpcRequest};
+
+/// Lowest difficulty counted as a share unless the operator picks another, well under the epoch
+/// difficulty so workers report in every few seconds.
+pub const DEFAULT_SHARE_DIFFICULTY: u32 = 4;
+
+/// Nonces handed to a worker per job.
+const NONCE_RANGE: u64 = 4096;
+
+/// How often the coordinator checks the miner for a new challenge or a proof to submit.
+const ROUND_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How a pool splits work and records what it owes.
+#[derive(Clone)]
+pub struct PoolPolicy {
+    /// Lowest difficulty a solution needs to count as a share.
+    pub share_difficulty: u32,
+    /// File each landed round's shares are appended to, one JSON line per round.
+    pub payout_log: Option<PathBuf>,
+    /// The token each worker authenticates with, by worker name. Nobody else gets work.
+    pub workers: BTreeMap<String, String>,
+}
+
+impl Default for PoolPolicy {
+    fn default() -> Self {
+        Self {
+            share_difficulty: DEFAULT_SHARE_DIFFICULTY,
+            payout_log: None,
+            workers: BTreeMap::new(),
+        }
+    }
+}
+
+/// Read the pool's workers from a JSON file of worker names to their tokens, e.g.
+/// `{"rig-01": "<token>"}`.
+pub fn load_workers(path: &Path) -> Result<BTreeMap<String, String>> {
+    let json = fs::read_to_string(path)
+        .map_err(|e| anyhow!("Failed to read workers file {}: {}", path.display(), e))?;
+    let workers: BTreeMap<String, String> = serde_json::from_str(&json)
+        .map_err(|e| anyhow!("Invalid workers file {}: {}", path.display(), e))?;
+
+    for (worker, token) in &workers {
+        check_worker_name(worker)?;
+        if token.is_empty() {
+            bail!("Worker {} has an empty token in {}", worker, path.display());
+        }
+    }
+    if workers.is_empty() {
+        bail!("Workers file {} lists no workers", path.display());
+    }
+
+    Ok(workers)
+}
+
+/// What a worker has found since the coordinator started.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct WorkerStats {
+    pub shares: u64,
+    pub best_difficulty: u32,
+    pub last_share_at: i64,
+}
+
+/// Work on the miner's current challenge: one job id, handed out in nonce ranges.
+struct Round {
+    job: u64,
+    challenge: [u8; 32],
+    segment: [u8; SEGMENT_SIZE],
+    next_nonce: u64,
+    /// The nonce ranges handed to each worker, who only gets shares for nonces in them.
+    assigned: BTreeMap<String, Vec<Range<u64>>>,
+    /// Nonces already credited on this challenge. A new challenge opens a new round, and with
+    /// it an empty set.
+    seen: HashSet<[u8; 8]>,
+    /// Difficulty and bytes of the hardest solution found.
+    best: Option<(u32, [u8; 24])>,
+    shares: BTreeMap<String, u64>,
+}
+
+#[derive(Default)]
+struct PoolInner {
+    round: Option<Round>,
+    jobs: u64,
+    workers: BTreeMap<String, WorkerStats>,
+}
+
+/// Shares and jobs of a pool, shared by the RPC handlers and the round loop.
+pub struct PoolState {
+    share_difficulty: u32,
+    workers: BTreeMap<String, String>,
+    inner: Mutex<PoolInner>,
+}
+
+impl PoolState {
+    /// A pool handing out work to `workers`, each authenticated by its token.
+    pub fn new(share_difficulty: u32, workers: BTreeMap<String, String>) -> Self {
+        Self {
+            share_difficulty,
+            workers,
+            inner: Mutex::new(PoolInner::default()),
+        }
+    }
+
+    /// Check `worker` is one of the pool's and `token` is its token.
+    fn authenticate(&self, worker: &str, token: &str) -> Result<()> {
+        check_worker_name(worker)?;
+        match self.workers.get(worker) {
+            Some(expected) if tokens_match(token, expected) => Ok(()),
+            _ => bail!("unknown worker or wrong token"),
+        }
+    }
+
+    /// Start handing out work on a new challenge, dropping the shares of the round before.
+    /// Returns the new round's job id.
+    pub fn start_round(&self, challenge: [u8; 32], segment: [u8; SEGMENT_SIZE]) -> u64 {
+        let mut inner = self.inner.lock().unwrap();
+        inner.jobs += 1;
+        let job = inner.jobs;
+        inner.round = Some(Round {
+            job,
+            challenge,
+            segment,
+            next_nonce: 0,
+            assigned: BTreeMap::new(),
+            seen: HashSet::new(),
+            best: None,
+            shares: BTreeMap::new(),
+        });
+        job
+    }
+
+    /// The next range of nonces for `worker` to try.
+    pub fn next_job(&self, worker: &str, token: &str) -> Result<PoolJob> {
+        self.authenticate(worker, token)?;
+
+        let mut inner = self.inner.lock().unwrap();
+        let round = inner.round.as_mut().ok_or_else(|| anyhow!("no round is open yet"))?;
+        let start = round.next_nonce;
+        round.next_nonce = start.saturating_add(NONCE_RANGE);
+        round.assigned.entry(worker.to_string()).or_default().push(start..round.next_nonce);
+
+        Ok(PoolJob {
+            job: round.job,
+            challenge: round.challenge,
+            segment: round.segment,
+            share_difficulty: self.share_difficulty,
+            nonces: start..round.next_nonce,
+        })
+    }
+
+    /// Credit `worker` with a share for `solution`, if it solves the open round at the share
+    /// difficulty with a nonce the worker was handed, and nobody submitted that nonce before.
+    /// Returns the worker's shares this round.
+    pub fn submit_share(&self, worker: &str, token: &str, job: u64, solution: &Solution, now: i64) -> Result<u64> {
+        self.authenticate(worker, token)?;
+
+        let mut inner = self.inner.lock().unwrap();
+        let inner = &mut *inner;
+        let round = match inner.round.as_mut() {
+            Some(round) if round.job == job => round,
+            _ => bail!("job {} is no longer open", job),
+        };
+
+        let nonce = u64::from_le_bytes(solution.n);
+        let assigned = round.assigned.get(worker);
+        if !assigned.is_some_and(|ranges| ranges.iter().any(|range| range.contains(&nonce))) {
+            bail!("nonce {} wasn't handed to {}", nonce, worker);
+        }
+
+        let difficulty = solution.difficulty();
+        if difficulty < self.share_difficulty {
+            bail!("difficulty {} is below the share difficulty {}", difficulty, self.share_difficulty);
+        }
+        if solution.is_valid(&round.challenge, &round.segment).is_err() {
+            bail!("solution doesn't solve job {}", job);
+        }
+        if !round.seen.insert(solution.n) {
+            bail!("nonce was already submitted");
+        }
+
+        if !round.best.is_some_and(|(best, _)| best >= difficulty) {
+            round.best = Some((difficulty, solution.to_bytes()));
+        }
+
+        let shares = round.shares.entry(worker.to_string()).or_default();
+        *shares += 1;
+
+        let stats = inner.workers.entry(worker.to_string()).or_default();
+        stats.shares += 1;
+        stats.best_difficulty = stats.best_difficulty.max(difficulty);
+        stats.last_share_at = now;
+
+        Ok(*shares)
+    }
+
+    /// The hardest solution found for `job`, if it's still open.
+    pub fn best(&self, job: u64) -> Option<Solution> {
+        let inner = self.inner.lock().unwrap();
+        inner.round
+            .as_ref()
+            .filter(|round| round.job == job)
+            .and_then(|round| round.best.as_ref())
+            .map(|(_, bytes)| Solution::from_bytes(bytes))
+    }
+
+    /// Stop handing out `job`, returning the shares each worker found for it.
+    pub fn close_round(&self, job: u64) -> Option<BTreeMap<String, u64>> {
+        let mut inner = self.inner.lock().unwrap();
+        if !inner.round.as_ref().is_some_and(|round| round.job == job) {
+            return None;
+        }
+        inner.round.take().map(|round| round.shares)
+    }
+
+    fn shares(&self) -> Value {
+        let inner = self.inner.lock().unwrap();
+        json!({
+            "job": inner.round.as_ref().map(|round| round.job),
+            "round": inner.round.as_ref().map(|round| round.shares.clone()).unwrap_or_default(),
+            "workers": inner.workers,
+        })
+    }
+}
+
+fn invalid_params(message: impl ToString) -> RpcError {
+    RpcError {
+        code: ErrorCode::InvalidParams.code(),
+        message: message.to_string(),
+    }
+}
+
+fn worker_param(params: &Value) -> Result<&str, RpcError> {
+    params
+        .get("worker")
+        .and_then(Value::as_str)
+        .ok_or_else(|| invalid_params("missing worker"))
+}
+
+fn token_param(params: &Value) -> Result<&str, RpcError> {
+    params
+        .get("token")
+        .and_then(Value::as_str)
+        .ok_or_else(|| invalid_params("missing token"))
+}
+
+/// Hand the worker the next range of nonces to try on the open round.
+///
+/// Example invocation:
+/// ```bash
+/// curl -X POST http://127.0.0.1:3002/ \
+///      -H 'Content-Type: application/json' \
+///      -d '{"jsonrpc":"2.0","id":1,"method":"getJob","params":{"worker":"rig-01","token":"<TOKEN>"}}'
+/// ```
+fn rpc_get_job(state: &PoolState, params: &Value) -> Result<Value, RpcError> {
+    let worker = worker_param(params)?;
+    let token = token_param(params)?;
+    let job = state.next_job(worker, token).map_err(|e| RpcError {
+        code: ErrorCode::ServerError.code(),
+        message: e.to_string(),
+    })?;
+
+    Ok(job.to_json())
+}
+
+/// Submit a base64 solution to a job, returning its difficulty and the worker's shares this
+/// round.
+///
+/// Example invocation:
+/// ```bash
+/// curl -X POST http://127.0.0.1:3002/ \
+///      -H 'Content-Type: application/json' \
+///      -d '{"jsonrpc":"2.0","id":2,"method":"submitShare","params":{"worker":"rig-01","token":"<TOKEN>","job":1,"solution":"<BASE64>"}}'
+/// ```
+fn rpc_submit_share(state: &PoolState, params: &Value) -> Result<Value, RpcError> {
+    let worker = worker_param(params)?;
+    let token = token_param(params)?;
+    let job = params
+        .get("job")
+        .and_then(Value::as_u64)
+        .ok_or_else(|| invalid_params("missing job"))?;
+    let bytes: [u8; 24] = params
+        .get("solution")
+        .and_then(Value::as_str)
+        .and_then(|s| base64::decode(s).ok())
+        .and_then(|b| b.try_into().ok())
+        .ok_or_else(|| invalid_params("solution must be 24 bytes of base64"))?;
+
+    let solution = Solution::from_bytes(&bytes);
+    let shares = state
+        .submit_share(worker, token, job, &solution, Utc::now().timestamp())
+        .map_err(invalid_params)?;
+
+    Ok(json!({ "difficulty": solution.difficulty(), "shares": shares }))
+}
+
+/// Retrieve the shares of every worker, in the open round and since the pool started.
+///
+/// Example invocation:
+/// ```bash
+/// curl -X POST http://127.0.0.1:3002/ \
+///      -H 'Content-Type: application/json' \
+///      -d '{"jsonrpc":"2.0","id":3,"method":"getShares","params":{}}'
+/// ```
+fn rpc_get_shares(state: &PoolState, _params: &Value) -> Result<Value, RpcError> {
+    Ok(state.shares())
+}
+
+async fn pool_handler(
+    State(state): State<Arc<PoolState>>,
+    Json(req): Json<RpcRequest>,
+) -> impl IntoResponse {
+    let outcome = match req.method.as_str() {
+        "getJob" => rpc_get_job(&state, &req.params),
+        "submitShare" => rpc_submit_share(&state, &req.params),
+        "getShares" => rpc_get_shares(&state, &req.params),
+        _ => Err(RpcError {
+            code: ErrorCode::MethodNotFound.code(),
+            message: "method not found".into(),
+        }),
+    };
+
+    make_response(req.id, outcome)
+}
+
+/// The round the coordinator is proving, with the recall data the workers are solving against.
+struct OpenRound {
+    job: u64,
+    challenge: [u8; 32],
+    recall: Recall,
+}
+
+/// Coordinate a pool mining with `miner_address`: serve jobs to workers on `port`, and submit
+/// the best solution they find with `signer` once the miner is due its next proof. The shares of
+/// each round that lands are appended to the policy's payout log. Rounds that stop landing, or
+/// keep failing on the store, fire `alerts`.
+#[allow(clippy::too_many_arguments)]
+pub async fn pool_loop(
+    store: &SecondaryStoreHandle,
+    client: &RpcClient,
+    miner_address: &Pubkey,
+    signer: &dyn Signer,
+    max_drift: u64,
+    policy: PoolPolicy,
+    port: u16,
+    admin_queue: &AdminQueue,
+    alerts: &Alerter,
+) -> Result<()> {
+    let state = Arc::new(PoolState::new(policy.share_difficulty, policy.workers.clone()));
+
+    let app = Router::new()
+        .route("/", post(pool_handler))
+        .with_state(Arc::clone(&state));
+
+    // Workers run on other machines
+    let addr = SocketAddr::from(([0, 0, 0, 0], port));
+    let listener = tokio::net::TcpListener::bind(&addr).await?;
+
+    tokio::try_join!(
+        async { axum::serve(listener, app).await.map_err(anyhow::Error::from) },
+        round_loop(store, client, miner_address, signer, max_drift, &policy, &state, admin_queue, alerts),
+    )?;
+
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn round_loop(
+    store: &SecondaryStoreHandle,
+    client: &RpcClient,
+    miner_address: &Pubkey,
+    signer: &dyn Signer,
+    max_drift: u64,
+    policy: &PoolPolicy,
+    state: &PoolState,
+    admin_queue: &AdminQueue,
+    alerts: &Alerter,
+) -> Result<()> {
+    let mut current = None;
+
+    loop {
+        let result = try_pool_iteration(
+            store, client, miner_address, signer, max_drift, policy, state, admin_queue, &mut current,
+        ).await;
+        if let Err(e) = &result {
+            eprintln!("ERROR: Pool iteration failed: {:?}", e);
+        }
+        alerts.record_iteration(&result).await;
+        alerts.watch_proofs(client, miner_address).await;
+
+        sleep(ROUND_INTERVAL).await;
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn try_pool_iteration(
+    handle: &SecondaryStoreHandle,
+    client: &RpcClient,
+    miner_address: &Pubkey,
+    signer: &dyn Signer,
+    max_drift: u64,
+    policy: &PoolPolicy,
+    state: &PoolState,
+    admin_queue: &AdminQueue,
+    current: &mut Option<OpenRound>,
+) -> Result<()> {
+    ensure_not_paused(client).await?;
+
+    let miner = get_miner_account(client, miner_address)
+        .await
+        .map_err(|e| anyhow!("Failed to get miner account: {}", e))?.0;
+
+    if !current.as_ref().is_some_and(|round| round.challenge == miner.current_challenge) {
+        // A new challenge, hand out work on it once the store can prove its recall segment
+        if !store_in_sync(handle, client, max_drift).await? {
+            return Ok(());
+        }
+        let RecallStatus::Ready(recall) = load_recall(&handle.store(), client, &miner, admin_queue, None).await? else {
+            return Ok(());
+        };
+
+        let job = state.start_round(miner.current_challenge, recall.segment);
+        println!("DEBUG: Opened pool round {} on recall tape {}", job, recall.tape_address);
+        *current = Some(OpenRound { job, challenge: miner.current_challenge, recall });
+        return Ok(());
+    }
+    let Some(round) = current.as_ref() else {
+        return Ok(());
+    };
+
+    // Keep collecting shares until the miner is due, a harder solution earns the same reward
+    if Utc::now().timestamp() < miner.last_proof_at + ONE_MINUTE {
+        return Ok(());
+    }
+
+    let epoch = advance_stale_epoch(client, signer).await?;
+    let Some(solution) = state.best(round.job) else {
+        println!("DEBUG: No shares yet for pool round {}", round.job);
+        return Ok(());
+    };
+    if (solution.difficulty() as u64) < epoch.difficulty {
+        println!(
+            "DEBUG: Best share of pool round {} is difficulty {}, waiting for {}...",
+            round.job, solution.difficulty(), epoch.difficulty
+        );
+        return Ok(());
+    }
+
+    let sig = perform_mining(
+        client,
+        signer,
+        *miner_address,
+        round.recall.tape_address,
+        solution,
+        round.recall.segment,
+        round.recall.proof,
+    ).await?;
+
+    println!("DEBUG: Pool round {} mined! Signature: {:?}", round.job, sig);
+
+    if let Some(shares) = state.close_round(round.job) {
+        if let Some(path) = &policy.payout_log {
+            if let Err(e) = append_payout(path, miner_address, round.job, &sig, &shares) {
+                eprintln!("ERROR: Failed to record payout of pool round {}: {:?}", round.job, e);
+            }
+        }
+    }
+    *current = None;
+
+    Ok(())
+}
+
+/// Append a landed round to the payout log, as one JSON line.
+fn append_payout(
+    path: &Path,
+    miner_address: &Pubkey,
+    job: u64,
+    signature: &Signature,
+    shares: &BTreeMap<String, u64>,
+) -> Result<()> {
+    let line = json!({
+        "job": job,
+        "miner": miner_address.to_string(),
+        "signature": signature.to_string(),
+        "mined_at": Utc::now().timestamp(),
+        "shares": shares,
+    });
+
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", line)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crankx::solve;
+
+    /// The first `count` nonces from `start` that solve the challenge, most nonces do.
+    fn solutions(challenge: &[u8; 32], segment: &[u8; SEGMENT_SIZE], start: u64, count: usize) -> Vec<Solution> {
+        (start..)
+            .filter_map(|nonce| solve(challenge, segment, &nonce.to_le_bytes()).ok())
+            .take(count)
+            .collect()
+    }
+
+    /// A pool with workers `rig-01` and `rig-02`, whose tokens are their names backwards.
+    fn pool(share_difficulty: u32) -> PoolState {
+        let workers = BTreeMap::from([
+            ("rig-01".to_string(), "10-gir".to_string()),
+            ("rig-02".to_string(), "20-gir".to_string()),
+        ]);
+        PoolState::new(share_difficulty, workers)
+    }
+
+    #[test]
+    fn test_pool_shares() {
+        let state = pool(0);
+        let challenge = [7; 32];
+        let segment = [9; SEGMENT_SIZE];
+
+        // Nothing to hand out before the first round
+        assert!(state.next_job("rig-01", "10-gir").is_err());
+
+        let job = state.start_round(challenge, segment);
+        let first = state.next_job("rig-01", "10-gir").unwrap();
+        let second = state.next_job("rig-02", "20-gir").unwrap();
+        assert_eq!(first.job, job);
+        assert_eq!(first.nonces, 0..NONCE_RANGE);
+        assert_eq!(second.nonces, NONCE_RANGE..2 * NONCE_RANGE);
+        assert!(state.next_job("rig 01", "10-gir").is_err());
+
+        let found = solutions(&challenge, &segment, 0, 2);
+        let other = solutions(&challenge, &segment, NONCE_RANGE, 1);
+        assert_eq!(state.submit_share("rig-01", "10-gir", job, &found[0], 1).unwrap(), 1);
+        assert_eq!(state.submit_share("rig-01", "10-gir", job, &found[1], 2).unwrap(), 2);
+        assert_eq!(state.submit_share("rig-02", "20-gir", job, &other[0], 3).unwrap(), 1);
+
+        // The same nonce twice, or a nonce handed to someone else
+        assert!(state.submit_share("rig-01", "10-gir", job, &found[0], 4).is_err());
+        assert!(state.submit_share("rig-02", "20-gir", job, &found[1], 4).is_err());
+
+        // Another job, or a digest that doesn't solve the nonce
+        assert!(state.submit_share("rig-01", "10-gir", job + 1, &found[0], 4).is_err());
+        let mut forged = found[0].to_bytes();
+        forged[0] ^= 1;
+        assert!(state.submit_share("rig-01", "10-gir", job, &Solution::from_bytes(&forged), 4).is_err());
+
+        let hardest = found.iter().chain(&other).map(Solution::difficulty).max().unwrap();
+        assert_eq!(state.best(job).unwrap().difficulty(), hardest);
+
+        let shares = state.close_round(job).unwrap();
+        assert_eq!(shares, BTreeMap::from([("rig-01".to_string(), 2), ("rig-02".to_string(), 1)]));
+        assert!(state.close_round(job).is_none());
+        assert!(state.best(job).is_none());
+
+        // Totals outlive the round
+        let stats = state.shares();
+        assert_eq!(stats["workers"]["rig-01"]["shares"], 2);
+        assert_eq!(stats["workers"]["rig-02"]["last_share_at"], 3);
+
+        // A new challenge starts over, nonces credited before count again
+        let challenge = [8; 32];
+        let job = state.start_round(challenge, segment);
+        state.next_job("rig-01", "10-gir").unwrap();
+        let found = solutions(&challenge, &segment, 0, 1);
+        assert_eq!(state.submit_share("rig-01", "10-gir", job, &found[0], 5).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_pool_authentication() {
+        let state = pool(0);
+        let challenge = [7; 32];
+        let segment = [9; SEGMENT_SIZE];
+        let job = state.start_round(challenge, segment);
+
+        // Unknown workers and wrong tokens get no work
+        assert!(state.next_job("rig-03", "30-gir").is_err());
+        assert!(state.next_job("rig-01", "20-gir").is_err());
+        assert!(state.next_job("rig-01", "").is_err());
+
+        // And can't claim another worker's shares
+        state.next_job("rig-01", "10-gir").unwrap();
+        let found = solutions(&challenge, &segment, 0, 1);
+        assert!(state.submit_share("rig-01", "20-gir", job, &found[0], 1).is_err());
+        assert!(state.best(job).is_none());
+        assert_eq!(state.submit_share("rig-01", "10-gir", job, &found[0], 1).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_load_workers() -> Result<()> {
+        let dir = tempdir::TempDir::new("pool")?;
+        let path = dir.path().join("workers.json");
+
+        fs::write(&path, r#"{"rig-01": "10-gir"}"#)?;
+        assert_eq!(load_workers(&path)?, BTreeMap::from([("rig-01".to_string(), "10-gir".to_string())]));
+
+        for invalid in [r#"{}"#, r#"{"rig-01": ""}"#, r#"{"rig 01": "10-gir"}"#, r#"["rig-01"]"#] {
+            fs::write(&path, invalid)?;
+            assert!(load_workers(&path).is_err(), "{}", invalid);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_share_difficulty() {
+        let state = pool(u32::MAX);
+        let challenge = [7; 32];
+        let segment = [9; SEGMENT_SIZE];
+        let job = state.start_round(challenge, segment);
+        state.next_job("rig-01", "10-gir").unwrap();
+
+        let found = solutions(&challenge, &segment, 0, 1);
+        assert!(state.submit_share("rig-01", "10-gir", job, &found[0], 1).is_err());
+        assert!(state.best(job).is_none());
+    }
+}
@@ -0,0 +1,232 @@
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use anyhow::{bail, Result};
+use axum::{extract::State, response::IntoResponse, routing::post, Json, Router};
+use serde_json::{json, Value};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{
+    compute_budget,
+    pubkey::Pubkey,
+    signature::Keypair,
+    signer::Signer,
+    transaction::Transaction,
+};
+use tape_client::{decode_transaction, encode_transaction, program_context};
+
+use super::web::{make_response, ErrorCode, RpcError, RpcRequest};
+
+/// Discriminant of `ComputeBudgetInstruction::SetComputeUnitPrice`.
+const SET_COMPUTE_UNIT_PRICE: u8 = 3;
+
+/// What a relayer is willing to pay for.
+#[derive(Clone, Debug, Default)]
+pub struct RelayPolicy {
+    /// Highest priority fee, in micro-lamports per compute unit.
+    pub max_cu_price: u64,
+}
+
+struct RelayState {
+    rpc: RpcClient,
+    payer: Keypair,
+    policy: RelayPolicy,
+}
+
+/// Check a transaction is one the relayer should pay for: paid by `fee_payer`, only calling the
+/// tape program and the compute budget program, and never using the fee payer for anything but
+/// fees.
+pub fn check_relayable(tx: &Transaction, fee_payer: &Pubkey, policy: &RelayPolicy) -> Result<()> {
+    let message = &tx.message;
+    if message.account_keys.first() != Some(fee_payer) {
+        bail!("transaction isn't paid by {}", fee_payer);
+    }
+
+    for ix in &message.instructions {
+        let Some(program_id) = message.account_keys.get(ix.program_id_index as usize) else {
+            bail!("instruction calls a missing program");
+        };
+
+        // The fee payer would otherwise pay rent, or sign for someone else's tape
+        if ix.accounts.contains(&0) {
+            bail!("instruction uses the fee payer");
+        }
+
+        if compute_budget::check_id(program_id) {
+            if ix.data.first() == Some(&SET_COMPUTE_UNIT_PRICE) {
+                let price = ix.data.get(1..9)
+                    .and_then(|b| b.try_into().ok())
+                    .map(u64::from_le_bytes)
+                    .unwrap_or(u64::MAX);
+                if price > policy.max_cu_price {
+                    bail!("compute unit price {} is above {}", price, policy.max_cu_price);
+                }
+            }
+        } else if *program_id != program_context().program_id {
+            bail!("program {} isn't relayed", program_id);
+        }
+    }
+
+    Ok(())
+}
+
+fn invalid_params(message: impl ToString) -> RpcError {
+    RpcError {
+        code: ErrorCode::InvalidParams.code(),
+        message: message.to_string(),
+    }
+}
+
+/// The transaction in `params`, checked against the policy and signed by the fee payer.
+fn sign_transaction(state: &RelayState, params: &Value) -> Result<Transaction, RpcError> {
+    let data = params
+        .get("transaction")
+        .and_then(Value::as_str)
+        .ok_or_else(|| invalid_params("missing transaction"))?;
+    let mut tx = decode_transaction(data).map_err(invalid_params)?;
+
+    check_relayable(&tx, &state.payer.pubkey(), &state.policy).map_err(|e| RpcError {
+        code: ErrorCode::Unauthorized.code(),
+        message: e.to_string(),
+    })?;
+
+    let blockhash = tx.message.recent_blockhash;
+    tx.try_partial_sign(&[&state.payer], blockhash).map_err(invalid_params)?;
+
+    // Every other signer must have signed already
+    tx.verify().map_err(|e| invalid_params(format!("transaction isn't fully signed: {}", e)))?;
+
+    Ok(tx)
+}
+
+/// Retrieve the fee payer transactions sent to this relayer must be paid by.
+///
+/// Example invocation:
+/// ```bash
+/// curl -X POST http://127.0.0.1:3001/ \
+///      -H 'Content-Type: application/json' \
+///      -d '{"jsonrpc":"2.0","id":1,"method":"getFeePayer","params":{}}'
+/// ```
+fn rpc_get_fee_payer(state: &RelayState, _params: &Value) -> Result<Value, RpcError> {
+    Ok(json!(state.payer.pubkey().to_string()))
+}
+
+/// Add the fee payer's signature to a base64 transaction and return it.
+///
+/// Example invocation:
+/// ```bash
+/// curl -X POST http://127.0.0.1:3001/ \
+///      -H 'Content-Type: application/json' \
+///      -d '{"jsonrpc":"2.0","id":2,"method":"signTransaction","params":{"transaction":"<BASE64>"}}'
+/// ```
+fn rpc_sign_transaction(state: &RelayState, params: &Value) -> Result<Value, RpcError> {
+    let tx = sign_transaction(state, params)?;
+    let data = encode_transaction(&tx).map_err(|e| RpcError {
+        code: ErrorCode::InternalError.code(),
+        message: e.to_string(),
+    })?;
+
+    Ok(json!({ "transaction": data }))
+}
+
+/// Sign a base64 transaction and send it, returning its signature.
+///
+/// Example invocation:
+/// ```bash
+/// curl -X POST http://127.0.0.1:3001/ \
+///      -H 'Content-Type: application/json' \
+///      -d '{"jsonrpc":"2.0","id":3,"method":"sendTransaction","params":{"transaction":"<BASE64>"}}'
+/// ```
+async fn rpc_send_transaction(state: &RelayState, params: &Value) -> Result<Value, RpcError> {
+    let tx = sign_transaction(state, params)?;
+    let signature = state.rpc.send_transaction(&tx).await.map_err(|e| RpcError {
+        code: ErrorCode::ServerError.code(),
+        message: e.to_string(),
+    })?;
+
+    Ok(json!(signature.to_string()))
+}
+
+async fn relay_handler(
+    State(state): State<Arc<RelayState>>,
+    Json(req): Json<RpcRequest>,
+) -> impl IntoResponse {
+    let outcome = match req.method.as_str() {
+        "getFeePayer" => rpc_get_fee_payer(&state, &req.params),
+        "signTransaction" => rpc_sign_transaction(&state, &req.params),
+        "sendTransaction" => rpc_send_transaction(&state, &req.params).await,
+        _ => Err(RpcError {
+            code: ErrorCode::MethodNotFound.code(),
+            message: "method not found".into(),
+        }),
+    };
+
+    make_response(req.id, outcome)
+}
+
+/// Serve the relayer on `port`, paying for transactions the policy allows with `payer`.
+pub async fn relay_loop(rpc: RpcClient, payer: Keypair, policy: RelayPolicy, port: u16) -> Result<()> {
+    let state = Arc::new(RelayState { rpc, payer, policy });
+
+    let app = Router::new()
+        .route("/", post(relay_handler))
+        .with_state(state);
+
+    let addr = SocketAddr::from(([127, 0, 0, 1], port));
+    let listener = tokio::net::TcpListener::bind(&addr).await?;
+
+    axum::serve(listener, app).await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::{
+        compute_budget::ComputeBudgetInstruction,
+        instruction::{AccountMeta, Instruction},
+        system_instruction,
+    };
+
+    fn tape_ix(signer: &Pubkey) -> Instruction {
+        Instruction::new_with_bytes(program_context().program_id, &[0], vec![AccountMeta::new(*signer, true)])
+    }
+
+    #[test]
+    fn test_check_relayable() {
+        let fee_payer = Pubkey::new_unique();
+        let user = Pubkey::new_unique();
+        let policy = RelayPolicy { max_cu_price: 1_000 };
+
+        let tx = Transaction::new_with_payer(
+            &[
+                ComputeBudgetInstruction::set_compute_unit_limit(50_000),
+                ComputeBudgetInstruction::set_compute_unit_price(1_000),
+                tape_ix(&user),
+            ],
+            Some(&fee_payer),
+        );
+        assert!(check_relayable(&tx, &fee_payer, &policy).is_ok());
+
+        // Paid by someone else
+        assert!(check_relayable(&tx, &user, &policy).is_err());
+
+        // Too high a priority fee
+        let tx = Transaction::new_with_payer(
+            &[ComputeBudgetInstruction::set_compute_unit_price(1_001), tape_ix(&user)],
+            Some(&fee_payer),
+        );
+        assert!(check_relayable(&tx, &fee_payer, &policy).is_err());
+
+        // Another program
+        let tx = Transaction::new_with_payer(
+            &[system_instruction::transfer(&user, &Pubkey::new_unique(), 1)],
+            Some(&fee_payer),
+        );
+        assert!(check_relayable(&tx, &fee_payer, &policy).is_err());
+
+        // The fee payer signing for a tape
+        let tx = Transaction::new_with_payer(&[tape_ix(&fee_payer)], Some(&fee_payer));
+        assert!(check_relayable(&tx, &fee_payer, &policy).is_err());
+    }
+}
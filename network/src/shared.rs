@@ -0,0 +1,152 @@
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::Mutex;
+use tokio::time::{Duration, Instant};
+
+use super::admin::{AdminQueue, AdminTask};
+use super::store::{self, StoreError, TapeStore};
+
+/// A tape read again within this long isn't queued again, reads only need to be recorded
+/// closely enough for garbage collection to tell recently read tapes apart.
+const TOUCH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// One secondary store shared by every subsystem in a process. RocksDB doesn't cope well with
+/// several secondary handles on one directory catching up independently, so all readers go
+/// through the same handle and catch-ups are coordinated: a single background task keeps it
+/// fresh, and on-demand catch-ups are skipped if that task has just run.
+#[derive(Clone)]
+pub struct SecondaryStoreHandle {
+    store: Arc<TapeStore>,
+    last_catch_up: Arc<Mutex<Option<Instant>>>,
+    task_started: Arc<AtomicBool>,
+    access_queue: Option<Arc<AdminQueue>>,
+    recent_touches: Arc<std::sync::Mutex<HashMap<Pubkey, Instant>>>,
+}
+
+impl SecondaryStoreHandle {
+    pub fn new(store: TapeStore) -> Self {
+        Self {
+            store: Arc::new(store),
+            last_catch_up: Arc::new(Mutex::new(None)),
+            task_started: Arc::new(AtomicBool::new(false)),
+            access_queue: None,
+            recent_touches: Arc::new(std::sync::Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Open the read-only view of the store for a namespace. Reads recorded with `touch_tape`
+    /// go to the namespace's admin queue.
+    pub fn open(namespace: &str) -> Result<Self, StoreError> {
+        let queue = AdminQueue::open(namespace).map_err(|e| StoreError::IoError(std::io::Error::other(e)))?;
+        Ok(Self::new(store::secondary(namespace)?).with_access_queue(queue))
+    }
+
+    /// Queue reads recorded with `touch_tape` on `queue`, for the archive to write to the store.
+    pub fn with_access_queue(mut self, queue: AdminQueue) -> Self {
+        self.access_queue = Some(Arc::new(queue));
+        self
+    }
+
+    /// Record that a tape was read, so the archive's garbage collector evicts it after tapes read
+    /// longer ago. A secondary can't write the store, so the read is queued for the archive, at
+    /// most once per tape a minute however often it's read. Failing to queue it only costs GC
+    /// accuracy, so it's logged rather than returned.
+    pub fn touch_tape(&self, tape_address: &Pubkey) {
+        let Some(queue) = &self.access_queue else {
+            return;
+        };
+
+        {
+            let mut recent = self.recent_touches.lock().unwrap_or_else(|e| e.into_inner());
+            if matches!(recent.get(tape_address), Some(at) if at.elapsed() < TOUCH_INTERVAL) {
+                return;
+            }
+            recent.retain(|_, at| at.elapsed() < TOUCH_INTERVAL);
+            recent.insert(*tape_address, Instant::now());
+        }
+
+        let read_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let task = AdminTask::Touch(*tape_address, read_at);
+
+        let queued = queue
+            .is_queued(&task)
+            .and_then(|queued| if queued { Ok(()) } else { queue.push(task) });
+        if let Err(e) = queued {
+            log::warn!("Failed to queue a read of tape {}: {:?}", tape_address, e);
+        }
+    }
+
+    /// The store, for reading.
+    pub fn store(&self) -> Arc<TapeStore> {
+        Arc::clone(&self.store)
+    }
+
+    /// Catch up with the primary, unless that already happened within `max_age`. The catch-up
+    /// replays the primary's log from disk, so it runs on the blocking pool, and callers asking
+    /// meanwhile wait on the lock for it to finish instead of starting another.
+    pub async fn catch_up(&self, max_age: Duration) -> Result<(), StoreError> {
+        let mut last = self.last_catch_up.lock().await;
+        if matches!(*last, Some(at) if at.elapsed() < max_age) {
+            return Ok(());
+        }
+
+        let store = self.store();
+        tokio::task::spawn_blocking(move || store.catch_up_with_primary())
+            .await
+            .map_err(|e| StoreError::IoError(std::io::Error::other(e)))??;
+        *last = Some(Instant::now());
+
+        Ok(())
+    }
+
+    /// Start the background task that catches up every `interval`. Only the first call starts
+    /// one, later calls share it.
+    pub fn spawn_catch_up(&self, interval: Duration) {
+        if self.task_started.swap(true, Ordering::SeqCst) {
+            return;
+        }
+
+        let handle = self.clone();
+        tokio::spawn(async move {
+            loop {
+                if let Err(e) = handle.catch_up(interval).await {
+                    eprintln!("ERROR: Failed to catch up with the primary store: {:?}", e);
+                }
+                tokio::time::sleep(interval).await;
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempdir::TempDir;
+
+    #[test]
+    fn test_touch_tape() -> anyhow::Result<()> {
+        let temp_dir = TempDir::new("shared_test")?;
+        let store = TapeStore::new(temp_dir.path().join("store"))?;
+        let queue_dir = temp_dir.path().join("admin");
+        let handle = SecondaryStoreHandle::new(store).with_access_queue(AdminQueue::new(&queue_dir)?);
+        let (a, b) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+        // Repeated reads of a tape are queued once
+        handle.touch_tape(&a);
+        handle.touch_tape(&a);
+        handle.touch_tape(&b);
+
+        let tasks = AdminQueue::new(&queue_dir)?.drain()?;
+        assert_eq!(tasks.len(), 2);
+        assert!(matches!(tasks[0], AdminTask::Touch(tape, _) if tape == a));
+        assert!(matches!(tasks[1], AdminTask::Touch(tape, _) if tape == b));
+
+        Ok(())
+    }
+}
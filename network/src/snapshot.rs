@@ -0,0 +1,220 @@
+use anyhow::{anyhow, bail, Result};
+use brine_tree::MerkleTree;
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+use std::fs;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tape_api::prelude::*;
+
+use super::store::{TapeStore, STORE_FORMAT_VERSION};
+
+/// File in a snapshot directory describing what the snapshot holds.
+pub const MANIFEST_FILE: &str = "snapshot.json";
+
+/// What a snapshot held when it was taken, written next to the store files so a copy can be
+/// checked before it's relied on.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapshotManifest {
+    /// The [`STORE_FORMAT_VERSION`] of the node that took it.
+    pub format_version: u32,
+    pub namespace: String,
+    pub created_at: u64,
+    pub last_processed_slot: Option<u64>,
+    #[serde(flatten)]
+    pub counts: StoreCounts,
+    /// Size of the store files, without the manifest.
+    pub disk_bytes: u64,
+}
+
+/// How much a store holds.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StoreCounts {
+    pub tapes: u64,
+    pub segments: u64,
+    pub segment_bytes: u64,
+}
+
+impl StoreCounts {
+    /// Count everything in a store. Reads every segment, so it takes a while on a large one.
+    pub fn of(store: &TapeStore) -> Result<Self> {
+        let mut counts = Self::default();
+
+        for tape in store.iter_tapes()? {
+            tape?;
+            counts.tapes += 1;
+        }
+
+        for item in store.iter_all_segments()? {
+            let (_, _, data) = item?;
+            counts.segments += 1;
+            counts.segment_bytes += data.len() as u64;
+        }
+
+        Ok(counts)
+    }
+}
+
+/// Write a snapshot of `store` to `path`, which must not exist yet, with its manifest. Only a
+/// primary can take one.
+pub fn create_snapshot(store: &TapeStore, namespace: &str, path: &Path) -> Result<SnapshotManifest> {
+    store.checkpoint(path)?;
+    write_manifest(namespace, path)
+}
+
+/// Count the snapshot at `path` and write its manifest. Reads every segment of the copy, but
+/// not the store it was taken from, so it can run apart from the archive.
+pub fn write_manifest(namespace: &str, path: &Path) -> Result<SnapshotManifest> {
+    // Count the copy rather than the store, which the archive keeps writing to
+    let snapshot = TapeStore::open_read_only(path)?;
+    let manifest = SnapshotManifest {
+        format_version: STORE_FORMAT_VERSION,
+        namespace: namespace.to_string(),
+        created_at: SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs(),
+        last_processed_slot: snapshot.get_health().ok().map(|(slot, _)| slot),
+        counts: StoreCounts::of(&snapshot)?,
+        disk_bytes: disk_bytes(path)?,
+    };
+
+    let json = serde_json::to_string_pretty(&manifest)?;
+    let manifest_path = path.join(MANIFEST_FILE);
+    fs::write(&manifest_path, json)
+        .map_err(|e| anyhow!("Failed to write {}: {}", manifest_path.display(), e))?;
+
+    Ok(manifest)
+}
+
+/// Read the manifest of a snapshot and open its store read-only. Fails if the snapshot was taken
+/// by a newer node with a store layout this one doesn't know.
+pub fn open_snapshot(path: &Path) -> Result<(SnapshotManifest, TapeStore)> {
+    let manifest_path = path.join(MANIFEST_FILE);
+    let json = fs::read_to_string(&manifest_path)
+        .map_err(|e| anyhow!("Failed to read {}: {}", manifest_path.display(), e))?;
+    let manifest: SnapshotManifest = serde_json::from_str(&json)
+        .map_err(|e| anyhow!("Invalid manifest {}: {}", manifest_path.display(), e))?;
+
+    if manifest.format_version > STORE_FORMAT_VERSION {
+        bail!(
+            "Snapshot has store format {}, this node only reads up to {}",
+            manifest.format_version,
+            STORE_FORMAT_VERSION
+        );
+    }
+
+    let store = TapeStore::open_read_only(path)
+        .map_err(|e| anyhow!("Failed to open snapshot {}: {}", path.display(), e))?;
+
+    Ok((manifest, store))
+}
+
+/// Compare a snapshot against its manifest. Returns every mismatch found, none if it's intact.
+pub fn check_snapshot(path: &Path, manifest: &SnapshotManifest, store: &TapeStore) -> Result<Vec<String>> {
+    let mut problems = Vec::new();
+
+    match store.get_namespace()? {
+        Some(found) if found != manifest.namespace => problems.push(format!(
+            "Store belongs to namespace {}, manifest says {}",
+            found, manifest.namespace
+        )),
+        _ => {}
+    }
+
+    let counts = StoreCounts::of(store)?;
+    for (what, expected, found) in [
+        ("tapes", manifest.counts.tapes, counts.tapes),
+        ("segments", manifest.counts.segments, counts.segments),
+        ("segment bytes", manifest.counts.segment_bytes, counts.segment_bytes),
+    ] {
+        if expected != found {
+            problems.push(format!("Manifest lists {} {}, found {}", expected, what, found));
+        }
+    }
+
+    let on_disk = disk_bytes(path)?;
+    if on_disk != manifest.disk_bytes {
+        problems.push(format!(
+            "Manifest expects {} bytes on disk, found {}",
+            manifest.disk_bytes, on_disk
+        ));
+    }
+
+    Ok(problems)
+}
+
+/// Rebuild the Merkle root of a tape from the segments in a store, the same way the program
+/// builds it. Returns the root and the number of segments it was built from.
+pub fn tape_root(store: &TapeStore, tape_address: &Pubkey, merkle_seed: &[u8; 32]) -> Result<([u8; 32], usize)> {
+    let mut tree = MerkleTree::<{TREE_HEIGHT}>::new(&[merkle_seed.as_ref()]);
+
+    let visited = store.for_each_segment(tape_address, |segment_id, data| -> Result<()> {
+        let leaf = compute_leaf(segment_id, &padded_array::<SEGMENT_SIZE>(data));
+        tree.try_add_leaf(leaf)
+            .map_err(|e| anyhow!("Failed to add leaf to Merkle tree: {:?}", e))
+    })?;
+
+    Ok((tree.get_root().to_bytes(), visited))
+}
+
+/// Total size of the store files in a snapshot directory. The manifest and RocksDB's info logs,
+/// which are written again every time the store is opened, don't count.
+fn disk_bytes(path: &Path) -> Result<u64> {
+    let mut total = 0;
+    for entry in fs::read_dir(path).map_err(|e| anyhow!("Failed to read {}: {}", path.display(), e))? {
+        let entry = entry?;
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        if name != MANIFEST_FILE && !name.starts_with("LOG") {
+            total += entry.metadata()?.len();
+        }
+    }
+
+    Ok(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempdir::TempDir;
+
+    #[test]
+    fn test_snapshot() -> Result<()> {
+        let dir = TempDir::new("snapshot")?;
+        let store = TapeStore::new(dir.path().join("store"))?;
+        store.init_namespace("test")?;
+
+        let address = Pubkey::new_unique();
+        store.finalize_tape(&address, 1)?;
+        store.add_segment(&address, 0, vec![1, 2, 3])?;
+        store.add_segment(&address, 1, vec![4, 5])?;
+
+        let path = dir.path().join("snapshot");
+        let manifest = create_snapshot(&store, "test", &path)?;
+        assert_eq!(manifest.counts, StoreCounts { tapes: 1, segments: 2, segment_bytes: 5 });
+
+        let (opened, snapshot) = open_snapshot(&path)?;
+        assert_eq!(opened, manifest);
+        assert!(check_snapshot(&path, &opened, &snapshot)?.is_empty());
+
+        // The root matches one built straight from the segments
+        let seed = [7u8; 32];
+        let mut tree = MerkleTree::<{TREE_HEIGHT}>::new(&[seed.as_ref()]);
+        for (id, data) in [[1u8, 2, 3].as_slice(), &[4, 5]].iter().enumerate() {
+            tree.try_add_leaf(compute_leaf(id as u64, &padded_array::<SEGMENT_SIZE>(data))).unwrap();
+        }
+        assert_eq!(tape_root(&snapshot, &address, &seed)?, (tree.get_root().to_bytes(), 2));
+
+        // A manifest that doesn't match the store is caught
+        let tampered = SnapshotManifest {
+            counts: StoreCounts { segments: 3, ..manifest.counts },
+            ..manifest.clone()
+        };
+        assert_eq!(check_snapshot(&path, &tampered, &snapshot)?.len(), 1);
+
+        // As is a snapshot from a newer node
+        let newer = SnapshotManifest { format_version: STORE_FORMAT_VERSION + 1, ..manifest };
+        fs::write(path.join(MANIFEST_FILE), serde_json::to_string(&newer)?)?;
+        assert!(open_snapshot(&path).is_err());
+
+        Ok(())
+    }
+}
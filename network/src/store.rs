@@ -1,10 +1,18 @@
-use rocksdb::{ColumnFamilyDescriptor, DBCompressionType, Options, WriteBatch, DB};
+use async_trait::async_trait;
+use rocksdb::{ColumnFamily, ColumnFamilyDescriptor, DBCompressionType, Direction, IteratorMode, Options, WriteBatch, DB};
 use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+use std::ops::Range;
 use std::env;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tape_api::prelude::MineEvent;
 use tape_api::SEGMENT_SIZE;
+use tape_client::SegmentSource;
 use thiserror::Error;
 
+use super::breaker::BreakerState;
+
 #[derive(Error, Debug)]
 pub enum StoreError {
     #[error("RocksDB error: {0}")]
@@ -19,36 +27,422 @@ pub enum StoreError {
     TapesCfNotFound,
     #[error("Segments column family not found")]
     SegmentsCfNotFound,
+    #[error("Thumbnails column family not found")]
+    ThumbnailsCfNotFound,
+    #[error("Access column family not found")]
+    AccessCfNotFound,
+    #[error("Meta column family not found")]
+    MetaCfNotFound,
+    #[error("Miners column family not found")]
+    MinersCfNotFound,
+    #[error("Epochs column family not found")]
+    EpochsCfNotFound,
+    #[error("Segment slots column family not found")]
+    SegmentSlotsCfNotFound,
+    #[error("Gaps column family not found")]
+    GapsCfNotFound,
+    #[error("Conflicts column family not found")]
+    ConflictsCfNotFound,
+    #[error("Labels column family not found")]
+    LabelsCfNotFound,
+    #[error("Blocklist column family not found")]
+    BlocklistCfNotFound,
+    #[error("First seen column family not found")]
+    FirstSeenCfNotFound,
+    #[error("Tape slots column family not found")]
+    TapeSlotsCfNotFound,
+    #[error("Checksums column family not found")]
+    ChecksumsCfNotFound,
+    #[error("Mutable segments column family not found")]
+    MutableSegmentsCfNotFound,
+    #[error("Column family {0} not found")]
+    CfNotFound(&'static str),
+    #[error("Store at {path} is locked by {holder}, stop it first or use a command that only reads the store")]
+    Locked { path: String, holder: String },
+    #[error("Store belongs to namespace {found}, but the cluster is {expected}")]
+    NamespaceMismatch { expected: String, found: String },
+    #[error("Store at {0} is from before stores were kept per cluster, run `tapedrive archive` once to move it")]
+    LegacyLayout(String),
+    #[error("Store at {0} is from before stores were kept per cluster, but {1} already holds a store, move one of them aside")]
+    LegacyConflict(String, String),
     #[error("Tape not found: number {0}")]
     TapeNotFound(u64),
     #[error("Segment not found for tape address {0}, segment {1}")]
     SegmentNotFound(String, u64),
     #[error("Tape not found for address: {0}")]
     TapeNotFoundForAddress(String),
+    #[error("Thumbnail not found for address: {0}")]
+    ThumbnailNotFound(String),
     #[error("Invalid pubkey: {0}")]
     InvalidPubkey(String),
+    #[error("Segment {1} of tape {0} doesn't match its checksum, run `tapedrive snapshot scrub` to re-fetch it")]
+    ChecksumMismatch(String, u64),
     #[error("Segment data exceeds maximum size of {0} bytes")]
     SegmentSizeExceeded(usize),
     #[error("Invalid segment key format")]
     InvalidSegmentKey,
+    #[error("Invalid thumbnail cursor")]
+    InvalidThumbnailCursor,
+    #[error("Invalid tape label")]
+    InvalidLabel,
     #[error("Invalid path")]
     InvalidPath,
 }
 
+/// Number of slots per leaderboard bucket (about an hour).
+pub const LEADERBOARD_BUCKET_SLOTS: u64 = 9_000;
+
+// Key prefixes in the miners column family
+const MINER_TOTAL: u8 = 0;
+const MINER_BUCKET: u8 = 1;
+const MINER_CURSOR: u8 = 2;
+
+// Key prefixes in the blocklist column family
+const BLOCKED: u8 = 0;
+const POLICY_LOG: u8 = 1;
+
+// Key prefixes in the first_seen column family
+const SEEN_TAPE: u8 = 0;
+const SEEN_SLOT: u8 = 1;
+
+/// What the leaderboard ranks miners by.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LeaderboardSort {
+    Proofs,
+    Rewards,
+}
+
+/// Mining totals for a single miner.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MinerStats {
+    pub miner: Pubkey,
+    pub proofs: u64,
+    pub rewards: u64,
+}
+
+/// The state of the Epoch account as the archive last saw it during an epoch.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct EpochRecord {
+    pub number: u64,
+    pub difficulty: u64,
+    pub last_epoch_at: i64,
+    pub base_rate: u64,
+    pub target_rate: u64,
+    /// Slot the snapshot was taken at.
+    pub slot: u64,
+}
+
+impl EpochRecord {
+    const SIZE: usize = 48;
+
+    fn to_bytes(self) -> [u8; Self::SIZE] {
+        let mut bytes = [0u8; Self::SIZE];
+        bytes[0..8].copy_from_slice(&self.number.to_be_bytes());
+        bytes[8..16].copy_from_slice(&self.difficulty.to_be_bytes());
+        bytes[16..24].copy_from_slice(&self.last_epoch_at.to_be_bytes());
+        bytes[24..32].copy_from_slice(&self.base_rate.to_be_bytes());
+        bytes[32..40].copy_from_slice(&self.target_rate.to_be_bytes());
+        bytes[40..48].copy_from_slice(&self.slot.to_be_bytes());
+        bytes
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, StoreError> {
+        let field = |i: usize| -> Result<[u8; 8], StoreError> {
+            bytes
+                .get(i * 8..i * 8 + 8)
+                .and_then(|b| b.try_into().ok())
+                .ok_or(StoreError::InvalidSegmentKey)
+        };
+
+        Ok(Self {
+            number: u64::from_be_bytes(field(0)?),
+            difficulty: u64::from_be_bytes(field(1)?),
+            last_epoch_at: i64::from_be_bytes(field(2)?),
+            base_rate: u64::from_be_bytes(field(3)?),
+            target_rate: u64::from_be_bytes(field(4)?),
+            slot: u64::from_be_bytes(field(5)?),
+        })
+    }
+}
+
+/// A stored segment with the slot it was last written at. The slot is unknown for segments
+/// synced from a trusted peer.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StoredSegment {
+    pub segment_number: u64,
+    pub data: Vec<u8>,
+    pub slot: Option<u64>,
+}
+
+/// What [`TapeStore::scrub`] found.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ScrubReport {
+    /// Segments that match their checksum.
+    pub checked: u64,
+    /// Segments stored before checksums were added, which can't be checked.
+    pub unchecked: u64,
+    /// Segments that don't match their checksum, as `(tape address, segment number)`.
+    pub corrupt: Vec<(Pubkey, u64)>,
+}
+
+/// A write to a segment that was already written earlier in the same slot, e.g. a retried
+/// transaction that landed twice. The first write is kept, this one was dropped.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SegmentConflict {
+    pub segment_number: u64,
+    pub slot: u64,
+    /// Whether the dropped write carried the same data as the one kept.
+    pub identical: bool,
+    /// The data of the dropped write.
+    pub data: Vec<u8>,
+}
+
+/// A label and note a node operator put on a tape, e.g. to curate what their gateway serves or
+/// to triage abuse reports. Local to the node, nothing of it is on-chain.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TapeLabel {
+    pub label: String,
+    pub note: String,
+    /// Unix time the label was set.
+    pub updated_at: u64,
+}
+
+impl TapeLabel {
+    /// Longest label accepted, in bytes.
+    pub const MAX_LABEL_LEN: usize = 64;
+    /// Longest note accepted, in bytes.
+    pub const MAX_NOTE_LEN: usize = 1024;
+
+    /// `<updated_at><label length, u16><label><note>`
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(10 + self.label.len() + self.note.len());
+        bytes.extend_from_slice(&self.updated_at.to_be_bytes());
+        bytes.extend_from_slice(&(self.label.len() as u16).to_be_bytes());
+        bytes.extend_from_slice(self.label.as_bytes());
+        bytes.extend_from_slice(self.note.as_bytes());
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, StoreError> {
+        if bytes.len() < 10 {
+            return Err(StoreError::InvalidLabel);
+        }
+
+        let mut updated_at = [0u8; 8];
+        updated_at.copy_from_slice(&bytes[0..8]);
+        let label_len = u16::from_be_bytes([bytes[8], bytes[9]]) as usize;
+        if bytes.len() < 10 + label_len {
+            return Err(StoreError::InvalidLabel);
+        }
+        let (label, note) = bytes[10..].split_at(label_len);
+
+        Ok(Self {
+            label: String::from_utf8(label.to_vec()).map_err(|_| StoreError::InvalidLabel)?,
+            note: String::from_utf8(note.to_vec()).map_err(|_| StoreError::InvalidLabel)?,
+            updated_at: u64::from_be_bytes(updated_at),
+        })
+    }
+}
+
+/// Why and when a tape was blocked.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BlockedTape {
+    pub reason: String,
+    /// Unix time it was blocked.
+    pub blocked_at: u64,
+}
+
+/// A change to what a node serves.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PolicyAction {
+    Block,
+    Unblock,
+}
+
+impl std::fmt::Display for PolicyAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            PolicyAction::Block => write!(f, "block"),
+            PolicyAction::Unblock => write!(f, "unblock"),
+        }
+    }
+}
+
+/// When the archive first saw a tape, from the block it was created or first written in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FirstSeen {
+    pub slot: u64,
+    /// Unix time of the block, or of archiving it if the block had none.
+    pub time: i64,
+}
+
+/// An entry in the log of every block and unblock, kept for audits.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PolicyLogEntry {
+    /// Unix time the action took effect.
+    pub at: u64,
+    pub tape_address: Pubkey,
+    pub action: PolicyAction,
+    pub reason: String,
+}
+
+/// A slot the archive failed to process, kept until a repair succeeds.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SlotGap {
+    pub slot: u64,
+    /// How many times processing the slot has failed.
+    pub attempts: u32,
+    /// The most recent error.
+    pub error: String,
+}
+
+/// The archive's progress at one point in time, kept to tell how fast it's syncing.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HealthSample {
+    /// Unix time the sample was taken.
+    pub time: u64,
+    pub last_processed_slot: u64,
+    pub drift: u64,
+}
+
+/// How fast the archive is getting through slots and closing on the tip, from its recent
+/// health samples.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SyncEstimate {
+    /// Slots processed per second.
+    pub slots_per_second: f64,
+    /// How much faster than the cluster the archive moves, negative while it falls behind.
+    pub closing_per_second: f64,
+    /// Seconds until the archive reaches the tip, `None` while it isn't gaining on it.
+    pub eta_seconds: Option<u64>,
+}
+
+impl SyncEstimate {
+    /// Estimate from samples oldest first, `None` without two samples to compare.
+    pub fn from_samples(samples: &[HealthSample]) -> Option<Self> {
+        let (first, last) = (samples.first()?, samples.last()?);
+        let elapsed = last.time.checked_sub(first.time).filter(|&t| t > 0)? as f64;
+
+        let processed = last.last_processed_slot.saturating_sub(first.last_processed_slot) as f64;
+        // The tip moves too, the drift shrinking is what brings the archive closer
+        let closed = first.drift as f64 - last.drift as f64;
+        let closing_per_second = closed / elapsed;
+
+        let eta_seconds = match last.drift {
+            0 => Some(0),
+            drift if closing_per_second > 0.0 => Some((drift as f64 / closing_per_second).ceil() as u64),
+            _ => None,
+        };
+
+        Some(Self { slots_per_second: processed / elapsed, closing_per_second, eta_seconds })
+    }
+}
+
+impl std::fmt::Display for SyncEstimate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:.1} slots/s", self.slots_per_second)?;
+        match self.eta_seconds {
+            Some(0) => write!(f, ", at the tip"),
+            Some(secs) => write!(f, ", at the tip in ~{}", format_eta(secs)),
+            None => write!(f, ", not gaining on the tip"),
+        }
+    }
+}
+
+/// A rough duration, in its two largest units.
+fn format_eta(secs: u64) -> String {
+    let (days, hours, minutes) = (secs / 86_400, secs / 3_600 % 24, secs / 60 % 60);
+    match (days, hours, minutes) {
+        (0, 0, 0) => format!("{}s", secs),
+        (0, 0, m) => format!("{}m", m),
+        (0, h, m) => format!("{}h {}m", h, m),
+        (d, h, _) => format!("{}d {}h", d, h),
+    }
+}
+
+/// Version of the store's on-disk layout, bumped whenever keys or values change shape. Recorded
+/// in snapshots, so a node can tell whether it understands one.
+///
+/// 2: segment checksums, in a column family older nodes can't open the store with.
+/// 3: segments of tapes not yet finalized kept apart from sealed ones, in another column family.
+pub const STORE_FORMAT_VERSION: u32 = 3;
+
+/// Key prefix of health samples in the health column family, followed by the sample's time.
+const HEALTH_SAMPLE_PREFIX: &[u8] = b"sample";
+
+/// Least time between two health samples.
+const HEALTH_SAMPLE_INTERVAL: u64 = 60;
+
+/// Health samples kept, an hour's worth at the sample interval.
+const MAX_HEALTH_SAMPLES: usize = 60;
+
+/// Every column family in the store.
+const COLUMN_FAMILIES: [&str; 17] = [
+    "tapes", "segments", "health", "thumbnails", "access", "meta", "miners", "epochs", "segment_slots", "gaps",
+    "conflicts", "labels", "blocklist", "first_seen", "tape_slots", "checksums", "mutable_segments",
+];
+
+/// How the writable store uses IO.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct StoreOptions {
+    /// Bytes per second flushes and compactions may write, unlimited if `None`.
+    pub rate_limit: Option<u64>,
+    /// Leave compaction to [`TapeStore::compact`] instead of letting RocksDB run it whenever
+    /// it decides to.
+    pub manual_compaction: bool,
+}
+
+/// The archive's RocksDB store.
+///
+/// One process opens a directory as the primary and does all the writing, a second primary on
+/// the same directory is refused. Every write goes through a single `WriteBatch`, so readers see
+/// all of it or none of it, and the primary reads its own writes as soon as the call returns.
+///
+/// Other processes (web nodes, miners) open secondaries. A secondary sees nothing the primary
+/// wrote after it opened until [`Self::catch_up_with_primary`], and then sees whole batches, up
+/// to the primary's latest.
+///
+/// A tape's segments are mutable until it's finalized, and kept apart from the sealed ones that
+/// readers see. [`Self::finalize_tape`] seals them and indexes the tape by number in the same
+/// write, so a reader finds a tape by number and by content at once, with all of its segments.
+/// Only [`Self::get_tape_segments_with_slots`] reads mutable segments.
+///
+/// Writes are in the write-ahead log when they return, which survives the process crashing but
+/// not the machine losing power. [`Self::sync`] makes them durable, [`Self::flush`] moves them
+/// into table files too. Both are for the primary only.
 pub struct TapeStore {
     db: DB,
 }
 
 impl TapeStore {
     pub fn new<P: AsRef<Path>>(path: P) -> Result<Self, StoreError> {
+        Self::with_options(path, &StoreOptions::default())
+    }
+
+    pub fn with_options<P: AsRef<Path>>(path: P, options: &StoreOptions) -> Result<Self, StoreError> {
         let path = path.as_ref();
         let mut cf_opts = Options::default();
         cf_opts.create_if_missing(true);
         cf_opts.set_compression_type(DBCompressionType::Lz4);
+        cf_opts.set_disable_auto_compactions(options.manual_compaction);
 
         let cf_tapes    = ColumnFamilyDescriptor::new("tapes", cf_opts.clone());
         let cf_segments = ColumnFamilyDescriptor::new("segments", cf_opts.clone());
         let cf_health   = ColumnFamilyDescriptor::new("health", cf_opts.clone());
+        let cf_thumbs   = ColumnFamilyDescriptor::new("thumbnails", cf_opts.clone());
+        let cf_access   = ColumnFamilyDescriptor::new("access", cf_opts.clone());
+        let cf_meta     = ColumnFamilyDescriptor::new("meta", cf_opts.clone());
+        let cf_miners   = ColumnFamilyDescriptor::new("miners", cf_opts.clone());
+        let cf_epochs   = ColumnFamilyDescriptor::new("epochs", cf_opts.clone());
+        let cf_slots    = ColumnFamilyDescriptor::new("segment_slots", cf_opts.clone());
+        let cf_gaps     = ColumnFamilyDescriptor::new("gaps", cf_opts.clone());
+        let cf_conflict = ColumnFamilyDescriptor::new("conflicts", cf_opts.clone());
+        let cf_labels   = ColumnFamilyDescriptor::new("labels", cf_opts.clone());
+        let cf_blocked  = ColumnFamilyDescriptor::new("blocklist", cf_opts.clone());
+        let cf_seen     = ColumnFamilyDescriptor::new("first_seen", cf_opts.clone());
+        let cf_written  = ColumnFamilyDescriptor::new("tape_slots", cf_opts.clone());
+        let cf_checks   = ColumnFamilyDescriptor::new("checksums", cf_opts.clone());
+        let cf_mutable  = ColumnFamilyDescriptor::new("mutable_segments", cf_opts.clone());
 
         let mut db_opts = Options::default();
         db_opts.create_if_missing(true);
@@ -56,11 +450,14 @@ impl TapeStore {
         db_opts.set_write_buffer_size(128 * 1024 * 1024);
         db_opts.set_max_write_buffer_number(4);
         db_opts.create_missing_column_families(true);
+        if let Some(rate_limit) = options.rate_limit {
+            db_opts.set_ratelimiter(rate_limit.min(i64::MAX as u64) as i64, 100_000, 10);
+        }
 
         let db = DB::open_cf_descriptors(
             &db_opts,
             path,
-            vec![cf_tapes, cf_segments, cf_health],
+            vec![cf_tapes, cf_segments, cf_health, cf_thumbs, cf_access, cf_meta, cf_miners, cf_epochs, cf_slots, cf_gaps, cf_conflict, cf_labels, cf_blocked, cf_seen, cf_written, cf_checks, cf_mutable],
         )?;
 
         Ok(Self { db })
@@ -78,6 +475,20 @@ impl TapeStore {
         let cf_tapes    = ColumnFamilyDescriptor::new("tapes", cf_opts.clone());
         let cf_segments = ColumnFamilyDescriptor::new("segments", cf_opts.clone());
         let cf_health   = ColumnFamilyDescriptor::new("health", cf_opts.clone());
+        let cf_thumbs   = ColumnFamilyDescriptor::new("thumbnails", cf_opts.clone());
+        let cf_access   = ColumnFamilyDescriptor::new("access", cf_opts.clone());
+        let cf_meta     = ColumnFamilyDescriptor::new("meta", cf_opts.clone());
+        let cf_miners   = ColumnFamilyDescriptor::new("miners", cf_opts.clone());
+        let cf_epochs   = ColumnFamilyDescriptor::new("epochs", cf_opts.clone());
+        let cf_slots    = ColumnFamilyDescriptor::new("segment_slots", cf_opts.clone());
+        let cf_gaps     = ColumnFamilyDescriptor::new("gaps", cf_opts.clone());
+        let cf_conflict = ColumnFamilyDescriptor::new("conflicts", cf_opts.clone());
+        let cf_labels   = ColumnFamilyDescriptor::new("labels", cf_opts.clone());
+        let cf_blocked  = ColumnFamilyDescriptor::new("blocklist", cf_opts.clone());
+        let cf_seen     = ColumnFamilyDescriptor::new("first_seen", cf_opts.clone());
+        let cf_written  = ColumnFamilyDescriptor::new("tape_slots", cf_opts.clone());
+        let cf_checks   = ColumnFamilyDescriptor::new("checksums", cf_opts.clone());
+        let cf_mutable  = ColumnFamilyDescriptor::new("mutable_segments", cf_opts.clone());
 
         let mut db_opts = Options::default();
         db_opts.set_compression_type(DBCompressionType::Lz4);
@@ -86,18 +497,110 @@ impl TapeStore {
             &db_opts,
             primary_path,
             secondary_path,
-            vec![cf_tapes, cf_segments, cf_health],
+            vec![cf_tapes, cf_segments, cf_health, cf_thumbs, cf_access, cf_meta, cf_miners, cf_epochs, cf_slots, cf_gaps, cf_conflict, cf_labels, cf_blocked, cf_seen, cf_written, cf_checks, cf_mutable],
         )?;
         Ok(Self { db })
     }
 
+    /// Open a store that nothing else writes to, e.g. a snapshot, without changing any of its
+    /// files.
+    pub fn open_read_only<P: AsRef<Path>>(path: P) -> Result<Self, StoreError> {
+        let mut cf_opts = Options::default();
+        cf_opts.set_compression_type(DBCompressionType::Lz4);
+
+        let descriptors = COLUMN_FAMILIES
+            .iter()
+            .map(|name| ColumnFamilyDescriptor::new(*name, cf_opts.clone()));
+
+        let db = DB::open_cf_descriptors_read_only(&Options::default(), path, descriptors, false)?;
+        Ok(Self { db })
+    }
+
     pub fn catch_up_with_primary(&self) -> Result<(), StoreError> {
         self.db.try_catch_up_with_primary()?;
         Ok(())
     }
 
-    /// Update the health values in the database.
+    /// Write every column family's memtable out to table files, so a secondary catching up
+    /// doesn't have to replay them from the write-ahead log. Also makes them durable.
+    pub fn flush(&self) -> Result<(), StoreError> {
+        for name in COLUMN_FAMILIES {
+            let cf = self.db.cf_handle(name).ok_or(StoreError::CfNotFound(name))?;
+            self.db.flush_cf(&cf)?;
+        }
+        Ok(())
+    }
+
+    /// Sync the write-ahead log to disk, so every write so far survives a power loss.
+    pub fn sync(&self) -> Result<(), StoreError> {
+        self.db.flush_wal(true)?;
+        Ok(())
+    }
+
+    /// Compact every column family in full. Blocks until done, which can take a while on a large
+    /// store, and is throttled by the store's rate limit.
+    pub fn compact(&self) -> Result<(), StoreError> {
+        for name in COLUMN_FAMILIES {
+            let cf = self.db.cf_handle(name).ok_or(StoreError::CfNotFound(name))?;
+            self.db.compact_range_cf(&cf, None::<&[u8]>, None::<&[u8]>);
+        }
+        Ok(())
+    }
+
+    /// Directory the store's files are in.
+    pub fn path(&self) -> &Path {
+        self.db.path()
+    }
+
+    /// Write a consistent copy of the store to `path`, which must not exist yet. Files are hard
+    /// linked where the filesystem allows, so this is cheap even for a large store.
+    pub fn checkpoint<P: AsRef<Path>>(&self, path: P) -> Result<(), StoreError> {
+        rocksdb::checkpoint::Checkpoint::new(&self.db)?.create_checkpoint(path)?;
+        Ok(())
+    }
+
+    /// Bind the store to a namespace on first use, or check it matches the one it was created with.
+    pub fn init_namespace(&self, namespace: &str) -> Result<(), StoreError> {
+        match self.get_namespace()? {
+            Some(found) if found != namespace => Err(StoreError::NamespaceMismatch {
+                expected: namespace.to_string(),
+                found,
+            }),
+            Some(_) => Ok(()),
+            None => {
+                let cf_meta = self
+                    .db
+                    .cf_handle("meta")
+                    .ok_or(StoreError::MetaCfNotFound)?;
+
+                self.db.put_cf(cf_meta, b"namespace", namespace.as_bytes())?;
+                Ok(())
+            }
+        }
+    }
+
+    /// Load the namespace the store is bound to, if any.
+    pub fn get_namespace(&self) -> Result<Option<String>, StoreError> {
+        let cf_meta = self
+            .db
+            .cf_handle("meta")
+            .ok_or(StoreError::MetaCfNotFound)?;
+
+        let namespace = self
+            .db
+            .get_cf(cf_meta, b"namespace")?
+            .map(|bytes| String::from_utf8_lossy(&bytes).into_owned());
+
+        Ok(namespace)
+    }
+
+    /// Update the health values in the database. A sample of them is kept at most once a
+    /// minute, for [`Self::get_health_samples`].
     pub fn update_health(&self, last_processed_slot: u64, drift: u64) -> Result<(), StoreError> {
+        self.update_health_at(now(), last_processed_slot, drift)
+    }
+
+    fn update_health_at(&self, time: u64, last_processed_slot: u64, drift: u64) -> Result<(), StoreError> {
         let cf = self
             .db
             .cf_handle("health")
@@ -107,12 +610,91 @@ impl TapeStore {
         batch.put_cf(cf, b"last_processed_slot", &last_processed_slot.to_be_bytes());
         batch.put_cf(cf, b"drift", &drift.to_be_bytes());
 
+        let samples = self.get_health_samples()?;
+        if samples.last().is_none_or(|last| time >= last.time + HEALTH_SAMPLE_INTERVAL) {
+            let mut value = last_processed_slot.to_be_bytes().to_vec();
+            value.extend_from_slice(&drift.to_be_bytes());
+            batch.put_cf(cf, health_sample_key(time), value);
+
+            let excess = (samples.len() + 1).saturating_sub(MAX_HEALTH_SAMPLES);
+            for sample in &samples[..excess] {
+                batch.delete_cf(cf, health_sample_key(sample.time));
+            }
+        }
+
         self.db.write(batch)?;
 
         Ok(())
     }
 
+    /// The archive's recent health samples, oldest first.
+    pub fn get_health_samples(&self) -> Result<Vec<HealthSample>, StoreError> {
+        let cf = self
+            .db
+            .cf_handle("health")
+            .ok_or(StoreError::HealthCfNotFound)?;
+
+        let mut samples = Vec::new();
+        let iter = self.db.iterator_cf(cf, IteratorMode::From(HEALTH_SAMPLE_PREFIX, Direction::Forward));
+        for item in iter {
+            let (key, value) = item?;
+            let Some(time) = key.strip_prefix(HEALTH_SAMPLE_PREFIX) else {
+                break;
+            };
+            let time = time.try_into().map(u64::from_be_bytes).map_err(|_| StoreError::InvalidSegmentKey)?;
+            let field = |range: Range<usize>| {
+                value
+                    .get(range)
+                    .and_then(|b: &[u8]| b.try_into().ok())
+                    .map(u64::from_be_bytes)
+                    .ok_or(StoreError::InvalidSegmentKey)
+            };
+            samples.push(HealthSample { time, last_processed_slot: field(0..8)?, drift: field(8..16)? });
+        }
+
+        Ok(samples)
+    }
+
     /// Load the last‐written health values.
+    /// Save the state of the archive's RPC circuit breaker.
+    pub fn update_rpc_status(&self, state: BreakerState, failures: u32) -> Result<(), StoreError> {
+        let cf = self
+            .db
+            .cf_handle("health")
+            .ok_or(StoreError::HealthCfNotFound)?;
+
+        let mut value = vec![state.to_u8()];
+        value.extend_from_slice(&failures.to_be_bytes());
+        self.db.put_cf(cf, b"rpc_status", value)?;
+
+        Ok(())
+    }
+
+    /// The state of the archive's RPC circuit breaker and its consecutive failures, if the
+    /// archive has saved one.
+    pub fn get_rpc_status(&self) -> Result<Option<(BreakerState, u32)>, StoreError> {
+        let cf = self
+            .db
+            .cf_handle("health")
+            .ok_or(StoreError::HealthCfNotFound)?;
+
+        let Some(value) = self.db.get_cf(cf, b"rpc_status")? else {
+            return Ok(None);
+        };
+
+        let state = value
+            .first()
+            .and_then(|&b| BreakerState::from_u8(b))
+            .ok_or(StoreError::InvalidSegmentKey)?;
+        let failures = value
+            .get(1..5)
+            .and_then(|b| b.try_into().ok())
+            .map(u32::from_be_bytes)
+            .ok_or(StoreError::InvalidSegmentKey)?;
+
+        Ok(Some((state, failures)))
+    }
+
     pub fn get_health(&self) -> Result<(u64, u64), StoreError> {
         let cf = self
             .db
@@ -136,16 +718,66 @@ impl TapeStore {
     }
 
 
-    pub fn add_tape(&self, tape_number: u64, address: &Pubkey) -> Result<(), StoreError> {
+    /// Finalize a tape: seal its mutable segments and index it under its number, both ways, in
+    /// one write. Until then readers see none of it, by number or by content, so they never see
+    /// a tape the store hasn't finished. A tape finalized again under another number drops the
+    /// old one in the same write, a number never points at an address that points elsewhere.
+    pub fn finalize_tape(&self, address: &Pubkey, tape_number: u64) -> Result<(), StoreError> {
         let cf_tapes = self
             .db
             .cf_handle("tapes")
             .ok_or(StoreError::TapesCfNotFound)?;
 
+        let cf_segments = self
+            .db
+            .cf_handle("segments")
+            .ok_or(StoreError::SegmentsCfNotFound)?;
+
+        let cf_checksums = self
+            .db
+            .cf_handle("checksums")
+            .ok_or(StoreError::ChecksumsCfNotFound)?;
+
+        let cf_mutable = self
+            .db
+            .cf_handle("mutable_segments")
+            .ok_or(StoreError::MutableSegmentsCfNotFound)?;
+
+        let cf_access = self
+            .db
+            .cf_handle("access")
+            .ok_or(StoreError::AccessCfNotFound)?;
+
         let tape_number_key = tape_number.to_be_bytes().to_vec();
         let address_key = address.to_bytes().to_vec();
 
         let mut batch = WriteBatch::default();
+        match self.get_tape_number(address) {
+            Ok(previous) if previous != tape_number => {
+                batch.delete_cf(cf_tapes, previous.to_be_bytes());
+            }
+            Ok(_) | Err(StoreError::TapeNotFoundForAddress(_)) => {}
+            Err(e) => return Err(e),
+        }
+
+        // Move the mutable segments over to the sealed ones, checksummed on the way
+        let prefix = address.to_bytes();
+        let mut sealed = 0;
+        for item in self.db.iterator_cf(cf_mutable, IteratorMode::From(&prefix, Direction::Forward)) {
+            let (key, data) = item?;
+            if !key.starts_with(&prefix) {
+                break;
+            }
+            batch.put_cf(cf_checksums, &key, segment_checksum(&data).to_be_bytes());
+            batch.put_cf(cf_segments, &key, &data);
+            batch.delete_cf(cf_mutable, &key);
+            sealed += 1;
+        }
+        // A freshly sealed tape counts as recently accessed
+        if sealed > 0 {
+            batch.put_cf(cf_access, address.to_bytes(), now().to_be_bytes());
+        }
+
         // Store tape_number -> address
         batch.put_cf(cf_tapes, tape_number_key, address.to_bytes());
         // Store address -> tape_number
@@ -155,29 +787,125 @@ impl TapeStore {
         Ok(())
     }
 
+    /// Store a segment of a sealed tape, e.g. one fetched again after it rotted on disk. It's
+    /// visible to readers right away, segments of a tape still being written go through
+    /// [`Self::add_mutable_segment`] instead.
     pub fn add_segment(
         &self,
         tape_address: &Pubkey,
         segment_number: u64,
         data: Vec<u8>,
+    ) -> Result<(), StoreError> {
+        let mut batch = WriteBatch::default();
+        self.batch_segment(&mut batch, tape_address, segment_number, data, true)?;
+        self.db.write(batch)?;
+
+        Ok(())
+    }
+
+    /// Store a segment of a tape that isn't finalized yet. Readers don't see it until
+    /// [`Self::finalize_tape`] seals the tape.
+    pub fn add_mutable_segment(
+        &self,
+        tape_address: &Pubkey,
+        segment_number: u64,
+        data: Vec<u8>,
+    ) -> Result<(), StoreError> {
+        let mut batch = WriteBatch::default();
+        self.batch_segment(&mut batch, tape_address, segment_number, data, false)?;
+        self.db.write(batch)?;
+
+        Ok(())
+    }
+
+    /// Store a segment written at `slot`, sealed if its tape is finalized and mutable otherwise.
+    /// Writes older than the one already stored are ignored, so re-processing old blocks never
+    /// rolls a mutable tape back. The segment and its slot go in one write. Returns whether the
+    /// segment was stored.
+    pub fn add_segment_at(
+        &self,
+        tape_address: &Pubkey,
+        segment_number: u64,
+        data: Vec<u8>,
+        slot: u64,
+    ) -> Result<bool, StoreError> {
+        let cf_slots = self
+            .db
+            .cf_handle("segment_slots")
+            .ok_or(StoreError::SegmentSlotsCfNotFound)?;
+
+        let mut key = Vec::with_capacity(40);
+        key.extend_from_slice(&tape_address.to_bytes());
+        key.extend_from_slice(&segment_number.to_be_bytes());
+
+        if let Some(stored) = self.db.get_cf(cf_slots, &key)? {
+            if decode_slot(&stored)? > slot {
+                return Ok(false);
+            }
+        }
+
+        let sealed = match self.get_tape_number(tape_address) {
+            Ok(_) => true,
+            Err(StoreError::TapeNotFoundForAddress(_)) => false,
+            Err(e) => return Err(e),
+        };
+
+        let mut batch = WriteBatch::default();
+        self.batch_segment(&mut batch, tape_address, segment_number, data, sealed)?;
+        batch.put_cf(cf_slots, &key, slot.to_be_bytes());
+        self.db.write(batch)?;
+
+        Ok(true)
+    }
+
+    /// Add the writes storing a segment to `batch`: with its checksum among the sealed segments,
+    /// or in the mutable area.
+    fn batch_segment(
+        &self,
+        batch: &mut WriteBatch,
+        tape_address: &Pubkey,
+        segment_number: u64,
+        data: Vec<u8>,
+        sealed: bool,
     ) -> Result<(), StoreError> {
         if data.len() > SEGMENT_SIZE {
             return Err(StoreError::SegmentSizeExceeded(SEGMENT_SIZE));
         }
 
+        // Create key: [<tape_address><segment_number>]
+        let mut key = Vec::with_capacity(40); // 32 bytes for pubkey + 8 bytes for segment_number
+        key.extend_from_slice(&tape_address.to_bytes());
+        key.extend_from_slice(&segment_number.to_be_bytes());
+
+        if !sealed {
+            let cf_mutable = self
+                .db
+                .cf_handle("mutable_segments")
+                .ok_or(StoreError::MutableSegmentsCfNotFound)?;
+
+            batch.put_cf(cf_mutable, &key, data);
+            return Ok(());
+        }
+
         let cf_segments = self
             .db
             .cf_handle("segments")
             .ok_or(StoreError::SegmentsCfNotFound)?;
 
-        // Create key: [<tape_address><segment_number>]
-        let mut key = Vec::with_capacity(40); // 32 bytes for pubkey + 8 bytes for segment_number
-        key.extend_from_slice(&tape_address.to_bytes());
-        key.extend_from_slice(&segment_number.to_be_bytes());
+        let cf_access = self
+            .db
+            .cf_handle("access")
+            .ok_or(StoreError::AccessCfNotFound)?;
 
-        let mut batch = WriteBatch::default();
+        let cf_checksums = self
+            .db
+            .cf_handle("checksums")
+            .ok_or(StoreError::ChecksumsCfNotFound)?;
+
+        batch.put_cf(cf_checksums, &key, segment_checksum(&data).to_be_bytes());
         batch.put_cf(cf_segments, &key, data);
-        self.db.write(batch)?;
+        // A freshly written tape counts as recently accessed
+        batch.put_cf(cf_access, tape_address.to_bytes(), now().to_be_bytes());
 
         Ok(())
     }
@@ -217,106 +945,1632 @@ impl TapeStore {
             .map_err(|e| StoreError::InvalidPubkey(e.to_string()))
     }
 
-    pub fn get_tape_segments(
-        &self,
-        tape_address: &Pubkey,
-    ) -> Result<Vec<(u64, Vec<u8>)>, StoreError> {
-        let cf_segments = self
+    /// The most recently numbered tapes in the store, newest first.
+    pub fn get_recent_tapes(&self, limit: usize) -> Result<Vec<(u64, Pubkey)>, StoreError> {
+        let cf_tapes = self
             .db
-            .cf_handle("segments")
-            .ok_or(StoreError::SegmentsCfNotFound)?;
-
-        let mut segments = Vec::new();
-        let prefix = tape_address.to_bytes().to_vec();
+            .cf_handle("tapes")
+            .ok_or(StoreError::TapesCfNotFound)?;
 
-        let iter = self.db.prefix_iterator_cf(cf_segments, &prefix);
+        // Tape number keys are 8 big-endian bytes, so they all sort below [1] alongside the few
+        // 32 byte address keys that start with a zero byte.
+        let mut tapes = Vec::with_capacity(limit);
+        let iter = self.db.iterator_cf(cf_tapes, IteratorMode::From(&[1u8], Direction::Reverse));
         for item in iter {
-            let (key, value) = item?;
-            if key.len() != 40 {
-                // 32 bytes for pubkey + 8 bytes for segment_number
-                continue;
+            if tapes.len() == limit {
+                break;
             }
-            if !key.starts_with(&prefix) {
+
+            let (key, value) = item?;
+            if key.len() != 8 {
                 continue;
             }
 
-            let segment_number = u64::from_be_bytes(
-                key[32..40]
+            let tape_number = u64::from_be_bytes(
+                key[..]
                     .try_into()
                     .map_err(|_| StoreError::InvalidSegmentKey)?,
             );
+            let address = Pubkey::try_from(&value[..])
+                .map_err(|e| StoreError::InvalidPubkey(e.to_string()))?;
 
-            segments.push((segment_number, value.to_vec()));
+            tapes.push((tape_number, address));
         }
 
-        // Sort by segment_number
-        segments.sort_by(|a, b| a.0.cmp(&b.0));
-        Ok(segments)
+        Ok(tapes)
     }
 
-    pub fn get_segment(
+    /// Every numbered tape in the store, lowest number first. Tapes are read as the iterator
+    /// advances, so callers can stop early without loading the whole index.
+    pub fn iter_tapes(
+        &self,
+    ) -> Result<impl Iterator<Item = Result<(u64, Pubkey), StoreError>> + '_, StoreError> {
+        let cf_tapes = self
+            .db
+            .cf_handle("tapes")
+            .ok_or(StoreError::TapesCfNotFound)?;
+
+        // Tape number keys are 8 big-endian bytes, so they all start with a zero byte. The few
+        // 32 byte address keys that do too are skipped.
+        Ok(self
+            .db
+            .iterator_cf(cf_tapes, IteratorMode::Start)
+            .take_while(|item| !matches!(item, Ok((key, _)) if key.first() != Some(&0)))
+            .filter_map(|item| match item {
+                Ok((key, value)) if key.len() == 8 => Some(decode_tape_entry(&key, &value)),
+                Ok(_) => None,
+                Err(e) => Some(Err(e.into())),
+            }))
+    }
+
+    /// The segments of a tape in segment order, read as the iterator advances. The data is
+    /// handed over as RocksDB returns it, without another copy. A segment that doesn't match
+    /// its checksum is an error.
+    pub fn iter_segments(
         &self,
         tape_address: &Pubkey,
-        segment_number: u64,
-    ) -> Result<Vec<u8>, StoreError> {
+    ) -> Result<impl Iterator<Item = Result<(u64, Box<[u8]>), StoreError>> + '_, StoreError> {
         let cf_segments = self
             .db
             .cf_handle("segments")
             .ok_or(StoreError::SegmentsCfNotFound)?;
 
-        let mut key = Vec::with_capacity(40); // 32 bytes for pubkey + 8 bytes for segment_number
-        key.extend_from_slice(&tape_address.to_bytes());
-        key.extend_from_slice(&segment_number.to_be_bytes());
-
-        let segment_data = self
+        let cf_checksums = self
             .db
-            .get_cf(cf_segments, &key)?
-            .ok_or_else(|| StoreError::SegmentNotFound(tape_address.to_string(), segment_number))?;
+            .cf_handle("checksums")
+            .ok_or(StoreError::ChecksumsCfNotFound)?;
 
-        Ok(segment_data.to_vec())
+        // Keys are the tape address followed by the big-endian segment number, so a tape's
+        // segments are contiguous and already in order
+        let prefix = tape_address.to_bytes();
+        Ok(self
+            .db
+            .iterator_cf(cf_segments, IteratorMode::From(&prefix, Direction::Forward))
+            .take_while(move |item| !matches!(item, Ok((key, _)) if !key.starts_with(&prefix)))
+            .filter_map(move |item| match item {
+                Ok((key, value)) if key.len() == 40 => Some(
+                    self.check_segment(cf_checksums, &key, &value)
+                        .and_then(|()| decode_segment_key(&key))
+                        .map(|(_, segment_number)| (segment_number, value)),
+                ),
+                Ok(_) => None,
+                Err(e) => Some(Err(e.into())),
+            }))
     }
-}
 
-impl Drop for TapeStore {
-    fn drop(&mut self) {
+    /// Every segment in the store as `(tape address, segment number, data)`, grouped by tape
+    /// and in segment order within each. A segment that doesn't match its checksum is an error.
+    pub fn iter_all_segments(
+        &self,
+    ) -> Result<impl Iterator<Item = Result<(Pubkey, u64, Box<[u8]>), StoreError>> + '_, StoreError> {
+        let cf_segments = self
+            .db
+            .cf_handle("segments")
+            .ok_or(StoreError::SegmentsCfNotFound)?;
+
+        let cf_checksums = self
+            .db
+            .cf_handle("checksums")
+            .ok_or(StoreError::ChecksumsCfNotFound)?;
+
+        Ok(self
+            .db
+            .iterator_cf(cf_segments, IteratorMode::Start)
+            .filter_map(move |item| match item {
+                Ok((key, value)) if key.len() == 40 => Some(
+                    self.check_segment(cf_checksums, &key, &value)
+                        .and_then(|()| decode_segment_key(&key))
+                        .map(|(address, segment_number)| (address, segment_number, value)),
+                ),
+                Ok(_) => None,
+                Err(e) => Some(Err(e.into())),
+            }))
+    }
+
+    /// Visit the segments of a tape in segment order without copying them out of RocksDB. Each
+    /// `data` slice borrows the iterator's buffer and is only valid for the call. Stops at the
+    /// first error `f` returns, or the first segment that doesn't match its checksum. Returns
+    /// the number of segments visited.
+    pub fn for_each_segment<E, F>(&self, tape_address: &Pubkey, mut f: F) -> Result<usize, E>
+    where
+        E: From<StoreError>,
+        F: FnMut(u64, &[u8]) -> Result<(), E>,
+    {
+        let cf_segments = self
+            .db
+            .cf_handle("segments")
+            .ok_or(StoreError::SegmentsCfNotFound)?;
+
+        let cf_checksums = self
+            .db
+            .cf_handle("checksums")
+            .ok_or(StoreError::ChecksumsCfNotFound)?;
+
+        let prefix = tape_address.to_bytes();
+        let mut iter = self.db.raw_iterator_cf(cf_segments);
+        iter.seek(prefix);
+
+        let mut visited = 0;
+        while let (Some(key), Some(data)) = (iter.key(), iter.value()) {
+            if !key.starts_with(&prefix) {
+                break;
+            }
+
+            if key.len() == 40 {
+                self.check_segment(cf_checksums, key, data)?;
+                let (_, segment_number) = decode_segment_key(key)?;
+                f(segment_number, data)?;
+                visited += 1;
+            }
+
+            iter.next();
+        }
+        iter.status().map_err(StoreError::from)?;
+
+        Ok(visited)
+    }
+
+    pub fn get_tape_segments(
+        &self,
+        tape_address: &Pubkey,
+    ) -> Result<Vec<(u64, Vec<u8>)>, StoreError> {
+        self.iter_segments(tape_address)?
+            .map(|item| item.map(|(segment_number, data)| (segment_number, data.into_vec())))
+            .collect()
+    }
+
+    /// Record that the block at `slot` holds a write to a tape. Kept when the tape is evicted,
+    /// so the tape can be archived again from just those blocks.
+    pub fn add_tape_slot(&self, tape_address: &Pubkey, slot: u64) -> Result<(), StoreError> {
+        let cf_written = self
+            .db
+            .cf_handle("tape_slots")
+            .ok_or(StoreError::TapeSlotsCfNotFound)?;
+
+        // Key: [<tape_address><slot>], a tape's slots are contiguous and oldest first
+        let mut key = Vec::with_capacity(40);
+        key.extend_from_slice(&tape_address.to_bytes());
+        key.extend_from_slice(&slot.to_be_bytes());
+        self.db.put_cf(cf_written, key, [])?;
+
+        Ok(())
+    }
+
+    /// Every slot the archive found a write to a tape in, oldest first. Only blocks archived
+    /// since the index was added are known.
+    pub fn get_tape_slots(&self, tape_address: &Pubkey) -> Result<Vec<u64>, StoreError> {
+        let cf_written = self
+            .db
+            .cf_handle("tape_slots")
+            .ok_or(StoreError::TapeSlotsCfNotFound)?;
+
+        let prefix = tape_address.to_bytes();
+        let mut slots = Vec::new();
+        for item in self.db.iterator_cf(cf_written, IteratorMode::From(&prefix, Direction::Forward)) {
+            let (key, _) = item?;
+            if !key.starts_with(&prefix) {
+                break;
+            }
+            if key.len() == 40 {
+                slots.push(decode_slot(&key[32..40])?);
+            }
+        }
+
+        Ok(slots)
+    }
+
+    /// All segments of a tape with the slot each was last written at, whether or not the tape
+    /// has been finalized. The one reader that sees mutable segments, for applications using
+    /// tapes as mutable state.
+    pub fn get_tape_segments_with_slots(
+        &self,
+        tape_address: &Pubkey,
+    ) -> Result<Vec<StoredSegment>, StoreError> {
+        let cf_slots = self
+            .db
+            .cf_handle("segment_slots")
+            .ok_or(StoreError::SegmentSlotsCfNotFound)?;
+
+        let cf_mutable = self
+            .db
+            .cf_handle("mutable_segments")
+            .ok_or(StoreError::MutableSegmentsCfNotFound)?;
+
+        let mut slots = HashMap::new();
+        let prefix = tape_address.to_bytes().to_vec();
+        for item in self.db.prefix_iterator_cf(cf_slots, &prefix) {
+            let (key, value) = item?;
+            if key.len() != 40 || !key.starts_with(&prefix) {
+                continue;
+            }
+
+            let segment_number = u64::from_be_bytes(
+                key[32..40]
+                    .try_into()
+                    .map_err(|_| StoreError::InvalidSegmentKey)?,
+            );
+            slots.insert(segment_number, decode_slot(&value)?);
+        }
+
+        // A tape's segments are either all sealed or all still mutable
+        let mut segments = self.get_tape_segments(tape_address)?;
+        for item in self.db.iterator_cf(cf_mutable, IteratorMode::From(&prefix, Direction::Forward)) {
+            let (key, data) = item?;
+            if !key.starts_with(&prefix) {
+                break;
+            }
+            if key.len() == 40 {
+                let (_, segment_number) = decode_segment_key(&key)?;
+                segments.push((segment_number, data.into_vec()));
+            }
+        }
+        segments.sort_by_key(|(segment_number, _)| *segment_number);
+
+        Ok(segments
+            .into_iter()
+            .map(|(segment_number, data)| StoredSegment {
+                segment_number,
+                data,
+                slot: slots.get(&segment_number).copied(),
+            })
+            .collect())
+    }
+
+    pub fn get_segment(
+        &self,
+        tape_address: &Pubkey,
+        segment_number: u64,
+    ) -> Result<Vec<u8>, StoreError> {
+        let cf_segments = self
+            .db
+            .cf_handle("segments")
+            .ok_or(StoreError::SegmentsCfNotFound)?;
+
+        let cf_checksums = self
+            .db
+            .cf_handle("checksums")
+            .ok_or(StoreError::ChecksumsCfNotFound)?;
+
+        let mut key = Vec::with_capacity(40); // 32 bytes for pubkey + 8 bytes for segment_number
+        key.extend_from_slice(&tape_address.to_bytes());
+        key.extend_from_slice(&segment_number.to_be_bytes());
+
+        let segment_data = self
+            .db
+            .get_cf(cf_segments, &key)?
+            .ok_or_else(|| StoreError::SegmentNotFound(tape_address.to_string(), segment_number))?;
+        self.check_segment(cf_checksums, &key, &segment_data)?;
+
+        Ok(segment_data.to_vec())
+    }
+
+    /// Check a segment's data against the checksum stored with it. Segments stored before
+    /// checksums were added have none and always pass.
+    fn check_segment(&self, cf_checksums: &ColumnFamily, key: &[u8], data: &[u8]) -> Result<(), StoreError> {
+        match self.db.get_pinned_cf(cf_checksums, key)? {
+            Some(stored) if *stored != segment_checksum(data).to_be_bytes() => {
+                let (address, segment_number) = decode_segment_key(key)?;
+                Err(StoreError::ChecksumMismatch(address.to_string(), segment_number))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Check every segment in the store against its checksum, without stopping at the ones that
+    /// don't match. Reads every segment, so it takes a while on a large store.
+    pub fn scrub(&self) -> Result<ScrubReport, StoreError> {
+        let cf_segments = self
+            .db
+            .cf_handle("segments")
+            .ok_or(StoreError::SegmentsCfNotFound)?;
+
+        let cf_checksums = self
+            .db
+            .cf_handle("checksums")
+            .ok_or(StoreError::ChecksumsCfNotFound)?;
+
+        let mut report = ScrubReport::default();
+        let mut iter = self.db.raw_iterator_cf(cf_segments);
+        iter.seek_to_first();
+
+        while let (Some(key), Some(data)) = (iter.key(), iter.value()) {
+            if key.len() == 40 {
+                match self.db.get_pinned_cf(cf_checksums, key)? {
+                    None => report.unchecked += 1,
+                    Some(stored) if *stored == segment_checksum(data).to_be_bytes() => report.checked += 1,
+                    Some(_) => report.corrupt.push(decode_segment_key(key)?),
+                }
+            }
+
+            iter.next();
+        }
+        iter.status()?;
+
+        Ok(report)
+    }
+
+    /// Drop a segment along with its checksum and slot, e.g. one that rotted on disk, so it can
+    /// be stored again from any write.
+    pub fn drop_segment(&self, tape_address: &Pubkey, segment_number: u64) -> Result<(), StoreError> {
+        let cf_segments = self
+            .db
+            .cf_handle("segments")
+            .ok_or(StoreError::SegmentsCfNotFound)?;
+
+        let cf_mutable = self
+            .db
+            .cf_handle("mutable_segments")
+            .ok_or(StoreError::MutableSegmentsCfNotFound)?;
+
+        let cf_checksums = self
+            .db
+            .cf_handle("checksums")
+            .ok_or(StoreError::ChecksumsCfNotFound)?;
+
+        let cf_slots = self
+            .db
+            .cf_handle("segment_slots")
+            .ok_or(StoreError::SegmentSlotsCfNotFound)?;
+
+        let mut key = Vec::with_capacity(40);
+        key.extend_from_slice(&tape_address.to_bytes());
+        key.extend_from_slice(&segment_number.to_be_bytes());
+
+        let mut batch = WriteBatch::default();
+        batch.delete_cf(cf_segments, &key);
+        batch.delete_cf(cf_mutable, &key);
+        batch.delete_cf(cf_checksums, &key);
+        batch.delete_cf(cf_slots, &key);
+        self.db.write(batch)?;
+
+        Ok(())
+    }
+
+    /// Store a generated thumbnail for the given tape.
+    pub fn put_thumbnail(&self, tape_address: &Pubkey, data: &[u8]) -> Result<(), StoreError> {
+        let cf_thumbs = self
+            .db
+            .cf_handle("thumbnails")
+            .ok_or(StoreError::ThumbnailsCfNotFound)?;
+
+        self.db.put_cf(cf_thumbs, tape_address.to_bytes(), data)?;
+
+        Ok(())
+    }
+
+    pub fn get_thumbnail(&self, tape_address: &Pubkey) -> Result<Vec<u8>, StoreError> {
+        let cf_thumbs = self
+            .db
+            .cf_handle("thumbnails")
+            .ok_or(StoreError::ThumbnailsCfNotFound)?;
+
+        let data = self
+            .db
+            .get_cf(cf_thumbs, tape_address.to_bytes())?
+            .ok_or_else(|| StoreError::ThumbnailNotFound(tape_address.to_string()))?;
+
+        Ok(data)
+    }
+
+    /// Record the last tape number the thumbnail task has processed.
+    pub fn update_thumbnail_cursor(&self, tape_number: u64) -> Result<(), StoreError> {
+        let cf_meta = self
+            .db
+            .cf_handle("meta")
+            .ok_or(StoreError::MetaCfNotFound)?;
+
+        self.db.put_cf(cf_meta, b"thumbnail_cursor", tape_number.to_be_bytes())?;
+
+        Ok(())
+    }
+
+    /// Load the last tape number the thumbnail task has processed (0 if none). Stores that kept
+    /// it among the thumbnails, before it moved to the meta column family, pick up from there.
+    pub fn get_thumbnail_cursor(&self) -> Result<u64, StoreError> {
+        let cf_meta = self
+            .db
+            .cf_handle("meta")
+            .ok_or(StoreError::MetaCfNotFound)?;
+
+        let bytes = match self.db.get_cf(cf_meta, b"thumbnail_cursor")? {
+            Some(bytes) => Some(bytes),
+            None => {
+                let cf_thumbs = self
+                    .db
+                    .cf_handle("thumbnails")
+                    .ok_or(StoreError::ThumbnailsCfNotFound)?;
+
+                self.db.get_cf(cf_thumbs, b"last_tape_number")?
+            }
+        };
+
+        let cursor = match bytes {
+            Some(bytes) => u64::from_be_bytes(
+                bytes[..]
+                    .try_into()
+                    .map_err(|_| StoreError::InvalidThumbnailCursor)?,
+            ),
+            None => 0,
+        };
+
+        Ok(cursor)
+    }
+
+    /// Record that a tape was just accessed. Only primary stores can record accesses, secondary
+    /// instances are read-only and queue theirs for the archive, see
+    /// [`SecondaryStoreHandle::touch_tape`](super::shared::SecondaryStoreHandle::touch_tape).
+    pub fn touch_tape(&self, tape_address: &Pubkey) -> Result<(), StoreError> {
+        self.touch_tape_at(tape_address, now())
+    }
+
+    /// Record that a tape was accessed at unix time `at`. An access older than the one recorded
+    /// is ignored, accesses queued by other processes can arrive out of order.
+    pub fn touch_tape_at(&self, tape_address: &Pubkey, at: u64) -> Result<(), StoreError> {
+        let cf_access = self
+            .db
+            .cf_handle("access")
+            .ok_or(StoreError::AccessCfNotFound)?;
+
+        if self.get_last_access(tape_address)?.is_some_and(|last| last >= at) {
+            return Ok(());
+        }
+
+        self.db.put_cf(cf_access, tape_address.to_bytes(), at.to_be_bytes())?;
+
+        Ok(())
+    }
+
+    /// Load the unix timestamp a tape was last accessed at, if it was ever recorded.
+    pub fn get_last_access(&self, tape_address: &Pubkey) -> Result<Option<u64>, StoreError> {
+        let cf_access = self
+            .db
+            .cf_handle("access")
+            .ok_or(StoreError::AccessCfNotFound)?;
+
+        let timestamp = match self.db.get_cf(cf_access, tape_address.to_bytes())? {
+            Some(bytes) => Some(u64::from_be_bytes(
+                bytes[..]
+                    .try_into()
+                    .map_err(|_| StoreError::InvalidSegmentKey)?,
+            )),
+            None => None,
+        };
+
+        Ok(timestamp)
+    }
+
+    /// Total bytes of segment data held for each tape in the store.
+    pub fn get_tape_sizes(&self) -> Result<HashMap<Pubkey, u64>, StoreError> {
+        let mut sizes = HashMap::new();
+        for item in self.iter_all_segments()? {
+            let (address, _, data) = item?;
+            *sizes.entry(address).or_insert(0) += data.len() as u64;
+        }
+
+        Ok(sizes)
+    }
+
+    /// Drop all segments, sealed or mutable, the thumbnail and access record of a tape. The tape number mapping is
+    /// kept so the tape is still known to the store, it just no longer holds the data.
+    pub fn evict_tape(&self, tape_address: &Pubkey) -> Result<(), StoreError> {
+        let cf_segments = self
+            .db
+            .cf_handle("segments")
+            .ok_or(StoreError::SegmentsCfNotFound)?;
+
+        let cf_thumbs = self
+            .db
+            .cf_handle("thumbnails")
+            .ok_or(StoreError::ThumbnailsCfNotFound)?;
+
+        let cf_access = self
+            .db
+            .cf_handle("access")
+            .ok_or(StoreError::AccessCfNotFound)?;
+
+        let cf_slots = self
+            .db
+            .cf_handle("segment_slots")
+            .ok_or(StoreError::SegmentSlotsCfNotFound)?;
+
+        let cf_conflicts = self
+            .db
+            .cf_handle("conflicts")
+            .ok_or(StoreError::ConflictsCfNotFound)?;
+
+        let cf_checksums = self
+            .db
+            .cf_handle("checksums")
+            .ok_or(StoreError::ChecksumsCfNotFound)?;
+
+        let cf_mutable = self
+            .db
+            .cf_handle("mutable_segments")
+            .ok_or(StoreError::MutableSegmentsCfNotFound)?;
+
+        let mut start = Vec::with_capacity(40);
+        start.extend_from_slice(&tape_address.to_bytes());
+        start.extend_from_slice(&0u64.to_be_bytes());
+
+        let mut end = Vec::with_capacity(40);
+        end.extend_from_slice(&tape_address.to_bytes());
+        end.extend_from_slice(&u64::MAX.to_be_bytes());
+
+        let mut batch = WriteBatch::default();
+        // delete_range excludes the end key, so drop it separately
+        batch.delete_range_cf(cf_segments, &start, &end);
+        batch.delete_cf(cf_segments, &end);
+        batch.delete_range_cf(cf_mutable, &start, &end);
+        batch.delete_cf(cf_mutable, &end);
+        batch.delete_range_cf(cf_slots, &start, &end);
+        batch.delete_cf(cf_slots, &end);
+        batch.delete_range_cf(cf_checksums, &start, &end);
+        batch.delete_cf(cf_checksums, &end);
+        // Conflict keys carry a slot after the segment number
+        let mut conflicts_end = end.clone();
+        conflicts_end.extend_from_slice(&u64::MAX.to_be_bytes());
+        batch.delete_range_cf(cf_conflicts, &start, &conflicts_end);
+        batch.delete_cf(cf_conflicts, &conflicts_end);
+        batch.delete_cf(cf_thumbs, tape_address.to_bytes());
+        batch.delete_cf(cf_access, tape_address.to_bytes());
+        self.db.write(batch)?;
+
+        // Reclaim the disk space right away instead of waiting for a background compaction
+        self.db.compact_range_cf(cf_segments, Some(&start), Some(&end));
+
+        Ok(())
+    }
+
+    /// Add the mine events of a block to the per-miner totals. Blocks at or below the last
+    /// indexed slot are skipped, so re-processing a block after a restart doesn't count twice.
+    pub fn record_mines(&self, slot: u64, events: &[MineEvent]) -> Result<(), StoreError> {
+        let cf_miners = self
+            .db
+            .cf_handle("miners")
+            .ok_or(StoreError::MinersCfNotFound)?;
+
+        if self.get_leaderboard_cursor()?.is_some_and(|cursor| slot <= cursor) {
+            return Ok(());
+        }
+
+        // Events of the same miner can repeat within a block, so accumulate before writing
+        let mut updates: HashMap<Vec<u8>, (u64, u64)> = HashMap::new();
+        for event in events {
+            let bucket = event.slot / LEADERBOARD_BUCKET_SLOTS;
+
+            let mut total_key = vec![MINER_TOTAL];
+            total_key.extend_from_slice(&event.miner);
+
+            let mut bucket_key = vec![MINER_BUCKET];
+            bucket_key.extend_from_slice(&bucket.to_be_bytes());
+            bucket_key.extend_from_slice(&event.miner);
+
+            for key in [total_key, bucket_key] {
+                let (proofs, rewards) = match updates.get(&key) {
+                    Some(stats) => *stats,
+                    None => self.db.get_cf(cf_miners, &key)?.map(|v| decode_stats(&v)).unwrap_or((0, 0)),
+                };
+                updates.insert(key, (proofs + 1, rewards.saturating_add(event.reward)));
+            }
+        }
+
+        let mut batch = WriteBatch::default();
+        for (key, (proofs, rewards)) in updates {
+            batch.put_cf(cf_miners, key, encode_stats(proofs, rewards));
+        }
+        batch.put_cf(cf_miners, [MINER_CURSOR], slot.to_be_bytes());
+        self.db.write(batch)?;
+
+        Ok(())
+    }
+
+    /// The last slot whose mine events were indexed, if any.
+    pub fn get_leaderboard_cursor(&self) -> Result<Option<u64>, StoreError> {
+        let cf_miners = self
+            .db
+            .cf_handle("miners")
+            .ok_or(StoreError::MinersCfNotFound)?;
+
+        let cursor = match self.db.get_cf(cf_miners, [MINER_CURSOR])? {
+            Some(bytes) => Some(u64::from_be_bytes(
+                bytes[..]
+                    .try_into()
+                    .map_err(|_| StoreError::InvalidSegmentKey)?,
+            )),
+            None => None,
+        };
+
+        Ok(cursor)
+    }
+
+    /// The top `limit` miners, either all-time or over the buckets covering the last
+    /// `window_slots` indexed slots.
+    pub fn get_leaderboard(
+        &self,
+        sort: LeaderboardSort,
+        window_slots: Option<u64>,
+        limit: usize,
+    ) -> Result<Vec<MinerStats>, StoreError> {
+        let cf_miners = self
+            .db
+            .cf_handle("miners")
+            .ok_or(StoreError::MinersCfNotFound)?;
+
+        let mut stats: HashMap<Pubkey, MinerStats> = HashMap::new();
+
+        let start = match window_slots {
+            None => vec![MINER_TOTAL],
+            Some(window) => {
+                let cursor = self.get_leaderboard_cursor()?.unwrap_or(0);
+                let first_bucket = cursor.saturating_sub(window) / LEADERBOARD_BUCKET_SLOTS;
+                let mut start = vec![MINER_BUCKET];
+                start.extend_from_slice(&first_bucket.to_be_bytes());
+                start
+            }
+        };
+
+        let iter = self.db.iterator_cf(cf_miners, IteratorMode::From(&start, Direction::Forward));
+        for item in iter {
+            let (key, value) = item?;
+            if key[0] != start[0] {
+                break;
+            }
+
+            let miner = Pubkey::try_from(&key[key.len() - 32..])
+                .map_err(|e| StoreError::InvalidPubkey(e.to_string()))?;
+            let (proofs, rewards) = decode_stats(&value);
+
+            let entry = stats.entry(miner).or_insert(MinerStats { miner, ..Default::default() });
+            entry.proofs += proofs;
+            entry.rewards = entry.rewards.saturating_add(rewards);
+        }
+
+        let mut leaderboard: Vec<MinerStats> = stats.into_values().collect();
+        leaderboard.sort_by(|a, b| {
+            let (a_key, b_key) = match sort {
+                LeaderboardSort::Proofs => ((a.proofs, a.rewards), (b.proofs, b.rewards)),
+                LeaderboardSort::Rewards => ((a.rewards, a.proofs), (b.rewards, b.proofs)),
+            };
+            b_key.cmp(&a_key).then(a.miner.cmp(&b.miner))
+        });
+        leaderboard.truncate(limit);
+
+        Ok(leaderboard)
+    }
+
+    /// Record a write that was dropped as a duplicate of an earlier one in the same slot.
+    pub fn add_conflict(&self, tape_address: &Pubkey, conflict: &SegmentConflict) -> Result<(), StoreError> {
+        let cf_conflicts = self
+            .db
+            .cf_handle("conflicts")
+            .ok_or(StoreError::ConflictsCfNotFound)?;
+
+        // Key: [<tape_address><segment_number><slot>], the same duplicate seen again replaces itself
+        let mut key = Vec::with_capacity(48);
+        key.extend_from_slice(&tape_address.to_bytes());
+        key.extend_from_slice(&conflict.segment_number.to_be_bytes());
+        key.extend_from_slice(&conflict.slot.to_be_bytes());
+
+        let mut value = Vec::with_capacity(1 + conflict.data.len());
+        value.push(conflict.identical as u8);
+        value.extend_from_slice(&conflict.data);
+        self.db.put_cf(cf_conflicts, key, value)?;
+
+        Ok(())
+    }
+
+    /// Every duplicate write dropped for a tape, by segment then slot.
+    pub fn get_conflicts(&self, tape_address: &Pubkey) -> Result<Vec<SegmentConflict>, StoreError> {
+        let cf_conflicts = self
+            .db
+            .cf_handle("conflicts")
+            .ok_or(StoreError::ConflictsCfNotFound)?;
+
+        let prefix = tape_address.to_bytes();
+        let mut conflicts = Vec::new();
+
+        for item in self.db.iterator_cf(cf_conflicts, IteratorMode::From(&prefix, Direction::Forward)) {
+            let (key, value) = item?;
+            if !key.starts_with(&prefix) {
+                break;
+            }
+
+            let segment_number = decode_slot(key.get(32..40).ok_or(StoreError::InvalidSegmentKey)?)?;
+            let slot = decode_slot(key.get(40..48).ok_or(StoreError::InvalidSegmentKey)?)?;
+            let (&identical, data) = value.split_first().ok_or(StoreError::InvalidSegmentKey)?;
+
+            conflicts.push(SegmentConflict {
+                segment_number,
+                slot,
+                identical: identical != 0,
+                data: data.to_vec(),
+            });
+        }
+
+        Ok(conflicts)
+    }
+
+    /// Label a tape, replacing any label it had. A label with neither text nor a note clears it.
+    /// Labels stay when a tape is evicted, so a tape that was flagged is still flagged if it's
+    /// archived again.
+    pub fn put_label(&self, tape_address: &Pubkey, label: &TapeLabel) -> Result<(), StoreError> {
+        let cf_labels = self
+            .db
+            .cf_handle("labels")
+            .ok_or(StoreError::LabelsCfNotFound)?;
+
+        if label.label.is_empty() && label.note.is_empty() {
+            self.db.delete_cf(cf_labels, tape_address.to_bytes())?;
+        } else {
+            self.db.put_cf(cf_labels, tape_address.to_bytes(), label.to_bytes())?;
+        }
+
+        Ok(())
+    }
+
+    pub fn get_label(&self, tape_address: &Pubkey) -> Result<Option<TapeLabel>, StoreError> {
+        let cf_labels = self
+            .db
+            .cf_handle("labels")
+            .ok_or(StoreError::LabelsCfNotFound)?;
+
+        self.db
+            .get_cf(cf_labels, tape_address.to_bytes())?
+            .map(|value| TapeLabel::from_bytes(&value))
+            .transpose()
+    }
+
+    /// Every labelled tape, by address.
+    pub fn get_labels(&self) -> Result<Vec<(Pubkey, TapeLabel)>, StoreError> {
+        let cf_labels = self
+            .db
+            .cf_handle("labels")
+            .ok_or(StoreError::LabelsCfNotFound)?;
+
+        let mut labels = Vec::new();
+        for item in self.db.iterator_cf(cf_labels, IteratorMode::Start) {
+            let (key, value) = item?;
+            let address = Pubkey::try_from(key.as_ref())
+                .map_err(|_| StoreError::InvalidPubkey(format!("{:?}", key)))?;
+            labels.push((address, TapeLabel::from_bytes(&value)?));
+        }
+
+        Ok(labels)
+    }
+
+    /// Stop the web node from serving a tape, e.g. after a legal takedown. Its segments stay in the
+    /// store, so mining with them goes on. Logged with `reason` in the policy log.
+    pub fn block_tape(&self, tape_address: &Pubkey, reason: &str) -> Result<(), StoreError> {
+        self.apply_policy(tape_address, PolicyAction::Block, reason)
+    }
+
+    /// Serve a blocked tape again. Logged with `reason` in the policy log.
+    pub fn unblock_tape(&self, tape_address: &Pubkey, reason: &str) -> Result<(), StoreError> {
+        self.apply_policy(tape_address, PolicyAction::Unblock, reason)
+    }
+
+    fn apply_policy(&self, tape_address: &Pubkey, action: PolicyAction, reason: &str) -> Result<(), StoreError> {
+        let cf_blocklist = self
+            .db
+            .cf_handle("blocklist")
+            .ok_or(StoreError::BlocklistCfNotFound)?;
+
+        let at = now();
+
+        // Key: [BLOCKED<tape_address>] -> [<blocked_at><reason>]
+        let mut key = Vec::with_capacity(33);
+        key.push(BLOCKED);
+        key.extend_from_slice(&tape_address.to_bytes());
+
+        let mut batch = WriteBatch::default();
+        match action {
+            PolicyAction::Block => {
+                let mut value = at.to_be_bytes().to_vec();
+                value.extend_from_slice(reason.as_bytes());
+                batch.put_cf(cf_blocklist, &key, value);
+            }
+            PolicyAction::Unblock => batch.delete_cf(cf_blocklist, &key),
+        }
+
+        // Key: [POLICY_LOG<at><tape_address>] -> [<action><reason>], oldest first
+        let mut log_key = Vec::with_capacity(41);
+        log_key.push(POLICY_LOG);
+        log_key.extend_from_slice(&at.to_be_bytes());
+        log_key.extend_from_slice(&tape_address.to_bytes());
+
+        let mut log_value = vec![action as u8];
+        log_value.extend_from_slice(reason.as_bytes());
+        batch.put_cf(cf_blocklist, log_key, log_value);
+
+        self.db.write(batch)?;
+        Ok(())
+    }
+
+    /// Why a tape is blocked, `None` if it isn't.
+    pub fn get_blocked(&self, tape_address: &Pubkey) -> Result<Option<BlockedTape>, StoreError> {
+        let cf_blocklist = self
+            .db
+            .cf_handle("blocklist")
+            .ok_or(StoreError::BlocklistCfNotFound)?;
+
+        let mut key = Vec::with_capacity(33);
+        key.push(BLOCKED);
+        key.extend_from_slice(&tape_address.to_bytes());
+
+        self.db
+            .get_cf(cf_blocklist, key)?
+            .map(|value| decode_blocked(&value))
+            .transpose()
+    }
+
+    pub fn is_blocked(&self, tape_address: &Pubkey) -> Result<bool, StoreError> {
+        Ok(self.get_blocked(tape_address)?.is_some())
+    }
+
+    /// Every blocked tape, by address.
+    pub fn get_blocklist(&self) -> Result<Vec<(Pubkey, BlockedTape)>, StoreError> {
+        let cf_blocklist = self
+            .db
+            .cf_handle("blocklist")
+            .ok_or(StoreError::BlocklistCfNotFound)?;
+
+        let mut blocked = Vec::new();
+        for item in self.db.prefix_iterator_cf(cf_blocklist, [BLOCKED]) {
+            let (key, value) = item?;
+            if key.first() != Some(&BLOCKED) {
+                break;
+            }
+
+            let address = Pubkey::try_from(&key[1..])
+                .map_err(|_| StoreError::InvalidPubkey(format!("{:?}", &key[1..])))?;
+            blocked.push((address, decode_blocked(&value)?));
+        }
+
+        Ok(blocked)
+    }
+
+    /// The last `limit` blocks and unblocks, newest first.
+    pub fn get_policy_log(&self, limit: usize) -> Result<Vec<PolicyLogEntry>, StoreError> {
+        let cf_blocklist = self
+            .db
+            .cf_handle("blocklist")
+            .ok_or(StoreError::BlocklistCfNotFound)?;
+
+        let mut entries = Vec::new();
+        // Walk back from the end of the log, which is the last prefix in the column family
+        for item in self.db.iterator_cf(cf_blocklist, IteratorMode::End) {
+            let (key, value) = item?;
+            if key.first() != Some(&POLICY_LOG) || entries.len() >= limit {
+                break;
+            }
+
+            let at = decode_slot(key.get(1..9).ok_or(StoreError::InvalidSegmentKey)?)?;
+            let tape_address = Pubkey::try_from(key.get(9..41).ok_or(StoreError::InvalidSegmentKey)?)
+                .map_err(|_| StoreError::InvalidSegmentKey)?;
+            let (&action, reason) = value.split_first().ok_or(StoreError::InvalidSegmentKey)?;
+
+            entries.push(PolicyLogEntry {
+                at,
+                tape_address,
+                action: if action == PolicyAction::Block as u8 { PolicyAction::Block } else { PolicyAction::Unblock },
+                reason: String::from_utf8_lossy(reason).into_owned(),
+            });
+        }
+
+        Ok(entries)
+    }
+
+    /// Record that the archive saw a tape at `slot`, unless it saw it earlier. Returns whether
+    /// this was the first sighting. Kept when the tape is evicted, like its number.
+    pub fn record_first_seen(&self, tape_address: &Pubkey, seen: FirstSeen) -> Result<bool, StoreError> {
+        let cf_seen = self
+            .db
+            .cf_handle("first_seen")
+            .ok_or(StoreError::FirstSeenCfNotFound)?;
+
+        let mut key = Vec::with_capacity(33);
+        key.push(SEEN_TAPE);
+        key.extend_from_slice(&tape_address.to_bytes());
+
+        let earlier = self.get_first_seen(tape_address)?;
+        if earlier.is_some_and(|earlier| earlier.slot <= seen.slot) {
+            return Ok(false);
+        }
+
+        let mut value = seen.slot.to_be_bytes().to_vec();
+        value.extend_from_slice(&seen.time.to_be_bytes());
+
+        let mut batch = WriteBatch::default();
+        // Key: [SEEN_TAPE<tape_address>] -> [<slot><time>]
+        batch.put_cf(cf_seen, &key, value);
+        // Key: [SEEN_SLOT<slot><tape_address>] -> [<time>], oldest first
+        if let Some(earlier) = earlier {
+            batch.delete_cf(cf_seen, seen_slot_key(earlier.slot, tape_address));
+        }
+        batch.put_cf(cf_seen, seen_slot_key(seen.slot, tape_address), seen.time.to_be_bytes());
+        self.db.write(batch)?;
+
+        Ok(earlier.is_none())
+    }
+
+    pub fn get_first_seen(&self, tape_address: &Pubkey) -> Result<Option<FirstSeen>, StoreError> {
+        let cf_seen = self
+            .db
+            .cf_handle("first_seen")
+            .ok_or(StoreError::FirstSeenCfNotFound)?;
+
+        let mut key = Vec::with_capacity(33);
+        key.push(SEEN_TAPE);
+        key.extend_from_slice(&tape_address.to_bytes());
+
+        self.db
+            .get_cf(cf_seen, key)?
+            .map(|value| {
+                Ok(FirstSeen {
+                    slot: decode_slot(value.get(..8).ok_or(StoreError::InvalidSegmentKey)?)?,
+                    time: decode_slot(value.get(8..16).ok_or(StoreError::InvalidSegmentKey)?)? as i64,
+                })
+            })
+            .transpose()
+    }
+
+    /// The `limit` tapes the archive saw most recently, newest first. Unlike
+    /// [`get_recent_tapes`](Self::get_recent_tapes) this includes tapes still being written,
+    /// which have no number yet.
+    pub fn get_recently_seen(&self, limit: usize) -> Result<Vec<(Pubkey, FirstSeen)>, StoreError> {
+        let cf_seen = self
+            .db
+            .cf_handle("first_seen")
+            .ok_or(StoreError::FirstSeenCfNotFound)?;
+
+        let mut tapes = Vec::new();
+        // Walk back from the end of the slot index, which is the last prefix in the column family
+        for item in self.db.iterator_cf(cf_seen, IteratorMode::End) {
+            let (key, value) = item?;
+            if key.first() != Some(&SEEN_SLOT) || tapes.len() >= limit {
+                break;
+            }
+
+            let slot = decode_slot(key.get(1..9).ok_or(StoreError::InvalidSegmentKey)?)?;
+            let tape_address = Pubkey::try_from(key.get(9..41).ok_or(StoreError::InvalidSegmentKey)?)
+                .map_err(|_| StoreError::InvalidSegmentKey)?;
+            let time = decode_slot(&value)? as i64;
+
+            tapes.push((tape_address, FirstSeen { slot, time }));
+        }
+
+        Ok(tapes)
+    }
+
+    /// Record a failure to process `slot`, counting up the attempts if it's already a gap.
+    pub fn add_gap(&self, slot: u64, error: &str) -> Result<(), StoreError> {
+        let cf_gaps = self
+            .db
+            .cf_handle("gaps")
+            .ok_or(StoreError::GapsCfNotFound)?;
+
+        let attempts = match self.db.get_cf(cf_gaps, slot.to_be_bytes())? {
+            Some(value) => decode_gap(slot, &value)?.attempts + 1,
+            None => 1,
+        };
+
+        let mut value = attempts.to_be_bytes().to_vec();
+        value.extend_from_slice(error.as_bytes());
+        self.db.put_cf(cf_gaps, slot.to_be_bytes(), value)?;
+
+        Ok(())
+    }
+
+    /// Forget a gap once its slot has been processed.
+    pub fn remove_gap(&self, slot: u64) -> Result<(), StoreError> {
+        let cf_gaps = self
+            .db
+            .cf_handle("gaps")
+            .ok_or(StoreError::GapsCfNotFound)?;
+
+        self.db.delete_cf(cf_gaps, slot.to_be_bytes())?;
+
+        Ok(())
+    }
+
+    /// The oldest gaps, at most `limit`.
+    pub fn get_gaps(&self, limit: usize) -> Result<Vec<SlotGap>, StoreError> {
+        let cf_gaps = self
+            .db
+            .cf_handle("gaps")
+            .ok_or(StoreError::GapsCfNotFound)?;
+
+        let mut gaps = Vec::new();
+        for item in self.db.iterator_cf(cf_gaps, IteratorMode::Start) {
+            if gaps.len() == limit {
+                break;
+            }
+
+            let (key, value) = item?;
+            let slot = decode_slot(&key)?;
+            gaps.push(decode_gap(slot, &value)?);
+        }
+
+        Ok(gaps)
+    }
+
+    /// Save a snapshot of an epoch, replacing any earlier one of the same epoch number.
+    pub fn put_epoch(&self, record: &EpochRecord) -> Result<(), StoreError> {
+        let cf_epochs = self
+            .db
+            .cf_handle("epochs")
+            .ok_or(StoreError::EpochsCfNotFound)?;
+
+        self.db.put_cf(cf_epochs, record.number.to_be_bytes(), record.to_bytes())?;
+
+        Ok(())
+    }
+
+    /// The newest epoch snapshot in the store, if any.
+    pub fn get_latest_epoch(&self) -> Result<Option<EpochRecord>, StoreError> {
+        let cf_epochs = self
+            .db
+            .cf_handle("epochs")
+            .ok_or(StoreError::EpochsCfNotFound)?;
+
+        match self.db.iterator_cf(cf_epochs, IteratorMode::End).next() {
+            Some(item) => Ok(Some(EpochRecord::from_bytes(&item?.1)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Epoch snapshots from epoch `from` to `to` (inclusive), oldest first, at most `limit`.
+    pub fn get_epoch_history(
+        &self,
+        from: u64,
+        to: u64,
+        limit: usize,
+    ) -> Result<Vec<EpochRecord>, StoreError> {
+        let cf_epochs = self
+            .db
+            .cf_handle("epochs")
+            .ok_or(StoreError::EpochsCfNotFound)?;
+
+        let start = from.to_be_bytes();
+        let mut records = Vec::new();
+        for item in self.db.iterator_cf(cf_epochs, IteratorMode::From(&start, Direction::Forward)) {
+            if records.len() == limit {
+                break;
+            }
+
+            let record = EpochRecord::from_bytes(&item?.1)?;
+            if record.number > to {
+                break;
+            }
+            records.push(record);
+        }
+
+        Ok(records)
+    }
+}
+
+#[async_trait]
+impl SegmentSource for TapeStore {
+    async fn read_segments(&self, tape_address: &Pubkey) -> anyhow::Result<Vec<(u64, Vec<u8>)>> {
+        Ok(self.get_tape_segments(tape_address)?)
+    }
+
+    async fn read_segment_range(&self, tape_address: &Pubkey, range: Range<u64>) -> anyhow::Result<Vec<(u64, Vec<u8>)>> {
+        let mut segments = Vec::new();
+        for segment_number in range {
+            match self.get_segment(tape_address, segment_number) {
+                Ok(data) => segments.push((segment_number, data)),
+                Err(StoreError::SegmentNotFound(..)) => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Ok(segments)
+    }
+
+    fn name(&self) -> String {
+        "Local store".to_string()
+    }
+}
+
+fn encode_stats(proofs: u64, rewards: u64) -> [u8; 16] {
+    let mut value = [0u8; 16];
+    value[..8].copy_from_slice(&proofs.to_be_bytes());
+    value[8..].copy_from_slice(&rewards.to_be_bytes());
+    value
+}
+
+fn decode_tape_entry(key: &[u8], value: &[u8]) -> Result<(u64, Pubkey), StoreError> {
+    let tape_number = u64::from_be_bytes(key.try_into().map_err(|_| StoreError::InvalidSegmentKey)?);
+    let address = Pubkey::try_from(value).map_err(|e| StoreError::InvalidPubkey(e.to_string()))?;
+    Ok((tape_number, address))
+}
+
+/// Splits a `<tape address><segment number>` key.
+fn decode_segment_key(key: &[u8]) -> Result<(Pubkey, u64), StoreError> {
+    if key.len() != 40 {
+        return Err(StoreError::InvalidSegmentKey);
+    }
+
+    let address = Pubkey::try_from(&key[..32])
+        .map_err(|e| StoreError::InvalidPubkey(e.to_string()))?;
+    let segment_number = u64::from_be_bytes(
+        key[32..40]
+            .try_into()
+            .map_err(|_| StoreError::InvalidSegmentKey)?,
+    );
+
+    Ok((address, segment_number))
+}
+
+fn decode_slot(value: &[u8]) -> Result<u64, StoreError> {
+    Ok(u64::from_be_bytes(
+        value.try_into().map_err(|_| StoreError::InvalidSegmentKey)?,
+    ))
+}
+
+fn seen_slot_key(slot: u64, tape_address: &Pubkey) -> Vec<u8> {
+    let mut key = Vec::with_capacity(41);
+    key.push(SEEN_SLOT);
+    key.extend_from_slice(&slot.to_be_bytes());
+    key.extend_from_slice(&tape_address.to_bytes());
+    key
+}
+
+fn decode_gap(slot: u64, value: &[u8]) -> Result<SlotGap, StoreError> {
+    let attempts = value
+        .get(..4)
+        .and_then(|b| b.try_into().ok())
+        .map(u32::from_be_bytes)
+        .ok_or(StoreError::InvalidSegmentKey)?;
+
+    Ok(SlotGap {
+        slot,
+        attempts,
+        error: String::from_utf8_lossy(&value[4..]).into_owned(),
+    })
+}
+
+fn decode_blocked(value: &[u8]) -> Result<BlockedTape, StoreError> {
+    let blocked_at = decode_slot(value.get(..8).ok_or(StoreError::InvalidSegmentKey)?)?;
+    Ok(BlockedTape {
+        reason: String::from_utf8_lossy(&value[8..]).into_owned(),
+        blocked_at,
+    })
+}
+
+fn decode_stats(value: &[u8]) -> (u64, u64) {
+    let proofs = value.get(..8).and_then(|b| b.try_into().ok()).map(u64::from_be_bytes);
+    let rewards = value.get(8..16).and_then(|b| b.try_into().ok()).map(u64::from_be_bytes);
+    (proofs.unwrap_or(0), rewards.unwrap_or(0))
+}
+
+fn health_sample_key(time: u64) -> Vec<u8> {
+    let mut key = HEALTH_SAMPLE_PREFIX.to_vec();
+    key.extend_from_slice(&time.to_be_bytes());
+    key
+}
+
+/// CRC-32 of a segment's data, stored next to it to catch bit rot.
+fn segment_checksum(data: &[u8]) -> u32 {
+    crc32fast::hash(data)
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+impl Drop for TapeStore {
+    fn drop(&mut self) {
         // RocksDB handles cleanup automatically
     }
-}
+}
+
+/// Open the writable store for a namespace (the cluster genesis hash). Each namespace lives in its
+/// own directory, so nodes for several clusters can run side by side.
+pub fn primary(namespace: &str) -> Result<TapeStore, StoreError> {
+    primary_with_options(namespace, &StoreOptions::default())
+}
+
+/// Open the writable store for a namespace with IO options.
+pub fn primary_with_options(namespace: &str, options: &StoreOptions) -> Result<TapeStore, StoreError> {
+    let db_primary = primary_path(namespace)?;
+    if let Some(root) = db_primary.parent() {
+        adopt_legacy_store(root, namespace)?;
+    }
+    std::fs::create_dir_all(&db_primary).map_err(|e| StoreError::IoError(e))?;
+
+    let store = TapeStore::with_options(&db_primary, options).map_err(|e| match e {
+        StoreError::RocksDB(ref err) if is_lock_error(err) => locked(&db_primary),
+        other => other,
+    })?;
+    store.init_namespace(namespace)?;
+    Ok(store)
+}
+
+/// Where the writable store for a namespace lives.
+pub fn primary_path(namespace: &str) -> Result<PathBuf, StoreError> {
+    let current_dir = env::current_dir().map_err(|e| StoreError::IoError(e))?;
+    Ok(current_dir.join("db_tapestore").join(namespace))
+}
+
+/// Whether `root` holds a store from before namespacing, its files right in `root` rather than
+/// in a directory per namespace.
+fn is_legacy_store(root: &Path) -> bool {
+    root.join("CURRENT").is_file()
+}
+
+/// Move a store from before namespacing into the directory for `namespace`, where it's opened
+/// from now on. Files are moved into a staging directory first, `CURRENT` last, so an
+/// interrupted move is picked up again the next time the store opens.
+fn adopt_legacy_store(root: &Path, namespace: &str) -> Result<(), StoreError> {
+    let target = root.join(namespace);
+    let staging = root.join(format!(".{}.adopting", namespace));
+
+    if is_legacy_store(root) {
+        if is_legacy_store(&target) {
+            return Err(StoreError::LegacyConflict(
+                root.display().to_string(),
+                target.display().to_string(),
+            ));
+        }
+
+        // Opening it takes the lock, so an older node still running on it isn't moved under,
+        // and shows whether it was already bound to another cluster
+        {
+            let legacy = TapeStore::new(root).map_err(|e| match e {
+                StoreError::RocksDB(ref err) if is_lock_error(err) => locked(root),
+                other => other,
+            })?;
+            if let Some(found) = legacy.get_namespace()? {
+                if found != namespace {
+                    return Err(StoreError::NamespaceMismatch {
+                        expected: namespace.to_string(),
+                        found,
+                    });
+                }
+            }
+        }
+
+        std::fs::create_dir_all(&staging).map_err(StoreError::IoError)?;
+
+        // The store's own files, not the namespace directories next to them
+        for entry in std::fs::read_dir(root).map_err(StoreError::IoError)? {
+            let entry = entry.map_err(StoreError::IoError)?;
+            let is_file = entry.file_type().map_err(StoreError::IoError)?.is_file();
+            if is_file && entry.file_name() != "CURRENT" {
+                std::fs::rename(entry.path(), staging.join(entry.file_name()))
+                    .map_err(StoreError::IoError)?;
+            }
+        }
+
+        std::fs::rename(root.join("CURRENT"), staging.join("CURRENT"))
+            .map_err(StoreError::IoError)?;
+    }
+
+    if staging.is_dir() {
+        // An empty directory left by a failed open is replaced, one with files is refused
+        if target.is_dir() {
+            std::fs::remove_dir(&target).map_err(|_| {
+                StoreError::LegacyConflict(root.display().to_string(), target.display().to_string())
+            })?;
+        }
+        std::fs::rename(&staging, &target).map_err(StoreError::IoError)?;
+    }
+
+    Ok(())
+}
+
+/// Where read-only views of the store for a namespace keep their own files.
+pub fn secondary_path(namespace: &str) -> Result<PathBuf, StoreError> {
+    let current_dir = env::current_dir().map_err(|e| StoreError::IoError(e))?;
+    Ok(current_dir.join("db_tapestore_read").join(namespace))
+}
+
+/// Open the store for a namespace to read from. Opens the primary when nothing else has it, and
+/// falls back to a secondary when an archiver holds the primary's lock.
+pub fn read_only(namespace: &str) -> Result<TapeStore, StoreError> {
+    match primary(namespace) {
+        Err(StoreError::Locked { .. }) => secondary(namespace),
+        result => result,
+    }
+}
+
+/// Whether RocksDB failed to take the lock on a store another process has open.
+fn is_lock_error(err: &rocksdb::Error) -> bool {
+    let message = err.to_string();
+    message.contains("LOCK") && (message.contains("lock") || message.contains("unavailable"))
+}
+
+fn locked(path: &Path) -> StoreError {
+    let holder = lock_holder(&path.join("LOCK"))
+        .unwrap_or_else(|| "another process (probably `tapedrive archive`)".to_string());
+
+    StoreError::Locked { path: path.display().to_string(), holder }
+}
+
+/// The process with `lock` open, e.g. `pid 1234 (tapedrive archive)`. Only works where /proc
+/// lists open files, elsewhere the holder is unknown.
+fn lock_holder(lock: &Path) -> Option<String> {
+    let lock = std::fs::canonicalize(lock).ok()?;
+
+    for entry in std::fs::read_dir("/proc").ok()?.flatten() {
+        let Some(pid) = entry.file_name().to_str().and_then(|p| p.parse::<u32>().ok()) else {
+            continue;
+        };
+        if pid == std::process::id() {
+            continue;
+        }
+
+        let Ok(fds) = std::fs::read_dir(entry.path().join("fd")) else {
+            continue;
+        };
+        if fds.flatten().any(|fd| std::fs::read_link(fd.path()).ok().as_deref() == Some(lock.as_path())) {
+            let cmdline = std::fs::read(entry.path().join("cmdline")).unwrap_or_default();
+            let command = cmdline
+                .split(|&b| b == 0)
+                .filter(|arg| !arg.is_empty())
+                .map(|arg| String::from_utf8_lossy(arg).into_owned())
+                .collect::<Vec<_>>()
+                .join(" ");
+
+            return Some(format!("pid {} ({})", pid, command));
+        }
+    }
+
+    None
+}
+
+/// Open a read-only view of the store for a namespace.
+pub fn secondary(namespace: &str) -> Result<TapeStore, StoreError> {
+    let db_primary = primary_path(namespace)?;
+    let db_secondary = secondary_path(namespace)?;
+
+    // Only a primary moves a store from before namespacing
+    if let Some(root) = db_primary.parent() {
+        if is_legacy_store(root) {
+            return Err(StoreError::LegacyLayout(root.display().to_string()));
+        }
+    }
+
+    std::fs::create_dir_all(&db_secondary).map_err(|e| StoreError::IoError(e))?;
+    let store = TapeStore::new_secondary(&db_primary, &db_secondary)?;
+
+    // The primary writes the namespace when it first opens, secondaries can only check it
+    if let Some(found) = store.get_namespace()? {
+        if found != namespace {
+            return Err(StoreError::NamespaceMismatch {
+                expected: namespace.to_string(),
+                found,
+            });
+        }
+    }
+
+    Ok(store)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::pubkey::Pubkey;
+    use tempdir::TempDir;
+
+    fn setup_store() -> Result<(TapeStore, TempDir), StoreError> {
+        let temp_dir = TempDir::new("rocksdb_test").map_err(StoreError::IoError)?;
+        let store = TapeStore::new(temp_dir.path())?;
+        Ok((store, temp_dir))
+    }
+
+    #[test]
+    fn test_locked_store() -> Result<(), StoreError> {
+        let temp_dir = TempDir::new("rocksdb_test").map_err(StoreError::IoError)?;
+        let _store = TapeStore::new(temp_dir.path())?;
+
+        // RocksDB refuses a second primary on the same directory
+        match TapeStore::new(temp_dir.path()) {
+            Err(StoreError::RocksDB(err)) => assert!(is_lock_error(&err)),
+            Err(other) => panic!("unexpected error: {}", other),
+            Ok(_) => panic!("opened a locked store"),
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_adopt_legacy_store() -> Result<(), StoreError> {
+        let temp_dir = TempDir::new("rocksdb_test").map_err(StoreError::IoError)?;
+        let root = temp_dir.path();
+        let address = Pubkey::new_unique();
+
+        // A store from before namespacing, next to another cluster's store
+        {
+            let legacy = TapeStore::new(root)?;
+            legacy.finalize_tape(&address, 1)?;
+        }
+        std::fs::create_dir_all(root.join("testnet")).map_err(StoreError::IoError)?;
+        {
+            let _other = TapeStore::new(root.join("testnet"))?;
+        }
+
+        adopt_legacy_store(root, "devnet")?;
+        assert!(!is_legacy_store(root));
+        assert!(!root.join(".devnet.adopting").exists());
+        assert!(is_legacy_store(&root.join("testnet")));
+
+        let store = TapeStore::new(root.join("devnet"))?;
+        assert_eq!(store.get_tape_address(1)?, address);
+        drop(store);
+
+        // Once moved there's nothing left to adopt
+        adopt_legacy_store(root, "devnet")?;
+
+        // A legacy store bound to another cluster stays where it is
+        {
+            let legacy = TapeStore::new(root)?;
+            legacy.init_namespace("mainnet")?;
+        }
+        let result = adopt_legacy_store(root, "devnet");
+        assert!(matches!(result, Err(StoreError::NamespaceMismatch { .. })));
+        assert!(is_legacy_store(root));
+
+        // And one can't be adopted over a store the cluster already has
+        let result = adopt_legacy_store(root, "testnet");
+        assert!(matches!(result, Err(StoreError::LegacyConflict(..))));
+
+        Ok(())
+    }
 
-pub fn primary() -> Result<TapeStore, StoreError> {
-    let current_dir = env::current_dir().map_err(|e| StoreError::IoError(e))?;
-    let db_primary = current_dir.join("db_tapestore");
-    std::fs::create_dir_all(&db_primary).map_err(|e| StoreError::IoError(e))?;
-    TapeStore::new(&db_primary)
-}
+    #[test]
+    fn test_checkpoint() -> Result<(), StoreError> {
+        let (store, temp_dir) = setup_store()?;
+        let address = Pubkey::new_unique();
+        store.finalize_tape(&address, 1)?;
+        store.add_segment(&address, 0, vec![1, 2, 3])?;
 
-pub fn secondary() -> Result<TapeStore, StoreError> {
-    let current_dir = env::current_dir().map_err(|e| StoreError::IoError(e))?;
-    let db_primary = current_dir.join("db_tapestore");
-    let db_secondary = current_dir.join("db_tapestore_read");
-    std::fs::create_dir_all(&db_secondary).map_err(|e| StoreError::IoError(e))?;
-    TapeStore::new_secondary(&db_primary, &db_secondary)
-}
+        let path = temp_dir.path().join("checkpoint");
+        store.checkpoint(&path)?;
+        store.finalize_tape(&Pubkey::new_unique(), 2)?;
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use solana_sdk::pubkey::Pubkey;
-    use tempdir::TempDir;
+        // The copy has what the store held when it was taken, and nothing after
+        let copy = TapeStore::new(&path)?;
+        assert_eq!(copy.get_tape_address(1)?, address);
+        assert_eq!(copy.get_tape_segments(&address)?.len(), 1);
+        assert!(copy.get_tape_address(2).is_err());
 
-    fn setup_store() -> Result<(TapeStore, TempDir), StoreError> {
-        let temp_dir = TempDir::new("rocksdb_test").map_err(StoreError::IoError)?;
-        let store = TapeStore::new(temp_dir.path())?;
+        Ok(())
+    }
+
+    fn setup_secondary(primary: &TempDir) -> Result<(TapeStore, TempDir), StoreError> {
+        let temp_dir = TempDir::new("rocksdb_secondary").map_err(StoreError::IoError)?;
+        let store = TapeStore::new_secondary(primary.path(), temp_dir.path())?;
         Ok((store, temp_dir))
     }
 
+    #[test]
+    fn test_secondary_visibility() -> Result<(), StoreError> {
+        let (store, temp_dir) = setup_store()?;
+        let (secondary, _secondary_dir) = setup_secondary(&temp_dir)?;
+        let address = Pubkey::new_unique();
+
+        // The primary reads its own writes, the secondary only after catching up
+        store.add_segment(&address, 0, vec![1, 2, 3])?;
+        assert_eq!(store.get_tape_segments(&address)?.len(), 1);
+        assert!(secondary.get_tape_segments(&address)?.is_empty());
+
+        secondary.catch_up_with_primary()?;
+        assert_eq!(secondary.get_tape_segments(&address)?.len(), 1);
+        assert!(secondary.get_tape_address(1).is_err());
+
+        // Flushed writes are picked up the same as those still in the log
+        store.finalize_tape(&address, 1)?;
+        store.flush()?;
+        store.add_segment(&address, 1, vec![4, 5, 6])?;
+        store.sync()?;
+        assert!(secondary.get_tape_address(1).is_err());
+
+        secondary.catch_up_with_primary()?;
+        assert_eq!(secondary.get_tape_address(1)?, address);
+        assert_eq!(secondary.get_tape_number(&address)?, 1);
+        assert_eq!(secondary.get_tape_segments(&address)?.len(), 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_concurrent_writers_and_secondary() -> Result<(), StoreError> {
+        const TAPES: u64 = 4;
+        const SEGMENTS: u64 = 32;
+
+        let (store, temp_dir) = setup_store()?;
+        let (secondary, _secondary_dir) = setup_secondary(&temp_dir)?;
+        let addresses: Vec<Pubkey> = (0..TAPES).map(|_| Pubkey::new_unique()).collect();
+
+        std::thread::scope(|scope| -> Result<(), StoreError> {
+            let writers = addresses
+                .iter()
+                .enumerate()
+                .map(|(i, address)| {
+                    let store = &store;
+                    scope.spawn(move || -> Result<(), StoreError> {
+                        for segment in 0..SEGMENTS {
+                            store.add_segment_at(address, segment, vec![i as u8; 64], segment)?;
+                        }
+                        store.finalize_tape(address, i as u64 + 1)
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            // A tape the secondary finds by number is whole, and indexed both ways
+            loop {
+                let done = writers.iter().all(|writer| writer.is_finished());
+                secondary.catch_up_with_primary()?;
+                for number in 1..=TAPES {
+                    if let Ok(address) = secondary.get_tape_address(number) {
+                        assert_eq!(secondary.get_tape_number(&address)?, number);
+                        assert_eq!(secondary.get_tape_segments(&address)?.len(), SEGMENTS as usize);
+                    }
+                }
+                if done {
+                    break;
+                }
+            }
+
+            for writer in writers {
+                writer.join().unwrap()?;
+            }
+            Ok(())
+        })?;
+
+        secondary.catch_up_with_primary()?;
+        for (i, address) in addresses.iter().enumerate() {
+            assert_eq!(secondary.get_tape_address(i as u64 + 1)?, *address);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_flush_and_sync() -> Result<(), StoreError> {
+        let (store, temp_dir) = setup_store()?;
+        let address = Pubkey::new_unique();
+        store.add_segment(&address, 0, vec![1, 2, 3])?;
+        store.finalize_tape(&address, 1)?;
+        store.sync()?;
+        store.flush()?;
+        drop(store);
+
+        let store = TapeStore::new(temp_dir.path())?;
+        assert_eq!(store.get_tape_address(1)?, address);
+        assert_eq!(store.get_tape_segments(&address)?.len(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_manual_compaction() -> Result<(), StoreError> {
+        let temp_dir = TempDir::new("rocksdb_test").map_err(StoreError::IoError)?;
+        let options = StoreOptions { rate_limit: Some(1024 * 1024), manual_compaction: true };
+        let store = TapeStore::with_options(temp_dir.path(), &options)?;
+
+        let address = Pubkey::new_unique();
+        for segment in 0..64 {
+            store.add_segment(&address, segment, vec![segment as u8; SEGMENT_SIZE])?;
+        }
+        store.compact()?;
+
+        assert_eq!(store.get_tape_segments(&address)?.len(), 64);
+        Ok(())
+    }
+
     #[test]
     fn test_add_and_get_tape() -> Result<(), StoreError> {
         let (store, _temp_dir) = setup_store()?;
         let tape_number = 1;
         let address = Pubkey::new_unique();
 
-        store.add_tape(tape_number, &address)?;
+        store.finalize_tape(&address, tape_number)?;
         let retrieved_number = store.get_tape_number(&address)?;
         assert_eq!(retrieved_number, tape_number);
         let retrieved_address = store.get_tape_address(tape_number)?;
@@ -325,13 +2579,382 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_finalize_tape() -> Result<(), StoreError> {
+        let (store, _temp_dir) = setup_store()?;
+        let address = Pubkey::new_unique();
+
+        // Written but not finalized, only mutable
+        store.add_segment_at(&address, 0, vec![1, 2, 3], 10)?;
+        assert!(store.get_tape_number(&address).is_err());
+        assert!(store.get_tape_address(1).is_err());
+        assert_eq!(store.get_tape_segments_with_slots(&address)?.len(), 1);
+
+        store.finalize_tape(&address, 1)?;
+        assert_eq!(store.get_tape_number(&address)?, 1);
+        assert_eq!(store.get_tape_address(1)?, address);
+        assert_eq!(store.get_segment(&address, 0)?, vec![1, 2, 3]);
+
+        // Writes after finalizing go straight to the sealed segments
+        assert!(store.add_segment_at(&address, 1, vec![4], 20)?);
+        assert_eq!(store.get_segment(&address, 1)?, vec![4]);
+
+        // Finalizing again is a no-op, under another number the old one goes
+        store.finalize_tape(&address, 1)?;
+        assert_eq!(store.get_tape_address(1)?, address);
+        store.finalize_tape(&address, 2)?;
+        assert_eq!(store.get_tape_number(&address)?, 2);
+        assert_eq!(store.get_tape_address(2)?, address);
+        assert!(matches!(store.get_tape_address(1), Err(StoreError::TapeNotFound(1))));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_unfinalized_tape_hidden() -> Result<(), StoreError> {
+        let (store, temp_dir) = setup_store()?;
+        let (secondary, _secondary_dir) = setup_secondary(&temp_dir)?;
+        let address = Pubkey::new_unique();
+
+        store.add_mutable_segment(&address, 0, vec![1, 2, 3])?;
+        store.add_segment_at(&address, 1, vec![4, 5], 10)?;
+
+        // Not by number, nor by content
+        secondary.catch_up_with_primary()?;
+        for store in [&store, &secondary] {
+            assert!(matches!(store.get_tape_address(1), Err(StoreError::TapeNotFound(1))));
+            assert!(matches!(store.get_tape_number(&address), Err(StoreError::TapeNotFoundForAddress(_))));
+            assert!(store.get_recent_tapes(10)?.is_empty());
+            assert_eq!(store.iter_tapes()?.count(), 0);
+            assert!(matches!(store.get_segment(&address, 0), Err(StoreError::SegmentNotFound(_, 0))));
+            assert!(store.get_tape_segments(&address)?.is_empty());
+            assert_eq!(store.for_each_segment(&address, |_, _| Ok::<_, StoreError>(()))?, 0);
+            assert_eq!(store.iter_all_segments()?.count(), 0);
+            assert_eq!(store.scrub()?.checked, 0);
+        }
+
+        // Sealed and numbered at once
+        store.finalize_tape(&address, 1)?;
+        secondary.catch_up_with_primary()?;
+        for store in [&store, &secondary] {
+            assert_eq!(store.get_tape_address(1)?, address);
+            assert_eq!(store.get_tape_segments(&address)?, vec![(0, vec![1, 2, 3]), (1, vec![4, 5])]);
+            assert_eq!(store.scrub()?.checked, 2);
+        }
+
+        // Nothing is left behind in the mutable area
+        let segments = store.get_tape_segments_with_slots(&address)?;
+        assert_eq!(segments.iter().map(|s| s.segment_number).collect::<Vec<_>>(), vec![0, 1]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_recent_tapes() -> Result<(), StoreError> {
+        let (store, _temp_dir) = setup_store()?;
+        let addresses: Vec<Pubkey> = (0..5).map(|_| Pubkey::new_unique()).collect();
+        for (i, address) in addresses.iter().enumerate() {
+            store.finalize_tape(address, i as u64 + 1)?;
+        }
+
+        let recent = store.get_recent_tapes(3)?;
+        assert_eq!(recent, vec![(5, addresses[4]), (4, addresses[3]), (3, addresses[2])]);
+        assert_eq!(store.get_recent_tapes(10)?.len(), 5);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_first_seen() -> Result<(), StoreError> {
+        let (store, _temp_dir) = setup_store()?;
+        let (a, b, c) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+
+        assert!(store.record_first_seen(&a, FirstSeen { slot: 10, time: 100 })?);
+        assert!(store.record_first_seen(&b, FirstSeen { slot: 30, time: 300 })?);
+        assert!(store.record_first_seen(&c, FirstSeen { slot: 20, time: 200 })?);
+
+        // Later sightings don't move a tape
+        assert!(!store.record_first_seen(&b, FirstSeen { slot: 40, time: 400 })?);
+        assert_eq!(store.get_first_seen(&b)?, Some(FirstSeen { slot: 30, time: 300 }));
+
+        // An earlier block processed late does
+        assert!(!store.record_first_seen(&b, FirstSeen { slot: 5, time: 50 })?);
+
+        let recent = store.get_recently_seen(2)?;
+        assert_eq!(recent, vec![(c, FirstSeen { slot: 20, time: 200 }), (a, FirstSeen { slot: 10, time: 100 })]);
+        assert_eq!(store.get_recently_seen(10)?.len(), 3);
+        assert_eq!(store.get_first_seen(&Pubkey::new_unique())?, None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_tape_slots() -> Result<(), StoreError> {
+        let (store, _temp_dir) = setup_store()?;
+        let (a, b) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+        store.add_tape_slot(&a, 30)?;
+        store.add_tape_slot(&a, 10)?;
+        store.add_tape_slot(&b, 20)?;
+        // Archiving a block again changes nothing
+        store.add_tape_slot(&a, 10)?;
+
+        assert_eq!(store.get_tape_slots(&a)?, vec![10, 30]);
+        assert_eq!(store.get_tape_slots(&b)?, vec![20]);
+        assert!(store.get_tape_slots(&Pubkey::new_unique())?.is_empty());
+
+        // Eviction drops the data, not where to find it again
+        store.add_segment(&a, 0, vec![1; SEGMENT_SIZE])?;
+        store.evict_tape(&a)?;
+        assert_eq!(store.get_tape_slots(&a)?, vec![10, 30]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_leaderboard() -> Result<(), StoreError> {
+        let (store, _temp_dir) = setup_store()?;
+        let (a, b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mine = |miner: &Pubkey, slot: u64, reward: u64| MineEvent {
+            miner: miner.to_bytes(),
+            tape: 1,
+            difficulty: 10,
+            reward,
+            slot,
+        };
+
+        // An old bucket where b mined more, then a recent one where only a mined
+        store.record_mines(10, &[mine(&a, 10, 5), mine(&b, 10, 50), mine(&b, 10, 50)])?;
+        store.record_mines(100_000, &[mine(&a, 100_000, 5)])?;
+        // Replayed blocks are ignored
+        store.record_mines(10, &[mine(&b, 10, 50)])?;
+
+        let all_time = store.get_leaderboard(LeaderboardSort::Proofs, None, 10)?;
+        assert_eq!(all_time[0], MinerStats { miner: b, proofs: 2, rewards: 100 });
+        assert_eq!(all_time[1], MinerStats { miner: a, proofs: 2, rewards: 10 });
+
+        let recent = store.get_leaderboard(LeaderboardSort::Rewards, Some(1_000), 10)?;
+        assert_eq!(recent, vec![MinerStats { miner: a, proofs: 1, rewards: 5 }]);
+
+        assert_eq!(store.get_leaderboard(LeaderboardSort::Rewards, None, 1)?.len(), 1);
+        assert_eq!(store.get_leaderboard_cursor()?, Some(100_000));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_epoch_history() -> Result<(), StoreError> {
+        let (store, _temp_dir) = setup_store()?;
+        assert_eq!(store.get_latest_epoch()?, None);
+
+        for number in 1..=5 {
+            store.put_epoch(&EpochRecord {
+                number,
+                difficulty: 10 + number,
+                last_epoch_at: -1,
+                slot: number * 100,
+                ..Default::default()
+            })?;
+        }
+
+        // A later snapshot of the same epoch replaces the earlier one
+        let latest = EpochRecord { number: 5, difficulty: 20, slot: 550, ..Default::default() };
+        store.put_epoch(&latest)?;
+        assert_eq!(store.get_latest_epoch()?, Some(latest));
+
+        let history = store.get_epoch_history(2, 4, 100)?;
+        assert_eq!(history.iter().map(|r| r.number).collect::<Vec<_>>(), vec![2, 3, 4]);
+        assert_eq!(history[0].difficulty, 12);
+        assert_eq!(history[0].last_epoch_at, -1);
+
+        assert_eq!(store.get_epoch_history(0, u64::MAX, 2)?.len(), 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_health_samples() -> Result<(), StoreError> {
+        let (store, _temp_dir) = setup_store()?;
+        assert!(store.get_health_samples()?.is_empty());
+
+        // Updates between samples only move the current values
+        store.update_health_at(1_000, 100, 900)?;
+        store.update_health_at(1_030, 150, 880)?;
+        assert_eq!(store.get_health()?, (150, 880));
+        assert_eq!(
+            store.get_health_samples()?,
+            vec![HealthSample { time: 1_000, last_processed_slot: 100, drift: 900 }]
+        );
+
+        for i in 1..=MAX_HEALTH_SAMPLES as u64 {
+            store.update_health_at(1_000 + i * HEALTH_SAMPLE_INTERVAL, 100 + i * 200, 900 - i * 10)?;
+        }
+        let samples = store.get_health_samples()?;
+        assert_eq!(samples.len(), MAX_HEALTH_SAMPLES);
+        assert_eq!(samples[0].time, 1_000 + HEALTH_SAMPLE_INTERVAL);
+        assert_eq!(store.get_rpc_status()?, None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_sync_estimate() {
+        let sample = |time, last_processed_slot, drift| HealthSample { time, last_processed_slot, drift };
+
+        assert_eq!(SyncEstimate::from_samples(&[]), None);
+        assert_eq!(SyncEstimate::from_samples(&[sample(0, 100, 1000)]), None);
+
+        // 300 slots in 100s while the tip moved 250, closing 0.5 slots/s on 950 left
+        let estimate = SyncEstimate::from_samples(&[sample(0, 100, 1000), sample(100, 400, 950)]).unwrap();
+        assert_eq!(estimate.slots_per_second, 3.0);
+        assert_eq!(estimate.closing_per_second, 0.5);
+        assert_eq!(estimate.eta_seconds, Some(1900));
+        assert_eq!(estimate.to_string(), "3.0 slots/s, at the tip in ~31m");
+
+        let behind = SyncEstimate::from_samples(&[sample(0, 100, 1000), sample(100, 300, 1050)]).unwrap();
+        assert_eq!(behind.eta_seconds, None);
+        let caught_up = SyncEstimate::from_samples(&[sample(0, 100, 10), sample(100, 360, 0)]).unwrap();
+        assert_eq!(caught_up.eta_seconds, Some(0));
+
+        assert_eq!(format_eta(45), "45s");
+        assert_eq!(format_eta(3 * 3_600 + 120), "3h 2m");
+        assert_eq!(format_eta(2 * 86_400 + 5 * 3_600), "2d 5h");
+    }
+
+    #[test]
+    fn test_rpc_status() -> Result<(), StoreError> {
+        let (store, _temp_dir) = setup_store()?;
+        assert_eq!(store.get_rpc_status()?, None);
+
+        store.update_rpc_status(BreakerState::Open, 7)?;
+        assert_eq!(store.get_rpc_status()?, Some((BreakerState::Open, 7)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_gaps() -> Result<(), StoreError> {
+        let (store, _temp_dir) = setup_store()?;
+        assert!(store.get_gaps(10)?.is_empty());
+
+        store.add_gap(200, "timeout")?;
+        store.add_gap(100, "timeout")?;
+        store.add_gap(100, "block not available")?;
+
+        let gaps = store.get_gaps(10)?;
+        assert_eq!(
+            gaps,
+            vec![
+                SlotGap { slot: 100, attempts: 2, error: "block not available".into() },
+                SlotGap { slot: 200, attempts: 1, error: "timeout".into() },
+            ]
+        );
+        assert_eq!(store.get_gaps(1)?.len(), 1);
+
+        store.remove_gap(100)?;
+        assert_eq!(store.get_gaps(10)?[0].slot, 200);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_conflicts() -> Result<(), StoreError> {
+        let (store, _temp_dir) = setup_store()?;
+        let address = Pubkey::new_unique();
+        let other = Pubkey::new_unique();
+        assert!(store.get_conflicts(&address)?.is_empty());
+
+        let conflict = |segment_number, slot, identical, data: &[u8]| SegmentConflict {
+            segment_number,
+            slot,
+            identical,
+            data: data.to_vec(),
+        };
+
+        store.add_conflict(&address, &conflict(3, 20, false, &[9, 9]))?;
+        store.add_conflict(&address, &conflict(1, 30, true, &[1]))?;
+        store.add_conflict(&address, &conflict(3, 10, true, &[]))?;
+        store.add_conflict(&other, &conflict(0, 10, true, &[2]))?;
+
+        assert_eq!(
+            store.get_conflicts(&address)?,
+            vec![
+                conflict(1, 30, true, &[1]),
+                conflict(3, 10, true, &[]),
+                conflict(3, 20, false, &[9, 9]),
+            ]
+        );
+
+        store.finalize_tape(&address, 1)?;
+        store.evict_tape(&address)?;
+        assert!(store.get_conflicts(&address)?.is_empty());
+        assert_eq!(store.get_conflicts(&other)?.len(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_labels() -> Result<(), StoreError> {
+        let (store, _temp_dir) = setup_store()?;
+        let address = Pubkey::new_unique();
+        assert_eq!(store.get_label(&address)?, None);
+
+        let label = TapeLabel {
+            label: "spam".to_string(),
+            note: "reported twice, ünïcode ok".to_string(),
+            updated_at: 1_700_000_000,
+        };
+        store.put_label(&address, &label)?;
+        assert_eq!(store.get_label(&address)?, Some(label.clone()));
+
+        // Kept across an eviction
+        store.finalize_tape(&address, 1)?;
+        store.evict_tape(&address)?;
+        assert_eq!(store.get_labels()?, vec![(address, label)]);
+
+        store.put_label(&address, &TapeLabel::default())?;
+        assert_eq!(store.get_label(&address)?, None);
+        assert!(store.get_labels()?.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_blocklist() -> Result<(), StoreError> {
+        let (store, _temp_dir) = setup_store()?;
+        let (a, b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        assert!(!store.is_blocked(&a)?);
+
+        store.block_tape(&a, "takedown #1")?;
+        store.block_tape(&b, "takedown #2")?;
+        assert!(store.is_blocked(&a)?);
+        assert_eq!(store.get_blocked(&a)?.unwrap().reason, "takedown #1");
+        assert_eq!(store.get_blocklist()?.len(), 2);
+
+        store.unblock_tape(&a, "counter-notice")?;
+        assert!(!store.is_blocked(&a)?);
+        assert_eq!(store.get_blocklist()?.len(), 1);
+
+        // Blocking leaves the data alone
+        store.add_segment(&b, 0, vec![1, 2, 3])?;
+        assert_eq!(store.get_segment(&b, 0)?, vec![1, 2, 3]);
+
+        let log = store.get_policy_log(10)?;
+        assert_eq!(log.len(), 3);
+        assert!(log.iter().any(|e| e.tape_address == a && e.action == PolicyAction::Unblock && e.reason == "counter-notice"));
+        assert_eq!(log.iter().filter(|e| e.action == PolicyAction::Block).count(), 2);
+        assert_eq!(store.get_policy_log(1)?.len(), 1);
+
+        Ok(())
+    }
+
     #[test]
     fn test_add_and_get_segments() -> Result<(), StoreError> {
         let (store, _temp_dir) = setup_store()?;
         let tape_number = 1;
         let address = Pubkey::new_unique();
 
-        store.add_tape(tape_number, &address)?;
+        store.finalize_tape(&address, tape_number)?;
         let segment_data_1 = vec![1, 2, 3];
         let segment_data_2 = vec![4, 5, 6];
         store.add_segment(&address, 0, segment_data_1.clone())?;
@@ -383,8 +3006,8 @@ mod tests {
         let tape2_number = 2;
         let tape2_address = Pubkey::new_unique();
 
-        store.add_tape(tape1_number, &tape1_address)?;
-        store.add_tape(tape2_number, &tape2_address)?;
+        store.finalize_tape(&tape1_address, tape1_number)?;
+        store.finalize_tape(&tape2_address, tape2_number)?;
         store.add_segment(&tape1_address, 0, vec![1, 2, 3])?;
         store.add_segment(&tape2_address, 0, vec![4, 5, 6])?;
 
@@ -403,6 +3026,107 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_iterators() -> Result<(), StoreError> {
+        let (store, _temp_dir) = setup_store()?;
+
+        // Addresses sorting right next to each other, and one whose key starts with a zero byte
+        // like a tape number key does
+        let tape1_address = Pubkey::new_from_array([0; 32]);
+        let tape2_address = Pubkey::new_from_array([1; 32]);
+        let mut next_to = [1; 32];
+        next_to[31] = 2;
+        let tape3_address = Pubkey::new_from_array(next_to);
+
+        store.finalize_tape(&tape2_address, 2)?;
+        store.finalize_tape(&tape1_address, 1)?;
+        store.finalize_tape(&tape3_address, 300)?;
+
+        let tapes = store.iter_tapes()?.collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(tapes, vec![(1, tape1_address), (2, tape2_address), (300, tape3_address)]);
+        assert_eq!(store.iter_tapes()?.next().transpose()?, Some((1, tape1_address)));
+
+        for segment_number in [2, 0, 256, 1] {
+            store.add_segment(&tape2_address, segment_number, vec![segment_number as u8])?;
+        }
+        store.add_segment(&tape3_address, 0, vec![9, 9])?;
+
+        let segments = store
+            .iter_segments(&tape2_address)?
+            .map(|item| item.map(|(segment_number, _)| segment_number))
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(segments, vec![0, 1, 2, 256]);
+        assert_eq!(store.iter_segments(&tape1_address)?.count(), 0);
+
+        assert_eq!(store.iter_all_segments()?.count(), 5);
+        let sizes = store.get_tape_sizes()?;
+        assert_eq!(sizes[&tape2_address], 4);
+        assert_eq!(sizes[&tape3_address], 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_for_each_segment() -> Result<(), StoreError> {
+        let (store, _temp_dir) = setup_store()?;
+        let tape_address = Pubkey::new_unique();
+
+        for segment_number in [3, 1, 2] {
+            store.add_segment(&tape_address, segment_number, vec![segment_number as u8; 4])?;
+        }
+        store.add_segment(&Pubkey::new_unique(), 0, vec![0; 4])?;
+
+        let mut seen = Vec::new();
+        let visited = store.for_each_segment(&tape_address, |segment_number, data| {
+            seen.push((segment_number, data[0]));
+            Ok::<_, StoreError>(())
+        })?;
+        assert_eq!(visited, 3);
+        assert_eq!(seen, vec![(1, 1), (2, 2), (3, 3)]);
+
+        // The first error stops the walk
+        let result = store.for_each_segment(&tape_address, |segment_number, _| match segment_number {
+            2 => Err(StoreError::InvalidSegmentKey),
+            _ => Ok(()),
+        });
+        assert!(matches!(result, Err(StoreError::InvalidSegmentKey)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_segment_slots() -> Result<(), StoreError> {
+        let (store, _temp_dir) = setup_store()?;
+        let address = Pubkey::new_unique();
+
+        // An unfinalized tape has no number, its mutable segments are still served here
+        assert!(store.add_segment_at(&address, 0, vec![1], 100)?);
+        assert!(store.add_segment_at(&address, 1, vec![2], 100)?);
+        assert!(store.add_segment_at(&address, 0, vec![3], 120)?);
+
+        // Older writes don't replace newer ones
+        assert!(!store.add_segment_at(&address, 0, vec![4], 110)?);
+
+        // Segments without a known slot, e.g. from a trusted peer
+        store.add_mutable_segment(&address, 2, vec![5])?;
+
+        let expected = vec![
+            StoredSegment { segment_number: 0, data: vec![3], slot: Some(120) },
+            StoredSegment { segment_number: 1, data: vec![2], slot: Some(100) },
+            StoredSegment { segment_number: 2, data: vec![5], slot: None },
+        ];
+        assert_eq!(store.get_tape_segments_with_slots(&address)?, expected);
+
+        // Sealing keeps the slots
+        store.finalize_tape(&address, 1)?;
+        assert_eq!(store.get_tape_segments_with_slots(&address)?, expected);
+
+        store.evict_tape(&address)?;
+        assert!(store.get_tape_segments_with_slots(&address)?.is_empty());
+
+        Ok(())
+    }
+
     #[test]
     fn test_get_segment() -> Result<(), StoreError> {
         let (store, _temp_dir) = setup_store()?;
@@ -411,7 +3135,7 @@ mod tests {
         let address = Pubkey::new_unique();
         let segment_data = vec![1, 2, 3];
 
-        store.add_tape(tape_number, &address)?;
+        store.finalize_tape(&address, tape_number)?;
         store.add_segment(&address, segment_number, segment_data.clone())?;
 
         let retrieved_data = store.get_segment(&address, segment_number)?;
@@ -432,6 +3156,130 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_checksums() -> Result<(), StoreError> {
+        let (store, _temp_dir) = setup_store()?;
+        let address = Pubkey::new_unique();
+        for segment_number in 0..3 {
+            store.add_segment(&address, segment_number, vec![segment_number as u8; 8])?;
+        }
+
+        // Flip a bit of segment 1 behind the store's back, and write segment 2 as an older
+        // store would have, without a checksum
+        let cf_segments = store.db.cf_handle("segments").unwrap();
+        let cf_checksums = store.db.cf_handle("checksums").unwrap();
+        let key = |n: u64| [address.to_bytes().as_slice(), &n.to_be_bytes()].concat();
+        store.db.put_cf(cf_segments, key(1), [1, 1, 1, 0, 1, 1, 1, 1])?;
+        store.db.delete_cf(cf_checksums, key(2))?;
+
+        assert_eq!(store.get_segment(&address, 0)?, vec![0; 8]);
+        assert!(matches!(store.get_segment(&address, 1), Err(StoreError::ChecksumMismatch(_, 1))));
+        assert_eq!(store.get_segment(&address, 2)?, vec![2; 8]);
+        assert!(matches!(store.get_tape_segments(&address), Err(StoreError::ChecksumMismatch(_, 1))));
+        let visited = store.for_each_segment(&address, |_, _| Ok::<_, StoreError>(()));
+        assert!(matches!(visited, Err(StoreError::ChecksumMismatch(_, 1))));
+
+        let report = store.scrub()?;
+        assert_eq!(report.checked, 1);
+        assert_eq!(report.unchecked, 1);
+        assert_eq!(report.corrupt, vec![(address, 1)]);
+
+        // Storing the segment again repairs it
+        store.drop_segment(&address, 1)?;
+        assert!(matches!(store.get_segment(&address, 1), Err(StoreError::SegmentNotFound(_, 1))));
+        store.add_segment(&address, 1, vec![1; 8])?;
+        assert_eq!(store.get_tape_segments(&address)?.len(), 3);
+        assert!(store.scrub()?.corrupt.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_thumbnails()-> Result<(), StoreError> {
+        let (store, _temp_dir) = setup_store()?;
+        let address = Pubkey::new_unique();
+
+        let result = store.get_thumbnail(&address);
+        assert!(matches!(result, Err(StoreError::ThumbnailNotFound(_))));
+        assert_eq!(store.get_thumbnail_cursor()?, 0);
+
+        store.put_thumbnail(&address, &[1, 2, 3])?;
+        store.update_thumbnail_cursor(7)?;
+
+        assert_eq!(store.get_thumbnail(&address)?, vec![1, 2, 3]);
+        assert_eq!(store.get_thumbnail_cursor()?, 7);
+
+        // A cursor kept among the thumbnails, before it moved to the meta column family
+        let (legacy, _legacy_dir) = setup_store()?;
+        let cf_thumbs = legacy.db.cf_handle("thumbnails").unwrap();
+        legacy.db.put_cf(cf_thumbs, b"last_tape_number", 5u64.to_be_bytes())?;
+        assert_eq!(legacy.get_thumbnail_cursor()?, 5);
+        legacy.update_thumbnail_cursor(6)?;
+        assert_eq!(legacy.get_thumbnail_cursor()?, 6);
+
+        let cf_meta = legacy.db.cf_handle("meta").unwrap();
+        legacy.db.put_cf(cf_meta, b"thumbnail_cursor", [1, 2, 3])?;
+        let result = legacy.get_thumbnail_cursor();
+        assert!(matches!(result, Err(StoreError::InvalidThumbnailCursor)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_access_and_eviction() -> Result<(), StoreError> {
+        let (store, _temp_dir) = setup_store()?;
+        let tape1_address = Pubkey::new_unique();
+        let tape2_address = Pubkey::new_unique();
+
+        assert_eq!(store.get_last_access(&tape1_address)?, None);
+
+        store.finalize_tape(&tape1_address, 1)?;
+        store.finalize_tape(&tape2_address, 2)?;
+        store.add_segment(&tape1_address, 0, vec![1, 2, 3])?;
+        store.add_segment(&tape1_address, 1, vec![4, 5])?;
+        store.add_segment(&tape2_address, 0, vec![6])?;
+        store.put_thumbnail(&tape1_address, &[7])?;
+
+        assert!(store.get_last_access(&tape1_address)?.is_some());
+
+        let sizes = store.get_tape_sizes()?;
+        assert_eq!(sizes.get(&tape1_address), Some(&5));
+        assert_eq!(sizes.get(&tape2_address), Some(&1));
+
+        store.evict_tape(&tape1_address)?;
+
+        assert_eq!(store.get_tape_segments(&tape1_address)?.len(), 0);
+        assert_eq!(store.get_tape_segments(&tape2_address)?.len(), 1);
+        assert_eq!(store.get_last_access(&tape1_address)?, None);
+        assert!(matches!(
+            store.get_thumbnail(&tape1_address),
+            Err(StoreError::ThumbnailNotFound(_))
+        ));
+        assert_eq!(store.get_tape_number(&tape1_address)?, 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_namespace() -> Result<(), StoreError> {
+        let (store, _temp_dir) = setup_store()?;
+
+        assert_eq!(store.get_namespace()?, None);
+
+        store.init_namespace("devnet")?;
+        store.init_namespace("devnet")?;
+        assert_eq!(store.get_namespace()?, Some("devnet".to_string()));
+
+        let result = store.init_namespace("mainnet");
+        assert!(matches!(
+            result,
+            Err(StoreError::NamespaceMismatch { expected, found })
+                if expected == "mainnet" && found == "devnet"
+        ));
+
+        Ok(())
+    }
+
     #[test]
     fn test_get_multiple_segments() -> Result<(), StoreError> {
         let (store, _temp_dir) = setup_store()?;
@@ -440,7 +3288,7 @@ mod tests {
         let segment_data_1 = vec![1, 2, 3];
         let segment_data_2 = vec![4, 5, 6];
 
-        store.add_tape(tape_number, &address)?;
+        store.finalize_tape(&address, tape_number)?;
         store.add_segment(&address, 0, segment_data_1.clone())?;
         store.add_segment(&address, 1, segment_data_2.clone())?;
 
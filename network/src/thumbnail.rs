@@ -0,0 +1,155 @@
+use anyhow::{anyhow, Result};
+use image::ImageFormat;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+use std::io::Cursor;
+use tokio::time::{sleep, Duration};
+use tape_api::prelude::Tape;
+use tape_client::{decode_tape, get_tape_accounts, unprefix_segments, TapeHeader};
+
+use super::store::{StoreError, TapeStore};
+
+/// Maximum width and height of a generated thumbnail, in pixels.
+pub const THUMBNAIL_SIZE: u32 = 256;
+
+/// Tapes whose accounts are looked up in a single request.
+const BATCH_SIZE: u64 = 100;
+
+/// Times a tape's thumbnail is tried before the tape is skipped, so one tape that never renders
+/// doesn't hold up every tape after it.
+const MAX_ATTEMPTS: u32 = 3;
+
+/// Thumbnail loop that generates previews for image tapes as they are finalized and archived.
+pub async fn thumbnail_loop(store: &TapeStore, client: &RpcClient) -> Result<()> {
+    let interval = Duration::from_secs(10);
+
+    // The tape number the last iteration stopped on and how many times it failed
+    let mut failed = None;
+
+    loop {
+        match try_thumbnail_iteration(store, client, &mut failed).await {
+            Ok(()) => {}
+            Err(e) => eprintln!("ERROR: Thumbnail iteration failed: {:?}", e),
+        }
+
+        sleep(interval).await;
+    }
+}
+
+/// Walk every archived tape past the thumbnail cursor, generating thumbnails for images. The
+/// cursor only moves past a tape once its thumbnail is stored (or it isn't an image), a failure
+/// ends the iteration so the tape is tried again, until it's been tried `MAX_ATTEMPTS` times.
+async fn try_thumbnail_iteration(
+    store: &TapeStore,
+    client: &RpcClient,
+    failed: &mut Option<(u64, u32)>,
+) -> Result<()> {
+    loop {
+        let batch = next_batch(store, store.get_thumbnail_cursor()?)?;
+        if batch.is_empty() {
+            return Ok(());
+        }
+
+        let addresses = batch.iter().map(|(_, address)| *address).collect::<Vec<_>>();
+        let tapes: HashMap<Pubkey, Tape> = get_tape_accounts(client, &addresses)
+            .await?
+            .into_iter()
+            .collect();
+
+        for (tape_number, tape_address) in batch {
+            let result = match tapes.get(&tape_address) {
+                Some(tape) => thumbnail_tape(store, &tape_address, tape),
+                None => Err(anyhow!("Tape account not found")),
+            };
+
+            match result {
+                Ok(true) => {}
+                // Not all of its segments have been archived yet, try again later
+                Ok(false) => return Ok(()),
+                Err(e) => {
+                    let attempts = match *failed {
+                        Some((number, attempts)) if number == tape_number => attempts + 1,
+                        _ => 1,
+                    };
+
+                    if attempts < MAX_ATTEMPTS {
+                        *failed = Some((tape_number, attempts));
+                        return Err(e.context(format!(
+                            "Tape {} failed, attempt {} of {}",
+                            tape_address, attempts, MAX_ATTEMPTS
+                        )));
+                    }
+
+                    eprintln!(
+                        "ERROR: Skipping the thumbnail of tape {} after {} attempts: {:?}",
+                        tape_address, attempts, e
+                    );
+                }
+            }
+
+            *failed = None;
+            store.update_thumbnail_cursor(tape_number)?;
+        }
+    }
+}
+
+/// The next tapes past `cursor` the store knows the address of, by tape number.
+fn next_batch(store: &TapeStore, cursor: u64) -> Result<Vec<(u64, Pubkey)>> {
+    let mut batch = Vec::new();
+
+    for tape_number in cursor + 1..=cursor + BATCH_SIZE {
+        match store.get_tape_address(tape_number) {
+            Ok(address) => batch.push((tape_number, address)),
+            Err(StoreError::TapeNotFound(_)) => break,
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    Ok(batch)
+}
+
+/// Generate and store the thumbnail of an archived tape, if it's an image. Returns `false` if
+/// the store doesn't hold all of an image's segments yet.
+fn thumbnail_tape(store: &TapeStore, tape_address: &Pubkey, tape: &Tape) -> Result<bool> {
+    let header = TapeHeader::try_from_bytes(&tape.header)?;
+    if !header.is_image() {
+        return Ok(true);
+    }
+
+    let segments = store.get_tape_segments(tape_address)?;
+    if segments.len() < tape.total_segments as usize {
+        return Ok(false);
+    }
+
+    if let Some(thumbnail) = generate_thumbnail(header, segments)? {
+        store.put_thumbnail(tape_address, &thumbnail)?;
+        println!("DEBUG: Generated thumbnail for tape {}", tape_address);
+    }
+
+    Ok(true)
+}
+
+/// Decode an image tape and render a PNG thumbnail, returns `None` if the tape isn't an image.
+pub fn generate_thumbnail(
+    header: &TapeHeader,
+    segments: Vec<(u64, Vec<u8>)>,
+) -> Result<Option<Vec<u8>>> {
+    if !header.is_image() {
+        return Ok(None);
+    }
+
+    let encoded = unprefix_segments(segments, header)?;
+    let data = decode_tape(encoded, header)?;
+
+    let image = image::load_from_memory(&data)
+        .map_err(|e| anyhow!("Failed to decode image: {}", e))?;
+    let thumbnail = image.thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE);
+
+    let mut output = Cursor::new(Vec::new());
+    thumbnail
+        .write_to(&mut output, ImageFormat::Png)
+        .map_err(|e| anyhow!("Failed to encode thumbnail: {}", e))?;
+
+    Ok(Some(output.into_inner()))
+}
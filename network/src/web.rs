@@ -1,19 +1,48 @@
-use std::{net::SocketAddr, str::FromStr};
+use std::{collections::HashSet, net::SocketAddr, str::FromStr};
 use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use axum::{
-    extract::State,
-    http::StatusCode,
-    response::IntoResponse,
-    routing::post,
+    extract::{FromRef, Path, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{get, post},
     Json,
     Router,
 };
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
-use solana_sdk::pubkey::Pubkey;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{hash::Hash, pubkey::Pubkey};
 
-use super::store::{StoreError, TapeStore};
+use super::admin::{snapshot_path, AdminConfig, AdminQueue, AdminTask};
+use super::snapshot::{tape_root, MANIFEST_FILE};
+use super::logfile;
+use super::content::tape_content_handler;
+use super::explore::{explore_handler, tape_view_handler, to_hex};
+use super::shared::SecondaryStoreHandle;
+use super::store::{self, LeaderboardSort, PolicyLogEntry, StoreError, SyncEstimate, TapeLabel, TapeStore};
+
+/// Most segments a single getTape or getTapeByAddress call returns.
+const MAX_TAPE_SEGMENTS: usize = 1000;
+
+/// Most slots a single getTapeSlots call returns.
+const MAX_TAPE_SLOTS: usize = 1000;
+
+/// Most miners a single getLeaderboard call returns.
+const MAX_LEADERBOARD: usize = 100;
+
+/// Most epochs a single getEpochHistory call returns.
+const MAX_EPOCH_HISTORY: usize = 1000;
+
+/// Most tapes a single getRecentTapes call returns.
+const MAX_RECENT_TAPES: usize = 100;
+
+/// Most entries a single adminPolicyLog call returns.
+const MAX_POLICY_LOG: usize = 1000;
+
+/// How often the web node catches up with the archive's store.
+const CATCH_UP_INTERVAL: std::time::Duration = std::time::Duration::from_secs(15);
 
 #[repr(i64)]
 #[derive(Copy, Clone)]
@@ -24,6 +53,8 @@ pub enum ErrorCode {
     InvalidParams = -32602,
     InternalError = -32603,
     ServerError = -32000,
+    Unauthorized = -32001,
+    Blocked = -32002,
 }
 
 impl ErrorCode {
@@ -33,20 +64,20 @@ impl ErrorCode {
 }
 
 #[derive(Deserialize)]
-struct RpcRequest {
-    method: String,
-    params: Value,
-    id: Option<Value>,
+pub(crate) struct RpcRequest {
+    pub(crate) method: String,
+    pub(crate) params: Value,
+    pub(crate) id: Option<Value>,
 }
 
-#[derive(Serialize)]
+#[derive(Debug, Serialize)]
 pub struct RpcError {
-    code: i64,
-    message: String,
+    pub(crate) code: i64,
+    pub(crate) message: String,
 }
 
 #[derive(Serialize)]
-struct RpcResponse {
+pub(crate) struct RpcResponse {
     jsonrpc: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     result: Option<Value>,
@@ -56,7 +87,7 @@ struct RpcResponse {
 }
 
 // Helper: wrap a Result<Value, RpcError> into RpcResponse
-fn make_response(
+pub(crate) fn make_response(
     id: Option<Value>,
     result: Result<Value, RpcError>,
 ) -> (StatusCode, Json<RpcResponse>) {
@@ -73,7 +104,120 @@ fn make_response(
     (StatusCode::OK, Json(resp))
 }
 
-/// Retrieve the last‐persisted block height & drift.
+/// How a read method encodes raw bytes, picked with its `encoding` parameter.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Encoding {
+    #[default]
+    Base64,
+    /// URL-safe Base64 without padding.
+    Base64Url,
+    Hex,
+}
+
+impl Encoding {
+    /// The `encoding` parameter, Base64 if it's left out.
+    pub fn from_params(params: &Value) -> Result<Self, RpcError> {
+        match params.get("encoding") {
+            None | Some(Value::Null) => Ok(Encoding::Base64),
+            Some(encoding) => match encoding.as_str() {
+                Some("base64") => Ok(Encoding::Base64),
+                Some("base64url") => Ok(Encoding::Base64Url),
+                Some("hex") => Ok(Encoding::Hex),
+                _ => Err(RpcError {
+                    code: ErrorCode::InvalidParams.code(),
+                    message: format!("invalid encoding: {}, expected base64, base64url or hex", encoding),
+                }),
+            },
+        }
+    }
+
+    pub fn encode(self, data: &[u8]) -> String {
+        match self {
+            Encoding::Base64 => base64::encode(data),
+            Encoding::Base64Url => base64::encode_config(data, base64::URL_SAFE_NO_PAD),
+            Encoding::Hex => to_hex(data),
+        }
+    }
+}
+
+/// The page a list method was asked for, from its `cursor` and `limit` parameters. Every list
+/// method pages the same way and answers with an [`envelope`](Self::envelope).
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Page {
+    /// Items of the full list before this page.
+    pub offset: usize,
+    pub limit: usize,
+}
+
+impl Page {
+    /// Read `cursor` and `limit`, where `limit` defaults to `default` and is capped at `max`. The
+    /// cursor is opaque to callers, who only pass back what the previous page returned.
+    pub fn from_params(params: &Value, default: usize, max: usize) -> Result<Self, RpcError> {
+        let offset = match params.get("cursor") {
+            None | Some(Value::Null) => 0,
+            Some(cursor) => cursor
+                .as_str()
+                .and_then(|s| s.parse().ok())
+                .ok_or(RpcError {
+                    code: ErrorCode::InvalidParams.code(),
+                    message: "invalid cursor".into(),
+                })?,
+        };
+
+        let limit = match params.get("limit") {
+            None | Some(Value::Null) => default,
+            Some(limit) => limit
+                .as_u64()
+                .filter(|&limit| limit > 0)
+                .ok_or(RpcError {
+                    code: ErrorCode::InvalidParams.code(),
+                    message: "invalid limit, expected a positive number".into(),
+                })?
+                .min(max as u64) as usize,
+        };
+
+        Ok(Page { offset, limit })
+    }
+
+    /// Whether the item at `index` of the full list is on this page.
+    pub fn contains(&self, index: usize) -> bool {
+        index >= self.offset && index - self.offset < self.limit
+    }
+
+    /// `{ items, cursor, limit, total }`, where `cursor` fetches the next page and is null on the
+    /// last one, and `total` counts the items of every page.
+    pub fn envelope(&self, items: Vec<Value>, total: usize) -> Value {
+        let next = self.offset.saturating_add(self.limit);
+        json!({
+            "items": items,
+            "cursor": (next < total).then(|| next.to_string()),
+            "limit": self.limit,
+            "total": total,
+        })
+    }
+
+    /// Walk the full list, turning only the items on this page into JSON.
+    pub fn paginate<T>(
+        &self,
+        items: impl IntoIterator<Item = T>,
+        mut f: impl FnMut(T) -> Result<Value, RpcError>,
+    ) -> Result<Value, RpcError> {
+        let mut page = Vec::new();
+        let mut total = 0;
+        for item in items {
+            if self.contains(total) {
+                page.push(f(item)?);
+            }
+            total += 1;
+        }
+
+        Ok(self.envelope(page, total))
+    }
+}
+
+/// Retrieve the last‐persisted block height & drift, the state of the archive's RPC circuit
+/// breaker (`null` until the archive has run), and how fast the archive is syncing over the last
+/// hour (`null` until it has run for a minute).
 ///
 /// Example invocation:
 /// ```bash
@@ -88,7 +232,26 @@ pub fn rpc_get_health(store: &TapeStore, _params: &Value) -> Result<Value, RpcEr
             code: ErrorCode::ServerError.code(),
             message: e.to_string(),
         })?;
-    Ok(json!({ "last_processed_slot": last_processed_slot, "drift": drift }))
+    let rpc = store
+        .get_rpc_status()
+        .map_err(|e| RpcError {
+            code: ErrorCode::ServerError.code(),
+            message: e.to_string(),
+        })?
+        .map(|(state, failures)| json!({ "state": state.to_string(), "consecutive_failures": failures }));
+    let samples = store
+        .get_health_samples()
+        .map_err(|e| RpcError {
+            code: ErrorCode::ServerError.code(),
+            message: e.to_string(),
+        })?;
+    let sync = SyncEstimate::from_samples(&samples).map(|estimate| json!({
+        "slots_per_second": estimate.slots_per_second,
+        "closing_per_second": estimate.closing_per_second,
+        "eta_seconds": estimate.eta_seconds,
+    }));
+
+    Ok(json!({ "last_processed_slot": last_processed_slot, "drift": drift, "rpc": rpc, "sync": sync }))
 }
 
 /// Retrieve the pubkey (tape address) associated with a tape number.
@@ -175,8 +338,9 @@ pub fn rpc_get_tape_number(store: &TapeStore, params: &Value) -> Result<Value, R
 /// Parameters:
 /// - `tape_address`: Base-58 pubkey identifying the tape.
 /// - `segment_number`: Zero-based segment index.
+/// - `encoding` (optional): `"base64"` (default), `"base64url"` or `"hex"`.
 ///
-/// Returns a Base64-encoded string of the raw bytes.
+/// Returns the raw bytes as a string in the requested encoding.
 ///
 /// Example invocation:
 /// ```bash
@@ -201,6 +365,8 @@ pub fn rpc_get_segment(store: &TapeStore, params: &Value) -> Result<Value, RpcEr
             message: "invalid or missing segment_number".into(),
         })?;
 
+    let encoding = Encoding::from_params(params)?;
+
     let pk = Pubkey::from_str(addr).map_err(|e| RpcError {
         code: ErrorCode::InvalidParams.code(),
         message: format!("invalid pubkey: {}", e),
@@ -208,7 +374,7 @@ pub fn rpc_get_segment(store: &TapeStore, params: &Value) -> Result<Value, RpcEr
 
     store
         .get_segment(&pk, sn)
-        .map(|data| json!(base64::encode(data)))
+        .map(|data| json!(encoding.encode(&data)))
         .map_err(|e| match e {
             StoreError::SegmentNotFound(_, num) => RpcError {
                 code: ErrorCode::ServerError.code(),
@@ -221,19 +387,24 @@ pub fn rpc_get_segment(store: &TapeStore, params: &Value) -> Result<Value, RpcEr
         })
 }
 
-/// Retrieve all segments and their data for a given tape address.
+/// Retrieve the segments and their data for a given tape address, a page at a time.
 ///
 /// Parameters:
 /// - `tape_address`: Base-58 pubkey identifying the tape.
+/// - `cursor` (optional): Where the previous page left off.
+/// - `limit` (optional): Number of segments to return, default and at most 1000.
+/// - `encoding` (optional): `"base64"` (default), `"base64url"` or `"hex"`.
 ///
-/// Returns a JSON array of objects `[{ segment_number, data }]`, where `data` is Base64.
+/// Returns `{ items: [{ segment_number, data }], cursor, limit, total }`, in segment order. Calls
+/// that pass neither `cursor` nor `limit` get the bare array of every segment instead, the shape
+/// getTape had before it was paged, so older peers and clients keep working.
 ///
 /// Example invocation:
 ///
 /// ```bash
 /// curl -X POST http://127.0.0.1:3000/api \
 ///      -H 'Content-Type: application/json' \
-///      -d '{"jsonrpc":"2.0","id":4,"method":"getTape","params":{"tape_address":"<PUBKEY>"}}'
+///      -d '{"jsonrpc":"2.0","id":4,"method":"getTape","params":{"tape_address":"<PUBKEY>","cursor":null}}'
 /// ```
 pub fn rpc_get_tape(store: &TapeStore, params: &Value) -> Result<Value, RpcError> {
     let addr = params
@@ -249,66 +420,900 @@ pub fn rpc_get_tape(store: &TapeStore, params: &Value) -> Result<Value, RpcError
         message: format!("invalid pubkey: {}", e),
     })?;
 
-    let segments = store.get_tape_segments(&pk).map_err(|e| RpcError {
+    let encoding = Encoding::from_params(params)?;
+
+    if params.get("cursor").is_none() && params.get("limit").is_none() {
+        let mut segments = Vec::new();
+        store
+            .for_each_segment(&pk, |num, data| {
+                segments.push(json!({
+                    "segment_number": num,
+                    "data": encoding.encode(data),
+                }));
+                Ok::<_, StoreError>(())
+            })
+            .map_err(server_error)?;
+        return Ok(Value::Array(segments));
+    }
+
+    let page = Page::from_params(params, MAX_TAPE_SEGMENTS, MAX_TAPE_SEGMENTS)?;
+
+    // Encode the page's segments straight from the store, only counting the rest
+    let mut items = Vec::new();
+    let mut index = 0;
+    let total = store
+        .for_each_segment(&pk, |num, data| {
+            if page.contains(index) {
+                items.push(json!({
+                    "segment_number": num,
+                    "data": encoding.encode(data),
+                }));
+            }
+            index += 1;
+            Ok::<_, StoreError>(())
+        })
+        .map_err(server_error)?;
+
+    Ok(page.envelope(items, total))
+}
+
+/// Retrieve whatever segments the store holds for a tape, finalized or not, each annotated with
+/// the slot it was last written at. Lets applications that use tapes as mutable state read
+/// through the archive without waiting for a finalize.
+///
+/// Parameters:
+/// - `tape_address`: Base-58 pubkey identifying the tape.
+/// - `cursor` (optional): Where the previous page left off.
+/// - `limit` (optional): Number of segments to return, default and at most 1000.
+/// - `encoding` (optional): `"base64"` (default), `"base64url"` or `"hex"`.
+///
+/// Returns `{ tape_address, tape_number, last_slot, items: [{ segment_number, data, slot }],
+/// cursor, limit, total }`, where `tape_number` is null until the tape is finalized, `last_slot`
+/// covers every page and `slot` is null for segments synced from a trusted peer.
+///
+/// Example invocation:
+/// ```bash
+/// curl -X POST http://127.0.0.1:3000/api \
+///      -H 'Content-Type: application/json' \
+///      -d '{"jsonrpc":"2.0","id":8,"method":"getTapeByAddress","params":{"tape_address":"<PUBKEY>"}}'
+/// ```
+pub fn rpc_get_tape_by_address(store: &TapeStore, params: &Value) -> Result<Value, RpcError> {
+    let addr = params
+        .get("tape_address")
+        .and_then(Value::as_str)
+        .ok_or(RpcError {
+            code: ErrorCode::InvalidParams.code(),
+            message: "invalid or missing tape_address".into(),
+        })?;
+
+    let pk = Pubkey::from_str(addr).map_err(|e| RpcError {
+        code: ErrorCode::InvalidParams.code(),
+        message: format!("invalid pubkey: {}", e),
+    })?;
+
+    let page = Page::from_params(params, MAX_TAPE_SEGMENTS, MAX_TAPE_SEGMENTS)?;
+    let encoding = Encoding::from_params(params)?;
+
+    let tape_number = match store.get_tape_number(&pk) {
+        Ok(number) => Some(number),
+        Err(StoreError::TapeNotFoundForAddress(_)) => None,
+        Err(e) => {
+            return Err(RpcError {
+                code: ErrorCode::ServerError.code(),
+                message: e.to_string(),
+            })
+        }
+    };
+
+    let segments = store.get_tape_segments_with_slots(&pk).map_err(|e| RpcError {
         code: ErrorCode::ServerError.code(),
         message: e.to_string(),
     })?;
 
-    let arr: Vec<Value> = segments
+    if segments.is_empty() && tape_number.is_none() {
+        return Err(RpcError {
+            code: ErrorCode::ServerError.code(),
+            message: format!("tape {} not found", addr),
+        });
+    }
+
+    let last_slot = segments.iter().filter_map(|s| s.slot).max();
+    let mut result = page.paginate(segments, |segment| {
+        Ok(json!({
+            "segment_number": segment.segment_number,
+            "data": encoding.encode(&segment.data),
+            "slot": segment.slot,
+        }))
+    })?;
+
+    result["tape_address"] = json!(addr);
+    result["tape_number"] = json!(tape_number);
+    result["last_slot"] = json!(last_slot);
+
+    Ok(result)
+}
+
+/// The slots of the blocks the archive found writes to a tape in, oldest first, to fetch the
+/// tape's transactions from those blocks without walking its signatures. Only blocks archived
+/// since the node started indexing them are known.
+///
+/// Parameters:
+/// - `tape_address`: Base-58 pubkey identifying the tape.
+/// - `cursor` (optional): Where the previous page left off.
+/// - `limit` (optional): Number of slots to return, default and at most 1000.
+///
+/// Returns `{ items: [<slot>], cursor, limit, total }`.
+///
+/// Example invocation:
+/// ```bash
+/// curl -X POST http://127.0.0.1:3000/api \
+///      -H 'Content-Type: application/json' \
+///      -d '{"jsonrpc":"2.0","id":12,"method":"getTapeSlots","params":{"tape_address":"<PUBKEY>"}}'
+/// ```
+pub fn rpc_get_tape_slots(store: &TapeStore, params: &Value) -> Result<Value, RpcError> {
+    let pk = params
+        .get("tape_address")
+        .and_then(Value::as_str)
+        .and_then(|s| Pubkey::from_str(s).ok())
+        .ok_or(RpcError {
+            code: ErrorCode::InvalidParams.code(),
+            message: "invalid or missing tape_address".into(),
+        })?;
+
+    let page = Page::from_params(params, MAX_TAPE_SLOTS, MAX_TAPE_SLOTS)?;
+    let slots = store.get_tape_slots(&pk).map_err(server_error)?;
+
+    page.paginate(slots, |slot| Ok(json!(slot)))
+}
+
+/// Rebuild a tape's Merkle root from the segments the node holds, so nodes can check they agree
+/// without sending each other the data. The tree's seed is the `merkle_seed` of the tape account,
+/// which the caller looks up.
+///
+/// Parameters:
+/// - `tape_address`: Base-58 pubkey identifying the tape.
+/// - `merkle_seed`: Base-58 seed of the tape's Merkle tree.
+///
+/// Returns `{ tape_address, tape_number, segments, root }`, where `root` is Base-58 and
+/// `tape_number` is null until the tape is finalized.
+///
+/// Example invocation:
+/// ```bash
+/// curl -X POST http://127.0.0.1:3000/api \
+///      -H 'Content-Type: application/json' \
+///      -d '{"jsonrpc":"2.0","id":9,"method":"getTapeRoot","params":{"tape_address":"<PUBKEY>","merkle_seed":"<HASH>"}}'
+/// ```
+pub fn rpc_get_tape_root(store: &TapeStore, params: &Value) -> Result<Value, RpcError> {
+    let addr = params
+        .get("tape_address")
+        .and_then(Value::as_str)
+        .ok_or(RpcError {
+            code: ErrorCode::InvalidParams.code(),
+            message: "invalid or missing tape_address".into(),
+        })?;
+
+    let seed = params
+        .get("merkle_seed")
+        .and_then(Value::as_str)
+        .and_then(|s| Hash::from_str(s).ok())
+        .ok_or(RpcError {
+            code: ErrorCode::InvalidParams.code(),
+            message: "invalid or missing merkle_seed".into(),
+        })?;
+
+    let pk = Pubkey::from_str(addr).map_err(|e| RpcError {
+        code: ErrorCode::InvalidParams.code(),
+        message: format!("invalid pubkey: {}", e),
+    })?;
+
+    let tape_number = match store.get_tape_number(&pk) {
+        Ok(number) => Some(number),
+        Err(StoreError::TapeNotFoundForAddress(_)) => None,
+        Err(e) => return Err(server_error(e)),
+    };
+
+    let (root, segments) = tape_root(store, &pk, &seed.to_bytes()).map_err(server_error)?;
+
+    Ok(json!({
+        "tape_address": addr,
+        "tape_number": tape_number,
+        "segments": segments,
+        "root": Hash::new_from_array(root).to_string(),
+    }))
+}
+
+/// The label and note this node's operator put on a tape, if any. Labels are local to the node,
+/// nothing of them is on-chain.
+///
+/// Parameters:
+/// - `tape_address`: Base-58 pubkey identifying the tape.
+///
+/// Returns `{ tape_address, label, note, updated_at }`, or null if the tape has no label.
+///
+/// Example invocation:
+/// ```bash
+/// curl -X POST http://127.0.0.1:3000/api \
+///      -H 'Content-Type: application/json' \
+///      -d '{"jsonrpc":"2.0","id":10,"method":"getTapeLabel","params":{"tape_address":"<PUBKEY>"}}'
+/// ```
+pub fn rpc_get_tape_label(store: &TapeStore, params: &Value) -> Result<Value, RpcError> {
+    let pk = params
+        .get("tape_address")
+        .and_then(Value::as_str)
+        .and_then(|s| Pubkey::from_str(s).ok())
+        .ok_or(RpcError {
+            code: ErrorCode::InvalidParams.code(),
+            message: "invalid or missing tape_address".into(),
+        })?;
+
+    let label = store.get_label(&pk).map_err(server_error)?;
+
+    Ok(match label {
+        Some(label) => json!({
+            "tape_address": pk.to_string(),
+            "label": label.label,
+            "note": label.note,
+            "updated_at": label.updated_at,
+        }),
+        None => Value::Null,
+    })
+}
+
+/// The tapes the archive saw most recently, newest first, including ones still being written.
+/// Blocked tapes are left out.
+///
+/// Parameters (all optional):
+/// - `cursor`: Where the previous page left off.
+/// - `limit`: Number of tapes to return, default 20, at most 100.
+///
+/// Returns `{ items: [{ tape_address, tape_number, slot, time }], cursor, limit, total }`, with a
+/// null `tape_number` for tapes that aren't finalized yet.
+///
+/// Example invocation:
+/// ```bash
+/// curl -X POST http://127.0.0.1:3000/api \
+///      -H 'Content-Type: application/json' \
+///      -d '{"jsonrpc":"2.0","id":11,"method":"getRecentTapes","params":{"limit":20}}'
+/// ```
+pub fn rpc_get_recent_tapes(store: &TapeStore, params: &Value) -> Result<Value, RpcError> {
+    let page = Page::from_params(params, 20, MAX_RECENT_TAPES)?;
+
+    // Filter blocked tapes before paging, so they don't leave pages short or skew the total
+    let blocked: HashSet<Pubkey> = store
+        .get_blocklist()
+        .map_err(server_error)?
         .into_iter()
-        .map(|(num, data)| {
+        .map(|(address, _)| address)
+        .collect();
+    let tapes = store
+        .get_recently_seen(usize::MAX)
+        .map_err(server_error)?
+        .into_iter()
+        .filter(|(address, _)| !blocked.contains(address));
+
+    page.paginate(tapes, |(address, seen)| {
+        let number = match store.get_tape_number(&address) {
+            Ok(number) => Some(number),
+            Err(StoreError::TapeNotFoundForAddress(_)) => None,
+            Err(e) => return Err(server_error(e)),
+        };
+
+        Ok(json!({
+            "tape_address": address.to_string(),
+            "tape_number": number,
+            "slot": seen.slot,
+            "time": seen.time,
+        }))
+    })
+}
+
+/// Rank miners by proofs or rewards, as indexed by an archive started with `--leaderboard`.
+///
+/// Parameters (all optional):
+/// - `sort_by`: `"proofs"` (default) or `"rewards"`.
+/// - `window_slots`: Only count the last N slots, rounded to whole buckets. All-time if omitted.
+/// - `cursor`: Where the previous page left off.
+/// - `limit`: Number of miners to return, default 10, at most 100.
+///
+/// Returns `{ items: [{ miner, proofs, rewards }], cursor, limit, total }`, best first.
+///
+/// Example invocation:
+/// ```bash
+/// curl -X POST http://127.0.0.1:3000/api \
+///      -H 'Content-Type: application/json' \
+///      -d '{"jsonrpc":"2.0","id":6,"method":"getLeaderboard","params":{"sort_by":"rewards","window_slots":216000}}'
+/// ```
+pub fn rpc_get_leaderboard(store: &TapeStore, params: &Value) -> Result<Value, RpcError> {
+    let sort = match params.get("sort_by").and_then(Value::as_str) {
+        None | Some("proofs") => LeaderboardSort::Proofs,
+        Some("rewards") => LeaderboardSort::Rewards,
+        Some(other) => {
+            return Err(RpcError {
+                code: ErrorCode::InvalidParams.code(),
+                message: format!("invalid sort_by: {}", other),
+            })
+        }
+    };
+
+    let window_slots = params.get("window_slots").and_then(Value::as_u64);
+    let page = Page::from_params(params, 10, MAX_LEADERBOARD)?;
+
+    // The store ranks every miner anyway, keep them all for the total
+    let leaderboard = store
+        .get_leaderboard(sort, window_slots, usize::MAX)
+        .map_err(|e| RpcError {
+            code: ErrorCode::ServerError.code(),
+            message: e.to_string(),
+        })?;
+
+    page.paginate(leaderboard, |stats| {
+        Ok(json!({
+            "miner": stats.miner.to_string(),
+            "proofs": stats.proofs,
+            "rewards": stats.rewards,
+        }))
+    })
+}
+
+/// Retrieve the archived snapshots of the Epoch account, oldest first.
+///
+/// Parameters (all optional):
+/// - `from`: First epoch number, default 0.
+/// - `to`: Last epoch number (inclusive), defaults to the latest.
+/// - `cursor`: Where the previous page left off.
+/// - `limit`: Number of epochs to return, default and at most 1000.
+///
+/// Returns `{ items: [{ number, difficulty, last_epoch_at, base_rate, target_rate, slot }],
+/// cursor, limit, total }`.
+///
+/// Example invocation:
+/// ```bash
+/// curl -X POST http://127.0.0.1:3000/api \
+///      -H 'Content-Type: application/json' \
+///      -d '{"jsonrpc":"2.0","id":7,"method":"getEpochHistory","params":{"from":100,"to":200}}'
+/// ```
+pub fn rpc_get_epoch_history(store: &TapeStore, params: &Value) -> Result<Value, RpcError> {
+    let from = params.get("from").and_then(Value::as_u64).unwrap_or(0);
+    let to = params.get("to").and_then(Value::as_u64).unwrap_or(u64::MAX);
+
+    if from > to {
+        return Err(RpcError {
+            code: ErrorCode::InvalidParams.code(),
+            message: "from must not be greater than to".into(),
+        });
+    }
+
+    let page = Page::from_params(params, MAX_EPOCH_HISTORY, MAX_EPOCH_HISTORY)?;
+
+    let history = store
+        .get_epoch_history(from, to, usize::MAX)
+        .map_err(|e| RpcError {
+            code: ErrorCode::ServerError.code(),
+            message: e.to_string(),
+        })?;
+
+    page.paginate(history, |epoch| {
+        Ok(json!({
+            "number": epoch.number,
+            "difficulty": epoch.difficulty,
+            "last_epoch_at": epoch.last_epoch_at,
+            "base_rate": epoch.base_rate,
+            "target_rate": epoch.target_rate,
+            "slot": epoch.slot,
+        }))
+    })
+}
+
+/// The admin side of the web node, when it's enabled.
+struct AdminState {
+    config: AdminConfig,
+    queue: AdminQueue,
+    handle: SecondaryStoreHandle,
+    port: u16,
+}
+
+#[derive(Clone)]
+struct WebState {
+    store: Arc<TapeStore>,
+    handle: SecondaryStoreHandle,
+    rpc: Arc<RpcClient>,
+    admin: Option<Arc<AdminState>>,
+}
+
+impl FromRef<WebState> for SecondaryStoreHandle {
+    fn from_ref(state: &WebState) -> Self {
+        state.handle.clone()
+    }
+}
+
+impl FromRef<WebState> for Arc<TapeStore> {
+    fn from_ref(state: &WebState) -> Self {
+        Arc::clone(&state.store)
+    }
+}
+
+impl FromRef<WebState> for Arc<RpcClient> {
+    fn from_ref(state: &WebState) -> Self {
+        Arc::clone(&state.rpc)
+    }
+}
+
+fn server_error(e: impl std::fmt::Display) -> RpcError {
+    RpcError {
+        code: ErrorCode::ServerError.code(),
+        message: e.to_string(),
+    }
+}
+
+/// Catch up with the archive's store right away, instead of waiting for the next scheduled
+/// catch-up. Returns the health after catching up, as `getHealth` does.
+///
+/// Example invocation:
+/// ```bash
+/// curl -X POST http://127.0.0.1:3000/api \
+///      -H 'Content-Type: application/json' \
+///      -H "Authorization: Bearer $TAPEDRIVE_ADMIN_TOKEN" \
+///      -d '{"jsonrpc":"2.0","id":1,"method":"adminCatchUp","params":{}}'
+/// ```
+async fn rpc_admin_catch_up(admin: &AdminState, params: &Value) -> Result<Value, RpcError> {
+    admin.handle
+        .catch_up(std::time::Duration::ZERO)
+        .await
+        .map_err(server_error)?;
+
+    rpc_get_health(&admin.handle.store(), params)
+}
+
+/// Queue a tape to be archived again from the chain, repairing missing or stale segments. The
+/// archive running on this machine picks it up between iterations.
+///
+/// Parameters:
+/// - `tape_address`: The tape's pubkey.
+///
+/// Example invocation:
+/// ```bash
+/// curl -X POST http://127.0.0.1:3000/api \
+///      -H 'Content-Type: application/json' \
+///      -H "Authorization: Bearer $TAPEDRIVE_ADMIN_TOKEN" \
+///      -d '{"jsonrpc":"2.0","id":2,"method":"adminResync","params":{"tape_address":"<PUBKEY>"}}'
+/// ```
+fn rpc_admin_resync(admin: &AdminState, params: &Value) -> Result<Value, RpcError> {
+    let tape_address = params
+        .get("tape_address")
+        .and_then(|v| v.as_str())
+        .and_then(|s| Pubkey::from_str(s).ok())
+        .ok_or(RpcError {
+            code: ErrorCode::InvalidParams.code(),
+            message: "invalid or missing tape_address".into(),
+        })?;
+
+    admin.queue.push(AdminTask::Resync(tape_address)).map_err(server_error)?;
+    let pending = admin.queue.pending().map_err(server_error)?;
+
+    Ok(json!({ "queued": tape_address.to_string(), "pending": pending }))
+}
+
+/// Label a tape, or clear its label with an empty `label` and `note`. The archive running on this
+/// machine stores it between iterations, `getTapeLabel` returns it once this node catches up.
+///
+/// Parameters:
+/// - `tape_address`: The tape's pubkey.
+/// - `label`: A short label, at most 64 bytes.
+/// - `note` (optional): A longer note, at most 1024 bytes.
+///
+/// Example invocation:
+/// ```bash
+/// curl -X POST http://127.0.0.1:3000/api \
+///      -H 'Content-Type: application/json' \
+///      -H "Authorization: Bearer $TAPEDRIVE_ADMIN_TOKEN" \
+///      -d '{"jsonrpc":"2.0","id":5,"method":"setTapeLabel","params":{"tape_address":"<PUBKEY>","label":"spam","note":"reported 2024-05-01"}}'
+/// ```
+fn rpc_set_tape_label(admin: &AdminState, params: &Value) -> Result<Value, RpcError> {
+    let invalid = |message: String| RpcError {
+        code: ErrorCode::InvalidParams.code(),
+        message,
+    };
+
+    let tape_address = params
+        .get("tape_address")
+        .and_then(|v| v.as_str())
+        .and_then(|s| Pubkey::from_str(s).ok())
+        .ok_or_else(|| invalid("invalid or missing tape_address".into()))?;
+
+    let label = params
+        .get("label")
+        .and_then(Value::as_str)
+        .ok_or_else(|| invalid("invalid or missing label".into()))?;
+    let note = params.get("note").and_then(Value::as_str).unwrap_or_default();
+
+    if label.len() > TapeLabel::MAX_LABEL_LEN {
+        return Err(invalid(format!("label is longer than {} bytes", TapeLabel::MAX_LABEL_LEN)));
+    }
+    if note.len() > TapeLabel::MAX_NOTE_LEN {
+        return Err(invalid(format!("note is longer than {} bytes", TapeLabel::MAX_NOTE_LEN)));
+    }
+
+    let label = TapeLabel {
+        label: label.to_string(),
+        note: note.to_string(),
+        updated_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_err(server_error)?
+            .as_secs(),
+    };
+
+    admin.queue.push(AdminTask::Label(tape_address, label)).map_err(server_error)?;
+    let pending = admin.queue.pending().map_err(server_error)?;
+
+    Ok(json!({ "queued": tape_address.to_string(), "pending": pending }))
+}
+
+/// Stop serving a tape, e.g. after a legal takedown. Every read method refuses it with error
+/// `-32002` once the archive has applied the block and this node caught up, its segments stay in
+/// the store for mining. The block is recorded in the policy log with its reason.
+///
+/// Parameters:
+/// - `tape_address`: The tape's pubkey.
+/// - `reason`: Why it's blocked, e.g. a takedown reference.
+///
+/// Example invocation:
+/// ```bash
+/// curl -X POST http://127.0.0.1:3000/api \
+///      -H 'Content-Type: application/json' \
+///      -H "Authorization: Bearer $TAPEDRIVE_ADMIN_TOKEN" \
+///      -d '{"jsonrpc":"2.0","id":6,"method":"adminBlockTape","params":{"tape_address":"<PUBKEY>","reason":"DMCA #123"}}'
+/// ```
+fn rpc_admin_block_tape(admin: &AdminState, params: &Value) -> Result<Value, RpcError> {
+    let (tape_address, reason) = policy_params(params, true)?;
+
+    admin.queue.push(AdminTask::Block(tape_address, reason)).map_err(server_error)?;
+    let pending = admin.queue.pending().map_err(server_error)?;
+
+    Ok(json!({ "queued": tape_address.to_string(), "pending": pending }))
+}
+
+/// Serve a blocked tape again. Recorded in the policy log, with a reason if one is given.
+///
+/// Parameters:
+/// - `tape_address`: The tape's pubkey.
+/// - `reason` (optional): Why it's unblocked.
+///
+/// Example invocation:
+/// ```bash
+/// curl -X POST http://127.0.0.1:3000/api \
+///      -H 'Content-Type: application/json' \
+///      -H "Authorization: Bearer $TAPEDRIVE_ADMIN_TOKEN" \
+///      -d '{"jsonrpc":"2.0","id":7,"method":"adminUnblockTape","params":{"tape_address":"<PUBKEY>"}}'
+/// ```
+fn rpc_admin_unblock_tape(admin: &AdminState, params: &Value) -> Result<Value, RpcError> {
+    let (tape_address, reason) = policy_params(params, false)?;
+
+    admin.queue.push(AdminTask::Unblock(tape_address, reason)).map_err(server_error)?;
+    let pending = admin.queue.pending().map_err(server_error)?;
+
+    Ok(json!({ "queued": tape_address.to_string(), "pending": pending }))
+}
+
+fn policy_params(params: &Value, require_reason: bool) -> Result<(Pubkey, String), RpcError> {
+    let tape_address = params
+        .get("tape_address")
+        .and_then(|v| v.as_str())
+        .and_then(|s| Pubkey::from_str(s).ok())
+        .ok_or(RpcError {
+            code: ErrorCode::InvalidParams.code(),
+            message: "invalid or missing tape_address".into(),
+        })?;
+
+    let reason = params.get("reason").and_then(Value::as_str).unwrap_or_default();
+    if require_reason && reason.trim().is_empty() {
+        return Err(RpcError {
+            code: ErrorCode::InvalidParams.code(),
+            message: "missing reason".into(),
+        });
+    }
+
+    Ok((tape_address, reason.to_string()))
+}
+
+/// The tapes this node doesn't serve, and the log of every block and unblock, newest first.
+///
+/// Parameters (all optional):
+/// - `cursor`: Where the previous page of the log left off.
+/// - `limit`: Number of log entries to return, default 100, at most 1000.
+///
+/// Returns `{ blocked, items, cursor, limit, total }`, where `items` pages through the log and
+/// `blocked` always lists every blocked tape.
+///
+/// Example invocation:
+/// ```bash
+/// curl -X POST http://127.0.0.1:3000/api \
+///      -H 'Content-Type: application/json' \
+///      -H "Authorization: Bearer $TAPEDRIVE_ADMIN_TOKEN" \
+///      -d '{"jsonrpc":"2.0","id":8,"method":"adminPolicyLog","params":{"limit":20}}'
+/// ```
+fn rpc_admin_policy_log(admin: &AdminState, params: &Value) -> Result<Value, RpcError> {
+    let page = Page::from_params(params, 100, MAX_POLICY_LOG)?;
+
+    let store = admin.handle.store();
+    let blocked: Vec<Value> = store
+        .get_blocklist()
+        .map_err(server_error)?
+        .into_iter()
+        .map(|(address, blocked)| {
             json!({
-                "segment_number": num,
-                "data": base64::encode(data),
+                "tape_address": address.to_string(),
+                "reason": blocked.reason,
+                "blocked_at": blocked.blocked_at,
             })
         })
         .collect();
 
-    Ok(json!(arr))
+    let log = store.get_policy_log(usize::MAX).map_err(server_error)?;
+    let mut result = page.paginate(log, |PolicyLogEntry { at, tape_address, action, reason }| {
+        Ok(json!({
+            "at": at,
+            "tape_address": tape_address.to_string(),
+            "action": action.to_string(),
+            "reason": reason,
+        }))
+    })?;
+
+    result["blocked"] = json!(blocked);
+
+    Ok(result)
+}
+
+/// Ask the archive for a point-in-time copy of the store under
+/// `db_tapestore_snapshots/<namespace>/`, without stopping it. A web node only has a secondary,
+/// which can't take one. Returns the directory the snapshot goes to, which holds its
+/// `snapshot.json` manifest once the archive has written it.
+///
+/// Example invocation:
+/// ```bash
+/// curl -X POST http://127.0.0.1:3000/api \
+///      -H 'Content-Type: application/json' \
+///      -H "Authorization: Bearer $TAPEDRIVE_ADMIN_TOKEN" \
+///      -d '{"jsonrpc":"2.0","id":3,"method":"adminSnapshot","params":{}}'
+/// ```
+fn rpc_admin_snapshot(admin: &AdminState, _params: &Value) -> Result<Value, RpcError> {
+    let path = snapshot_path(&admin.config.namespace).map_err(server_error)?;
+    admin.queue.push(AdminTask::Snapshot(path.clone())).map_err(server_error)?;
+    let pending = admin.queue.pending().map_err(server_error)?;
+
+    Ok(json!({
+        "queued": path.display().to_string(),
+        "manifest": path.join(MANIFEST_FILE).display().to_string(),
+        "pending": pending,
+    }))
+}
+
+/// Reopen this node's log file and queue the archive to reopen its own, so both can be rotated
+/// without a restart. Move the files aside first, the nodes start fresh ones at the same paths.
+/// A node started without `--log-file` logs to stdout, and has nothing to reopen.
+///
+/// Example invocation:
+/// ```bash
+/// curl -X POST http://127.0.0.1:3000/api \
+///      -H 'Content-Type: application/json' \
+///      -H "Authorization: Bearer $TAPEDRIVE_ADMIN_TOKEN" \
+///      -d '{"jsonrpc":"2.0","id":5,"method":"adminRotateLogs","params":{}}'
+/// ```
+fn rpc_admin_rotate_logs(admin: &AdminState, _params: &Value) -> Result<Value, RpcError> {
+    let reopened = logfile::reopen().map_err(server_error)?;
+    admin.queue.push(AdminTask::RotateLogs).map_err(server_error)?;
+    let pending = admin.queue.pending().map_err(server_error)?;
+
+    Ok(json!({
+        "reopened": reopened.map(|path| path.display().to_string()),
+        "pending": pending,
+    }))
+}
+
+/// The web node's configuration.
+///
+/// Example invocation:
+/// ```bash
+/// curl -X POST http://127.0.0.1:3000/api \
+///      -H 'Content-Type: application/json' \
+///      -H "Authorization: Bearer $TAPEDRIVE_ADMIN_TOKEN" \
+///      -d '{"jsonrpc":"2.0","id":4,"method":"adminConfig","params":{}}'
+/// ```
+fn rpc_admin_config(admin: &AdminState, _params: &Value) -> Result<Value, RpcError> {
+    let namespace = &admin.config.namespace;
+
+    Ok(json!({
+        "version": env!("CARGO_PKG_VERSION"),
+        "namespace": namespace,
+        "port": admin.port,
+        "catch_up_interval_secs": CATCH_UP_INTERVAL.as_secs(),
+        "store": store::primary_path(namespace).map_err(server_error)?.display().to_string(),
+        "secondary": store::secondary_path(namespace).map_err(server_error)?.display().to_string(),
+        "admin_queue": admin.queue.dir().display().to_string(),
+        "pending_tasks": admin.queue.pending().map_err(server_error)?,
+    }))
+}
+
+/// Run an admin method, once the caller has shown the admin token.
+async fn admin_handler(
+    admin: Option<&AdminState>,
+    headers: &HeaderMap,
+    method: &str,
+    params: &Value,
+) -> Result<Value, RpcError> {
+    let Some(admin) = admin else {
+        return Err(RpcError {
+            code: ErrorCode::MethodNotFound.code(),
+            message: "admin methods are disabled on this node".into(),
+        });
+    };
+
+    let authorization = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok());
+    if !admin.config.authorize(authorization) {
+        return Err(RpcError {
+            code: ErrorCode::Unauthorized.code(),
+            message: "unauthorized".into(),
+        });
+    }
+
+    match method {
+        "adminCatchUp" => rpc_admin_catch_up(admin, params).await,
+        "adminResync" => rpc_admin_resync(admin, params),
+        "adminSnapshot" => rpc_admin_snapshot(admin, params),
+        "adminConfig" => rpc_admin_config(admin, params),
+        "adminRotateLogs" => rpc_admin_rotate_logs(admin, params),
+        "setTapeLabel" => rpc_set_tape_label(admin, params),
+        "adminBlockTape" => rpc_admin_block_tape(admin, params),
+        "adminUnblockTape" => rpc_admin_unblock_tape(admin, params),
+        "adminPolicyLog" => rpc_admin_policy_log(admin, params),
+        _ => Err(RpcError {
+            code: ErrorCode::MethodNotFound.code(),
+            message: "method not found".into(),
+        }),
+    }
 }
 
 async fn rpc_handler(
-    State(store): State<Arc<TapeStore>>,
+    State(state): State<WebState>,
+    headers: HeaderMap,
     Json(req): Json<RpcRequest>,
 ) -> impl IntoResponse {
 
+    let store = Arc::clone(&state.store);
     let id = req.id.clone();
-    let outcome = match req.method.as_str() {
-        "getHealth" => rpc_get_health(&store, &req.params),
-        "getTapeAddress" => rpc_get_tape_address(&store, &req.params),
-        "getTapeNumber" => rpc_get_tape_number(&store, &req.params),
-        "getSegment" => rpc_get_segment(&store, &req.params),
-        "getTape" => rpc_get_tape(&store, &req.params),
+    let admin = req.method.starts_with("admin") || req.method == "setTapeLabel";
+
+    let outcome = if admin {
+        admin_handler(state.admin.as_deref(), &headers, &req.method, &req.params).await
+    } else {
+        let outcome = check_blocklist(&store, &req.params)
+            .and_then(|()| read_handler(&store, &req.method, &req.params));
+        if outcome.is_ok() {
+            touch_read_tape(&state.handle, &req.method, &req.params);
+        }
+        outcome
+    };
+
+    make_response(id, outcome)
+}
+
+/// Refuse to answer about a tape this node blocked. Every read method names its tape with
+/// `tape_address` or `tape_number`, so all of them are checked here before they run.
+fn check_blocklist(store: &TapeStore, params: &Value) -> Result<(), RpcError> {
+    let address = match params.get("tape_address").and_then(Value::as_str) {
+        Some(addr) => Pubkey::from_str(addr).ok(),
+        None => params
+            .get("tape_number")
+            .and_then(Value::as_u64)
+            .and_then(|number| store.get_tape_address(number).ok()),
+    };
+
+    match address {
+        Some(pk) if store.is_blocked(&pk).map_err(server_error)? => Err(RpcError {
+            code: ErrorCode::Blocked.code(),
+            message: "tape is not served by this node".into(),
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// Record a read of a tape's data with the archive, so its garbage collector keeps tapes that
+/// are still being read. Lookups that don't return segment data don't count.
+fn touch_read_tape(handle: &SecondaryStoreHandle, method: &str, params: &Value) {
+    if !matches!(method, "getSegment" | "getTape") {
+        return;
+    }
+
+    if let Some(pk) = params
+        .get("tape_address")
+        .and_then(Value::as_str)
+        .and_then(|addr| Pubkey::from_str(addr).ok())
+    {
+        handle.touch_tape(&pk);
+    }
+}
+
+fn read_handler(store: &TapeStore, method: &str, params: &Value) -> Result<Value, RpcError> {
+    match method {
+        "getHealth" => rpc_get_health(store, params),
+        "getTapeAddress" => rpc_get_tape_address(store, params),
+        "getTapeNumber" => rpc_get_tape_number(store, params),
+        "getSegment" => rpc_get_segment(store, params),
+        "getTape" => rpc_get_tape(store, params),
+        "getTapeByAddress" => rpc_get_tape_by_address(store, params),
+        "getTapeSlots" => rpc_get_tape_slots(store, params),
+        "getLeaderboard" => rpc_get_leaderboard(store, params),
+        "getEpochHistory" => rpc_get_epoch_history(store, params),
+        "getTapeRoot" => rpc_get_tape_root(store, params),
+        "getTapeLabel" => rpc_get_tape_label(store, params),
+        "getRecentTapes" => rpc_get_recent_tapes(store, params),
         _ => Err(RpcError {
             code: ErrorCode::MethodNotFound.code(),
             message: "method not found".into(),
         }),
+    }
+}
+
+/// Serve the PNG thumbnail generated for an image tape.
+///
+/// Example invocation:
+/// ```bash
+/// curl http://127.0.0.1:3000/tape/<PUBKEY>/thumbnail -o thumbnail.png
+/// ```
+async fn thumbnail_handler(
+    State(store): State<Arc<TapeStore>>,
+    Path(address): Path<String>,
+) -> Response {
+    let pk = match Pubkey::from_str(&address) {
+        Ok(pk) => pk,
+        Err(e) => return (StatusCode::BAD_REQUEST, format!("invalid pubkey: {}", e)).into_response(),
     };
 
-    make_response(id, outcome)
+    match store.is_blocked(&pk) {
+        Ok(false) => {}
+        Ok(true) => return (StatusCode::UNAVAILABLE_FOR_LEGAL_REASONS, "tape is not served by this node").into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+
+    match store.get_thumbnail(&pk) {
+        Ok(data) => ([(header::CONTENT_TYPE, "image/png")], data).into_response(),
+        Err(StoreError::ThumbnailNotFound(_)) => {
+            (StatusCode::NOT_FOUND, "thumbnail not found").into_response()
+        }
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
 }
 
+/// Serve the web RPC service on `port`. Admin methods are only served when `admin` is set.
+/// `rpc` is only used to look up tape accounts when serving a tape's data.
 pub async fn web_loop(
-    store: TapeStore,
+    handle: SecondaryStoreHandle,
+    rpc: RpcClient,
     port: u16,
+    admin: Option<AdminConfig>,
 ) -> anyhow::Result<()> {
-    let store = Arc::new(store);
+    // Refresh the store regularly, shared with anything else reading it in this process
+    handle.spawn_catch_up(CATCH_UP_INTERVAL);
 
-    // Refresh the store every 15 seconds
-    {
-        let store = Arc::clone(&store);
-        tokio::spawn(async move {
-            let interval = std::time::Duration::from_secs(15);
-            loop {
-                store.catch_up_with_primary().unwrap();
-                tokio::time::sleep(interval).await;
-            }
-        });
-    }
+    let admin = match admin {
+        Some(config) => Some(Arc::new(AdminState {
+            queue: AdminQueue::open(&config.namespace)?,
+            config,
+            handle: handle.clone(),
+            port,
+        })),
+        None => None,
+    };
+    let state = WebState { store: handle.store(), handle, rpc: Arc::new(rpc), admin };
 
     let app = Router::new()
         .route("/api", post(rpc_handler))
-        .with_state(store);
+        .route("/tape/{address}", get(tape_content_handler))
+        .route("/tape/{address}/thumbnail", get(thumbnail_handler))
+        .route("/explore", get(explore_handler))
+        .route("/tape/{address}/view", get(tape_view_handler))
+        .with_state(state);
 
     let addr = SocketAddr::from(([127, 0, 0, 1], port));
     let listener = tokio::net::TcpListener::bind(&addr).await?;
@@ -317,3 +1322,113 @@ pub async fn web_loop(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encoding() {
+        let data = [0xfb, 0xff, 0x00, 0x10];
+
+        assert_eq!(Encoding::from_params(&json!({})).unwrap(), Encoding::Base64);
+        assert_eq!(Encoding::from_params(&json!({ "encoding": "base64url" })).unwrap(), Encoding::Base64Url);
+        assert!(Encoding::from_params(&json!({ "encoding": "base58" })).is_err());
+        assert!(Encoding::from_params(&json!({ "encoding": 1 })).is_err());
+
+        assert_eq!(Encoding::Base64.encode(&data), "+/8AEA==");
+        assert_eq!(Encoding::Base64Url.encode(&data), "-_8AEA");
+        assert_eq!(Encoding::Hex.encode(&data), "fbff0010");
+    }
+
+    #[test]
+    fn test_page() {
+        let page = Page::from_params(&json!({}), 10, 100).unwrap();
+        assert_eq!(page, Page { offset: 0, limit: 10 });
+
+        let page = Page::from_params(&json!({ "limit": 1000 }), 10, 100).unwrap();
+        assert_eq!(page.limit, 100);
+
+        assert!(Page::from_params(&json!({ "limit": 0 }), 10, 100).is_err());
+        assert!(Page::from_params(&json!({ "cursor": 5 }), 10, 100).is_err());
+        assert!(Page::from_params(&json!({ "cursor": "next" }), 10, 100).is_err());
+
+        // Walk a list of 5 two at a time, following the cursors
+        let mut params = json!({ "limit": 2 });
+        let mut pages = Vec::new();
+        loop {
+            let page = Page::from_params(&params, 10, 100).unwrap();
+            let result = page.paginate(0..5, |i| Ok(json!(i))).unwrap();
+            assert_eq!(result["total"], 5);
+            assert_eq!(result["limit"], 2);
+            pages.push(result["items"].clone());
+
+            if result["cursor"].is_null() {
+                break;
+            }
+            params["cursor"] = result["cursor"].clone();
+        }
+        assert_eq!(pages, vec![json!([0, 1]), json!([2, 3]), json!([4])]);
+
+        // A cursor past the end is an empty last page
+        let page = Page::from_params(&json!({ "cursor": "10" }), 10, 100).unwrap();
+        let result = page.paginate(0..5, |i| Ok(json!(i))).unwrap();
+        assert_eq!(result["items"], json!([]));
+        assert!(result["cursor"].is_null());
+    }
+
+    #[test]
+    fn test_get_tape_shapes() -> Result<(), StoreError> {
+        let temp_dir = tempdir::TempDir::new("rocksdb_test").map_err(StoreError::IoError)?;
+        let store = TapeStore::new(temp_dir.path())?;
+        let tape = Pubkey::new_unique();
+        for n in 0..3 {
+            store.add_segment(&tape, n, vec![n as u8])?;
+        }
+
+        // Without paging params, the bare array older peers expect
+        let result = rpc_get_tape(&store, &json!({ "tape_address": tape.to_string() })).unwrap();
+        assert_eq!(result.as_array().map(Vec::len), Some(3));
+        assert_eq!(result[2]["segment_number"], 2);
+
+        let params = json!({ "tape_address": tape.to_string(), "cursor": null, "limit": 2 });
+        let result = rpc_get_tape(&store, &params).unwrap();
+        assert_eq!(result["items"].as_array().map(Vec::len), Some(2));
+        assert_eq!(result["cursor"], "2");
+        assert_eq!(result["total"], 3);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_recent_tapes_skip_blocked() -> Result<(), StoreError> {
+        let temp_dir = tempdir::TempDir::new("rocksdb_test").map_err(StoreError::IoError)?;
+        let store = TapeStore::new(temp_dir.path())?;
+        let (a, b, c) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        store.record_first_seen(&a, store::FirstSeen { slot: 10, time: 100 })?;
+        store.record_first_seen(&b, store::FirstSeen { slot: 20, time: 200 })?;
+        store.record_first_seen(&c, store::FirstSeen { slot: 30, time: 300 })?;
+        store.block_tape(&b, "takedown #1")?;
+
+        // Left out of the page and the total, not just hidden
+        let result = rpc_get_recent_tapes(&store, &json!({ "limit": 1 })).unwrap();
+        assert_eq!(result["total"], 2);
+        assert_eq!(result["items"][0]["tape_address"], c.to_string());
+
+        let result = rpc_get_recent_tapes(&store, &json!({})).unwrap();
+        let listed: Vec<&str> = result["items"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|item| item["tape_address"].as_str().unwrap())
+            .collect();
+        assert_eq!(listed, vec![c.to_string(), a.to_string()]);
+
+        // And listed again once unblocked
+        store.unblock_tape(&b, "counter-notice")?;
+        let result = rpc_get_recent_tapes(&store, &json!({})).unwrap();
+        assert_eq!(result["total"], 3);
+
+        Ok(())
+    }
+}
@@ -35,6 +35,16 @@ pub fn process_instruction(
         InstructionType::Mine => process_mine(accounts, data)?,
         InstructionType::Claim => process_claim(accounts, data)?,
 
+        // Guardian instructions
+        InstructionType::Pause => process_pause(accounts, data)?,
+        InstructionType::Resume => process_resume(accounts, data)?,
+        InstructionType::SetGuardian => process_set_guardian(accounts, data)?,
+        InstructionType::SetMinerDeposit => process_set_miner_deposit(accounts, data)?,
+
+        // Maintenance instructions
+        InstructionType::Migrate => process_migrate(accounts, data)?,
+        InstructionType::InitializeConfig => process_initialize_config(accounts, data)?,
+
         _ => { return Err(ProgramError::InvalidInstructionData); }
     }
 
@@ -2,6 +2,7 @@ use tape_api::prelude::*;
 use steel::*;
 
 pub fn process_claim(accounts: &[AccountInfo<'_>], data: &[u8]) -> ProgramResult {
+    let current_time = Clock::get()?.unix_timestamp;
     let args = Claim::try_from_bytes(data)?;
     let [
         signer_info, 
@@ -40,11 +41,29 @@ pub fn process_claim(accounts: &[AccountInfo<'_>], data: &[u8]) -> ProgramResult
 
     let amount = u64::from_le_bytes(args.amount);
 
+    // Release whatever has unlocked, the rest stays in the miner
+    unlock_rewards(miner, current_time);
+
+    check_condition(
+        amount <= miner.unclaimed_rewards,
+        TapeError::ClaimTooLarge,
+    )?;
+
+    if amount > miner.unclaimed_rewards - miner.locked_rewards {
+        solana_program::msg!(
+            "{} of the unclaimed rewards are locked until {}",
+            miner.locked_rewards,
+            miner.vest_end
+        );
+    }
+
+    check_condition(
+        amount <= miner.unclaimed_rewards - miner.locked_rewards,
+        TapeError::RewardsLocked,
+    )?;
+
     // Update miner balance.
-    miner.unclaimed_rewards = miner
-        .unclaimed_rewards
-        .checked_sub(amount)
-        .ok_or(TapeError::ClaimTooLarge)?;
+    miner.unclaimed_rewards -= amount;
 
     // Transfer tokens from treasury to beneficiary.
     transfer_signed(
@@ -56,5 +75,13 @@ pub fn process_claim(accounts: &[AccountInfo<'_>], data: &[u8]) -> ProgramResult
         &[TREASURY],
     )?;
 
+    ClaimEvent {
+        miner: proof_info.key.to_bytes(),
+        beneficiary: beneficiary_info.key.to_bytes(),
+        amount,
+        unclaimed: miner.unclaimed_rewards,
+    }
+    .log();
+
     Ok(())
 }
@@ -2,17 +2,19 @@ use tape_api::prelude::*;
 use steel::*;
 
 pub fn process_close(accounts: &[AccountInfo<'_>], _data: &[u8]) -> ProgramResult {
+    let current_time = Clock::get()?.unix_timestamp;
     let [
         signer_info, 
         miner_info, 
         system_program_info,
+        refund_accounts @ ..
     ] = accounts else {
         return Err(ProgramError::NotEnoughAccountKeys);
     };
 
     signer_info.is_signer()?;
 
-    miner_info
+    let miner = miner_info
         .is_writable()?
         .as_account::<Miner>(&tape_api::ID)?
         .assert_err(
@@ -24,6 +26,49 @@ pub fn process_close(accounts: &[AccountInfo<'_>], _data: &[u8]) -> ProgramResul
     system_program_info
         .is_program(&system_program::ID)?;
 
+    // Refund the deposit once the miner has stopped proving for long enough.
+    let deposit = miner.deposit;
+
+    if deposit > 0 {
+        let [
+            beneficiary_info,
+            escrow_info,
+            escrow_ata_info,
+            token_program_info,
+        ] = refund_accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+
+        check_condition(
+            current_time >= miner.last_proof_at.saturating_add(MINER_DEPOSIT_COOLDOWN),
+            TapeError::DepositLocked,
+        )?;
+
+        beneficiary_info
+            .is_writable()?
+            .as_token_account()?
+            .assert(|t| t.mint() == MINT_ADDRESS)?;
+
+        escrow_info
+            .is_escrow()?;
+
+        escrow_ata_info
+            .is_writable()?
+            .is_escrow_ata()?;
+
+        token_program_info
+            .is_program(&spl_token::ID)?;
+
+        transfer_signed(
+            escrow_info,
+            escrow_ata_info,
+            beneficiary_info,
+            token_program_info,
+            deposit,
+            &[ESCROW],
+        )?;
+    }
+
     // Return rent to signer.
     miner_info.close(signer_info)?;
 
@@ -2,15 +2,13 @@ use crankx::Solution;
 use brine_tree::{Leaf, verify};
 use steel::*;
 use tape_api::prelude::*;
+use tape_api::curve::*;
 
 use super::compute_challenge;
 
-const MIN_CONSISTENCY_MULTIPLIER: u64  = 1;
-const MAX_CONSISTENCY_MULTIPLIER: u64  = 32;
-const REWARD_SCALE_FACTOR: u64         = 16;
-
 pub fn process_mine(accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
-    let current_time = Clock::get()?.unix_timestamp;
+    let clock = Clock::get()?;
+    let current_time = clock.unix_timestamp;
     let args = Mine::try_from_bytes(data)?;
     let [
         signer_info, 
@@ -19,13 +17,23 @@ pub fn process_mine(accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
         tape_info,
         epoch_info, 
         archive_info,
-        slot_hashes_info
+        slot_hashes_info,
+        config_info,
     ] = accounts else { 
         return Err(ProgramError::NotEnoughAccountKeys);
     };
 
     signer_info.is_signer()?;
 
+    let config = config_info
+        .is_config()?
+        .as_account::<Config>(&tape_api::ID)?;
+
+    check_condition(
+        !config.is_paused(),
+        TapeError::Paused,
+    )?;
+
     let epoch = epoch_info
         .is_epoch()?
         .as_account::<Epoch>(&tape_api::ID)?;
@@ -66,6 +74,13 @@ pub fn process_mine(accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
     let target_time = miner.last_proof_at + ONE_MINUTE;
     let early_threshold = target_time - GRACE_PERIOD_SECONDS;
 
+    if current_time <= early_threshold {
+        solana_program::msg!(
+            "Solution is {} seconds early",
+            early_threshold - current_time + 1
+        );
+    }
+
     check_condition(
         current_time > early_threshold,
         TapeError::SolutionTooEarly,
@@ -74,21 +89,26 @@ pub fn process_mine(accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
     let solution   = Solution::new(args.digest, args.nonce);
     let difficulty = solution.difficulty();
 
+    if difficulty < epoch.difficulty as u32 {
+        solana_program::msg!(
+            "Solution difficulty {} is below the epoch difficulty {}",
+            difficulty,
+            epoch.difficulty
+        );
+    }
+
     check_condition(
         difficulty >= epoch.difficulty as u32,
         TapeError::SolutionTooEasy,
     )?;
 
+    let (recall_tape, segment_number) = compute_recall_indices(miner, tape);
+
     check_condition(
-        tape.number == miner.recall_tape,
+        tape.number == recall_tape,
         TapeError::SolutionInvalid,
     )?;
 
-    let segment_number = compute_recall_segment(
-        &miner.current_challenge, 
-        tape.total_segments
-    );
-
     solana_program::msg!(
         "Recall tape: {}",
         tape.number
@@ -137,7 +157,7 @@ pub fn process_mine(accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
     );
 
     // Update miner multiplier
-    update_miner_multiplier(miner, current_time);
+    miner.multiplier = update_multiplier(miner.multiplier, miner.last_proof_at, current_time);
 
     // Calculate reward
     let base_reward = compute_base_reward(
@@ -174,6 +194,9 @@ pub fn process_mine(accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
     miner.total_proofs        += 1;
     miner.last_proof_at        = current_time.max(target_time);
 
+    // Lock the miner's share of the reward, if it registered with a lockup
+    lock_reward(miner, final_reward, current_time);
+
     // Calculate the next challenge and recall tape
     let next_challenge = compute_challenge(
         &miner.current_challenge,
@@ -198,112 +221,14 @@ pub fn process_mine(accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
         miner.recall_tape
     );
 
-    Ok(())
-}
-
-// Helper: Update miner multiplier based on timing of this solution.
-//
-// Miners that consistently submit solutions on-time will have a higher multiplier number. This
-// number is the base of pow(), which is used to calculate the base reward. Initially, the
-// multiplier is set to 1, meaning the reward earned for a solution is low. As the miner submits
-// more solutions, the multiplier increases, and the reward increases as well. It is in the miner's
-// best interest to submit solutions on-time, as the multiplier will decrease if they are late.
-//
-// This encourages miners to come up with strategies that allow them quick access to the tape data
-// needed to solve the challenge.
-#[inline(always)]
-fn update_miner_multiplier(miner: &mut Miner, current_time: i64) {
-    let target_time = miner.last_proof_at + ONE_MINUTE;
-    let liveness_threshold = target_time + GRACE_PERIOD_SECONDS;
-
-    if current_time > liveness_threshold {
-        // Maybe this should be a division instead of a subtraction?
-        miner.multiplier = miner.multiplier
-            .saturating_sub(1)
-            .max(MIN_CONSISTENCY_MULTIPLIER);
-    } else {
-        miner.multiplier = miner.multiplier
-            .saturating_add(1)
-            .min(MAX_CONSISTENCY_MULTIPLIER);
+    MineEvent {
+        miner: miner_info.key.to_bytes(),
+        tape: tape.number,
+        difficulty: difficulty as u64,
+        reward: final_reward,
+        slot: clock.slot,
     }
-}
+    .log();
 
-// Helper: Calculate base reward based on difficulty and multiplier
-#[inline(always)]
-fn compute_base_reward(
-    multiplier: u64,
-    base_rate: u64,
-    difficulty: u64,
-    difficulty_attempt: u32,
-) -> u64 {
-    assert!(difficulty_attempt >= difficulty as u32);
-
-    let consistency_multiplier = multiplier
-        .saturating_div(REWARD_SCALE_FACTOR)
-        .max(MIN_CONSISTENCY_MULTIPLIER)
-        .min(MAX_CONSISTENCY_MULTIPLIER);
-
-    let difficulty_excess = difficulty_attempt - difficulty as u32;
-
-    consistency_multiplier
-        .saturating_pow(difficulty_excess)
-        .saturating_mul(base_rate)
-}
-
-// Helper: Apply lateness penalty to base reward
-//
-// Following what ORE has done, we penalize the reward based on how late the miner is, the timings
-// are different, but the formula is the same.
-//
-// Example: If late by 90 seconds (1 minute + 30 seconds), the reward was first halved for the 
-// 1 minute, then further reduced for the 30 seconds.
-//
-// Reference:
-// https://github.com/regolith-labs/ore/blob/c18503d0ee98b8a7823b993b38823b7867059659/program/src/mine.rs#L105-L125
-#[inline(always)]
-fn penalize_lateness(
-    base_reward: u64, 
-    last_proof_at: i64, 
-    current_time: i64
-) -> u64 {
-    let target_time = last_proof_at + ONE_MINUTE;
-    let liveness_threshold = target_time + GRACE_PERIOD_SECONDS;
-    let mut penalized_reward = base_reward;
-
-    if current_time > liveness_threshold {
-        let late_seconds = current_time.saturating_sub(target_time) as u64;
-        let late_minutes = late_seconds.saturating_div(ONE_MINUTE as u64);
-
-        // An exponential penalty for full minutes late: base_reward / (2^late_minutes).
-        if late_minutes > 0 {
-            penalized_reward =
-                base_reward.saturating_div(2u64.saturating_pow(late_minutes as u32));
-        }
-
-        // A linear penalty for any extra seconds: A fraction of the reward is subtracted based on the
-        // proportion of extra_seconds relative to ONE_MINUTE. 
-        let extra_seconds = late_seconds.saturating_sub(late_minutes.saturating_mul(ONE_MINUTE as u64));
-        if extra_seconds > 0 && penalized_reward > 0 {
-            let time_penalty = penalized_reward
-                .saturating_div(2)
-                .saturating_mul(extra_seconds)
-                .saturating_div(ONE_MINUTE as u64);
-            penalized_reward = penalized_reward.saturating_sub(time_penalty);
-        }
-    }
-
-    penalized_reward
-}
-
-// Helper: Calculate final reward with limits
-#[inline(always)]
-fn compute_final_reward(
-    penalized_reward: u64,
-    theoretical_rewards: u64,
-    target_rate: u64,
-) -> u64 {
-    let final_reward = penalized_reward
-        .min(theoretical_rewards)
-        .min(target_rate);
-    final_reward
+    Ok(())
 }
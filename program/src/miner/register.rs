@@ -14,7 +14,9 @@ pub fn process_register(accounts: &[AccountInfo<'_>], data: &[u8]) -> ProgramRes
         archive_info,
         system_program_info, 
         rent_info,
-        slot_hashes_info
+        slot_hashes_info,
+        config_info,
+        deposit_accounts @ ..
     ] = accounts else {
         return Err(ProgramError::NotEnoughAccountKeys);
     };
@@ -36,6 +38,22 @@ pub fn process_register(accounts: &[AccountInfo<'_>], data: &[u8]) -> ProgramRes
     rent_info.is_sysvar(&sysvar::rent::ID)?;
     slot_hashes_info.is_sysvar(&sysvar::slot_hashes::ID)?;
 
+    let deposit = config_info
+        .is_config()?
+        .as_account::<Config>(&tape_api::ID)?
+        .miner_deposit;
+
+    // A lockup locks part of each reward, and needs epochs to unlock it over
+    let locked_percent = u64::from_le_bytes(args.locked_percent);
+    let vest_epochs    = u64::from_le_bytes(args.vest_epochs);
+
+    check_condition(
+        locked_percent <= 100 &&
+        vest_epochs <= MAX_VEST_EPOCHS &&
+        (locked_percent == 0) == (vest_epochs == 0),
+        ProgramError::InvalidInstructionData,
+    )?;
+
     // Register miner.
     create_program_account::<Miner>(
         miner_info,
@@ -56,6 +74,71 @@ pub fn process_register(accounts: &[AccountInfo<'_>], data: &[u8]) -> ProgramRes
     miner.total_proofs      = 0;
     miner.total_rewards     = 0;
     miner.unclaimed_rewards = 0;
+    miner.version           = ACCOUNT_VERSION;
+
+    miner.locked_percent    = locked_percent;
+    miner.vest_epochs       = vest_epochs;
+    miner.locked_rewards    = 0;
+    miner.vested_at         = current_time;
+    miner.vest_end          = current_time;
+
+    // Lock the deposit in escrow, the token accounts are only needed with one
+    miner.deposit           = deposit;
+
+    if deposit > 0 {
+        let [
+            deposit_info,
+            escrow_info,
+            escrow_ata_info,
+            mint_info,
+            token_program_info,
+            associated_token_program_info,
+        ] = deposit_accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+
+        deposit_info
+            .is_writable()?
+            .as_token_account()?
+            .assert(|t| t.mint() == MINT_ADDRESS)?;
+
+        escrow_info
+            .is_escrow()?;
+
+        escrow_ata_info
+            .is_writable()?
+            .is_escrow_ata()?;
+
+        mint_info
+            .has_address(&MINT_ADDRESS)?;
+
+        token_program_info
+            .is_program(&spl_token::ID)?;
+
+        associated_token_program_info
+            .is_program(&spl_associated_token_account::ID)?;
+
+        // The first deposit opens the escrow's token account
+        if escrow_ata_info.data_is_empty() {
+            create_associated_token_account(
+                signer_info,
+                escrow_info,
+                escrow_ata_info,
+                mint_info,
+                system_program_info,
+                token_program_info,
+                associated_token_program_info,
+            )?;
+        }
+
+        transfer(
+            signer_info,
+            deposit_info,
+            escrow_ata_info,
+            token_program_info,
+            deposit,
+        )?;
+    }
 
     let next_challenge = compute_challenge(
         &miner.current_challenge,
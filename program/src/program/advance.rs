@@ -1,9 +1,9 @@
 use tape_api::prelude::*;
+use tape_api::curve::*;
 use steel::*;
 
 const LOW_REWARD_THRESHOLD: u64  = 32;
 const HIGH_REWARD_THRESHOLD: u64 = 256;
-const SMOOTHING_FACTOR: u64      = 2;
 
 pub fn process_advance(accounts: &[AccountInfo<'_>], _data: &[u8]) -> ProgramResult {
     let current_time = Clock::get()?.unix_timestamp;
@@ -195,67 +195,3 @@ fn adjust_difficulty(epoch: &mut Epoch) {
 
     epoch.difficulty = epoch.difficulty.max(7);
 }
-
-// Helper: Compute new reward rate based on current rate and epoch rewards.
-//
-// Formula:
-// new_rate = current_rate * (target_rewards / actual_rewards)
-//
-// Following what ORE here to avoid footguns.
-// Reference: https://github.com/regolith-labs/ore/blob/c18503d0ee98b8a7823b993b38823b7867059659/program/src/reset.rs#L146
-#[inline(always)]
-fn compute_new_reward_rate(
-    current_rate: u64,
-    actual_rewards: u64,
-    target_rewards: u64,
-) -> u64 {
-
-    if actual_rewards == 0 {
-        return current_rate;
-    }
-
-    let adjusted_rate = (current_rate as u128)
-        .saturating_mul(target_rewards as u128)
-        .saturating_div(actual_rewards as u128) as u64;
-
-    let min_rate = current_rate.saturating_div(SMOOTHING_FACTOR);
-    let max_rate = current_rate.saturating_mul(SMOOTHING_FACTOR);
-    let smoothed_rate = adjusted_rate.min(max_rate).max(min_rate);
-
-    smoothed_rate
-        .max(1)
-        .min(target_rewards)
-}
-
-// Pre-computed emissions rate based on current supply. Decay of ~14% every 12 months with
-// a target of 7 million TAPE.
-pub fn get_emissions_rate(current_supply: u64) -> u64 {
-    match current_supply {
-        n if n < ONE_TAPE * 1000000 => 19025875190, // Year 1: ~1.90 TAPE/min
-        n if n < ONE_TAPE * 1861000 => 16381278538, // Year 2: ~1.64 TAPE/min
-        n if n < ONE_TAPE * 2602321 => 14104280821, // Year 3: ~1.41 TAPE/min
-        n if n < ONE_TAPE * 3240598 => 12143785787, // Year 4: ~1.21 TAPE/min
-        n if n < ONE_TAPE * 3790155 => 10455799563, // Year 5: ~1.05 TAPE/min
-        n if n < ONE_TAPE * 4263323 => 9002443423,  // Year 6: ~0.90 TAPE/min
-        n if n < ONE_TAPE * 4670721 => 7751103787,  // Year 7: ~0.78 TAPE/min
-        n if n < ONE_TAPE * 5021491 => 6673700361,  // Year 8: ~0.67 TAPE/min
-        n if n < ONE_TAPE * 5323504 => 5746056011,  // Year 9: ~0.57 TAPE/min
-        n if n < ONE_TAPE * 5583536 => 4947354225,  // Year 10: ~0.49 TAPE/min
-        n if n < ONE_TAPE * 5807425 => 4259671988,  // Year 11: ~0.43 TAPE/min
-        n if n < ONE_TAPE * 6000193 => 3667577581,  // Year 12: ~0.37 TAPE/min
-        n if n < ONE_TAPE * 6166166 => 3157784298,  // Year 13: ~0.32 TAPE/min
-        n if n < ONE_TAPE * 6309069 => 2718852280,  // Year 14: ~0.27 TAPE/min
-        n if n < ONE_TAPE * 6432108 => 2340931813,  // Year 15: ~0.23 TAPE/min
-        n if n < ONE_TAPE * 6538045 => 2015542291,  // Year 16: ~0.20 TAPE/min
-        n if n < ONE_TAPE * 6629257 => 1735381912,  // Year 17: ~0.17 TAPE/min
-        n if n < ONE_TAPE * 6707790 => 1494163827,  // Year 18: ~0.15 TAPE/min
-        n if n < ONE_TAPE * 6775407 => 1286475055,  // Year 19: ~0.13 TAPE/min
-        n if n < ONE_TAPE * 6833625 => 1107655022,  // Year 20: ~0.11 TAPE/min
-        n if n < ONE_TAPE * 6883751 => 953690974,   // Year 21: ~0.10 TAPE/min
-        n if n < ONE_TAPE * 6926910 => 821127928,   // Year 22: ~0.08 TAPE/min
-        n if n < ONE_TAPE * 6964069 => 706991146,   // Year 23: ~0.07 TAPE/min
-        n if n < ONE_TAPE * 6996064 => 608719377,   // Year 24: ~0.06 TAPE/min
-        n if n < ONE_TAPE * 7000000 => 524107383,   // Year 25: ~0.05 TAPE/min
-        _ => 0,
-    }
-}
@@ -0,0 +1,64 @@
+use tape_api::prelude::*;
+use steel::*;
+
+pub fn process_pause(accounts: &[AccountInfo<'_>], _data: &[u8]) -> ProgramResult {
+    let config = load_guarded_config(accounts)?;
+
+    config.paused = 1;
+
+    Ok(())
+}
+
+pub fn process_resume(accounts: &[AccountInfo<'_>], _data: &[u8]) -> ProgramResult {
+    let config = load_guarded_config(accounts)?;
+
+    config.paused = 0;
+
+    Ok(())
+}
+
+pub fn process_set_guardian(accounts: &[AccountInfo<'_>], data: &[u8]) -> ProgramResult {
+    let args = SetGuardian::try_from_bytes(data)?;
+    let config = load_guarded_config(accounts)?;
+
+    config.guardian = Pubkey::new_from_array(args.guardian);
+
+    Ok(())
+}
+
+pub fn process_set_miner_deposit(accounts: &[AccountInfo<'_>], data: &[u8]) -> ProgramResult {
+    let args = SetMinerDeposit::try_from_bytes(data)?;
+    let config = load_guarded_config(accounts)?;
+
+    let amount = u64::from_le_bytes(args.amount);
+
+    check_condition(
+        amount <= MAX_MINER_DEPOSIT,
+        ProgramError::InvalidInstructionData,
+    )?;
+
+    config.miner_deposit = amount;
+
+    Ok(())
+}
+
+/// Loads the config account, checking that the signer is its guardian.
+fn load_guarded_config<'a>(accounts: &'a [AccountInfo<'_>]) -> Result<&'a mut Config, ProgramError> {
+    let [
+        signer_info,
+        config_info,
+    ] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    signer_info.is_signer()?;
+
+    config_info
+        .is_config()?
+        .is_writable()?
+        .as_account_mut::<Config>(&tape_api::ID)?
+        .assert_mut_err(
+            |c| c.guardian == *signer_info.key,
+            ProgramError::MissingRequiredSignature,
+        )
+}
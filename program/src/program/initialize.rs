@@ -19,6 +19,7 @@ pub fn process_initialize(accounts: &[AccountInfo<'_>], _data: &[u8]) -> Program
         spool_7_info, 
         archive_info, 
         epoch_info, 
+        config_info, 
         metadata_info, 
         mint_info, 
         treasury_info, 
@@ -75,6 +76,11 @@ pub fn process_initialize(accounts: &[AccountInfo<'_>], _data: &[u8]) -> Program
         .is_writable()?
         .has_seeds(&[EPOCH], &tape_api::ID)?;
 
+    config_info
+        .is_empty()?
+        .is_writable()?
+        .has_seeds(&[CONFIG], &tape_api::ID)?;
+
     // Check mint, metadata, treasury
     let (mint_address, mint_bump) = mint_pda();
     let (treasury_address, treasury_bump) = treasury_pda();
@@ -141,6 +147,7 @@ pub fn process_initialize(accounts: &[AccountInfo<'_>], _data: &[u8]) -> Program
         spool.id = i as u64;
         spool.available_rewards = 0;
         spool.theoretical_rewards = 0;
+        spool.version = ACCOUNT_VERSION;
     }
 
     // Initialize epoch.
@@ -157,6 +164,25 @@ pub fn process_initialize(accounts: &[AccountInfo<'_>], _data: &[u8]) -> Program
     epoch.base_rate     = INITIAL_REWARD_RATE;
     epoch.difficulty    = INITIAL_DIFFICULTY as u64;
     epoch.last_epoch_at = 0;
+    epoch.version       = ACCOUNT_VERSION;
+
+    // Initialize config, the initializer is the first guardian.
+    create_program_account::<Config>(
+        config_info,
+        system_program_info,
+        signer_info,
+        &tape_api::ID,
+        &[CONFIG],
+    )?;
+
+    let config = config_info.as_account_mut::<Config>(&tape_api::ID)?;
+
+    config.guardian = *signer_info.key;
+    config.paused   = 0;
+    config.version  = ACCOUNT_VERSION;
+
+    // Registration stays free until the guardian asks for a deposit
+    config.miner_deposit = 0;
 
     create_program_account::<Archive>(
         archive_info,
@@ -170,6 +196,7 @@ pub fn process_initialize(accounts: &[AccountInfo<'_>], _data: &[u8]) -> Program
 
     // No tapes have been created yet.
     archive.tapes_stored  = 0;
+    archive.version       = ACCOUNT_VERSION;
 
     // Initialize treasury.
     create_program_account::<Treasury>(
@@ -180,6 +207,10 @@ pub fn process_initialize(accounts: &[AccountInfo<'_>], _data: &[u8]) -> Program
         &[TREASURY],
     )?;
 
+    let treasury = treasury_info.as_account_mut::<Treasury>(&tape_api::ID)?;
+
+    treasury.version = ACCOUNT_VERSION;
+
     // Initialize mint.
     allocate_account_with_bump(
         mint_info,
@@ -0,0 +1,149 @@
+use tape_api::prelude::*;
+use solana_program::bpf_loader_upgradeable::{self, UpgradeableLoaderState};
+use steel::*;
+
+/// Brings a program account up to the current layout. Accounts created before a layout grew are
+/// realloced to the current size (the signer tops up rent, new bytes are zeroed) and stamped with
+/// `ACCOUNT_VERSION`. Anyone can pay for this, it never touches existing fields.
+pub fn process_migrate(accounts: &[AccountInfo<'_>], _data: &[u8]) -> ProgramResult {
+    let [
+        signer_info,
+        account_info,
+        system_program_info,
+    ] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    signer_info.is_signer()?;
+
+    account_info
+        .is_writable()?
+        .has_owner(&tape_api::ID)?;
+
+    system_program_info
+        .is_program(&system_program::ID)?;
+
+    let discriminator = *account_info
+        .try_borrow_data()?
+        .first()
+        .ok_or(ProgramError::InvalidAccountData)?;
+
+    let account_type = AccountType::try_from(discriminator)
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+
+    match account_type {
+        AccountType::Archive => migrate::<Archive>(signer_info, account_info, system_program_info),
+        AccountType::Spool => migrate::<Spool>(signer_info, account_info, system_program_info),
+        AccountType::Writer => migrate::<Writer>(signer_info, account_info, system_program_info),
+        AccountType::Tape => migrate::<Tape>(signer_info, account_info, system_program_info),
+        AccountType::Miner => migrate::<Miner>(signer_info, account_info, system_program_info),
+        AccountType::Epoch => migrate::<Epoch>(signer_info, account_info, system_program_info),
+        AccountType::Treasury => migrate::<Treasury>(signer_info, account_info, system_program_info),
+        AccountType::Config => migrate::<Config>(signer_info, account_info, system_program_info),
+        AccountType::Unknown => Err(ProgramError::InvalidAccountData),
+    }
+}
+
+fn migrate<'info, T>(
+    signer_info: &AccountInfo<'info>,
+    account_info: &AccountInfo<'info>,
+    system_program_info: &AccountInfo<'info>,
+) -> ProgramResult
+where
+    T: AccountDeserialize + Discriminator + Pod + Versioned,
+{
+    let size = 8 + core::mem::size_of::<T>();
+
+    if account_info.data_len() < size {
+        let rent = solana_program::rent::Rent::get()?.minimum_balance(size);
+        let shortfall = rent.saturating_sub(account_info.lamports());
+
+        if shortfall > 0 {
+            solana_program::program::invoke(
+                &solana_program::system_instruction::transfer(
+                    signer_info.key,
+                    account_info.key,
+                    shortfall,
+                ),
+                &[
+                    signer_info.clone(),
+                    account_info.clone(),
+                    system_program_info.clone(),
+                ],
+            )?;
+        }
+
+        account_info.realloc(size, true)?;
+    }
+
+    let account = account_info.as_account_mut::<T>(&tape_api::ID)?;
+    if account.version() < ACCOUNT_VERSION {
+        account.set_version(ACCOUNT_VERSION);
+    }
+
+    Ok(())
+}
+
+/// Creates the config account of a deployment initialized before config existed, so Write,
+/// Update and Mine work again after the upgrade. Only the program's upgrade authority can sign
+/// this, and it becomes the first guardian, as the initializer does for a fresh deployment.
+pub fn process_initialize_config(accounts: &[AccountInfo<'_>], _data: &[u8]) -> ProgramResult {
+    let [
+        signer_info,
+        config_info,
+        program_data_info,
+        system_program_info,
+    ] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    signer_info.is_signer()?;
+
+    config_info
+        .is_empty()?
+        .is_writable()?
+        .has_seeds(&[CONFIG], &tape_api::ID)?;
+
+    program_data_info
+        .has_address(&program_data_pda().0)?
+        .has_owner(&bpf_loader_upgradeable::ID)?;
+
+    system_program_info
+        .is_program(&system_program::ID)?;
+
+    check_condition(
+        upgrade_authority(&program_data_info.try_borrow_data()?) == Some(*signer_info.key),
+        ProgramError::MissingRequiredSignature,
+    )?;
+
+    create_program_account::<Config>(
+        config_info,
+        system_program_info,
+        signer_info,
+        &tape_api::ID,
+        &[CONFIG],
+    )?;
+
+    let config = config_info.as_account_mut::<Config>(&tape_api::ID)?;
+
+    config.guardian      = *signer_info.key;
+    config.paused        = 0;
+    config.version       = ACCOUNT_VERSION;
+    config.miner_deposit = 0;
+
+    Ok(())
+}
+
+/// The upgrade authority recorded in a ProgramData account, None if the program is immutable.
+fn upgrade_authority(data: &[u8]) -> Option<Pubkey> {
+    // Serialized as a u32 variant tag, the slot, then an optional authority
+    const PROGRAM_DATA_TAG: u32 = 3;
+
+    let metadata = data.get(..UpgradeableLoaderState::size_of_programdata_metadata())?;
+    let tag = u32::from_le_bytes(metadata[0..4].try_into().ok()?);
+    if tag != PROGRAM_DATA_TAG || metadata[12] != 1 {
+        return None;
+    }
+
+    Some(Pubkey::new_from_array(metadata[13..45].try_into().ok()?))
+}
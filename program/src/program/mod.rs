@@ -1,5 +1,9 @@
 pub mod initialize;
 pub mod advance;
+pub mod guardian;
+pub mod migrate;
 
 pub use initialize::*;
 pub use advance::*;
+pub use guardian::*;
+pub use migrate::*;
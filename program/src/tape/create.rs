@@ -79,9 +79,11 @@ pub fn process_create(accounts: &[AccountInfo<'_>], data: &[u8]) -> ProgramResul
     tape.merkle_seed       = empty_seed.to_bytes();
     tape.merkle_root       = [0; 32];
     tape.header            = args.header;
+    tape.version           = ACCOUNT_VERSION;
 
     writer.tape            = *tape_info.key;
     writer.state           = MerkleTree::new(&[empty_seed.as_ref()]);
+    writer.version         = ACCOUNT_VERSION;
 
     Ok(())
 }
@@ -9,12 +9,22 @@ pub fn process_update(accounts: &[AccountInfo<'_>], data: &[u8]) -> ProgramResul
         signer_info, 
         tape_info,
         writer_info, 
+        config_info,
     ] = accounts else {
         return Err(ProgramError::NotEnoughAccountKeys);
     };
 
     signer_info.is_signer()?;
 
+    let config = config_info
+        .is_config()?
+        .as_account::<Config>(&tape_api::ID)?;
+
+    check_condition(
+        !config.is_paused(),
+        TapeError::Paused,
+    )?;
+
     let tape = tape_info
         .as_account_mut::<Tape>(&tape_api::ID)?
         .assert_mut_err(
@@ -44,6 +54,13 @@ pub fn process_update(accounts: &[AccountInfo<'_>], data: &[u8]) -> ProgramResul
     let segment_number = args.segment_number;
     let merkle_proof   = args.proof;
 
+    // Only segments already written can be replaced
+
+    check_condition(
+        u64::from_le_bytes(segment_number) < tape.total_segments,
+        TapeError::SegmentTooLarge,
+    )?;
+
     assert!(args.old_data.len() == SEGMENT_SIZE);
     assert!(args.new_data.len() == SEGMENT_SIZE);
     assert!(merkle_proof.len() == PROOF_LEN);
@@ -6,12 +6,22 @@ pub fn process_write(accounts: &[AccountInfo<'_>], data: &[u8]) -> ProgramResult
         signer_info, 
         tape_info,
         writer_info, 
+        config_info,
     ] = accounts else {
         return Err(ProgramError::NotEnoughAccountKeys);
     };
 
     signer_info.is_signer()?;
 
+    let config = config_info
+        .is_config()?
+        .as_account::<Config>(&tape_api::ID)?;
+
+    check_condition(
+        !config.is_paused(),
+        TapeError::Paused,
+    )?;
+
     let tape = tape_info
         .as_account_mut::<Tape>(&tape_api::ID)?
         .assert_mut_err(
@@ -38,6 +48,14 @@ pub fn process_write(accounts: &[AccountInfo<'_>], data: &[u8]) -> ProgramResult
         TapeError::UnexpectedState,
     )?;
 
+    // Check the write fits before touching the tree, so a full
+    // tape says so instead of failing deep inside the tree add
+
+    check_condition(
+        write_fits(tape.total_segments, tape.total_size, data.len()),
+        TapeError::TapeFull,
+    )?;
+
     // Convert the data to a canonical segments of data 
     // and write them to the Merkle tree (all segments are 
     // written as SEGMENT_SIZE bytes, no matter the size 
@@ -0,0 +1,251 @@
+#![cfg(test)]
+//! Epochs over time: the clock is moved forward with `warp` and the epoch advanced again and
+//! again, with miners' proofs stood in for by what they earn from each spool, to check how the
+//! reward rate, difficulty and emissions in `advance.rs` respond.
+
+pub mod utils;
+use utils::*;
+
+use litesvm::LiteSVM;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use solana_sdk::{
+    instruction::InstructionError,
+    signature::Keypair,
+    signer::Signer,
+    transaction::{Transaction, TransactionError},
+};
+use tape_api::curve::{compute_new_reward_rate, get_emissions_rate};
+use tape_api::prelude::*;
+
+/// Reward rates the difficulty moves at, as in `advance.rs`.
+const LOW_REWARD_THRESHOLD: u64 = 32;
+const HIGH_REWARD_THRESHOLD: u64 = 256;
+
+/// The lowest difficulty an epoch is set to.
+const MIN_DIFFICULTY: u64 = 7;
+
+/// A fresh program, initialized with `payer` as the guardian.
+fn setup() -> (LiteSVM, Keypair) {
+    let mut svm = setup_svm();
+    let payer = create_payer(&mut svm);
+
+    let blockhash = svm.latest_blockhash();
+    let ix = build_initialize_ix(payer.pubkey());
+    let tx = Transaction::new_signed_with_payer(&[ix], Some(&payer.pubkey()), &[&payer], blockhash);
+    assert!(send_tx(&mut svm, tx).is_ok());
+
+    (svm, payer)
+}
+
+fn supply(svm: &LiteSVM) -> u64 {
+    get_mint(svm, &MINT_ADDRESS).supply
+}
+
+fn target_rewards(epoch: &Epoch) -> u64 {
+    epoch.target_rate * EPOCH_DURATION_MINUTES as u64
+}
+
+/// The same payout from every spool, adding up to `total`.
+fn spread(total: u64) -> [u64; SPOOL_COUNT] {
+    [total / SPOOL_COUNT as u64; SPOOL_COUNT]
+}
+
+/// Start the next epoch from `base_rate` and `difficulty`.
+fn set_rate(svm: &mut LiteSVM, base_rate: u64, difficulty: u64) {
+    edit_epoch(svm, |epoch| {
+        epoch.base_rate = base_rate;
+        epoch.difficulty = difficulty;
+    });
+}
+
+#[test]
+fn test_epoch_advances_once() {
+    let (mut svm, payer) = setup();
+
+    let epoch = next_epoch(&mut svm, &payer);
+    assert_eq!(epoch.number, 1);
+    assert_eq!(epoch.last_epoch_at, now(&svm));
+
+    // Advancing again before the epoch is over changes nothing
+    let minted = supply(&svm);
+    warp(&mut svm, 0);
+    assert!(send_advance(&mut svm, &payer).is_ok());
+    assert_eq!(get_epoch(&svm), epoch);
+    assert_eq!(supply(&svm), minted);
+
+    for number in 2..=5 {
+        assert_eq!(next_epoch(&mut svm, &payer).number, number);
+    }
+}
+
+#[test]
+fn test_spools_topped_up() {
+    let (mut svm, payer) = setup();
+
+    // The first epoch fills every spool with an epoch's rewards
+    let epoch = next_epoch(&mut svm, &payer);
+    let target = target_rewards(&epoch);
+    assert_eq!(epoch.target_rate, get_emissions_rate(0));
+    for spool in get_spools(&svm) {
+        assert_eq!(spool.available_rewards, target);
+        assert_eq!(spool.theoretical_rewards, 0);
+    }
+    assert_eq!(supply(&svm), SPOOL_COUNT as u64 * target);
+    assert_eq!(get_ata_balance(&svm, &TREASURY_ATA), supply(&svm));
+
+    // Nothing was paid out, so nothing more is minted
+    let minted = supply(&svm);
+    next_epoch(&mut svm, &payer);
+    assert_eq!(supply(&svm), minted);
+
+    // Only what was paid out is minted again
+    let mut rewards = [0; SPOOL_COUNT];
+    rewards[0] = 1_000;
+    rewards[7] = 500;
+    mine_from_spools(&mut svm, rewards);
+    next_epoch(&mut svm, &payer);
+    assert_eq!(supply(&svm), minted + 1_500);
+    for spool in get_spools(&svm) {
+        assert_eq!(spool.available_rewards, target);
+        assert_eq!(spool.theoretical_rewards, 0);
+    }
+}
+
+#[test]
+fn test_epochs_without_miners() {
+    let (mut svm, payer) = setup();
+    let initial = get_epoch(&svm);
+
+    // With nothing earned the rate isn't recalculated, but it's over the high threshold, so it
+    // halves every epoch while the difficulty stays at its floor
+    for number in 1..=30u32 {
+        let epoch = next_epoch(&mut svm, &payer);
+        let halvings = number.min(26);
+        assert_eq!(epoch.base_rate, initial.base_rate >> halvings, "epoch {}", number);
+        assert_eq!(epoch.difficulty, MIN_DIFFICULTY, "epoch {}", number);
+    }
+    assert!(get_epoch(&svm).base_rate < HIGH_REWARD_THRESHOLD);
+}
+
+#[test]
+fn test_reward_rate_follows_participation() {
+    let (mut svm, payer) = setup();
+    let target = target_rewards(&next_epoch(&mut svm, &payer));
+    set_rate(&mut svm, 100, 10);
+
+    // Each step is (rewards earned, base rate and difficulty after). The rate moves against what
+    // was earned, at most by half or double an epoch, and the difficulty only past a threshold
+    let steps = [
+        (2 * target, 50, 10),
+        (target / 2, 100, 10),
+        (target / 4, 200, 10),
+        (4 * target, 100, 10),
+        (4 * target, 50, 10),
+        (4 * target, 50, 11),
+    ];
+
+    for (i, (earned, base_rate, difficulty)) in steps.into_iter().enumerate() {
+        mine_from_spools(&mut svm, spread(earned));
+        let epoch = next_epoch(&mut svm, &payer);
+        assert_eq!((epoch.base_rate, epoch.difficulty), (base_rate, difficulty), "step {}", i);
+    }
+}
+
+#[test]
+fn test_difficulty_thresholds() {
+    let (mut svm, payer) = setup();
+    next_epoch(&mut svm, &payer);
+
+    // (base rate and difficulty before, and after an epoch with nothing earned)
+    let cases = [
+        ((LOW_REWARD_THRESHOLD - 12, 10), (2 * (LOW_REWARD_THRESHOLD - 12), 11)),
+        ((LOW_REWARD_THRESHOLD, 10), (LOW_REWARD_THRESHOLD, 10)),
+        ((HIGH_REWARD_THRESHOLD - 1, 10), (HIGH_REWARD_THRESHOLD - 1, 10)),
+        ((1_000, 10), (500, 9)),
+        // Never below the floor, though the rate still halves
+        ((1_000, MIN_DIFFICULTY), (500, MIN_DIFFICULTY)),
+        ((1_000, 2), (500, MIN_DIFFICULTY)),
+    ];
+
+    for ((base_rate, difficulty), after) in cases {
+        set_rate(&mut svm, base_rate, difficulty);
+        let epoch = next_epoch(&mut svm, &payer);
+        assert_eq!((epoch.base_rate, epoch.difficulty), after, "from {} at {}", base_rate, difficulty);
+    }
+}
+
+#[test]
+fn test_emissions_decay() {
+    let (mut svm, payer) = setup();
+    let first = next_epoch(&mut svm, &payer);
+
+    // The rate steps down as the supply crosses each year's boundary
+    set_mint_supply(&mut svm, &MINT_ADDRESS, 1_000_000 * ONE_TAPE - 1);
+    assert_eq!(next_epoch(&mut svm, &payer).target_rate, first.target_rate);
+
+    set_mint_supply(&mut svm, &MINT_ADDRESS, 1_000_000 * ONE_TAPE);
+    let epoch = next_epoch(&mut svm, &payer);
+    assert_eq!(epoch.target_rate, get_emissions_rate(1_000_000 * ONE_TAPE));
+    assert!(epoch.target_rate < first.target_rate);
+
+    // Spools still hold more than the lower target, so nothing is minted
+    assert_eq!(supply(&svm), 1_000_000 * ONE_TAPE);
+
+    // Near the cap only what's left is minted, to the first spool that needs it
+    set_mint_supply(&mut svm, &MINT_ADDRESS, MAX_SUPPLY - 100);
+    mine_from_spools(&mut svm, spread(SPOOL_COUNT as u64 * target_rewards(&first)));
+    next_epoch(&mut svm, &payer);
+    assert_eq!(supply(&svm), MAX_SUPPLY);
+    let spools = get_spools(&svm);
+    assert_eq!(spools[0].available_rewards, 100);
+    assert!(spools[1..].iter().all(|spool| spool.available_rewards == 0));
+
+    // And once it's reached, epochs stop advancing
+    warp(&mut svm, EPOCH_SECONDS);
+    let failed = send_advance(&mut svm, &payer).unwrap_err();
+    assert_eq!(
+        failed.err,
+        TransactionError::InstructionError(0, InstructionError::Custom(TapeError::MaxSupply as u32))
+    );
+}
+
+#[test]
+fn test_many_epochs() {
+    let (mut svm, payer) = setup();
+    let mut rng = StdRng::seed_from_u64(7);
+    let mut previous = next_epoch(&mut svm, &payer);
+
+    // A day of epochs with anywhere from no miners to three times the target earned
+    for _ in 0..96 {
+        let minted = supply(&svm);
+        let target = get_emissions_rate(minted) * EPOCH_DURATION_MINUTES as u64;
+        let rewards: [u64; SPOOL_COUNT] = std::array::from_fn(|_| rng.gen_range(0..=3 * target / SPOOL_COUNT as u64));
+        mine_from_spools(&mut svm, rewards);
+
+        let spools = get_spools(&svm);
+        let earned: u64 = spools.iter().map(|spool| spool.theoretical_rewards).sum();
+        let top_ups: u64 = spools.iter().map(|spool| target.saturating_sub(spool.available_rewards)).sum();
+
+        let epoch = next_epoch(&mut svm, &payer);
+        assert_eq!(epoch.number, previous.number + 1);
+        assert_eq!(epoch.last_epoch_at, now(&svm));
+        assert_eq!(supply(&svm), minted + top_ups);
+        assert!(get_spools(&svm).iter().all(|spool| spool.theoretical_rewards == 0));
+
+        // The rate follows what was earned, then moves the difficulty if it's out of bounds
+        let mut base_rate = compute_new_reward_rate(previous.base_rate, earned, target);
+        let mut difficulty = previous.difficulty;
+        if base_rate < LOW_REWARD_THRESHOLD {
+            difficulty += 1;
+            base_rate *= 2;
+        }
+        if base_rate >= HIGH_REWARD_THRESHOLD && difficulty > 1 {
+            difficulty -= 1;
+            base_rate /= 2;
+        }
+        assert_eq!(epoch.base_rate, base_rate);
+        assert_eq!(epoch.difficulty, difficulty.max(MIN_DIFFICULTY));
+
+        previous = epoch;
+    }
+}
@@ -5,10 +5,13 @@ use rand::Rng;
 
 use solana_sdk::{
     signer::Signer,
-    transaction::Transaction,
+    transaction::{Transaction, TransactionError},
+    instruction::InstructionError,
     clock::Clock,
     pubkey::Pubkey,
-    signature::Keypair
+    signature::Keypair,
+    account::Account,
+    bpf_loader_upgradeable::{self, UpgradeableLoaderState},
 };
 
 use tape_api::prelude::*;
@@ -40,6 +43,7 @@ fn run_integration() {
     verify_spool_accounts(&svm);
     verify_archive_account(&svm, 0);
     verify_epoch_account(&svm);
+    verify_config_account(&svm, payer.pubkey(), false);
     verify_treasury_account(&svm);
     verify_mint_account(&svm);
     verify_metadata_account(&svm);
@@ -100,6 +104,241 @@ fn run_integration() {
     println!("next challenge: {:?}", miner.current_challenge);
 }
 
+#[test]
+fn run_pause() {
+    let (mut svm, payer) = setup_environment();
+    initialize_program(&mut svm, &payer);
+
+    let payer_pk = payer.pubkey();
+    let tape_name = "paused-tape";
+    let (tape_address, _tape_bump) = tape_pda(payer_pk, &to_name(tape_name));
+    let (writer_address, _writer_bump) = writer_pda(tape_address);
+    create_tape(&mut svm, &payer, tape_name, [0; HEADER_SIZE], tape_address, writer_address);
+
+    // Only the guardian can pause
+    let intruder = create_payer(&mut svm);
+    assert!(send_ix(&mut svm, &intruder, build_pause_ix(intruder.pubkey())).is_err());
+
+    // Writes are rejected while paused
+    assert!(send_ix(&mut svm, &payer, build_pause_ix(payer_pk)).is_ok());
+    verify_config_account(&svm, payer_pk, true);
+    let ix = build_write_ix(payer_pk, tape_address, writer_address, b"paused");
+    assert!(send_ix(&mut svm, &payer, ix).is_err());
+
+    // And accepted again once resumed
+    assert!(send_ix(&mut svm, &payer, build_resume_ix(payer_pk)).is_ok());
+    verify_config_account(&svm, payer_pk, false);
+    let ix = build_write_ix(payer_pk, tape_address, writer_address, b"resumed");
+    assert!(send_ix(&mut svm, &payer, ix).is_ok());
+
+    // The guardian role can be handed over
+    let ix = build_set_guardian_ix(payer_pk, intruder.pubkey());
+    assert!(send_ix(&mut svm, &payer, ix).is_ok());
+    verify_config_account(&svm, intruder.pubkey(), false);
+    assert!(send_ix(&mut svm, &payer, build_pause_ix(payer_pk)).is_err());
+    assert!(send_ix(&mut svm, &intruder, build_pause_ix(intruder.pubkey())).is_ok());
+}
+
+#[test]
+fn run_migrate() {
+    let (mut svm, payer) = setup_environment();
+    initialize_program(&mut svm, &payer);
+
+    let payer_pk = payer.pubkey();
+    let tape_name = "old-tape";
+    let (tape_address, _tape_bump) = tape_pda(payer_pk, &to_name(tape_name));
+    let (writer_address, _writer_bump) = writer_pda(tape_address);
+    let stored_tape = create_tape(&mut svm, &payer, tape_name, [7; HEADER_SIZE], tape_address, writer_address);
+    assert_eq!(stored_tape.account.version, ACCOUNT_VERSION);
+
+    // Simulate an account created before the versioned tail existed
+    let mut account = svm.get_account(&tape_address).unwrap();
+    account.data.truncate(Tape::get_size() - 72);
+    svm.set_account(tape_address, account).unwrap();
+
+    assert!(send_ix(&mut svm, &payer, build_migrate_ix(payer_pk, tape_address)).is_ok());
+
+    let account = svm.get_account(&tape_address).unwrap();
+    assert_eq!(account.data.len(), Tape::get_size());
+    let tape = Tape::unpack(&account.data).unwrap();
+    assert_eq!(tape.version, ACCOUNT_VERSION);
+    assert_eq!(tape.reserved, [0; 64]);
+    assert_eq!(tape.name, stored_tape.account.name);
+    assert_eq!(tape.header, stored_tape.account.header);
+
+    // Migrating a current account is a no-op
+    assert!(send_ix(&mut svm, &payer, build_migrate_ix(payer_pk, tape_address)).is_ok());
+    let account = svm.get_account(&tape_address).unwrap();
+    assert_eq!(account.data.len(), Tape::get_size());
+
+    // A legacy miner is rejected until it's migrated, then loads as before
+    let miner_address = register_miner(&mut svm, &payer, "old-miner");
+    let mut account = svm.get_account(&miner_address).unwrap();
+    account.data.truncate(Miner::get_size() - 72);
+    svm.set_account(miner_address, account).unwrap();
+
+    let account = svm.get_account(&miner_address).unwrap();
+    assert!(needs_migration(&account.data));
+    assert!(Miner::unpack(&account.data).is_err());
+
+    let close_ix = build_close_ix(payer_pk, miner_address);
+    assert!(send_ix(&mut svm, &payer, close_ix.clone()).is_err());
+    assert!(send_ix(&mut svm, &payer, build_migrate_ix(payer_pk, miner_address)).is_ok());
+
+    let account = svm.get_account(&miner_address).unwrap();
+    assert!(!needs_migration(&account.data));
+    assert_eq!(Miner::unpack(&account.data).unwrap().authority, payer_pk);
+    assert!(send_ix(&mut svm, &payer, close_ix).is_ok());
+}
+
+#[test]
+fn run_initialize_config() {
+    let (mut svm, payer) = setup_environment();
+    initialize_program(&mut svm, &payer);
+
+    let payer_pk = payer.pubkey();
+    let tape_name = "pre-config-tape";
+    let (tape_address, _tape_bump) = tape_pda(payer_pk, &to_name(tape_name));
+    let (writer_address, _writer_bump) = writer_pda(tape_address);
+    create_tape(&mut svm, &payer, tape_name, [0; HEADER_SIZE], tape_address, writer_address);
+
+    // Simulate a deployment initialized before config existed
+    let (config_address, _config_bump) = config_pda();
+    svm.set_account(config_address, Account::default()).unwrap();
+    let ix = build_write_ix(payer_pk, tape_address, writer_address, b"no config");
+    assert!(send_ix(&mut svm, &payer, ix).is_err());
+
+    // Only the upgrade authority can create it
+    let authority = create_payer(&mut svm);
+    set_upgrade_authority(&mut svm, Some(authority.pubkey()));
+
+    let res = send_ix(&mut svm, &payer, build_initialize_config_ix(payer_pk));
+    assert_eq!(
+        res.unwrap_err().err,
+        TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature)
+    );
+
+    // Nor can anyone once the program is immutable
+    set_upgrade_authority(&mut svm, None);
+    let res = send_ix(&mut svm, &authority, build_initialize_config_ix(authority.pubkey()));
+    assert_eq!(
+        res.unwrap_err().err,
+        TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature)
+    );
+
+    set_upgrade_authority(&mut svm, Some(authority.pubkey()));
+    assert!(send_ix(&mut svm, &authority, build_initialize_config_ix(authority.pubkey())).is_ok());
+    verify_config_account(&svm, authority.pubkey(), false);
+
+    // Writes work again, and the authority is now the guardian
+    let ix = build_write_ix(payer_pk, tape_address, writer_address, b"config restored");
+    assert!(send_ix(&mut svm, &payer, ix).is_ok());
+    assert!(send_ix(&mut svm, &authority, build_pause_ix(authority.pubkey())).is_ok());
+
+    // It only runs once
+    assert!(send_ix(&mut svm, &authority, build_initialize_config_ix(authority.pubkey())).is_err());
+    verify_config_account(&svm, authority.pubkey(), true);
+}
+
+#[test]
+fn run_multiple_miners() {
+    let (mut svm, payer) = setup_environment();
+    initialize_program(&mut svm, &payer);
+
+    let mut tape_db = vec![];
+    for tape_idx in 0..4 {
+        create_and_verify_tape(&mut svm, &payer, tape_idx, &mut tape_db);
+    }
+    advance_epoch(&mut svm, &payer, 0);
+
+    // A known rate and no difficulty to beat, so every reward is the base rate and proofs solve
+    // quickly
+    let base_rate = 1_000;
+    edit_epoch(&mut svm, |epoch| {
+        epoch.base_rate = base_rate;
+        epoch.difficulty = 0;
+    });
+
+    // Miners with their own authorities, registered together
+    let authorities = (0..4).map(|_| create_payer(&mut svm)).collect::<Vec<_>>();
+    let miners = authorities
+        .iter()
+        .enumerate()
+        .map(|(i, authority)| register_miner(&mut svm, authority, &format!("miner-{}", i)))
+        .collect::<Vec<_>>();
+    warp(&mut svm, ONE_MINUTE);
+
+    let spools_before = get_spools(&svm);
+    let proofs = miners[..3]
+        .iter()
+        .map(|miner_address| prove(&svm, miner_address, &tape_db))
+        .collect::<Vec<_>>();
+
+    // Three proofs in the same block, two of them on the same spool
+    for (i, spool) in [0u8, 1, 1].into_iter().enumerate() {
+        let res = send_proof(&mut svm, &authorities[i], miners[i], spool, &proofs[i]);
+        assert!(res.is_ok());
+    }
+
+    // Proving again in the same block is too early, and another miner's proof isn't one
+    let res = send_proof(&mut svm, &authorities[0], miners[0], 0, &proofs[0]);
+    assert_tape_error(res, TapeError::SolutionTooEarly);
+    let res = send_proof(&mut svm, &authorities[3], miners[3], 2, &proofs[0]);
+    assert_tape_error(res, TapeError::SolutionInvalid);
+
+    // Only the accepted proofs are paid, each from the spool it was sent to
+    for (i, miner_address) in miners.iter().enumerate() {
+        let miner = *Miner::unpack(&svm.get_account(miner_address).unwrap().data).unwrap();
+        let proofs = if i < 3 { 1 } else { 0 };
+        assert_eq!(miner.total_proofs, proofs, "miner {}", i);
+        assert_eq!(miner.unclaimed_rewards, proofs * base_rate, "miner {}", i);
+    }
+
+    let spools = get_spools(&svm);
+    for (id, paid) in [(0, base_rate), (1, 2 * base_rate), (2, 0)] {
+        assert_eq!(spools[id].theoretical_rewards, paid, "spool {}", id);
+        assert_eq!(spools[id].available_rewards, spools_before[id].available_rewards - paid, "spool {}", id);
+    }
+
+    // The next epoch counts what was earned across the spools and mints back what was paid
+    let supply = get_mint(&svm, &MINT_ADDRESS).supply;
+    next_epoch(&mut svm, &payer);
+    assert_eq!(get_mint(&svm, &MINT_ADDRESS).supply, supply + 3 * base_rate);
+    for (spool, before) in get_spools(&svm).iter().zip(&spools_before) {
+        assert_eq!(spool.theoretical_rewards, 0);
+        assert_eq!(spool.available_rewards, before.available_rewards);
+    }
+}
+
+fn send_ix(
+    svm: &mut LiteSVM,
+    payer: &Keypair,
+    ix: solana_sdk::instruction::Instruction,
+) -> litesvm::types::TransactionResult {
+    let payer_pk = payer.pubkey();
+    svm.expire_blockhash();
+    let blockhash = svm.latest_blockhash();
+    let tx = Transaction::new_signed_with_payer(&[ix], Some(&payer_pk), &[&payer], blockhash);
+    send_tx(svm, tx)
+}
+
+/// Stand in for the ProgramData account the upgradeable loader keeps for a deployed program.
+fn set_upgrade_authority(svm: &mut LiteSVM, authority: Option<Pubkey>) {
+    let state = UpgradeableLoaderState::ProgramData {
+        slot: 0,
+        upgrade_authority_address: authority,
+    };
+    let data = bincode::serialize(&state).unwrap();
+    let account = Account {
+        lamports: svm.minimum_balance_for_rent_exemption(data.len()),
+        data,
+        owner: bpf_loader_upgradeable::ID,
+        executable: false,
+        rent_epoch: 0,
+    };
+    svm.set_account(program_data_pda().0, account).unwrap();
+}
+
 fn setup_environment() -> (LiteSVM, Keypair) {
     let mut svm = setup_svm();
     let payer = create_payer(&mut svm);
@@ -136,6 +375,7 @@ fn verify_archive_account(svm: &LiteSVM, expected_tapes_stored: u64) {
         .expect("Archive account should exist");
     let archive = Archive::unpack(&account.data).expect("Failed to unpack Archive account");
     assert_eq!(archive.tapes_stored, expected_tapes_stored);
+    assert_eq!(archive.version, ACCOUNT_VERSION);
 }
 
 fn verify_epoch_account(svm: &LiteSVM) {
@@ -147,6 +387,17 @@ fn verify_epoch_account(svm: &LiteSVM) {
     assert_eq!(epoch.base_rate, ONE_TAPE);
     assert_eq!(epoch.last_epoch_at, 0);
     assert_eq!(epoch.difficulty, 7);
+    assert_eq!(epoch.version, ACCOUNT_VERSION);
+}
+
+fn verify_config_account(svm: &LiteSVM, expected_guardian: Pubkey, expected_paused: bool) {
+    let (config_address, _config_bump) = config_pda();
+    let account = svm
+        .get_account(&config_address)
+        .expect("Config account should exist");
+    let config = Config::unpack(&account.data).expect("Failed to unpack Config account");
+    assert_eq!(config.guardian, expected_guardian);
+    assert_eq!(config.is_paused(), expected_paused);
 }
 
 fn verify_treasury_account(svm: &LiteSVM) {
@@ -508,10 +759,9 @@ fn compute_challenge_solution(
     miner: &Miner,
     epoch_difficulty: u64,
 ) -> (Solution, [u8; SEGMENT_SIZE], [[u8; 32]; PROOF_LEN]) {
-    let segment_number = compute_recall_segment(
-        &miner.current_challenge, 
-        stored_tape.account.total_segments
-    ) as usize;
+    let (recall_tape, segment_number) = compute_recall_indices(miner, &stored_tape.account);
+    assert_eq!(recall_tape, stored_tape.account.number);
+    let segment_number = segment_number as usize;
 
     let mut leaves = Vec::new();
     let mut recall_segment = [0; SEGMENT_SIZE];
@@ -577,6 +827,61 @@ fn perform_mining(
     let account = svm.get_account(&miner_address).unwrap();
     let miner = Miner::unpack(&account.data).unwrap();
     assert!(miner.unclaimed_rewards > 0);
+
+    let data = find_event(&res.unwrap(), EventType::MineEvent).unwrap();
+    let event = MineEvent::try_from_bytes(&data).unwrap();
+    assert_eq!(event.miner, miner_address.to_bytes());
+    assert_eq!(event.reward, miner.unclaimed_rewards);
+    assert!(event.difficulty >= 1);
+}
+
+/// A solved challenge for a miner, with the recall segment and proof from its recall tape.
+struct Proof {
+    tape_address: Pubkey,
+    solution: Solution,
+    recall_segment: [u8; SEGMENT_SIZE],
+    merkle_proof: [[u8; 32]; PROOF_LEN],
+}
+
+fn prove(svm: &LiteSVM, miner_address: &Pubkey, tape_db: &[StoredTape]) -> Proof {
+    let miner = *Miner::unpack(&svm.get_account(miner_address).unwrap().data).unwrap();
+    let epoch = get_epoch(svm);
+    let stored_tape = &tape_db[(miner.recall_tape - 1) as usize];
+    let (solution, recall_segment, merkle_proof) = compute_challenge_solution(stored_tape, &miner, epoch.difficulty);
+
+    Proof {
+        tape_address: stored_tape.pubkey,
+        solution,
+        recall_segment,
+        merkle_proof,
+    }
+}
+
+fn send_proof(
+    svm: &mut LiteSVM,
+    authority: &Keypair,
+    miner_address: Pubkey,
+    spool: u8,
+    proof: &Proof,
+) -> litesvm::types::TransactionResult {
+    let ix = build_mine_ix(
+        authority.pubkey(),
+        miner_address,
+        spool_pda(spool).0,
+        proof.tape_address,
+        proof.solution,
+        proof.recall_segment,
+        proof.merkle_proof,
+    );
+    send_ix(svm, authority, ix)
+}
+
+fn assert_tape_error(res: litesvm::types::TransactionResult, err: TapeError) {
+    let failed = res.expect_err("instruction should fail");
+    assert_eq!(
+        failed.err,
+        TransactionError::InstructionError(0, InstructionError::Custom(err as u32))
+    );
 }
 
 fn solve_challenge<const N: usize>(
@@ -0,0 +1,88 @@
+#![cfg(test)]
+//! Handler-level checks: every instruction fed wrong signers, wrong PDAs, wrong owners, oversized
+//! data and duplicate accounts, and the error each one is rejected with.
+
+#[path = "../utils/mod.rs"]
+pub mod utils;
+mod miner;
+mod tape;
+
+use utils::*;
+
+use litesvm::{types::TransactionResult, LiteSVM};
+use solana_sdk::{
+    account::Account,
+    instruction::{Instruction, InstructionError},
+    pubkey::Pubkey,
+    signature::Keypair,
+    signer::Signer,
+    transaction::{Transaction, TransactionError},
+};
+use tape_api::prelude::*;
+
+/// A fresh program, initialized with `payer` as the guardian.
+pub fn setup() -> (LiteSVM, Keypair) {
+    let mut svm = setup_svm();
+    let payer = create_payer(&mut svm);
+    assert!(send_ix(&mut svm, &payer, build_initialize_ix(payer.pubkey())).is_ok());
+    (svm, payer)
+}
+
+pub fn send_ix(svm: &mut LiteSVM, payer: &Keypair, ix: Instruction) -> TransactionResult {
+    // Identical transactions would otherwise be rejected as already processed
+    svm.expire_blockhash();
+    let blockhash = svm.latest_blockhash();
+    let tx = Transaction::new_signed_with_payer(&[ix], Some(&payer.pubkey()), &[payer], blockhash);
+    send_tx(svm, tx)
+}
+
+/// `ix` with the account at `index` swapped for `address`, keeping its signer and writable flags.
+pub fn with_account(mut ix: Instruction, index: usize, address: Pubkey) -> Instruction {
+    ix.accounts[index].pubkey = address;
+    ix
+}
+
+/// `ix` with its first account no longer marked as a signer.
+pub fn unsigned(mut ix: Instruction) -> Instruction {
+    ix.accounts[0].is_signer = false;
+    ix
+}
+
+/// `ix` with its instruction data cut short.
+pub fn truncated(mut ix: Instruction) -> Instruction {
+    ix.data.truncate(ix.data.len() / 2);
+    ix
+}
+
+/// Copy the account at `from` to a fresh address, the program's data under a key it didn't derive.
+pub fn clone_account(svm: &mut LiteSVM, from: &Pubkey) -> Pubkey {
+    let address = Pubkey::new_unique();
+    let account = svm.get_account(from).unwrap();
+    svm.set_account(address, account).unwrap();
+    address
+}
+
+/// Hand the account at `address` to another owner, keeping its data.
+pub fn set_owner(svm: &mut LiteSVM, address: &Pubkey, owner: Pubkey) {
+    let account = svm.get_account(address).unwrap();
+    svm.set_account(*address, Account { owner, ..account }).unwrap();
+}
+
+/// Edit the raw data of a program account in place.
+pub fn edit_account(svm: &mut LiteSVM, address: &Pubkey, edit: impl FnOnce(&mut [u8])) {
+    let mut account = svm.get_account(address).unwrap();
+    edit(&mut account.data);
+    svm.set_account(*address, account).unwrap();
+}
+
+/// The program rejected the instruction with a specific `TapeError`.
+pub fn assert_tape_error(res: TransactionResult, err: TapeError) {
+    assert_program_error(res, InstructionError::Custom(err as u32));
+}
+
+/// The program rejected the instruction with a specific error, e.g. from one of the account checks
+/// it delegates to steel, or from a CPI.
+pub fn assert_program_error(res: TransactionResult, err: InstructionError) {
+    let failed = res.expect_err("instruction should fail");
+    assert_eq!(failed.err, TransactionError::InstructionError(0, err));
+}
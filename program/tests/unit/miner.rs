@@ -0,0 +1,323 @@
+use super::*;
+use super::tape::written_tape;
+use crankx::Solution;
+use solana_sdk::{clock::Clock, system_program, sysvar};
+use spl_token::error::TokenError;
+
+/// Register a miner named `name` for `payer`, returning its address.
+pub fn register_miner(svm: &mut LiteSVM, payer: &Keypair, name: &str) -> Pubkey {
+    let (miner_address, _miner_bump) = miner_pda(payer.pubkey(), to_name(name));
+    assert!(send_ix(svm, payer, build_register_ix(payer.pubkey(), name)).is_ok());
+    miner_address
+}
+
+/// A registered miner and a finalized tape, with the clock moved past the miner's next proof and
+/// the epoch difficulty dropped to zero, so any solution gets as far as the recall checks.
+fn setup_mining() -> (LiteSVM, Keypair, Pubkey, Pubkey) {
+    let (mut svm, payer) = setup();
+    let payer_pk = payer.pubkey();
+    let miner_address = register_miner(&mut svm, &payer, "miner");
+
+    let (tape_address, writer_address) = written_tape(&mut svm, &payer, "tape");
+    let ix = build_finalize_ix(payer_pk, tape_address, writer_address, None);
+    assert!(send_ix(&mut svm, &payer, ix).is_ok());
+
+    let mut clock = svm.get_sysvar::<Clock>();
+    clock.unix_timestamp += 2 * ONE_MINUTE;
+    svm.set_sysvar::<Clock>(&clock);
+
+    edit_account(&mut svm, &epoch_pda().0, |data| {
+        let epoch = Epoch::unpack_mut(data).unwrap();
+        epoch.last_epoch_at = clock.unix_timestamp - ONE_MINUTE;
+        epoch.difficulty = 0;
+    });
+
+    (svm, payer, miner_address, tape_address)
+}
+
+fn mine_ix(signer: Pubkey, miner: Pubkey, tape: Pubkey) -> Instruction {
+    build_mine_ix(
+        signer,
+        miner,
+        spool_pda(0).0,
+        tape,
+        Solution::new([0; 16], [0; 8]),
+        [0; SEGMENT_SIZE],
+        [[0; 32]; PROOF_LEN],
+    )
+}
+
+#[test]
+fn test_register() {
+    let (mut svm, payer) = setup();
+    let payer_pk = payer.pubkey();
+    let fee_payer = create_payer(&mut svm);
+    let ix = build_register_ix(payer_pk, "miner");
+
+    // Not signed by the authority the miner is derived from
+    let res = send_ix(&mut svm, &fee_payer, unsigned(ix.clone()));
+    assert_program_error(res, InstructionError::MissingRequiredSignature);
+
+    // An address derived from another name
+    let (other_miner, _) = miner_pda(payer_pk, to_name("other"));
+    let res = send_ix(&mut svm, &payer, with_account(ix.clone(), 1, other_miner));
+    assert_program_error(res, InstructionError::InvalidAccountData);
+
+    // An archive, program or sysvar that isn't the real one
+    let res = send_ix(&mut svm, &payer, with_account(ix.clone(), 2, epoch_pda().0));
+    assert_program_error(res, InstructionError::InvalidAccountData);
+    let res = send_ix(&mut svm, &payer, with_account(ix.clone(), 3, Pubkey::new_unique()));
+    assert_program_error(res, InstructionError::InvalidAccountData);
+    let res = send_ix(&mut svm, &payer, with_account(ix.clone(), 5, sysvar::clock::ID));
+    assert_program_error(res, InstructionError::InvalidAccountData);
+
+    // Arguments cut short
+    let res = send_ix(&mut svm, &payer, truncated(ix.clone()));
+    assert_program_error(res, InstructionError::InvalidInstructionData);
+
+    // Lockups that can't be kept
+    for (locked_percent, vest_epochs) in [(101, 4), (50, 0), (0, 4), (50, MAX_VEST_EPOCHS + 1)] {
+        let ix = build_register_with_lockup_ix(payer_pk, "miner", locked_percent, vest_epochs);
+        assert_program_error(send_ix(&mut svm, &payer, ix), InstructionError::InvalidInstructionData);
+    }
+
+    // Only once per name
+    assert!(send_ix(&mut svm, &payer, ix.clone()).is_ok());
+    assert_program_error(send_ix(&mut svm, &payer, ix), InstructionError::AccountAlreadyInitialized);
+}
+
+#[test]
+fn test_mine() {
+    let (mut svm, payer, miner_address, tape_address) = setup_mining();
+    let payer_pk = payer.pubkey();
+    let ix = mine_ix(payer_pk, miner_address, tape_address);
+
+    // Someone else's miner
+    let intruder = create_payer(&mut svm);
+    let res = send_ix(&mut svm, &intruder, mine_ix(intruder.pubkey(), miner_address, tape_address));
+    assert_program_error(res, InstructionError::InvalidAccountOwner);
+
+    let res = send_ix(&mut svm, &intruder, unsigned(ix.clone()));
+    assert_program_error(res, InstructionError::MissingRequiredSignature);
+
+    // A copy of the miner the program didn't derive
+    let fake_miner = clone_account(&mut svm, &miner_address);
+    let res = send_ix(&mut svm, &payer, with_account(ix.clone(), 2, fake_miner));
+    assert_program_error(res, InstructionError::InvalidSeeds);
+
+    // A spool that isn't one of the program's
+    let res = send_ix(&mut svm, &payer, with_account(ix.clone(), 1, Pubkey::new_unique()));
+    assert_program_error(res, InstructionError::InvalidSeeds);
+
+    // The miner doubling as the tape
+    let res = send_ix(&mut svm, &payer, with_account(ix.clone(), 3, miner_address));
+    assert_program_error(res, InstructionError::InvalidAccountData);
+
+    // A tape owned by another program
+    let fake_tape = clone_account(&mut svm, &tape_address);
+    set_owner(&mut svm, &fake_tape, system_program::ID);
+    let res = send_ix(&mut svm, &payer, with_account(ix.clone(), 3, fake_tape));
+    assert_program_error(res, InstructionError::InvalidAccountOwner);
+
+    // A tape that isn't the one recalled
+    let (unfinalized_tape, _) = written_tape(&mut svm, &payer, "unfinalized");
+    let res = send_ix(&mut svm, &payer, mine_ix(payer_pk, miner_address, unfinalized_tape));
+    assert_tape_error(res, TapeError::SolutionInvalid);
+
+    // A recall proof that doesn't match the tape
+    assert_tape_error(send_ix(&mut svm, &payer, ix.clone()), TapeError::SolutionInvalid);
+
+    // Below the epoch difficulty
+    edit_account(&mut svm, &epoch_pda().0, |data| {
+        Epoch::unpack_mut(data).unwrap().difficulty = u64::from(u32::MAX);
+    });
+    assert_tape_error(send_ix(&mut svm, &payer, ix.clone()), TapeError::SolutionTooEasy);
+
+    // Right after the last proof
+    let now = svm.get_sysvar::<Clock>().unix_timestamp;
+    edit_account(&mut svm, &miner_address, |data| {
+        Miner::unpack_mut(data).unwrap().last_proof_at = now;
+    });
+    assert_tape_error(send_ix(&mut svm, &payer, ix.clone()), TapeError::SolutionTooEarly);
+
+    // In an epoch that just started
+    edit_account(&mut svm, &epoch_pda().0, |data| {
+        Epoch::unpack_mut(data).unwrap().last_epoch_at = now;
+    });
+    assert_tape_error(send_ix(&mut svm, &payer, ix.clone()), TapeError::StaleEpoch);
+
+    // Paused by the guardian
+    assert!(send_ix(&mut svm, &payer, build_pause_ix(payer_pk)).is_ok());
+    assert_tape_error(send_ix(&mut svm, &payer, ix), TapeError::Paused);
+}
+
+#[test]
+fn test_claim() {
+    let (mut svm, payer) = setup();
+    let payer_pk = payer.pubkey();
+    let miner_address = register_miner(&mut svm, &payer, "miner");
+    let beneficiary = create_ata(&mut svm, &payer, &MINT_ADDRESS, &payer_pk);
+    let ix = build_claim_ix(payer_pk, miner_address, beneficiary, 1);
+
+    // Someone else's miner
+    let intruder = create_payer(&mut svm);
+    let intruder_ata = create_ata(&mut svm, &intruder, &MINT_ADDRESS, &intruder.pubkey());
+    let res = send_ix(&mut svm, &intruder, build_claim_ix(intruder.pubkey(), miner_address, intruder_ata, 1));
+    assert_program_error(res, InstructionError::MissingRequiredSignature);
+
+    // A beneficiary holding another token
+    let other_mint = create_mint(&mut svm, &payer, &payer_pk, 0);
+    let other_ata = create_ata(&mut svm, &payer, &other_mint, &payer_pk);
+    let res = send_ix(&mut svm, &payer, with_account(ix.clone(), 1, other_ata));
+    assert_program_error(res, InstructionError::InvalidAccountData);
+
+    // The miner doubling as the beneficiary
+    let res = send_ix(&mut svm, &payer, with_account(ix.clone(), 1, miner_address));
+    assert_program_error(res, InstructionError::InvalidAccountOwner);
+
+    // A miner owned by another program
+    let fake_miner = clone_account(&mut svm, &miner_address);
+    set_owner(&mut svm, &fake_miner, system_program::ID);
+    let res = send_ix(&mut svm, &payer, with_account(ix.clone(), 2, fake_miner));
+    assert_program_error(res, InstructionError::InvalidAccountOwner);
+
+    // A treasury account that isn't the program's
+    let res = send_ix(&mut svm, &payer, with_account(ix.clone(), 4, beneficiary));
+    assert_program_error(res, InstructionError::InvalidAccountData);
+
+    // More than the miner has earned
+    assert_tape_error(send_ix(&mut svm, &payer, ix.clone()), TapeError::ClaimTooLarge);
+    let res = send_ix(&mut svm, &payer, build_claim_ix(payer_pk, miner_address, beneficiary, u64::MAX));
+    assert_tape_error(res, TapeError::ClaimTooLarge);
+
+    // Rewards that are still locked
+    let now = svm.get_sysvar::<Clock>().unix_timestamp;
+    edit_account(&mut svm, &miner_address, |data| {
+        let miner = Miner::unpack_mut(data).unwrap();
+        miner.unclaimed_rewards = 1;
+        miner.locked_rewards = 1;
+        miner.vested_at = now;
+        miner.vest_end = now + EPOCH_SECONDS;
+    });
+    assert_tape_error(send_ix(&mut svm, &payer, ix), TapeError::RewardsLocked);
+}
+
+#[test]
+fn test_close() {
+    let (mut svm, payer) = setup();
+    let payer_pk = payer.pubkey();
+    let miner_address = register_miner(&mut svm, &payer, "miner");
+    let ix = build_close_ix(payer_pk, miner_address);
+
+    // Someone else's miner
+    let intruder = create_payer(&mut svm);
+    let res = send_ix(&mut svm, &intruder, build_close_ix(intruder.pubkey(), miner_address));
+    assert_program_error(res, InstructionError::MissingRequiredSignature);
+
+    let res = send_ix(&mut svm, &intruder, unsigned(ix.clone()));
+    assert_program_error(res, InstructionError::MissingRequiredSignature);
+
+    // A miner owned by another program
+    let fake_miner = clone_account(&mut svm, &miner_address);
+    set_owner(&mut svm, &fake_miner, system_program::ID);
+    let res = send_ix(&mut svm, &payer, with_account(ix.clone(), 1, fake_miner));
+    assert_program_error(res, InstructionError::InvalidAccountOwner);
+
+    // A fake system program
+    let res = send_ix(&mut svm, &payer, with_account(ix.clone(), 2, Pubkey::new_unique()));
+    assert_program_error(res, InstructionError::InvalidAccountData);
+
+    // Rewards left to claim
+    edit_account(&mut svm, &miner_address, |data| {
+        Miner::unpack_mut(data).unwrap().unclaimed_rewards = 1;
+    });
+    assert_program_error(send_ix(&mut svm, &payer, ix.clone()), InstructionError::InvalidAccountData);
+    edit_account(&mut svm, &miner_address, |data| {
+        Miner::unpack_mut(data).unwrap().unclaimed_rewards = 0;
+    });
+
+    // Without a deposit the token accounts can be left off
+    let mut ix = ix;
+    ix.accounts.truncate(3);
+
+    // Only once
+    assert!(send_ix(&mut svm, &payer, ix.clone()).is_ok());
+    assert_program_error(send_ix(&mut svm, &payer, ix), InstructionError::InvalidAccountOwner);
+}
+
+#[test]
+fn test_deposit() {
+    let (mut svm, payer) = setup();
+    let payer_pk = payer.pubkey();
+    let deposit = 5 * ONE_TAPE;
+
+    // Only the guardian sets it, and only up to the cap
+    let intruder = create_payer(&mut svm);
+    let res = send_ix(&mut svm, &intruder, build_set_miner_deposit_ix(intruder.pubkey(), deposit));
+    assert_program_error(res, InstructionError::MissingRequiredSignature);
+    let res = send_ix(&mut svm, &payer, build_set_miner_deposit_ix(payer_pk, MAX_MINER_DEPOSIT + 1));
+    assert_program_error(res, InstructionError::InvalidInstructionData);
+    assert!(send_ix(&mut svm, &payer, build_set_miner_deposit_ix(payer_pk, deposit)).is_ok());
+
+    let ix = build_register_ix(payer_pk, "miner");
+
+    // Without the TAPE to lock
+    let payer_ata = create_ata(&mut svm, &payer, &MINT_ADDRESS, &payer_pk);
+    let res = send_ix(&mut svm, &payer, ix.clone());
+    assert_program_error(res, InstructionError::Custom(TokenError::InsufficientFunds as u32));
+    set_ata_balance(&mut svm, &payer_ata, deposit);
+
+    // Without the token accounts
+    let mut short_ix = ix.clone();
+    short_ix.accounts.truncate(7);
+    assert_program_error(send_ix(&mut svm, &payer, short_ix), InstructionError::NotEnoughAccountKeys);
+
+    // Paid in another token, or into an account that isn't the escrow's
+    let other_mint = create_mint(&mut svm, &payer, &payer_pk, 0);
+    let other_ata = create_ata(&mut svm, &payer, &other_mint, &payer_pk);
+    let res = send_ix(&mut svm, &payer, with_account(ix.clone(), 7, other_ata));
+    assert_program_error(res, InstructionError::InvalidAccountData);
+    let res = send_ix(&mut svm, &payer, with_account(ix.clone(), 8, TREASURY_ADDRESS));
+    assert_program_error(res, InstructionError::InvalidAccountData);
+    let res = send_ix(&mut svm, &payer, with_account(ix.clone(), 9, TREASURY_ATA));
+    assert_program_error(res, InstructionError::InvalidAccountData);
+
+    // Held apart from the treasury's rewards
+    let treasury_balance = get_ata_balance(&svm, &TREASURY_ATA);
+    let miner_address = register_miner(&mut svm, &payer, "miner");
+    assert_eq!(get_ata_balance(&svm, &payer_ata), 0);
+    assert_eq!(get_ata_balance(&svm, &ESCROW_ATA), deposit);
+    assert_eq!(get_ata_balance(&svm, &TREASURY_ATA), treasury_balance);
+
+    let miner = Miner::unpack(&svm.get_account(&miner_address).unwrap().data).unwrap();
+    assert_eq!(miner.deposit, deposit);
+
+    // Lowering the deposit later doesn't change what the miner locked
+    assert!(send_ix(&mut svm, &payer, build_set_miner_deposit_ix(payer_pk, 0)).is_ok());
+
+    // Not refunded until the cooldown has passed since the last proof
+    let ix = build_close_ix(payer_pk, miner_address);
+    assert_tape_error(send_ix(&mut svm, &payer, ix.clone()), TapeError::DepositLocked);
+
+    let mut clock = svm.get_sysvar::<Clock>();
+    clock.unix_timestamp += MINER_DEPOSIT_COOLDOWN;
+    svm.set_sysvar::<Clock>(&clock);
+
+    // Refunded in another token, or from an account that isn't the escrow's
+    let res = send_ix(&mut svm, &payer, with_account(ix.clone(), 3, other_ata));
+    assert_program_error(res, InstructionError::InvalidAccountData);
+    let res = send_ix(&mut svm, &payer, with_account(ix.clone(), 4, TREASURY_ADDRESS));
+    assert_program_error(res, InstructionError::InvalidAccountData);
+    let res = send_ix(&mut svm, &payer, with_account(ix.clone(), 5, TREASURY_ATA));
+    assert_program_error(res, InstructionError::InvalidAccountData);
+
+    // A miner with a deposit needs the token accounts to refund it
+    let mut short_ix = ix.clone();
+    short_ix.accounts.truncate(3);
+    assert_program_error(send_ix(&mut svm, &payer, short_ix), InstructionError::NotEnoughAccountKeys);
+
+    assert!(send_ix(&mut svm, &payer, ix).is_ok());
+    assert_eq!(get_ata_balance(&svm, &payer_ata), deposit);
+    assert_eq!(get_ata_balance(&svm, &ESCROW_ATA), 0);
+    assert_eq!(get_ata_balance(&svm, &TREASURY_ATA), treasury_balance);
+}
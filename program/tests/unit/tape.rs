@@ -0,0 +1,205 @@
+use super::*;
+use solana_sdk::{system_program, sysvar};
+
+/// Create a tape named `name` owned by `payer`, returning its tape and writer addresses.
+pub fn create_tape(svm: &mut LiteSVM, payer: &Keypair, name: &str) -> (Pubkey, Pubkey) {
+    let (tape_address, _tape_bump) = tape_pda(payer.pubkey(), &to_name(name));
+    let (writer_address, _writer_bump) = writer_pda(tape_address);
+    assert!(send_ix(svm, payer, build_create_ix(payer.pubkey(), name, None)).is_ok());
+    (tape_address, writer_address)
+}
+
+/// Create a tape with one write on it, ready to be finalized.
+pub fn written_tape(svm: &mut LiteSVM, payer: &Keypair, name: &str) -> (Pubkey, Pubkey) {
+    let (tape_address, writer_address) = create_tape(svm, payer, name);
+    let ix = build_write_ix(payer.pubkey(), tape_address, writer_address, b"<segment_0_data>");
+    assert!(send_ix(svm, payer, ix).is_ok());
+    (tape_address, writer_address)
+}
+
+fn bad_update_ix(signer: Pubkey, tape: Pubkey, writer: Pubkey, segment_number: u64) -> Instruction {
+    build_update_ix(
+        signer,
+        tape,
+        writer,
+        segment_number,
+        padded_array::<SEGMENT_SIZE>(b"<segment_0_data>"),
+        padded_array::<SEGMENT_SIZE>(b"<segment_0_updated>"),
+        [[0; 32]; PROOF_LEN],
+    )
+}
+
+#[test]
+fn test_create() {
+    let (mut svm, payer) = setup();
+    let payer_pk = payer.pubkey();
+    let fee_payer = create_payer(&mut svm);
+    let ix = build_create_ix(payer_pk, "tape", None);
+
+    // Not signed by the authority the tape is derived from
+    let res = send_ix(&mut svm, &fee_payer, unsigned(ix.clone()));
+    assert_program_error(res, InstructionError::MissingRequiredSignature);
+
+    // Addresses derived from another name
+    let (other_tape, _) = tape_pda(payer_pk, &to_name("other"));
+    let (other_writer, _) = writer_pda(other_tape);
+    let res = send_ix(&mut svm, &payer, with_account(ix.clone(), 1, other_tape));
+    assert_program_error(res, InstructionError::InvalidAccountData);
+    let res = send_ix(&mut svm, &payer, with_account(ix.clone(), 2, other_writer));
+    assert_program_error(res, InstructionError::InvalidAccountData);
+
+    // The tape doubling as its own writer
+    let (tape_address, _) = tape_pda(payer_pk, &to_name("tape"));
+    let res = send_ix(&mut svm, &payer, with_account(ix.clone(), 2, tape_address));
+    assert_program_error(res, InstructionError::InvalidAccountData);
+
+    // Fake programs and sysvars
+    let res = send_ix(&mut svm, &payer, with_account(ix.clone(), 3, Pubkey::new_unique()));
+    assert_program_error(res, InstructionError::InvalidAccountData);
+    let res = send_ix(&mut svm, &payer, with_account(ix.clone(), 4, sysvar::clock::ID));
+    assert_program_error(res, InstructionError::InvalidAccountData);
+    let res = send_ix(&mut svm, &payer, with_account(ix.clone(), 5, sysvar::clock::ID));
+    assert_program_error(res, InstructionError::InvalidAccountData);
+
+    // Arguments cut short
+    let res = send_ix(&mut svm, &payer, truncated(ix.clone()));
+    assert_program_error(res, InstructionError::InvalidInstructionData);
+
+    // Only once per name
+    assert!(send_ix(&mut svm, &payer, ix.clone()).is_ok());
+    assert_program_error(send_ix(&mut svm, &payer, ix), InstructionError::AccountAlreadyInitialized);
+}
+
+#[test]
+fn test_write() {
+    let (mut svm, payer) = setup();
+    let payer_pk = payer.pubkey();
+    let (tape_address, writer_address) = create_tape(&mut svm, &payer, "tape");
+    let (_, other_writer) = create_tape(&mut svm, &payer, "other");
+    let ix = build_write_ix(payer_pk, tape_address, writer_address, b"data");
+
+    // Someone else's tape
+    let intruder = create_payer(&mut svm);
+    let res = send_ix(&mut svm, &intruder, build_write_ix(intruder.pubkey(), tape_address, writer_address, b"data"));
+    assert_program_error(res, InstructionError::MissingRequiredSignature);
+
+    let res = send_ix(&mut svm, &intruder, unsigned(ix.clone()));
+    assert_program_error(res, InstructionError::MissingRequiredSignature);
+
+    // The writer of another tape
+    let res = send_ix(&mut svm, &payer, with_account(ix.clone(), 2, other_writer));
+    assert_program_error(res, InstructionError::InvalidAccountData);
+
+    // The tape doubling as its own writer
+    let res = send_ix(&mut svm, &payer, with_account(ix.clone(), 2, tape_address));
+    assert_program_error(res, InstructionError::InvalidAccountData);
+
+    // A copy of the writer the program didn't derive
+    let fake_writer = clone_account(&mut svm, &writer_address);
+    let res = send_ix(&mut svm, &payer, with_account(ix.clone(), 2, fake_writer));
+    assert_program_error(res, InstructionError::InvalidAccountData);
+
+    // A config that isn't the program's
+    let res = send_ix(&mut svm, &payer, with_account(ix.clone(), 3, Pubkey::new_unique()));
+    assert_program_error(res, InstructionError::InvalidAccountData);
+
+    // A tape owned by another program
+    let fake_tape = clone_account(&mut svm, &tape_address);
+    set_owner(&mut svm, &fake_tape, system_program::ID);
+    let res = send_ix(&mut svm, &payer, with_account(ix.clone(), 1, fake_tape));
+    assert_program_error(res, InstructionError::InvalidAccountOwner);
+
+    // Paused by the guardian
+    assert!(send_ix(&mut svm, &payer, build_pause_ix(payer_pk)).is_ok());
+    assert_tape_error(send_ix(&mut svm, &payer, ix.clone()), TapeError::Paused);
+    assert!(send_ix(&mut svm, &payer, build_resume_ix(payer_pk)).is_ok());
+
+    // No room left in the tree
+    edit_account(&mut svm, &tape_address, |data| {
+        Tape::unpack_mut(data).unwrap().total_segments = MAX_SEGMENTS as u64;
+    });
+    assert_tape_error(send_ix(&mut svm, &payer, ix.clone()), TapeError::TapeFull);
+    edit_account(&mut svm, &tape_address, |data| {
+        Tape::unpack_mut(data).unwrap().total_segments = 0;
+    });
+
+    // A tape that is no longer being written
+    edit_account(&mut svm, &tape_address, |data| {
+        Tape::unpack_mut(data).unwrap().state = TapeState::Finalized.into();
+    });
+    assert_tape_error(send_ix(&mut svm, &payer, ix), TapeError::UnexpectedState);
+}
+
+#[test]
+fn test_update() {
+    let (mut svm, payer) = setup();
+    let payer_pk = payer.pubkey();
+    let (tape_address, writer_address) = written_tape(&mut svm, &payer, "tape");
+    let (_, other_writer) = written_tape(&mut svm, &payer, "other");
+    let ix = bad_update_ix(payer_pk, tape_address, writer_address, 0);
+
+    // Someone else's tape
+    let intruder = create_payer(&mut svm);
+    let res = send_ix(&mut svm, &intruder, bad_update_ix(intruder.pubkey(), tape_address, writer_address, 0));
+    assert_program_error(res, InstructionError::MissingRequiredSignature);
+
+    // The writer of another tape
+    let res = send_ix(&mut svm, &payer, with_account(ix.clone(), 2, other_writer));
+    assert_program_error(res, InstructionError::InvalidAccountData);
+
+    // The tape doubling as its own writer
+    let res = send_ix(&mut svm, &payer, with_account(ix.clone(), 2, tape_address));
+    assert_program_error(res, InstructionError::InvalidAccountData);
+
+    // Arguments cut short
+    let res = send_ix(&mut svm, &payer, truncated(ix.clone()));
+    assert_program_error(res, InstructionError::InvalidInstructionData);
+
+    // A proof that doesn't match the tree
+    assert_tape_error(send_ix(&mut svm, &payer, ix.clone()), TapeError::WriteFailed);
+
+    // A segment past the end of the tape
+    let res = send_ix(&mut svm, &payer, bad_update_ix(payer_pk, tape_address, writer_address, 1));
+    assert_tape_error(res, TapeError::SegmentTooLarge);
+    let res = send_ix(&mut svm, &payer, bad_update_ix(payer_pk, tape_address, writer_address, u64::MAX));
+    assert_tape_error(res, TapeError::SegmentTooLarge);
+
+    // Paused by the guardian
+    assert!(send_ix(&mut svm, &payer, build_pause_ix(payer_pk)).is_ok());
+    assert_tape_error(send_ix(&mut svm, &payer, ix), TapeError::Paused);
+}
+
+#[test]
+fn test_finalize() {
+    let (mut svm, payer) = setup();
+    let payer_pk = payer.pubkey();
+    let (tape_address, writer_address) = written_tape(&mut svm, &payer, "tape");
+    let (_, other_writer) = written_tape(&mut svm, &payer, "other");
+    let ix = build_finalize_ix(payer_pk, tape_address, writer_address, None);
+
+    // Nothing written yet
+    let (empty_tape, empty_writer) = create_tape(&mut svm, &payer, "empty");
+    let res = send_ix(&mut svm, &payer, build_finalize_ix(payer_pk, empty_tape, empty_writer, None));
+    assert_tape_error(res, TapeError::UnexpectedState);
+
+    // Someone else's tape
+    let intruder = create_payer(&mut svm);
+    let res = send_ix(&mut svm, &intruder, build_finalize_ix(intruder.pubkey(), tape_address, writer_address, None));
+    assert_program_error(res, InstructionError::MissingRequiredSignature);
+
+    // The writer of another tape
+    let res = send_ix(&mut svm, &payer, with_account(ix.clone(), 2, other_writer));
+    assert_program_error(res, InstructionError::InvalidAccountData);
+
+    // The tape doubling as its own writer
+    let res = send_ix(&mut svm, &payer, with_account(ix.clone(), 2, tape_address));
+    assert_program_error(res, InstructionError::InvalidAccountData);
+
+    // An archive that isn't the program's
+    let res = send_ix(&mut svm, &payer, with_account(ix.clone(), 3, epoch_pda().0));
+    assert_program_error(res, InstructionError::InvalidAccountData);
+
+    // Only once, the writer is closed with it
+    assert!(send_ix(&mut svm, &payer, ix.clone()).is_ok());
+    assert_program_error(send_ix(&mut svm, &payer, ix), InstructionError::InvalidAccountOwner);
+}
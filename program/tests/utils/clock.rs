@@ -0,0 +1,87 @@
+use litesvm::{types::TransactionResult, LiteSVM};
+use solana_sdk::{
+    clock::{Clock, DEFAULT_MS_PER_SLOT},
+    hash::hashv,
+    pubkey::Pubkey,
+    signature::Keypair,
+    signer::Signer,
+    slot_hashes::SlotHashes,
+    transaction::Transaction,
+};
+use tape_api::prelude::*;
+
+use super::send_tx;
+
+/// Move the cluster `seconds` forward: the clock, its slot at the default slot time, and the slot
+/// hashes. Hashes are derived from the slot, so a scenario replays exactly. The blockhash expires
+/// too, so the same instruction can be sent again.
+pub fn warp(svm: &mut LiteSVM, seconds: i64) {
+    let mut clock = svm.get_sysvar::<Clock>();
+    clock.unix_timestamp += seconds;
+    clock.slot += seconds as u64 * 1000 / DEFAULT_MS_PER_SLOT;
+    svm.set_sysvar::<Clock>(&clock);
+
+    let hash = hashv(&[b"slot", &clock.slot.to_le_bytes()]);
+    svm.set_sysvar::<SlotHashes>(&SlotHashes::new(&[(clock.slot, hash)]));
+
+    svm.expire_blockhash();
+}
+
+pub fn now(svm: &LiteSVM) -> i64 {
+    svm.get_sysvar::<Clock>().unix_timestamp
+}
+
+pub fn send_advance(svm: &mut LiteSVM, payer: &Keypair) -> TransactionResult {
+    let blockhash = svm.latest_blockhash();
+    let ix = build_advance_ix(payer.pubkey());
+    let tx = Transaction::new_signed_with_payer(&[ix], Some(&payer.pubkey()), &[payer], blockhash);
+    send_tx(svm, tx)
+}
+
+/// Wait out the current epoch and advance to the next, returning the new epoch.
+pub fn next_epoch(svm: &mut LiteSVM, payer: &Keypair) -> Epoch {
+    warp(svm, EPOCH_SECONDS);
+    assert!(send_advance(svm, payer).is_ok());
+    get_epoch(svm)
+}
+
+pub fn get_epoch(svm: &LiteSVM) -> Epoch {
+    let account = svm.get_account(&epoch_pda().0).unwrap();
+    *Epoch::unpack(&account.data).unwrap()
+}
+
+pub fn get_spools(svm: &LiteSVM) -> Vec<Spool> {
+    (0..SPOOL_COUNT as u8)
+        .map(|id| {
+            let account = svm.get_account(&spool_pda(id).0).unwrap();
+            *Spool::unpack(&account.data).unwrap()
+        })
+        .collect()
+}
+
+/// Change the epoch in place, e.g. to start a scenario from a given rate or difficulty.
+pub fn edit_epoch(svm: &mut LiteSVM, edit: impl FnOnce(&mut Epoch)) {
+    edit_state(svm, &epoch_pda().0, |data| edit(Epoch::unpack_mut(data).unwrap()));
+}
+
+/// Change a spool in place, e.g. to stand in for the proofs miners landed on it this epoch.
+pub fn edit_spool(svm: &mut LiteSVM, id: u8, edit: impl FnOnce(&mut Spool)) {
+    edit_state(svm, &spool_pda(id).0, |data| edit(Spool::unpack_mut(data).unwrap()));
+}
+
+/// Stand in for the proofs landed on each spool over an epoch, earning `rewards` from it: its
+/// theoretical rewards go up by all of it, its available rewards down by what it can pay.
+pub fn mine_from_spools(svm: &mut LiteSVM, rewards: [u64; SPOOL_COUNT]) {
+    for (id, reward) in rewards.into_iter().enumerate() {
+        edit_spool(svm, id as u8, |spool| {
+            spool.theoretical_rewards += reward;
+            spool.available_rewards -= reward.min(spool.available_rewards);
+        });
+    }
+}
+
+fn edit_state(svm: &mut LiteSVM, address: &Pubkey, edit: impl FnOnce(&mut [u8])) {
+    let mut account = svm.get_account(address).unwrap();
+    edit(&mut account.data);
+    svm.set_account(*address, account).unwrap();
+}
@@ -1,5 +1,7 @@
 pub mod svm;
 pub mod token;
+pub mod clock;
 
 pub use svm::*;
 pub use token::*;
+pub use clock::*;
@@ -1,5 +1,5 @@
 use std::path::PathBuf;
-use tape_api::prelude::InstructionType;
+use tape_api::prelude::{EventType, InstructionType};
 use solana_sdk::{signature::Keypair, signer::Signer, transaction::Transaction};
 use solana_compute_budget::compute_budget::ComputeBudget;
 use litesvm::{types::{TransactionMetadata, TransactionResult}, LiteSVM};
@@ -49,6 +49,15 @@ pub fn send_tx(svm: &mut LiteSVM, tx: Transaction) -> TransactionResult {
     res.clone()
 }
 
+/// The data of the first event of the given type logged by a transaction.
+pub fn find_event(meta: &TransactionMetadata, event_type: EventType) -> Option<Vec<u8>> {
+    meta.logs
+        .iter()
+        .filter_map(|log| log.strip_prefix("Program data: "))
+        .filter_map(|data| base64::decode(data).ok())
+        .find(|data| data.first() == Some(&(event_type as u8)))
+}
+
 pub fn create_payer(svm: &mut LiteSVM) -> Keypair {
     let payer_kp = Keypair::new();
     let payer_pk = payer_kp.pubkey();
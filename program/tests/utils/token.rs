@@ -1,4 +1,4 @@
-use solana_sdk::{pubkey::Pubkey, signature::Keypair};
+use solana_sdk::{program_pack::Pack, pubkey::Pubkey, signature::Keypair};
 use litesvm::{types::FailedTransactionMetadata, LiteSVM};
 use litesvm_token::{
     CreateAssociatedTokenAccount, 
@@ -28,6 +28,24 @@ pub fn get_ata_balance(svm: &LiteSVM, ata: &Pubkey) -> u64 {
     info.amount
 }
 
+/// Overwrite the balance of a token account, for mints the test can't sign for.
+pub fn set_ata_balance(svm: &mut LiteSVM, ata: &Pubkey, amount: u64) {
+    let mut account = svm.get_account(ata).unwrap();
+    let mut info = Account::unpack(&account.data).unwrap();
+    info.amount = amount;
+    Account::pack(info, &mut account.data).unwrap();
+    svm.set_account(*ata, account).unwrap();
+}
+
+/// Overwrite the supply of a mint, e.g. to start a scenario late in the emissions schedule.
+pub fn set_mint_supply(svm: &mut LiteSVM, mint: &Pubkey, supply: u64) {
+    let mut account = svm.get_account(mint).unwrap();
+    let mut info = Mint::unpack(&account.data).unwrap();
+    info.supply = supply;
+    Mint::pack(info, &mut account.data).unwrap();
+    svm.set_account(*mint, account).unwrap();
+}
+
 pub fn get_mint(svm: &LiteSVM, mint: &Pubkey) -> Mint {
     get_spl_account(svm, &mint).unwrap()
 }